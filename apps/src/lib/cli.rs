@@ -216,6 +216,9 @@ pub mod cmds {
                 .subcommand(TxIbcTransfer::def().display_order(1))
                 .subcommand(TxUpdateAccount::def().display_order(1))
                 .subcommand(TxInitAccount::def().display_order(1))
+                .subcommand(TxRecoverAccount::def().display_order(1))
+                .subcommand(TxAddSessionKey::def().display_order(1))
+                .subcommand(TxRevokeSessionKey::def().display_order(1))
                 .subcommand(TxRevealPk::def().display_order(1))
                 // Governance transactions
                 .subcommand(TxInitProposal::def().display_order(1))
@@ -232,10 +235,16 @@ pub mod cmds {
                 // PGF transactions
                 .subcommand(TxUpdateStewardCommission::def().display_order(4))
                 .subcommand(TxResignSteward::def().display_order(4))
+                // Scheduled transactions
+                .subcommand(TxSchedule::def().display_order(4))
+                .subcommand(TxCancelScheduled::def().display_order(4))
                 // Queries
                 .subcommand(QueryEpoch::def().display_order(5))
                 .subcommand(QueryAccount::def().display_order(5))
                 .subcommand(QueryTransfers::def().display_order(5))
+                .subcommand(ShieldedHistory::def().display_order(5))
+                .subcommand(DisclosePayment::def().display_order(5))
+                .subcommand(VerifyDisclosure::def().display_order(5))
                 .subcommand(QueryConversions::def().display_order(5))
                 .subcommand(QueryBlock::def().display_order(5))
                 .subcommand(QueryBalance::def().display_order(5))
@@ -250,9 +259,12 @@ pub mod cmds {
                 .subcommand(QueryProposalResult::def().display_order(5))
                 .subcommand(QueryProtocolParameters::def().display_order(5))
                 .subcommand(QueryPgf::def().display_order(5))
+                .subcommand(QueryVesting::def().display_order(5))
                 .subcommand(QueryValidatorState::def().display_order(5))
                 // Actions
                 .subcommand(SignTx::def().display_order(6))
+                .subcommand(AssembleTx::def().display_order(6))
+                .subcommand(GenIbcShieldedTransfer::def().display_order(6))
                 // Utils
                 .subcommand(Utils::def().display_order(7))
         }
@@ -265,6 +277,12 @@ pub mod cmds {
             let tx_update_account =
                 Self::parse_with_ctx(matches, TxUpdateAccount);
             let tx_init_account = Self::parse_with_ctx(matches, TxInitAccount);
+            let tx_recover_account =
+                Self::parse_with_ctx(matches, TxRecoverAccount);
+            let tx_add_session_key =
+                Self::parse_with_ctx(matches, TxAddSessionKey);
+            let tx_revoke_session_key =
+                Self::parse_with_ctx(matches, TxRevokeSessionKey);
             let tx_init_validator =
                 Self::parse_with_ctx(matches, TxInitValidator);
             let tx_unjail_validator =
@@ -278,6 +296,9 @@ pub mod cmds {
                 Self::parse_with_ctx(matches, TxUpdateStewardCommission);
             let tx_resign_steward =
                 Self::parse_with_ctx(matches, TxResignSteward);
+            let tx_schedule = Self::parse_with_ctx(matches, TxSchedule);
+            let tx_cancel_scheduled =
+                Self::parse_with_ctx(matches, TxCancelScheduled);
             let tx_commission_rate_change =
                 Self::parse_with_ctx(matches, TxCommissionRateChange);
             let bond = Self::parse_with_ctx(matches, Bond);
@@ -286,6 +307,12 @@ pub mod cmds {
             let query_epoch = Self::parse_with_ctx(matches, QueryEpoch);
             let query_account = Self::parse_with_ctx(matches, QueryAccount);
             let query_transfers = Self::parse_with_ctx(matches, QueryTransfers);
+            let shielded_history =
+                Self::parse_with_ctx(matches, ShieldedHistory);
+            let disclose_payment =
+                Self::parse_with_ctx(matches, DisclosePayment);
+            let verify_disclosure =
+                Self::parse_with_ctx(matches, VerifyDisclosure);
             let query_conversions =
                 Self::parse_with_ctx(matches, QueryConversions);
             let query_block = Self::parse_with_ctx(matches, QueryBlock);
@@ -306,17 +333,24 @@ pub mod cmds {
             let query_protocol_parameters =
                 Self::parse_with_ctx(matches, QueryProtocolParameters);
             let query_pgf = Self::parse_with_ctx(matches, QueryPgf);
+            let query_vesting = Self::parse_with_ctx(matches, QueryVesting);
             let query_validator_state =
                 Self::parse_with_ctx(matches, QueryValidatorState);
             let add_to_eth_bridge_pool =
                 Self::parse_with_ctx(matches, AddToEthBridgePool);
             let sign_tx = Self::parse_with_ctx(matches, SignTx);
+            let assemble_tx = Self::parse_with_ctx(matches, AssembleTx);
+            let gen_ibc_shielded =
+                Self::parse_with_ctx(matches, GenIbcShieldedTransfer);
             let utils = SubCmd::parse(matches).map(Self::WithoutContext);
             tx_custom
                 .or(tx_transfer)
                 .or(tx_ibc_transfer)
                 .or(tx_update_account)
                 .or(tx_init_account)
+                .or(tx_recover_account)
+                .or(tx_add_session_key)
+                .or(tx_revoke_session_key)
                 .or(tx_reveal_pk)
                 .or(tx_init_proposal)
                 .or(tx_vote_proposal)
@@ -329,8 +363,13 @@ pub mod cmds {
                 .or(add_to_eth_bridge_pool)
                 .or(tx_update_steward_commission)
                 .or(tx_resign_steward)
+                .or(tx_schedule)
+                .or(tx_cancel_scheduled)
                 .or(query_epoch)
                 .or(query_transfers)
+                .or(shielded_history)
+                .or(disclose_payment)
+                .or(verify_disclosure)
                 .or(query_conversions)
                 .or(query_block)
                 .or(query_balance)
@@ -345,9 +384,12 @@ pub mod cmds {
                 .or(query_proposal_result)
                 .or(query_protocol_parameters)
                 .or(query_pgf)
+                .or(query_vesting)
                 .or(query_validator_state)
                 .or(query_account)
                 .or(sign_tx)
+                .or(assemble_tx)
+                .or(gen_ibc_shielded)
                 .or(utils)
         }
     }
@@ -391,6 +433,9 @@ pub mod cmds {
         QueryResult(QueryResult),
         TxUpdateAccount(TxUpdateAccount),
         TxInitAccount(TxInitAccount),
+        TxRecoverAccount(TxRecoverAccount),
+        TxAddSessionKey(TxAddSessionKey),
+        TxRevokeSessionKey(TxRevokeSessionKey),
         TxInitValidator(TxInitValidator),
         TxCommissionRateChange(TxCommissionRateChange),
         TxUnjailValidator(TxUnjailValidator),
@@ -403,9 +448,14 @@ pub mod cmds {
         AddToEthBridgePool(AddToEthBridgePool),
         TxUpdateStewardCommission(TxUpdateStewardCommission),
         TxResignSteward(TxResignSteward),
+        TxSchedule(TxSchedule),
+        TxCancelScheduled(TxCancelScheduled),
         QueryEpoch(QueryEpoch),
         QueryAccount(QueryAccount),
         QueryTransfers(QueryTransfers),
+        ShieldedHistory(ShieldedHistory),
+        DisclosePayment(DisclosePayment),
+        VerifyDisclosure(VerifyDisclosure),
         QueryConversions(QueryConversions),
         QueryBlock(QueryBlock),
         QueryBalance(QueryBalance),
@@ -420,8 +470,11 @@ pub mod cmds {
         QueryProposalResult(QueryProposalResult),
         QueryProtocolParameters(QueryProtocolParameters),
         QueryPgf(QueryPgf),
+        QueryVesting(QueryVesting),
         QueryValidatorState(QueryValidatorState),
         SignTx(SignTx),
+        AssembleTx(AssembleTx),
+        GenIbcShieldedTransfer(GenIbcShieldedTransfer),
     }
 
     #[allow(clippy::large_enum_variant)]
@@ -1222,6 +1275,28 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct QueryVesting(pub args::QueryVesting<args::CliTypes>);
+
+    impl SubCmd for QueryVesting {
+        const CMD: &'static str = "query-vesting";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryVesting(args::QueryVesting::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query the vested and locked balances of an account.")
+                .add_args::<args::QueryVesting<args::CliTypes>>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct TxCustom(pub args::TxCustom<args::CliTypes>);
 
@@ -1279,6 +1354,32 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct GenIbcShieldedTransfer(
+        pub args::GenIbcShieldedTransfer<args::CliTypes>,
+    );
+
+    impl SubCmd for GenIbcShieldedTransfer {
+        const CMD: &'static str = "ibc-gen-shielded";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                GenIbcShieldedTransfer(args::GenIbcShieldedTransfer::parse(
+                    matches,
+                ))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Generate the shielded transfer to be set as the memo of \
+                     an IBC transfer to a payment address on this chain.",
+                )
+                .add_args::<args::GenIbcShieldedTransfer<args::CliTypes>>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct TxUpdateAccount(pub args::TxUpdateAccount<args::CliTypes>);
 
@@ -1578,6 +1679,29 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct AssembleTx(pub args::AssembleTx<args::CliTypes>);
+
+    impl SubCmd for AssembleTx {
+        const CMD: &'static str = "assemble-tx";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| AssembleTx(args::AssembleTx::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Merge the offline signatures of a dumped transaction, \
+                     check them against the threshold of the account on \
+                     chain, and submit the transaction.",
+                )
+                .add_args::<args::AssembleTx<args::CliTypes>>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct QueryValidatorState(
         pub args::QueryValidatorState<args::CliTypes>,
@@ -1618,6 +1742,71 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct ShieldedHistory(pub args::ShieldedHistory<args::CliTypes>);
+
+    impl SubCmd for ShieldedHistory {
+        const CMD: &'static str = "shielded-history";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                ShieldedHistory(args::ShieldedHistory::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Export the notes received and spent by a viewing key \
+                     with their memos and exchanged amounts.",
+                )
+                .add_args::<args::ShieldedHistory<args::CliTypes>>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct DisclosePayment(pub args::DisclosePayment<args::CliTypes>);
+
+    impl SubCmd for DisclosePayment {
+        const CMD: &'static str = "disclose-payment";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                DisclosePayment(args::DisclosePayment::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Disclose the shielded payments made in a transaction \
+                     without revealing the viewing key.",
+                )
+                .add_args::<args::DisclosePayment<args::CliTypes>>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct VerifyDisclosure(pub args::VerifyDisclosure<args::CliTypes>);
+
+    impl SubCmd for VerifyDisclosure {
+        const CMD: &'static str = "verify-disclosure";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                VerifyDisclosure(args::VerifyDisclosure::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Verify a shielded payment disclosure against the chain.",
+                )
+                .add_args::<args::VerifyDisclosure<args::CliTypes>>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct QueryCommissionRate(
         pub args::QueryCommissionRate<args::CliTypes>,
@@ -1794,6 +1983,124 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct TxSchedule(pub args::ScheduleTx<args::CliTypes>);
+
+    impl SubCmd for TxSchedule {
+        const CMD: &'static str = "schedule-tx";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| TxSchedule(args::ScheduleTx::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Schedule a transaction for execution at a future block \
+                     height or epoch.",
+                )
+                .add_args::<args::ScheduleTx<args::CliTypes>>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxCancelScheduled(pub args::CancelScheduledTx<args::CliTypes>);
+
+    impl SubCmd for TxCancelScheduled {
+        const CMD: &'static str = "cancel-scheduled-tx";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxCancelScheduled(args::CancelScheduledTx::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Cancel a scheduled transaction.")
+                .add_args::<args::CancelScheduledTx<args::CliTypes>>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxRecoverAccount(pub args::TxRecoverAccount<args::CliTypes>);
+
+    impl SubCmd for TxRecoverAccount {
+        const CMD: &'static str = "recover-account";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxRecoverAccount(args::TxRecoverAccount::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Request, approve, cancel or execute the replacement of \
+                     an account's keys by its guardians.",
+                )
+                .add_args::<args::TxRecoverAccount<args::CliTypes>>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxAddSessionKey(pub args::TxAddSessionKey<args::CliTypes>);
+
+    impl SubCmd for TxAddSessionKey {
+        const CMD: &'static str = "add-session-key";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxAddSessionKey(args::TxAddSessionKey::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Add a session key that can sign some txs for an account                      until its expiry.",
+                )
+                .add_args::<args::TxAddSessionKey<args::CliTypes>>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxRevokeSessionKey(pub args::TxRevokeSessionKey<args::CliTypes>);
+
+    impl SubCmd for TxRevokeSessionKey {
+        const CMD: &'static str = "revoke-session-key";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxRevokeSessionKey(args::TxRevokeSessionKey::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Revoke a session key of an account.")
+                .add_args::<args::TxRevokeSessionKey<args::CliTypes>>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct TxCommissionRateChange(
         pub args::CommissionRateChange<args::CliTypes>,
@@ -2461,6 +2768,7 @@ pub mod args {
 
     use namada::ibc::core::ics24_host::identifier::{ChannelId, PortId};
     pub use namada::ledger::args::*;
+    use namada::ledger::masp::PaymentDisclosure;
     use namada::types::address::Address;
     use namada::types::chain::{ChainId, ChainIdPrefix};
     use namada::types::dec::Dec;
@@ -2472,6 +2780,7 @@ pub mod args {
     use namada::types::time::DateTimeUtc;
     use namada::types::token;
     use namada::types::token::NATIVE_MAX_DECIMAL_PLACES;
+    use namada::types::transaction::scheduled::ScheduleTarget;
     use namada::types::transaction::GasLimit;
 
     use super::context::*;
@@ -2489,6 +2798,8 @@ pub mod args {
     pub const TX_INIT_ACCOUNT_WASM: &str = "tx_init_account.wasm";
     pub const TX_INIT_PROPOSAL: &str = "tx_init_proposal.wasm";
     pub const TX_INIT_VALIDATOR_WASM: &str = "tx_init_validator.wasm";
+    pub const TX_RECOVER_ACCOUNT_WASM: &str = "tx_recover_account.wasm";
+    pub const TX_UPDATE_SESSION_KEY_WASM: &str = "tx_update_session_key.wasm";
     pub const TX_REVEAL_PK: &str = "tx_reveal_pk.wasm";
     pub const TX_UPDATE_ACCOUNT_WASM: &str = "tx_update_account.wasm";
     pub const TX_TRANSFER_WASM: &str = "tx_transfer.wasm";
@@ -2500,6 +2811,8 @@ pub mod args {
     pub const TX_VOTE_PROPOSAL: &str = "tx_vote_proposal.wasm";
     pub const TX_WITHDRAW_WASM: &str = "tx_withdraw.wasm";
     pub const TX_RESIGN_STEWARD: &str = "tx_resign_steward.wasm";
+    pub const TX_SCHEDULE_WASM: &str = "tx_schedule.wasm";
+    pub const TX_CANCEL_SCHEDULED_WASM: &str = "tx_cancel_scheduled.wasm";
 
     pub const VP_USER_WASM: &str = "vp_user.wasm";
 
@@ -2508,7 +2821,11 @@ pub mod args {
     pub const ALIAS: Arg<String> = arg("alias");
     pub const ALIAS_FORCE: ArgFlag = flag("alias-force");
     pub const ALLOW_DUPLICATE_IP: ArgFlag = flag("allow-duplicate-ip");
+    pub const ALLOWED_RECIPIENTS: ArgMulti<WalletAddress> =
+        arg_multi("allowed-recipients");
+    pub const ALLOWED_TXS: ArgMulti<PathBuf> = arg_multi("allowed-txs");
     pub const AMOUNT: Arg<token::DenominatedAmount> = arg("amount");
+    pub const APPROVE: ArgFlag = flag("approve");
     pub const ARCHIVE_DIR: ArgOpt<PathBuf> = arg_opt("archive-dir");
     pub const BALANCE_OWNER: ArgOpt<WalletBalanceOwner> = arg_opt("owner");
     pub const BASE_DIR: ArgDefault<PathBuf> = arg_default(
@@ -2518,6 +2835,7 @@ pub mod args {
             Err(_) => config::get_default_namada_folder(),
         }),
     );
+    pub const BIRTHDAY: ArgOpt<BlockHeight> = arg_opt("birthday");
     pub const BLOCK_HEIGHT: Arg<BlockHeight> = arg("block-height");
     pub const BLOCK_HEIGHT_OPT: ArgOpt<BlockHeight> = arg_opt("height");
     pub const BRIDGE_POOL_GAS_AMOUNT: ArgDefault<token::DenominatedAmount> =
@@ -2537,10 +2855,13 @@ pub mod args {
         );
     pub const BRIDGE_POOL_TARGET: Arg<EthAddress> = arg("target");
     pub const BROADCAST_ONLY: ArgFlag = flag("broadcast-only");
+    pub const CANCEL: ArgFlag = flag("cancel");
     pub const CHAIN_ID: Arg<ChainId> = arg("chain-id");
     pub const CHAIN_ID_OPT: ArgOpt<ChainId> = CHAIN_ID.opt();
     pub const CHAIN_ID_PREFIX: Arg<ChainIdPrefix> = arg("chain-prefix");
     pub const CHANNEL_ID: Arg<ChannelId> = arg("channel-id");
+    pub const CLEAR_GUARDIANS: ArgFlag = flag("clear-guardians");
+    pub const CLEAR_POLICY: ArgFlag = flag("clear-policy");
     pub const CODE_PATH: Arg<PathBuf> = arg("code-path");
     pub const CODE_PATH_OPT: ArgOpt<PathBuf> = CODE_PATH.opt();
     pub const COMMISSION_RATE: Arg<Dec> = arg("commission-rate");
@@ -2556,6 +2877,7 @@ pub mod args {
     pub const DATA_PATH_OPT: ArgOpt<PathBuf> = arg_opt("data-path");
     pub const DATA_PATH: Arg<PathBuf> = arg("data-path");
     pub const DECRYPT: ArgFlag = flag("decrypt");
+    pub const DISCLOSURE: Arg<PaymentDisclosure> = arg("disclosure");
     pub const DISPOSABLE_SIGNING_KEY: ArgFlag = flag("disposable-gas-payer");
     pub const DONT_ARCHIVE: ArgFlag = flag("dont-archive");
     pub const DONT_PREFETCH_WASM: ArgFlag = flag("dont-prefetch-wasm");
@@ -2574,7 +2896,9 @@ pub mod args {
         DefaultFn(|| "http://localhost:8545".into()),
     );
     pub const ETH_SYNC: ArgFlag = flag("sync");
+    pub const EXECUTE: ArgFlag = flag("execute");
     pub const EXPIRATION_OPT: ArgOpt<DateTimeUtc> = arg_opt("expiration");
+    pub const EXPIRY_EPOCH: Arg<Epoch> = arg("expiry-epoch");
     pub const FEE_UNSHIELD_SPENDING_KEY: ArgOpt<WalletTransferSource> =
         arg_opt("gas-spending-key");
     pub const FEE_AMOUNT_OPT: ArgOpt<token::DenominatedAmount> =
@@ -2583,9 +2907,13 @@ pub mod args {
     pub const FORCE: ArgFlag = flag("force");
     pub const GAS_LIMIT: ArgDefault<GasLimit> =
         arg_default("gas-limit", DefaultFn(|| GasLimit::from(20_000)));
+    pub const GAS_REPORT: ArgFlag = flag("gas-report");
     pub const FEE_TOKEN: ArgDefaultFromCtx<WalletAddress> =
         arg_default_from_ctx("gas-token", DefaultFn(|| "NAM".parse().unwrap()));
     pub const GENESIS_PATH: Arg<PathBuf> = arg("genesis-path");
+    pub const GUARDIAN_OPT: ArgOpt<WalletAddress> = arg_opt("guardian");
+    pub const GUARDIANS: ArgMulti<WalletAddress> = arg_multi("guardians");
+    pub const GUARDIAN_THRESHOLD: ArgOpt<u8> = arg_opt("guardian-threshold");
     pub const GENESIS_VALIDATOR: ArgOpt<String> =
         arg("genesis-validator").opt();
     pub const HALT_ACTION: ArgFlag = flag("halt");
@@ -2594,7 +2922,12 @@ pub mod args {
     pub const HD_WALLET_DERIVATION_PATH_OPT: ArgOpt<String> =
         HD_WALLET_DERIVATION_PATH.opt();
     pub const HISTORIC: ArgFlag = flag("historic");
+    pub const HISTORY_FORMAT: ArgDefault<HistoryFormat> =
+        arg_default("format", DefaultFn(|| HistoryFormat::Csv));
     pub const IBC_TRANSFER_MEMO: ArgOpt<String> = arg_opt("memo");
+    pub const LARGE_DEBIT_AMOUNTS: ArgMulti<String> = arg_multi("large-debit");
+    pub const LARGE_DEBIT_THRESHOLD: ArgOpt<u8> =
+        arg_opt("large-debit-threshold");
     pub const LEDGER_ADDRESS_ABOUT: &str =
         "Address of a ledger node as \"{scheme}://{host}:{port}\". If the \
          scheme is not supplied, it is assumed to be TCP.";
@@ -2610,12 +2943,14 @@ pub mod args {
     pub const MAX_COMMISSION_RATE_CHANGE: Arg<Dec> =
         arg("max-commission-rate-change");
     pub const MAX_ETH_GAS: ArgOpt<u64> = arg_opt("max_eth-gas");
+    pub const MAX_SPEND: ArgMulti<String> = arg_multi("max-spend");
     pub const MODE: ArgOpt<String> = arg_opt("mode");
     pub const NET_ADDRESS: Arg<SocketAddr> = arg("net-address");
     pub const NAMADA_START_TIME: ArgOpt<DateTimeUtc> = arg_opt("time");
     pub const NO_CONVERSIONS: ArgFlag = flag("no-conversions");
     pub const NUT: ArgFlag = flag("nut");
     pub const OUT_FILE_PATH_OPT: ArgOpt<PathBuf> = arg_opt("out-file-path");
+    pub const OUTFLOW_LIMITS: ArgMulti<String> = arg_multi("outflow-limit");
     pub const OUTPUT_FOLDER_PATH: ArgOpt<PathBuf> =
         arg_opt("output-folder-path");
     pub const OWNER: Arg<WalletAddress> = arg("owner");
@@ -2644,10 +2979,14 @@ pub mod args {
     pub const RAW_PUBLIC_KEY_OPT: ArgOpt<common::PublicKey> =
         arg_opt("public-key");
     pub const RECEIVER: Arg<String> = arg("receiver");
+    pub const RECOVERY_DELAY: ArgOpt<u64> = arg_opt("recovery-delay");
+    pub const REFUND_TARGET: ArgOpt<WalletAddress> = arg_opt("refund-target");
     pub const RELAYER: Arg<Address> = arg("relayer");
     pub const SAFE_MODE: ArgFlag = flag("safe-mode");
     pub const SCHEME: ArgDefault<SchemeType> =
         arg_default("scheme", DefaultFn(|| SchemeType::Ed25519));
+    pub const SIGNER: ArgDefault<SignerKind> =
+        arg_default("signer", DefaultFn(|| SignerKind::Wallet));
     pub const SIGNING_KEYS: ArgMulti<WalletKeypair> = arg_multi("signing-keys");
     pub const SIGNATURES: ArgMulti<PathBuf> = arg_multi("signatures");
     pub const SOURCE: Arg<WalletAddress> = arg("source");
@@ -2660,9 +2999,11 @@ pub mod args {
     pub const TM_ADDRESS: Arg<String> = arg("tm-address");
     pub const TOKEN_OPT: ArgOpt<WalletAddress> = TOKEN.opt();
     pub const TOKEN: Arg<WalletAddress> = arg("token");
+    pub const TOKEN_STR: Arg<String> = arg("token");
     pub const TRANSFER_SOURCE: Arg<WalletTransferSource> = arg("source");
     pub const TRANSFER_TARGET: Arg<WalletTransferTarget> = arg("target");
     pub const TX_HASH: Arg<String> = arg("tx-hash");
+    pub const TX_INDEX: Arg<u32> = arg("tx-index");
     pub const THRESOLD: ArgOpt<u8> = arg_opt("threshold");
     pub const UNSAFE_DONT_ENCRYPT: ArgFlag = flag("unsafe-dont-encrypt");
     pub const UNSAFE_SHOW_SECRET: ArgFlag = flag("unsafe-show-secret");
@@ -3444,7 +3785,9 @@ pub mod args {
             app.add_args::<Tx<CliTypes>>()
                 .arg(TRANSFER_SOURCE.def().help(
                     "The source account address. The source's key may be used \
-                     to produce the signature.",
+                     to produce the signature. A viewing key spends the notes \
+                     of a spending key held by the external signer given with \
+                     --signer.",
                 ))
                 .arg(TRANSFER_TARGET.def().help(
                     "The target account address. The target's key may be used \
@@ -3459,7 +3802,7 @@ pub mod args {
         fn to_sdk(self, ctx: &mut Context) -> TxIbcTransfer<SdkTypes> {
             TxIbcTransfer::<SdkTypes> {
                 tx: self.tx.to_sdk(ctx),
-                source: ctx.get(&self.source),
+                source: ctx.get_cached(&self.source),
                 receiver: self.receiver,
                 token: ctx.get(&self.token),
                 amount: self.amount,
@@ -3468,6 +3811,10 @@ pub mod args {
                 timeout_height: self.timeout_height,
                 timeout_sec_offset: self.timeout_sec_offset,
                 memo: self.memo,
+                refund_target: self
+                    .refund_target
+                    .map(|refund_target| ctx.get(&refund_target)),
+                native_token: ctx.native_token.clone(),
                 tx_code_path: self.tx_code_path.to_path_buf(),
             }
         }
@@ -3476,7 +3823,7 @@ pub mod args {
     impl Args for TxIbcTransfer<CliTypes> {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
-            let source = SOURCE.parse(matches);
+            let source = TRANSFER_SOURCE.parse(matches);
             let receiver = RECEIVER.parse(matches);
             let token = TOKEN.parse(matches);
             let amount = InputAmount::Unvalidated(AMOUNT.parse(matches));
@@ -3485,6 +3832,7 @@ pub mod args {
             let timeout_height = TIMEOUT_HEIGHT.parse(matches);
             let timeout_sec_offset = TIMEOUT_SEC_OFFSET.parse(matches);
             let memo = IBC_TRANSFER_MEMO.parse(matches);
+            let refund_target = REFUND_TARGET.parse(matches);
             let tx_code_path = PathBuf::from(TX_IBC_WASM);
             Self {
                 tx,
@@ -3497,15 +3845,18 @@ pub mod args {
                 timeout_height,
                 timeout_sec_offset,
                 memo,
+                refund_target,
+                native_token: (),
                 tx_code_path,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx<CliTypes>>()
-                .arg(SOURCE.def().help(
-                    "The source account address. The source's key is used to \
-                     produce the signature.",
+                .arg(TRANSFER_SOURCE.def().help(
+                    "The source account address or a spending key to send \
+                     shielded tokens. The source's key is used to produce the \
+                     signature.",
                 ))
                 .arg(RECEIVER.def().help(
                     "The receiver address on the destination chain as string.",
@@ -3525,6 +3876,64 @@ pub mod args {
                         .def()
                         .help("Memo field of ICS20 transfer."),
                 )
+                .arg(REFUND_TARGET.def().help(
+                    "The transparent address refunded when the packet fails. \
+                     Required when sending from a spending key.",
+                ))
+        }
+    }
+
+    impl CliToSdk<GenIbcShieldedTransfer<SdkTypes>>
+        for GenIbcShieldedTransfer<CliTypes>
+    {
+        fn to_sdk(self, ctx: &mut Context) -> GenIbcShieldedTransfer<SdkTypes> {
+            GenIbcShieldedTransfer::<SdkTypes> {
+                query: self.query.to_sdk(ctx),
+                target: ctx.get(&self.target),
+                token: self.token,
+                amount: self.amount,
+                port_id: self.port_id,
+                channel_id: self.channel_id,
+            }
+        }
+    }
+
+    impl Args for GenIbcShieldedTransfer<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let target = TRANSFER_TARGET.parse(matches);
+            let token = TOKEN_STR.parse(matches);
+            let amount = InputAmount::Unvalidated(AMOUNT.parse(matches));
+            let port_id = PORT_ID.parse(matches);
+            let channel_id = CHANNEL_ID.parse(matches);
+            Self {
+                query,
+                target,
+                token,
+                amount,
+                port_id,
+                channel_id,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query<CliTypes>>()
+                .arg(
+                    TRANSFER_TARGET
+                        .def()
+                        .help("The payment address receiving the tokens."),
+                )
+                .arg(TOKEN_STR.def().help(
+                    "The token address on this chain, or the denom of the \
+                     token on the sending chain.",
+                ))
+                .arg(AMOUNT.def().help("The amount to transfer in decimal."))
+                .arg(PORT_ID.def().help(
+                    "The port ID of the receiving channel on this chain.",
+                ))
+                .arg(CHANNEL_ID.def().help(
+                    "The channel ID of the receiving channel on this chain.",
+                ))
         }
     }
 
@@ -3713,10 +4122,59 @@ pub mod args {
                     .map(|pk| ctx.get_cached(pk))
                     .collect(),
                 threshold: self.threshold,
+                outflow_limits: self
+                    .outflow_limits
+                    .iter()
+                    .map(|(token, amount)| (ctx.get(token), *amount))
+                    .collect(),
+                allowed_recipients: self
+                    .allowed_recipients
+                    .iter()
+                    .map(|addr| ctx.get(addr))
+                    .collect(),
+                large_debit_amounts: self
+                    .large_debit_amounts
+                    .iter()
+                    .map(|(token, amount)| (ctx.get(token), *amount))
+                    .collect(),
+                large_debit_threshold: self.large_debit_threshold,
+                clear_policy: self.clear_policy,
+                guardians: self
+                    .guardians
+                    .iter()
+                    .map(|guardian| ctx.get(guardian))
+                    .collect(),
+                guardian_threshold: self.guardian_threshold,
+                recovery_delay: self.recovery_delay,
+                clear_guardians: self.clear_guardians,
             }
         }
     }
 
+    /// Parse a list of `TOKEN=AMOUNT` pairs
+    fn parse_token_amounts(
+        values: Vec<String>,
+    ) -> Vec<(WalletAddress, token::DenominatedAmount)> {
+        values
+            .into_iter()
+            .map(|value| {
+                let parsed =
+                    value.split_once('=').and_then(|(token, amount)| {
+                        let amount =
+                            token::DenominatedAmount::from_str(amount).ok()?;
+                        Some((WalletAddress::new(token.to_owned()), amount))
+                    });
+                parsed.unwrap_or_else(|| {
+                    eprintln!(
+                        "Could not parse '{}' as a TOKEN=AMOUNT pair.",
+                        value
+                    );
+                    safe_exit(1)
+                })
+            })
+            .collect()
+    }
+
     impl Args for TxUpdateAccount<CliTypes> {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
@@ -3725,6 +4183,17 @@ pub mod args {
             let tx_code_path = PathBuf::from(TX_UPDATE_ACCOUNT_WASM);
             let public_keys = PUBLIC_KEYS.parse(matches);
             let threshold = THRESOLD.parse(matches);
+            let outflow_limits =
+                parse_token_amounts(OUTFLOW_LIMITS.parse(matches));
+            let allowed_recipients = ALLOWED_RECIPIENTS.parse(matches);
+            let large_debit_amounts =
+                parse_token_amounts(LARGE_DEBIT_AMOUNTS.parse(matches));
+            let large_debit_threshold = LARGE_DEBIT_THRESHOLD.parse(matches);
+            let clear_policy = CLEAR_POLICY.parse(matches);
+            let guardians = GUARDIANS.parse(matches);
+            let guardian_threshold = GUARDIAN_THRESHOLD.parse(matches);
+            let recovery_delay = RECOVERY_DELAY.parse(matches);
+            let clear_guardians = CLEAR_GUARDIANS.parse(matches);
             Self {
                 tx,
                 vp_code_path,
@@ -3732,6 +4201,15 @@ pub mod args {
                 tx_code_path,
                 public_keys,
                 threshold,
+                outflow_limits,
+                allowed_recipients,
+                large_debit_amounts,
+                large_debit_threshold,
+                clear_policy,
+                guardians,
+                guardian_threshold,
+                recovery_delay,
+                clear_guardians,
             }
         }
 
@@ -3755,23 +4233,256 @@ pub mod args {
                      authorization. Must be less then the maximum number of \
                      public keys provided.",
                 ))
-        }
-    }
-
-    impl CliToSdk<Bond<SdkTypes>> for Bond<CliTypes> {
-        fn to_sdk(self, ctx: &mut Context) -> Bond<SdkTypes> {
-            Bond::<SdkTypes> {
-                tx: self.tx.to_sdk(ctx),
-                validator: ctx.get(&self.validator),
-                amount: self.amount,
-                source: self.source.map(|x| ctx.get(&x)),
-                native_token: ctx.native_token.clone(),
-                tx_code_path: self.tx_code_path.to_path_buf(),
-            }
-        }
-    }
-
-    impl Args for Bond<CliTypes> {
+                .arg(OUTFLOW_LIMITS.def().help(
+                    "Per-epoch outflow limits of the account's spending \
+                     policy, given as TOKEN=AMOUNT pairs. Replaces the \
+                     current policy.",
+                ))
+                .arg(ALLOWED_RECIPIENTS.def().help(
+                    "The only addresses that the account's spending policy \
+                     allows to receive its tokens. Replaces the current \
+                     policy.",
+                ))
+                .arg(LARGE_DEBIT_AMOUNTS.def().help(
+                    "Debit amounts above which the account's spending policy \
+                     requires the large debit threshold, given as \
+                     TOKEN=AMOUNT pairs. Replaces the current policy.",
+                ))
+                .arg(LARGE_DEBIT_THRESHOLD.def().help(
+                    "The minimum number of signatures required for large \
+                     debits and for changes of the account's spending \
+                     policy. Replaces the current policy.",
+                ))
+                .arg(
+                    CLEAR_POLICY
+                        .def()
+                        .help("Remove the account's spending policy.")
+                        .conflicts_with_all([
+                            OUTFLOW_LIMITS.name,
+                            ALLOWED_RECIPIENTS.name,
+                            LARGE_DEBIT_AMOUNTS.name,
+                            LARGE_DEBIT_THRESHOLD.name,
+                        ]),
+                )
+                .arg(
+                    GUARDIANS
+                        .def()
+                        .help(
+                            "The guardians that may jointly replace the \
+                             account's keys. Replaces the current guardians.",
+                        )
+                        .requires(GUARDIAN_THRESHOLD.name)
+                        .requires(RECOVERY_DELAY.name),
+                )
+                .arg(GUARDIAN_THRESHOLD.def().help(
+                    "The number of guardians' approvals required to replace \
+                     the account's keys.",
+                ))
+                .arg(RECOVERY_DELAY.def().help(
+                    "The number of epochs between a recovery request and its \
+                     execution, during which the account can cancel it.",
+                ))
+                .arg(
+                    CLEAR_GUARDIANS
+                        .def()
+                        .help("Remove the account's guardians.")
+                        .conflicts_with(GUARDIANS.name),
+                )
+        }
+    }
+
+    impl CliToSdk<TxRecoverAccount<SdkTypes>> for TxRecoverAccount<CliTypes> {
+        fn to_sdk(self, ctx: &mut Context) -> TxRecoverAccount<SdkTypes> {
+            TxRecoverAccount::<SdkTypes> {
+                tx: self.tx.to_sdk(ctx),
+                owner: ctx.get(&self.owner),
+                guardian: self.guardian.map(|guardian| ctx.get(&guardian)),
+                public_keys: self
+                    .public_keys
+                    .iter()
+                    .map(|pk| ctx.get_cached(pk))
+                    .collect(),
+                threshold: self.threshold,
+                approve: self.approve,
+                cancel: self.cancel,
+                execute: self.execute,
+                tx_code_path: self.tx_code_path,
+            }
+        }
+    }
+
+    impl Args for TxRecoverAccount<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let owner = OWNER.parse(matches);
+            let guardian = GUARDIAN_OPT.parse(matches);
+            let public_keys = PUBLIC_KEYS.parse(matches);
+            let threshold = THRESOLD.parse(matches);
+            let approve = APPROVE.parse(matches);
+            let cancel = CANCEL.parse(matches);
+            let execute = EXECUTE.parse(matches);
+            let tx_code_path = PathBuf::from(TX_RECOVER_ACCOUNT_WASM);
+            Self {
+                tx,
+                owner,
+                guardian,
+                public_keys,
+                threshold,
+                approve,
+                cancel,
+                execute,
+                tx_code_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx<CliTypes>>()
+                .arg(OWNER.def().help("The address of the recovered account."))
+                .arg(GUARDIAN_OPT.def().help(
+                    "The guardian requesting or approving the recovery. Its \
+                     keys are used to sign the transaction.",
+                ))
+                .arg(PUBLIC_KEYS.def().help(
+                    "The new public keys of the account, to request a \
+                     recovery.",
+                ))
+                .arg(THRESOLD.def().help(
+                    "The new signature threshold of the account, to request \
+                     a recovery. Defaults to 1.",
+                ))
+                .arg(
+                    APPROVE
+                        .def()
+                        .help("Approve the pending recovery.")
+                        .requires(GUARDIAN_OPT.name),
+                )
+                .arg(CANCEL.def().help(
+                    "Cancel the pending recovery. Must be signed by the \
+                     account.",
+                ))
+                .arg(EXECUTE.def().help(
+                    "Execute the pending recovery, once it's been approved by \
+                     enough guardians and its delay has passed.",
+                ))
+                .group(ArgGroup::new("recovery_action").args([
+                    APPROVE.name,
+                    CANCEL.name,
+                    EXECUTE.name,
+                ]))
+        }
+    }
+
+    impl CliToSdk<TxAddSessionKey<SdkTypes>> for TxAddSessionKey<CliTypes> {
+        fn to_sdk(self, ctx: &mut Context) -> TxAddSessionKey<SdkTypes> {
+            TxAddSessionKey::<SdkTypes> {
+                tx: self.tx.to_sdk(ctx),
+                owner: ctx.get(&self.owner),
+                public_key: ctx.get_cached(&self.public_key),
+                allowed_txs: self.allowed_txs,
+                max_spend: self
+                    .max_spend
+                    .iter()
+                    .map(|(token, amount)| (ctx.get(token), *amount))
+                    .collect(),
+                expiry: self.expiry,
+                tx_code_path: self.tx_code_path,
+            }
+        }
+    }
+
+    impl Args for TxAddSessionKey<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let owner = OWNER.parse(matches);
+            let public_key = PUBLIC_KEY.parse(matches);
+            let allowed_txs = ALLOWED_TXS.parse(matches);
+            let max_spend = parse_token_amounts(MAX_SPEND.parse(matches));
+            let expiry = EXPIRY_EPOCH.parse(matches);
+            let tx_code_path = PathBuf::from(TX_UPDATE_SESSION_KEY_WASM);
+            Self {
+                tx,
+                owner,
+                public_key,
+                allowed_txs,
+                max_spend,
+                expiry,
+                tx_code_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx<CliTypes>>()
+                .arg(OWNER.def().help("The address of the account."))
+                .arg(
+                    PUBLIC_KEY
+                        .def()
+                        .help("The public key of the new session key."),
+                )
+                .arg(ALLOWED_TXS.def().help(
+                    "The paths to the wasm codes of the transactions that the \
+                     session key may sign. At least one is required.",
+                ))
+                .arg(MAX_SPEND.def().help(
+                    "The maximum amounts of tokens that a transaction signed \
+                     by the session key may debit from the account, given as \
+                     TOKEN=AMOUNT pairs. Other tokens cannot be debited.",
+                ))
+                .arg(EXPIRY_EPOCH.def().help(
+                    "The last epoch in which the session key can be used.",
+                ))
+        }
+    }
+
+    impl CliToSdk<TxRevokeSessionKey<SdkTypes>> for TxRevokeSessionKey<CliTypes> {
+        fn to_sdk(self, ctx: &mut Context) -> TxRevokeSessionKey<SdkTypes> {
+            TxRevokeSessionKey::<SdkTypes> {
+                tx: self.tx.to_sdk(ctx),
+                owner: ctx.get(&self.owner),
+                public_key: ctx.get_cached(&self.public_key),
+                tx_code_path: self.tx_code_path,
+            }
+        }
+    }
+
+    impl Args for TxRevokeSessionKey<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let owner = OWNER.parse(matches);
+            let public_key = PUBLIC_KEY.parse(matches);
+            let tx_code_path = PathBuf::from(TX_UPDATE_SESSION_KEY_WASM);
+            Self {
+                tx,
+                owner,
+                public_key,
+                tx_code_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx<CliTypes>>()
+                .arg(OWNER.def().help("The address of the account."))
+                .arg(
+                    PUBLIC_KEY
+                        .def()
+                        .help("The public key of the revoked session key."),
+                )
+        }
+    }
+
+    impl CliToSdk<Bond<SdkTypes>> for Bond<CliTypes> {
+        fn to_sdk(self, ctx: &mut Context) -> Bond<SdkTypes> {
+            Bond::<SdkTypes> {
+                tx: self.tx.to_sdk(ctx),
+                validator: ctx.get(&self.validator),
+                amount: self.amount,
+                source: self.source.map(|x| ctx.get(&x)),
+                native_token: ctx.native_token.clone(),
+                tx_code_path: self.tx_code_path.to_path_buf(),
+            }
+        }
+    }
+
+    impl Args for Bond<CliTypes> {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
             let validator = VALIDATOR.parse(matches);
@@ -3928,6 +4639,125 @@ pub mod args {
         }
     }
 
+    impl CliToSdk<ScheduleTx<SdkTypes>> for ScheduleTx<CliTypes> {
+        fn to_sdk(self, ctx: &mut Context) -> ScheduleTx<SdkTypes> {
+            ScheduleTx::<SdkTypes> {
+                tx: self.tx.to_sdk(ctx),
+                owner: ctx.get(&self.owner),
+                target: self.target,
+                tx_data: std::fs::read(self.tx_data).expect(""),
+                tx_code_path: self.tx_code_path.to_path_buf(),
+            }
+        }
+    }
+
+    impl Args for ScheduleTx<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let owner = OWNER.parse(matches);
+            let target = parse_schedule_target(matches);
+            let tx_data = TX_PATH.parse(matches);
+            let tx_code_path = PathBuf::from(TX_SCHEDULE_WASM);
+            Self {
+                tx,
+                owner,
+                target,
+                tx_data,
+                tx_code_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx<CliTypes>>()
+                .arg(OWNER.def().help(
+                    "The owner of the scheduled transaction, paying the fee \
+                     for its execution.",
+                ))
+                .arg(BLOCK_HEIGHT_OPT.def().help(
+                    "The block height at which the transaction is executed.",
+                ))
+                .arg(EPOCH.def().help(
+                    "The epoch at the start of which the transaction is \
+                     executed.",
+                ))
+                .group(
+                    ArgGroup::new("schedule_target")
+                        .args([BLOCK_HEIGHT_OPT.name, EPOCH.name])
+                        .required(true),
+                )
+                .arg(TX_PATH.def().help(
+                    "The path to the serialized transaction to schedule, as \
+                     produced by `--dump-tx`. It is signed with the owner's \
+                     keys.",
+                ))
+        }
+    }
+
+    /// Parse the height or epoch targeted by a scheduled transaction, one of
+    /// which is required by the `schedule_target` group
+    fn parse_schedule_target(matches: &ArgMatches) -> ScheduleTarget {
+        match EPOCH.parse(matches) {
+            Some(epoch) => ScheduleTarget::Epoch(epoch),
+            None => ScheduleTarget::Height(
+                BLOCK_HEIGHT_OPT
+                    .parse(matches)
+                    .expect("Either a block height or an epoch is required"),
+            ),
+        }
+    }
+
+    impl CliToSdk<CancelScheduledTx<SdkTypes>> for CancelScheduledTx<CliTypes> {
+        fn to_sdk(self, ctx: &mut Context) -> CancelScheduledTx<SdkTypes> {
+            CancelScheduledTx::<SdkTypes> {
+                tx: self.tx.to_sdk(ctx),
+                owner: ctx.get(&self.owner),
+                target: self.target,
+                tx_hash: self.tx_hash,
+                tx_code_path: self.tx_code_path.to_path_buf(),
+            }
+        }
+    }
+
+    impl Args for CancelScheduledTx<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let owner = OWNER.parse(matches);
+            let target = parse_schedule_target(matches);
+            let tx_hash = TX_HASH.parse(matches);
+            let tx_code_path = PathBuf::from(TX_CANCEL_SCHEDULED_WASM);
+            Self {
+                tx,
+                owner,
+                target,
+                tx_hash,
+                tx_code_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx<CliTypes>>()
+                .arg(
+                    OWNER.def().help("The owner of the scheduled transaction."),
+                )
+                .arg(BLOCK_HEIGHT_OPT.def().help(
+                    "The block height at which the transaction is scheduled.",
+                ))
+                .arg(EPOCH.def().help(
+                    "The epoch at which the transaction is scheduled.",
+                ))
+                .group(
+                    ArgGroup::new("schedule_target")
+                        .args([BLOCK_HEIGHT_OPT.name, EPOCH.name])
+                        .required(true),
+                )
+                .arg(
+                    TX_HASH
+                        .def()
+                        .help("The hash of the scheduled transaction."),
+                )
+        }
+    }
+
     impl CliToSdk<InitProposal<SdkTypes>> for InitProposal<CliTypes> {
         fn to_sdk(self, ctx: &mut Context) -> InitProposal<SdkTypes> {
             InitProposal::<SdkTypes> {
@@ -4253,6 +5083,32 @@ pub mod args {
         }
     }
 
+    impl CliToSdk<QueryVesting<SdkTypes>> for QueryVesting<CliTypes> {
+        fn to_sdk(self, ctx: &mut Context) -> QueryVesting<SdkTypes> {
+            QueryVesting::<SdkTypes> {
+                query: self.query.to_sdk(ctx),
+                owner: ctx.get(&self.owner),
+            }
+        }
+    }
+
+    impl Args for QueryVesting<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let owner = OWNER.parse(matches);
+            Self { query, owner }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query<CliTypes>>().arg(
+                OWNER
+                    .def()
+                    .help("The address of the vesting account.")
+                    .required(true),
+            )
+        }
+    }
+
     impl CliToSdk<Withdraw<SdkTypes>> for Withdraw<CliTypes> {
         fn to_sdk(self, ctx: &mut Context) -> Withdraw<SdkTypes> {
             Withdraw::<SdkTypes> {
@@ -4430,6 +5286,115 @@ pub mod args {
         }
     }
 
+    impl CliToSdk<ShieldedHistory<SdkTypes>> for ShieldedHistory<CliTypes> {
+        fn to_sdk(self, ctx: &mut Context) -> ShieldedHistory<SdkTypes> {
+            ShieldedHistory::<SdkTypes> {
+                query: self.query.to_sdk(ctx),
+                viewing_key: ctx.get_cached(&self.viewing_key),
+                format: self.format,
+                output: self.output,
+            }
+        }
+    }
+
+    impl Args for ShieldedHistory<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let viewing_key = VIEWING_KEY.parse(matches);
+            let format = HISTORY_FORMAT.parse(matches);
+            let output = OUT_FILE_PATH_OPT.parse(matches);
+            Self {
+                query,
+                viewing_key,
+                format,
+                output,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query<CliTypes>>()
+                .arg(
+                    VIEWING_KEY
+                        .def()
+                        .help("The viewing key whose history is exported."),
+                )
+                .arg(HISTORY_FORMAT.def().help(
+                    "The format of the report, either \"csv\" or \"json\". \
+                     Defaults to \"csv\".",
+                ))
+                .arg(OUT_FILE_PATH_OPT.def().help(
+                    "The file to write the report to. Defaults to the \
+                     standard output.",
+                ))
+        }
+    }
+
+    impl CliToSdk<DisclosePayment<SdkTypes>> for DisclosePayment<CliTypes> {
+        fn to_sdk(self, ctx: &mut Context) -> DisclosePayment<SdkTypes> {
+            DisclosePayment::<SdkTypes> {
+                query: self.query.to_sdk(ctx),
+                viewing_key: ctx.get_cached(&self.viewing_key),
+                height: self.height,
+                tx_index: self.tx_index,
+            }
+        }
+    }
+
+    impl Args for DisclosePayment<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let viewing_key = VIEWING_KEY.parse(matches);
+            let height = BLOCK_HEIGHT.parse(matches);
+            let tx_index = storage::TxIndex(TX_INDEX.parse(matches));
+            Self {
+                query,
+                viewing_key,
+                height,
+                tx_index,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query<CliTypes>>()
+                .arg(VIEWING_KEY.def().help(
+                    "The viewing key of the account that made the payments.",
+                ))
+                .arg(BLOCK_HEIGHT.def().help(
+                    "The height of the block containing the transaction.",
+                ))
+                .arg(
+                    TX_INDEX
+                        .def()
+                        .help("The index of the transaction within its block."),
+                )
+        }
+    }
+
+    impl CliToSdk<VerifyDisclosure<SdkTypes>> for VerifyDisclosure<CliTypes> {
+        fn to_sdk(self, ctx: &mut Context) -> VerifyDisclosure<SdkTypes> {
+            VerifyDisclosure::<SdkTypes> {
+                query: self.query.to_sdk(ctx),
+                disclosure: self.disclosure,
+            }
+        }
+    }
+
+    impl Args for VerifyDisclosure<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let disclosure = DISCLOSURE.parse(matches);
+            Self { query, disclosure }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query<CliTypes>>().arg(
+                DISCLOSURE
+                    .def()
+                    .help("The payment disclosure produced by the payer."),
+            )
+        }
+    }
+
     impl CliToSdk<QueryBonds<SdkTypes>> for QueryBonds<CliTypes> {
         fn to_sdk(self, ctx: &mut Context) -> QueryBonds<SdkTypes> {
             QueryBonds::<SdkTypes> {
@@ -4641,6 +5606,38 @@ pub mod args {
         }
     }
 
+    impl CliToSdk<AssembleTx<SdkTypes>> for AssembleTx<CliTypes> {
+        fn to_sdk(self, ctx: &mut Context) -> AssembleTx<SdkTypes> {
+            AssembleTx::<SdkTypes> {
+                tx: self.tx.to_sdk(ctx),
+                tx_data: std::fs::read(self.tx_data).expect(""),
+                owner: ctx.get(&self.owner),
+            }
+        }
+    }
+
+    impl Args for AssembleTx<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let tx_path = TX_PATH.parse(matches);
+            let owner = OWNER.parse(matches);
+            Self {
+                tx,
+                tx_data: tx_path,
+                owner,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx<CliTypes>>()
+                .arg(TX_PATH.def().help(
+                    "The path to the tx file with the serialized tx, as \
+                     dumped with --dump-tx.",
+                ))
+                .arg(OWNER.def().help("The address of the account owner"))
+        }
+    }
+
     impl CliToSdk<QueryCommissionRate<SdkTypes>> for QueryCommissionRate<CliTypes> {
         fn to_sdk(self, ctx: &mut Context) -> QueryCommissionRate<SdkTypes> {
             QueryCommissionRate::<SdkTypes> {
@@ -4796,6 +5793,7 @@ pub mod args {
             Tx::<SdkTypes> {
                 dry_run: self.dry_run,
                 dry_run_wrapper: self.dry_run_wrapper,
+                gas_report: self.gas_report,
                 dump_tx: self.dump_tx,
                 output_folder: self.output_folder,
                 force: self.force,
@@ -4825,6 +5823,7 @@ pub mod args {
                 disposable_signing_key: self.disposable_signing_key,
                 tx_reveal_code_path: self.tx_reveal_code_path,
                 password: self.password,
+                signer: self.signer,
                 expiration: self.expiration,
                 chain_id: self
                     .chain_id
@@ -4853,6 +5852,11 @@ pub mod args {
                     )
                     .conflicts_with(DRY_RUN_TX.name),
             )
+            .arg(GAS_REPORT.def().help(
+                "Print a breakdown of the gas used by the transaction per \
+                 host function, validity predicate and storage key. Only \
+                 used with --dry-run or --dry-run-wrapper.",
+            ))
             .arg(DUMP_TX.def().help("Dump transaction bytes to a file."))
             .arg(FORCE.def().help(
                 "Submit the transaction even if it doesn't pass client checks.",
@@ -4957,11 +5961,20 @@ pub mod args {
                     )
                     .conflicts_with(DISPOSABLE_SIGNING_KEY.name),
             )
+            .arg(SIGNER.def().help(
+                "The holder of the signing keys: \"wallet\" for the local \
+                 wallet, \"stdio:<command>\" for an external signer process \
+                 spawned with the given command, whose arguments may be \
+                 quoted, or \"unix:<socket path>\" for an external signer \
+                 listening on the given Unix socket. External signers are \
+                 sent a JSON request per line.",
+            ))
         }
 
         fn parse(matches: &ArgMatches) -> Self {
             let dry_run = DRY_RUN_TX.parse(matches);
             let dry_run_wrapper = DRY_RUN_WRAPPER_TX.parse(matches);
+            let gas_report = GAS_REPORT.parse(matches);
             let dump_tx = DUMP_TX.parse(matches);
             let force = FORCE.parse(matches);
             let broadcast_only = BROADCAST_ONLY.parse(matches);
@@ -4982,11 +5995,13 @@ pub mod args {
             let tx_reveal_code_path = PathBuf::from(TX_REVEAL_PK);
             let chain_id = CHAIN_ID_OPT.parse(matches);
             let password = None;
+            let signer = SIGNER.parse(matches);
             let wrapper_fee_payer = FEE_PAYER_OPT.parse(matches);
             let output_folder = OUTPUT_FOLDER_PATH.parse(matches);
             Self {
                 dry_run,
                 dry_run_wrapper,
+                gas_report,
                 dump_tx,
                 force,
                 broadcast_only,
@@ -5004,6 +6019,7 @@ pub mod args {
                 verification_key,
                 tx_reveal_code_path,
                 password,
+                signer,
                 chain_id,
                 wrapper_fee_payer,
                 output_folder,
@@ -5039,11 +6055,13 @@ pub mod args {
             let alias = ALIAS.parse(matches);
             let alias_force = ALIAS_FORCE.parse(matches);
             let value = MASP_VALUE.parse(matches);
+            let birthday = BIRTHDAY.parse(matches);
             let unsafe_dont_encrypt = UNSAFE_DONT_ENCRYPT.parse(matches);
             Self {
                 alias,
                 alias_force,
                 value,
+                birthday,
                 unsafe_dont_encrypt,
             }
         }
@@ -5062,6 +6080,11 @@ pub mod args {
                     .def()
                     .help("A spending key, viewing key, or payment address."),
             )
+            .arg(BIRTHDAY.def().help(
+                "The block height before which the key cannot have received \
+                 any notes. The shielded pool is only scanned for the key's \
+                 notes from this height on.",
+            ))
             .arg(UNSAFE_DONT_ENCRYPT.def().help(
                 "UNSAFE: Do not encrypt the keypair. Do not use this for keys \
                  used in a live network.",
@@ -5073,10 +6096,12 @@ pub mod args {
         fn parse(matches: &ArgMatches) -> Self {
             let alias = ALIAS.parse(matches);
             let alias_force = ALIAS_FORCE.parse(matches);
+            let birthday = BIRTHDAY.parse(matches);
             let unsafe_dont_encrypt = UNSAFE_DONT_ENCRYPT.parse(matches);
             Self {
                 alias,
                 alias_force,
+                birthday,
                 unsafe_dont_encrypt,
             }
         }
@@ -5090,6 +6115,11 @@ pub mod args {
             .arg(ALIAS_FORCE.def().help(
                 "Override the alias without confirmation if it already exists.",
             ))
+            .arg(BIRTHDAY.def().help(
+                "The block height before which the key cannot have received \
+                 any notes. The shielded pool is only scanned for the key's \
+                 notes from this height on.",
+            ))
             .arg(UNSAFE_DONT_ENCRYPT.def().help(
                 "UNSAFE: Do not encrypt the keypair. Do not use this for keys \
                  used in a live network.",
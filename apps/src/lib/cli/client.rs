@@ -92,6 +92,48 @@ impl<IO> CliApi<IO> {
                         tx::submit_update_account(&client, &mut ctx, args)
                             .await?;
                     }
+                    Sub::TxRecoverAccount(TxRecoverAccount(mut args)) => {
+                        let client = client.unwrap_or_else(|| {
+                            C::from_tendermint_address(
+                                &mut args.tx.ledger_address,
+                            )
+                        });
+                        client
+                            .wait_until_node_is_synced()
+                            .await
+                            .proceed_or_else(error)?;
+                        let args = args.to_sdk(&mut ctx);
+                        tx::submit_recover_account(&client, &mut ctx, args)
+                            .await?;
+                    }
+                    Sub::TxAddSessionKey(TxAddSessionKey(mut args)) => {
+                        let client = client.unwrap_or_else(|| {
+                            C::from_tendermint_address(
+                                &mut args.tx.ledger_address,
+                            )
+                        });
+                        client
+                            .wait_until_node_is_synced()
+                            .await
+                            .proceed_or_else(error)?;
+                        let args = args.to_sdk(&mut ctx);
+                        tx::submit_add_session_key(&client, &mut ctx, args)
+                            .await?;
+                    }
+                    Sub::TxRevokeSessionKey(TxRevokeSessionKey(mut args)) => {
+                        let client = client.unwrap_or_else(|| {
+                            C::from_tendermint_address(
+                                &mut args.tx.ledger_address,
+                            )
+                        });
+                        client
+                            .wait_until_node_is_synced()
+                            .await
+                            .proceed_or_else(error)?;
+                        let args = args.to_sdk(&mut ctx);
+                        tx::submit_revoke_session_key(&client, &mut ctx, args)
+                            .await?;
+                    }
                     Sub::TxInitAccount(TxInitAccount(mut args)) => {
                         let client = client.unwrap_or_else(|| {
                             C::from_tendermint_address(
@@ -339,6 +381,33 @@ impl<IO> CliApi<IO> {
                         let args = args.to_sdk(&mut ctx);
                         tx::submit_resign_steward(&client, ctx, args).await?;
                     }
+                    Sub::TxSchedule(TxSchedule(mut args)) => {
+                        let client = client.unwrap_or_else(|| {
+                            C::from_tendermint_address(
+                                &mut args.tx.ledger_address,
+                            )
+                        });
+                        client
+                            .wait_until_node_is_synced()
+                            .await
+                            .proceed_or_else(error)?;
+                        let args = args.to_sdk(&mut ctx);
+                        tx::submit_schedule_tx(&client, ctx, args).await?;
+                    }
+                    Sub::TxCancelScheduled(TxCancelScheduled(mut args)) => {
+                        let client = client.unwrap_or_else(|| {
+                            C::from_tendermint_address(
+                                &mut args.tx.ledger_address,
+                            )
+                        });
+                        client
+                            .wait_until_node_is_synced()
+                            .await
+                            .proceed_or_else(error)?;
+                        let args = args.to_sdk(&mut ctx);
+                        tx::submit_cancel_scheduled_tx(&client, ctx, args)
+                            .await?;
+                    }
                     // Ledger queries
                     Sub::QueryEpoch(QueryEpoch(mut args)) => {
                         let client = client.unwrap_or_else(|| {
@@ -387,6 +456,63 @@ impl<IO> CliApi<IO> {
                         )
                         .await;
                     }
+                    Sub::ShieldedHistory(ShieldedHistory(mut args)) => {
+                        let client = client.unwrap_or_else(|| {
+                            C::from_tendermint_address(
+                                &mut args.query.ledger_address,
+                            )
+                        });
+                        client
+                            .wait_until_node_is_synced()
+                            .await
+                            .proceed_or_else(error)?;
+                        let args = args.to_sdk(&mut ctx);
+                        rpc::query_shielded_history(
+                            &client,
+                            &mut ctx.wallet,
+                            &mut ctx.shielded,
+                            args,
+                        )
+                        .await;
+                    }
+                    Sub::DisclosePayment(DisclosePayment(mut args)) => {
+                        let client = client.unwrap_or_else(|| {
+                            C::from_tendermint_address(
+                                &mut args.query.ledger_address,
+                            )
+                        });
+                        client
+                            .wait_until_node_is_synced()
+                            .await
+                            .proceed_or_else(error)?;
+                        let args = args.to_sdk(&mut ctx);
+                        rpc::disclose_payment(
+                            &client,
+                            &mut ctx.wallet,
+                            &mut ctx.shielded,
+                            args,
+                        )
+                        .await;
+                    }
+                    Sub::VerifyDisclosure(VerifyDisclosure(mut args)) => {
+                        let client = client.unwrap_or_else(|| {
+                            C::from_tendermint_address(
+                                &mut args.query.ledger_address,
+                            )
+                        });
+                        client
+                            .wait_until_node_is_synced()
+                            .await
+                            .proceed_or_else(error)?;
+                        let args = args.to_sdk(&mut ctx);
+                        rpc::verify_disclosure(
+                            &client,
+                            &mut ctx.wallet,
+                            &mut ctx.shielded,
+                            args,
+                        )
+                        .await;
+                    }
                     Sub::QueryConversions(QueryConversions(mut args)) => {
                         let client = client.unwrap_or_else(|| {
                             C::from_tendermint_address(
@@ -598,6 +724,19 @@ impl<IO> CliApi<IO> {
                         let args = args.to_sdk(&mut ctx);
                         rpc::query_pgf(&client, args).await;
                     }
+                    Sub::QueryVesting(QueryVesting(mut args)) => {
+                        let client = client.unwrap_or_else(|| {
+                            C::from_tendermint_address(
+                                &mut args.query.ledger_address,
+                            )
+                        });
+                        client
+                            .wait_until_node_is_synced()
+                            .await
+                            .proceed_or_else(error)?;
+                        let args = args.to_sdk(&mut ctx);
+                        rpc::query_vesting(&client, &ctx.wallet, args).await;
+                    }
                     Sub::QueryAccount(QueryAccount(mut args)) => {
                         let client = client.unwrap_or_else(|| {
                             C::from_tendermint_address(
@@ -624,6 +763,35 @@ impl<IO> CliApi<IO> {
                         let args = args.to_sdk(&mut ctx);
                         tx::sign_tx(&client, &mut ctx, args).await?;
                     }
+                    Sub::AssembleTx(AssembleTx(mut args)) => {
+                        let client = client.unwrap_or_else(|| {
+                            C::from_tendermint_address(
+                                &mut args.tx.ledger_address,
+                            )
+                        });
+                        client
+                            .wait_until_node_is_synced()
+                            .await
+                            .proceed_or_else(error)?;
+                        let args = args.to_sdk(&mut ctx);
+                        tx::assemble_tx(&client, &mut ctx, args).await?;
+                    }
+                    Sub::GenIbcShieldedTransfer(GenIbcShieldedTransfer(
+                        mut args,
+                    )) => {
+                        let client = client.unwrap_or_else(|| {
+                            C::from_tendermint_address(
+                                &mut args.query.ledger_address,
+                            )
+                        });
+                        client
+                            .wait_until_node_is_synced()
+                            .await
+                            .proceed_or_else(error)?;
+                        let args = args.to_sdk(&mut ctx);
+                        tx::gen_ibc_shielded_transfer(&client, &mut ctx, args)
+                            .await?;
+                    }
                 }
             }
             cli::NamadaClient::WithoutContext(cmd, global_args) => match cmd {
@@ -139,12 +139,15 @@ impl Context {
                 }
             }
         }
+        let mut shielded = CLIShieldedUtils::new(chain_dir);
+        // Keys need not be scanned for before their birthday
+        shielded.set_birthdays(wallet.get_viewing_key_birthdays());
         Ok(Self {
             global_args,
             wallet,
             global_config,
             config,
-            shielded: CLIShieldedUtils::new(chain_dir),
+            shielded,
             native_token,
         })
     }
@@ -469,13 +472,18 @@ impl ArgFromMutContext for TransferSource {
         raw: impl AsRef<str>,
     ) -> Result<Self, String> {
         let raw = raw.as_ref();
-        // Either the string is a transparent address or a spending key
+        // Either the string is a transparent address or a spending key, or a
+        // viewing key whose spending key is held by an external signer
         Address::arg_from_ctx(ctx, raw)
             .map(Self::Address)
             .or_else(|_| {
                 ExtendedSpendingKey::arg_from_mut_ctx(ctx, raw)
                     .map(Self::ExtendedSpendingKey)
             })
+            .or_else(|_| {
+                ExtendedViewingKey::arg_from_mut_ctx(ctx, raw)
+                    .map(Self::ExtendedViewingKey)
+            })
     }
 }
 
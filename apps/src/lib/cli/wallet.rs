@@ -216,6 +216,7 @@ fn spending_key_gen(
     args::MaspSpendKeyGen {
         alias,
         alias_force,
+        birthday,
         unsafe_dont_encrypt,
     }: args::MaspSpendKeyGen,
 ) {
@@ -223,6 +224,11 @@ fn spending_key_gen(
     let alias = alias.to_lowercase();
     let password = read_and_confirm_encryption_password(unsafe_dont_encrypt);
     let (alias, _key) = wallet.gen_spending_key(alias, password, alias_force);
+    if let Some(birthday) = birthday {
+        wallet
+            .set_birthday(&alias, birthday)
+            .expect("The spending key has just been added");
+    }
     crate::wallet::save(&wallet).unwrap_or_else(|err| eprintln!("{}", err));
     println!(
         "Successfully added a spending key with alias: \"{}\"",
@@ -271,10 +277,15 @@ fn address_key_add(
         alias,
         alias_force,
         value,
+        birthday,
         unsafe_dont_encrypt,
     }: args::MaspAddrKeyAdd,
 ) {
     let alias = alias.to_lowercase();
+    if birthday.is_some() && matches!(value, MaspValue::PaymentAddress(_)) {
+        eprintln!("A birthday can only be given to spending and viewing keys");
+        cli::safe_exit(1);
+    }
     let (alias, typ) = match value {
         MaspValue::FullViewingKey(viewing_key) => {
             let alias = ctx
@@ -314,6 +325,11 @@ fn address_key_add(
             (alias, "payment address")
         }
     };
+    if let Some(birthday) = birthday {
+        ctx.wallet
+            .set_birthday(&alias, birthday)
+            .expect("The key has just been added");
+    }
     crate::wallet::save(&ctx.wallet).unwrap_or_else(|err| eprintln!("{}", err));
     println!(
         "Successfully added a {} with the following alias to wallet: {}",
@@ -11,6 +11,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use data_encoding::HEXLOWER;
 use itertools::Either;
 use masp_primitives::asset_type::AssetType;
+use masp_primitives::memo::MemoBytes;
 use masp_primitives::merkle_tree::MerklePath;
 use masp_primitives::sapling::{Node, ViewingKey};
 use masp_primitives::zip32::ExtendedFullViewingKey;
@@ -28,9 +29,15 @@ use namada::core::ledger::governance::utils::{
 };
 use namada::core::ledger::pgf::parameters::PgfParameters;
 use namada::core::ledger::pgf::storage::steward::StewardDetail;
+use namada::core::ledger::vesting::VestingBalance;
+use namada::core::types::account::{
+    AccountGuardians, AccountPolicy, OutflowAllowance, PendingRecovery,
+    SessionKey,
+};
 use namada::ledger::events::Event;
 use namada::ledger::masp::{
-    Conversions, MaspAmount, MaspChange, ShieldedContext, ShieldedUtils,
+    Conversions, MaspAmount, MaspChange, NoteEvent, ShieldedContext,
+    ShieldedUtils,
 };
 use namada::ledger::parameters::{storage as param_storage, EpochDuration};
 use namada::ledger::pos::{CommissionPair, PosParams, Slash};
@@ -246,6 +253,243 @@ pub async fn query_transfers<
     }
 }
 
+/// Export the notes received and spent by a viewing key as a CSV or JSON
+/// report
+pub async fn query_shielded_history<
+    C: namada::ledger::queries::Client + Sync,
+    U: ShieldedUtils,
+>(
+    client: &C,
+    wallet: &mut Wallet<CliWalletUtils>,
+    shielded: &mut ShieldedContext<U>,
+    args: args::ShieldedHistory,
+) {
+    let vk = ExtendedFullViewingKey::from(args.viewing_key).fvk.vk;
+    let _ = shielded.load().await;
+    let history = shielded
+        .query_shielded_history(client, &vk, &wallet.get_viewing_keys())
+        .await
+        .unwrap_or_else(|err| {
+            eprintln!("Querying the shielded history failed: {}", err);
+            cli::safe_exit(1)
+        });
+    // Flatten the history into one row per note event and exchanged token
+    let mut rows = vec![];
+    for entry in history {
+        let (note_epoch, note_token, note_amount) =
+            match entry.amount.iter().next() {
+                Some(((epoch, token), change)) => (
+                    epoch.to_string(),
+                    token.clone(),
+                    format_change(client, token, *change).await,
+                ),
+                None => continue,
+            };
+        let event = match entry.event {
+            NoteEvent::Received => "received",
+            NoteEvent::Spent => "spent",
+        };
+        let memo = memo_to_string(&entry.memo);
+        for (token, change) in entry.exchanged {
+            rows.push(vec![
+                entry.height.to_string(),
+                entry.index.0.to_string(),
+                entry.epoch.to_string(),
+                event.to_string(),
+                entry.note_pos.to_string(),
+                note_token.to_string(),
+                note_epoch.clone(),
+                note_amount.clone(),
+                token.to_string(),
+                wallet.lookup_alias(&token),
+                format_change(client, &token, change).await,
+                memo.clone(),
+            ]);
+        }
+    }
+    const COLUMNS: [&str; 12] = [
+        "height",
+        "index",
+        "epoch",
+        "event",
+        "note_position",
+        "note_token",
+        "note_epoch",
+        "note_amount",
+        "token",
+        "token_alias",
+        "exchanged_amount",
+        "memo",
+    ];
+    let report = match args.format {
+        args::HistoryFormat::Csv => {
+            let mut report = COLUMNS.join(",");
+            report.push('\n');
+            for row in rows {
+                let row: Vec<_> =
+                    row.iter().map(String::as_str).map(csv_field).collect();
+                report.push_str(&row.join(","));
+                report.push('\n');
+            }
+            report
+        }
+        args::HistoryFormat::Json => {
+            let rows: Vec<serde_json::Map<String, serde_json::Value>> = rows
+                .into_iter()
+                .map(|row| {
+                    COLUMNS
+                        .iter()
+                        .map(|column| column.to_string())
+                        .zip(row.into_iter().map(serde_json::Value::String))
+                        .collect()
+                })
+                .collect();
+            serde_json::to_string_pretty(&rows)
+                .expect("Serializing the shielded history shouldn't fail")
+        }
+    };
+    match args.output {
+        Some(path) => {
+            fs::write(&path, report).unwrap_or_else(|err| {
+                eprintln!(
+                    "Writing the shielded history to {} failed: {}",
+                    path.to_string_lossy(),
+                    err
+                );
+                cli::safe_exit(1)
+            });
+            println!("Shielded history written to {}", path.to_string_lossy());
+        }
+        None => println!("{}", report),
+    }
+}
+
+/// Format a signed change of the given token with its denomination
+async fn format_change<C: namada::ledger::queries::Client + Sync>(
+    client: &C,
+    token: &Address,
+    change: Change,
+) -> String {
+    let sign = if change.is_negative() { "-" } else { "" };
+    format!(
+        "{}{}",
+        sign,
+        format_denominated_amount(client, token, change.into()).await
+    )
+}
+
+/// Render a note memo as text if it is valid UTF-8, or as hex otherwise
+fn memo_to_string(memo: &MemoBytes) -> String {
+    let bytes = memo.as_slice();
+    // The empty memo starts with the 0xF6 byte
+    if bytes.first() == Some(&0xF6) {
+        return String::new();
+    }
+    let end = bytes.iter().rposition(|b| *b != 0).map_or(0, |pos| pos + 1);
+    match std::str::from_utf8(&bytes[..end]) {
+        Ok(text) => text.to_string(),
+        Err(_) => HEXLOWER.encode(bytes),
+    }
+}
+
+/// Quote a CSV field if it contains a separator, a quote or a line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Disclose the shielded payments made with a viewing key in a transaction
+pub async fn disclose_payment<
+    C: namada::ledger::queries::Client + Sync,
+    U: ShieldedUtils,
+>(
+    client: &C,
+    wallet: &mut Wallet<CliWalletUtils>,
+    shielded: &mut ShieldedContext<U>,
+    args: args::DisclosePayment,
+) {
+    let fvk = ExtendedFullViewingKey::from(args.viewing_key);
+    let disclosures = ShieldedContext::<U>::disclose_payments(
+        client,
+        &fvk,
+        args.height,
+        args.tx_index,
+    )
+    .await
+    .unwrap_or_else(|err| {
+        eprintln!("Disclosing the payments failed: {}", err);
+        cli::safe_exit(1)
+    });
+    if disclosures.is_empty() {
+        println!(
+            "No payments made with the given viewing key were found in the \
+             transaction."
+        );
+        return;
+    }
+    for disclosure in disclosures {
+        println!("Output {}:", disclosure.output_idx);
+        println!("  Payment address: {}", disclosure.payment_address);
+        let decoded = shielded
+            .decode_asset_type(client, disclosure.asset_type)
+            .await;
+        match decoded {
+            Some((token, denom, _)) => {
+                let amount = token::Amount::from_masp_denominated(
+                    disclosure.value,
+                    denom,
+                );
+                println!(
+                    "  Amount: {} {}",
+                    format_denominated_amount(client, &token, amount).await,
+                    wallet.lookup_alias(&token)
+                );
+            }
+            None => println!(
+                "  Amount: {} of asset type {}",
+                disclosure.value, disclosure.asset_type
+            ),
+        }
+        println!("  Memo: {}", memo_to_string(&disclosure.memo));
+        println!("  Disclosure: {}", disclosure);
+    }
+}
+
+/// Verify a shielded payment disclosure against the chain
+pub async fn verify_disclosure<
+    C: namada::ledger::queries::Client + Sync,
+    U: ShieldedUtils,
+>(
+    client: &C,
+    wallet: &mut Wallet<CliWalletUtils>,
+    shielded: &mut ShieldedContext<U>,
+    args: args::VerifyDisclosure,
+) {
+    let disclosure = args.disclosure;
+    let payment = shielded
+        .verify_payment_disclosure(client, &disclosure)
+        .await
+        .unwrap_or_else(|err| {
+            eprintln!("The payment disclosure is invalid: {}", err);
+            cli::safe_exit(1)
+        });
+    println!("The payment disclosure is valid.");
+    println!(
+        "  Transaction: height {}, index {}, epoch {}",
+        disclosure.height, disclosure.index.0, payment.epoch
+    );
+    println!("  Payment address: {}", disclosure.payment_address);
+    println!(
+        "  Amount: {} {}",
+        format_denominated_amount(client, &payment.token, payment.amount).await,
+        wallet.lookup_alias(&payment.token)
+    );
+    println!("  Memo: {}", memo_to_string(&disclosure.memo));
+}
+
 /// Query the raw bytes of given storage key
 pub async fn query_raw_bytes<C: namada::ledger::queries::Client + Sync>(
     client: &C,
@@ -1032,11 +1276,189 @@ pub async fn query_account<C: namada::ledger::queries::Client + Sync>(
         for (public_key, _) in account.public_keys_map.pk_to_idx {
             println!("- {}", public_key);
         }
+        if let Some(policy) = query_account_policy(client, &args.owner).await {
+            print_account_policy(client, &args.owner, policy).await;
+        }
+        if let Some(guardians) =
+            query_account_guardians(client, &args.owner).await
+        {
+            print_account_guardians(guardians);
+        }
+        if let Some(recovery) =
+            query_pending_recovery(client, &args.owner).await
+        {
+            print_pending_recovery(recovery);
+        }
+        let session_keys = query_session_keys(client, &args.owner).await;
+        if !session_keys.is_empty() {
+            print_session_keys(client, session_keys).await;
+        }
     } else {
         println!("No account exists for {}", args.owner);
     }
 }
 
+/// Print the spending policy of an account and its remaining allowances
+async fn print_account_policy<C: namada::ledger::queries::Client + Sync>(
+    client: &C,
+    owner: &Address,
+    policy: AccountPolicy,
+) {
+    println!("Spending policy:");
+    if let Some(threshold) = policy.large_debit_threshold {
+        println!("{:2}Large debit threshold: {}", "", threshold);
+    }
+    if !policy.allowed_recipients.is_empty() {
+        println!("{:2}Allowed recipients:", "");
+        for recipient in policy.allowed_recipients {
+            println!("{:4}- {}", "", recipient);
+        }
+    }
+    if !policy.large_debit_amounts.is_empty() {
+        println!("{:2}Large debit amounts:", "");
+        for (token, amount) in policy.large_debit_amounts {
+            println!(
+                "{:4}- {}: {}",
+                "",
+                token,
+                format_denominated_amount(client, &token, amount).await
+            );
+        }
+    }
+    let allowances = query_outflow_allowances(client, owner).await;
+    if !allowances.is_empty() {
+        println!("{:2}Outflow limits in the current epoch:", "");
+        for OutflowAllowance {
+            token,
+            limit,
+            spent,
+            remaining,
+        } in allowances
+        {
+            println!("{:4}- {}", "", token);
+            println!(
+                "{:6}Limit: {}",
+                "",
+                format_denominated_amount(client, &token, limit).await
+            );
+            println!(
+                "{:6}Spent: {}",
+                "",
+                format_denominated_amount(client, &token, spent).await
+            );
+            println!(
+                "{:6}Remaining: {}",
+                "",
+                format_denominated_amount(client, &token, remaining).await
+            );
+        }
+    }
+}
+
+/// Print the guardians of an account
+fn print_account_guardians(guardians: AccountGuardians) {
+    println!("Guardians:");
+    println!("{:2}Threshold: {}", "", guardians.threshold);
+    println!(
+        "{:2}Recovery delay: {} epochs",
+        "", guardians.recovery_delay
+    );
+    for guardian in guardians.guardians {
+        println!("{:2}- {}", "", guardian);
+    }
+}
+
+/// Print the pending recovery of an account
+fn print_pending_recovery(recovery: PendingRecovery) {
+    println!("Pending recovery:");
+    println!(
+        "{:2}Executable from epoch: {}",
+        "", recovery.executable_from
+    );
+    println!("{:2}New threshold: {}", "", recovery.threshold);
+    println!("{:2}New public keys:", "");
+    for public_key in recovery.public_keys {
+        println!("{:4}- {}", "", public_key);
+    }
+    println!("{:2}Approved by:", "");
+    for guardian in recovery.approvals {
+        println!("{:4}- {}", "", guardian);
+    }
+}
+
+/// Print the session keys of an account
+async fn print_session_keys<C: namada::ledger::queries::Client + Sync>(
+    client: &C,
+    session_keys: Vec<(common::PublicKey, SessionKey)>,
+) {
+    println!("Session keys:");
+    for (public_key, session_key) in session_keys {
+        println!("{:2}- {}", "", public_key);
+        println!("{:4}Expiry epoch: {}", "", session_key.expiry);
+        println!("{:4}Allowed txs:", "");
+        for tx_code_hash in session_key.allowed_txs {
+            println!("{:6}- {}", "", tx_code_hash);
+        }
+        if !session_key.max_spend.is_empty() {
+            println!("{:4}Maximum spend:", "");
+            for (token, amount) in &session_key.max_spend {
+                let spent = session_key.spent(token);
+                println!(
+                    "{:6}- {}: {} (spent {})",
+                    "",
+                    token,
+                    format_denominated_amount(client, token, *amount).await,
+                    format_denominated_amount(client, token, spent).await
+                );
+            }
+        }
+    }
+}
+
+pub async fn query_vesting<C: namada::ledger::queries::Client + Sync>(
+    client: &C,
+    wallet: &Wallet<CliWalletUtils>,
+    args: args::QueryVesting,
+) {
+    let balances = query_vesting_balances(client, &args.owner).await;
+    if balances.is_empty() {
+        println!("No vesting schedule exists for {}", args.owner);
+        return;
+    }
+    println!("Vesting balances of {}:", args.owner);
+    for VestingBalance {
+        token,
+        schedule,
+        balance,
+        vested,
+        locked,
+    } in balances
+    {
+        println!("{:4}- {}", "", wallet.lookup_alias(&token));
+        println!(
+            "{:6}Balance: {}",
+            "",
+            format_denominated_amount(client, &token, balance).await
+        );
+        println!(
+            "{:6}Vesting amount: {}",
+            "",
+            format_denominated_amount(client, &token, schedule.amount).await
+        );
+        println!("{:6}Vesting schedule: {:?}", "", schedule.kind);
+        println!(
+            "{:6}Vested: {}",
+            "",
+            format_denominated_amount(client, &token, vested).await
+        );
+        println!(
+            "{:6}Locked: {}",
+            "",
+            format_denominated_amount(client, &token, locked).await
+        );
+    }
+}
+
 pub async fn query_pgf<C: namada::ledger::queries::Client + Sync>(
     client: &C,
     _args: args::QueryPgf,
@@ -1067,8 +1489,32 @@ pub async fn query_pgf<C: namada::ledger::queries::Client + Sync>(
                     "{:4}- {} for {}",
                     "",
                     funding.detail.target,
-                    funding.detail.amount.to_string_native()
+                    funding.detail.target.amount().to_string_native()
+                );
+                let epoch_or_none = |epoch: Option<Epoch>| {
+                    epoch
+                        .map(|epoch| epoch.to_string())
+                        .unwrap_or_else(|| "-".to_string())
+                };
+                println!(
+                    "{:6}Start epoch: {}, end epoch: {}",
+                    "",
+                    epoch_or_none(funding.detail.start_epoch),
+                    epoch_or_none(funding.detail.end_epoch)
                 );
+                match funding.detail.cap {
+                    Some(cap) => println!(
+                        "{:6}Paid: {} out of {}",
+                        "",
+                        funding.paid.to_string_native(),
+                        cap.to_string_native()
+                    ),
+                    None => println!(
+                        "{:6}Paid: {}",
+                        "",
+                        funding.paid.to_string_native()
+                    ),
+                }
             }
         }
     }
@@ -1261,6 +1707,68 @@ pub async fn query_pgf_fundings<C: namada::ledger::queries::Client + Sync>(
     unwrap_client_response::<C, _>(RPC.vp().pgf().funding(client).await)
 }
 
+pub async fn query_vesting_balances<
+    C: namada::ledger::queries::Client + Sync,
+>(
+    client: &C,
+    owner: &Address,
+) -> Vec<VestingBalance> {
+    unwrap_client_response::<C, _>(
+        RPC.vp().vesting().balances(client, owner).await,
+    )
+}
+
+pub async fn query_account_policy<C: namada::ledger::queries::Client + Sync>(
+    client: &C,
+    owner: &Address,
+) -> Option<AccountPolicy> {
+    unwrap_client_response::<C, _>(
+        RPC.vp().account().policy(client, owner).await,
+    )
+}
+
+pub async fn query_outflow_allowances<
+    C: namada::ledger::queries::Client + Sync,
+>(
+    client: &C,
+    owner: &Address,
+) -> Vec<OutflowAllowance> {
+    unwrap_client_response::<C, _>(
+        RPC.vp().account().allowances(client, owner).await,
+    )
+}
+
+pub async fn query_account_guardians<
+    C: namada::ledger::queries::Client + Sync,
+>(
+    client: &C,
+    owner: &Address,
+) -> Option<AccountGuardians> {
+    unwrap_client_response::<C, _>(
+        RPC.vp().account().guardians(client, owner).await,
+    )
+}
+
+pub async fn query_pending_recovery<
+    C: namada::ledger::queries::Client + Sync,
+>(
+    client: &C,
+    owner: &Address,
+) -> Option<PendingRecovery> {
+    unwrap_client_response::<C, _>(
+        RPC.vp().account().recovery(client, owner).await,
+    )
+}
+
+pub async fn query_session_keys<C: namada::ledger::queries::Client + Sync>(
+    client: &C,
+    owner: &Address,
+) -> Vec<(common::PublicKey, SessionKey)> {
+    unwrap_client_response::<C, _>(
+        RPC.vp().account().session_keys(client, owner).await,
+    )
+}
+
 pub async fn query_pgf_parameters<C: namada::ledger::queries::Client + Sync>(
     client: &C,
 ) -> PgfParameters {
@@ -17,7 +17,7 @@ use namada::ledger::rpc::{TxBroadcastData, TxResponse};
 use namada::ledger::wallet::{Wallet, WalletUtils};
 use namada::ledger::{masp, pos, signing, tx};
 use namada::proof_of_stake::parameters::PosParams;
-use namada::proto::Tx;
+use namada::proto::{SignatureIndex, Tx};
 use namada::tendermint_rpc::HttpClient;
 use namada::types::address::{Address, ImplicitAddress};
 use namada::types::dec::Dec;
@@ -194,6 +194,132 @@ where
     Ok(())
 }
 
+pub async fn submit_recover_account<C>(
+    client: &C,
+    ctx: &mut Context,
+    args: args::TxRecoverAccount,
+) -> Result<(), error::Error>
+where
+    C: namada::ledger::queries::Client + Sync,
+    C::Error: std::fmt::Display,
+{
+    // Cancellations are signed by the account, any other recovery step by
+    // the guardian
+    let signer = if args.cancel {
+        Some(args.owner.clone())
+    } else {
+        args.guardian.clone()
+    };
+    let signing_data = aux_signing_data(
+        client,
+        &mut ctx.wallet,
+        &args.tx,
+        &signer,
+        signer.clone(),
+    )
+    .await?;
+
+    let (mut tx, _epoch) = tx::build_recover_account(
+        client,
+        &mut ctx.wallet,
+        &mut ctx.shielded,
+        args.clone(),
+        signing_data.fee_payer.clone(),
+    )
+    .await?;
+
+    signing::generate_test_vector(client, &mut ctx.wallet, &tx).await?;
+
+    if args.tx.dump_tx {
+        tx::dump_tx(&args.tx, tx);
+    } else {
+        signing::sign_tx(&mut ctx.wallet, &args.tx, &mut tx, signing_data)?;
+        tx::process_tx(client, &mut ctx.wallet, &args.tx, tx).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn submit_add_session_key<C>(
+    client: &C,
+    ctx: &mut Context,
+    args: args::TxAddSessionKey,
+) -> Result<(), error::Error>
+where
+    C: namada::ledger::queries::Client + Sync,
+    C::Error: std::fmt::Display,
+{
+    let default_signer = Some(args.owner.clone());
+    let signing_data = aux_signing_data(
+        client,
+        &mut ctx.wallet,
+        &args.tx,
+        &Some(args.owner.clone()),
+        default_signer,
+    )
+    .await?;
+
+    let (mut tx, _epoch) = tx::build_add_session_key(
+        client,
+        &mut ctx.wallet,
+        &mut ctx.shielded,
+        args.clone(),
+        signing_data.fee_payer.clone(),
+    )
+    .await?;
+
+    signing::generate_test_vector(client, &mut ctx.wallet, &tx).await?;
+
+    if args.tx.dump_tx {
+        tx::dump_tx(&args.tx, tx);
+    } else {
+        signing::sign_tx(&mut ctx.wallet, &args.tx, &mut tx, signing_data)?;
+        tx::process_tx(client, &mut ctx.wallet, &args.tx, tx).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn submit_revoke_session_key<C>(
+    client: &C,
+    ctx: &mut Context,
+    args: args::TxRevokeSessionKey,
+) -> Result<(), error::Error>
+where
+    C: namada::ledger::queries::Client + Sync,
+    C::Error: std::fmt::Display,
+{
+    let default_signer = Some(args.owner.clone());
+    let signing_data = aux_signing_data(
+        client,
+        &mut ctx.wallet,
+        &args.tx,
+        &Some(args.owner.clone()),
+        default_signer,
+    )
+    .await?;
+
+    let (mut tx, _epoch) = tx::build_revoke_session_key(
+        client,
+        &mut ctx.wallet,
+        &mut ctx.shielded,
+        args.clone(),
+        signing_data.fee_payer.clone(),
+    )
+    .await?;
+
+    signing::generate_test_vector(client, &mut ctx.wallet, &tx).await?;
+
+    if args.tx.dump_tx {
+        tx::dump_tx(&args.tx, tx);
+    } else {
+        signing::sign_tx(&mut ctx.wallet, &args.tx, &mut tx, signing_data)?;
+        tx::process_tx(client, &mut ctx.wallet, &args.tx, tx).await?;
+    }
+
+    Ok(())
+}
+
 pub async fn submit_init_account<C: namada::ledger::queries::Client + Sync>(
     client: &C,
     ctx: &mut Context,
@@ -531,6 +657,9 @@ pub async fn submit_init_validator<
 /// Shielded context file name
 const FILE_NAME: &str = "shielded.dat";
 const TMP_FILE_NAME: &str = "shielded.tmp";
+/// Name of the log of transactions scanned since the shielded context file
+/// was last written
+const LOG_FILE_NAME: &str = "shielded.log";
 
 #[derive(Debug, BorshSerialize, BorshDeserialize, Clone)]
 pub struct CLIShieldedUtils {
@@ -590,16 +719,27 @@ impl masp::ShieldedUtils for CLIShieldedUtils {
     }
 
     /// Try to load the last saved shielded context from the given context
-    /// directory. If this fails, then leave the current context unchanged.
+    /// directory, replaying the transactions logged since. If this fails,
+    /// then leave the current context unchanged.
     async fn load(self) -> std::io::Result<masp::ShieldedContext<Self>> {
         // Try to load shielded context from file
         let mut ctx_file = File::open(self.context_dir.join(FILE_NAME))?;
         let mut bytes = Vec::new();
         ctx_file.read_to_end(&mut bytes)?;
-        let mut new_ctx = masp::ShieldedContext::deserialize(&mut &bytes[..])?;
-        // Associate the originating context directory with the
-        // shielded context under construction
-        new_ctx.utils = self;
+        let log_path = self.context_dir.join(LOG_FILE_NAME);
+        let mut new_ctx = masp::ShieldedContext::from_snapshot(self, &bytes)?;
+        // Catch up with the transactions scanned since the file was written
+        match std::fs::read(log_path) {
+            Ok(log) => {
+                if new_ctx.replay_log(&log).is_err() {
+                    // An unreadable log is discarded by the next save and
+                    // its transactions are fetched again
+                    new_ctx.snapshot_stale = true;
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
         Ok(new_ctx)
     }
 
@@ -620,20 +760,49 @@ impl masp::ShieldedUtils for CLIShieldedUtils {
                 .write(true)
                 .create_new(true)
                 .open(tmp_path.clone())?;
-            let mut bytes = Vec::new();
-            ctx.serialize(&mut bytes)
-                .expect("cannot serialize shielded context");
-            ctx_file.write_all(&bytes[..])?;
+            ctx_file.write_all(&ctx.to_snapshot())?;
         }
         // Atomically update the old shielded context file with new data.
         // Atomicity is required to prevent other client instances from reading
         // corrupt data.
         std::fs::rename(tmp_path.clone(), self.context_dir.join(FILE_NAME))?;
+        // The logged transactions are now contained in the file. Should
+        // removing the log fail, they are skipped when replaying it.
+        match std::fs::remove_file(self.context_dir.join(LOG_FILE_NAME)) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                return Err(err);
+            }
+            _ => {}
+        }
         // Finally, remove our temporary file to allow future saving of shielded
         // contexts.
         std::fs::remove_file(tmp_path)?;
         Ok(())
     }
+
+    /// Append the given scanned transactions to the log in the associated
+    /// context directory. The whole context is saved instead once the log
+    /// outgrows the context file, so that loading stays fast.
+    async fn append(
+        &self,
+        ctx: &masp::ShieldedContext<Self>,
+        updates: &[masp::ScannedTx],
+    ) -> std::io::Result<()> {
+        let ctx_len =
+            std::fs::metadata(self.context_dir.join(FILE_NAME))?.len();
+        let mut log_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.context_dir.join(LOG_FILE_NAME))?;
+        let log_len = log_file.metadata()?.len();
+        if log_len > ctx_len {
+            drop(log_file);
+            return self.save(ctx).await;
+        }
+        log_file
+            .write_all(&masp::ScannedTx::encode_log(updates, log_len == 0))?;
+        Ok(())
+    }
 }
 
 pub async fn submit_transfer<C: namada::ledger::queries::Client + Sync>(
@@ -667,6 +836,7 @@ pub async fn submit_transfer<C: namada::ledger::queries::Client + Sync>(
             &mut ctx.shielded,
             arg,
             signing_data.fee_payer.clone(),
+            signing_data.external_signer.as_ref(),
         )
         .await?;
         signing::generate_test_vector(client, &mut ctx.wallet, &tx).await?;
@@ -707,6 +877,27 @@ pub async fn submit_transfer<C: namada::ledger::queries::Client + Sync>(
     Ok(())
 }
 
+pub async fn gen_ibc_shielded_transfer<
+    C: namada::ledger::queries::Client + Sync,
+>(
+    client: &C,
+    ctx: &mut Context,
+    args: args::GenIbcShieldedTransfer,
+) -> Result<(), error::Error> {
+    let shielded_transfer =
+        tx::gen_ibc_shielded_transfer(client, &mut ctx.shielded, args).await?;
+    match shielded_transfer {
+        Some(shielded_transfer) => {
+            println!("{}", shielded_transfer);
+        }
+        None => {
+            eprintln!("No shielded transfer has been generated.");
+            safe_exit(1)
+        }
+    }
+    Ok(())
+}
+
 pub async fn submit_ibc_transfer<C: namada::ledger::queries::Client + Sync>(
     client: &C,
     mut ctx: Context,
@@ -715,17 +906,18 @@ pub async fn submit_ibc_transfer<C: namada::ledger::queries::Client + Sync>(
 where
     C::Error: std::fmt::Display,
 {
-    let default_signer = Some(args.source.clone());
+    let source = args.source.effective_address();
+    let default_signer = Some(source.clone());
     let signing_data = aux_signing_data(
         client,
         &mut ctx.wallet,
         &args.tx,
-        &Some(args.source.clone()),
+        &Some(source.clone()),
         default_signer,
     )
     .await?;
 
-    submit_reveal_aux(client, &mut ctx, args.tx.clone(), &args.source).await?;
+    submit_reveal_aux(client, &mut ctx, args.tx.clone(), &source).await?;
 
     let (mut tx, _epoch) = tx::build_ibc_transfer(
         client,
@@ -1096,6 +1288,10 @@ where
 
     if let Some(account_public_keys_map) = signing_data.account_public_keys_map
     {
+        println!(
+            "Signing the sections of the transaction over {}.",
+            tx.raw_sighash()
+        );
         let signatures =
             tx.compute_section_signature(secret_keys, &account_public_keys_map);
 
@@ -1130,6 +1326,78 @@ where
     Ok(())
 }
 
+pub async fn assemble_tx<C>(
+    client: &C,
+    ctx: &mut Context,
+    args::AssembleTx {
+        tx: tx_args,
+        tx_data,
+        owner,
+    }: args::AssembleTx,
+) -> Result<(), error::Error>
+where
+    C: namada::ledger::queries::Client + Sync,
+    C::Error: std::fmt::Display,
+{
+    let (mut tx, fee_payer) =
+        merge_offline_signatures(client, &tx_args, &tx_data, &owner).await?;
+    signing::sign_tx_wrapper(&mut ctx.wallet, &tx_args, &mut tx, &fee_payer)?;
+
+    if tx_args.dump_tx {
+        tx::dump_tx(&tx_args, tx);
+    } else {
+        tx::process_tx(client, &mut ctx.wallet, &tx_args, tx).await?;
+    }
+    Ok(())
+}
+
+/// Decode a dumped transaction and merge the given offline signatures of its
+/// owner into it. Returns the transaction along with the key of its fee payer
+/// that is left to sign its wrapper.
+async fn merge_offline_signatures<C>(
+    client: &C,
+    tx_args: &args::Tx,
+    tx_data: &[u8],
+    owner: &Address,
+) -> Result<(Tx, common::PublicKey), error::Error>
+where
+    C: namada::ledger::queries::Client + Sync,
+{
+    let mut tx = Tx::deserialize(tx_data).map_err(|_| {
+        error::Error::Other("Couldn't decode the transaction.".to_string())
+    })?;
+    let Some(wrapper) = tx.header().wrapper() else {
+        return Err(error::Error::Other(
+            "The transaction has no wrapper header to be signed.".to_string(),
+        ));
+    };
+    // The fee payer was fixed when the transaction was dumped
+    if let Some(fee_payer) = &tx_args.wrapper_fee_payer {
+        if fee_payer.ref_to() != wrapper.pk {
+            return Err(error::Error::Other(format!(
+                "The transaction's fees are to be paid by {}, not by {}.",
+                wrapper.pk,
+                fee_payer.ref_to()
+            )));
+        }
+    }
+
+    let signatures = tx_args
+        .signatures
+        .iter()
+        .map(|bytes| {
+            SignatureIndex::deserialize(bytes).map_err(|err| {
+                error::Error::Other(format!(
+                    "Couldn't decode an offline signature: {}",
+                    err
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    signing::assemble_signatures(client, &mut tx, owner, signatures).await?;
+    Ok((tx, wrapper.pk))
+}
+
 pub async fn submit_reveal_pk<C: namada::ledger::queries::Client + Sync>(
     client: &C,
     ctx: &mut Context,
@@ -1433,6 +1701,88 @@ where
     Ok(())
 }
 
+pub async fn submit_schedule_tx<C: namada::ledger::queries::Client + Sync>(
+    client: &C,
+    mut ctx: Context,
+    args: args::ScheduleTx,
+) -> Result<(), error::Error>
+where
+    C: namada::ledger::queries::Client + Sync,
+    C::Error: std::fmt::Display,
+{
+    let default_signer = Some(args.owner.clone());
+    let signing_data = signing::aux_signing_data(
+        client,
+        &mut ctx.wallet,
+        &args.tx,
+        &Some(args.owner.clone()),
+        default_signer,
+    )
+    .await?;
+
+    let (mut tx, _fee_unshield_epoch) = tx::build_schedule_tx(
+        client,
+        &mut ctx.wallet,
+        &mut ctx.shielded,
+        args.clone(),
+        &signing_data,
+    )
+    .await?;
+
+    signing::generate_test_vector(client, &mut ctx.wallet, &tx).await?;
+
+    if args.tx.dump_tx {
+        tx::dump_tx(&args.tx, tx);
+    } else {
+        signing::sign_tx(&mut ctx.wallet, &args.tx, &mut tx, signing_data)?;
+        tx::process_tx(client, &mut ctx.wallet, &args.tx, tx).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn submit_cancel_scheduled_tx<
+    C: namada::ledger::queries::Client + Sync,
+>(
+    client: &C,
+    mut ctx: Context,
+    args: args::CancelScheduledTx,
+) -> Result<(), error::Error>
+where
+    C: namada::ledger::queries::Client + Sync,
+    C::Error: std::fmt::Display,
+{
+    let default_signer = Some(args.owner.clone());
+    let signing_data = signing::aux_signing_data(
+        client,
+        &mut ctx.wallet,
+        &args.tx,
+        &Some(args.owner.clone()),
+        default_signer,
+    )
+    .await?;
+
+    let (mut tx, _fee_unshield_epoch) = tx::build_cancel_scheduled_tx(
+        client,
+        &mut ctx.wallet,
+        &mut ctx.shielded,
+        args.clone(),
+        &signing_data.fee_payer,
+    )
+    .await?;
+
+    signing::generate_test_vector(client, &mut ctx.wallet, &tx).await?;
+
+    if args.tx.dump_tx {
+        tx::dump_tx(&args.tx, tx);
+    } else {
+        signing::sign_tx(&mut ctx.wallet, &args.tx, &mut tx, signing_data)?;
+        tx::process_tx(client, &mut ctx.wallet, &args.tx, tx).await?;
+    }
+
+    Ok(())
+}
+
 /// Save accounts initialized from a tx into the wallet, if any.
 pub async fn save_initialized_accounts<U: WalletUtils>(
     wallet: &mut Wallet<U>,
@@ -1467,3 +1817,201 @@ pub async fn submit_tx(
 ) -> Result<TxResponse, error::Error> {
     tx::submit_tx(client, to_broadcast).await
 }
+
+#[cfg(test)]
+mod test_assemble_tx {
+    use namada::core::ledger::storage_api::account::init_account_storage;
+    use namada::core::ledger::storage_api::StorageWrite;
+    use namada::ledger::queries::testing::TestClient;
+    use namada::ledger::queries::RPC;
+    use namada::proto::{Code, Data, Section};
+    use namada::types::address::nam;
+    use namada::types::address::testing::established_address_1;
+    use namada::types::hash::Hash;
+    use namada::types::key::testing::{keypair_1, keypair_2};
+    use namada::types::storage::{Epoch, Key};
+    use namada::types::transaction::{Fee, TxType, WrapperTx};
+
+    use super::*;
+
+    /// A client whose storage holds the account of the given keys and
+    /// threshold
+    fn client_with_account(
+        owner: &Address,
+        public_keys: &[common::PublicKey],
+        threshold: u8,
+    ) -> TestClient<RPC> {
+        let mut client = TestClient::new(RPC);
+        init_account_storage(
+            &mut client.wl_storage,
+            owner,
+            public_keys,
+            threshold,
+        )
+        .unwrap();
+        client
+            .wl_storage
+            .write(&Key::validity_predicate(owner), Hash::zero())
+            .unwrap();
+        client.wl_storage.commit_tx();
+        client.wl_storage.commit_block().unwrap();
+        client
+    }
+
+    /// A transaction whose fees are paid by the given key
+    fn wrapper_tx(fee_payer: &common::SecretKey) -> Tx {
+        let mut tx = Tx::from_type(TxType::Wrapper(Box::new(WrapperTx::new(
+            Fee {
+                amount_per_gas_unit: Default::default(),
+                token: nam(),
+            },
+            fee_payer.ref_to(),
+            Epoch(0),
+            Default::default(),
+            #[cfg(not(feature = "mainnet"))]
+            None,
+            None,
+        ))));
+        tx.set_code(Code::new(vec![1, 2, 3]));
+        tx.set_data(Data::new(vec![4, 5, 6]));
+        tx
+    }
+
+    /// The dumped transaction as read from its file
+    fn dumped(tx: &Tx) -> Vec<u8> {
+        serde_json::to_vec(&tx.serialize()).unwrap()
+    }
+
+    /// The offline signature of the key with the given index as read from its
+    /// file
+    fn offline_signature(
+        tx: &Tx,
+        keypair: &common::SecretKey,
+        index: u8,
+    ) -> Vec<u8> {
+        let signature = SignatureIndex {
+            signature: common::SigScheme::sign(keypair, tx.raw_sighash()),
+            index,
+        };
+        serde_json::to_vec(&signature.serialize()).unwrap()
+    }
+
+    /// The arguments of an assembly with the given offline signatures
+    fn tx_args(signatures: Vec<Vec<u8>>) -> args::Tx {
+        args::Tx {
+            dry_run: false,
+            dry_run_wrapper: false,
+            gas_report: false,
+            dump_tx: false,
+            output_folder: None,
+            force: false,
+            broadcast_only: false,
+            ledger_address: (),
+            initialized_account_alias: None,
+            wallet_alias_force: false,
+            fee_amount: None,
+            wrapper_fee_payer: None,
+            fee_token: nam(),
+            fee_unshield: None,
+            gas_limit: Default::default(),
+            expiration: None,
+            disposable_signing_key: false,
+            chain_id: None,
+            signing_keys: vec![],
+            signatures,
+            tx_reveal_code_path: PathBuf::new(),
+            verification_key: None,
+            password: None,
+            signer: Default::default(),
+        }
+    }
+
+    /// Test that the offline signatures meeting the threshold of the owner
+    /// are merged into the dumped transaction
+    #[tokio::test]
+    async fn test_assemble_tx() {
+        let owner = established_address_1();
+        let public_keys = [keypair_1().ref_to(), keypair_2().ref_to()];
+        let client = client_with_account(&owner, &public_keys, 2);
+        let tx = wrapper_tx(&keypair_1());
+        let args = tx_args(vec![
+            offline_signature(&tx, &keypair_1(), 0),
+            offline_signature(&tx, &keypair_2(), 1),
+        ]);
+
+        let (assembled, fee_payer) =
+            merge_offline_signatures(&client, &args, &dumped(&tx), &owner)
+                .await
+                .unwrap();
+        assert_eq!(fee_payer, keypair_1().ref_to());
+        let sections = assembled
+            .sections
+            .iter()
+            .filter_map(|section| match section {
+                Section::SectionSignature(signatures) => Some(signatures),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].total_signatures(), 2);
+
+        // The fee payer given must be the one fixed in the transaction
+        let args = args::Tx {
+            wrapper_fee_payer: Some(keypair_2()),
+            ..args
+        };
+        assert!(
+            merge_offline_signatures(&client, &args, &dumped(&tx), &owner)
+                .await
+                .is_err()
+        );
+    }
+
+    /// Test that the assembly of malformed or insufficiently signed
+    /// transactions fails
+    #[tokio::test]
+    async fn test_assemble_tx_rejected() {
+        let owner = established_address_1();
+        let public_keys = [keypair_1().ref_to(), keypair_2().ref_to()];
+        let client = client_with_account(&owner, &public_keys, 2);
+        let tx = wrapper_tx(&keypair_1());
+
+        // Short of the threshold
+        let args = tx_args(vec![offline_signature(&tx, &keypair_1(), 0)]);
+        assert!(
+            merge_offline_signatures(&client, &args, &dumped(&tx), &owner)
+                .await
+                .is_err()
+        );
+        // Not a transaction
+        let args = tx_args(vec![
+            offline_signature(&tx, &keypair_1(), 0),
+            offline_signature(&tx, &keypair_2(), 1),
+        ]);
+        assert!(merge_offline_signatures(&client, &args, b"\"00\"", &owner)
+            .await
+            .is_err());
+        // Not a signature
+        let args = tx_args(vec![b"\"00\"".to_vec()]);
+        assert!(
+            merge_offline_signatures(&client, &args, &dumped(&tx), &owner)
+                .await
+                .is_err()
+        );
+        // Without a wrapper header
+        let mut raw = tx.clone();
+        raw.update_header(TxType::Raw);
+        let args = tx_args(vec![
+            offline_signature(&raw, &keypair_1(), 0),
+            offline_signature(&raw, &keypair_2(), 1),
+        ]);
+        assert!(merge_offline_signatures(
+            &client,
+            &args,
+            &dumped(&raw),
+            &owner
+        )
+        .await
+        .is_err());
+    }
+}
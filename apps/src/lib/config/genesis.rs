@@ -8,6 +8,7 @@ use namada::core::ledger::governance::parameters::GovernanceParameters;
 use namada::core::ledger::pgf::parameters::PgfParameters;
 #[cfg(not(feature = "mainnet"))]
 use namada::core::ledger::testnet_pow;
+use namada::core::ledger::vesting::VestingSchedule;
 use namada::ledger::eth_bridge::EthereumBridgeConfig;
 use namada::ledger::parameters::EpochDuration;
 use namada::ledger::pos::{Dec, GenesisValidator, PosParams};
@@ -34,6 +35,7 @@ pub mod genesis_config {
     use namada::core::ledger::pgf::parameters::PgfParameters;
     #[cfg(not(feature = "mainnet"))]
     use namada::core::ledger::testnet_pow;
+    use namada::core::ledger::vesting::{VestingKind, VestingSchedule};
     use namada::ledger::parameters::EpochDuration;
     use namada::ledger::pos::{Dec, GenesisValidator, PosParams};
     use namada::types::address::Address;
@@ -225,6 +227,8 @@ pub mod genesis_config {
         pub vp: Option<String>,
         // Initial balances held by accounts defined elsewhere.
         pub balances: Option<HashMap<String, token::Amount>>,
+        // Initial shielded reward parameters (default: not rewarded).
+        pub masp_params: Option<token::MaspParams>,
     }
 
     #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -237,6 +241,17 @@ pub mod genesis_config {
         pub public_key: Option<HexString>,
         // Initial storage key values.
         pub storage: Option<HashMap<String, HexString>>,
+        // Vesting schedules of the account's balances, keyed by token alias.
+        pub vesting: Option<HashMap<String, VestingConfig>>,
+    }
+
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    pub struct VestingConfig {
+        // Amount of the token's genesis balance subject to vesting.
+        pub amount: token::Amount,
+        // The vesting schedule.
+        #[serde(flatten)]
+        pub kind: VestingKind,
     }
 
     #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -418,6 +433,7 @@ pub mod genesis_config {
         TokenAccount {
             address: Address::decode(config.address.as_ref().unwrap()).unwrap(),
             denom: config.denom,
+            masp_params: config.masp_params.clone(),
             balances: config
                 .balances
                 .as_ref()
@@ -485,6 +501,7 @@ pub mod genesis_config {
     fn load_established(
         config: &EstablishedAccountConfig,
         wasm: &HashMap<String, WasmConfig>,
+        tokens: &HashMap<String, TokenAccountConfig>,
     ) -> EstablishedAccount {
         let account_vp_name = config.vp.as_ref().unwrap();
         let account_vp_config = wasm.get(account_vp_name).unwrap();
@@ -517,6 +534,33 @@ pub mod genesis_config {
                     )
                 })
                 .collect(),
+            vesting: config
+                .vesting
+                .as_ref()
+                .unwrap_or(&HashMap::default())
+                .iter()
+                .map(|(alias, vesting)| {
+                    let token = tokens.get(alias).unwrap_or_else(|| {
+                        eprintln!("Unknown vesting token alias {}", alias);
+                        cli::safe_exit(1)
+                    });
+                    (
+                        Address::decode(token.address.as_ref().unwrap())
+                            .unwrap(),
+                        VestingSchedule {
+                            amount: token::Amount::from_uint(
+                                vesting.amount,
+                                token.denom,
+                            )
+                            .expect(
+                                "expected a vesting amount that fits into 256 \
+                                 bits",
+                            ),
+                            kind: vesting.kind.clone(),
+                        },
+                    )
+                })
+                .collect(),
         }
     }
 
@@ -571,7 +615,9 @@ pub mod genesis_config {
             established
                 .unwrap_or_default()
                 .iter()
-                .map(|(name, cfg)| (name.clone(), load_established(cfg, &wasm)))
+                .map(|(name, cfg)| {
+                    (name.clone(), load_established(cfg, &wasm, &token))
+                })
                 .collect();
         let implicit_accounts: HashMap<String, ImplicitAccount> = implicit
             .unwrap_or_default()
@@ -828,6 +874,9 @@ pub struct EstablishedAccount {
     /// Account's sub-space storage. The values must be borsh encoded bytes.
     #[derivative(PartialOrd = "ignore", Ord = "ignore")]
     pub storage: HashMap<storage::Key, Vec<u8>>,
+    /// Vesting schedules of the account's balances of the given tokens
+    #[derivative(PartialOrd = "ignore", Ord = "ignore")]
+    pub vesting: HashMap<Address, VestingSchedule>,
 }
 
 #[derive(
@@ -842,6 +891,9 @@ pub struct TokenAccount {
     /// Accounts' balances of this token
     #[derivative(PartialOrd = "ignore", Ord = "ignore")]
     pub balances: HashMap<Address, token::Amount>,
+    /// Shielded reward parameters of this token, if it is rewarded
+    #[derivative(PartialOrd = "ignore", Ord = "ignore")]
+    pub masp_params: Option<token::MaspParams>,
 }
 
 #[derive(
@@ -1041,6 +1093,7 @@ pub fn genesis(num_validators: u64) -> Genesis {
         vp_sha256: Default::default(),
         public_key: Some(wallet::defaults::albert_keypair().ref_to()),
         storage: HashMap::default(),
+        vesting: HashMap::default(),
     };
     let bertha = EstablishedAccount {
         address: wallet::defaults::bertha_address(),
@@ -1048,6 +1101,7 @@ pub fn genesis(num_validators: u64) -> Genesis {
         vp_sha256: Default::default(),
         public_key: Some(wallet::defaults::bertha_keypair().ref_to()),
         storage: HashMap::default(),
+        vesting: HashMap::default(),
     };
     let christel = EstablishedAccount {
         address: wallet::defaults::christel_address(),
@@ -1055,6 +1109,7 @@ pub fn genesis(num_validators: u64) -> Genesis {
         vp_sha256: Default::default(),
         public_key: Some(wallet::defaults::christel_keypair().ref_to()),
         storage: HashMap::default(),
+        vesting: HashMap::default(),
     };
     let masp = EstablishedAccount {
         address: namada::types::address::masp(),
@@ -1062,6 +1117,7 @@ pub fn genesis(num_validators: u64) -> Genesis {
         vp_sha256: Default::default(),
         public_key: None,
         storage: HashMap::default(),
+        vesting: HashMap::default(),
     };
     let implicit_accounts = vec![
         ImplicitAccount {
@@ -1112,6 +1168,7 @@ pub fn genesis(num_validators: u64) -> Genesis {
         .into_iter()
         .collect()
     }
+    let masp_rewards = address::masp_rewards();
     let token_accounts = tokens()
         .into_iter()
         .map(|(address, (_, denom))| TokenAccount {
@@ -1122,6 +1179,12 @@ pub fn genesis(num_validators: u64) -> Genesis {
                 .into_iter()
                 .map(|(k, v)| (k, token::Amount::from_uint(v, denom).unwrap()))
                 .collect(),
+            masp_params: masp_rewards.get(&address).map(|&reward_rate| {
+                token::MaspParams {
+                    reward_rate,
+                    controller: None,
+                }
+            }),
         })
         .collect();
     Genesis {
@@ -112,6 +112,14 @@ pub struct Shell {
     /// Tx WASM compilation in-memory cache maximum size in bytes.
     /// When not set, defaults to 1/6 of the available memory.
     pub tx_wasm_compilation_cache_bytes: Option<u64>,
+    /// Pre-compile all the whitelisted tx and VP WASMs on start-up, so that
+    /// they don't have to be compiled lazily on first use after a restart.
+    /// When not set, defaults to `true`.
+    pub warm_wasm_compilation_cache: Option<bool>,
+    /// Optimistically execute the decrypted txs of a block in parallel,
+    /// re-executing in block order the ones that conflict with the txs
+    /// before them. When not set, defaults to `false`.
+    pub parallel_tx_execution: Option<bool>,
     /// When set, will limit the how many block heights in the past can the
     /// storage be queried for reading values.
     pub storage_read_past_height_limit: Option<u64>,
@@ -145,6 +153,8 @@ impl Ledger {
                 block_cache_bytes: None,
                 vp_wasm_compilation_cache_bytes: None,
                 tx_wasm_compilation_cache_bytes: None,
+                warm_wasm_compilation_cache: None,
+                parallel_tx_execution: None,
                 // Default corresponds to 1 hour of past blocks at 1 block/sec
                 storage_read_past_height_limit: Some(3600),
                 db_dir: DB_DIR.into(),
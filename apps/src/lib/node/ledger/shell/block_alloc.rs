@@ -37,25 +37,23 @@
 
 pub mod states;
 
-// TODO: what if a tx has a size greater than the threshold for
-// its bin? how do we handle this? if we keep it in the mempool
-// forever, it'll be a DoS vec, as we can make nodes run out of
-// memory! maybe we should allow block decisions for txs that are
-// too big to fit in their respective bin? in these special block
-// decisions, we would only decide proposals with "large" txs??
-//
-// MAYBE: in the state machine impl, reset to beginning state, and
-// and alloc space for large tx right at the start. the problem with
-// this is that then we may not have enough space for decrypted txs
+// NOTE: txs whose size is greater than the threshold for their
+// bin are rejected by `mempool_validate`. If the max proposal size
+// is lowered by governance after such txs entered the mempool,
+// they are dropped from proposals and tracked by an
+// [`AllocFailureTracker`], eventually getting evicted from the
+// mempool on recheck.
 
 // TODO: panic if we don't have enough space reserved for a
 // decrypted tx; in theory, we should always have enough space
 // reserved for decrypted txs, given the invariants of the state
 // machine
 
+use std::collections::HashMap;
 use std::marker::PhantomData;
 
 use namada::core::ledger::storage::{self, WlStorage};
+use namada::core::types::hash::Hash;
 use namada::proof_of_stake::pos_queries::PosQueries;
 
 #[allow(unused_imports)]
@@ -263,7 +261,7 @@ pub struct EncryptedTxsBins {
 
 impl EncryptedTxsBins {
     pub fn new(max_bytes: u64, max_gas: u64) -> Self {
-        let allotted_space_in_bytes = threshold::ONE_THIRD.over(max_bytes);
+        let allotted_space_in_bytes = max_encrypted_tx_size(max_bytes);
         Self {
             space: TxBin::init(allotted_space_in_bytes),
             gas: TxBin::init(max_gas),
@@ -293,6 +291,61 @@ impl EncryptedTxsBins {
     }
 }
 
+/// Return the maximum size, in bytes, of a wrapper tx that may
+/// ever be allocated in a block proposal, given the maximum
+/// proposal size in bytes.
+#[inline]
+pub fn max_encrypted_tx_size(max_proposal_bytes: u64) -> u64 {
+    threshold::ONE_THIRD.over(max_proposal_bytes)
+}
+
+/// Number of consecutive block proposals a mempool tx may fail to be
+/// allocated in, before it gets evicted from the mempool.
+pub const MAX_CONSECUTIVE_ALLOC_FAILURES: u64 = 3;
+
+/// Keeps track of mempool txs which overflowed their respective
+/// bin, over consecutive block proposals of this node.
+#[derive(Debug, Default)]
+pub struct AllocFailureTracker {
+    /// Failures recorded in the last finished proposal.
+    last: HashMap<Hash, u64>,
+    /// Failures recorded in the proposal being built.
+    current: HashMap<Hash, u64>,
+}
+
+impl AllocFailureTracker {
+    /// Start tracking the allocation failures of a new proposal.
+    ///
+    /// Txs that did not fail allocation in the previous proposal
+    /// have their failure count reset.
+    pub fn begin_proposal(&mut self) {
+        self.last = std::mem::take(&mut self.current);
+    }
+
+    /// Record that the tx with the given hash could not be allocated
+    /// in the proposal being built.
+    pub fn record_failure(&mut self, tx_hash: Hash) {
+        let failures = self.last.get(&tx_hash).copied().unwrap_or_default();
+        self.current.insert(tx_hash, failures + 1);
+    }
+
+    /// Return the number of consecutive proposals the tx with the
+    /// given hash has failed to be allocated in.
+    pub fn consecutive_failures(&self, tx_hash: &Hash) -> u64 {
+        self.current
+            .get(tx_hash)
+            .or_else(|| self.last.get(tx_hash))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Check if the tx with the given hash should be evicted from
+    /// the mempool.
+    pub fn should_evict(&self, tx_hash: &Hash) -> bool {
+        self.consecutive_failures(tx_hash) >= MAX_CONSECUTIVE_ALLOC_FAILURES
+    }
+}
+
 pub mod threshold {
     //! Transaction allotment thresholds.
 
@@ -408,6 +461,31 @@ mod tests {
         );
     }
 
+    /// Check that txs are only flagged for eviction after failing
+    /// allocation in enough consecutive proposals.
+    #[test]
+    fn test_alloc_failure_tracker_consecutive_failures() {
+        let tx_hash = Hash::sha256(b"tx");
+        let mut tracker = AllocFailureTracker::default();
+
+        for _ in 0..MAX_CONSECUTIVE_ALLOC_FAILURES - 1 {
+            tracker.begin_proposal();
+            tracker.record_failure(tx_hash);
+            assert!(!tracker.should_evict(&tx_hash));
+        }
+
+        // a proposal without failures resets the count
+        tracker.begin_proposal();
+        tracker.begin_proposal();
+        assert_eq!(tracker.consecutive_failures(&tx_hash), 0);
+
+        for _ in 0..MAX_CONSECUTIVE_ALLOC_FAILURES {
+            tracker.begin_proposal();
+            tracker.record_failure(tx_hash);
+        }
+        assert!(tracker.should_evict(&tx_hash));
+    }
+
     proptest! {
         /// Check if we reject a tx when its respective bin
         /// capacity has been reached on a [`BlockAllocator`].
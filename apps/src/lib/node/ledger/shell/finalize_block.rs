@@ -27,9 +27,12 @@ use namada::types::token::Amount;
 use namada::types::transaction::protocol::{
     ethereum_tx_data_variants, ProtocolTxType,
 };
+use namada::types::transaction::scheduled::ScheduleTarget;
 use namada::types::vote_extensions::ethereum_events::MultiSignedEthEvent;
 
-use super::governance::execute_governance_proposals;
+use super::governance::{self, execute_governance_proposals};
+use super::parallel_txs::{execute_decrypted_txs, SpeculativeTxs};
+use super::scheduled_txs::execute_scheduled_txs;
 use super::*;
 use crate::facade::tendermint_proto::abci::{
     Misbehavior as Evidence, VoteInfo,
@@ -87,6 +90,18 @@ where
             self.wl_storage.storage.update_epoch_blocks_delay
         );
 
+        // The pgf fundings and proposals stored with the legacy layout must be
+        // moved to the current one before any tx or proposal reads them. This
+        // only runs once.
+        if let Some((fundings, proposals)) =
+            pgf::migrate_legacy_storage(&mut self.wl_storage)?
+        {
+            tracing::info!(
+                "Migrated {fundings} legacy PGF fundings and {proposals} \
+                 legacy PGF proposals."
+            );
+        }
+
         if new_epoch {
             namada::ledger::storage::update_allowed_conversions(
                 &mut self.wl_storage,
@@ -143,6 +158,12 @@ where
                 )
         };
 
+        let mut speculative_txs = if self.parallel_tx_execution {
+            execute_decrypted_txs(self, &req.txs)
+        } else {
+            SpeculativeTxs::default()
+        };
+
         // Tracks the accepted transactions
         self.wl_storage.storage.block.results = BlockResults::default();
         for (tx_index, processed_tx) in req.txs.iter().enumerate() {
@@ -441,24 +462,37 @@ where
                 },
             };
 
-            match protocol::dispatch_tx(
-                tx,
-                processed_tx.tx.as_ref(),
-                TxIndex(
-                    tx_index
-                        .try_into()
-                        .expect("transaction index out of bounds"),
+            let speculative = tx_unsigned_hash.as_ref().and_then(|hash| {
+                speculative_txs.adopt(
+                    tx_index,
+                    hash,
+                    &mut self.wl_storage.write_log,
+                )
+            });
+            let dispatch_result = match speculative {
+                Some((result, gas_meter)) => {
+                    stats.increment_adopted_txs();
+                    tx_gas_meter = gas_meter;
+                    result
+                }
+                None => protocol::dispatch_tx(
+                    tx,
+                    processed_tx.tx.as_ref(),
+                    TxIndex(
+                        tx_index
+                            .try_into()
+                            .expect("transaction index out of bounds"),
+                    ),
+                    &mut tx_gas_meter,
+                    &mut self.wl_storage,
+                    &mut self.vp_wasm_cache,
+                    &mut self.tx_wasm_cache,
+                    Some(&native_block_proposer_address),
+                    #[cfg(not(feature = "mainnet"))]
+                    has_valid_pow,
                 ),
-                &mut tx_gas_meter,
-                &mut self.wl_storage,
-                &mut self.vp_wasm_cache,
-                &mut self.tx_wasm_cache,
-                Some(&native_block_proposer_address),
-                #[cfg(not(feature = "mainnet"))]
-                has_valid_pow,
-            )
-            .map_err(Error::TxApply)
-            {
+            };
+            match dispatch_result.map_err(Error::TxApply) {
                 Ok(result) => {
                     if result.is_accepted() {
                         if let EventType::Accepted = tx_event.event_type {
@@ -491,6 +525,16 @@ where
                                 .results
                                 .accept(tx_index);
                         }
+                        tx_event.add_tx_events(&result.tx_events);
+                        for custom_event in &result.tx_events {
+                            // Add the custom event besides the tx_event
+                            let mut event = Event::from(custom_event.clone());
+                            // Add the hash and height of the tx for the custom
+                            // event query
+                            event["hash"] = tx_event["hash"].clone();
+                            event["height"] = height.to_string();
+                            response.events.push(event);
+                        }
                         for ibc_event in &result.ibc_events {
                             // Add the IBC event besides the tx_event
                             let mut event = Event::from(ibc_event.clone());
@@ -526,6 +570,24 @@ where
                     }
                     tx_event["gas_used"] = result.gas_used.to_string();
                     tx_event["info"] = result.to_string();
+                    if !result.batch_results.is_empty() {
+                        let batch_results: Vec<_> = result
+                            .batch_results
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect();
+                        match serde_json::to_string(&batch_results) {
+                            Ok(batch_results) => {
+                                tx_event["batch_results"] = batch_results;
+                            }
+                            Err(err) => {
+                                tracing::error!(
+                                    "Failed to serialize the batch results: {}",
+                                    err
+                                );
+                            }
+                        }
+                    }
                 }
                 Err(msg) => {
                     tracing::info!(
@@ -566,6 +628,31 @@ where
             }
             response.events.push(tx_event);
         }
+        self.adopted_parallel_txs = stats.adopted_txs();
+
+        // The scheduled txs are indexed after the txs of the block
+        let mut scheduled_tx_index = TxIndex(
+            req.txs
+                .len()
+                .try_into()
+                .expect("transaction index out of bounds"),
+        );
+        execute_scheduled_txs(
+            self,
+            &mut response,
+            ScheduleTarget::Height(height),
+            &native_block_proposer_address,
+            &mut scheduled_tx_index,
+        );
+        if new_epoch {
+            execute_scheduled_txs(
+                self,
+                &mut response,
+                ScheduleTarget::Epoch(current_epoch),
+                &native_block_proposer_address,
+                &mut scheduled_tx_index,
+            );
+        }
 
         stats.set_tx_cache_size(
             self.tx_wasm_cache.get_size(),
@@ -680,16 +767,6 @@ where
         let pos_locked_ratio_target = params.target_staked_ratio;
         let pos_max_inflation_rate = params.max_inflation_rate;
 
-        // TODO: properly fetch these values (arbitrary for now)
-        let masp_locked_supply: Amount = Amount::default();
-        let masp_locked_ratio_target = Dec::new(5, 1).expect("Cannot fail");
-        let masp_locked_ratio_last = Dec::new(5, 1).expect("Cannot fail");
-        let masp_max_inflation_rate = Dec::new(2, 1).expect("Cannot fail");
-        let masp_last_inflation_rate = Dec::new(12, 2).expect("Cannot fail");
-        let masp_p_gain = Dec::new(1, 1).expect("Cannot fail");
-        let masp_d_gain = Dec::new(1, 1).expect("Cannot fail");
-
-        // Run rewards PD controller
         let pos_controller = inflation::RewardsController {
             locked_tokens: pos_locked_supply,
             total_tokens,
@@ -701,26 +778,12 @@ where
             d_gain_nom: pos_d_gain_nom,
             epochs_per_year,
         };
-        let _masp_controller = inflation::RewardsController {
-            locked_tokens: masp_locked_supply,
-            total_tokens,
-            locked_ratio_target: masp_locked_ratio_target,
-            locked_ratio_last: masp_locked_ratio_last,
-            max_reward_rate: masp_max_inflation_rate,
-            last_inflation_amount: token::Amount::from(
-                masp_last_inflation_rate,
-            ),
-            p_gain_nom: masp_p_gain,
-            d_gain_nom: masp_d_gain,
-            epochs_per_year,
-        };
-
-        // Run the rewards controllers
+        // Run the rewards controller. The shielded rewards controllers are
+        // run per token when the MASP conversions are updated.
         let inflation::ValsToUpdate {
             locked_ratio,
             inflation,
         } = pos_controller.run();
-        // let new_masp_vals = _masp_controller.run();
 
         // Get the number of blocks in the last epoch
         let first_block_of_last_epoch = self
@@ -877,32 +940,7 @@ where
             pgf_inflation_amount.to_string_native()
         );
 
-        let mut pgf_fundings = pgf::get_payments(&self.wl_storage)?;
-        // we want to pay first the oldest fundings
-        pgf_fundings.sort_by(|a, b| a.id.cmp(&b.id));
-
-        for funding in pgf_fundings {
-            if credit_tokens(
-                &mut self.wl_storage,
-                &staking_token,
-                &funding.detail.target,
-                funding.detail.amount,
-            )
-            .is_ok()
-            {
-                tracing::info!(
-                    "Minted {} tokens for {} project.",
-                    funding.detail.amount.to_string_native(),
-                    &funding.detail.target,
-                );
-            } else {
-                tracing::warn!(
-                    "Failed Minting {} tokens for {} project.",
-                    funding.detail.amount.to_string_native(),
-                    &funding.detail.target,
-                );
-            }
-        }
+        governance::execute_pgf_fundings(self, &staking_token, current_epoch)?;
 
         // Pgf steward inflation
         let stewards = pgf::get_stewards(&self.wl_storage)?;
@@ -2,7 +2,8 @@ use std::collections::HashMap;
 
 use namada::core::ledger::governance::storage::keys as gov_storage;
 use namada::core::ledger::governance::storage::proposal::{
-    AddRemove, PGFAction, ProposalType, StoragePgfFunding,
+    AddRemove, PGFAction, PGFIbcTarget, PGFTarget, ProposalType,
+    StoragePgfFunding,
 };
 use namada::core::ledger::governance::utils::{
     compute_proposal_result, ProposalVotes, TallyResult, TallyType, TallyVote,
@@ -14,20 +15,28 @@ use namada::core::ledger::pgf::storage::steward::StewardDetail;
 use namada::core::ledger::pgf::ADDRESS;
 use namada::core::ledger::storage_api::governance as gov_api;
 use namada::ledger::governance::utils::ProposalEvent;
+use namada::ledger::ibc;
 use namada::ledger::pos::BondId;
 use namada::ledger::protocol;
 use namada::ledger::storage::types::encode;
 use namada::ledger::storage::{DBIter, StorageHasher, DB};
-use namada::ledger::storage_api::{pgf, token, StorageWrite};
+use namada::ledger::storage_api::{pgf, token, ResultExt, StorageWrite};
+use namada::ledger::tx::TX_IBC_WASM;
 use namada::proof_of_stake::parameters::PosParams;
 use namada::proof_of_stake::{bond_amount, read_total_stake};
 use namada::proto::{Code, Data};
 use namada::types::address::Address;
-use namada::types::storage::Epoch;
+use namada::types::hash::Hash;
+use namada::types::storage::{Epoch, Key};
+use namada::types::time::DurationSecs;
 
 use super::utils::force_read;
 use super::*;
 
+/// The timeout of the IBC transfers of PGF payments, in seconds after the last
+/// block time
+const PGF_IBC_TRANSFER_TIMEOUT_SECS: u64 = 3600;
+
 #[derive(Default)]
 pub struct ProposalsResult {
     passed: Vec<u64>,
@@ -120,7 +129,7 @@ where
                         let native_token =
                             &shell.wl_storage.get_native_token()?;
                         let result = execute_pgf_payment_proposal(
-                            &mut shell.wl_storage,
+                            shell,
                             native_token,
                             payments,
                             id,
@@ -340,47 +349,50 @@ where
     Ok(true)
 }
 
-fn execute_pgf_payment_proposal<S>(
-    storage: &mut S,
+fn execute_pgf_payment_proposal<D, H>(
+    shell: &mut Shell<D, H>,
     token: &Address,
     payments: Vec<PGFAction>,
     proposal_id: u64,
 ) -> Result<bool>
 where
-    S: StorageRead + StorageWrite,
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
 {
     for payment in payments {
         match payment {
             PGFAction::Continuous(action) => match action {
-                AddRemove::Add(target) => {
+                AddRemove::Add(funding) => {
                     pgf_storage::fundings_handle().insert(
-                        storage,
-                        target.target.clone(),
-                        StoragePgfFunding::new(target.clone(), proposal_id),
+                        &mut shell.wl_storage,
+                        funding.target.target(),
+                        StoragePgfFunding::new(funding.clone(), proposal_id),
                     )?;
                     tracing::info!(
                         "Execute ContinousPgf from proposal id {}: set {} to \
                          {}.",
                         proposal_id,
-                        target.amount.to_string_native(),
-                        target.target
+                        funding.target.amount().to_string_native(),
+                        funding.target
                     );
                 }
-                AddRemove::Remove(target) => {
-                    pgf_storage::fundings_handle()
-                        .remove(storage, &target.target)?;
+                AddRemove::Remove(funding) => {
+                    pgf_storage::fundings_handle().remove(
+                        &mut shell.wl_storage,
+                        &funding.target.target(),
+                    )?;
                     tracing::info!(
                         "Execute ContinousPgf from proposal id {}: set {} to \
                          {}.",
                         proposal_id,
-                        target.amount.to_string_native(),
-                        target.target
+                        funding.target.amount().to_string_native(),
+                        funding.target
                     );
                 }
             },
-            PGFAction::Retro(target) => {
+            PGFAction::Retro(PGFTarget::Internal(target)) => {
                 token::transfer(
-                    storage,
+                    &mut shell.wl_storage,
                     token,
                     &ADDRESS,
                     &target.target,
@@ -393,8 +405,390 @@ where
                     target.target
                 );
             }
+            PGFAction::Retro(PGFTarget::Ibc(target)) => {
+                let result = transfer_pgf_over_ibc(
+                    shell,
+                    token,
+                    &target,
+                    target.amount,
+                )?;
+                tracing::info!(
+                    "Execute RetroPgf from proposal id {}: sent {} to {} \
+                     over IBC ({}).",
+                    proposal_id,
+                    target.amount.to_string_native(),
+                    target.target,
+                    result
+                );
+            }
         }
     }
 
     Ok(true)
 }
+
+/// Pay the continuous PGF fundings active at the given epoch, starting from
+/// the oldest ones. The fundings that are completed are removed.
+pub fn execute_pgf_fundings<D, H>(
+    shell: &mut Shell<D, H>,
+    token: &Address,
+    current_epoch: Epoch,
+) -> Result<()>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    let mut pgf_fundings = pgf::get_payments(&shell.wl_storage)?;
+    // we want to pay first the oldest fundings
+    pgf_fundings.sort_by(|a, b| a.id.cmp(&b.id));
+
+    for mut funding in pgf_fundings {
+        let key = funding.detail.target.target();
+        if let Some(amount) = funding.payment_at(current_epoch) {
+            let paid = match &funding.detail.target {
+                PGFTarget::Internal(target) => token::credit_tokens(
+                    &mut shell.wl_storage,
+                    token,
+                    &target.target,
+                    amount,
+                )
+                .is_ok(),
+                // Funds are minted into the PGF account first, so that
+                // failed transfers are refunded to it
+                PGFTarget::Ibc(target) => {
+                    token::credit_tokens(
+                        &mut shell.wl_storage,
+                        token,
+                        &ADDRESS,
+                        amount,
+                    )?;
+                    transfer_pgf_over_ibc(shell, token, target, amount)?
+                }
+            };
+            if paid {
+                tracing::info!(
+                    "Minted {} tokens for {} project.",
+                    amount.to_string_native(),
+                    &funding.detail.target,
+                );
+                funding.paid += amount;
+            } else {
+                tracing::warn!(
+                    "Failed Minting {} tokens for {} project.",
+                    amount.to_string_native(),
+                    &funding.detail.target,
+                );
+            }
+        }
+        if funding.is_completed(current_epoch) {
+            tracing::info!(
+                "Continuous funding {} for {} project is completed.",
+                funding.id,
+                &funding.detail.target,
+            );
+            pgf_storage::fundings_handle()
+                .remove(&mut shell.wl_storage, &key)?;
+        } else {
+            pgf_storage::fundings_handle().insert(
+                &mut shell.wl_storage,
+                key,
+                funding,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Transfer the given amount of a token from the PGF account to a target on
+/// another chain by executing an IBC transfer tx on its behalf. If the tx is
+/// rejected, its changes are dropped and the funds stay in the PGF account,
+/// while the changes applied before the transfer are kept pending.
+/// Transfers failing on the counterparty chain (error acknowledgement or
+/// timeout) are refunded to the PGF account by the IBC module.
+pub fn transfer_pgf_over_ibc<D, H>(
+    shell: &mut Shell<D, H>,
+    token: &Address,
+    target: &PGFIbcTarget,
+    amount: token::Amount,
+) -> Result<bool>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    let code_hash = match shell
+        .wl_storage
+        .read_bytes(&Key::wasm_hash(TX_IBC_WASM))?
+        .map(|bytes| Hash::try_from(&bytes[..]))
+    {
+        Some(Ok(hash)) => hash,
+        _ => {
+            tracing::warn!("Missing IBC transfer tx code for PGF payments");
+            return Ok(false);
+        }
+    };
+    let timeout = shell
+        .wl_storage
+        .storage
+        .get_last_block_timestamp()
+        .into_storage_result()?
+        + DurationSecs(PGF_IBC_TRANSFER_TIMEOUT_SECS);
+    let data = match ibc::make_transfer_data(
+        token,
+        amount,
+        &ADDRESS,
+        &target.target,
+        &target.port_id,
+        &target.channel_id,
+        timeout,
+    ) {
+        Ok(data) => data,
+        Err(err) => {
+            tracing::warn!(
+                "Invalid PGF IBC transfer to {}: {}",
+                target.target,
+                err
+            );
+            return Ok(false);
+        }
+    };
+
+    let mut tx = Tx::from_type(TxType::Decrypted(DecryptedTx::Decrypted {
+        #[cfg(not(feature = "mainnet"))]
+        has_valid_pow: false,
+    }));
+    tx.header.chain_id = shell.chain_id.clone();
+    tx.set_data(Data::new(data));
+    tx.set_code(Code::from_hash(code_hash));
+
+    // The pending changes are precommitted, so that the transfer is validated
+    // on top of them and a failed transfer doesn't drop them
+    shell.wl_storage.write_log.precommit_tx();
+    let tx_result = protocol::dispatch_tx(
+        tx,
+        &[], /*  this is used to compute the fee
+              * based on the code size. We dont
+              * need it here. */
+        TxIndex::default(),
+        &mut TxGasMeter::new_from_sub_limit(u64::MAX.into()), /* No gas limit for PGF payments */
+        &mut shell.wl_storage,
+        &mut shell.vp_wasm_cache,
+        &mut shell.tx_wasm_cache,
+        None,
+        #[cfg(not(feature = "mainnet"))]
+        false,
+    );
+    match tx_result {
+        Ok(tx_result) if tx_result.is_accepted() => {
+            shell.wl_storage.commit_tx();
+            Ok(true)
+        }
+        Ok(tx_result) => {
+            shell.wl_storage.write_log.drop_tx_keep_precommit();
+            tracing::warn!(
+                "PGF IBC transfer to {} was rejected: {}",
+                target.target,
+                tx_result
+            );
+            Ok(false)
+        }
+        Err(err) => {
+            shell.wl_storage.write_log.drop_tx_keep_precommit();
+            tracing::warn!(
+                "PGF IBC transfer to {} failed: {}",
+                target.target,
+                err
+            );
+            Ok(false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_pgf_fundings {
+    use namada::core::ledger::governance::storage::proposal::{
+        PGFContinuousFunding, PGFInternalTarget,
+    };
+    use namada::ledger::storage_api::StorageRead;
+    use namada::types::address::testing::established_address_1;
+    use namada::types::token::Amount;
+
+    use super::*;
+    use crate::node::ledger::shell::test_utils::*;
+
+    fn ibc_target(amount: Amount) -> PGFIbcTarget {
+        PGFIbcTarget {
+            target: "cosmos1pgftarget".to_string(),
+            amount,
+            port_id: "transfer".to_string(),
+            channel_id: "channel-0".to_string(),
+        }
+    }
+
+    fn read_balance(shell: &TestShell, owner: &Address) -> Amount {
+        let token = shell.wl_storage.get_native_token().unwrap();
+        token::read_balance(&shell.wl_storage, &token, owner).unwrap()
+    }
+
+    /// Test that the continuous fundings are paid according to their schedule
+    /// and cap, and that failed IBC payments are kept in the PGF account
+    #[test]
+    fn test_execute_pgf_fundings() {
+        let (mut shell, _recv, _, _) = setup();
+        let token = shell.wl_storage.get_native_token().unwrap();
+        // without the IBC transfer tx code, the IBC payments fail
+        shell
+            .wl_storage
+            .delete(&Key::wasm_hash(TX_IBC_WASM))
+            .unwrap();
+
+        let target = established_address_1();
+        let internal = StoragePgfFunding::new(
+            PGFContinuousFunding {
+                target: PGFTarget::Internal(PGFInternalTarget {
+                    target: target.clone(),
+                    amount: Amount::native_whole(10),
+                }),
+                start_epoch: Some(Epoch(1)),
+                end_epoch: None,
+                cap: Some(Amount::native_whole(25)),
+            },
+            0,
+        );
+        let ibc = StoragePgfFunding::new(
+            PGFContinuousFunding {
+                target: PGFTarget::Ibc(ibc_target(Amount::native_whole(5))),
+                start_epoch: None,
+                end_epoch: Some(Epoch(1)),
+                cap: None,
+            },
+            1,
+        );
+        for funding in [&internal, &ibc] {
+            pgf_storage::fundings_handle()
+                .insert(
+                    &mut shell.wl_storage,
+                    funding.detail.target.target(),
+                    funding.clone(),
+                )
+                .unwrap();
+        }
+        let pgf_balance = read_balance(&shell, &ADDRESS);
+
+        // the internal funding hasn't started yet
+        execute_pgf_fundings(&mut shell.shell, &token, Epoch(0)).unwrap();
+        assert_eq!(read_balance(&shell, &target), Amount::zero());
+        let stored = pgf_storage::fundings_handle()
+            .get(&shell.wl_storage, &internal.detail.target.target())
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.paid, Amount::zero());
+        // the failed IBC payment is not counted and its funds stay in the
+        // PGF account
+        let stored = pgf_storage::fundings_handle()
+            .get(&shell.wl_storage, &ibc.detail.target.target())
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.paid, Amount::zero());
+        assert_eq!(
+            read_balance(&shell, &ADDRESS),
+            pgf_balance + Amount::native_whole(5)
+        );
+
+        execute_pgf_fundings(&mut shell.shell, &token, Epoch(1)).unwrap();
+        assert_eq!(read_balance(&shell, &target), Amount::native_whole(10));
+        // the IBC funding reached its end epoch
+        assert!(
+            !pgf_storage::fundings_handle()
+                .contains(&shell.wl_storage, &ibc.detail.target.target())
+                .unwrap()
+        );
+        assert_eq!(
+            read_balance(&shell, &ADDRESS),
+            pgf_balance + Amount::native_whole(10)
+        );
+
+        execute_pgf_fundings(&mut shell.shell, &token, Epoch(2)).unwrap();
+        assert_eq!(read_balance(&shell, &target), Amount::native_whole(20));
+        // the last payment is reduced to the cap and completes the funding
+        execute_pgf_fundings(&mut shell.shell, &token, Epoch(3)).unwrap();
+        assert_eq!(read_balance(&shell, &target), Amount::native_whole(25));
+        assert!(pgf::get_payments(&shell.wl_storage).unwrap().is_empty());
+    }
+
+    /// Test that a failed IBC retro payment keeps the funds in the PGF account
+    /// and that the changes applied before it are kept
+    #[test]
+    fn test_pgf_retro_ibc_payment_failure() {
+        let (mut shell, _recv, _, _) = setup();
+        let token = shell.wl_storage.get_native_token().unwrap();
+        shell
+            .wl_storage
+            .delete(&Key::wasm_hash(TX_IBC_WASM))
+            .unwrap();
+        token::credit_tokens(
+            &mut shell.wl_storage,
+            &token,
+            &ADDRESS,
+            Amount::native_whole(100),
+        )
+        .unwrap();
+        let pgf_balance = read_balance(&shell, &ADDRESS);
+        let target = established_address_1();
+
+        let payments = vec![
+            PGFAction::Retro(PGFTarget::Internal(PGFInternalTarget {
+                target: target.clone(),
+                amount: Amount::native_whole(30),
+            })),
+            PGFAction::Retro(PGFTarget::Ibc(ibc_target(Amount::native_whole(
+                50,
+            )))),
+        ];
+        let result =
+            execute_pgf_payment_proposal(&mut shell.shell, &token, payments, 0)
+                .unwrap();
+        assert!(result);
+        assert_eq!(read_balance(&shell, &target), Amount::native_whole(30));
+        assert_eq!(
+            read_balance(&shell, &ADDRESS),
+            pgf_balance - Amount::native_whole(30)
+        );
+    }
+
+    /// Test that a failed IBC transfer doesn't commit the changes applied
+    /// before it, which are left pending
+    #[test]
+    fn test_pgf_ibc_transfer_failure_keeps_pending_changes() {
+        let (mut shell, _recv, _, _) = setup();
+        let token = shell.wl_storage.get_native_token().unwrap();
+        // the IBC transfer tx code cannot be found, so the transfer fails
+        shell
+            .wl_storage
+            .write_bytes(&Key::wasm_hash(TX_IBC_WASM), [0u8; 32])
+            .unwrap();
+        shell.wl_storage.commit_tx();
+        let target = established_address_1();
+        token::credit_tokens(
+            &mut shell.wl_storage,
+            &token,
+            &target,
+            Amount::native_whole(10),
+        )
+        .unwrap();
+
+        let paid = transfer_pgf_over_ibc(
+            &mut shell.shell,
+            &token,
+            &ibc_target(Amount::native_whole(5)),
+            Amount::native_whole(5),
+        )
+        .unwrap();
+        assert!(!paid);
+        assert_eq!(read_balance(&shell, &target), Amount::native_whole(10));
+
+        // the change was not committed by the transfer
+        shell.wl_storage.drop_tx();
+        assert_eq!(read_balance(&shell, &target), Amount::zero());
+    }
+}
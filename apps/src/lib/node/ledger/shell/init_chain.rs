@@ -11,6 +11,7 @@ use namada::ledger::storage::traits::StorageHasher;
 use namada::ledger::storage::{DBIter, DB};
 use namada::ledger::storage_api::token::{
     credit_tokens, read_balance, read_total_supply, write_denom,
+    write_masp_params,
 };
 use namada::ledger::storage_api::{ResultExt, StorageRead, StorageWrite};
 use namada::ledger::{ibc, pos};
@@ -283,6 +284,7 @@ where
             vp_sha256,
             public_key,
             storage,
+            vesting,
         } in accounts
         {
             let vp_code_hash = read_wasm_hash(&self.wl_storage, &vp_code_path)?
@@ -321,6 +323,15 @@ where
                 self.wl_storage.write_bytes(&key, value).unwrap();
             }
 
+            for (token, schedule) in vesting {
+                storage_api::vesting::write_schedule(
+                    &mut self.wl_storage,
+                    &address,
+                    &token,
+                    schedule,
+                )?;
+            }
+
             // When using a faucet WASM, initialize its PoW challenge storage
             #[cfg(not(feature = "mainnet"))]
             if vp_code_path == "vp_testnet_faucet.wasm" {
@@ -371,10 +382,16 @@ where
             address,
             denom,
             balances,
+            masp_params,
         } in accounts
         {
             // associate a token with its denomination.
             write_denom(&mut self.wl_storage, &address, denom).unwrap();
+            // set up the initial shielded reward parameters, if any
+            if let Some(masp_params) = masp_params {
+                write_masp_params(&mut self.wl_storage, &address, &masp_params)
+                    .unwrap();
+            }
             for (owner, amount) in balances {
                 credit_tokens(&mut self.wl_storage, &address, &owner, amount)
                     .unwrap();
@@ -9,9 +9,11 @@ pub mod block_alloc;
 mod finalize_block;
 mod governance;
 mod init_chain;
+mod parallel_txs;
 pub mod prepare_proposal;
 pub mod process_proposal;
 pub(super) mod queries;
+mod scheduled_txs;
 mod stats;
 #[cfg(any(test, feature = "testing"))]
 #[allow(dead_code)]
@@ -19,6 +21,7 @@ pub mod testing;
 pub mod utils;
 mod vote_extensions;
 
+use std::cell::RefCell;
 use std::collections::{BTreeSet, HashSet};
 use std::convert::{TryFrom, TryInto};
 use std::mem;
@@ -33,7 +36,7 @@ use namada::ledger::eth_bridge::{EthBridgeQueries, EthereumOracleConfig};
 use namada::ledger::events::log::EventLog;
 use namada::ledger::events::Event;
 use namada::ledger::gas::{Gas, TxGasMeter};
-use namada::ledger::pos::into_tm_voting_power;
+use namada::ledger::pos::{into_tm_voting_power, PosQueries};
 use namada::ledger::pos::namada_proof_of_stake::types::{
     ConsensusValidator, ValidatorSetUpdate,
 };
@@ -397,10 +400,19 @@ where
     /// limit the how many block heights in the past can the storage be
     /// queried for reading values.
     storage_read_past_height_limit: Option<u64>,
+    /// Taken from config `parallel_tx_execution`. When set, the decrypted
+    /// txs of a block are optimistically executed in parallel.
+    parallel_tx_execution: bool,
+    /// The number of decrypted txs of the last finalized block whose parallel
+    /// execution was adopted
+    adopted_parallel_txs: u64,
     /// Proposal execution tracking
     pub proposal_data: HashSet<u64>,
     /// Log of events emitted by `FinalizeBlock` ABCI calls.
     event_log: EventLog,
+    /// Mempool txs which could not be allocated in the block
+    /// proposals of this node.
+    alloc_failures: RefCell<block_alloc::AllocFailureTracker>,
 }
 
 /// Channels for communicating with an Ethereum oracle.
@@ -449,6 +461,10 @@ where
         let mode = config.shell.tendermint_mode;
         let storage_read_past_height_limit =
             config.shell.storage_read_past_height_limit;
+        let warm_wasm_compilation_cache =
+            config.shell.warm_wasm_compilation_cache.unwrap_or(true);
+        let parallel_tx_execution =
+            config.shell.parallel_tx_execution.unwrap_or(false);
         if !Path::new(&base_dir).is_dir() {
             std::fs::create_dir(&base_dir)
                 .expect("Creating directory for Namada should not fail");
@@ -544,14 +560,87 @@ where
                 tx_wasm_compilation_cache as usize,
             ),
             storage_read_past_height_limit,
+            parallel_tx_execution,
+            adopted_parallel_txs: 0,
             proposal_data: HashSet::new(),
             // TODO: config event log params
             event_log: EventLog::default(),
+            alloc_failures: RefCell::default(),
         };
         shell.update_eth_oracle();
+        if warm_wasm_compilation_cache {
+            shell.warm_wasm_compilation_cache();
+        }
         shell
     }
 
+    /// Pre-compile all the tx and VP WASMs whitelisted in storage. The
+    /// compilation runs in parallel in the background and the modules that
+    /// already have an up-to-date compiled file are skipped. Before the
+    /// chain is initialized there is nothing to do, as the genesis WASMs are
+    /// pre-compiled in `init_chain`.
+    fn warm_wasm_compilation_cache(&mut self) {
+        if self.wl_storage.storage.last_block.is_none() {
+            return;
+        }
+        let read_whitelist = |key: &Key| -> Vec<String> {
+            self.wl_storage
+                .read(key)
+                .expect("Must be able to read the WASM whitelist")
+                .unwrap_or_default()
+        };
+        let tx_whitelist = read_whitelist(
+            &parameters::storage::get_tx_whitelist_storage_key(),
+        );
+        let vp_whitelist = read_whitelist(
+            &parameters::storage::get_vp_whitelist_storage_key(),
+        );
+        tracing::info!(
+            "Warming up the WASM compilation cache with {} tx and {} VP \
+             WASMs.",
+            tx_whitelist.len(),
+            vp_whitelist.len()
+        );
+        for code in self.read_whitelisted_wasm_code(tx_whitelist) {
+            self.tx_wasm_cache.pre_compile(code);
+        }
+        for code in self.read_whitelisted_wasm_code(vp_whitelist) {
+            self.vp_wasm_cache.pre_compile(code);
+        }
+    }
+
+    /// Read the code of the given whitelisted WASM hashes from storage,
+    /// skipping the ones that are invalid or missing.
+    fn read_whitelisted_wasm_code(
+        &self,
+        whitelist: Vec<String>,
+    ) -> Vec<Vec<u8>> {
+        whitelist
+            .into_iter()
+            .filter_map(|id| {
+                let code_hash = match hash::Hash::try_from(id.as_str()) {
+                    Ok(code_hash) => code_hash,
+                    Err(err) => {
+                        tracing::warn!(
+                            "Invalid whitelisted WASM hash {id}: {err}"
+                        );
+                        return None;
+                    }
+                };
+                let code = self
+                    .wl_storage
+                    .read_bytes(&Key::wasm_code(&code_hash))
+                    .expect("Must be able to read the WASM code");
+                if code.is_none() {
+                    tracing::warn!(
+                        "The whitelisted WASM {id} is not in storage."
+                    );
+                }
+                code
+            })
+            .collect()
+    }
+
     /// Return a reference to the [`EventLog`].
     #[inline]
     pub fn event_log(&self) -> &EventLog {
@@ -1022,7 +1111,7 @@ where
     pub fn mempool_validate(
         &self,
         tx_bytes: &[u8],
-        r#_type: MempoolTxType,
+        r#type: MempoolTxType,
     ) -> response::CheckTx {
         #[cfg(not(feature = "abcipp"))]
         use namada::types::transaction::protocol::{
@@ -1044,6 +1133,37 @@ where
             }
         };
 
+        // Tx size check
+        let max_proposal_bytes =
+            self.wl_storage.pos_queries().get_max_proposal_bytes().get();
+        if tx_bytes.len() as u64 > max_proposal_bytes {
+            response.code = ErrorCodes::AllocationError.into();
+            response.log = format!(
+                "{INVALID_MSG}: Tx of {} bytes exceeds the maximum proposal \
+                 size of {max_proposal_bytes} bytes",
+                tx_bytes.len(),
+            );
+            return response;
+        }
+
+        // Evict txs which could not be allocated in the recent
+        // block proposals of this node
+        if let MempoolTxType::RecheckTransaction = r#type {
+            if self
+                .alloc_failures
+                .borrow()
+                .should_evict(&hash::Hash::sha256(tx_bytes))
+            {
+                response.code = ErrorCodes::AllocationError.into();
+                response.log = format!(
+                    "{INVALID_MSG}: Tx could not be allocated in {} \
+                     consecutive block proposals",
+                    block_alloc::MAX_CONSECUTIVE_ALLOC_FAILURES,
+                );
+                return response;
+            }
+        }
+
         let tx_chain_id = tx.header.chain_id.clone();
         let tx_expiration = tx.header.expiration;
 
@@ -1082,6 +1202,16 @@ where
             }
         };
 
+        // Scheduled txs can only be executed by the protocol
+        if tx.schedule_target().is_some() {
+            response.code = ErrorCodes::InvalidTx.into();
+            response.log = format!(
+                "{INVALID_MSG}: Scheduled txs can only be executed at their \
+                 target height or epoch"
+            );
+            return response;
+        }
+
         // try to parse a vote extension protocol tx from
         // the provided tx data
         macro_rules! try_vote_extension {
@@ -1217,6 +1347,19 @@ where
                     return response;
                 }
 
+                // Max wrapper tx size
+                let max_tx_bytes =
+                    block_alloc::max_encrypted_tx_size(max_proposal_bytes);
+                if tx_bytes.len() as u64 > max_tx_bytes {
+                    response.code = ErrorCodes::AllocationError.into();
+                    response.log = format!(
+                        "{INVALID_MSG}: Wrapper tx of {} bytes exceeds the \
+                         maximum wrapper tx size of {max_tx_bytes} bytes",
+                        tx_bytes.len(),
+                    );
+                    return response;
+                }
+
                 // Replay protection check
                 let mut inner_tx = tx;
                 inner_tx.update_header(TxType::Raw);
@@ -1724,12 +1867,14 @@ mod test_utils {
             let base_dir = tempdir().unwrap().as_ref().canonicalize().unwrap();
             let vp_wasm_compilation_cache = 50 * 1024 * 1024; // 50 kiB
             let tx_wasm_compilation_cache = 50 * 1024 * 1024; // 50 kiB
+            let mut config = config::Ledger::new(
+                base_dir,
+                Default::default(),
+                TendermintMode::Validator,
+            );
+            config.shell.warm_wasm_compilation_cache = Some(false);
             let mut shell = Shell::<MockDB, Sha256Hasher>::new(
-                config::Ledger::new(
-                    base_dir,
-                    Default::default(),
-                    TendermintMode::Validator,
-                ),
+                config,
                 top_level_directory().join("wasm"),
                 sender,
                 Some(eth_oracle),
@@ -2024,12 +2169,14 @@ mod test_utils {
         let vp_wasm_compilation_cache = 50 * 1024 * 1024; // 50 kiB
         let tx_wasm_compilation_cache = 50 * 1024 * 1024; // 50 kiB
         let native_token = address::nam();
+        let mut config = config::Ledger::new(
+            base_dir,
+            Default::default(),
+            TendermintMode::Validator,
+        );
+        config.shell.warm_wasm_compilation_cache = Some(false);
         let mut shell = Shell::<PersistentDB, PersistentStorageHasher>::new(
-            config::Ledger::new(
-                base_dir.clone(),
-                Default::default(),
-                TendermintMode::Validator,
-            ),
+            config.clone(),
             top_level_directory().join("wasm"),
             sender.clone(),
             Some(eth_oracle),
@@ -2094,11 +2241,7 @@ mod test_utils {
         );
         // Reboot the shell and check that the queue was restored from DB
         let shell = Shell::<PersistentDB, PersistentStorageHasher>::new(
-            config::Ledger::new(
-                base_dir,
-                Default::default(),
-                TendermintMode::Validator,
-            ),
+            config,
             top_level_directory().join("wasm"),
             sender,
             Some(eth_oracle),
@@ -2311,8 +2454,11 @@ mod abciplus_mempool_tests {
 
 #[cfg(test)]
 mod test_mempool_validate {
+    use namada::ledger::storage_api::StorageWrite;
     use namada::proof_of_stake::Epoch;
     use namada::proto::{Code, Data, Section, Signature, Tx};
+    use namada::types::chain::ProposalBytes;
+    use namada::types::transaction::scheduled::ScheduleTarget;
     use namada::types::transaction::{Fee, WrapperTx};
 
     use super::*;
@@ -2583,6 +2729,41 @@ mod test_mempool_validate {
         assert_eq!(result.code, u32::from(ErrorCodes::ExpiredTx));
     }
 
+    /// Check that a tx marked for scheduled execution gets rejected
+    #[test]
+    fn test_scheduled_tx() {
+        let (shell, _recv, _, _) = test_utils::setup();
+        let keypair = super::test_utils::gen_keypair();
+
+        let mut wrapper =
+            Tx::from_type(TxType::Wrapper(Box::new(WrapperTx::new(
+                Fee {
+                    amount_per_gas_unit: 100.into(),
+                    token: shell.wl_storage.storage.native_token.clone(),
+                },
+                keypair.ref_to(),
+                Epoch(0),
+                Default::default(),
+                #[cfg(not(feature = "mainnet"))]
+                None,
+                None,
+            ))));
+        wrapper.header.chain_id = shell.chain_id.clone();
+        wrapper.set_code(Code::new("wasm_code".as_bytes().to_owned()));
+        wrapper.set_data(Data::new("transaction data".as_bytes().to_owned()));
+        wrapper.set_schedule(ScheduleTarget::Height(BlockHeight(100)));
+        wrapper.add_section(Section::Signature(Signature::new(
+            wrapper.sechashes(),
+            &keypair,
+        )));
+
+        let result = shell.mempool_validate(
+            wrapper.to_bytes().as_ref(),
+            MempoolTxType::NewTransaction,
+        );
+        assert_eq!(result.code, u32::from(ErrorCodes::InvalidTx));
+    }
+
     /// Check that a tx requiring more gas than the block limit gets rejected
     #[test]
     fn test_exceeding_max_block_gas_tx() {
@@ -2621,6 +2802,85 @@ mod test_mempool_validate {
         assert_eq!(result.code, u32::from(ErrorCodes::AllocationError));
     }
 
+    /// Check that a wrapper tx which could never fit in the wrapper tx
+    /// bin of a block proposal gets rejected
+    #[test]
+    fn test_exceeding_max_wrapper_tx_size() {
+        let (mut shell, _recv, _, _) = test_utils::setup();
+
+        // shrink the max proposal size, such that the wrapper
+        // tx bin can only hold 100 bytes
+        let max_proposal_bytes = ProposalBytes::new(300).unwrap();
+        shell
+            .wl_storage
+            .write(
+                &parameters::storage::get_max_proposal_bytes_key(),
+                max_proposal_bytes,
+            )
+            .unwrap();
+
+        let keypair = super::test_utils::gen_keypair();
+
+        let mut wrapper =
+            Tx::from_type(TxType::Wrapper(Box::new(WrapperTx::new(
+                Fee {
+                    amount_per_gas_unit: 100.into(),
+                    token: shell.wl_storage.storage.native_token.clone(),
+                },
+                keypair.ref_to(),
+                Epoch(0),
+                GAS_LIMIT_MULTIPLIER.into(),
+                #[cfg(not(feature = "mainnet"))]
+                None,
+                None,
+            ))));
+        wrapper.header.chain_id = shell.chain_id.clone();
+        wrapper.set_code(Code::new("wasm_code".as_bytes().to_owned()));
+        wrapper.set_data(Data::new(vec![0; 200]));
+        wrapper.add_section(Section::Signature(Signature::new(
+            wrapper.sechashes(),
+            &keypair,
+        )));
+
+        let result = shell.mempool_validate(
+            wrapper.to_bytes().as_ref(),
+            MempoolTxType::NewTransaction,
+        );
+        assert_eq!(result.code, u32::from(ErrorCodes::AllocationError));
+    }
+
+    /// Check that a tx which failed allocation in too many consecutive
+    /// block proposals gets evicted from the mempool on recheck
+    #[test]
+    fn test_evict_tx_failing_allocation() {
+        let (shell, _recv, _, _) = test_utils::setup();
+
+        let keypair = super::test_utils::gen_keypair();
+
+        let mut tx = Tx::new(shell.chain_id.clone(), None);
+        tx.add_code("wasm_code".as_bytes().to_owned())
+            .add_data("transaction data".as_bytes().to_owned())
+            .sign_wrapper(keypair);
+        let tx_bytes = tx.to_bytes();
+
+        for _ in 0..block_alloc::MAX_CONSECUTIVE_ALLOC_FAILURES {
+            let mut alloc_failures = shell.alloc_failures.borrow_mut();
+            alloc_failures.begin_proposal();
+            alloc_failures.record_failure(hash::Hash::sha256(&tx_bytes));
+        }
+
+        // new txs are not subject to eviction
+        let result = shell
+            .mempool_validate(tx_bytes.as_ref(), MempoolTxType::NewTransaction);
+        assert_ne!(result.code, u32::from(ErrorCodes::AllocationError));
+
+        let result = shell.mempool_validate(
+            tx_bytes.as_ref(),
+            MempoolTxType::RecheckTransaction,
+        );
+        assert_eq!(result.code, u32::from(ErrorCodes::AllocationError));
+    }
+
     // Check that a tx requiring more gas than its limit gets rejected
     #[test]
     fn test_exceeding_gas_limit_tx() {
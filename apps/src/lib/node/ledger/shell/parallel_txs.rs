@@ -0,0 +1,620 @@
+//! Optimistic parallel execution of the decrypted transactions of a block.
+//!
+//! All the decrypted txs of a block are first executed concurrently, each
+//! against its own snapshot of the write log taken before any tx of the block
+//! is applied, recording the storage accessed by the tx. When `FinalizeBlock`
+//! then reaches a tx in block order, its speculative result is adopted only
+//! if none of the state it accessed has been changed by the preceding txs.
+//! Otherwise, the tx is re-executed sequentially as usual, so the resulting
+//! state is always identical to a sequential execution.
+
+use std::collections::HashMap;
+
+use namada::ledger::storage::write_log::AccessSet;
+use namada::types::hash::Hash;
+use namada::types::transaction::TxResult;
+use rayon::prelude::*;
+
+use super::*;
+use crate::node::ledger::shims::abcipp_shim_types::shim::request::ProcessedTx;
+
+/// A decrypted tx executed against a snapshot of the write log
+#[derive(Debug)]
+struct SpeculativeTx {
+    /// The hash of the wrapper tx in the queue that carried this tx
+    wrapper_hash: Hash,
+    /// The result of the execution
+    result: protocol::Result<TxResult>,
+    /// The gas meter after the execution
+    gas_meter: TxGasMeter,
+    /// The snapshot of the write log with the tx's modifications
+    write_log: WriteLog,
+    /// The storage accessed by the tx
+    access_set: AccessSet,
+}
+
+/// A decrypted tx to be executed speculatively
+struct Candidate {
+    /// The index of the tx in the block
+    tx_index: usize,
+    /// The decrypted tx
+    tx: Tx,
+    /// The hash of the wrapper tx in the queue that carried this tx
+    wrapper_hash: Hash,
+    /// The gas available to the tx
+    gas: Gas,
+    #[cfg(not(feature = "mainnet"))]
+    /// Whether the wrapper tx contained a valid PoW solution
+    has_valid_pow: bool,
+}
+
+/// The decrypted txs of a block executed in parallel, indexed by their
+/// position in the block
+#[derive(Debug, Default)]
+pub struct SpeculativeTxs {
+    /// The write log from before any tx of the block was applied
+    snapshot: WriteLog,
+    /// The executed txs by their index in the block
+    txs: HashMap<usize, SpeculativeTx>,
+}
+
+impl SpeculativeTxs {
+    /// Adopt the speculative execution of the decrypted tx at the given index
+    /// in the block into the given write log and return its result and gas
+    /// meter. Returns `None` if the tx hasn't been executed speculatively or
+    /// if it conflicts with the txs applied before it, in which case it must
+    /// be executed again.
+    pub fn adopt(
+        &mut self,
+        tx_index: usize,
+        wrapper_hash: &Hash,
+        write_log: &mut WriteLog,
+    ) -> Option<(protocol::Result<TxResult>, TxGasMeter)> {
+        let tx = self.txs.remove(&tx_index)?;
+        if tx.wrapper_hash != *wrapper_hash {
+            tracing::error!(
+                "Internal logic error: speculatively executed tx at index \
+                 {tx_index} doesn't match the tx queue"
+            );
+            return None;
+        }
+        if write_log.adopt_tx(&self.snapshot, tx.write_log, &tx.access_set) {
+            tracing::trace!("Adopted speculatively executed tx {tx_index}");
+            Some((tx.result, tx.gas_meter))
+        } else {
+            tracing::debug!(
+                "Re-executing tx {tx_index} that conflicts with a preceding \
+                 tx"
+            );
+            None
+        }
+    }
+}
+
+/// Execute the decrypted txs of the block that were accepted by
+/// `ProcessProposal` in parallel, each against its own snapshot of the
+/// current write log. The txs are matched with the wrapper txs in the queue
+/// in the same way as in `FinalizeBlock`.
+pub fn execute_decrypted_txs<D, H>(
+    shell: &Shell<D, H>,
+    processed_txs: &[ProcessedTx],
+) -> SpeculativeTxs
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    let tx_queue = &shell.wl_storage.storage.tx_queue;
+    let mut queue_index = 0;
+    let mut candidates = vec![];
+    for (tx_index, processed_tx) in processed_txs.iter().enumerate() {
+        let Ok(tx) = Tx::try_from(processed_tx.tx.as_ref()) else {
+            continue;
+        };
+        let code = ErrorCodes::from_u32(processed_tx.result.code);
+        if code == Some(ErrorCodes::InvalidSig) || tx.validate_tx().is_err() {
+            continue;
+        }
+        let TxType::Decrypted(decrypted) = tx.header().tx_type else {
+            continue;
+        };
+        // Any other decrypted tx dequeues its wrapper in `FinalizeBlock`
+        let Some(tx_in_queue) = tx_queue.get(queue_index) else {
+            break;
+        };
+        queue_index += 1;
+        let DecryptedTx::Decrypted {
+            #[cfg(not(feature = "mainnet"))]
+            has_valid_pow,
+        } = decrypted
+        else {
+            continue;
+        };
+        if code != Some(ErrorCodes::Ok) {
+            continue;
+        }
+        let wrapper_hash = tx_in_queue
+            .tx
+            .clone()
+            .update_header(TxType::Raw)
+            .header_hash();
+        candidates.push(Candidate {
+            tx_index,
+            tx,
+            wrapper_hash,
+            gas: tx_in_queue.gas,
+            #[cfg(not(feature = "mainnet"))]
+            has_valid_pow,
+        });
+    }
+    if candidates.is_empty() {
+        return SpeculativeTxs::default();
+    }
+
+    let snapshot = shell.wl_storage.write_log.clone();
+    let storage = &shell.wl_storage.storage;
+    let vp_wasm_cache = shell.vp_wasm_cache.clone();
+    let tx_wasm_cache = shell.tx_wasm_cache.clone();
+    let txs = candidates
+        .into_par_iter()
+        .map(
+            |Candidate {
+                 tx_index,
+                 tx,
+                 wrapper_hash,
+                 gas,
+                 #[cfg(not(feature = "mainnet"))]
+                 has_valid_pow,
+             }| {
+                let mut wl_storage = TempWlStorage::new(storage);
+                wl_storage.write_log = snapshot.clone();
+                wl_storage.write_log.enable_access_tracking();
+                let mut gas_meter = TxGasMeter::new_from_sub_limit(gas);
+                let mut vp_wasm_cache = vp_wasm_cache.clone();
+                let mut tx_wasm_cache = tx_wasm_cache.clone();
+                let result = apply_wasm_tx(
+                    tx,
+                    &TxIndex(
+                        tx_index
+                            .try_into()
+                            .expect("transaction index out of bounds"),
+                    ),
+                    ShellParams::new(
+                        &mut gas_meter,
+                        &mut wl_storage,
+                        &mut vp_wasm_cache,
+                        &mut tx_wasm_cache,
+                    ),
+                    #[cfg(not(feature = "mainnet"))]
+                    has_valid_pow,
+                );
+                let access_set = wl_storage
+                    .write_log
+                    .take_access_set()
+                    .expect("Access tracking must be enabled");
+                (
+                    tx_index,
+                    SpeculativeTx {
+                        wrapper_hash,
+                        result,
+                        gas_meter,
+                        write_log: wl_storage.write_log,
+                        access_set,
+                    },
+                )
+            },
+        )
+        .collect();
+
+    SpeculativeTxs { snapshot, txs }
+}
+
+#[cfg(test)]
+mod test {
+    use namada::ledger::storage_api::StorageWrite;
+    use namada::proto::{Code, Data};
+    use namada::types::address::testing::{
+        established_address_1, established_address_2, established_address_3,
+        established_address_4,
+    };
+    use namada::types::key::testing::keypair_1;
+    use namada::types::storage::Epoch;
+    use namada::types::transaction::Fee;
+    use namada_test_utils::tx_data::TxWriteData;
+    use namada_test_utils::TestWasms;
+
+    use super::*;
+    use crate::node::ledger::shell::test_utils::*;
+    use crate::node::ledger::shims::abcipp_shim_types::shim::request::FinalizeBlock;
+    use crate::node::ledger::shims::abcipp_shim_types::shim::response::TxResult as ProcessedTxResult;
+
+    const GAS_LIMIT_MULTIPLIER: u64 = 300_000;
+
+    /// The initial native token balance of the test accounts
+    const INITIAL_BALANCE: u64 = 1_000;
+
+    /// A tx of the differential test
+    #[derive(Clone, Debug)]
+    enum TestTx {
+        /// Write the value under the key with the `tx_write` wasm
+        Write(Key, &'static str),
+        /// Read the key with the `tx_read_storage_key` wasm
+        Read(Key),
+        /// Count the keys with the given prefix and write the count under
+        /// the given key
+        Iter(Key, Key),
+        /// Transfer the amount of the native token from the first account
+        /// to the second one
+        Transfer(Address, Address, u64),
+    }
+
+    /// Make a decrypted tx with the given code and data and enqueue its
+    /// wrapper.
+    fn mk_tx(
+        shell: &mut TestShell,
+        keypair: &common::SecretKey,
+        code: Vec<u8>,
+        data: Vec<u8>,
+    ) -> ProcessedTx {
+        let mut outer_tx =
+            Tx::from_type(TxType::Wrapper(Box::new(WrapperTx::new(
+                Fee {
+                    amount_per_gas_unit: 1.into(),
+                    token: shell.wl_storage.storage.native_token.clone(),
+                },
+                keypair.ref_to(),
+                Epoch(0),
+                GAS_LIMIT_MULTIPLIER.into(),
+                #[cfg(not(feature = "mainnet"))]
+                None,
+                None,
+            ))));
+        outer_tx.header.chain_id = shell.chain_id.clone();
+        outer_tx.set_code(Code::new(code));
+        outer_tx.set_data(Data::new(data));
+        let gas_limit =
+            Gas::from(outer_tx.header().wrapper().unwrap().gas_limit)
+                .checked_sub(Gas::from(outer_tx.to_bytes().len() as u64))
+                .unwrap();
+        shell.enqueue_tx(outer_tx.clone(), gas_limit);
+        outer_tx.update_header(TxType::Decrypted(DecryptedTx::Decrypted {
+            #[cfg(not(feature = "mainnet"))]
+            has_valid_pow: false,
+        }));
+        outer_tx
+            .decrypt(<EllipticCurve as PairingEngine>::G2Affine::prime_subgroup_generator())
+            .expect("Test failed");
+        ProcessedTx {
+            tx: outer_tx.to_bytes(),
+            result: ProcessedTxResult {
+                code: ErrorCodes::Ok.into(),
+                info: "".into(),
+            },
+        }
+    }
+
+    /// Make a decrypted `tx_write` tx that writes the given value under the
+    /// given key and enqueue its wrapper.
+    fn mk_write_tx(
+        shell: &mut TestShell,
+        keypair: &common::SecretKey,
+        key: &Key,
+        value: &str,
+    ) -> ProcessedTx {
+        let data = TxWriteData {
+            key: key.clone(),
+            value: value.try_to_vec().unwrap(),
+        };
+        mk_tx(
+            shell,
+            keypair,
+            TestWasms::TxWriteStorageKey.read_bytes(),
+            data.try_to_vec().unwrap(),
+        )
+    }
+
+    /// Make the decrypted tx for the given test tx and enqueue its wrapper.
+    fn mk_test_tx(
+        shell: &mut TestShell,
+        keypair: &common::SecretKey,
+        test_tx: &TestTx,
+    ) -> ProcessedTx {
+        match test_tx {
+            TestTx::Write(key, value) => {
+                mk_write_tx(shell, keypair, key, value)
+            }
+            TestTx::Read(key) => mk_tx(
+                shell,
+                keypair,
+                TestWasms::TxReadStorageKey.read_bytes(),
+                key.try_to_vec().unwrap(),
+            ),
+            TestTx::Iter(prefix, key) => {
+                mk_tx(shell, keypair, iter_tx_wasm(prefix, key), vec![])
+            }
+            TestTx::Transfer(source, target, amount) => {
+                let token = shell.wl_storage.storage.native_token.clone();
+                let code = transfer_tx_wasm(
+                    &token::balance_key(&token, source),
+                    &token::balance_key(&token, target),
+                    *amount,
+                );
+                mk_tx(shell, keypair, code, vec![])
+            }
+        }
+    }
+
+    /// A tx that counts the keys with the given prefix and writes the count
+    /// as a little-endian `u64` under the given key
+    fn iter_tx_wasm(prefix: &Key, key: &Key) -> Vec<u8> {
+        let (prefix, key) = (prefix.to_string(), key.to_string());
+        wat::parse_str(format!(
+            r#"
+            (module
+                (import "env" "namada_tx_iter_prefix" (func $iter_prefix (param i64 i64) (result i64)))
+                (import "env" "namada_tx_iter_next" (func $iter_next (param i64) (result i64)))
+                (import "env" "namada_tx_write" (func $write (param i64 i64 i64 i64)))
+
+                (func $_apply_tx (param i64 i64)
+                (local $iter i64)
+                (local $count i64)
+                (local.set $iter
+                    (call $iter_prefix (i64.const 65536) (i64.const {prefix_len})))
+                (block $done
+                    (loop $next
+                    (br_if $done
+                        (i64.lt_s (call $iter_next (local.get $iter)) (i64.const 0)))
+                    (local.set $count (i64.add (local.get $count) (i64.const 1)))
+                    (br $next)))
+                (i64.store (i32.const 131072) (local.get $count))
+                (call $write
+                    (i64.const 196608) (i64.const {key_len})
+                    (i64.const 131072) (i64.const 8)))
+
+                (memory (;0;) 16)
+                (data (i32.const 65536) "{prefix}")
+                (data (i32.const 196608) "{key}")
+                (export "memory" (memory 0))
+                (export "_apply_tx" (func $_apply_tx)))
+            "#,
+            prefix_len = prefix.len(),
+            key_len = key.len(),
+        ))
+        .expect("unexpected error converting wat2wasm")
+    }
+
+    /// A tx that transfers the given amount from the source balance to the
+    /// target balance by updating the lowest limb of the amounts
+    fn transfer_tx_wasm(source: &Key, target: &Key, amount: u64) -> Vec<u8> {
+        let (source, target) = (source.to_string(), target.to_string());
+        wat::parse_str(format!(
+            r#"
+            (module
+                (import "env" "namada_tx_read" (func $read (param i64 i64) (result i64)))
+                (import "env" "namada_tx_result_buffer" (func $result_buffer (param i64)))
+                (import "env" "namada_tx_write" (func $write (param i64 i64 i64 i64)))
+
+                (func $_apply_tx (param i64 i64)
+                (drop (call $read (i64.const 65536) (i64.const {source_len})))
+                (call $result_buffer (i64.const 131072))
+                (i64.store (i32.const 131072)
+                    (i64.sub (i64.load (i32.const 131072)) (i64.const {amount})))
+                (call $write
+                    (i64.const 65536) (i64.const {source_len})
+                    (i64.const 131072) (i64.const 32))
+                (drop (call $read (i64.const 196608) (i64.const {target_len})))
+                (call $result_buffer (i64.const 262144))
+                (i64.store (i32.const 262144)
+                    (i64.add (i64.load (i32.const 262144)) (i64.const {amount})))
+                (call $write
+                    (i64.const 196608) (i64.const {target_len})
+                    (i64.const 262144) (i64.const 32)))
+
+                (memory (;0;) 16)
+                (data (i32.const 65536) "{source}")
+                (data (i32.const 196608) "{target}")
+                (export "memory" (memory 0))
+                (export "_apply_tx" (func $_apply_tx)))
+            "#,
+            source_len = source.len(),
+            target_len = target.len(),
+        ))
+        .expect("unexpected error converting wat2wasm")
+    }
+
+    /// Initialize the storage read by the test txs: the keys under the
+    /// `parallel` and `iterated` prefixes and the test accounts, which use
+    /// the `vp_always_true` VP and hold some native tokens.
+    fn init_test_storage(shell: &mut TestShell) {
+        for name in ["a", "b", "c"] {
+            let key = Key::parse(format!("parallel/{name}")).unwrap();
+            shell
+                .wl_storage
+                .write(&key, "0".to_string())
+                .expect("Test failed");
+        }
+        for name in ["x", "y"] {
+            let key = Key::parse(format!("iterated/{name}")).unwrap();
+            shell
+                .wl_storage
+                .write(&key, "0".to_string())
+                .expect("Test failed");
+        }
+
+        let vp_code = TestWasms::VpAlwaysTrue.read_bytes();
+        let vp_hash = Hash::sha256(&vp_code);
+        shell
+            .wl_storage
+            .write(&Key::wasm_code_len(&vp_hash), vp_code.len() as u64)
+            .expect("Test failed");
+        shell
+            .wl_storage
+            .write_bytes(&Key::wasm_code(&vp_hash), vp_code)
+            .expect("Test failed");
+        let token = shell.wl_storage.storage.native_token.clone();
+        for account in [
+            established_address_1(),
+            established_address_2(),
+            established_address_3(),
+            established_address_4(),
+        ] {
+            shell
+                .wl_storage
+                .write(&Key::validity_predicate(&account), vp_hash)
+                .expect("Test failed");
+            shell
+                .wl_storage
+                .write(
+                    &token::balance_key(&token, &account),
+                    token::Amount::from(INITIAL_BALANCE),
+                )
+                .expect("Test failed");
+        }
+    }
+
+    /// Finalize and commit a block of the given txs, either in parallel or
+    /// sequentially. Returns the state roots before and after the block, the
+    /// codes and gas of the txs and the number of adopted parallel txs.
+    fn finalize_txs(
+        parallel: bool,
+        test_txs: &[TestTx],
+    ) -> ([u8; 32], [u8; 32], Vec<(String, String)>, u64) {
+        let (mut shell, _, _, _) = setup();
+        shell.parallel_tx_execution = parallel;
+        init_test_storage(&mut shell);
+        let root_before = shell.wl_storage.storage.merkle_root().0;
+        let keypair = keypair_1();
+        let txs = test_txs
+            .iter()
+            .map(|test_tx| mk_test_tx(&mut shell, &keypair, test_tx))
+            .collect();
+        let events = shell
+            .finalize_block(FinalizeBlock {
+                txs,
+                ..Default::default()
+            })
+            .expect("Test failed")
+            .iter()
+            .map(|event| {
+                (
+                    event.attributes["code"].clone(),
+                    event.attributes["gas_used"].clone(),
+                )
+            })
+            .collect();
+        let adopted = shell.adopted_parallel_txs;
+        shell.commit();
+        let root_after = shell.wl_storage.storage.merkle_root().0;
+        (root_before, root_after, events, adopted)
+    }
+
+    /// Differential test of the parallel execution against the sequential
+    /// execution of the same blocks, with and without conflicting txs.
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let key = |name: &str| Key::parse(format!("parallel/{name}")).unwrap();
+        let prefix = |name: &str| Key::parse(name).unwrap();
+        let (alice, bob, carol, dave) = (
+            established_address_1(),
+            established_address_2(),
+            established_address_3(),
+            established_address_4(),
+        );
+        // The txs of each block with the number of txs whose parallel
+        // execution is expected to be adopted
+        let blocks: Vec<(Vec<TestTx>, u64)> = vec![
+            // No conflicts
+            (
+                vec![
+                    TestTx::Write(key("a"), "1"),
+                    TestTx::Write(key("b"), "2"),
+                    TestTx::Read(key("c")),
+                    TestTx::Iter(prefix("iterated"), key("count")),
+                    TestTx::Transfer(alice.clone(), bob.clone(), 10),
+                    TestTx::Transfer(carol.clone(), dave.clone(), 20),
+                ],
+                6,
+            ),
+            // Every tx conflicts with the previous one
+            (
+                vec![
+                    TestTx::Write(key("a"), "1"),
+                    TestTx::Write(key("a"), "22"),
+                    TestTx::Read(key("a")),
+                ],
+                1,
+            ),
+            // Some conflicts
+            (
+                vec![
+                    TestTx::Write(key("a"), "1"),
+                    TestTx::Read(key("a")),
+                    TestTx::Iter(prefix("parallel"), key("count")),
+                    TestTx::Transfer(alice.clone(), bob.clone(), 10),
+                    TestTx::Transfer(bob, carol.clone(), 5),
+                    TestTx::Transfer(carol, dave, 1),
+                    TestTx::Write(key("b"), "55"),
+                    TestTx::Transfer(alice, established_address_4(), 1),
+                ],
+                3,
+            ),
+        ];
+        for (txs, expected_adopted) in blocks {
+            let sequential = finalize_txs(false, &txs);
+            let parallel = finalize_txs(true, &txs);
+            assert_eq!(sequential.0, parallel.0);
+            assert_eq!(sequential.1, parallel.1);
+            assert_eq!(sequential.2, parallel.2);
+            assert_ne!(sequential.0, sequential.1);
+            assert!(
+                sequential
+                    .2
+                    .iter()
+                    .all(|(code, _)| *code == String::from(ErrorCodes::Ok))
+            );
+            assert_eq!(sequential.3, 0);
+            assert_eq!(parallel.3, expected_adopted);
+        }
+    }
+
+    /// Test that a tx that read a key written by a preceding tx in the
+    /// block is not adopted.
+    #[test]
+    fn test_conflicting_tx_not_adopted() {
+        let (mut shell, _, _, _) = setup();
+        let keypair = keypair_1();
+        let key = Key::parse("parallel/a").unwrap();
+        let txs: Vec<_> = ["1", "22"]
+            .iter()
+            .map(|value| mk_write_tx(&mut shell, &keypair, &key, value))
+            .collect();
+        let mut speculative = execute_decrypted_txs(&shell, &txs);
+        assert_eq!(speculative.txs.len(), 2);
+        let wrapper_hash = |index: usize| {
+            shell
+                .wl_storage
+                .storage
+                .tx_queue
+                .get(index)
+                .unwrap()
+                .tx
+                .clone()
+                .update_header(TxType::Raw)
+                .header_hash()
+        };
+        let (first, second) = (wrapper_hash(0), wrapper_hash(1));
+
+        // The first tx doesn't conflict with anything
+        let (result, _) = speculative
+            .adopt(0, &first, &mut shell.wl_storage.write_log)
+            .expect("The first tx must be adopted");
+        assert!(result.expect("Test failed").is_accepted());
+        shell.wl_storage.commit_tx();
+
+        // The second one read the key written by the first one
+        assert!(
+            speculative
+                .adopt(1, &second, &mut shell.wl_storage.write_log)
+                .is_none()
+        );
+    }
+}
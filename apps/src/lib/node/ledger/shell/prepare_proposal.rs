@@ -9,6 +9,7 @@ use namada::ledger::storage::{DBIter, StorageHasher, TempWlStorage, DB};
 use namada::proof_of_stake::find_validator_by_raw_hash;
 use namada::proto::{Section, Tx};
 use namada::types::address::Address;
+use namada::types::hash;
 use namada::types::internal::TxInQueue;
 use namada::types::key::tm_raw_hash_to_string;
 use namada::types::time::DateTimeUtc;
@@ -56,6 +57,8 @@ where
         req: RequestPrepareProposal,
     ) -> response::PrepareProposal {
         let txs = if let ShellMode::Validator { .. } = self.mode {
+            self.alloc_failures.borrow_mut().begin_proposal();
+
             // start counting allotted space for txs
             let alloc = self.get_encrypted_txs_allocator();
 
@@ -174,37 +177,38 @@ where
                     }
                 }
             })
-            .take_while(|(tx_bytes, tx_gas)| {
-                alloc.try_alloc(BlockResources::new(&tx_bytes[..], tx_gas.to_owned()))
-                    .map_or_else(
-                        |status| match status {
-                            AllocFailure::Rejected { bin_resource_left} => {
-                                tracing::debug!(
-                                    ?tx_bytes,
-                                    bin_resource_left,
-                                    proposal_height =
-                                        ?pos_queries.get_current_decision_height(),
-                                    "Dropping encrypted tx from the current proposal",
-                                );
-                                false
-                            }
-                            AllocFailure::OverflowsBin { bin_resource} => {
-                                // TODO: handle tx whose size is greater
-                                // than bin size
-                                tracing::warn!(
-                                    ?tx_bytes,
-                                    bin_resource,
-                                    proposal_height =
-                                        ?pos_queries.get_current_decision_height(),
-                                    "Dropping large encrypted tx from the current proposal",
-                                );
-                                true
-                            }
-                        },
-                        |()| true,
-                    )
+            .map_while(|(tx_bytes, tx_gas)| {
+                match alloc.try_alloc(BlockResources::new(&tx_bytes[..], tx_gas)) {
+                    Ok(()) => Some(Some(tx_bytes)),
+                    Err(AllocFailure::Rejected { bin_resource_left}) => {
+                        tracing::debug!(
+                            ?tx_bytes,
+                            bin_resource_left,
+                            proposal_height =
+                                ?pos_queries.get_current_decision_height(),
+                            "Dropping encrypted tx from the current proposal",
+                        );
+                        None
+                    }
+                    Err(AllocFailure::OverflowsBin { bin_resource}) => {
+                        // this tx will never fit in this bin, so we
+                        // skip it, and keep track of it, in order to
+                        // evict it from the mempool
+                        tracing::warn!(
+                            ?tx_bytes,
+                            bin_resource,
+                            proposal_height =
+                                ?pos_queries.get_current_decision_height(),
+                            "Dropping large encrypted tx from the current proposal",
+                        );
+                        self.alloc_failures
+                            .borrow_mut()
+                            .record_failure(hash::Hash::sha256(&tx_bytes));
+                        Some(None)
+                    }
+                }
             })
-            .map(|(tx, _)| tx)
+            .flatten()
             .collect();
         let alloc = alloc.next_state();
 
@@ -448,44 +452,44 @@ where
         let deserialized_iter = self.deserialize_vote_extensions(txs);
         let pos_queries = self.wl_storage.pos_queries();
 
-        deserialized_iter.take_while(|tx_bytes|
-            alloc.try_alloc(&tx_bytes[..])
-                .map_or_else(
-                    |status| match status {
-                        AllocFailure::Rejected { bin_resource_left} => {
-                            // TODO: maybe we should find a way to include
-                            // validator set updates all the time. for instance,
-                            // we could have recursive bins -> bin space within
-                            // a bin is partitioned into yet more bins. so, we
-                            // could have, say, 2/3 of the bin space available
-                            // for eth events, and 1/3 available for valset
-                            // upds. to be determined, as we implement CheckTx
-                            // changes (issue #367)
-                            tracing::debug!(
-                                ?tx_bytes,
-                                bin_resource_left,
-                                proposal_height =
-                                    ?pos_queries.get_current_decision_height(),
-                                "Dropping protocol tx from the current proposal",
-                            );
-                            false
-                        }
-                        AllocFailure::OverflowsBin { bin_resource} => {
-                            // TODO: handle tx whose size is greater
-                            // than bin size
-                            tracing::warn!(
-                                ?tx_bytes,
-                                bin_resource,
-                                proposal_height =
-                                    ?pos_queries.get_current_decision_height(),
-                                "Dropping large protocol tx from the current proposal",
-                            );
-                            true
-                        }
-                    },
-                    |()| true,
-                )
+        deserialized_iter.map_while(|tx_bytes|
+            match alloc.try_alloc(&tx_bytes[..]) {
+                Ok(()) => Some(Some(tx_bytes)),
+                Err(AllocFailure::Rejected { bin_resource_left}) => {
+                    // TODO: maybe we should find a way to include
+                    // validator set updates all the time. for instance,
+                    // we could have recursive bins -> bin space within
+                    // a bin is partitioned into yet more bins. so, we
+                    // could have, say, 2/3 of the bin space available
+                    // for eth events, and 1/3 available for valset
+                    // upds. to be determined, as we implement CheckTx
+                    // changes (issue #367)
+                    tracing::debug!(
+                        ?tx_bytes,
+                        bin_resource_left,
+                        proposal_height =
+                            ?pos_queries.get_current_decision_height(),
+                        "Dropping protocol tx from the current proposal",
+                    );
+                    None
+                }
+                Err(AllocFailure::OverflowsBin { bin_resource}) => {
+                    // this tx will never fit in this bin, so we
+                    // skip it. it was built from vote extensions
+                    // rather than taken from the mempool, so there
+                    // is nothing to evict
+                    tracing::warn!(
+                        ?tx_bytes,
+                        bin_resource,
+                        proposal_height =
+                            ?pos_queries.get_current_decision_height(),
+                        "Dropping large protocol tx from the current proposal",
+                    );
+                    Some(None)
+                }
+            }
         )
+        .flatten()
         .collect()
     }
 }
@@ -866,6 +866,16 @@ where
                     }
                 }
 
+                // Scheduled txs can only be executed by the protocol
+                if tx.schedule_target().is_some() {
+                    return TxResult {
+                        code: ErrorCodes::InvalidTx.into(),
+                        info: "Scheduled txs can only be executed at their \
+                               target height or epoch"
+                            .into(),
+                    };
+                }
+
                 // validate the ciphertext via Ferveo
                 if !tx.validate_ciphertext() {
                     TxResult {
@@ -1012,6 +1022,7 @@ mod test_process_proposal {
     use namada::types::token;
     use namada::types::token::Amount;
     use namada::types::transaction::protocol::EthereumTxData;
+    use namada::types::transaction::scheduled::ScheduleTarget;
     use namada::types::transaction::{Fee, WrapperTx};
     #[cfg(feature = "abcipp")]
     use namada::types::vote_extensions::bridge_pool_roots::MultiSignedVext;
@@ -2585,6 +2596,50 @@ mod test_process_proposal {
         }
     }
 
+    /// Test that a wrapper transaction marked for scheduled execution causes
+    /// a block rejection
+    #[test]
+    fn test_scheduled_wrapper() {
+        let (shell, _recv, _, _) = test_utils::setup();
+        let keypair = crate::wallet::defaults::daewon_keypair();
+
+        let mut wrapper =
+            Tx::from_type(TxType::Wrapper(Box::new(WrapperTx::new(
+                Fee {
+                    amount_per_gas_unit: 1.into(),
+                    token: shell.wl_storage.storage.native_token.clone(),
+                },
+                keypair.ref_to(),
+                Epoch(0),
+                GAS_LIMIT_MULTIPLIER.into(),
+                #[cfg(not(feature = "mainnet"))]
+                None,
+                None,
+            ))));
+        wrapper.header.chain_id = shell.chain_id.clone();
+        wrapper.set_code(Code::new("wasm_code".as_bytes().to_owned()));
+        wrapper.set_data(Data::new("transaction data".as_bytes().to_owned()));
+        wrapper.set_schedule(ScheduleTarget::Height(BlockHeight(100)));
+        wrapper.add_section(Section::Signature(Signature::new(
+            wrapper.sechashes(),
+            &keypair,
+        )));
+
+        // Run validation
+        let request = ProcessProposal {
+            txs: vec![wrapper.to_bytes()],
+        };
+        match shell.process_proposal(request) {
+            Ok(_) => panic!("Test failed"),
+            Err(TestError::RejectProposal(response)) => {
+                assert_eq!(
+                    response[0].result.code,
+                    u32::from(ErrorCodes::InvalidTx)
+                );
+            }
+        }
+    }
+
     /// Test that an expired decrypted transaction is correctly marked as so
     /// without rejecting the entire block
     #[test]
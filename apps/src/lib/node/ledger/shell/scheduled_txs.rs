@@ -0,0 +1,235 @@
+//! Execution of the transactions scheduled for the current block height or
+//! epoch
+
+use namada::core::ledger::scheduled_txs::{
+    get_scheduled_gas_key, ADDRESS as scheduled_txs_address,
+};
+use namada::ledger::gas::GasMetering;
+use namada::ledger::storage::{DBIter, StorageHasher, DB};
+use namada::ledger::storage_api::{self, scheduled_txs, token, StorageWrite};
+use namada::types::address::Address;
+use namada::types::transaction::scheduled::{ScheduleTarget, ScheduledTx};
+
+use super::*;
+
+/// Execute the transactions that were scheduled for the given target. The
+/// escrowed fee of each scheduled tx is paid to the block proposer and the
+/// hash of the inner tx is written to the replay protection storage, even if
+/// the execution fails. Txs that cannot be executed because they have
+/// already been applied, have expired, carry a wrong chain id or are not
+/// marked for execution at the given target are dropped and their fee is
+/// refunded to the owner. Each scheduled tx is given the next index after
+/// the given one. A scheduled tx that fails on a storage error is skipped
+/// rather than halting the chain.
+pub fn execute_scheduled_txs<D, H>(
+    shell: &mut Shell<D, H>,
+    response: &mut shim::response::FinalizeBlock,
+    target: ScheduleTarget,
+    block_proposer: &Address,
+    tx_index: &mut TxIndex,
+) where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    let scheduled =
+        match scheduled_txs::get_scheduled_txs(&shell.wl_storage, &target) {
+            Ok(scheduled) => scheduled,
+            Err(err) => {
+                tracing::error!(
+                    "Failed to read the txs scheduled at {}, skipping them: {}",
+                    target,
+                    err
+                );
+                return;
+            }
+        };
+    if scheduled.is_empty() {
+        return;
+    }
+    match shell.wl_storage.delete(&get_scheduled_gas_key(&target)) {
+        Ok(()) => shell.wl_storage.commit_tx(),
+        Err(err) => {
+            shell.wl_storage.drop_tx();
+            tracing::error!(
+                "Failed to delete the gas of the txs scheduled at {}: {}",
+                target,
+                err
+            );
+        }
+    }
+    for (key, scheduled_tx) in scheduled {
+        let index = *tx_index;
+        *tx_index = *tx_index + 1;
+        if let Err(err) = execute_scheduled_tx(
+            shell,
+            response,
+            &key,
+            &scheduled_tx,
+            target,
+            index,
+            block_proposer,
+        ) {
+            shell.wl_storage.drop_tx();
+            tracing::error!(
+                "Failed to execute the scheduled tx stored at {}, skipping \
+                 it: {}",
+                key,
+                err
+            );
+            // Nothing of this tx has been committed, so we try to give the
+            // escrowed fee back to its owner
+            match scheduled_txs::refund_escrow(
+                &mut shell.wl_storage,
+                &scheduled_tx,
+            )
+            .and_then(|()| shell.wl_storage.delete(&key))
+            {
+                Ok(()) => shell.wl_storage.commit_tx(),
+                Err(err) => {
+                    shell.wl_storage.drop_tx();
+                    tracing::error!(
+                        "Failed to refund the escrow of the scheduled tx \
+                         stored at {}: {}",
+                        key,
+                        err
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Execute a single scheduled tx. The changes are only committed once all the
+/// fallible storage operations have succeeded, so the caller can drop them on
+/// error.
+fn execute_scheduled_tx<D, H>(
+    shell: &mut Shell<D, H>,
+    response: &mut shim::response::FinalizeBlock,
+    key: &Key,
+    scheduled_tx: &ScheduledTx,
+    target: ScheduleTarget,
+    tx_index: TxIndex,
+    block_proposer: &Address,
+) -> storage_api::Result<()>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    let height = shell.wl_storage.storage.block.height;
+    let block_time = shell
+        .wl_storage
+        .storage
+        .header
+        .as_ref()
+        .map(|header| header.time);
+
+    shell.wl_storage.delete(key)?;
+
+    let tx_hash = scheduled_tx.payload_hash();
+    let tx_hash_key = replay_protection::get_replay_protection_key(&tx_hash);
+    let mut tx = scheduled_tx.tx.clone();
+    tx.update_header(TxType::Decrypted(DecryptedTx::Decrypted {
+        #[cfg(not(feature = "mainnet"))]
+        has_valid_pow: false,
+    }));
+    let mut tx_event = Event::new_tx_event(&tx, height.0);
+
+    let rejection = if shell.wl_storage.has_key(&tx_hash_key)? {
+        Some((
+            ErrorCodes::ReplayTx,
+            "Scheduled tx has already been applied".to_string(),
+        ))
+    } else if tx.schedule_target() != Some(target) {
+        Some((
+            ErrorCodes::InvalidTx,
+            format!("Scheduled tx is not marked for execution at {}", target),
+        ))
+    } else if tx.header.chain_id != shell.chain_id {
+        Some((
+            ErrorCodes::InvalidChainId,
+            format!(
+                "Scheduled tx carries a wrong chain id: expected {}, \
+                 found {}",
+                shell.chain_id, tx.header.chain_id
+            ),
+        ))
+    } else {
+        match (block_time, tx.header.expiration) {
+            (Some(block_time), Some(exp)) if block_time > exp => Some((
+                ErrorCodes::ExpiredTx,
+                format!(
+                    "Scheduled tx expired at {:#?}, block time: {:#?}",
+                    exp, block_time
+                ),
+            )),
+            _ => None,
+        }
+    };
+
+    if let Some((code, info)) = rejection {
+        scheduled_txs::refund_escrow(&mut shell.wl_storage, scheduled_tx)?;
+        shell.wl_storage.commit_tx();
+        tracing::info!("Scheduled tx {} was not executed: {}", tx_hash, info);
+        tx_event["code"] = code.into();
+        tx_event["info"] = info;
+        tx_event["gas_used"] = "0".into();
+        response.events.push(tx_event);
+        return Ok(());
+    }
+
+    // Pay the fee and protect against replays before executing, these
+    // changes must be persisted even in case of failure
+    token::transfer(
+        &mut shell.wl_storage,
+        &scheduled_tx.fee.token,
+        &scheduled_txs_address,
+        block_proposer,
+        scheduled_tx.escrow().unwrap_or_default(),
+    )?;
+    shell.wl_storage.write(&tx_hash_key, ())?;
+    shell.wl_storage.commit_tx();
+
+    let mut tx_gas_meter = TxGasMeter::new(scheduled_tx.gas_limit);
+    match protocol::dispatch_tx(
+        tx,
+        &[], /*  this is used to compute the fee
+              * based on the code size. The fee
+              * was escrowed when scheduling. */
+        tx_index,
+        &mut tx_gas_meter,
+        &mut shell.wl_storage,
+        &mut shell.vp_wasm_cache,
+        &mut shell.tx_wasm_cache,
+        None,
+        #[cfg(not(feature = "mainnet"))]
+        false,
+    ) {
+        Ok(result) => {
+            if result.is_accepted() {
+                shell.wl_storage.commit_tx();
+                tx_event["code"] = ErrorCodes::Ok.into();
+            } else {
+                shell.wl_storage.drop_tx();
+                tx_event["code"] = ErrorCodes::InvalidTx.into();
+            }
+            tracing::info!(
+                "Scheduled tx {} has been executed: {}",
+                tx_hash,
+                result
+            );
+            tx_event["gas_used"] = result.gas_used.to_string();
+            tx_event["info"] = result.to_string();
+        }
+        Err(msg) => {
+            shell.wl_storage.drop_tx();
+            tracing::info!("Scheduled tx {} failed with: {}", tx_hash, msg);
+            tx_event["code"] = ErrorCodes::WasmRuntimeError.into();
+            tx_event["gas_used"] =
+                tx_gas_meter.get_tx_consumed_gas().to_string();
+            tx_event["info"] = msg.to_string();
+        }
+    }
+    response.events.push(tx_event);
+
+    Ok(())
+}
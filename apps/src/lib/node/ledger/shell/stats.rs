@@ -10,6 +10,7 @@ pub struct InternalStats {
     tx_cache_size: (usize, usize),
     tx_executed: HashMap<String, u64>,
     wrapper_txs: u64,
+    adopted_txs: u64,
 }
 
 impl InternalStats {
@@ -55,6 +56,14 @@ impl InternalStats {
     pub fn increment_wrapper_txs(&mut self) {
         self.wrapper_txs += 1;
     }
+
+    pub fn increment_adopted_txs(&mut self) {
+        self.adopted_txs += 1;
+    }
+
+    pub fn adopted_txs(&self) -> u64 {
+        self.adopted_txs
+    }
 }
 
 impl Display for InternalStats {
@@ -62,13 +71,15 @@ impl Display for InternalStats {
         write!(
             f,
             "Applied {} transactions. Wrappers: {}, successful inner txs: {}, \
-             rejected inner txs: {}, errored inner txs: {}, vp cache size: {} \
-             - {}, tx cache size {} - {}",
+             rejected inner txs: {}, errored inner txs: {}, inner txs \
+             adopted from parallel execution: {}, vp cache size: {} - {}, tx \
+             cache size {} - {}",
             self.successful_tx + self.rejected_txs + self.errored_txs,
             self.wrapper_txs,
             self.successful_tx,
             self.rejected_txs,
             self.errored_txs,
+            self.adopted_txs,
             self.vp_cache_size.0,
             self.vp_cache_size.1,
             self.tx_cache_size.0,
@@ -195,7 +195,11 @@ pub fn gen_validator_keys(
 
 #[cfg(all(test, feature = "dev"))]
 mod test_wallet {
+    use masp_primitives::zip32;
+    use namada::ledger::wallet::Wallet;
     use namada::types::address::Address;
+    use namada::types::masp::{ExtendedSpendingKey, ExtendedViewingKey};
+    use namada::types::storage::BlockHeight;
 
     use super::*;
 
@@ -224,4 +228,64 @@ mod test_wallet {
         let data = store.encode();
         let _ = Store::decode(data).expect("Test failed");
     }
+
+    #[test]
+    fn test_key_birthdays() {
+        let mut wallet = Wallet::<CliWalletUtils>::new(PathBuf::new(), new());
+        let spend_key = zip32::ExtendedSpendingKey::master(&[1; 32]);
+        let spend_vk = zip32::ExtendedFullViewingKey::from(&spend_key);
+        let view_vk = zip32::ExtendedFullViewingKey::from(
+            &zip32::ExtendedSpendingKey::master(&[2; 32]),
+        );
+        wallet
+            .encrypt_insert_spending_key(
+                "spend".to_string(),
+                ExtendedSpendingKey::from(spend_key),
+                None,
+                true,
+            )
+            .unwrap();
+        wallet
+            .insert_viewing_key(
+                "view".to_string(),
+                ExtendedViewingKey::from(view_vk),
+                true,
+            )
+            .unwrap();
+        wallet
+            .insert_payment_addr(
+                "pay".to_string(),
+                view_vk.default_address().1.into(),
+                true,
+            )
+            .unwrap();
+
+        // Both spending and viewing keys can be given a birthday
+        wallet.set_birthday("spend", BlockHeight(10)).unwrap();
+        wallet.set_birthday("view", BlockHeight(20)).unwrap();
+        assert!(wallet.set_birthday("pay", BlockHeight(30)).is_err());
+        assert!(wallet.set_birthday("unknown", BlockHeight(30)).is_err());
+        assert_eq!(wallet.find_birthday("spend"), Some(BlockHeight(10)));
+        assert_eq!(wallet.find_birthday("view"), Some(BlockHeight(20)));
+        assert_eq!(wallet.find_birthday("pay"), None);
+        let birthdays = wallet.get_viewing_key_birthdays();
+        assert_eq!(birthdays.len(), 2);
+        assert_eq!(birthdays[&spend_vk.fvk.vk], BlockHeight(10));
+        assert_eq!(birthdays[&view_vk.fvk.vk], BlockHeight(20));
+
+        // The birthdays are persisted
+        let store = Store::decode(wallet.store().encode()).unwrap();
+        assert_eq!(store.find_birthday("spend"), Some(&BlockHeight(10)));
+        assert_eq!(store.find_birthday("view"), Some(&BlockHeight(20)));
+
+        // Replacing a key forgets its birthday
+        wallet
+            .insert_viewing_key(
+                "view".to_string(),
+                ExtendedViewingKey::from(spend_vk),
+                true,
+            )
+            .unwrap();
+        assert_eq!(wallet.find_birthday("view"), None);
+    }
 }
@@ -736,6 +736,7 @@ impl BenchShieldedCtx {
         let mock_args = TxArgs {
             dry_run: false,
             dry_run_wrapper: false,
+            gas_report: false,
             dump_tx: false,
             force: false,
             broadcast_only: false,
@@ -754,6 +755,7 @@ impl BenchShieldedCtx {
             tx_reveal_code_path: TX_REVEAL_PK_WASM.into(),
             verification_key: None,
             password: None,
+            signer: Default::default(),
             wrapper_fee_payer: None,
             output_folder: None,
         };
@@ -784,7 +786,11 @@ impl BenchShieldedCtx {
             ))
             .unwrap();
         let shielded = async_runtime
-            .block_on(self.shielded.gen_shielded_transfer(&self.shell, args))
+            .block_on(self.shielded.gen_shielded_transfer(
+                &self.shell,
+                args,
+                None,
+            ))
             .unwrap()
             .map(
                 |ShieldedTransfer {
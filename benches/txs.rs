@@ -326,6 +326,8 @@ fn update_vp(c: &mut Criterion) {
         )),
         public_keys: vec![defaults::albert_keypair().ref_to()],
         threshold: None,
+        policy: None,
+        guardians: None,
     };
     let vp = generate_tx(
         TX_UPDATE_ACCOUNT_WASM,
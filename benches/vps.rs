@@ -87,6 +87,8 @@ fn vp_user(c: &mut Criterion) {
         )),
         public_keys: vec![defaults::albert_keypair().to_public()],
         threshold: None,
+        policy: None,
+        guardians: None,
     };
     let vp = generate_tx(
         TX_UPDATE_ACCOUNT_WASM,
@@ -371,6 +373,8 @@ fn vp_validator(c: &mut Criterion) {
         )),
         public_keys: vec![defaults::validator_keypair().to_public()],
         threshold: None,
+        policy: None,
+        guardians: None,
     };
     let vp = generate_tx(
         TX_UPDATE_ACCOUNT_WASM,
@@ -1,6 +1,7 @@
 //! Gas accounting module to track the gas usage in a block for transactions and
 //! validity predicates triggered by transactions.
 
+use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::ops::Div;
 
@@ -9,6 +10,8 @@ use thiserror::Error;
 
 use super::parameters;
 use super::storage_api::{self, StorageRead};
+use crate::types::address::Address;
+use crate::types::storage::Key;
 use crate::types::transaction::wrapper::GasLimit;
 
 #[allow(missing_docs)]
@@ -32,6 +35,8 @@ const PARALLEL_GAS_DIVIDER: u64 = 10;
 pub const STORAGE_ACCESS_GAS_PER_BYTE: u64 = 1;
 /// The cost of writing to storage, per byte
 pub const STORAGE_WRITE_GAS_PER_BYTE: u64 = 100;
+/// The cost of emitting a custom event from a transaction, per byte
+pub const TX_EVENT_GAS_PER_BYTE: u64 = 10;
 /// The cost of verifying a signle signature of a transaction
 pub const VERIFY_TX_SIG_GAS_COST: u64 = 10;
 /// The cost of accessing the WASM memory, per byte
@@ -172,6 +177,9 @@ pub trait GasMetering {
 
     /// Get the gas limit
     fn get_gas_limit(&self) -> Gas;
+
+    /// Get the gas profile, if profiling is enabled
+    fn gas_profile(&mut self) -> Option<&mut GasProfile>;
 }
 
 /// Gas metering in a transaction
@@ -180,6 +188,8 @@ pub struct TxGasMeter {
     /// The gas limit for a transaction
     pub tx_gas_limit: Gas,
     transaction_gas: Gas,
+    /// The breakdown of the consumed gas, only recorded in dry-runs
+    profile: Option<GasProfile>,
 }
 
 /// Gas metering in a validity predicate
@@ -191,6 +201,106 @@ pub struct VpGasMeter {
     initial_gas: Gas,
     /// The current gas usage in the VP
     current_gas: Gas,
+    /// The breakdown of the consumed gas, only recorded in dry-runs
+    profile: Option<GasProfile>,
+}
+
+/// A breakdown of the gas consumed by a transaction, used to report where the
+/// gas goes when dry-running a transaction. The gas of nested host functions
+/// calls (e.g. `namada_tx_call`) includes the gas of the host functions they
+/// call.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+pub struct GasProfile {
+    /// The gas consumed by each host function, including the wasm
+    /// instructions metered with the `gas` host function
+    pub host_fns: BTreeMap<String, Gas>,
+    /// The gas consumed by the validity predicate of each verifier
+    pub vps: BTreeMap<Address, Gas>,
+    /// The gas consumed by reading each storage key
+    pub storage_reads: BTreeMap<Key, Gas>,
+    /// The gas consumed by writing or deleting each storage key
+    pub storage_writes: BTreeMap<Key, Gas>,
+}
+
+impl GasProfile {
+    /// Record the gas consumed by a host function call
+    pub fn record_host_fn(&mut self, name: &str, gas: Gas) {
+        add_entry(&mut self.host_fns, name.to_owned(), gas)
+    }
+
+    /// Record the gas consumed by the validity predicate of a verifier
+    pub fn record_vp(&mut self, addr: &Address, gas: Gas) {
+        add_entry(&mut self.vps, addr.clone(), gas)
+    }
+
+    /// Record the gas consumed by reading a storage key
+    pub fn record_storage_read(&mut self, key: &Key, gas: u64) {
+        add_entry(&mut self.storage_reads, key.clone(), gas.into())
+    }
+
+    /// Record the gas consumed by writing or deleting a storage key
+    pub fn record_storage_write(&mut self, key: &Key, gas: u64) {
+        add_entry(&mut self.storage_writes, key.clone(), gas.into())
+    }
+
+    /// Add the entries of another profile into this one
+    pub fn merge(&mut self, other: GasProfile) {
+        for (name, gas) in other.host_fns {
+            add_entry(&mut self.host_fns, name, gas);
+        }
+        for (addr, gas) in other.vps {
+            add_entry(&mut self.vps, addr, gas);
+        }
+        for (key, gas) in other.storage_reads {
+            add_entry(&mut self.storage_reads, key, gas);
+        }
+        for (key, gas) in other.storage_writes {
+            add_entry(&mut self.storage_writes, key, gas);
+        }
+    }
+}
+
+impl Display for GasProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Gas report (in gas units, sorted by gas used):")?;
+        fmt_entries(f, "Host functions", &self.host_fns)?;
+        fmt_entries(f, "Validity predicates", &self.vps)?;
+        fmt_entries(f, "Storage reads", &self.storage_reads)?;
+        fmt_entries(f, "Storage writes", &self.storage_writes)
+    }
+}
+
+/// Format a section of a gas profile, with the most expensive entries first
+fn fmt_entries<K: Display>(
+    f: &mut std::fmt::Formatter<'_>,
+    title: &str,
+    entries: &BTreeMap<K, Gas>,
+) -> std::fmt::Result {
+    writeln!(f, "  {}:", title)?;
+    if entries.is_empty() {
+        return writeln!(f, "    none");
+    }
+    let mut entries: Vec<_> = entries.iter().collect();
+    entries.sort_by(|(_, a), (_, b)| b.sub.cmp(&a.sub));
+    for (key, gas) in entries {
+        writeln!(f, "    {}: {}", key, gas)?;
+    }
+    Ok(())
+}
+
+/// Add gas to an entry of a gas profile, saturating on overflow as the profile
+/// is only informative
+fn add_entry<K: Ord>(entries: &mut BTreeMap<K, Gas>, key: K, gas: Gas) {
+    let entry = entries.entry(key).or_default();
+    *entry = entry.checked_add(gas).unwrap_or(Gas { sub: u64::MAX });
 }
 
 /// Gas meter for VPs parallel runs
@@ -223,6 +333,10 @@ impl GasMetering for TxGasMeter {
     fn get_gas_limit(&self) -> Gas {
         self.tx_gas_limit
     }
+
+    fn gas_profile(&mut self) -> Option<&mut GasProfile> {
+        self.profile.as_mut()
+    }
 }
 
 impl TxGasMeter {
@@ -232,6 +346,7 @@ impl TxGasMeter {
         Self {
             tx_gas_limit: tx_gas_limit.into(),
             transaction_gas: Gas::default(),
+            profile: None,
         }
     }
 
@@ -241,9 +356,22 @@ impl TxGasMeter {
         Self {
             tx_gas_limit,
             transaction_gas: Gas::default(),
+            profile: None,
         }
     }
 
+    /// Start recording a breakdown of the consumed gas. This is only meant
+    /// for dry-runs.
+    pub fn enable_profiling(&mut self) {
+        self.profile.get_or_insert_with(GasProfile::default);
+    }
+
+    /// Take the recorded gas profile out of the gas meter, if profiling is
+    /// enabled
+    pub fn take_gas_profile(&mut self) -> Option<GasProfile> {
+        self.profile.take()
+    }
+
     /// Add the gas for the space that the transaction requires in the block
     pub fn add_tx_size_gas(&mut self, tx_bytes: &[u8]) -> Result<()> {
         let bytes_len: u64 = tx_bytes
@@ -296,17 +424,37 @@ impl GasMetering for VpGasMeter {
     fn get_gas_limit(&self) -> Gas {
         self.tx_gas_limit
     }
+
+    fn gas_profile(&mut self) -> Option<&mut GasProfile> {
+        self.profile.as_mut()
+    }
 }
 
 impl VpGasMeter {
-    /// Initialize a new VP gas meter from the `TxGasMeter`
+    /// Initialize a new VP gas meter from the `TxGasMeter`. The gas is
+    /// profiled if it is profiled in the `TxGasMeter`.
     pub fn new_from_tx_meter(tx_gas_meter: &TxGasMeter) -> Self {
         Self {
             tx_gas_limit: tx_gas_meter.tx_gas_limit,
             initial_gas: tx_gas_meter.transaction_gas,
             current_gas: Gas::default(),
+            profile: tx_gas_meter
+                .profile
+                .as_ref()
+                .map(|_| GasProfile::default()),
         }
     }
+
+    /// Get the gas consumed by the VP alone
+    pub fn get_vp_consumed_gas(&self) -> Gas {
+        self.current_gas
+    }
+
+    /// Take the recorded gas profile out of the gas meter, if profiling is
+    /// enabled
+    pub fn take_gas_profile(&mut self) -> Option<GasProfile> {
+        self.profile.take()
+    }
 }
 
 impl VpsGas {
@@ -344,6 +492,19 @@ impl VpsGas {
         self.check_limit(tx_gas_meter)
     }
 
+    /// Add the gas of validity predicates run after the ones of this instance,
+    /// e.g. for the next step of a batch transaction. Contrary to
+    /// [`VpsGas::merge`], the gas of the two runs is summed in full.
+    pub fn accumulate(&mut self, other: &VpsGas) -> Result<()> {
+        let total = self
+            .get_current_gas()?
+            .checked_add(other.get_current_gas()?)
+            .ok_or(Error::GasOverflow)?;
+        self.max = Some(total);
+        self.rest.clear();
+        Ok(())
+    }
+
     fn check_limit(&self, gas_meter: &impl GasMetering) -> Result<()> {
         let total = gas_meter
             .get_tx_consumed_gas()
@@ -382,6 +543,7 @@ mod tests {
         let tx_gas_meter = TxGasMeter {
             tx_gas_limit: BLOCK_GAS_LIMIT.into(),
             transaction_gas: Gas::default(),
+            profile: None,
         };
             let mut meter = VpGasMeter::new_from_tx_meter(&tx_gas_meter);
             meter.consume(gas).expect("cannot add the gas");
@@ -394,6 +556,7 @@ mod tests {
         let tx_gas_meter = TxGasMeter {
             tx_gas_limit: BLOCK_GAS_LIMIT.into(),
             transaction_gas: (TX_GAS_LIMIT - 1).into(),
+            profile: None,
         };
         let mut meter = VpGasMeter::new_from_tx_meter(&tx_gas_meter);
         assert_matches!(
@@ -407,6 +570,7 @@ mod tests {
         let tx_gas_meter = TxGasMeter {
             tx_gas_limit: TX_GAS_LIMIT.into(),
             transaction_gas: (TX_GAS_LIMIT - 1).into(),
+            profile: None,
         };
         let mut meter = VpGasMeter::new_from_tx_meter(&tx_gas_meter);
         assert_matches!(
@@ -437,4 +601,65 @@ mod tests {
             Error::TransactionGasExceededError
         );
     }
+
+    #[test]
+    fn test_vps_gas_accumulate() {
+        let tx_gas_meter = TxGasMeter::new_from_sub_limit(TX_GAS_LIMIT.into());
+        let mut first = VpsGas::default();
+        let mut meter = VpGasMeter::new_from_tx_meter(&tx_gas_meter);
+        meter.consume(10).expect("cannot add the gas");
+        first.set(meter).expect("cannot set the gas");
+        let mut parallel = VpsGas::default();
+        let mut meter = VpGasMeter::new_from_tx_meter(&tx_gas_meter);
+        meter
+            .consume(PARALLEL_GAS_DIVIDER * 2)
+            .expect("cannot add the gas");
+        parallel.set(meter).expect("cannot set the gas");
+        first
+            .merge(&mut parallel, &tx_gas_meter)
+            .expect("cannot merge the gas");
+
+        let mut second = VpsGas::default();
+        let mut meter = VpGasMeter::new_from_tx_meter(&tx_gas_meter);
+        meter.consume(20).expect("cannot add the gas");
+        second.set(meter).expect("cannot set the gas");
+
+        let expected = first
+            .get_current_gas()
+            .unwrap()
+            .checked_add(20.into())
+            .unwrap();
+        first
+            .accumulate(&second)
+            .expect("cannot accumulate the gas");
+        assert_eq!(first.get_current_gas().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_gas_profile() {
+        let mut meter = TxGasMeter::new_from_sub_limit(TX_GAS_LIMIT.into());
+        assert!(meter.gas_profile().is_none());
+        assert!(VpGasMeter::new_from_tx_meter(&meter).profile.is_none());
+
+        meter.enable_profiling();
+        let key = Key::parse("key").unwrap();
+        let profile = meter.gas_profile().unwrap();
+        profile.record_host_fn("namada_tx_read", 10.into());
+        profile.record_host_fn("namada_tx_read", 5.into());
+        profile.record_storage_read(&key, 3);
+
+        let mut vp_meter = VpGasMeter::new_from_tx_meter(&meter);
+        let vp_profile = vp_meter.gas_profile().unwrap();
+        vp_profile.record_storage_read(&key, 2);
+        vp_profile.record_vp(&crate::types::address::nam(), 7.into());
+        let vp_profile = vp_meter.take_gas_profile().unwrap();
+        meter.gas_profile().unwrap().merge(vp_profile);
+
+        let profile = meter.take_gas_profile().unwrap();
+        assert_eq!(profile.host_fns["namada_tx_read"], 15.into());
+        assert_eq!(profile.storage_reads[&key], 5.into());
+        assert_eq!(profile.vps[&crate::types::address::nam()], 7.into());
+        assert!(profile.storage_writes.is_empty());
+        assert!(meter.gas_profile().is_none());
+    }
 }
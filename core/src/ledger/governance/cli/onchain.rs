@@ -277,9 +277,9 @@ impl PgfAction {
 )]
 pub struct PgfFunding {
     /// Pgf continous funding
-    pub continous: Vec<PgfFundingTarget>,
+    pub continous: Vec<PgfContinousFunding>,
     /// pgf retro fundings
-    pub retro: Vec<PgfFundingTarget>,
+    pub retro: Vec<PgfTarget>,
 }
 
 /// Pgf continous funding
@@ -287,8 +287,8 @@ pub struct PgfFunding {
     Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
 )]
 pub struct PgfContinous {
-    /// Pgf target
-    pub target: PgfFundingTarget,
+    /// Pgf funding
+    pub funding: PgfContinousFunding,
     /// Pgf action
     pub action: PgfAction,
 }
@@ -299,13 +299,53 @@ pub struct PgfContinous {
 )]
 pub struct PgfRetro {
     /// Pgf retro target
-    pub target: PgfFundingTarget,
+    pub target: PgfTarget,
+}
+
+/// Pgf continous funding schedule
+#[derive(
+    Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+pub struct PgfContinousFunding {
+    /// Pgf target
+    pub target: PgfTarget,
+    /// First epoch at which the target is paid (default: right away)
+    #[serde(default)]
+    pub start_epoch: Option<Epoch>,
+    /// Last epoch at which the target is paid (default: until removed)
+    #[serde(default)]
+    pub end_epoch: Option<Epoch>,
+    /// Maximum total amount paid to the target (default: no cap)
+    #[serde(default)]
+    pub cap: Option<token::Amount>,
 }
 
 /// Pgf Target
 #[derive(
     Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
 )]
+#[serde(untagged)]
+pub enum PgfTarget {
+    /// Target on this chain
+    Internal(PgfFundingTarget),
+    /// Target on another chain
+    Ibc(PgfIbcFundingTarget),
+}
+
+impl PgfTarget {
+    /// The amount of the payment
+    pub fn amount(&self) -> token::Amount {
+        match self {
+            PgfTarget::Internal(target) => target.amount,
+            PgfTarget::Ibc(target) => target.amount,
+        }
+    }
+}
+
+/// Pgf Target on this chain
+#[derive(
+    Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
 pub struct PgfFundingTarget {
     /// Target amount
     pub amount: token::Amount,
@@ -313,6 +353,21 @@ pub struct PgfFundingTarget {
     pub address: Address,
 }
 
+/// Pgf Target on another chain, paid through an IBC transfer
+#[derive(
+    Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+pub struct PgfIbcFundingTarget {
+    /// Target amount
+    pub amount: token::Amount,
+    /// Target address on the counterparty chain
+    pub receiver: String,
+    /// IBC port of the transfer
+    pub port_id: String,
+    /// IBC channel of the transfer
+    pub channel_id: String,
+}
+
 /// Rappresent an proposal vote
 #[derive(
     Debug,
@@ -2,7 +2,7 @@ use std::collections::BTreeMap;
 
 use thiserror::Error;
 
-use super::onchain::{PgfFunding, StewardsUpdate};
+use super::onchain::{PgfFunding, PgfTarget, StewardsUpdate};
 use crate::types::address::Address;
 use crate::types::storage::Epoch;
 use crate::types::token;
@@ -69,6 +69,18 @@ pub enum ProposalValidation {
     /// The pgf funding data is not valid
     #[error("invalid proposal extra data: cannot be empty.")]
     InvalidPgfFundingExtraData,
+    /// The pgf continous funding schedule is not valid
+    #[error(
+        "Invalid pgf funding schedule: start epoch {0} must not be greater \
+         than end epoch {1}"
+    )]
+    InvalidPgfFundingSchedule(Epoch, Epoch),
+    /// The pgf IBC funding target is not valid
+    #[error(
+        "Invalid pgf IBC funding target {0}: the receiver, port and channel \
+         must be non-empty and cannot contain '/'"
+    )]
+    InvalidPgfIbcTarget(String),
 }
 
 pub fn is_valid_author_balance(
@@ -244,9 +256,166 @@ pub fn is_valid_pgf_stewards_data(
 pub fn is_valid_pgf_funding_data(
     data: &PgfFunding,
 ) -> Result<(), ProposalValidation> {
-    if !data.continous.is_empty() || !data.retro.is_empty() {
-        Ok(())
-    } else {
-        Err(ProposalValidation::InvalidPgfFundingExtraData)
+    if data.continous.is_empty() && data.retro.is_empty() {
+        return Err(ProposalValidation::InvalidPgfFundingExtraData);
+    }
+    for funding in &data.continous {
+        if let (Some(start), Some(end)) =
+            (funding.start_epoch, funding.end_epoch)
+        {
+            if start > end {
+                return Err(ProposalValidation::InvalidPgfFundingSchedule(
+                    start, end,
+                ));
+            }
+        }
+    }
+    data.continous
+        .iter()
+        .map(|funding| &funding.target)
+        .chain(data.retro.iter())
+        .try_for_each(is_valid_pgf_target)
+}
+
+fn is_valid_pgf_target(target: &PgfTarget) -> Result<(), ProposalValidation> {
+    match target {
+        PgfTarget::Internal(_) => Ok(()),
+        PgfTarget::Ibc(target) => {
+            let is_valid =
+                [&target.receiver, &target.port_id, &target.channel_id]
+                    .into_iter()
+                    .all(|field| !field.is_empty() && !field.contains('/'));
+            if is_valid {
+                Ok(())
+            } else {
+                Err(ProposalValidation::InvalidPgfIbcTarget(
+                    target.receiver.clone(),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::governance::cli::onchain::{
+        PgfContinousFunding, PgfFundingTarget, PgfIbcFundingTarget,
+    };
+    use crate::types::address::testing::established_address_1;
+
+    fn internal_target() -> PgfTarget {
+        PgfTarget::Internal(PgfFundingTarget {
+            amount: token::Amount::native_whole(10),
+            address: established_address_1(),
+        })
+    }
+
+    fn ibc_target(receiver: &str, channel_id: &str) -> PgfTarget {
+        PgfTarget::Ibc(PgfIbcFundingTarget {
+            amount: token::Amount::native_whole(10),
+            receiver: receiver.to_string(),
+            port_id: "transfer".to_string(),
+            channel_id: channel_id.to_string(),
+        })
+    }
+
+    fn continous(
+        target: PgfTarget,
+        start_epoch: Option<u64>,
+        end_epoch: Option<u64>,
+    ) -> PgfContinousFunding {
+        PgfContinousFunding {
+            target,
+            start_epoch: start_epoch.map(Epoch),
+            end_epoch: end_epoch.map(Epoch),
+            cap: None,
+        }
+    }
+
+    #[test]
+    fn test_pgf_funding_validation() {
+        let empty = PgfFunding {
+            continous: vec![],
+            retro: vec![],
+        };
+        assert_eq!(
+            is_valid_pgf_funding_data(&empty),
+            Err(ProposalValidation::InvalidPgfFundingExtraData)
+        );
+
+        let valid = PgfFunding {
+            continous: vec![
+                continous(internal_target(), Some(1), Some(1)),
+                continous(ibc_target("cosmos1pgf", "channel-0"), None, None),
+            ],
+            retro: vec![ibc_target("cosmos1pgf", "channel-1")],
+        };
+        assert_eq!(is_valid_pgf_funding_data(&valid), Ok(()));
+
+        let invalid_schedule = PgfFunding {
+            continous: vec![continous(internal_target(), Some(2), Some(1))],
+            retro: vec![],
+        };
+        assert_eq!(
+            is_valid_pgf_funding_data(&invalid_schedule),
+            Err(ProposalValidation::InvalidPgfFundingSchedule(
+                Epoch(2),
+                Epoch(1)
+            ))
+        );
+
+        for target in [ibc_target("", "channel-0"), ibc_target("a/b", "c")] {
+            let invalid_target = PgfFunding {
+                continous: vec![],
+                retro: vec![target.clone()],
+            };
+            assert!(matches!(
+                is_valid_pgf_funding_data(&invalid_target),
+                Err(ProposalValidation::InvalidPgfIbcTarget(_))
+            ));
+            let invalid_target = PgfFunding {
+                continous: vec![continous(target, None, None)],
+                retro: vec![],
+            };
+            assert!(matches!(
+                is_valid_pgf_funding_data(&invalid_target),
+                Err(ProposalValidation::InvalidPgfIbcTarget(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_pgf_funding_from_json() {
+        let json = format!(
+            r#"{{
+                "continous": [{{
+                    "target": {{
+                        "amount": "10",
+                        "address": "{}"
+                    }},
+                    "end_epoch": 10
+                }}],
+                "retro": [{{
+                    "amount": "5",
+                    "receiver": "cosmos1pgf",
+                    "port_id": "transfer",
+                    "channel_id": "channel-0"
+                }}]
+            }}"#,
+            established_address_1()
+        );
+        let funding: PgfFunding = serde_json::from_str(&json).unwrap();
+        assert_eq!(is_valid_pgf_funding_data(&funding), Ok(()));
+        assert!(matches!(
+            &funding.continous[..],
+            [PgfContinousFunding {
+                target: PgfTarget::Internal(_),
+                start_epoch: None,
+                end_epoch: Some(Epoch(10)),
+                cap: None,
+            }]
+        ));
+        assert!(matches!(&funding.retro[..], [PgfTarget::Ibc(_)]));
     }
 }
@@ -6,7 +6,8 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::ledger::governance::cli::onchain::{
-    PgfAction, PgfContinous, PgfRetro, PgfSteward, StewardsUpdate,
+    PgfAction, PgfContinous, PgfContinousFunding, PgfRetro, PgfSteward,
+    PgfTarget, StewardsUpdate,
 };
 use crate::ledger::governance::utils::{ProposalStatus, TallyType};
 use crate::ledger::storage_api::token::Amount;
@@ -35,15 +36,46 @@ pub enum ProposalTypeError {
 )]
 pub struct StoragePgfFunding {
     /// The data about the pgf funding
-    pub detail: PGFTarget,
+    pub detail: PGFContinuousFunding,
     /// The id of the proposal that added this funding
     pub id: u64,
+    /// The total amount paid so far
+    pub paid: Amount,
 }
 
 impl StoragePgfFunding {
     /// Init a new pgf funding struct
-    pub fn new(detail: PGFTarget, id: u64) -> Self {
-        Self { detail, id }
+    pub fn new(detail: PGFContinuousFunding, id: u64) -> Self {
+        Self {
+            detail,
+            id,
+            paid: Amount::zero(),
+        }
+    }
+
+    /// The amount to pay at the given epoch, if any. The amount is reduced
+    /// to not exceed the total cap of the funding.
+    pub fn payment_at(&self, epoch: Epoch) -> Option<Amount> {
+        if !self.detail.is_active(epoch) {
+            return None;
+        }
+        let amount = match self.detail.cap {
+            Some(cap) => {
+                let remaining = cap.checked_sub(self.paid).unwrap_or_default();
+                self.detail.target.amount().min(remaining)
+            }
+            None => self.detail.target.amount(),
+        };
+        (!amount.is_zero()).then_some(amount)
+    }
+
+    /// Check if the funding will not pay anything after the given epoch,
+    /// either because its end epoch has been reached or because its total
+    /// cap has been paid
+    pub fn is_completed(&self, epoch: Epoch) -> bool {
+        let ended = matches!(self.detail.end_epoch, Some(end) if epoch >= end);
+        let capped = matches!(self.detail.cap, Some(cap) if self.paid >= cap);
+        ended || capped
     }
 }
 
@@ -80,13 +112,124 @@ pub enum AddRemove<T> {
     Eq,
     PartialOrd,
 )]
-pub struct PGFTarget {
+pub enum PGFTarget {
+    /// Funding target on this chain
+    Internal(PGFInternalTarget),
+    /// Funding target on another chain, paid through an IBC transfer
+    Ibc(PGFIbcTarget),
+}
+
+impl PGFTarget {
+    /// The target of the payment, used to identify the funding
+    pub fn target(&self) -> String {
+        match self {
+            PGFTarget::Internal(target) => target.target.to_string(),
+            PGFTarget::Ibc(target) => target.target.clone(),
+        }
+    }
+
+    /// The amount of the payment
+    pub fn amount(&self) -> Amount {
+        match self {
+            PGFTarget::Internal(target) => target.amount,
+            PGFTarget::Ibc(target) => target.amount,
+        }
+    }
+}
+
+impl Display for PGFTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PGFTarget::Internal(target) => write!(f, "{}", target.target),
+            PGFTarget::Ibc(target) => write!(
+                f,
+                "{} via {}/{}",
+                target.target, target.port_id, target.channel_id
+            ),
+        }
+    }
+}
+
+/// The target of a PGF payment on this chain
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Ord,
+    Eq,
+    PartialOrd,
+)]
+pub struct PGFInternalTarget {
     /// The target address
     pub target: Address,
     /// The amount of token to fund the target address
     pub amount: Amount,
 }
 
+/// The target of a PGF payment on another chain
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Ord,
+    Eq,
+    PartialOrd,
+)]
+pub struct PGFIbcTarget {
+    /// The target address on the counterparty chain
+    pub target: String,
+    /// The amount of token to fund the target address
+    pub amount: Amount,
+    /// The IBC port of the transfer
+    pub port_id: String,
+    /// The IBC channel of the transfer
+    pub channel_id: String,
+}
+
+/// A continuous PGF funding, paid at every epoch between its start and end
+/// epochs until its total cap is reached
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Ord,
+    Eq,
+    PartialOrd,
+)]
+pub struct PGFContinuousFunding {
+    /// The target of the payments
+    pub target: PGFTarget,
+    /// The first epoch at which the target is paid. If `None`, the payments
+    /// start right away.
+    pub start_epoch: Option<Epoch>,
+    /// The last epoch at which the target is paid. If `None`, the payments
+    /// continue until the funding is removed or its cap is reached.
+    pub end_epoch: Option<Epoch>,
+    /// The maximum total amount paid to the target
+    pub cap: Option<Amount>,
+}
+
+impl PGFContinuousFunding {
+    /// Check if the funding has to be paid at the given epoch
+    pub fn is_active(&self, epoch: Epoch) -> bool {
+        let started = self.start_epoch.map_or(true, |start| epoch >= start);
+        let ended = self.end_epoch.map_or(false, |end| epoch > end);
+        started && !ended
+    }
+}
+
 /// The actions that a PGF Steward can propose to execute
 #[derive(
     Debug,
@@ -99,7 +242,7 @@ pub struct PGFTarget {
 )]
 pub enum PGFAction {
     /// A continuous payment
-    Continuous(AddRemove<PGFTarget>),
+    Continuous(AddRemove<PGFContinuousFunding>),
     /// A retro payment
     Retro(PGFTarget),
 }
@@ -167,23 +310,47 @@ impl TryFrom<PgfSteward> for AddRemove<Address> {
     }
 }
 
+impl From<PgfTarget> for PGFTarget {
+    fn from(value: PgfTarget) -> Self {
+        match value {
+            PgfTarget::Internal(target) => {
+                PGFTarget::Internal(PGFInternalTarget {
+                    target: target.address,
+                    amount: target.amount,
+                })
+            }
+            PgfTarget::Ibc(target) => PGFTarget::Ibc(PGFIbcTarget {
+                target: target.receiver,
+                amount: target.amount,
+                port_id: target.port_id,
+                channel_id: target.channel_id,
+            }),
+        }
+    }
+}
+
+impl From<PgfContinousFunding> for PGFContinuousFunding {
+    fn from(value: PgfContinousFunding) -> Self {
+        PGFContinuousFunding {
+            target: value.target.into(),
+            start_epoch: value.start_epoch,
+            end_epoch: value.end_epoch,
+            cap: value.cap,
+        }
+    }
+}
+
 impl TryFrom<PgfContinous> for PGFAction {
     type Error = ProposalTypeError;
 
     fn try_from(value: PgfContinous) -> Result<Self, Self::Error> {
         match value.action {
             PgfAction::Add => {
-                Ok(PGFAction::Continuous(AddRemove::Add(PGFTarget {
-                    target: value.target.address,
-                    amount: value.target.amount,
-                })))
-            }
-            PgfAction::Remove => {
-                Ok(PGFAction::Continuous(AddRemove::Remove(PGFTarget {
-                    target: value.target.address,
-                    amount: value.target.amount,
-                })))
+                Ok(PGFAction::Continuous(AddRemove::Add(value.funding.into())))
             }
+            PgfAction::Remove => Ok(PGFAction::Continuous(AddRemove::Remove(
+                value.funding.into(),
+            ))),
         }
     }
 }
@@ -192,10 +359,7 @@ impl TryFrom<PgfRetro> for PGFAction {
     type Error = ProposalTypeError;
 
     fn try_from(value: PgfRetro) -> Result<Self, Self::Error> {
-        Ok(PGFAction::Retro(PGFTarget {
-            target: value.target.address,
-            amount: value.target.amount,
-        }))
+        Ok(PGFAction::Retro(value.target.into()))
     }
 }
 
@@ -310,3 +474,78 @@ impl Display for StorageProposal {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::address::testing::established_address_1;
+
+    fn funding(
+        start_epoch: Option<u64>,
+        end_epoch: Option<u64>,
+        cap: Option<u64>,
+    ) -> StoragePgfFunding {
+        StoragePgfFunding::new(
+            PGFContinuousFunding {
+                target: PGFTarget::Internal(PGFInternalTarget {
+                    target: established_address_1(),
+                    amount: Amount::native_whole(10),
+                }),
+                start_epoch: start_epoch.map(Epoch),
+                end_epoch: end_epoch.map(Epoch),
+                cap: cap.map(Amount::native_whole),
+            },
+            0,
+        )
+    }
+
+    #[test]
+    fn test_pgf_funding_is_active() {
+        let unbounded = funding(None, None, None);
+        assert!(unbounded.detail.is_active(Epoch(0)));
+        assert!(unbounded.detail.is_active(Epoch(u64::MAX)));
+
+        let scheduled = funding(Some(2), Some(4), None);
+        assert!(!scheduled.detail.is_active(Epoch(1)));
+        assert!(scheduled.detail.is_active(Epoch(2)));
+        assert!(scheduled.detail.is_active(Epoch(4)));
+        assert!(!scheduled.detail.is_active(Epoch(5)));
+    }
+
+    #[test]
+    fn test_pgf_funding_payment_at() {
+        let scheduled = funding(Some(2), Some(4), None);
+        assert_eq!(scheduled.payment_at(Epoch(1)), None);
+        assert_eq!(
+            scheduled.payment_at(Epoch(2)),
+            Some(Amount::native_whole(10))
+        );
+        assert_eq!(scheduled.payment_at(Epoch(5)), None);
+
+        // the last payment is reduced to the remaining cap
+        let mut capped = funding(None, None, Some(25));
+        capped.paid = Amount::native_whole(20);
+        assert_eq!(capped.payment_at(Epoch(0)), Some(Amount::native_whole(5)));
+        capped.paid = Amount::native_whole(25);
+        assert_eq!(capped.payment_at(Epoch(0)), None);
+        // a payment can't underflow if more than the cap was paid
+        capped.paid = Amount::native_whole(30);
+        assert_eq!(capped.payment_at(Epoch(0)), None);
+    }
+
+    #[test]
+    fn test_pgf_funding_is_completed() {
+        let unbounded = funding(None, None, None);
+        assert!(!unbounded.is_completed(Epoch(u64::MAX)));
+
+        let scheduled = funding(Some(2), Some(4), None);
+        assert!(!scheduled.is_completed(Epoch(0)));
+        assert!(!scheduled.is_completed(Epoch(3)));
+        assert!(scheduled.is_completed(Epoch(4)));
+
+        let mut capped = funding(None, None, Some(25));
+        assert!(!capped.is_completed(Epoch(0)));
+        capped.paid = Amount::native_whole(25);
+        assert!(capped.is_completed(Epoch(0)));
+    }
+}
@@ -7,7 +7,7 @@ pub use ics23::ProofSpec;
 use super::super::Error;
 use crate::ledger::storage_api;
 use crate::types::address::Address;
-use crate::types::ibc::IbcEvent;
+use crate::types::ibc::{IbcEvent, IbcShieldedTransfer};
 use crate::types::storage::{BlockHeight, Header, Key};
 use crate::types::token::DenominatedAmount;
 
@@ -86,6 +86,12 @@ pub trait IbcStorageContext {
         amount: DenominatedAmount,
     ) -> Result<(), Self::Error>;
 
+    /// Record the MASP transaction of a shielded IBC transfer
+    fn handle_masp_tx(
+        &mut self,
+        shielded: &IbcShieldedTransfer,
+    ) -> Result<(), Self::Error>;
+
     /// Get the current height of this chain
     fn get_height(&self) -> Result<BlockHeight, Self::Error>;
 
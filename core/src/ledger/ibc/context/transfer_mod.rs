@@ -4,6 +4,8 @@ use std::cell::RefCell;
 use std::fmt::Debug;
 use std::rc::Rc;
 
+use borsh::BorshDeserialize;
+
 use super::common::IbcCommonContext;
 use crate::ibc::applications::transfer::coin::PrefixedCoin;
 use crate::ibc::applications::transfer::context::{
@@ -20,6 +22,7 @@ use crate::ibc::applications::transfer::context::{
 };
 use crate::ibc::applications::transfer::denom::PrefixedDenom;
 use crate::ibc::applications::transfer::error::TokenTransferError;
+use crate::ibc::applications::transfer::packet::PacketData;
 use crate::ibc::applications::transfer::MODULE_ID_STR;
 use crate::ibc::core::events::IbcEvent;
 use crate::ibc::core::ics02_client::client_state::ClientState;
@@ -47,7 +50,8 @@ use crate::ibc::core::router::{Module, ModuleExtras, ModuleId};
 use crate::ibc::core::ContextError;
 use crate::ibc::Signer;
 use crate::ledger::ibc::storage;
-use crate::types::address::{Address, InternalAddress};
+use crate::types::address::{masp, Address, InternalAddress};
+use crate::types::ibc::is_shielded_receiver;
 use crate::types::token;
 use crate::types::uint::Uint;
 
@@ -117,6 +121,79 @@ where
 
         Ok((token, amount))
     }
+
+    /// Get the packet whose receiver is replaced with the MASP address when
+    /// the receiver is a MASP payment address. Only the receiver of an
+    /// incoming packet is replaced so that a refund never credits the MASP
+    /// address.
+    fn with_shielded_receiver(packet: &Packet) -> Packet {
+        match serde_json::from_slice::<PacketData>(&packet.data) {
+            Ok(mut data) if is_shielded_receiver(&data.receiver) => {
+                data.receiver = masp().to_string().into();
+                let data = serde_json::to_vec(&data)
+                    .expect("Encoding PacketData shouldn't fail");
+                Packet {
+                    data,
+                    ..packet.clone()
+                }
+            }
+            // The packet data is checked by the transfer module
+            _ => packet.clone(),
+        }
+    }
+
+    /// Get the packet whose sender is replaced with the refund target when
+    /// the packet has been sent from the MASP address. The refund target is
+    /// removed since the packet has been acknowledged or timed out.
+    fn with_refund_target(
+        &mut self,
+        packet: &Packet,
+    ) -> Result<Packet, TokenTransferError> {
+        let mut data = match serde_json::from_slice::<PacketData>(&packet.data)
+        {
+            Ok(data) if data.sender.as_ref() == masp().to_string() => data,
+            // The packet data is checked by the transfer module
+            _ => return Ok(packet.clone()),
+        };
+        let key = storage::refund_target_key(
+            &packet.port_id_on_a,
+            &packet.chan_id_on_a,
+            packet.seq_on_a,
+        );
+        let refund_error = |description: String| {
+            TokenTransferError::ContextError(ContextError::ChannelError(
+                ChannelError::Other { description },
+            ))
+        };
+        let bytes = self.ctx.borrow().read(&key).map_err(|_| {
+            refund_error(format!(
+                "Reading the refund target failed: Key {}",
+                key
+            ))
+        })?;
+        let refund_target = bytes
+            .and_then(|bytes| Address::try_from_slice(&bytes).ok())
+            .ok_or_else(|| {
+                refund_error(format!(
+                    "No refund target for the packet sent from the MASP \
+                     address: Key {}",
+                    key
+                ))
+            })?;
+        self.ctx.borrow_mut().delete(&key).map_err(|_| {
+            refund_error(format!(
+                "Deleting the refund target failed: Key {}",
+                key
+            ))
+        })?;
+        data.sender = refund_target.to_string().into();
+        let data = serde_json::to_vec(&data)
+            .expect("Encoding PacketData shouldn't fail");
+        Ok(Packet {
+            data,
+            ..packet.clone()
+        })
+    }
 }
 
 impl<C> ModuleWrapper for TransferModule<C>
@@ -315,7 +392,8 @@ where
         packet: &Packet,
         _relayer: &Signer,
     ) -> (ModuleExtras, Acknowledgement) {
-        on_recv_packet_execute(self, packet)
+        let packet = Self::with_shielded_receiver(packet);
+        on_recv_packet_execute(self, &packet)
     }
 
     fn on_acknowledgement_packet_validate(
@@ -339,9 +417,15 @@ where
         acknowledgement: &Acknowledgement,
         relayer: &Signer,
     ) -> (ModuleExtras, Result<(), PacketError>) {
+        let packet = match self.with_refund_target(packet) {
+            Ok(packet) => packet,
+            Err(e) => {
+                return (ModuleExtras::empty(), Err(into_packet_error(e)))
+            }
+        };
         let (extras, result) = on_acknowledgement_packet_execute(
             self,
-            packet,
+            &packet,
             acknowledgement,
             relayer,
         );
@@ -362,7 +446,14 @@ where
         packet: &Packet,
         relayer: &Signer,
     ) -> (ModuleExtras, Result<(), PacketError>) {
-        let (extras, result) = on_timeout_packet_execute(self, packet, relayer);
+        let packet = match self.with_refund_target(packet) {
+            Ok(packet) => packet,
+            Err(e) => {
+                return (ModuleExtras::empty(), Err(into_packet_error(e)))
+            }
+        };
+        let (extras, result) =
+            on_timeout_packet_execute(self, &packet, relayer);
         (extras, result.map_err(into_packet_error))
     }
 }
@@ -7,8 +7,10 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::rc::Rc;
+use std::str::FromStr;
 use std::time::Duration;
 
+use borsh::BorshSerialize;
 pub use context::common::IbcCommonContext;
 pub use context::storage::{IbcStorageContext, ProofSpec};
 pub use context::transfer_mod::{ModuleWrapper, TransferModule};
@@ -17,16 +19,23 @@ use thiserror::Error;
 
 use crate::ibc::applications::transfer::error::TokenTransferError;
 use crate::ibc::applications::transfer::msgs::transfer::MsgTransfer;
+use crate::ibc::applications::transfer::packet::PacketData;
 use crate::ibc::applications::transfer::{
     send_transfer_execute, send_transfer_validate,
 };
 use crate::ibc::core::ics04_channel::msgs::PacketMsg;
 use crate::ibc::core::ics23_commitment::specs::ProofSpecs;
 use crate::ibc::core::ics24_host::identifier::{ChainId as IbcChainId, PortId};
+use crate::ibc::core::ics24_host::path::SeqSendPath;
 use crate::ibc::core::router::{Module, ModuleId, Router};
 use crate::ibc::core::{execute, validate, MsgEnvelope, RouterError};
 use crate::ibc_proto::google::protobuf::Any;
+use crate::types::address::masp;
 use crate::types::chain::ChainId;
+use crate::types::ibc::{
+    get_shielded_transfer, IbcShieldedTransfer, FUNGIBLE_TOKEN_PACKET_EVENT,
+};
+use crate::types::masp::PaymentAddress;
 
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
@@ -49,6 +58,8 @@ pub enum Error {
     Denom(String),
     #[error("Invalid chain ID: {0}")]
     ChainId(ChainId),
+    #[error("Handling MASP transaction error: {0}")]
+    MaspTx(String),
 }
 
 /// IBC actions to handle IBC operations
@@ -61,6 +72,7 @@ where
     modules: HashMap<ModuleId, Rc<dyn ModuleWrapper + 'a>>,
     ports: HashMap<PortId, ModuleId>,
     validation_params: ValidationParams,
+    shielded_transfer: Option<IbcShieldedTransfer>,
 }
 
 impl<'a, C> IbcActions<'a, C>
@@ -74,6 +86,7 @@ where
             modules: HashMap::new(),
             ports: HashMap::new(),
             validation_params: ValidationParams::default(),
+            shielded_transfer: None,
         }
     }
 
@@ -82,6 +95,14 @@ where
         self.validation_params = params;
     }
 
+    /// Set the shielded transfer attached to the IBC transaction. It is
+    /// recorded when sending tokens from the MASP address. Tokens received
+    /// for a payment address are only shielded by the MASP transaction in the
+    /// packet memo, never by the one attached by the relayer.
+    pub fn set_shielded_transfer(&mut self, shielded: IbcShieldedTransfer) {
+        self.shielded_transfer = Some(shielded);
+    }
+
     /// Add TokenTransfer route
     pub fn add_transfer_route(
         &mut self,
@@ -113,9 +134,22 @@ where
                 let port_id = msg.port_id_on_a.clone();
                 match self.get_route_mut_by_port(&port_id) {
                     Some(_module) => {
+                        // Spending shielded notes for the sent tokens
+                        let shielded = self.shielded_transfer.clone();
+                        if let Some(shielded) = &shielded {
+                            self.store_refund_target(&msg, shielded)?;
+                        }
                         let mut module = TransferModule::new(self.ctx.clone());
                         send_transfer_execute(&mut module, msg)
-                            .map_err(Error::TokenTransfer)
+                            .map_err(Error::TokenTransfer)?;
+                        match shielded {
+                            Some(shielded) => self
+                                .ctx
+                                .borrow_mut()
+                                .handle_masp_tx(&shielded)
+                                .map_err(|e| Error::MaspTx(format!("{:?}", e))),
+                            None => Ok(()),
+                        }
                     }
                     None => Err(Error::NoModule),
                 }
@@ -124,6 +158,7 @@ where
                 let envelope =
                     MsgEnvelope::try_from(any_msg).map_err(Error::Execution)?;
                 execute(self, envelope.clone()).map_err(Error::Execution)?;
+                self.handle_received_masp_tx(&envelope)?;
                 // the current ibc-rs execution doesn't store the denom for the
                 // token hash when transfer with MsgRecvPacket
                 self.store_denom(envelope)
@@ -131,6 +166,106 @@ where
         }
     }
 
+    /// Store the transparent address refunded instead of the MASP address
+    /// when the packet sent from the shielded pool times out or fails
+    fn store_refund_target(
+        &mut self,
+        msg: &MsgTransfer,
+        shielded: &IbcShieldedTransfer,
+    ) -> Result<(), Error> {
+        if shielded.transfer.source != masp() {
+            return Ok(());
+        }
+        let refund_target =
+            shielded.refund_target.as_ref().ok_or_else(|| {
+                Error::MaspTx(
+                    "No refund target for the shielded sender".to_string(),
+                )
+            })?;
+        if *refund_target == masp() {
+            return Err(Error::MaspTx(
+                "The MASP address can't be the refund target".to_string(),
+            ));
+        }
+        // The packet will be sent with the next sequence
+        let sequence = self
+            .ctx
+            .borrow()
+            .get_next_sequence_send(&SeqSendPath(
+                msg.port_id_on_a.clone(),
+                msg.chan_id_on_a.clone(),
+            ))
+            .map_err(|e| {
+                Error::MaspTx(format!("Reading the sequence failed: {}", e))
+            })?;
+        let key = storage::refund_target_key(
+            &msg.port_id_on_a,
+            &msg.chan_id_on_a,
+            sequence,
+        );
+        let bytes =
+            refund_target.try_to_vec().expect("encoding shouldn't fail");
+        self.ctx.borrow_mut().write(&key, bytes).map_err(|e| {
+            Error::MaspTx(format!("Writing the refund target failed: {:?}", e))
+        })
+    }
+
+    /// Record the MASP transaction when tokens have been received for a MASP
+    /// payment address with MsgRecvPacket
+    fn handle_received_masp_tx(
+        &mut self,
+        envelope: &MsgEnvelope,
+    ) -> Result<(), Error> {
+        let msg = match envelope {
+            MsgEnvelope::Packet(PacketMsg::Recv(msg)) => msg,
+            _ => return Ok(()),
+        };
+        let data = match serde_json::from_slice::<PacketData>(&msg.packet.data)
+        {
+            Ok(data) => data,
+            _ => return Ok(()),
+        };
+        let receiver = match PaymentAddress::from_str(data.receiver.as_ref()) {
+            Ok(receiver) => receiver,
+            _ => return Ok(()),
+        };
+        // Nothing has been received when the packet has been already received
+        // or rejected with an error acknowledgement
+        let received = self
+            .ctx
+            .borrow()
+            .get_ibc_event(FUNGIBLE_TOKEN_PACKET_EVENT)
+            .map_err(|_| {
+                Error::MaspTx("Reading the IBC event failed".to_string())
+            })?
+            .and_then(|event| event.attributes.get("success").cloned())
+            .map(|success| success == "true")
+            .unwrap_or_default();
+        if !received {
+            return Ok(());
+        }
+        let shielded = get_shielded_transfer(data.memo.as_ref())
+            .map_err(|e| Error::MaspTx(e.to_string()))?
+            .ok_or_else(|| {
+                Error::MaspTx(
+                    "No MASP transaction in the packet memo for the shielded \
+                     receiver"
+                        .to_string(),
+                )
+            })?;
+        if !shielded.pays_only(&receiver) {
+            return Err(Error::MaspTx(format!(
+                "The MASP transaction in the packet memo doesn't only pay the \
+                 receiver {}",
+                receiver
+            )));
+        }
+        self.ctx
+            .borrow_mut()
+            .handle_masp_tx(&shielded)
+            .map_err(|e| Error::MaspTx(format!("{:?}", e)))
+    }
+
     /// Store the denom when transfer with MsgRecvPacket
     fn store_denom(&mut self, envelope: MsgEnvelope) -> Result<(), Error> {
         match envelope {
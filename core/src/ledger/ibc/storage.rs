@@ -22,6 +22,7 @@ const CLIENTS_COUNTER: &str = "clients/counter";
 const CONNECTIONS_COUNTER: &str = "connections/counter";
 const CHANNELS_COUNTER: &str = "channelEnds/counter";
 const DENOM: &str = "ibc_denom";
+const REFUND_TARGET: &str = "refund_target";
 
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
@@ -196,6 +197,18 @@ pub fn ack_key(
         .expect("Creating a key for the ack shouldn't fail")
 }
 
+/// Returns a key for the transparent address refunded when the packet sent
+/// from the MASP address with the sequence times out or fails
+pub fn refund_target_key(
+    port_id: &PortId,
+    channel_id: &ChannelId,
+    sequence: Sequence,
+) -> Key {
+    let path =
+        format!("{}/{}/{}/{}", REFUND_TARGET, port_id, channel_id, sequence);
+    ibc_key(path).expect("Creating a key for the refund target shouldn't fail")
+}
+
 /// Returns a key for the timestamp for the client update
 pub fn client_update_timestamp_key(client_id: &ClientId) -> Key {
     let path = format!("clients/{}/update_timestamp", client_id);
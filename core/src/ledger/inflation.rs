@@ -2,8 +2,7 @@
 //! proof-of-stake, providing liquity to shielded asset pools, and public goods
 //! funding.
 
-use namada_core::types::dec::Dec;
-
+use crate::types::dec::Dec;
 use crate::types::token;
 
 /// The domains of inflation
@@ -49,6 +48,13 @@ pub struct RewardsController {
 impl RewardsController {
     /// Calculate a new rewards rate
     pub fn run(self) -> ValsToUpdate {
+        self.checked_run()
+            .expect("Should not fail to calculate a new rewards rate")
+    }
+
+    /// Calculate a new rewards rate. Returns `None` if any of the steps
+    /// overflows or divides by zero.
+    pub fn checked_run(self) -> Option<ValsToUpdate> {
         let Self {
             locked_tokens,
             total_tokens,
@@ -61,52 +67,46 @@ impl RewardsController {
             epochs_per_year,
         } = self;
 
+        let add = |lhs: Dec, rhs: Dec| lhs.0.checked_add(&rhs.0).map(Dec);
+        let sub = |lhs: Dec, rhs: Dec| lhs.0.checked_sub(&rhs.0).map(Dec);
+
         // Token amounts must be expressed in terms of the raw amount (namnam)
         // to properly run the PD controller
-        let locked = Dec::try_from(locked_tokens.raw_amount())
-            .expect("Should not fail to convert token Amount to Dec");
-        let total = Dec::try_from(total_tokens.raw_amount())
-            .expect("Should not fail to convert token Amount to Dec");
+        let locked = Dec::try_from(locked_tokens.raw_amount()).ok()?;
+        let total = Dec::try_from(total_tokens.raw_amount()).ok()?;
         let epochs_py: Dec = epochs_per_year.into();
 
-        let locked_ratio = locked / total;
-        let max_inflation = total * max_reward_rate / epochs_py;
-        let p_gain = p_gain_nom * max_inflation;
-        let d_gain = d_gain_nom * max_inflation;
+        let locked_ratio = locked.trunc_div(&total)?;
+        let max_inflation =
+            total.checked_mul(&max_reward_rate)?.trunc_div(&epochs_py)?;
+        let p_gain = p_gain_nom.checked_mul(&max_inflation)?;
+        let d_gain = d_gain_nom.checked_mul(&max_inflation)?;
 
-        let error = locked_ratio_target - locked_ratio;
-        let delta_error = locked_ratio_last - locked_ratio;
-        let control_val = p_gain * error - d_gain * delta_error;
+        let error = sub(locked_ratio_target, locked_ratio)?;
+        let delta_error = sub(locked_ratio_last, locked_ratio)?;
+        let control_val = sub(
+            p_gain.checked_mul(&error)?,
+            d_gain.checked_mul(&delta_error)?,
+        )?;
 
         let last_inflation_amount =
-            Dec::try_from(last_inflation_amount.raw_amount())
-                .expect("Should not fail to convert token Amount to Dec");
-        let new_inflation_amount_raw = last_inflation_amount + control_val;
+            Dec::try_from(last_inflation_amount.raw_amount()).ok()?;
+        let new_inflation_amount_raw = add(last_inflation_amount, control_val)?;
         let new_inflation_amount = if new_inflation_amount_raw.is_negative() {
             token::Amount::zero()
         } else {
-            token::Amount::from_uint(
-                new_inflation_amount_raw
-                    .to_uint()
-                    .expect("Should not fail to convert Dec to Uint"),
-                0,
-            )
-            .expect("Should not fail to convert Uint to Amount")
+            token::Amount::from_uint(new_inflation_amount_raw.to_uint()?, 0)
+                .ok()?
         };
 
-        let max_inflation = token::Amount::from_uint(
-            max_inflation
-                .to_uint()
-                .expect("Should not fail to convert Dec to Uint"),
-            0,
-        )
-        .expect("Should not fail to convert Uint to Amount");
+        let max_inflation =
+            token::Amount::from_uint(max_inflation.to_uint()?, 0).ok()?;
 
         let inflation = std::cmp::min(new_inflation_amount, max_inflation);
-        ValsToUpdate {
+        Some(ValsToUpdate {
             locked_ratio,
             inflation,
-        }
+        })
     }
 }
 
@@ -114,9 +114,8 @@ impl RewardsController {
 mod test {
     use std::str::FromStr;
 
-    use namada_core::types::token::NATIVE_MAX_DECIMAL_PLACES;
-
     use super::*;
+    use crate::types::token::NATIVE_MAX_DECIMAL_PLACES;
 
     #[test]
     fn test_inflation_calc_up() {
@@ -257,4 +256,29 @@ mod test {
         assert_eq!(locked_ratio_2, locked_ratio_1);
         assert_eq!(inflation_2, token::Amount::zero());
     }
+
+    #[test]
+    fn test_inflation_calc_overflow() {
+        let controller = RewardsController {
+            locked_tokens: token::Amount::zero(),
+            total_tokens: token::Amount::zero(),
+            locked_ratio_target: Dec::from_str("0.66666666").unwrap(),
+            locked_ratio_last: Dec::zero(),
+            max_reward_rate: Dec::from_str("0.1").unwrap(),
+            last_inflation_amount: token::Amount::zero(),
+            p_gain_nom: Dec::from_str("0.1").unwrap(),
+            d_gain_nom: Dec::from_str("0.1").unwrap(),
+            epochs_per_year: 365,
+        };
+        // A zero supply cannot be divided by
+        assert!(controller.clone().checked_run().is_none());
+
+        // The amounts must fit in a `Dec`
+        let controller = RewardsController {
+            locked_tokens: token::Amount::max(),
+            total_tokens: token::Amount::max(),
+            ..controller
+        };
+        assert!(controller.checked_run().is_none());
+    }
 }
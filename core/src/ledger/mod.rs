@@ -5,11 +5,14 @@ pub mod gas;
 pub mod governance;
 #[cfg(any(feature = "abciplus", feature = "abcipp"))]
 pub mod ibc;
+pub mod inflation;
 pub mod parameters;
 pub mod pgf;
 pub mod replay_protection;
+pub mod scheduled_txs;
 pub mod storage;
 pub mod storage_api;
 pub mod testnet_pow;
 pub mod tx_env;
+pub mod vesting;
 pub mod vp_env;
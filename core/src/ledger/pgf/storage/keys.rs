@@ -16,6 +16,7 @@ struct Keys {
     fundings: &'static str,
     pgf_inflation_rate: &'static str,
     steward_inflation_rate: &'static str,
+    legacy_storage_migrated: &'static str,
 }
 
 /// Obtain a storage key for stewards key
@@ -63,17 +64,36 @@ pub fn fundings_key_prefix() -> Key {
 }
 
 /// LazyMap handler for the pgf fundings substorage
-pub fn fundings_handle() -> LazyMap<Address, StoragePgfFunding> {
+pub fn fundings_handle() -> LazyMap<String, StoragePgfFunding> {
     LazyMap::open(fundings_key_prefix())
 }
 
 /// Check if the given storage key is a pgf funding key.
 pub fn is_fundings_key(key: &Key) -> bool {
-    matches!(&key.segments[..], [DbKeySeg::AddressSeg(pgf), DbKeySeg::StringSeg(prefix), DbKeySeg::StringSeg(data), DbKeySeg::AddressSeg(_)] if pgf.eq(&ADDRESS)
+    matches!(&key.segments[..], [DbKeySeg::AddressSeg(pgf), DbKeySeg::StringSeg(prefix), DbKeySeg::StringSeg(data), _] if pgf.eq(&ADDRESS)
                && prefix.as_str() == Keys::VALUES.fundings
                 && data.as_str() == lazy_map::DATA_SUBKEY)
 }
 
+/// Check if the given storage key is a pgf funding key with the legacy layout,
+/// in which the fundings were stored under the address of their target.
+pub fn is_legacy_fundings_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(pgf),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(data),
+            DbKeySeg::AddressSeg(target),
+        ] if pgf.eq(&ADDRESS)
+            && prefix.as_str() == Keys::VALUES.fundings
+            && data.as_str() == lazy_map::DATA_SUBKEY =>
+        {
+            Some(target)
+        }
+        _ => None,
+    }
+}
+
 /// Check if key is inside governance address space
 pub fn is_pgf_key(key: &Key) -> bool {
     matches!(&key.segments[0], DbKeySeg::AddressSeg(addr) if addr == &ADDRESS)
@@ -102,3 +122,11 @@ pub fn get_steward_inflation_rate_key() -> Key {
         .push(&Keys::VALUES.steward_inflation_rate.to_owned())
         .expect("Cannot obtain a storage key")
 }
+
+/// Get the key of the flag set once the pgf fundings and proposals stored with
+/// the legacy layout have been migrated
+pub fn get_legacy_storage_migrated_key() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&Keys::VALUES.legacy_storage_migrated.to_owned())
+        .expect("Cannot obtain a storage key")
+}
@@ -0,0 +1,144 @@
+//! Scheduled transactions storage
+
+use crate::types::address::{Address, InternalAddress};
+use crate::types::hash::Hash;
+use crate::types::storage::{BlockHeight, DbKeySeg, Epoch, Key, KeySeg};
+use crate::types::transaction::scheduled::ScheduleTarget;
+
+/// Internal scheduled transactions address. This address also holds the fees
+/// escrowed for the execution of the scheduled transactions.
+pub const ADDRESS: Address = Address::Internal(InternalAddress::ScheduledTxs);
+
+const TX_STORAGE_KEY: &str = "tx";
+const GAS_STORAGE_KEY: &str = "gas";
+const HEIGHT_STORAGE_KEY: &str = "height";
+const EPOCH_STORAGE_KEY: &str = "epoch";
+
+/// Check if a key is a scheduled transactions key
+pub fn is_scheduled_txs_key(key: &Key) -> bool {
+    matches!(&key.segments[0], DbKeySeg::AddressSeg(addr) if addr == &ADDRESS)
+}
+
+/// Get the key under the given prefix that designates the given target
+fn target_key(prefix: &str, target: &ScheduleTarget) -> Key {
+    let key = Key::from(ADDRESS.to_db_key())
+        .push(&prefix.to_owned())
+        .expect("Cannot obtain a valid db key");
+    match target {
+        ScheduleTarget::Height(height) => key
+            .push(&HEIGHT_STORAGE_KEY.to_owned())
+            .and_then(|key| key.push(height)),
+        ScheduleTarget::Epoch(epoch) => key
+            .push(&EPOCH_STORAGE_KEY.to_owned())
+            .and_then(|key| key.push(epoch)),
+    }
+    .expect("Cannot obtain a valid db key")
+}
+
+/// Parse the target designated by the given key segments
+fn parse_target(kind: &str, value: &str) -> Option<ScheduleTarget> {
+    match kind {
+        HEIGHT_STORAGE_KEY => BlockHeight::parse(value.to_owned())
+            .ok()
+            .map(ScheduleTarget::Height),
+        EPOCH_STORAGE_KEY => Epoch::parse(value.to_owned())
+            .ok()
+            .map(ScheduleTarget::Epoch),
+        _ => None,
+    }
+}
+
+/// Get the prefix of the transactions scheduled at the given target
+pub fn get_scheduled_txs_prefix(target: &ScheduleTarget) -> Key {
+    target_key(TX_STORAGE_KEY, target)
+}
+
+/// Get the key of the transaction with the given hash scheduled at the given
+/// target
+pub fn get_scheduled_tx_key(target: &ScheduleTarget, hash: &Hash) -> Key {
+    get_scheduled_txs_prefix(target)
+        .push(hash)
+        .expect("Cannot obtain a valid db key")
+}
+
+/// Check if the given key is a scheduled transaction key. If it is, returns
+/// the target and the hash of the transaction.
+pub fn is_scheduled_tx_key(key: &Key) -> Option<(ScheduleTarget, Hash)> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(kind),
+            DbKeySeg::StringSeg(target),
+            DbKeySeg::StringSeg(hash),
+        ] if addr == &ADDRESS && prefix == TX_STORAGE_KEY => {
+            let target = parse_target(kind, target)?;
+            let hash = Hash::parse(hash.clone()).ok()?;
+            Some((target, hash))
+        }
+        _ => None,
+    }
+}
+
+/// Get the key of the total gas limit of the transactions scheduled at the
+/// given target
+pub fn get_scheduled_gas_key(target: &ScheduleTarget) -> Key {
+    target_key(GAS_STORAGE_KEY, target)
+}
+
+/// Check if the given key is the key of the total gas limit of the scheduled
+/// transactions at some target. If it is, returns the target.
+pub fn is_scheduled_gas_key(key: &Key) -> Option<ScheduleTarget> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(kind),
+            DbKeySeg::StringSeg(target),
+        ] if addr == &ADDRESS && prefix == GAS_STORAGE_KEY => {
+            parse_target(kind, target)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scheduled_tx_key_roundtrip() {
+        let hash = Hash::sha256(b"scheduled");
+        for target in [
+            ScheduleTarget::Height(BlockHeight(42)),
+            ScheduleTarget::Epoch(Epoch(42)),
+        ] {
+            let key = get_scheduled_tx_key(&target, &hash);
+
+            assert!(is_scheduled_txs_key(&key));
+            assert_eq!(is_scheduled_tx_key(&key), Some((target, hash)));
+            assert_eq!(is_scheduled_gas_key(&key), None);
+            assert!(
+                key.split_prefix(&get_scheduled_txs_prefix(&target))
+                    .is_some()
+            );
+            assert_eq!(
+                is_scheduled_tx_key(&get_scheduled_txs_prefix(&target)),
+                None
+            );
+
+            let gas_key = get_scheduled_gas_key(&target);
+            assert!(is_scheduled_txs_key(&gas_key));
+            assert_eq!(is_scheduled_gas_key(&gas_key), Some(target));
+            assert_eq!(is_scheduled_tx_key(&gas_key), None);
+        }
+        // the targets of different kinds don't share a prefix
+        let height_key = get_scheduled_tx_key(
+            &ScheduleTarget::Height(BlockHeight(42)),
+            &hash,
+        );
+        let epoch_prefix =
+            get_scheduled_txs_prefix(&ScheduleTarget::Epoch(Epoch(42)));
+        assert!(height_key.split_prefix(&epoch_prefix).is_none());
+    }
+}
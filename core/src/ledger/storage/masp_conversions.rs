@@ -8,9 +8,14 @@ use masp_primitives::convert::AllowedConversion;
 use masp_primitives::merkle_tree::FrozenCommitmentTree;
 use masp_primitives::sapling::Node;
 
+use crate::ledger::inflation::{RewardsController, ValsToUpdate};
+use crate::ledger::parameters;
+use crate::ledger::storage_api::{self, StorageRead, StorageWrite};
 use crate::types::address::Address;
+use crate::types::dec::Dec;
 use crate::types::storage::Epoch;
-use crate::types::token::MaspDenom;
+use crate::types::token::{self, MaspDenom, MaspRewardsController};
+use crate::types::uint::Uint;
 
 /// A representation of the conversion state
 #[derive(Debug, Default, BorshSerialize, BorshDeserialize)]
@@ -44,15 +49,17 @@ where
     };
     use rayon::prelude::ParallelSlice;
 
-    use crate::ledger::storage_api::{ResultExt, StorageRead, StorageWrite};
+    use crate::ledger::storage_api::ResultExt;
+    use crate::types::address;
     use crate::types::storage::{self, KeySeg};
-    use crate::types::{address, token};
 
     // The derived conversions will be placed in MASP address space
     let masp_addr = address::masp();
     let key_prefix: storage::Key = masp_addr.to_db_key().into();
 
-    let masp_rewards = address::masp_rewards();
+    // The tokens with shielded rewards and their parameters, as set by
+    // governance
+    let masp_rewards = storage_api::token::read_all_masp_params(wl_storage)?;
     // The total transparent value of the rewards being distributed
     let mut total_reward = token::Amount::native_whole(0);
 
@@ -65,17 +72,46 @@ where
     // Conversions from the previous to current asset for each address
     let mut current_convs =
         BTreeMap::<(Address, MaspDenom), AllowedConversion>::new();
-    // Reward all tokens according to above reward rates
-    for (addr, reward) in &masp_rewards {
+    // Reward all tokens according to their reward rates
+    for (addr, params) in &masp_rewards {
         // Dispense a transparent reward in parallel to the shielded rewards
         let addr_bal: token::Amount = wl_storage
             .read(&token::balance_key(addr, &masp_addr))?
             .unwrap_or_default();
+        let reward = if !params.is_valid() {
+            // Invalid parameters don't dispense any rewards
+            tracing::warn!(
+                "Invalid shielded reward parameters of {addr}: {params:?}"
+            );
+            (0, 1)
+        } else if let Some(controller) = &params.controller {
+            calculate_masp_reward_rate(
+                wl_storage,
+                addr,
+                addr_bal,
+                params.reward_rate.1,
+                controller,
+            )?
+        } else {
+            params.reward_rate
+        };
         // The reward for each reward.1 units of the current asset is
         // reward.0 units of the reward token
         // Since floor(a) + floor(b) <= floor(a+b), there will always be
-        // enough rewards to reimburse users
-        total_reward += (addr_bal * *reward).0;
+        // enough rewards to reimburse users. A reward that overflows isn't
+        // dispensed.
+        let terms = checked_reward(addr_bal, reward).and_then(
+            |(reward, precision, addr_reward)| {
+                total_reward = total_reward.checked_add(addr_reward)?;
+                Some((reward, precision))
+            },
+        );
+        let (reward, precision) = terms.unwrap_or_else(|| {
+            tracing::warn!(
+                "The shielded rewards of {addr} at the rate {reward:?} overflow"
+            );
+            (0, 1)
+        });
         for denom in token::MaspDenom::iter() {
             // Provide an allowed conversion from previous timestamp. The
             // negative sign allows each instance of the old asset to be
@@ -92,11 +128,9 @@ where
             );
             current_convs.insert(
                 (addr.clone(), denom),
-                (MaspAmount::from_pair(old_asset, -(reward.1 as i32)).unwrap()
-                    + MaspAmount::from_pair(new_asset, reward.1 as i32)
-                        .unwrap()
-                    + MaspAmount::from_pair(reward_asset, reward.0 as i32)
-                        .unwrap())
+                (MaspAmount::from_pair(old_asset, -precision).unwrap()
+                    + MaspAmount::from_pair(new_asset, precision).unwrap()
+                    + MaspAmount::from_pair(reward_asset, reward).unwrap())
                 .into(),
             );
             // Add a conversion from the previous asset type
@@ -112,6 +146,11 @@ where
         }
     }
 
+    // There's nothing to convert without any tokens that have been rewarded
+    if wl_storage.storage.conversion_state.assets.is_empty() {
+        return Ok(());
+    }
+
     // Try to distribute Merkle leaf updating as evenly as possible across
     // multiple cores
     let num_threads = rayon::current_num_threads();
@@ -133,8 +172,11 @@ where
         .with_min_len(notes_per_thread_min)
         .with_max_len(notes_per_thread_max)
         .map(|(idx, (asset, _epoch, conv, pos))| {
-            // Use transitivity to update conversion
-            *conv += current_convs[asset].clone();
+            // Use transitivity to update conversion. The tokens that are no
+            // longer rewarded keep their last conversion.
+            if let Some(current_conv) = current_convs.get(asset) {
+                *conv += current_conv.clone();
+            }
             // Update conversion position to leaf we are about to create
             *pos = idx;
             // The merkle tree need only provide the conversion commitment,
@@ -212,6 +254,90 @@ where
     Ok(())
 }
 
+/// Run the PD controller of a token's shielded rewards to derive its reward
+/// rate for the current epoch, with the given precision. The controller's
+/// state is updated in storage. A controller that overflows dispenses no
+/// rewards.
+pub fn calculate_masp_reward_rate<S>(
+    storage: &mut S,
+    token: &Address,
+    masp_balance: token::Amount,
+    precision: u32,
+    controller: &MaspRewardsController,
+) -> storage_api::Result<(u32, u32)>
+where
+    S: StorageRead + StorageWrite,
+{
+    let epochs_per_year: u64 = storage
+        .read(&parameters::storage::get_epochs_per_year_key())?
+        .expect("Epochs per year should exist in storage");
+    let total_tokens = storage_api::token::read_total_supply(&*storage, token)?;
+    if total_tokens.is_zero() {
+        return Ok((0, precision));
+    }
+    let last_locked_ratio_key = token::masp_last_locked_ratio_key(token);
+    let last_inflation_key = token::masp_last_inflation_key(token);
+    let locked_ratio_last: Dec =
+        storage.read(&last_locked_ratio_key)?.unwrap_or_default();
+    let last_inflation_amount: token::Amount =
+        storage.read(&last_inflation_key)?.unwrap_or_default();
+
+    let vals = RewardsController {
+        locked_tokens: masp_balance,
+        total_tokens,
+        locked_ratio_target: controller.locked_ratio_target,
+        locked_ratio_last,
+        max_reward_rate: controller.max_reward_rate,
+        last_inflation_amount,
+        p_gain_nom: controller.kp_gain_nom,
+        d_gain_nom: controller.kd_gain_nom,
+        epochs_per_year,
+    }
+    .checked_run();
+    // The controller's state is kept if it overflows
+    let Some(ValsToUpdate {
+        locked_ratio,
+        inflation,
+    }) = vals
+    else {
+        tracing::warn!("The shielded rewards controller of {token} overflows");
+        return Ok((0, precision));
+    };
+    storage.write(&last_locked_ratio_key, locked_ratio)?;
+    storage.write(&last_inflation_key, inflation)?;
+
+    // Spread the inflation over the balance of the shielded pool. The rate
+    // is capped so that it can be used in a conversion.
+    let rate = if masp_balance.is_zero() {
+        0
+    } else {
+        let max_rate = i32::MAX as u32;
+        inflation
+            .raw_amount()
+            .checked_mul(Uint::from(precision))
+            .map(|rate| rate / masp_balance.raw_amount())
+            .filter(|rate| *rate <= Uint::from(max_rate))
+            .map_or(max_rate, |rate| rate.as_u32())
+    };
+    Ok((rate, precision))
+}
+
+/// Get the terms `(a, b)` of a reward rate for a conversion, with the reward
+/// `a * floor(x / b)` of a balance `x` of the token. Returns `None` if the
+/// terms don't fit in an `i32`, the denominator is zero or the reward
+/// overflows.
+fn checked_reward(
+    balance: token::Amount,
+    (reward, precision): (u32, u32),
+) -> Option<(i32, i32, token::Amount)> {
+    let reward_term = i32::try_from(reward).ok()?;
+    let precision_term = i32::try_from(precision).ok().filter(|p| *p > 0)?;
+    let amount = (balance.raw_amount() / Uint::from(precision))
+        .checked_mul(Uint::from(reward))?;
+    let amount = token::Amount::from_uint(amount, 0).ok()?;
+    Some((reward_term, precision_term, amount))
+}
+
 /// Construct MASP asset type with given epoch for given token
 pub fn encode_asset_type(
     addr: Address,
@@ -224,3 +350,115 @@ pub fn encode_asset_type(
     AssetType::new(new_asset_bytes.as_ref())
         .expect("unable to derive asset identifier")
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::ledger::storage::testing::TestWlStorage;
+    use crate::types::address;
+
+    fn controller() -> MaspRewardsController {
+        MaspRewardsController {
+            locked_ratio_target: Dec::from_str("0.6667").unwrap(),
+            max_reward_rate: Dec::from_str("0.1").unwrap(),
+            kp_gain_nom: Dec::from_str("0.1").unwrap(),
+            kd_gain_nom: Dec::from_str("0.1").unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_masp_reward_rate() {
+        let mut wl_storage = TestWlStorage::default();
+        let token = address::nam();
+        let precision = 1_000_000;
+        wl_storage
+            .write(&parameters::storage::get_epochs_per_year_key(), 365_u64)
+            .unwrap();
+
+        // Without any supply, no rewards are dispensed
+        let locked = token::Amount::native_whole(100_000);
+        let rate = calculate_masp_reward_rate(
+            &mut wl_storage,
+            &token,
+            locked,
+            precision,
+            &controller(),
+        )
+        .unwrap();
+        assert_eq!(rate, (0, precision));
+
+        // Below the locked ratio target, the controller increases the rate
+        // from one epoch to the next
+        wl_storage
+            .write(
+                &token::minted_balance_key(&token),
+                token::Amount::native_whole(1_000_000),
+            )
+            .unwrap();
+        let (rate_0, precision_0) = calculate_masp_reward_rate(
+            &mut wl_storage,
+            &token,
+            locked,
+            precision,
+            &controller(),
+        )
+        .unwrap();
+        assert_eq!(precision_0, precision);
+        assert!(rate_0 > 0);
+        let inflation_0: token::Amount = wl_storage
+            .read(&token::masp_last_inflation_key(&token))
+            .unwrap()
+            .unwrap();
+        assert!(!inflation_0.is_zero());
+        let (rate_1, _) = calculate_masp_reward_rate(
+            &mut wl_storage,
+            &token,
+            locked,
+            precision,
+            &controller(),
+        )
+        .unwrap();
+        assert!(rate_1 > rate_0);
+
+        // An overflowing controller dispenses no rewards and keeps its state
+        wl_storage
+            .write(&token::minted_balance_key(&token), token::Amount::max())
+            .unwrap();
+        let inflation_1: token::Amount = wl_storage
+            .read(&token::masp_last_inflation_key(&token))
+            .unwrap()
+            .unwrap();
+        let rate = calculate_masp_reward_rate(
+            &mut wl_storage,
+            &token,
+            locked,
+            precision,
+            &controller(),
+        )
+        .unwrap();
+        assert_eq!(rate, (0, precision));
+        let inflation: token::Amount = wl_storage
+            .read(&token::masp_last_inflation_key(&token))
+            .unwrap()
+            .unwrap();
+        assert_eq!(inflation, inflation_1);
+    }
+
+    #[test]
+    fn test_checked_reward() {
+        let balance = token::Amount::from_uint(1_050, 0).unwrap();
+        assert_eq!(
+            checked_reward(balance, (3, 100)),
+            Some((3, 100, token::Amount::from_uint(30, 0).unwrap()))
+        );
+        // The denominator cannot be zero
+        assert_eq!(checked_reward(balance, (3, 0)), None);
+        // The terms must fit in an `i32`
+        assert_eq!(checked_reward(balance, (3, u32::MAX)), None);
+        assert_eq!(checked_reward(balance, (u32::MAX, 100)), None);
+        // The reward cannot overflow
+        assert_eq!(checked_reward(token::Amount::max(), (2, 1)), None);
+    }
+}
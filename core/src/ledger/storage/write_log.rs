@@ -2,6 +2,7 @@
 //! before they are committed to the ledger's storage.
 
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 
 use itertools::Itertools;
 use thiserror::Error;
@@ -9,10 +10,12 @@ use thiserror::Error;
 use crate::ledger;
 use crate::ledger::gas::{
     STORAGE_ACCESS_GAS_PER_BYTE, STORAGE_WRITE_GAS_PER_BYTE,
+    TX_EVENT_GAS_PER_BYTE,
 };
 use crate::ledger::storage::traits::StorageHasher;
 use crate::ledger::storage::Storage;
 use crate::types::address::{Address, EstablishedAddressGen, InternalAddress};
+use crate::types::event::TxEvent;
 use crate::types::hash::Hash;
 use crate::types::ibc::IbcEvent;
 use crate::types::storage;
@@ -42,7 +45,7 @@ pub enum Error {
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// A storage modification
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum StorageModification {
     /// Write a new value
     Write {
@@ -85,8 +88,32 @@ pub struct WriteLog {
     /// only be populated through a dump of the `tx_write_log` and should be
     /// cleaned either when committing or dumping the `tx_write_log`
     tx_precommit_write_log: HashMap<storage::Key, StorageModification>,
+    /// Whether the `tx_precommit_write_log` holds the changes of the previous
+    /// steps of a batch tx, in which case they are part of the prior state of
+    /// the current step
+    batch_precommit: bool,
     /// The IBC events for the current transaction
     ibc_events: BTreeSet<IbcEvent>,
+    /// The custom events emitted by the current transaction, in order
+    tx_events: Vec<TxEvent>,
+    /// The storage accesses recorded while access tracking is enabled
+    access_set: Option<Arc<Mutex<AccessSet>>>,
+}
+
+/// The state accessed by a transaction, recorded by the write log when access
+/// tracking is enabled. A transaction executed against a snapshot of the
+/// write log produces the same result as long as none of the state it
+/// accessed has changed since the snapshot was taken.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessSet {
+    /// Keys read from the write log, or from the storage if absent from it
+    pub read: BTreeSet<storage::Key>,
+    /// Prefixes of the iterated keys
+    pub iterated: BTreeSet<storage::Key>,
+    /// Whether the established address generator was used
+    pub address_gen: bool,
+    /// Whether a modification was written directly to the block write log
+    pub block_write: bool,
 }
 
 /// Write log prefix iterator
@@ -112,18 +139,45 @@ impl Default for WriteLog {
             block_write_log: HashMap::with_capacity(100_000),
             tx_write_log: HashMap::with_capacity(100),
             tx_precommit_write_log: HashMap::with_capacity(100),
+            batch_precommit: false,
             ibc_events: BTreeSet::new(),
+            tx_events: Vec::new(),
+            access_set: None,
         }
     }
 }
 
 impl WriteLog {
+    /// Start recording the state accessed via this write log into a new
+    /// [`AccessSet`]. The recording is shared with clones of the write log.
+    pub fn enable_access_tracking(&mut self) {
+        self.access_set = Some(Default::default());
+    }
+
+    /// Stop recording the state accessed via this write log and return the
+    /// recorded [`AccessSet`], if the access tracking was enabled.
+    pub fn take_access_set(&mut self) -> Option<AccessSet> {
+        self.access_set
+            .take()
+            .map(|access_set| access_set.lock().unwrap().clone())
+    }
+
+    /// Record an access if the access tracking is enabled
+    fn record_access(&self, record: impl FnOnce(&mut AccessSet)) {
+        if let Some(access_set) = &self.access_set {
+            record(&mut access_set.lock().unwrap())
+        }
+    }
+
     /// Read a value at the given key and return the value and the gas cost,
     /// returns [`None`] if the key is not present in the write log
     pub fn read(
         &self,
         key: &storage::Key,
     ) -> (Option<&StorageModification>, u64) {
+        self.record_access(|access| {
+            access.read.insert(key.clone());
+        });
         // try to read from tx write log first
         match self
             .tx_write_log
@@ -157,13 +211,23 @@ impl WriteLog {
 
     /// Read a value before the latest tx execution at the given key and return
     /// the value and the gas cost, returns [`None`] if the key is not present
-    /// in the write log
+    /// in the write log. Modifications in the precommit log are considered
+    /// prior to the latest tx execution only when they come from the previous
+    /// steps of a batch tx.
     pub fn read_pre(
         &self,
         key: &storage::Key,
     ) -> (Option<&StorageModification>, u64) {
-        // try to read from tx write log first
-        match self.block_write_log.get(key) {
+        self.record_access(|access| {
+            access.read.insert(key.clone());
+        });
+        // try to read from the previous steps of a batch first
+        match self
+            .batch_precommit
+            .then(|| self.tx_precommit_write_log.get(key))
+            .flatten()
+            .or_else(|| self.block_write_log.get(key))
+        {
             Some(v) => {
                 let gas = match v {
                     StorageModification::Write { ref value } => {
@@ -228,6 +292,7 @@ impl WriteLog {
         key: &storage::Key,
         value: Vec<u8>,
     ) -> Result<()> {
+        self.record_access(|access| access.block_write = true);
         if let Some(prev) = self
             .block_write_log
             .insert(key.clone(), StorageModification::Write { value })
@@ -320,6 +385,7 @@ impl WriteLog {
         if key.is_validity_predicate().is_some() {
             return Err(Error::DeleteVp);
         }
+        self.record_access(|access| access.block_write = true);
         if let Some(prev) = self
             .block_write_log
             .insert(key.clone(), StorageModification::Delete)
@@ -342,6 +408,7 @@ impl WriteLog {
         storage_address_gen: &EstablishedAddressGen,
         vp_code_hash: Hash,
     ) -> (Address, u64) {
+        self.record_access(|access| access.address_gen = true);
         // If we've previously generated a new account, we use the local copy of
         // the generator. Otherwise, we create a new copy from the storage
         let address_gen =
@@ -366,6 +433,13 @@ impl WriteLog {
         len as u64 * STORAGE_ACCESS_GAS_PER_BYTE
     }
 
+    /// Set a custom event emitted by the transaction and return the gas cost.
+    pub fn emit_tx_event(&mut self, event: TxEvent) -> u64 {
+        let len = event.size();
+        self.tx_events.push(event);
+        len as u64 * TX_EVENT_GAS_PER_BYTE
+    }
+
     /// Get the storage keys changed and accounts keys initialized in the
     /// current transaction. The account keys point to the validity predicates
     /// of the newly created accounts. The keys in the precommit are not
@@ -430,6 +504,16 @@ impl WriteLog {
         &self.ibc_events
     }
 
+    /// Take the custom events emitted by the current transaction
+    pub fn take_tx_events(&mut self) -> Vec<TxEvent> {
+        std::mem::take(&mut self.tx_events)
+    }
+
+    /// Get the custom events emitted by the current transaction
+    pub fn get_tx_events(&self) -> &[TxEvent] {
+        &self.tx_events
+    }
+
     /// Add the entire content of the tx write log to the precommit one. The tx
     /// log gets reset in the process.
     pub fn precommit_tx(&mut self) {
@@ -441,6 +525,14 @@ impl WriteLog {
         self.tx_precommit_write_log.extend(tx_log)
     }
 
+    /// Precommit the changes of a step of a batch tx. Until the tx gets
+    /// committed or dropped, the precommitted changes are considered prior to
+    /// the execution of the next steps.
+    pub fn precommit_batch_step(&mut self) {
+        self.precommit_tx();
+        self.batch_precommit = true;
+    }
+
     /// Commit the current transaction's write log and precommit log to the
     /// block when it's accepted by all the triggered validity predicates.
     /// Starts a new transaction write log.
@@ -458,7 +550,9 @@ impl WriteLog {
         );
 
         self.block_write_log.extend(tx_precommit_write_log);
+        self.batch_precommit = false;
         self.take_ibc_events();
+        self.take_tx_events();
     }
 
     /// Drop the current transaction's write log and precommit when it's
@@ -466,15 +560,22 @@ impl WriteLog {
     /// transaction write log.
     pub fn drop_tx(&mut self) {
         self.tx_precommit_write_log.clear();
+        self.batch_precommit = false;
         self.tx_write_log.clear();
+        self.take_ibc_events();
+        self.take_tx_events();
     }
 
     /// Drop the current transaction's write log but keep the precommit one.
     /// This is useful only when a part of a transaction failed but it can still
     /// be valid and we want to keep the changes applied before the failed
-    /// section.
+    /// section. The events of the precommitted sections were already taken
+    /// with their results, so the remaining ones belong to the failed section
+    /// and are dropped with it.
     pub fn drop_tx_keep_precommit(&mut self) {
         self.tx_write_log.clear();
+        self.take_ibc_events();
+        self.take_tx_events();
     }
 
     /// Commit the current block's write log to the storage. Starts a new block
@@ -577,6 +678,9 @@ impl WriteLog {
     /// Iterate modifications prior to the current transaction, whose storage
     /// key matches the given prefix, sorted by their storage key.
     pub fn iter_prefix_pre(&self, prefix: &storage::Key) -> PrefixIter {
+        self.record_access(|access| {
+            access.iterated.insert(prefix.clone());
+        });
         let mut matches = BTreeMap::new();
 
         for (key, modification) in &self.block_write_log {
@@ -584,6 +688,13 @@ impl WriteLog {
                 matches.insert(key.to_string(), modification.clone());
             }
         }
+        if self.batch_precommit {
+            for (key, modification) in &self.tx_precommit_write_log {
+                if key.split_prefix(prefix).is_some() {
+                    matches.insert(key.to_string(), modification.clone());
+                }
+            }
+        }
 
         let iter = matches.into_iter();
         PrefixIter { iter }
@@ -592,6 +703,9 @@ impl WriteLog {
     /// Iterate modifications posterior of the current tx, whose storage key
     /// matches the given prefix, sorted by their storage key.
     pub fn iter_prefix_post(&self, prefix: &storage::Key) -> PrefixIter {
+        self.record_access(|access| {
+            access.iterated.insert(prefix.clone());
+        });
         let mut matches = BTreeMap::new();
 
         for (key, modification) in &self.block_write_log {
@@ -599,6 +713,11 @@ impl WriteLog {
                 matches.insert(key.to_string(), modification.clone());
             }
         }
+        for (key, modification) in &self.tx_precommit_write_log {
+            if key.split_prefix(prefix).is_some() {
+                matches.insert(key.to_string(), modification.clone());
+            }
+        }
         for (key, modification) in &self.tx_write_log {
             if key.split_prefix(prefix).is_some() {
                 matches.insert(key.to_string(), modification.clone());
@@ -608,6 +727,61 @@ impl WriteLog {
         let iter = matches.into_iter();
         PrefixIter { iter }
     }
+
+    /// Adopt the changes of a transaction that was executed against a
+    /// `snapshot` of this write log, ending up in the `executed` write log,
+    /// as if it was executed against this write log instead. This is only
+    /// possible if none of the state in the transaction's `access_set` has
+    /// changed since the snapshot was taken. Returns `false` without
+    /// modifying this write log when the transaction has to be re-executed.
+    pub fn adopt_tx(
+        &mut self,
+        snapshot: &WriteLog,
+        executed: WriteLog,
+        access_set: &AccessSet,
+    ) -> bool {
+        let is_clean = |log: &WriteLog| {
+            log.tx_write_log.is_empty()
+                && log.tx_precommit_write_log.is_empty()
+                && log.ibc_events.is_empty()
+                && log.tx_events.is_empty()
+        };
+        if access_set.block_write || !is_clean(self) || !is_clean(snapshot) {
+            return false;
+        }
+        if access_set.address_gen && self.address_gen != snapshot.address_gen {
+            return false;
+        }
+        let unchanged_reads = access_set
+            .read
+            .iter()
+            .all(|key| self.read(key).0 == snapshot.read(key).0);
+        let unchanged_iters = access_set.iterated.iter().all(|prefix| {
+            self.iter_prefix_post(prefix)
+                .eq(snapshot.iter_prefix_post(prefix))
+        });
+        if !unchanged_reads || !unchanged_iters {
+            return false;
+        }
+
+        let WriteLog {
+            address_gen,
+            block_write_log: _,
+            tx_write_log,
+            tx_precommit_write_log,
+            ibc_events,
+            tx_events,
+            access_set: _,
+        } = executed;
+        if access_set.address_gen {
+            self.address_gen = address_gen;
+        }
+        self.tx_write_log = tx_write_log;
+        self.tx_precommit_write_log = tx_precommit_write_log;
+        self.ibc_events = ibc_events;
+        self.tx_events = tx_events;
+        true
+    }
 }
 
 #[cfg(test)]
@@ -834,6 +1008,145 @@ mod tests {
         assert_eq!(value, None);
     }
 
+    #[test]
+    fn test_batch_precommit_is_prior_state() {
+        let mut write_log = WriteLog::default();
+        let key =
+            storage::Key::parse("key").expect("cannot parse the key string");
+
+        // precommit a value, as done between the steps of a batch tx
+        let val1 = "val1".as_bytes().to_vec();
+        write_log.write(&key, val1.clone()).unwrap();
+        write_log.precommit_batch_step();
+
+        let val2 = "val2".as_bytes().to_vec();
+        write_log.write(&key, val2.clone()).unwrap();
+
+        // the precommitted value is the prior state of the latest tx
+        let (value, _) = write_log.read_pre(&key);
+        assert!(matches!(
+            value,
+            Some(StorageModification::Write { value }) if *value == val1
+        ));
+        assert_eq!(write_log.iter_prefix_pre(&key).count(), 1);
+        let (value, _) = write_log.read(&key);
+        assert!(matches!(
+            value,
+            Some(StorageModification::Write { value }) if *value == val2
+        ));
+        assert!(write_log.get_keys().contains(&key));
+
+        // dropping the tx also drops the precommitted changes
+        write_log.drop_tx();
+        let (value, _) = write_log.read_pre(&key);
+        assert!(value.is_none());
+    }
+
+    #[test]
+    fn test_precommit_is_not_prior_state() {
+        let mut write_log = WriteLog::default();
+        let key =
+            storage::Key::parse("key").expect("cannot parse the key string");
+
+        // precommit a value outside of a batch, as done when unshielding fees
+        let val1 = "val1".as_bytes().to_vec();
+        write_log.write(&key, val1.clone()).unwrap();
+        write_log.precommit_tx();
+
+        // the precommitted value is not part of the prior state
+        let (value, _) = write_log.read_pre(&key);
+        assert!(value.is_none());
+        assert_eq!(write_log.iter_prefix_pre(&key).count(), 0);
+        assert_eq!(write_log.iter_prefix_post(&key).count(), 1);
+
+        // the batch state doesn't outlive the committed batch
+        write_log.precommit_batch_step();
+        write_log.commit_tx();
+        write_log.write(&key, "val2".as_bytes().to_vec()).unwrap();
+        write_log.precommit_tx();
+        let (value, _) = write_log.read_pre(&key);
+        assert!(matches!(
+            value,
+            Some(StorageModification::Write { value }) if *value == val1
+        ));
+    }
+
+    #[test]
+    fn test_drop_tx_drops_events() {
+        let mut write_log = WriteLog::default();
+        let key =
+            storage::Key::parse("key").expect("cannot parse the key string");
+
+        // a tx emits events and then fails
+        write_log.write(&key, "val1".as_bytes().to_vec()).unwrap();
+        write_log.emit_tx_event(TxEvent::new("failed"));
+        write_log.emit_ibc_event(IbcEvent {
+            event_type: "failed".to_string(),
+            attributes: Default::default(),
+        });
+        write_log.drop_tx();
+        assert!(write_log.get_tx_events().is_empty());
+        assert!(write_log.get_ibc_events().is_empty());
+
+        // the next tx only gets its own events
+        let event = TxEvent::new("accepted");
+        write_log.emit_tx_event(event.clone());
+        assert_eq!(write_log.take_tx_events(), vec![event]);
+        write_log.commit_tx();
+
+        // a failed section of a tx drops its events but keeps the precommit
+        write_log.write(&key, "val2".as_bytes().to_vec()).unwrap();
+        write_log.precommit_tx();
+        write_log.emit_tx_event(TxEvent::new("failed"));
+        write_log.drop_tx_keep_precommit();
+        assert!(write_log.get_tx_events().is_empty());
+        assert_eq!(write_log.get_keys_with_precommit().len(), 1);
+    }
+
+    #[test]
+    fn test_adopt_tx() {
+        let key1 =
+            storage::Key::parse("key1").expect("cannot parse the key string");
+        let key2 =
+            storage::Key::parse("key2").expect("cannot parse the key string");
+        let val1 = "val1".as_bytes().to_vec();
+        let val2 = "val2".as_bytes().to_vec();
+
+        let mut write_log = WriteLog::default();
+        let snapshot = write_log.clone();
+
+        // execute a tx that reads `key1` and writes `key2` on the snapshot
+        let execute = || {
+            let mut executed = snapshot.clone();
+            executed.enable_access_tracking();
+            let _ = executed.read(&key1);
+            executed.write(&key2, val2.clone()).unwrap();
+            let access_set = executed.take_access_set().unwrap();
+            (executed, access_set)
+        };
+        let (executed, access_set) = execute();
+        assert!(access_set.read.contains(&key1));
+        assert!(!access_set.read.contains(&key2));
+
+        // a change of a key that wasn't accessed doesn't prevent adoption
+        let mut unrelated = write_log.clone();
+        unrelated.write(&key2, val1.clone()).unwrap();
+        unrelated.commit_tx();
+        assert!(unrelated.adopt_tx(&snapshot, executed, &access_set));
+        assert_matches!(
+            unrelated.read(&key2).0,
+            Some(StorageModification::Write { value }) if *value == val2
+        );
+
+        // a change of a read key prevents adoption
+        let (executed, access_set) = execute();
+        write_log.write(&key1, val1).unwrap();
+        write_log.commit_tx();
+        let before = write_log.get_keys();
+        assert!(!write_log.adopt_tx(&snapshot, executed, &access_set));
+        assert_eq!(write_log.get_keys(), before);
+    }
+
     prop_compose! {
         fn arb_verifiers_changed_key_tx_all_key()
             (verifiers_from_tx in testing::arb_verifiers_from_tx())
@@ -1,10 +1,18 @@
 //! Cryptographic signature keys storage API
 
+use std::collections::BTreeSet;
+
 use super::*;
-use crate::types::account::AccountPublicKeysMap;
+use crate::types::account::{
+    AccountGuardians, AccountPolicy, AccountPublicKeysMap, DebitAuthorization,
+    OutflowAllowance, PendingRecovery, RecoveryAuthorization, SessionKey,
+};
 use crate::types::address::Address;
+use crate::types::hash::Hash;
 use crate::types::key::*;
-use crate::types::storage::Key;
+use crate::types::storage::{Epoch, Key, KeySeg};
+use crate::types::token;
+use crate::types::transaction::account::RecoveryAction;
 
 /// Init the subspace of a new account
 pub fn init_account_storage<S>(
@@ -105,3 +113,579 @@ where
     }
     Ok(())
 }
+
+/// Get the spending policy of an account, if any
+pub fn policy<S>(storage: &S, owner: &Address) -> Result<Option<AccountPolicy>>
+where
+    S: StorageRead,
+{
+    storage.read(&policy_key(owner))
+}
+
+/// Set the spending policy of an account. An empty policy removes it.
+pub fn set_policy<S>(
+    storage: &mut S,
+    owner: &Address,
+    policy: &AccountPolicy,
+) -> Result<()>
+where
+    S: StorageWrite + StorageRead,
+{
+    let policy_key = policy_key(owner);
+    if policy == &AccountPolicy::default() {
+        storage.delete(&policy_key)
+    } else {
+        storage.write(&policy_key, policy)
+    }
+}
+
+/// Get the amount of a token debited from an account in the given epoch
+pub fn epoch_outflow<S>(
+    storage: &S,
+    owner: &Address,
+    token: &Address,
+    epoch: Epoch,
+) -> Result<token::Amount>
+where
+    S: StorageRead,
+{
+    let outflow: Option<(Epoch, token::Amount)> =
+        storage.read(&outflow_key(owner, token))?;
+    Ok(match outflow {
+        Some((outflow_epoch, amount)) if outflow_epoch == epoch => amount,
+        _ => token::Amount::default(),
+    })
+}
+
+/// Record a debit of a token from an account, if its outflow is limited by
+/// the account's policy. The user VP rejects limited debits that haven't
+/// been recorded.
+pub fn record_outflow<S>(
+    storage: &mut S,
+    owner: &Address,
+    token: &Address,
+    amount: token::Amount,
+) -> Result<()>
+where
+    S: StorageWrite + StorageRead,
+{
+    let is_limited = policy(storage, owner)?
+        .map(|policy| policy.outflow_limits.contains_key(token))
+        .unwrap_or_default();
+    if !is_limited || amount.is_zero() {
+        return Ok(());
+    }
+    let epoch = storage.get_block_epoch()?;
+    let outflow = epoch_outflow(storage, owner, token, epoch)?
+        .checked_add(amount)
+        .ok_or_else(|| Error::new_const("Outflow overflow"))?;
+    storage.write(&outflow_key(owner, token), (epoch, outflow))
+}
+
+/// Get the outflow allowances in the current epoch of the tokens limited by
+/// an account's policy
+pub fn outflow_allowances<S>(
+    storage: &S,
+    owner: &Address,
+) -> Result<Vec<OutflowAllowance>>
+where
+    S: StorageRead,
+{
+    let Some(policy) = policy(storage, owner)? else {
+        return Ok(vec![]);
+    };
+    let epoch = storage.get_block_epoch()?;
+    policy
+        .outflow_limits
+        .into_iter()
+        .map(|(token, limit)| {
+            let spent = epoch_outflow(storage, owner, &token, epoch)?;
+            Ok(OutflowAllowance {
+                remaining: limit.checked_sub(spent).unwrap_or_default(),
+                token,
+                limit,
+                spent,
+            })
+        })
+        .collect()
+}
+
+/// Check a debit of a token from an account against the account's spending
+/// policy, if any, and find the authorization it requires
+pub fn check_debit_policy<Pre, Post>(
+    pre: &Pre,
+    post: &Post,
+    owner: &Address,
+    token: &Address,
+    debit: token::Amount,
+    keys_changed: &BTreeSet<Key>,
+) -> Result<DebitAuthorization>
+where
+    Pre: StorageRead,
+    Post: StorageRead,
+{
+    let Some(policy) = policy(pre, owner)? else {
+        return Ok(DebitAuthorization::AccountThreshold);
+    };
+
+    if !policy.allowed_recipients.is_empty() {
+        for key in keys_changed {
+            let Some([key_token, recipient]) =
+                token::is_any_token_balance_key(key)
+            else {
+                continue;
+            };
+            if key_token != token
+                || recipient == owner
+                || policy.allowed_recipients.contains(recipient)
+            {
+                continue;
+            }
+            let pre_balance: token::Amount = pre.read(key)?.unwrap_or_default();
+            let post_balance: token::Amount =
+                post.read(key)?.unwrap_or_default();
+            if post_balance > pre_balance {
+                return Ok(DebitAuthorization::Forbidden);
+            }
+        }
+    }
+
+    if let Some(limit) = policy.outflow_limits.get(token) {
+        let epoch = post.get_block_epoch()?;
+        let spent_pre = epoch_outflow(pre, owner, token, epoch)?;
+        let spent_post = epoch_outflow(post, owner, token, epoch)?;
+        // The debit must have been recorded within the limit
+        let Some(expected) = spent_pre.checked_add(debit) else {
+            return Ok(DebitAuthorization::Forbidden);
+        };
+        if spent_post < expected || spent_post > *limit {
+            return Ok(DebitAuthorization::Forbidden);
+        }
+    }
+
+    let is_large = policy
+        .large_debit_amounts
+        .get(token)
+        .map(|amount| debit > *amount)
+        .unwrap_or_default();
+    let threshold = threshold(pre, owner)?.unwrap_or(1);
+    Ok(match policy.large_debit_threshold {
+        Some(large_debit_threshold)
+            if is_large && large_debit_threshold > threshold =>
+        {
+            DebitAuthorization::LargeDebitThreshold(large_debit_threshold)
+        }
+        _ => DebitAuthorization::AccountThreshold,
+    })
+}
+
+/// Get the signature threshold required to update an account's spending
+/// policy, if it's higher than the account's threshold
+pub fn policy_update_threshold<S>(
+    storage: &S,
+    owner: &Address,
+) -> Result<Option<u8>>
+where
+    S: StorageRead,
+{
+    let Some(large_debit_threshold) =
+        policy(storage, owner)?.and_then(|policy| policy.large_debit_threshold)
+    else {
+        return Ok(None);
+    };
+    let threshold = threshold(storage, owner)?.unwrap_or(1);
+    Ok((large_debit_threshold > threshold).then_some(large_debit_threshold))
+}
+
+/// Check that an update of an account's outflow of a token only records new
+/// debits in the current epoch
+pub fn is_valid_outflow_update<Pre, Post>(
+    pre: &Pre,
+    post: &Post,
+    owner: &Address,
+    token: &Address,
+) -> Result<bool>
+where
+    Pre: StorageRead,
+    Post: StorageRead,
+{
+    let outflow: Option<(Epoch, token::Amount)> =
+        post.read(&outflow_key(owner, token))?;
+    let Some((epoch, amount)) = outflow else {
+        return Ok(false);
+    };
+    Ok(epoch == post.get_block_epoch()?
+        && amount >= epoch_outflow(pre, owner, token, epoch)?)
+}
+
+/// Get the guardians of an account, if any
+pub fn guardians<S>(
+    storage: &S,
+    owner: &Address,
+) -> Result<Option<AccountGuardians>>
+where
+    S: StorageRead,
+{
+    storage.read(&guardians_key(owner))
+}
+
+/// Set the guardians of an account. An empty guardian set removes them.
+pub fn set_guardians<S>(
+    storage: &mut S,
+    owner: &Address,
+    guardians: &AccountGuardians,
+) -> Result<()>
+where
+    S: StorageWrite + StorageRead,
+{
+    let guardians_key = guardians_key(owner);
+    if guardians.guardians.is_empty() {
+        return storage.delete(&guardians_key);
+    }
+    if guardians.threshold == 0
+        || guardians.threshold as usize > guardians.guardians.len()
+    {
+        return Err(Error::new_const(
+            "The guardian threshold must be between 1 and the number of \
+             guardians",
+        ));
+    }
+    if guardians.guardians.contains(owner) {
+        return Err(Error::new_const("An account cannot be its own guardian"));
+    }
+    storage.write(&guardians_key, guardians)
+}
+
+/// Get the pending recovery of an account, if any
+pub fn pending_recovery<S>(
+    storage: &S,
+    owner: &Address,
+) -> Result<Option<PendingRecovery>>
+where
+    S: StorageRead,
+{
+    storage.read(&recovery_key(owner))
+}
+
+/// Apply a step of the guardian-based recovery of an account
+pub fn recover_account<S>(
+    storage: &mut S,
+    owner: &Address,
+    action: RecoveryAction,
+) -> Result<()>
+where
+    S: StorageWrite + StorageRead,
+{
+    let recovery_key = recovery_key(owner);
+    match action {
+        RecoveryAction::Request {
+            guardian,
+            public_keys,
+            threshold,
+        } => {
+            let guardians = guardians(storage, owner)?.ok_or_else(|| {
+                Error::new_const("The account has no guardians")
+            })?;
+            if !guardians.guardians.contains(&guardian) {
+                return Err(Error::new_const("Not a guardian of the account"));
+            }
+            if pending_recovery(storage, owner)?.is_some() {
+                return Err(Error::new_const(
+                    "The account already has a pending recovery",
+                ));
+            }
+            if !is_valid_recovery_keys(&public_keys, threshold) {
+                return Err(Error::new_const(
+                    "The recovery threshold must be between 1 and the number \
+                     of public keys",
+                ));
+            }
+            let executable_from =
+                storage.get_block_epoch()? + guardians.recovery_delay;
+            let recovery = PendingRecovery {
+                public_keys,
+                threshold,
+                approvals: BTreeSet::from([guardian]),
+                executable_from,
+            };
+            storage.write(&recovery_key, recovery)
+        }
+        RecoveryAction::Approve { guardian } => {
+            let guardians = guardians(storage, owner)?.ok_or_else(|| {
+                Error::new_const("The account has no guardians")
+            })?;
+            if !guardians.guardians.contains(&guardian) {
+                return Err(Error::new_const("Not a guardian of the account"));
+            }
+            let mut recovery = pending_recovery(storage, owner)?
+                .ok_or_else(|| Error::new_const("No pending recovery"))?;
+            recovery.approvals.insert(guardian);
+            storage.write(&recovery_key, recovery)
+        }
+        RecoveryAction::Cancel => storage.delete(&recovery_key),
+        RecoveryAction::Execute => {
+            let recovery = pending_recovery(storage, owner)?
+                .ok_or_else(|| Error::new_const("No pending recovery"))?;
+            let epoch = storage.get_block_epoch()?;
+            if !is_recovery_executable(storage, owner, &recovery, epoch)? {
+                return Err(Error::new_const(
+                    "The recovery is not approved or its delay hasn't passed",
+                ));
+            }
+            clear_public_keys(storage, owner)?;
+            for (index, public_key) in recovery.public_keys.iter().enumerate() {
+                set_public_key_at(storage, owner, public_key, index as u8)?;
+            }
+            storage.write(&threshold_key(owner), recovery.threshold)?;
+            storage.delete(&recovery_key)
+        }
+    }
+}
+
+/// Check the authorization required for a change of an account's pending
+/// recovery
+pub fn check_recovery_update<Pre, Post>(
+    pre: &Pre,
+    post: &Post,
+    owner: &Address,
+) -> Result<RecoveryAuthorization>
+where
+    Pre: StorageRead,
+    Post: StorageRead,
+{
+    let Some(guardians) = guardians(pre, owner)? else {
+        // Without guardians, only the account can change its recovery
+        return Ok(RecoveryAuthorization::AccountThreshold);
+    };
+    let epoch = post.get_block_epoch()?;
+    let authorization = match (
+        pending_recovery(pre, owner)?,
+        pending_recovery(post, owner)?,
+    ) {
+        // A recovery can only be requested by a single guardian
+        (None, Some(post_recovery)) => {
+            let valid = post_recovery.approvals.len() == 1
+                && post_recovery.approvals.is_subset(&guardians.guardians)
+                && post_recovery.executable_from
+                    == epoch + guardians.recovery_delay
+                && is_valid_recovery_keys(
+                    &post_recovery.public_keys,
+                    post_recovery.threshold,
+                );
+            if valid {
+                RecoveryAuthorization::Guardians(post_recovery.approvals)
+            } else {
+                RecoveryAuthorization::Forbidden
+            }
+        }
+        // Guardians can only add their approvals
+        (Some(pre_recovery), Some(post_recovery)) => {
+            let new_approvals: BTreeSet<Address> = post_recovery
+                .approvals
+                .difference(&pre_recovery.approvals)
+                .cloned()
+                .collect();
+            let valid = post_recovery.public_keys == pre_recovery.public_keys
+                && post_recovery.threshold == pre_recovery.threshold
+                && post_recovery.executable_from
+                    == pre_recovery.executable_from
+                && post_recovery.approvals.is_superset(&pre_recovery.approvals)
+                && new_approvals.is_subset(&guardians.guardians);
+            if valid {
+                RecoveryAuthorization::Guardians(new_approvals)
+            } else {
+                RecoveryAuthorization::Forbidden
+            }
+        }
+        // The recovery is either executed or cancelled by the account
+        (Some(_), None) => {
+            if is_recovery_executed(pre, post, owner)? {
+                RecoveryAuthorization::Executed
+            } else {
+                RecoveryAuthorization::AccountThreshold
+            }
+        }
+        (None, None) => RecoveryAuthorization::AccountThreshold,
+    };
+    Ok(authorization)
+}
+
+/// Check that an account's pending recovery has been executed by replacing
+/// the account's keys and threshold
+pub fn is_recovery_executed<Pre, Post>(
+    pre: &Pre,
+    post: &Post,
+    owner: &Address,
+) -> Result<bool>
+where
+    Pre: StorageRead,
+    Post: StorageRead,
+{
+    let Some(recovery) = pending_recovery(pre, owner)? else {
+        return Ok(false);
+    };
+    if pending_recovery(post, owner)?.is_some()
+        || !is_recovery_executable(
+            pre,
+            owner,
+            &recovery,
+            post.get_block_epoch()?,
+        )?
+        || threshold(post, owner)? != Some(recovery.threshold)
+        || pks_handle(owner).len(post)? != recovery.public_keys.len() as u64
+    {
+        return Ok(false);
+    }
+    for (index, public_key) in recovery.public_keys.iter().enumerate() {
+        let index = index as u8;
+        if pks_handle(owner).get(post, &index)?.as_ref() != Some(public_key) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Check that a recovery has been approved by enough guardians and that its
+/// delay has passed in the given epoch
+fn is_recovery_executable<S>(
+    storage: &S,
+    owner: &Address,
+    recovery: &PendingRecovery,
+    epoch: Epoch,
+) -> Result<bool>
+where
+    S: StorageRead,
+{
+    let Some(guardians) = guardians(storage, owner)? else {
+        return Ok(false);
+    };
+    let approvals = recovery
+        .approvals
+        .intersection(&guardians.guardians)
+        .count();
+    Ok(epoch >= recovery.executable_from
+        && approvals >= guardians.threshold as usize)
+}
+
+/// Check that the new keys of a recovery are non-empty and satisfy the new
+/// threshold
+fn is_valid_recovery_keys(
+    public_keys: &[common::PublicKey],
+    threshold: u8,
+) -> bool {
+    threshold > 0
+        && public_keys.len() <= u8::MAX as usize
+        && threshold as usize <= public_keys.len()
+}
+
+/// Get the session keys of an account with their scopes
+pub fn session_keys<S>(
+    storage: &S,
+    owner: &Address,
+) -> Result<Vec<(common::PublicKey, SessionKey)>>
+where
+    S: StorageRead,
+{
+    iter_prefix(storage, &session_keys_prefix(owner))?
+        .map(|entry| {
+            let (key, session_key) = entry?;
+            let public_key = match key.last() {
+                Some(segment) => common::PublicKey::parse(segment.raw())
+                    .into_storage_result()?,
+                None => {
+                    return Err(Error::new_const("Invalid session key"));
+                }
+            };
+            Ok((public_key, session_key))
+        })
+        .collect()
+}
+
+/// Get an account's session key, if any
+pub fn session_key<S>(
+    storage: &S,
+    owner: &Address,
+    public_key: &common::PublicKey,
+) -> Result<Option<SessionKey>>
+where
+    S: StorageRead,
+{
+    storage.read(&session_key_key(owner, public_key))
+}
+
+/// Record a debit of a token from an account by a tx signed by its session
+/// key. The user VP rejects debits authorized by a session key that haven't
+/// been recorded.
+pub fn record_session_key_spend<S>(
+    storage: &mut S,
+    owner: &Address,
+    public_key: &common::PublicKey,
+    token: &Address,
+    amount: token::Amount,
+) -> Result<()>
+where
+    S: StorageWrite + StorageRead,
+{
+    if amount.is_zero() {
+        return Ok(());
+    }
+    let Some(mut session_key) = session_key(storage, owner, public_key)? else {
+        return Err(Error::new_const("Unknown session key"));
+    };
+    session_key
+        .record_spend(token, amount)
+        .ok_or_else(|| Error::new_const("Session key spend overflow"))?;
+    storage.write(&session_key_key(owner, public_key), session_key)
+}
+
+/// Add a session key to an account or replace its scope, if it already
+/// exists. The spend of an existing key is kept.
+pub fn add_session_key<S>(
+    storage: &mut S,
+    owner: &Address,
+    public_key: &common::PublicKey,
+    session_key: &SessionKey,
+) -> Result<()>
+where
+    S: StorageWrite + StorageRead,
+{
+    if session_key.allowed_txs.is_empty() {
+        return Err(Error::new_const(
+            "A session key must be allowed to sign some txs",
+        ));
+    }
+    let mut session_key = session_key.clone();
+    if let Some(existing) = self::session_key(storage, owner, public_key)? {
+        session_key.spent = existing.spent;
+    }
+    storage.write(&session_key_key(owner, public_key), session_key)
+}
+
+/// Revoke a session key of an account
+pub fn revoke_session_key<S>(
+    storage: &mut S,
+    owner: &Address,
+    public_key: &common::PublicKey,
+) -> Result<()>
+where
+    S: StorageWrite + StorageRead,
+{
+    storage.delete(&session_key_key(owner, public_key))
+}
+
+/// Get the session keys of an account that may sign a tx with the given code
+/// hash in the current epoch
+pub fn session_keys_in_scope<S>(
+    storage: &S,
+    owner: &Address,
+    tx_code_hash: &Hash,
+) -> Result<Vec<(common::PublicKey, SessionKey)>>
+where
+    S: StorageRead,
+{
+    let epoch = storage.get_block_epoch()?;
+    Ok(session_keys(storage, owner)?
+        .into_iter()
+        .filter(|(_, session_key)| session_key.is_in_scope(tx_code_hash, epoch))
+        .collect())
+}
@@ -7,8 +7,10 @@ mod error;
 pub mod governance;
 pub mod key;
 pub mod pgf;
+pub mod scheduled_txs;
 pub mod token;
 pub mod validation;
+pub mod vesting;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 pub use error::{CustomError, Error, OptionExt, Result, ResultExt};
@@ -1,14 +1,22 @@
 //! Pgf
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::ledger::governance::storage::proposal::StoragePgfFunding;
+use borsh::BorshDeserialize;
+
+use crate::ledger::governance::storage::keys as governance_keys;
+use crate::ledger::governance::storage::proposal::{
+    AddRemove, PGFAction, PGFContinuousFunding, PGFInternalTarget, PGFTarget,
+    ProposalType, StoragePgfFunding,
+};
 use crate::ledger::pgf::parameters::PgfParameters;
 use crate::ledger::pgf::storage::keys as pgf_keys;
 use crate::ledger::pgf::storage::steward::StewardDetail;
-use crate::ledger::storage_api::{self};
+use crate::ledger::storage_api::{self, ResultExt};
 use crate::types::address::Address;
 use crate::types::dec::Dec;
+use crate::types::hash::Hash;
+use crate::types::token::Amount;
 
 /// Query the current pgf steward set
 pub fn get_stewards<S>(storage: &S) -> storage_api::Result<Vec<StewardDetail>>
@@ -79,6 +87,157 @@ where
     Ok(fundings)
 }
 
+/// A continuous pgf funding with the legacy storage layout, before fundings
+/// had a schedule, a cap or a target on another chain
+#[derive(BorshDeserialize)]
+struct LegacyStoragePgfFunding {
+    detail: LegacyPGFTarget,
+    id: u64,
+}
+
+/// A pgf funding target with the legacy storage layout
+#[derive(BorshDeserialize)]
+struct LegacyPGFTarget {
+    target: Address,
+    amount: Amount,
+}
+
+/// A pgf action with the legacy storage layout
+#[derive(BorshDeserialize)]
+enum LegacyPGFAction {
+    Continuous(AddRemove<LegacyPGFTarget>),
+    Retro(LegacyPGFTarget),
+}
+
+/// A proposal type with the legacy storage layout of the pgf actions
+#[derive(BorshDeserialize)]
+enum LegacyProposalType {
+    Default(Option<Hash>),
+    PGFSteward(HashSet<AddRemove<Address>>),
+    PGFPayment(Vec<LegacyPGFAction>),
+}
+
+impl From<LegacyPGFTarget> for PGFTarget {
+    fn from(value: LegacyPGFTarget) -> Self {
+        PGFTarget::Internal(PGFInternalTarget {
+            target: value.target,
+            amount: value.amount,
+        })
+    }
+}
+
+impl From<LegacyPGFTarget> for PGFContinuousFunding {
+    fn from(value: LegacyPGFTarget) -> Self {
+        PGFContinuousFunding {
+            target: value.into(),
+            start_epoch: None,
+            end_epoch: None,
+            cap: None,
+        }
+    }
+}
+
+impl From<LegacyStoragePgfFunding> for StoragePgfFunding {
+    fn from(value: LegacyStoragePgfFunding) -> Self {
+        StoragePgfFunding::new(value.detail.into(), value.id)
+    }
+}
+
+impl From<LegacyPGFAction> for PGFAction {
+    fn from(value: LegacyPGFAction) -> Self {
+        match value {
+            LegacyPGFAction::Continuous(AddRemove::Add(target)) => {
+                PGFAction::Continuous(AddRemove::Add(target.into()))
+            }
+            LegacyPGFAction::Continuous(AddRemove::Remove(target)) => {
+                PGFAction::Continuous(AddRemove::Remove(target.into()))
+            }
+            LegacyPGFAction::Retro(target) => PGFAction::Retro(target.into()),
+        }
+    }
+}
+
+/// Move the continuous pgf fundings and the pgf funding proposals stored with
+/// the legacy layout to the current one. The migration only runs once, after
+/// which a flag is stored and this returns `None`. Otherwise, returns the
+/// number of migrated fundings and proposals.
+pub fn migrate_legacy_storage<S>(
+    storage: &mut S,
+) -> storage_api::Result<Option<(usize, usize)>>
+where
+    S: storage_api::StorageRead + storage_api::StorageWrite,
+{
+    let migrated_key = pgf_keys::get_legacy_storage_migrated_key();
+    if storage.has_key(&migrated_key)? {
+        return Ok(None);
+    }
+    let fundings = migrate_legacy_fundings(storage)?;
+    let proposals = migrate_legacy_proposals(storage)?;
+    storage.write(&migrated_key, ())?;
+    Ok(Some((fundings, proposals)))
+}
+
+/// Move the continuous pgf fundings stored with the legacy layout, keyed by
+/// the target address, to the current layout. Returns the number of migrated
+/// fundings.
+fn migrate_legacy_fundings<S>(storage: &mut S) -> storage_api::Result<usize>
+where
+    S: storage_api::StorageRead + storage_api::StorageWrite,
+{
+    let mut legacy = vec![];
+    for entry in storage_api::iter_prefix_bytes(
+        storage,
+        &pgf_keys::fundings_key_prefix(),
+    )? {
+        let (key, bytes) = entry?;
+        if pgf_keys::is_legacy_fundings_key(&key).is_some() {
+            legacy.push((key, bytes));
+        }
+    }
+
+    for (key, bytes) in &legacy {
+        let funding: StoragePgfFunding =
+            LegacyStoragePgfFunding::try_from_slice(bytes)
+                .into_storage_result()?
+                .into();
+        storage.delete(key)?;
+        pgf_keys::fundings_handle().insert(
+            storage,
+            funding.detail.target.target(),
+            funding,
+        )?;
+    }
+
+    Ok(legacy.len())
+}
+
+/// Rewrite the pgf funding proposals stored with the legacy layout of their
+/// actions. All the proposals stored before the migration have the legacy
+/// layout. Returns the number of migrated proposals.
+fn migrate_legacy_proposals<S>(storage: &mut S) -> storage_api::Result<usize>
+where
+    S: storage_api::StorageRead + storage_api::StorageWrite,
+{
+    let counter: u64 = storage
+        .read(&governance_keys::get_counter_key())?
+        .unwrap_or_default();
+    let mut migrated = 0;
+    for id in 0..counter {
+        let key = governance_keys::get_proposal_type_key(id);
+        let Some(bytes) = storage.read_bytes(&key)? else {
+            continue;
+        };
+        if let LegacyProposalType::PGFPayment(actions) =
+            LegacyProposalType::try_from_slice(&bytes).into_storage_result()?
+        {
+            let actions = actions.into_iter().map(PGFAction::from).collect();
+            storage.write(&key, ProposalType::PGFPayment(actions))?;
+            migrated += 1;
+        }
+    }
+    Ok(migrated)
+}
+
 /// Query the pgf parameters
 pub fn get_parameters<S>(storage: &S) -> storage_api::Result<PgfParameters>
 where
@@ -122,3 +281,181 @@ where
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSerialize;
+
+    use super::*;
+    use crate::ledger::storage::testing::TestWlStorage;
+    use crate::ledger::storage_api::collections::lazy_map;
+    use crate::ledger::storage_api::StorageWrite;
+    use crate::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+
+    /// The legacy funding layout, as it was serialized before the migration
+    #[derive(BorshSerialize)]
+    struct LegacyFunding {
+        target: Address,
+        amount: Amount,
+        id: u64,
+    }
+
+    /// The legacy pgf funding target layout
+    #[derive(BorshSerialize)]
+    struct LegacyTarget {
+        target: Address,
+        amount: Amount,
+    }
+
+    /// The legacy pgf action layout
+    #[derive(BorshSerialize)]
+    enum LegacyAction {
+        Continuous(AddRemove<LegacyTarget>),
+        Retro(LegacyTarget),
+    }
+
+    /// The legacy proposal type layout
+    #[allow(dead_code)]
+    #[derive(BorshSerialize)]
+    enum LegacyProposal {
+        Default(Option<Hash>),
+        PGFSteward(HashSet<AddRemove<Address>>),
+        PGFPayment(Vec<LegacyAction>),
+    }
+
+    #[test]
+    fn test_migrate_legacy_fundings() {
+        let mut storage = TestWlStorage::default();
+        let legacy_target = established_address_1();
+        let legacy_key = pgf_keys::fundings_key_prefix()
+            .push(&lazy_map::DATA_SUBKEY.to_owned())
+            .and_then(|key| key.push(&legacy_target))
+            .unwrap();
+        assert_eq!(
+            pgf_keys::is_legacy_fundings_key(&legacy_key),
+            Some(&legacy_target)
+        );
+        assert!(pgf_keys::is_fundings_key(&legacy_key));
+        storage
+            .write(
+                &legacy_key,
+                LegacyFunding {
+                    target: legacy_target.clone(),
+                    amount: Amount::native_whole(10),
+                    id: 3,
+                },
+            )
+            .unwrap();
+        // a funding that already has the current layout
+        let target = established_address_2();
+        let current = StoragePgfFunding::new(
+            PGFContinuousFunding {
+                target: PGFTarget::Internal(PGFInternalTarget {
+                    target: target.clone(),
+                    amount: Amount::native_whole(5),
+                }),
+                start_epoch: None,
+                end_epoch: None,
+                cap: Some(Amount::native_whole(20)),
+            },
+            4,
+        );
+        pgf_keys::fundings_handle()
+            .insert(&mut storage, target.to_string(), current)
+            .unwrap();
+
+        assert_eq!(migrate_legacy_storage(&mut storage).unwrap(), Some((1, 0)));
+        assert!(!storage.has_key(&legacy_key).unwrap());
+        let migrated = pgf_keys::fundings_handle()
+            .get(&storage, &legacy_target.to_string())
+            .unwrap()
+            .expect("The legacy funding should be migrated");
+        assert_eq!(migrated.id, 3);
+        assert_eq!(migrated.paid, Amount::zero());
+        assert_eq!(
+            migrated.detail,
+            PGFContinuousFunding {
+                target: PGFTarget::Internal(PGFInternalTarget {
+                    target: legacy_target,
+                    amount: Amount::native_whole(10),
+                }),
+                start_epoch: None,
+                end_epoch: None,
+                cap: None,
+            }
+        );
+        assert_eq!(get_payments(&storage).unwrap().len(), 2);
+
+        // the migration only runs once
+        assert_eq!(migrate_legacy_storage(&mut storage).unwrap(), None);
+        assert_eq!(get_payments(&storage).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_migrate_legacy_proposals() {
+        let mut storage = TestWlStorage::default();
+        let target = established_address_1();
+        let steward = established_address_2();
+
+        // a pgf funding proposal stored with the legacy layout and a steward
+        // proposal, whose layout didn't change
+        let legacy_payment = LegacyProposal::PGFPayment(vec![
+            LegacyAction::Continuous(AddRemove::Add(LegacyTarget {
+                target: target.clone(),
+                amount: Amount::native_whole(10),
+            })),
+            LegacyAction::Retro(LegacyTarget {
+                target: target.clone(),
+                amount: Amount::native_whole(3),
+            }),
+        ]);
+        let steward_proposal =
+            ProposalType::PGFSteward(HashSet::from([AddRemove::Add(steward)]));
+        storage
+            .write(&governance_keys::get_proposal_type_key(0), legacy_payment)
+            .unwrap();
+        storage
+            .write(
+                &governance_keys::get_proposal_type_key(1),
+                steward_proposal.clone(),
+            )
+            .unwrap();
+        storage
+            .write(&governance_keys::get_counter_key(), 2u64)
+            .unwrap();
+
+        assert_eq!(migrate_legacy_storage(&mut storage).unwrap(), Some((0, 1)));
+        let migrated: ProposalType = storage
+            .read(&governance_keys::get_proposal_type_key(0))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            migrated,
+            ProposalType::PGFPayment(vec![
+                PGFAction::Continuous(AddRemove::Add(PGFContinuousFunding {
+                    target: PGFTarget::Internal(PGFInternalTarget {
+                        target: target.clone(),
+                        amount: Amount::native_whole(10),
+                    }),
+                    start_epoch: None,
+                    end_epoch: None,
+                    cap: None,
+                })),
+                PGFAction::Retro(PGFTarget::Internal(PGFInternalTarget {
+                    target,
+                    amount: Amount::native_whole(3),
+                })),
+            ])
+        );
+        let unchanged: ProposalType = storage
+            .read(&governance_keys::get_proposal_type_key(1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(unchanged, steward_proposal);
+
+        // the migration only runs once
+        assert_eq!(migrate_legacy_storage(&mut storage).unwrap(), None);
+    }
+}
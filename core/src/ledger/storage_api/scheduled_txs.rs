@@ -0,0 +1,167 @@
+//! Scheduled transactions
+
+use super::token;
+use crate::ledger::gas::get_max_block_gas;
+use crate::ledger::parameters::read_gas_cost;
+use crate::ledger::scheduled_txs::{
+    get_scheduled_gas_key, get_scheduled_tx_key, get_scheduled_txs_prefix,
+    ADDRESS,
+};
+use crate::ledger::storage_api::{self, StorageRead, StorageWrite};
+use crate::types::storage::Key;
+use crate::types::transaction::scheduled::{
+    CancelScheduledTx, ScheduleTarget, ScheduledTx,
+};
+
+/// Schedule a transaction for execution at its target, escrowing the fee for
+/// its execution from the owner's balance.
+pub fn schedule_tx<S>(
+    storage: &mut S,
+    scheduled_tx: ScheduledTx,
+) -> storage_api::Result<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    if scheduled_tx.tx.schedule_target() != Some(scheduled_tx.target) {
+        return Err(storage_api::Error::new_const(
+            "The transaction is not marked for execution at its scheduled \
+             target",
+        ));
+    }
+    check_fee_and_gas(storage, &scheduled_tx)?;
+    let escrow = scheduled_tx.escrow().ok_or_else(|| {
+        storage_api::Error::new_const("The escrowed fee amount overflows")
+    })?;
+    let key = get_scheduled_tx_key(
+        &scheduled_tx.target,
+        &scheduled_tx.payload_hash(),
+    );
+    if storage.has_key(&key)? {
+        return Err(storage_api::Error::new_const(
+            "The transaction is already scheduled at this target",
+        ));
+    }
+    let max_block_gas = get_max_block_gas(storage)?;
+    let gas = read_scheduled_gas(storage, &scheduled_tx.target)?
+        .checked_add(u64::from(scheduled_tx.gas_limit))
+        .filter(|gas| *gas <= max_block_gas)
+        .ok_or_else(|| {
+            storage_api::Error::new_const(
+                "The total gas limit of the transactions scheduled at this \
+                 target exceeds the maximum block gas",
+            )
+        })?;
+    storage.write(&get_scheduled_gas_key(&scheduled_tx.target), gas)?;
+    token::transfer(
+        storage,
+        &scheduled_tx.fee.token,
+        &scheduled_tx.owner,
+        &ADDRESS,
+        escrow,
+    )?;
+    storage.write(&key, scheduled_tx)
+}
+
+/// Cancel a scheduled transaction, refunding the escrowed fee to its owner.
+pub fn cancel_scheduled_tx<S>(
+    storage: &mut S,
+    data: &CancelScheduledTx,
+) -> storage_api::Result<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    let key = get_scheduled_tx_key(&data.target, &data.hash);
+    let scheduled_tx: ScheduledTx = storage.read(&key)?.ok_or_else(|| {
+        storage_api::Error::new_const("The scheduled transaction doesn't exist")
+    })?;
+    if scheduled_tx.owner != data.owner {
+        return Err(storage_api::Error::new_const(
+            "Only the owner can cancel a scheduled transaction",
+        ));
+    }
+    storage.delete(&key)?;
+    let gas = read_scheduled_gas(storage, &data.target)?
+        .saturating_sub(u64::from(scheduled_tx.gas_limit));
+    let gas_key = get_scheduled_gas_key(&data.target);
+    if gas == 0 {
+        storage.delete(&gas_key)?;
+    } else {
+        storage.write(&gas_key, gas)?;
+    }
+    refund_escrow(storage, &scheduled_tx)
+}
+
+/// Check that the fee of a scheduled transaction is paid in a token allowed
+/// for fee payment, that it is at least the minimum gas cost of the token and
+/// that the gas limit doesn't exceed the maximum block gas.
+pub fn check_fee_and_gas<S>(
+    storage: &S,
+    scheduled_tx: &ScheduledTx,
+) -> storage_api::Result<()>
+where
+    S: StorageRead,
+{
+    let gas_cost = read_gas_cost(storage, &scheduled_tx.fee.token)?
+        .ok_or_else(|| {
+            storage_api::Error::new_const(
+                "The fee token is not allowed for fee payment",
+            )
+        })?;
+    if scheduled_tx.fee.amount_per_gas_unit < gas_cost {
+        return Err(storage_api::Error::new_const(
+            "The fee amount is lower than the minimum gas cost",
+        ));
+    }
+    if u64::from(scheduled_tx.gas_limit) > get_max_block_gas(storage)? {
+        return Err(storage_api::Error::new_const(
+            "The gas limit exceeds the maximum block gas",
+        ));
+    }
+    Ok(())
+}
+
+/// Refund the escrowed fee of a scheduled transaction to its owner.
+pub fn refund_escrow<S>(
+    storage: &mut S,
+    scheduled_tx: &ScheduledTx,
+) -> storage_api::Result<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    // The escrow was checked for overflow when the tx was scheduled
+    let escrow = scheduled_tx.escrow().unwrap_or_default();
+    token::transfer(
+        storage,
+        &scheduled_tx.fee.token,
+        &ADDRESS,
+        &scheduled_tx.owner,
+        escrow,
+    )
+}
+
+/// Read the total gas limit of the transactions scheduled at the given
+/// target.
+pub fn read_scheduled_gas<S>(
+    storage: &S,
+    target: &ScheduleTarget,
+) -> storage_api::Result<u64>
+where
+    S: StorageRead,
+{
+    Ok(storage
+        .read(&get_scheduled_gas_key(target))?
+        .unwrap_or_default())
+}
+
+/// Get all the transactions scheduled at the given target, together with
+/// their storage keys.
+pub fn get_scheduled_txs<S>(
+    storage: &S,
+    target: &ScheduleTarget,
+) -> storage_api::Result<Vec<(Key, ScheduledTx)>>
+where
+    S: StorageRead,
+{
+    storage_api::iter_prefix(storage, &get_scheduled_txs_prefix(target))?
+        .collect()
+}
@@ -1,5 +1,7 @@
 //! Token storage_api functions
 
+use std::collections::BTreeMap;
+
 use super::{StorageRead, StorageWrite};
 use crate::ledger::storage_api;
 use crate::types::address::{Address, InternalAddress};
@@ -85,6 +87,49 @@ where
     storage.write(&key, denom)
 }
 
+/// Read the shielded reward parameters of a given token, if any.
+pub fn read_masp_params<S>(
+    storage: &S,
+    token: &Address,
+) -> storage_api::Result<Option<token::MaspParams>>
+where
+    S: StorageRead,
+{
+    storage.read(&token::masp_params_key(token))
+}
+
+/// Read the shielded reward parameters of all the tokens that have them.
+pub fn read_all_masp_params<S>(
+    storage: &S,
+) -> storage_api::Result<BTreeMap<Address, token::MaspParams>>
+where
+    S: StorageRead,
+{
+    storage_api::iter_prefix(storage, &token::masp_params_prefix())?
+        .map(|entry| {
+            let (key, params) = entry?;
+            match token::is_any_masp_params_key(&key) {
+                Some(token) => Ok((token.clone(), params)),
+                None => Err(storage_api::Error::new_const(
+                    "Invalid shielded reward parameters key",
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Write the shielded reward parameters of a given token.
+pub fn write_masp_params<S>(
+    storage: &mut S,
+    token: &Address,
+    params: &token::MaspParams,
+) -> storage_api::Result<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    storage.write(&token::masp_params_key(token), params)
+}
+
 /// Transfer `token` from `src` to `dest`. Returns an `Err` if `src` has
 /// insufficient balance or if the transfer the `dest` would overflow (This can
 /// only happen if the total supply does't fit in `token::Amount`).
@@ -110,7 +155,10 @@ where
             match dest_balance.checked_add(amount) {
                 Some(new_dest_balance) => {
                     storage.write(&src_key, new_src_balance)?;
-                    storage.write(&dest_key, new_dest_balance)
+                    storage.write(&dest_key, new_dest_balance)?;
+                    storage_api::account::record_outflow(
+                        storage, src, token, amount,
+                    )
                 }
                 None => Err(storage_api::Error::new_const(
                     "The transfer would overflow destination balance",
@@ -0,0 +1,65 @@
+//! Vesting accounts
+
+use super::token;
+use crate::ledger::storage_api::{self, StorageRead, StorageWrite};
+use crate::ledger::vesting::{
+    get_owner_schedules_prefix, get_schedule_key, is_schedule_key,
+    VestingBalance, VestingSchedule,
+};
+use crate::types::address::Address;
+
+/// Set the vesting schedule of the balance of the given token held by the
+/// given owner.
+pub fn write_schedule<S>(
+    storage: &mut S,
+    owner: &Address,
+    token: &Address,
+    schedule: VestingSchedule,
+) -> storage_api::Result<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    storage.write(&get_schedule_key(owner, token), schedule)
+}
+
+/// Get the vesting schedule of the balance of the given token held by the
+/// given owner, if any.
+pub fn get_schedule<S>(
+    storage: &S,
+    owner: &Address,
+    token: &Address,
+) -> storage_api::Result<Option<VestingSchedule>>
+where
+    S: StorageRead,
+{
+    storage.read(&get_schedule_key(owner, token))
+}
+
+/// Get the vested and locked balances of all the tokens subject to a vesting
+/// schedule held by the given owner at the current epoch.
+pub fn get_vesting_balances<S>(
+    storage: &S,
+    owner: &Address,
+) -> storage_api::Result<Vec<VestingBalance>>
+where
+    S: StorageRead,
+{
+    let epoch = storage.get_block_epoch()?;
+    let mut balances = vec![];
+    for result in storage_api::iter_prefix::<VestingSchedule>(
+        storage,
+        &get_owner_schedules_prefix(owner),
+    )? {
+        let (key, schedule) = result?;
+        if let Some((_, token)) = is_schedule_key(&key) {
+            balances.push(VestingBalance {
+                token: token.clone(),
+                balance: token::read_balance(storage, token, owner)?,
+                vested: schedule.vested_amount(epoch),
+                locked: schedule.locked_amount(epoch),
+                schedule,
+            });
+        }
+    }
+    Ok(balances)
+}
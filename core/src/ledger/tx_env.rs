@@ -5,6 +5,8 @@ use borsh::BorshSerialize;
 
 use crate::ledger::storage_api::{self, StorageRead, StorageWrite};
 use crate::types::address::Address;
+use crate::types::event::TxEvent;
+use crate::types::hash::Hash;
 use crate::types::ibc::IbcEvent;
 use crate::types::storage;
 
@@ -56,6 +58,32 @@ pub trait TxEnv: StorageRead + StorageWrite {
         event: &IbcEvent,
     ) -> Result<(), storage_api::Error>;
 
+    /// Emit a custom event. The namespace of the event is set to the hash of
+    /// the code of this transaction and the event must not be too large. On
+    /// multiple calls, these emitted events will be added in order.
+    fn emit_event(&mut self, event: &TxEvent)
+        -> Result<(), storage_api::Error>;
+
+    /// Call the entrypoint of the stored wasm code with the given hash on
+    /// behalf of the given target account with the given input. The called
+    /// code runs in the write log of this transaction, but it can only modify
+    /// the storage of the target account and the target account's VP
+    /// validates these modifications. Returns the output set by the called
+    /// code, if any.
+    fn call(
+        &mut self,
+        target: &Address,
+        code_hash: &Hash,
+        input: impl AsRef<[u8]>,
+    ) -> Result<Option<Vec<u8>>, storage_api::Error>;
+
+    /// Deploy a wasm code that can then be called with [`TxEnv::call`].
+    /// Returns the hash of the stored code.
+    fn deploy_code(
+        &mut self,
+        code: impl AsRef<[u8]>,
+    ) -> Result<Hash, storage_api::Error>;
+
     /// Request to charge the provided amount of gas for the current transaction
     fn charge_gas(&mut self, used_gas: u64) -> Result<(), storage_api::Error>;
 
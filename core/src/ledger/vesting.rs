@@ -0,0 +1,264 @@
+//! Vesting accounts storage and schedules
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+use crate::types::address::{Address, InternalAddress};
+use crate::types::storage::{DbKeySeg, Epoch, Key, KeySeg};
+use crate::types::token::Amount;
+
+/// Internal vesting address
+pub const ADDRESS: Address = Address::Internal(InternalAddress::Vesting);
+
+const SCHEDULE_STORAGE_KEY: &str = "schedule";
+
+/// The way the vested funds of a vesting account are released
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum VestingKind {
+    /// All the funds are released at once at the given epoch
+    Cliff {
+        /// The epoch at which the funds are released
+        unlock_epoch: Epoch,
+    },
+    /// The funds are released linearly at every epoch between the start
+    /// epoch (nothing released) and the end epoch (everything released)
+    Linear {
+        /// The epoch at which the release starts
+        start_epoch: Epoch,
+        /// The epoch at which all the funds are released
+        end_epoch: Epoch,
+    },
+    /// An equal share of the funds is released at the end of each period
+    Periodic {
+        /// The epoch at which the first period starts
+        start_epoch: Epoch,
+        /// The number of epochs in a period
+        period_epochs: u64,
+        /// The number of periods
+        periods: u64,
+    },
+}
+
+/// The vesting schedule of the balance of a token held by an account
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct VestingSchedule {
+    /// The total amount subject to vesting
+    pub amount: Amount,
+    /// How the amount is released
+    pub kind: VestingKind,
+}
+
+impl VestingSchedule {
+    /// The amount that has been released at the given epoch
+    pub fn vested_amount(&self, epoch: Epoch) -> Amount {
+        match self.kind {
+            VestingKind::Cliff { unlock_epoch } => {
+                if epoch >= unlock_epoch {
+                    self.amount
+                } else {
+                    Amount::zero()
+                }
+            }
+            VestingKind::Linear {
+                start_epoch,
+                end_epoch,
+            } => {
+                if epoch >= end_epoch {
+                    self.amount
+                } else if epoch <= start_epoch {
+                    Amount::zero()
+                } else {
+                    self.fraction(
+                        epoch.0 - start_epoch.0,
+                        end_epoch.0 - start_epoch.0,
+                    )
+                }
+            }
+            VestingKind::Periodic {
+                start_epoch,
+                period_epochs,
+                periods,
+            } => {
+                if epoch < start_epoch {
+                    Amount::zero()
+                } else if period_epochs == 0 || periods == 0 {
+                    self.amount
+                } else {
+                    let elapsed = (epoch.0 - start_epoch.0) / period_epochs;
+                    self.fraction(elapsed.min(periods), periods)
+                }
+            }
+        }
+    }
+
+    /// The amount that is still locked at the given epoch
+    pub fn locked_amount(&self, epoch: Epoch) -> Amount {
+        self.amount
+            .checked_sub(self.vested_amount(epoch))
+            .unwrap_or_default()
+    }
+
+    /// Compute `amount * num / den`, rounded down, without overflowing
+    fn fraction(&self, num: u64, den: u64) -> Amount {
+        let (whole, rem) = self.amount * (num, den);
+        let rem = rem
+            .checked_mul(Amount::from_u64(num))
+            .and_then(|rem| rem.checked_div(Amount::from_u64(den)))
+            .unwrap_or_default();
+        whole + rem
+    }
+}
+
+/// The vesting status of the balance of a token held by a vesting account
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct VestingBalance {
+    /// The vested token
+    pub token: Address,
+    /// The vesting schedule
+    pub schedule: VestingSchedule,
+    /// The current balance of the account
+    pub balance: Amount,
+    /// The amount released so far
+    pub vested: Amount,
+    /// The amount that is still locked
+    pub locked: Amount,
+}
+
+/// Check if a key is a vesting key
+pub fn is_vesting_key(key: &Key) -> bool {
+    matches!(&key.segments[0], DbKeySeg::AddressSeg(addr) if addr == &ADDRESS)
+}
+
+/// Get the key of the vesting schedule of the balance of the given token held
+/// by the given owner
+pub fn get_schedule_key(owner: &Address, token: &Address) -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&SCHEDULE_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a valid db key")
+        .push(&owner.to_db_key())
+        .expect("Cannot obtain a valid db key")
+        .push(&token.to_db_key())
+        .expect("Cannot obtain a valid db key")
+}
+
+/// Get the prefix of the vesting schedules of the given owner
+pub fn get_owner_schedules_prefix(owner: &Address) -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&SCHEDULE_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a valid db key")
+        .push(&owner.to_db_key())
+        .expect("Cannot obtain a valid db key")
+}
+
+/// Check if the given key is a vesting schedule key. If it is, returns the
+/// owner and the token.
+pub fn is_schedule_key(key: &Key) -> Option<(&Address, &Address)> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(owner),
+            DbKeySeg::AddressSeg(token),
+        ] if addr == &ADDRESS && prefix == SCHEDULE_STORAGE_KEY => {
+            Some((owner, token))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::address::nam;
+    use crate::types::address::testing::established_address_1;
+
+    #[test]
+    fn test_vested_amount() {
+        let amount = Amount::native_whole(1_000);
+        let cliff = VestingSchedule {
+            amount,
+            kind: VestingKind::Cliff {
+                unlock_epoch: Epoch(10),
+            },
+        };
+        assert_eq!(cliff.vested_amount(Epoch(9)), Amount::zero());
+        assert_eq!(cliff.locked_amount(Epoch(9)), amount);
+        assert_eq!(cliff.vested_amount(Epoch(10)), amount);
+
+        let linear = VestingSchedule {
+            amount,
+            kind: VestingKind::Linear {
+                start_epoch: Epoch(10),
+                end_epoch: Epoch(20),
+            },
+        };
+        assert_eq!(linear.vested_amount(Epoch(10)), Amount::zero());
+        assert_eq!(linear.vested_amount(Epoch(13)), Amount::native_whole(300));
+        assert_eq!(linear.locked_amount(Epoch(13)), Amount::native_whole(700));
+        assert_eq!(linear.vested_amount(Epoch(25)), amount);
+
+        let periodic = VestingSchedule {
+            amount,
+            kind: VestingKind::Periodic {
+                start_epoch: Epoch(10),
+                period_epochs: 5,
+                periods: 4,
+            },
+        };
+        assert_eq!(periodic.vested_amount(Epoch(14)), Amount::zero());
+        assert_eq!(
+            periodic.vested_amount(Epoch(15)),
+            Amount::native_whole(250)
+        );
+        assert_eq!(
+            periodic.vested_amount(Epoch(29)),
+            Amount::native_whole(750)
+        );
+        assert_eq!(periodic.vested_amount(Epoch(100)), amount);
+    }
+
+    #[test]
+    fn test_schedule_key_roundtrip() {
+        let owner = established_address_1();
+        let token = nam();
+        let key = get_schedule_key(&owner, &token);
+
+        assert!(is_vesting_key(&key));
+        assert_eq!(is_schedule_key(&key), Some((&owner, &token)));
+        assert!(key
+            .split_prefix(&get_owner_schedules_prefix(&owner))
+            .is_some());
+        assert_eq!(is_schedule_key(&get_owner_schedules_prefix(&owner)), None);
+    }
+}
@@ -4,9 +4,9 @@ pub mod generated;
 mod types;
 
 pub use types::{
-    Code, Commitment, Data, Dkg, Error, Header, MaspBuilder, MultiSignature,
-    Section, Signable, SignableEthMessage, Signature, SignatureIndex, Signed,
-    Tx, TxError,
+    Batch, BatchStep, Code, Commitment, Data, Dkg, Error, Header, MaspBuilder,
+    MultiSignature, Schedule, Section, Signable, SignableEthMessage, Signature,
+    SignatureIndex, Signed, Tx, TxError,
 };
 
 #[cfg(test)]
@@ -40,6 +40,7 @@ use crate::types::token::MaspDenom;
 use crate::types::token::Transfer;
 #[cfg(feature = "ferveo-tpke")]
 use crate::types::transaction::protocol::ProtocolTx;
+use crate::types::transaction::scheduled::ScheduleTarget;
 #[cfg(feature = "ferveo-tpke")]
 use crate::types::transaction::EllipticCurve;
 #[cfg(feature = "ferveo-tpke")]
@@ -377,6 +378,90 @@ impl Code {
     }
 }
 
+/// A single step of a batch transaction, referencing a code section and a
+/// data section of the transaction it belongs to
+#[derive(
+    Clone,
+    Debug,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    Eq,
+    PartialEq,
+)]
+pub struct BatchStep {
+    /// The SHA-256 hash of the step's code section
+    pub code_hash: crate::types::hash::Hash,
+    /// The SHA-256 hash of the step's data section
+    pub data_hash: crate::types::hash::Hash,
+}
+
+/// A section representing an ordered list of inner transactions, which are
+/// either all applied or all dropped
+#[derive(
+    Clone,
+    Debug,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct Batch {
+    /// Additional random data
+    pub salt: [u8; 8],
+    /// The steps of the batch, in order of execution
+    pub steps: Vec<BatchStep>,
+}
+
+impl Batch {
+    /// Make a new batch section with the given steps
+    pub fn new(steps: Vec<BatchStep>) -> Self {
+        Self {
+            salt: DateTimeUtc::now().0.timestamp_millis().to_le_bytes(),
+            steps,
+        }
+    }
+
+    /// Hash this batch section
+    pub fn hash<'a>(&self, hasher: &'a mut Sha256) -> &'a mut Sha256 {
+        hasher.update(
+            self.try_to_vec()
+                .expect("unable to serialize batch section"),
+        );
+        hasher
+    }
+}
+
+/// A section marking a transaction as only executable by the protocol at the
+/// given height or epoch, as part of the scheduled transactions
+#[derive(
+    Clone,
+    Debug,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct Schedule {
+    /// The height or epoch at which the transaction gets executed
+    pub target: ScheduleTarget,
+}
+
+impl Schedule {
+    /// Hash this schedule section
+    pub fn hash<'a>(&self, hasher: &'a mut Sha256) -> &'a mut Sha256 {
+        hasher.update(
+            self.try_to_vec()
+                .expect("unable to serialize schedule section"),
+        );
+        hasher
+    }
+}
+
 #[derive(
     Clone,
     Debug,
@@ -917,6 +1002,11 @@ pub enum Section {
     MaspBuilder(MaspBuilder),
     /// Wrap a header with a section for the purposes of computing hashes
     Header(Header),
+    /// An ordered list of inner transactions to be applied atomically
+    Batch(Batch),
+    /// Restrict the execution of the transaction to the scheduled
+    /// transactions
+    Schedule(Schedule),
 }
 
 impl Section {
@@ -941,6 +1031,8 @@ impl Section {
                 hasher
             }
             Self::Header(header) => header.hash(hasher),
+            Self::Batch(batch) => batch.hash(hasher),
+            Self::Schedule(schedule) => schedule.hash(hasher),
         }
     }
 
@@ -1040,6 +1132,24 @@ impl Section {
             None
         }
     }
+
+    /// Extract the batch from this section if possible
+    pub fn batch(&self) -> Option<Batch> {
+        if let Self::Batch(data) = self {
+            Some(data.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Extract the schedule from this section if possible
+    pub fn schedule(&self) -> Option<Schedule> {
+        if let Self::Schedule(data) = self {
+            Some(data.clone())
+        } else {
+            None
+        }
+    }
 }
 
 /// A Namada transaction header indicating where transaction subcomponents can
@@ -1319,6 +1429,78 @@ impl Tx {
         }
     }
 
+    /// Add the given steps to the transaction as a batch and set the code
+    /// and data hashes in the header to the hash of the batch section
+    pub fn set_batch(&mut self, steps: Vec<(Code, Data)>) -> &mut Section {
+        let steps = steps
+            .into_iter()
+            .map(|(code, data)| {
+                let code_hash =
+                    self.add_section(Section::Code(code)).get_hash();
+                let data_hash =
+                    self.add_section(Section::Data(data)).get_hash();
+                BatchStep {
+                    code_hash,
+                    data_hash,
+                }
+            })
+            .collect();
+        let sec = Section::Batch(Batch::new(steps));
+        self.set_code_sechash(sec.get_hash());
+        self.set_data_sechash(sec.get_hash());
+        self.sections.push(sec);
+        self.sections.last_mut().unwrap()
+    }
+
+    /// Get the batch designated by the transaction code hash in the header,
+    /// if this is a batch transaction
+    pub fn batch(&self) -> Option<Batch> {
+        match self
+            .get_section(self.code_sechash())
+            .as_ref()
+            .map(Cow::as_ref)
+        {
+            Some(Section::Batch(batch)) => Some(batch.clone()),
+            _ => None,
+        }
+    }
+
+    /// Mark this transaction as only executable as a scheduled transaction at
+    /// the given height or epoch
+    pub fn set_schedule(&mut self, target: ScheduleTarget) -> &mut Section {
+        self.sections
+            .retain(|sec| !matches!(sec, Section::Schedule(_)));
+        self.add_section(Section::Schedule(Schedule { target }))
+    }
+
+    /// Get the height or epoch this transaction is scheduled for, if it can
+    /// only be executed as a scheduled transaction
+    pub fn schedule_target(&self) -> Option<ScheduleTarget> {
+        self.sections
+            .iter()
+            .find_map(Section::schedule)
+            .map(|schedule| schedule.target)
+    }
+
+    /// Split a batch transaction into one transaction per step. The header of
+    /// each step transaction points to the code and data sections of the step,
+    /// all the other sections are preserved. Returns [`None`] if this is not
+    /// a batch transaction.
+    pub fn batch_steps(&self) -> Option<Vec<Tx>> {
+        let batch = self.batch()?;
+        let steps = batch
+            .steps
+            .iter()
+            .map(|step| {
+                let mut tx = self.clone();
+                tx.set_code_sechash(step.code_hash);
+                tx.set_data_sechash(step.data_hash);
+                tx
+            })
+            .collect();
+        Some(steps)
+    }
+
     /// Convert this transaction into protobufs
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = vec![];
@@ -1336,7 +1518,10 @@ impl Tx {
             .sections
             .iter()
             .filter_map(|section| match section {
-                Section::Data(_) | Section::Code(_) => Some(section.get_hash()),
+                Section::Data(_)
+                | Section::Code(_)
+                | Section::Batch(_)
+                | Section::Schedule(_) => Some(section.get_hash()),
                 _ => None,
             })
             .collect::<Vec<crate::types::hash::Hash>>();
@@ -1490,7 +1675,9 @@ impl Tx {
                 self.sections.remove(i);
             }
         }
-        self.data().ok_or(WrapperTxErr::DecryptedHash)?;
+        if self.batch().is_none() {
+            self.data().ok_or(WrapperTxErr::DecryptedHash)?;
+        }
         self.get_section(self.code_sechash())
             .ok_or(WrapperTxErr::DecryptedHash)?;
         Ok(())
@@ -1739,6 +1926,38 @@ impl Tx {
         self
     }
 
+    /// Get the hash signed by the keys authorizing the inner sections of this
+    /// transaction
+    pub fn raw_sighash(&self) -> crate::types::hash::Hash {
+        MultiSignature {
+            targets: self.inner_section_targets(),
+            signatures: BTreeSet::new(),
+        }
+        .get_hash()
+    }
+
+    /// Get the hash signed by the fee payer of this transaction
+    pub fn wrapper_sighash(&self) -> crate::types::hash::Hash {
+        Signature {
+            targets: self.sechashes(),
+            signature: None,
+        }
+        .get_hash()
+    }
+
+    /// Add the signature of the fee payer over the wrapper sighash
+    pub fn add_wrapper_signature(
+        &mut self,
+        signature: common::Signature,
+    ) -> &mut Self {
+        self.protocol_filter();
+        self.add_section(Section::Signature(Signature {
+            targets: self.sechashes(),
+            signature: Some(signature),
+        }));
+        self
+    }
+
     /// Add signature
     pub fn add_signatures(
         &mut self,
@@ -1912,6 +2131,30 @@ mod tests {
         assert_eq!(dkg_from_types, dkg);
     }
 
+    #[test]
+    fn test_batch_steps() {
+        let mut tx = Tx::new(ChainId::default(), None);
+        tx.set_batch(vec![
+            (Code::new(b"code 1".to_vec()), Data::new(b"data 1".to_vec())),
+            (Code::new(b"code 2".to_vec()), Data::new(b"data 2".to_vec())),
+        ]);
+
+        // the batch section is covered by the inner section targets
+        let batch_hash = *tx.code_sechash();
+        assert_eq!(batch_hash, *tx.data_sechash());
+        assert!(tx.inner_section_targets().contains(&batch_hash));
+
+        let steps = tx.batch_steps().expect("Test failed");
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].code(), Some(b"code 1".to_vec()));
+        assert_eq!(steps[0].data(), Some(b"data 1".to_vec()));
+        assert_eq!(steps[1].code(), Some(b"code 2".to_vec()));
+        assert_eq!(steps[1].data(), Some(b"data 2".to_vec()));
+
+        // a step is not a batch itself
+        assert!(steps[0].batch_steps().is_none());
+    }
+
     /// Test that encryption and decryption are inverses.
     #[cfg(feature = "ferveo-tpke")]
     #[test]
@@ -1,12 +1,15 @@
 //! Helper structures to manage accounts
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
 use super::address::Address;
+use super::hash::Hash;
 use super::key::common;
+use super::storage::Epoch;
+use super::token;
 
 #[derive(
     Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
@@ -90,3 +93,211 @@ impl AccountPublicKeysMap {
         self.pk_to_idx.get(public_key).cloned()
     }
 }
+
+/// An optional spending policy of an account, enforced by the user VP on
+/// debits of the account's token balances
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct AccountPolicy {
+    /// The maximum amount of a token that may be debited per epoch
+    pub outflow_limits: BTreeMap<Address, token::Amount>,
+    /// If not empty, only these accounts may be credited with debited tokens
+    pub allowed_recipients: BTreeSet<Address>,
+    /// Debits of a token above these amounts require the
+    /// `large_debit_threshold` of signatures
+    pub large_debit_amounts: BTreeMap<Address, token::Amount>,
+    /// The signature threshold for large debits. When it's higher than the
+    /// account's threshold, it's also required to update the policy.
+    pub large_debit_threshold: Option<u8>,
+}
+
+/// The outflow allowance of a token limited by an account's policy
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct OutflowAllowance {
+    /// The limited token
+    pub token: Address,
+    /// The maximum amount that may be debited per epoch
+    pub limit: token::Amount,
+    /// The amount debited in the current epoch
+    pub spent: token::Amount,
+    /// The amount that can still be debited in the current epoch
+    pub remaining: token::Amount,
+}
+
+/// The authorization required for a debit from an account with respect to
+/// its spending policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebitAuthorization {
+    /// The debit violates the policy
+    Forbidden,
+    /// The debit requires the account's signature threshold
+    AccountThreshold,
+    /// The debit is large and requires a higher signature threshold
+    LargeDebitThreshold(u8),
+}
+
+/// The guardians of an account, who may jointly replace the account's keys
+/// after a delay during which the owner can cancel the recovery
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct AccountGuardians {
+    /// The addresses of the guardians
+    pub guardians: BTreeSet<Address>,
+    /// The number of guardians' approvals required for a recovery
+    pub threshold: u8,
+    /// The number of epochs between a recovery request and its execution
+    pub recovery_delay: u64,
+}
+
+/// A pending recovery of an account requested by its guardians
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct PendingRecovery {
+    /// The new public keys of the account
+    pub public_keys: Vec<common::PublicKey>,
+    /// The new signature threshold of the account
+    pub threshold: u8,
+    /// The guardians that approved the recovery
+    pub approvals: BTreeSet<Address>,
+    /// The first epoch in which the recovery can be executed
+    pub executable_from: Epoch,
+}
+
+/// The authorization required for a change of an account's pending recovery
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecoveryAuthorization {
+    /// The change is not a valid recovery step
+    Forbidden,
+    /// The change requires the account's signature threshold
+    AccountThreshold,
+    /// The change adds approvals that must be signed by these guardians
+    Guardians(BTreeSet<Address>),
+    /// The recovery has been executed and the account's keys replaced
+    Executed,
+}
+
+/// A short-lived secondary key of an account, whose signatures are only
+/// accepted for the allowed txs, up to the maximum total debits and until
+/// the expiry epoch
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct SessionKey {
+    /// The hashes of the tx codes that the key may sign
+    pub allowed_txs: BTreeSet<Hash>,
+    /// The maximum total amounts of tokens that the txs signed by the key
+    /// may debit from the account. Other tokens cannot be debited.
+    pub max_spend: BTreeMap<Address, token::Amount>,
+    /// The last epoch in which the key can be used
+    pub expiry: Epoch,
+    /// The total amounts of tokens debited by the txs signed by the key
+    pub spent: BTreeMap<Address, token::Amount>,
+}
+
+impl SessionKey {
+    /// Check that the key may sign a tx with the given code hash in the
+    /// given epoch
+    pub fn is_in_scope(&self, tx_code_hash: &Hash, epoch: Epoch) -> bool {
+        epoch <= self.expiry && self.allowed_txs.contains(tx_code_hash)
+    }
+
+    /// Get the total amount of a token debited by the txs signed by the key
+    pub fn spent(&self, token: &Address) -> token::Amount {
+        self.spent.get(token).copied().unwrap_or_default()
+    }
+
+    /// Record a debit of a token by a tx signed by the key. Returns `None` if
+    /// the total overflows.
+    pub fn record_spend(
+        &mut self,
+        token: &Address,
+        amount: token::Amount,
+    ) -> Option<()> {
+        let spent = self.spent(token).checked_add(amount)?;
+        self.spent.insert(token.clone(), spent);
+        Some(())
+    }
+
+    /// Check that an update of the key keeps its scope and only records new
+    /// debits within its maximum spend
+    pub fn is_valid_spend_update(&self, post: &SessionKey) -> bool {
+        self.allowed_txs == post.allowed_txs
+            && self.max_spend == post.max_spend
+            && self.expiry == post.expiry
+            && self
+                .spent
+                .keys()
+                .all(|token| post.spent.contains_key(token))
+            && post.spent.iter().all(|(token, spent)| {
+                *spent >= self.spent(token)
+                    && self
+                        .max_spend
+                        .get(token)
+                        .map(|max_spend| spent <= max_spend)
+                        .unwrap_or_default()
+            })
+    }
+
+    /// Check that a debit of a token by a tx signed by the key has been
+    /// recorded in the key's update, within its maximum spend
+    pub fn allows_debit(
+        &self,
+        post: &SessionKey,
+        token: &Address,
+        amount: token::Amount,
+    ) -> bool {
+        self.is_valid_spend_update(post)
+            && self
+                .spent(token)
+                .checked_add(amount)
+                .map(|spent| post.spent(token) >= spent)
+                .unwrap_or_default()
+    }
+}
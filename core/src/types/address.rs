@@ -88,6 +88,10 @@ mod internal {
         "ano::Multitoken                              ";
     pub const PGF: &str =
         "ano::Pgf                                     ";
+    pub const SCHEDULED_TXS: &str =
+        "ano::Scheduled Transactions                  ";
+    pub const VESTING: &str =
+        "ano::Vesting                                 ";
 }
 
 /// Fixed-length address strings prefix for established addresses.
@@ -250,6 +254,10 @@ impl Address {
                         internal::MULTITOKEN.to_string()
                     }
                     InternalAddress::Pgf => internal::PGF.to_string(),
+                    InternalAddress::ScheduledTxs => {
+                        internal::SCHEDULED_TXS.to_string()
+                    }
+                    InternalAddress::Vesting => internal::VESTING.to_string(),
                 };
                 debug_assert_eq!(string.len(), FIXED_LEN_STRING_BYTES);
                 string
@@ -336,6 +344,12 @@ impl Address {
                     Ok(Address::Internal(InternalAddress::Multitoken))
                 }
                 internal::PGF => Ok(Address::Internal(InternalAddress::Pgf)),
+                internal::SCHEDULED_TXS => {
+                    Ok(Address::Internal(InternalAddress::ScheduledTxs))
+                }
+                internal::VESTING => {
+                    Ok(Address::Internal(InternalAddress::Vesting))
+                }
                 _ => Err(DecodeError::InvalidInnerEncoding(
                     ErrorKind::InvalidData,
                     "Invalid internal address".to_string(),
@@ -578,6 +592,10 @@ pub enum InternalAddress {
     Multitoken,
     /// Pgf
     Pgf,
+    /// Queue of transactions scheduled for execution at a future height
+    ScheduledTxs,
+    /// Vesting schedules of the locked balances of vesting accounts
+    Vesting,
 }
 
 impl Display for InternalAddress {
@@ -599,6 +617,8 @@ impl Display for InternalAddress {
                 Self::ReplayProtection => "ReplayProtection".to_string(),
                 Self::Multitoken => "Multitoken".to_string(),
                 Self::Pgf => "PublicGoodFundings".to_string(),
+                Self::ScheduledTxs => "ScheduledTxs".to_string(),
+                Self::Vesting => "Vesting".to_string(),
             }
         )
     }
@@ -681,9 +701,11 @@ pub fn tokens() -> HashMap<Address, (&'static str, Denomination)> {
     .collect()
 }
 
-/// Temporary helper for testing, a hash map of tokens addresses with their
-/// MASP XAN incentive schedules. If the reward is (a, b) then a rewarded tokens
-/// are dispensed for every b possessed tokens.
+/// Helper for testing, a hash map of tokens addresses with the MASP XAN
+/// incentive schedules they are given in the dev and test genesis. If the
+/// reward is (a, b) then a rewarded tokens are dispensed for every b possessed
+/// tokens. On a live chain these are read from the per-token
+/// [`crate::types::token::MaspParams`] in storage.
 pub fn masp_rewards() -> HashMap<Address, (u32, u32)> {
     vec![
         (nam(), (0, 100)),
@@ -894,6 +916,8 @@ pub mod testing {
             InternalAddress::Nut(_) => {}
             InternalAddress::ReplayProtection => {}
             InternalAddress::Pgf => {}
+            InternalAddress::ScheduledTxs => {}
+            InternalAddress::Vesting => {}
             InternalAddress::Multitoken => {} /* Add new addresses in the
                                                * `prop_oneof` below. */
         };
@@ -911,6 +935,8 @@ pub mod testing {
             Just(InternalAddress::ReplayProtection),
             Just(InternalAddress::Multitoken),
             Just(InternalAddress::Pgf),
+            Just(InternalAddress::ScheduledTxs),
+            Just(InternalAddress::Vesting),
         ]
     }
 
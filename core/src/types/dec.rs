@@ -254,7 +254,9 @@ impl TryFrom<Uint> for Dec {
     fn try_from(value: Uint) -> std::result::Result<Self, Self::Error> {
         let i256 = I256::try_from(value)
             .map_err(|e| eyre!("Could not convert Uint to I256: {}", e))?;
-        Ok(Self(i256 * Uint::exp10(POS_DECIMAL_PRECISION as usize)))
+        i256.checked_mul(&I256(Uint::exp10(POS_DECIMAL_PRECISION as usize)))
+            .map(Self)
+            .ok_or_else(|| eyre!("Could not convert Uint to Dec").into())
     }
 }
 
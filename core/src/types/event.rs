@@ -0,0 +1,182 @@
+//! Custom events emitted by transactions
+
+use std::collections::BTreeMap;
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The maximum size in bytes of a custom event, counting its namespace, its
+/// name and the keys and values of its attributes
+pub const MAX_TX_EVENT_SIZE: usize = 4096;
+/// The maximum number of custom events a transaction can emit
+pub const MAX_TX_EVENTS: usize = 32;
+/// The separator of the namespace and the name of a custom event type
+pub const EVENT_TYPE_SEPARATOR: char = '/';
+/// The attribute keys that are set by the ledger on every custom event
+pub const RESERVED_ATTRIBUTE_KEYS: [&str; 2] = ["hash", "height"];
+
+#[allow(missing_docs)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("Invalid event namespace {0}")]
+    InvalidNamespace(String),
+    #[error("Invalid event name {0}")]
+    InvalidName(String),
+    #[error("Invalid event attribute key {0}")]
+    InvalidAttributeKey(String),
+    #[error("The event attribute key {0} is reserved")]
+    ReservedAttributeKey(String),
+    #[error(
+        "The event has a size of {0} bytes, the maximum is {MAX_TX_EVENT_SIZE}"
+    )]
+    EventTooLarge(usize),
+}
+
+/// A custom event emitted by a transaction. Every event is added to the block
+/// results on its own, besides the event of the applied transaction.
+///
+/// The namespace of the event is not chosen by the transaction. When the event
+/// is emitted, the ledger sets it to the hash of the code of the transaction,
+/// so that a transaction cannot emit events on behalf of another application.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct TxEvent {
+    /// The namespace of the event, i.e. the hash of the code of the
+    /// transaction that emitted it
+    pub namespace: String,
+    /// The name of the event
+    pub name: String,
+    /// The attributes of the event
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl TxEvent {
+    /// Create a new event without any attributes. The namespace is set when
+    /// the event gets emitted.
+    pub fn new(name: impl ToString) -> Self {
+        Self {
+            namespace: String::new(),
+            name: name.to_string(),
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    /// Set the namespace of the event
+    pub fn with_namespace(mut self, namespace: impl ToString) -> Self {
+        self.namespace = namespace.to_string();
+        self
+    }
+
+    /// Add an attribute to the event
+    pub fn with_attribute(
+        mut self,
+        key: impl ToString,
+        value: impl ToString,
+    ) -> Self {
+        self.attributes.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// The event type, formatted as `<namespace>/<name>`
+    pub fn event_type(&self) -> String {
+        format!("{}{}{}", self.namespace, EVENT_TYPE_SEPARATOR, self.name)
+    }
+
+    /// The size of the event in bytes
+    pub fn size(&self) -> usize {
+        self.attributes
+            .iter()
+            .fold(self.namespace.len() + self.name.len(), |acc, (k, v)| {
+                acc + k.len() + v.len()
+            })
+    }
+
+    /// Check that the namespace, the name and the attribute keys of the event
+    /// are well-formed and that the event is not too large
+    pub fn validate(&self) -> Result<(), Error> {
+        if !is_valid_identifier(&self.namespace) {
+            return Err(Error::InvalidNamespace(self.namespace.clone()));
+        }
+        if !is_valid_identifier(&self.name) {
+            return Err(Error::InvalidName(self.name.clone()));
+        }
+        for key in self.attributes.keys() {
+            if !is_valid_identifier(key) {
+                return Err(Error::InvalidAttributeKey(key.clone()));
+            }
+            if RESERVED_ATTRIBUTE_KEYS.contains(&key.as_str()) {
+                return Err(Error::ReservedAttributeKey(key.clone()));
+            }
+        }
+        let size = self.size();
+        if size > MAX_TX_EVENT_SIZE {
+            return Err(Error::EventTooLarge(size));
+        }
+        Ok(())
+    }
+}
+
+/// Check that an identifier is only made of ASCII alphanumeric characters,
+/// `_` or `-`
+fn is_valid_identifier(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tx_event_validation() {
+        let event = TxEvent::new("swap")
+            .with_namespace("my_dapp")
+            .with_attribute("pool", "nam-eth")
+            .with_attribute("amount", 100);
+        assert_eq!(event.event_type(), "my_dapp/swap");
+        assert_eq!(event.validate(), Ok(()));
+
+        // The namespace must be set
+        let event = TxEvent::new("swap");
+        assert_eq!(
+            event.validate(),
+            Err(Error::InvalidNamespace(String::new()))
+        );
+        let event = TxEvent::new("").with_namespace("my_dapp");
+        assert_eq!(event.validate(), Err(Error::InvalidName(String::new())));
+        let event = TxEvent::new("swap").with_namespace("my/dapp");
+        assert!(event.validate().is_err());
+
+        let event = TxEvent::new("swap")
+            .with_namespace("my_dapp")
+            .with_attribute("a.b", "");
+        assert_eq!(
+            event.validate(),
+            Err(Error::InvalidAttributeKey("a.b".to_string()))
+        );
+        let event = TxEvent::new("swap")
+            .with_namespace("my_dapp")
+            .with_attribute("hash", "");
+        assert_eq!(
+            event.validate(),
+            Err(Error::ReservedAttributeKey("hash".to_string()))
+        );
+
+        let event = TxEvent::new("swap")
+            .with_namespace("my_dapp")
+            .with_attribute("data", "x".repeat(MAX_TX_EVENT_SIZE));
+        assert!(matches!(event.validate(), Err(Error::EventTooLarge(_))));
+    }
+}
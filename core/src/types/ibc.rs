@@ -2,8 +2,32 @@
 
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::str::FromStr;
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use data_encoding::HEXUPPER;
+#[cfg(feature = "mainnet")]
+use masp_primitives::consensus::MainNetwork;
+#[cfg(not(feature = "mainnet"))]
+use masp_primitives::consensus::TestNetwork;
+use masp_primitives::sapling::note_encryption::{
+    try_sapling_output_recovery_with_ock, OutgoingCipherKey,
+};
+use masp_primitives::transaction::Transaction;
+use prost::Message;
+use thiserror::Error;
+
+use crate::ibc::applications::transfer::packet::PacketData;
+use crate::ibc::core::ics04_channel::msgs::PacketMsg;
+use crate::ibc::core::MsgEnvelope;
+use crate::ibc_proto::google::protobuf::Any;
+use crate::proto::Tx;
+use crate::types::address::Address;
+use crate::types::masp::PaymentAddress;
+use crate::types::token::Transfer;
+
+/// The event type of a received fungible token packet
+pub const FUNGIBLE_TOKEN_PACKET_EVENT: &str = "fungible_token_packet";
 
 /// Wrapped IbcEvent
 #[derive(
@@ -45,6 +69,136 @@ impl std::fmt::Display for IbcEvent {
     }
 }
 
+/// The network to decrypt the MASP outputs for
+#[cfg(feature = "mainnet")]
+const NETWORK: MainNetwork = MainNetwork;
+#[cfg(not(feature = "mainnet"))]
+const NETWORK: TestNetwork = TestNetwork;
+
+/// A MASP transaction and its transfer carried along an IBC transfer, either
+/// in the packet memo of an incoming packet or in an extra data section of
+/// the IBC transaction
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct IbcShieldedTransfer {
+    /// The transfer moving the tokens into or out of the MASP address
+    pub transfer: Transfer,
+    /// The MASP transaction creating or spending the notes
+    pub masp_tx: Transaction,
+    /// The transparent address refunded instead of the MASP address when a
+    /// packet sent from the shielded pool times out or fails
+    pub refund_target: Option<Address>,
+    /// The outgoing cipher keys of the outputs of the MASP transaction, in
+    /// order, disclosing that they pay the receiver of an incoming packet
+    pub output_ocks: Vec<[u8; 32]>,
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum ShieldedTransferError {
+    #[error("Decoding the hex string failed: {0}")]
+    Hex(data_encoding::DecodeError),
+    #[error("Decoding the shielded transfer failed: {0}")]
+    Decoding(std::io::Error),
+}
+
+impl IbcShieldedTransfer {
+    /// Find the shielded transfer attached to an extra data section of the
+    /// given transaction
+    pub fn from_tx(tx: &Tx) -> Option<Self> {
+        tx.sections.iter().find_map(|section| {
+            section
+                .extra_data()
+                .and_then(|data| Self::try_from_slice(&data).ok())
+        })
+    }
+
+    /// Check that every output of the MASP transaction pays the given
+    /// payment address. Each output has to decrypt with its disclosed
+    /// outgoing cipher key.
+    pub fn pays_only(&self, receiver: &PaymentAddress) -> bool {
+        let receiver =
+            masp_primitives::sapling::PaymentAddress::from(*receiver);
+        let outputs = self
+            .masp_tx
+            .sapling_bundle()
+            .map_or(&vec![], |x| &x.shielded_outputs);
+        outputs.len() == self.output_ocks.len()
+            && outputs.iter().zip(&self.output_ocks).all(|(output, ock)| {
+                try_sapling_output_recovery_with_ock(
+                    &NETWORK,
+                    1.into(),
+                    &OutgoingCipherKey(*ock),
+                    output,
+                )
+                .map_or(false, |(_, pa, _)| pa == receiver)
+            })
+    }
+}
+
+/// The shielded transfer is encoded as an upper-case hex string of its Borsh
+/// encoding to be put into the packet memo
+impl std::fmt::Display for IbcShieldedTransfer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bytes = self
+            .try_to_vec()
+            .expect("Encoding a shielded transfer shouldn't fail");
+        write!(f, "{}", HEXUPPER.encode(&bytes))
+    }
+}
+
+impl FromStr for IbcShieldedTransfer {
+    type Err = ShieldedTransferError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = HEXUPPER
+            .decode(s.as_bytes())
+            .map_err(ShieldedTransferError::Hex)?;
+        Self::try_from_slice(&bytes).map_err(ShieldedTransferError::Decoding)
+    }
+}
+
+/// Check if the receiver of a packet is a MASP payment address
+pub fn is_shielded_receiver(receiver: impl AsRef<str>) -> bool {
+    PaymentAddress::from_str(receiver.as_ref()).is_ok()
+}
+
+/// Get the shielded transfer from the memo of a packet received by a MASP
+/// payment address
+pub fn get_shielded_transfer(
+    memo: impl AsRef<str>,
+) -> Result<Option<IbcShieldedTransfer>, ShieldedTransferError> {
+    match memo.as_ref() {
+        "" => Ok(None),
+        memo => memo.parse().map(Some),
+    }
+}
+
+/// Get the payment address receiving the packet of the given IBC message
+/// data and the shielded transfer in the packet memo, if the message receives
+/// a packet for a MASP payment address
+pub fn get_received_shielded_transfer(
+    tx_data: &[u8],
+) -> Result<Option<(PaymentAddress, IbcShieldedTransfer)>, ShieldedTransferError>
+{
+    let msg = Any::decode(tx_data)
+        .ok()
+        .and_then(|any| MsgEnvelope::try_from(any).ok());
+    let data = match msg {
+        Some(MsgEnvelope::Packet(PacketMsg::Recv(msg))) => {
+            serde_json::from_slice::<PacketData>(&msg.packet.data).ok()
+        }
+        _ => None,
+    };
+    let Some(data) = data else {
+        return Ok(None);
+    };
+    let Ok(receiver) = PaymentAddress::from_str(data.receiver.as_ref()) else {
+        return Ok(None);
+    };
+    Ok(get_shielded_transfer(data.memo.as_ref())?
+        .map(|shielded| (receiver, shielded)))
+}
+
 #[cfg(any(feature = "abciplus", feature = "abcipp"))]
 mod ibc_rs_conversion {
     use std::collections::HashMap;
@@ -17,6 +17,8 @@ use super::{
     VerifySigError,
 };
 use crate::types::ethereum_events::EthAddress;
+use crate::types::hash::Hash;
+use crate::types::keccak::keccak_hash;
 use crate::types::key::{SignableBytes, StorageHasher};
 
 /// Public key
@@ -368,3 +370,74 @@ impl super::SigScheme for SigScheme {
         }
     }
 }
+
+/// The hashing applied to a message before its signature gets verified by
+/// [`verify_signature_with_mode`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SigHashMode {
+    /// The message is verified as is. Ed25519 signatures are made over the
+    /// whole message, while secp256k1 signatures can only be made over a 32
+    /// bytes digest, so the message must be the digest that was signed.
+    Raw,
+    /// The message is hashed with sha256, like the signatures made with
+    /// [`super::SigScheme::sign`]
+    Sha256,
+    /// The message is hashed with keccak256, like the Ethereum signatures
+    Keccak256,
+}
+
+impl From<SigHashMode> for u64 {
+    fn from(mode: SigHashMode) -> Self {
+        match mode {
+            SigHashMode::Raw => 0,
+            SigHashMode::Sha256 => 1,
+            SigHashMode::Keccak256 => 2,
+        }
+    }
+}
+
+impl TryFrom<u64> for SigHashMode {
+    type Error = u64;
+
+    fn try_from(mode: u64) -> Result<Self, Self::Error> {
+        match mode {
+            0 => Ok(SigHashMode::Raw),
+            1 => Ok(SigHashMode::Sha256),
+            2 => Ok(SigHashMode::Keccak256),
+            _ => Err(mode),
+        }
+    }
+}
+
+/// Verify the signature of a message hashed according to the given mode
+pub fn verify_signature_with_mode(
+    pk: &PublicKey,
+    msg: &[u8],
+    sig: &Signature,
+    mode: SigHashMode,
+) -> Result<(), VerifySigError> {
+    match mode {
+        SigHashMode::Sha256 => SigScheme::verify_signature(pk, &msg, sig),
+        // The signable hash of a `KeccakHash` is the hash itself
+        SigHashMode::Keccak256 => {
+            SigScheme::verify_signature(pk, &keccak_hash(msg), sig)
+        }
+        SigHashMode::Raw => match (pk, sig) {
+            (PublicKey::Ed25519(pk), Signature::Ed25519(sig)) => pk
+                .0
+                .verify(&sig.0, msg)
+                .map_err(|err| VerifySigError::SigVerifyError(err.to_string())),
+            (PublicKey::Secp256k1(_), Signature::Secp256k1(_)) => {
+                let digest: [u8; 32] = msg.try_into().map_err(|_| {
+                    VerifySigError::SigVerifyError(
+                        "A raw secp256k1 message must be a 32 bytes digest"
+                            .to_string(),
+                    )
+                })?;
+                // The signable hash of a `Hash` is the hash itself
+                SigScheme::verify_signature(pk, &Hash(digest), sig)
+            }
+            _ => Err(VerifySigError::MismatchedScheme),
+        },
+    }
+}
@@ -32,6 +32,11 @@ struct Keys {
     public_keys: &'static str,
     threshold: &'static str,
     protocol_public_keys: &'static str,
+    policy: &'static str,
+    outflow: &'static str,
+    guardians: &'static str,
+    recovery: &'static str,
+    session_keys: &'static str,
 }
 
 /// Obtain a storage key for user's public key.
@@ -111,6 +116,120 @@ pub fn is_protocol_pk_key(key: &Key) -> Option<&Address> {
     }
 }
 
+/// Obtain the storage key for an account's spending policy
+pub fn policy_key(owner: &Address) -> storage::Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(owner.to_owned()),
+            DbKeySeg::StringSeg(Keys::VALUES.policy.to_string()),
+        ],
+    }
+}
+
+/// Check if the given storage key is an account's spending policy. If it is,
+/// returns the owner.
+pub fn is_policy_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [DbKeySeg::AddressSeg(owner), DbKeySeg::StringSeg(key)]
+            if key.as_str() == Keys::VALUES.policy =>
+        {
+            Some(owner)
+        }
+        _ => None,
+    }
+}
+
+/// Obtain the storage key for the amount of a token debited from an account
+/// in an epoch, as tracked for its spending policy
+pub fn outflow_key(owner: &Address, token: &Address) -> storage::Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(owner.to_owned()),
+            DbKeySeg::StringSeg(Keys::VALUES.outflow.to_string()),
+            DbKeySeg::AddressSeg(token.to_owned()),
+        ],
+    }
+}
+
+/// Check if the given storage key is an account's outflow of a token. If it
+/// is, returns the owner and the token.
+pub fn is_outflow_key(key: &Key) -> Option<[&Address; 2]> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(owner),
+            DbKeySeg::StringSeg(key),
+            DbKeySeg::AddressSeg(token),
+        ] if key.as_str() == Keys::VALUES.outflow => Some([owner, token]),
+        _ => None,
+    }
+}
+
+/// Obtain the storage key for an account's guardians
+pub fn guardians_key(owner: &Address) -> storage::Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(owner.to_owned()),
+            DbKeySeg::StringSeg(Keys::VALUES.guardians.to_string()),
+        ],
+    }
+}
+
+/// Obtain the storage key for an account's pending recovery
+pub fn recovery_key(owner: &Address) -> storage::Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(owner.to_owned()),
+            DbKeySeg::StringSeg(Keys::VALUES.recovery.to_string()),
+        ],
+    }
+}
+
+/// Check if the given storage key is an account's pending recovery. If it
+/// is, returns the owner.
+pub fn is_recovery_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [DbKeySeg::AddressSeg(owner), DbKeySeg::StringSeg(key)]
+            if key.as_str() == Keys::VALUES.recovery =>
+        {
+            Some(owner)
+        }
+        _ => None,
+    }
+}
+
+/// Obtain the storage key prefix for an account's session keys
+pub fn session_keys_prefix(owner: &Address) -> storage::Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(owner.to_owned()),
+            DbKeySeg::StringSeg(Keys::VALUES.session_keys.to_string()),
+        ],
+    }
+}
+
+/// Obtain the storage key for the scope of an account's session key
+pub fn session_key_key(
+    owner: &Address,
+    public_key: &common::PublicKey,
+) -> storage::Key {
+    session_keys_prefix(owner)
+        .push(public_key)
+        .expect("Cannot obtain a storage key")
+}
+
+/// Check if the given storage key is an account's session key. If it is,
+/// returns the owner.
+pub fn is_session_key_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(owner),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(_public_key),
+        ] if prefix.as_str() == Keys::VALUES.session_keys => Some(owner),
+        _ => None,
+    }
+}
+
 /// Represents an error in signature verification
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
@@ -672,6 +791,95 @@ sigscheme_test! {secp256k1_test, secp256k1::SigScheme}
 mod more_tests {
     use super::*;
 
+    #[test]
+    fn test_verify_signature_with_mode() {
+        use common::{verify_signature_with_mode, SigHashMode};
+
+        use crate::types::hash::Hash as Sha256Hash;
+        use crate::types::keccak::keccak_hash;
+
+        let msg = b"some arbitrary message";
+        let ed25519_sk = testing::keypair_1();
+        let secp256k1_sk = testing::keypair_3();
+
+        // sha256 is the hashing of the signatures made with `sign`
+        for sk in [&ed25519_sk, &secp256k1_sk] {
+            let sig = common::SigScheme::sign(sk, msg);
+            let pk = sk.ref_to();
+            assert!(
+                verify_signature_with_mode(&pk, msg, &sig, SigHashMode::Sha256)
+                    .is_ok()
+            );
+            assert!(
+                verify_signature_with_mode(
+                    &pk,
+                    msg,
+                    &sig,
+                    SigHashMode::Keccak256
+                )
+                .is_err()
+            );
+        }
+
+        // Ethereum signatures are made over the keccak256 hash of the message
+        let sig = common::SigScheme::sign(&secp256k1_sk, keccak_hash(msg));
+        let pk = secp256k1_sk.ref_to();
+        assert!(
+            verify_signature_with_mode(&pk, msg, &sig, SigHashMode::Keccak256)
+                .is_ok()
+        );
+        assert!(
+            verify_signature_with_mode(&pk, msg, &sig, SigHashMode::Sha256)
+                .is_err()
+        );
+        // a raw secp256k1 message is the digest that was signed
+        let digest = keccak_hash(msg).0;
+        assert!(
+            verify_signature_with_mode(&pk, &digest, &sig, SigHashMode::Raw)
+                .is_ok()
+        );
+        assert!(
+            verify_signature_with_mode(&pk, msg, &sig, SigHashMode::Raw)
+                .is_err()
+        );
+
+        // raw ed25519 signatures are made over the whole message
+        let common::SecretKey::Ed25519(raw_sk) = &ed25519_sk else {
+            unreachable!()
+        };
+        let sig = common::Signature::Ed25519(ed25519::Signature(
+            raw_sk.0.sign(msg),
+        ));
+        let pk = ed25519_sk.ref_to();
+        assert!(
+            verify_signature_with_mode(&pk, msg, &sig, SigHashMode::Raw)
+                .is_ok()
+        );
+        assert!(
+            verify_signature_with_mode(&pk, msg, &sig, SigHashMode::Sha256)
+                .is_err()
+        );
+        let digest = Sha256Hash::sha256(msg).0;
+        assert!(
+            verify_signature_with_mode(&pk, &digest, &sig, SigHashMode::Raw)
+                .is_err()
+        );
+
+        // mismatched schemes are rejected
+        let sig = common::SigScheme::sign(&secp256k1_sk, Sha256Hash(digest));
+        assert!(matches!(
+            verify_signature_with_mode(&pk, &digest, &sig, SigHashMode::Raw),
+            Err(VerifySigError::MismatchedScheme)
+        ));
+
+        for mode in
+            [SigHashMode::Raw, SigHashMode::Sha256, SigHashMode::Keccak256]
+        {
+            assert_eq!(SigHashMode::try_from(u64::from(mode)), Ok(mode));
+        }
+        assert_eq!(SigHashMode::try_from(3), Err(3));
+    }
+
     #[test]
     fn zeroize_keypair_ed25519() {
         use rand::thread_rng;
@@ -346,6 +346,9 @@ pub enum TransferSource {
     Address(Address),
     /// A transfer coming from a shielded address
     ExtendedSpendingKey(ExtendedSpendingKey),
+    /// A transfer coming from a shielded address whose spending key is held
+    /// by an external signer
+    ExtendedViewingKey(ExtendedViewingKey),
 }
 
 impl TransferSource {
@@ -353,9 +356,11 @@ impl TransferSource {
     pub fn effective_address(&self) -> Address {
         match self {
             Self::Address(x) => x.clone(),
-            // An ExtendedSpendingKey for a source effectively means that
-            // assets will be drawn from the MASP
-            Self::ExtendedSpendingKey(_) => masp(),
+            // A shielded source effectively means that assets will be drawn
+            // from the MASP
+            Self::ExtendedSpendingKey(_) | Self::ExtendedViewingKey(_) => {
+                masp()
+            }
         }
     }
 
@@ -381,6 +386,7 @@ impl Display for TransferSource {
         match self {
             Self::Address(x) => x.fmt(f),
             Self::ExtendedSpendingKey(x) => x.fmt(f),
+            Self::ExtendedViewingKey(x) => x.fmt(f),
         }
     }
 }
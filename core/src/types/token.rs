@@ -810,6 +810,12 @@ pub const TX_KEY_PREFIX: &str = "tx-";
 pub const CONVERSION_KEY_PREFIX: &str = "conv";
 /// Key segment prefix for pinned shielded transactions
 pub const PIN_KEY_PREFIX: &str = "pin-";
+/// Key segment prefix for the shielded reward parameters of tokens
+pub const MASP_PARAMS_STORAGE_KEY: &str = "masp_params";
+/// Key segment for the last locked ratio of a token in the shielded pool
+pub const MASP_LAST_LOCKED_RATIO_KEY: &str = "masp_last_locked_ratio";
+/// Key segment for the last inflation of a token's shielded rewards
+pub const MASP_LAST_INFLATION_KEY: &str = "masp_last_inflation";
 
 /// Obtain a storage key for user's balance.
 pub fn balance_key(token_addr: &Address, owner: &Address) -> Key {
@@ -843,6 +849,41 @@ pub fn minted_balance_key(token_addr: &Address) -> Key {
         .expect("Cannot obtain a storage key")
 }
 
+/// Obtain a storage key prefix for the shielded reward parameters of all
+/// tokens.
+pub fn masp_params_prefix() -> Key {
+    Key::from(Address::Internal(InternalAddress::Multitoken).to_db_key())
+        .push(&MASP_PARAMS_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Obtain a storage key for the shielded reward parameters of a token.
+pub fn masp_params_key(token_addr: &Address) -> Key {
+    masp_params_prefix()
+        .push(&token_addr.to_db_key())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Obtain a storage key for the last locked ratio of a token in the shielded
+/// pool, used by the shielded rewards controller.
+pub fn masp_last_locked_ratio_key(token_addr: &Address) -> Key {
+    Key::from(Address::Internal(InternalAddress::Multitoken).to_db_key())
+        .push(&token_addr.to_db_key())
+        .expect("Cannot obtain a storage key")
+        .push(&MASP_LAST_LOCKED_RATIO_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Obtain a storage key for the last inflation of a token's shielded rewards,
+/// used by the shielded rewards controller.
+pub fn masp_last_inflation_key(token_addr: &Address) -> Key {
+    Key::from(Address::Internal(InternalAddress::Multitoken).to_db_key())
+        .push(&token_addr.to_db_key())
+        .expect("Cannot obtain a storage key")
+        .push(&MASP_LAST_INFLATION_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
 /// Check if the given storage key is balance key for the given token. If it is,
 /// returns the owner. For minted balances, use [`is_any_minted_balance_key()`].
 pub fn is_balance_key<'a>(
@@ -946,6 +987,100 @@ pub fn is_any_minted_balance_key(key: &Key) -> Option<&Address> {
     }
 }
 
+/// Check if the given storage key is for the shielded reward parameters of a
+/// token. If it is, returns the token.
+pub fn is_any_masp_params_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(params),
+            DbKeySeg::AddressSeg(token),
+        ] if *addr == Address::Internal(InternalAddress::Multitoken)
+            && params == MASP_PARAMS_STORAGE_KEY =>
+        {
+            Some(token)
+        }
+        _ => None,
+    }
+}
+
+/// The shielded (MASP) reward parameters of a token. They can only be changed
+/// by governance.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct MaspParams {
+    /// The reward rate `(a, b)` dispenses `a` units of the native token every
+    /// epoch for every `b` units of the token in the shielded pool. With a
+    /// controller, `a` is derived every epoch and only `b` is used.
+    pub reward_rate: (u32, u32),
+    /// An optional PD controller adjusting the reward rate every epoch
+    #[serde(default)]
+    pub controller: Option<MaspRewardsController>,
+}
+
+impl MaspParams {
+    /// Check that the parameters can be used in the conversions. The reward
+    /// rate's terms must fit in an `i32` and its denominator must be positive.
+    pub fn is_valid(&self) -> bool {
+        let (reward, precision) = self.reward_rate;
+        let max = i32::MAX as u32;
+        precision > 0
+            && precision <= max
+            && reward <= max
+            && self
+                .controller
+                .as_ref()
+                .map_or(true, MaspRewardsController::is_valid)
+    }
+}
+
+/// The targets and gains of the PD controller of a token's shielded rewards
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct MaspRewardsController {
+    /// The targeted ratio of the token's supply in the shielded pool
+    pub locked_ratio_target: Dec,
+    /// The maximum annual reward rate, capping the rewards of an epoch
+    pub max_reward_rate: Dec,
+    /// The nominal proportional gain
+    pub kp_gain_nom: Dec,
+    /// The nominal derivative gain
+    pub kd_gain_nom: Dec,
+}
+
+impl MaspRewardsController {
+    /// Check that the target, the maximum reward rate and the gains are all
+    /// between 0 and 1
+    pub fn is_valid(&self) -> bool {
+        [
+            &self.locked_ratio_target,
+            &self.max_reward_rate,
+            &self.kp_gain_nom,
+            &self.kd_gain_nom,
+        ]
+        .into_iter()
+        .all(|value| !value.is_negative() && *value <= Dec::one())
+    }
+}
+
 /// A simple bilateral token transfer
 #[derive(
     Debug,
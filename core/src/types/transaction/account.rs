@@ -1,6 +1,7 @@
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
+use crate::types::account::{AccountGuardians, AccountPolicy, SessionKey};
 use crate::types::address::Address;
 use crate::types::hash::Hash;
 use crate::types::key::common;
@@ -49,4 +50,80 @@ pub struct UpdateAccount {
     pub public_keys: Vec<common::PublicKey>,
     /// The account signature threshold
     pub threshold: Option<u8>,
+    /// The new spending policy of the account. An empty policy removes it.
+    pub policy: Option<AccountPolicy>,
+    /// The new guardians of the account. An empty guardian set removes them.
+    pub guardians: Option<AccountGuardians>,
+}
+
+/// A step of the guardian-based recovery of an account
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub enum RecoveryAction {
+    /// Request the replacement of the account's keys, approved by the
+    /// requesting guardian
+    Request {
+        /// The requesting guardian
+        guardian: Address,
+        /// The new public keys of the account
+        public_keys: Vec<common::PublicKey>,
+        /// The new signature threshold of the account
+        threshold: u8,
+    },
+    /// Approve the pending recovery
+    Approve {
+        /// The approving guardian
+        guardian: Address,
+    },
+    /// Cancel the pending recovery, which has to be signed by the account
+    Cancel,
+    /// Replace the account's keys with the approved recovery once its delay
+    /// has passed
+    Execute,
+}
+
+/// A tx data type to recover an account with the help of its guardians
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct RecoverAccount {
+    /// The address of the recovered account
+    pub owner: Address,
+    /// The recovery step
+    pub action: RecoveryAction,
+}
+
+/// A tx data type to add or revoke a session key of an account
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct UpdateSessionKey {
+    /// The address of the account
+    pub owner: Address,
+    /// The public key of the session key
+    pub public_key: common::PublicKey,
+    /// The scope of the session key, or `None` to revoke it
+    pub session_key: Option<SessionKey>,
 }
@@ -8,7 +8,7 @@ use crate::ledger::governance::cli::onchain::{
     DefaultProposal, PgfFundingProposal, PgfStewardProposal,
 };
 use crate::ledger::governance::storage::proposal::{
-    AddRemove, PGFAction, PGFTarget, ProposalType,
+    AddRemove, PGFAction, PGFContinuousFunding, ProposalType,
 };
 use crate::ledger::governance::storage::vote::StorageProposalVote;
 use crate::types::address::Address;
@@ -125,14 +125,12 @@ impl TryFrom<PgfFundingProposal> for InitProposalData {
             .iter()
             .cloned()
             .map(|funding| {
-                let target = PGFTarget {
-                    target: funding.address,
-                    amount: funding.amount,
-                };
-                if funding.amount.is_zero() {
-                    PGFAction::Continuous(AddRemove::Remove(target))
+                let is_removal = funding.target.amount().is_zero();
+                let funding = PGFContinuousFunding::from(funding);
+                if is_removal {
+                    PGFAction::Continuous(AddRemove::Remove(funding))
                 } else {
-                    PGFAction::Continuous(AddRemove::Add(target))
+                    PGFAction::Continuous(AddRemove::Add(funding))
                 }
             })
             .collect::<Vec<PGFAction>>();
@@ -142,13 +140,7 @@ impl TryFrom<PgfFundingProposal> for InitProposalData {
             .retro
             .iter()
             .cloned()
-            .map(|funding| {
-                let target = PGFTarget {
-                    target: funding.address,
-                    amount: funding.amount,
-                };
-                PGFAction::Retro(target)
-            })
+            .map(|target| PGFAction::Retro(target.into()))
             .collect::<Vec<PGFAction>>();
 
         let extra_data = [continous_fundings, retro_fundings].concat();
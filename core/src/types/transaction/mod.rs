@@ -15,6 +15,8 @@ pub mod pgf;
 pub mod pos;
 /// transaction protocols made by validators
 pub mod protocol;
+/// txs to schedule the execution of other txs
+pub mod scheduled;
 /// wrapper txs with encrypted payloads
 pub mod wrapper;
 
@@ -30,8 +32,9 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 pub use wrapper::*;
 
-use crate::ledger::gas::{Gas, VpsGas};
+use crate::ledger::gas::{Gas, GasProfile, VpsGas};
 use crate::types::address::Address;
+use crate::types::event::TxEvent;
 use crate::types::hash::Hash;
 use crate::types::ibc::IbcEvent;
 use crate::types::storage;
@@ -58,6 +61,17 @@ pub struct TxResult {
     pub initialized_accounts: Vec<Address>,
     /// IBC events emitted by the transaction
     pub ibc_events: BTreeSet<IbcEvent>,
+    /// Custom events emitted by the transaction, in order of emission
+    pub tx_events: Vec<TxEvent>,
+    /// The results of the individual steps of a batch transaction, in order
+    /// of execution. Empty for non-batch transactions.
+    pub batch_results: Vec<TxResult>,
+    /// The position of this step in its batch transaction, only set for the
+    /// results of the steps of a batch
+    pub batch_step: Option<u64>,
+    /// The breakdown of the gas used by the transaction, only recorded when
+    /// dry-running a transaction
+    pub gas_profile: Option<GasProfile>,
 }
 
 impl TxResult {
@@ -79,10 +93,16 @@ pub struct VpsResult {
     pub gas_used: VpsGas,
     /// Errors occurred in any of the VPs, if any
     pub errors: Vec<(Address, String)>,
+    /// The breakdown of the gas used by the VPs, only recorded when
+    /// dry-running a transaction
+    pub gas_profile: Option<GasProfile>,
 }
 
 impl fmt::Display for TxResult {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(step) = self.batch_step {
+            write!(f, "Batch step {}: ", step)?;
+        }
         write!(
             f,
             "Transaction is {}. Gas used: {};{} VPs result: {}",
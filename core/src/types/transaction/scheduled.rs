@@ -0,0 +1,92 @@
+//! Types used by transactions that schedule other transactions for execution
+//! at a future block height or epoch.
+
+use std::fmt;
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+use crate::proto::Tx;
+use crate::types::address::Address;
+use crate::types::hash::Hash;
+use crate::types::storage::{BlockHeight, Epoch};
+use crate::types::token::Amount;
+use crate::types::transaction::{Fee, GasLimit, TxType};
+
+/// The point at which a scheduled transaction gets executed
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub enum ScheduleTarget {
+    /// At the end of the block at the given height
+    Height(BlockHeight),
+    /// At the end of the first block of the given epoch
+    Epoch(Epoch),
+}
+
+impl fmt::Display for ScheduleTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Height(height) => write!(f, "height {}", height),
+            Self::Epoch(epoch) => write!(f, "epoch {}", epoch),
+        }
+    }
+}
+
+/// A transaction queued for execution by the protocol at a given block
+/// height or epoch. The fees for the execution are escrowed when the transaction is
+/// scheduled and paid to the block proposer when it gets executed.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct ScheduledTx {
+    /// The account that scheduled the tx, pays for its execution and is
+    /// allowed to cancel it
+    pub owner: Address,
+    /// The height or epoch at which the tx is executed
+    pub target: ScheduleTarget,
+    /// The fee paid for the execution
+    pub fee: Fee,
+    /// The gas limit of the execution
+    pub gas_limit: GasLimit,
+    /// The signed inner tx to be executed
+    pub tx: Tx,
+}
+
+impl ScheduledTx {
+    /// Get the hash of the scheduled inner tx. This is the same hash that is
+    /// used for the replay protection of inner txs.
+    pub fn payload_hash(&self) -> Hash {
+        let mut tx = self.tx.clone();
+        tx.update_header(TxType::Raw).header_hash()
+    }
+
+    /// Get the amount of the fee token to be escrowed for the execution of
+    /// the tx. Returns `None` if the amount overflows.
+    pub fn escrow(&self) -> Option<Amount> {
+        self.fee
+            .amount_per_gas_unit
+            .checked_mul(self.gas_limit.into())
+    }
+}
+
+/// A tx data type to cancel a scheduled transaction
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct CancelScheduledTx {
+    /// The owner of the scheduled tx
+    pub owner: Address,
+    /// The height or epoch at which the tx was scheduled
+    pub target: ScheduleTarget,
+    /// The hash of the scheduled inner tx
+    pub hash: Hash,
+}
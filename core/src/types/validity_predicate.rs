@@ -6,6 +6,10 @@ use serde::{Deserialize, Serialize};
 use crate::proto::Tx;
 use crate::types::hash::Hash;
 
+/// The reserved VP code hash that selects the native user VP. It's not the
+/// hash of any wasm code.
+pub const NATIVE_VP_USER_CODE_HASH: Hash = Hash([0xff; 32]);
+
 /// A validity predicate with an input that is intended to be invoked via `eval`
 /// host function.
 #[derive(
@@ -88,6 +88,81 @@ pub fn transaction(attr: TokenStream, input: TokenStream) -> TokenStream {
     TokenStream::from(gen)
 }
 
+/// Generate WASM binding for a wasm code that can be called from transactions.
+///
+/// This macro expects a function with signature:
+///
+/// ```compiler_fail
+/// fn call(
+///     ctx: &mut Ctx,
+///     input: Vec<u8>
+/// ) -> EnvResult<Vec<u8>>
+/// ```
+///
+/// The returned bytes are given back to the calling transaction. A module
+/// cannot use both this macro and the `#[transaction]` macro.
+#[proc_macro_attribute]
+pub fn tx_call(attr: TokenStream, input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as ItemFn);
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = ast;
+    let stmts = &block.stmts;
+    let ident = &sig.ident;
+    let attr_ast = parse_macro_input!(attr as ExprAssign);
+    let gas = attr_ast.right;
+    let ctx = match sig.inputs.first() {
+        Some(FnArg::Typed(pat_type)) => {
+            if let Pat::Ident(pat_ident) = pat_type.pat.as_ref() {
+                &pat_ident.ident
+            } else {
+                panic!("Unexpected token, expected ctx ident")
+            }
+        }
+        _ => panic!("Unexpected token, expected ctx ident"),
+    };
+    let gen = quote! {
+        // Use `wee_alloc` as the global allocator.
+        #[global_allocator]
+        static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
+
+        #(#attrs)* #vis #sig {
+            // Consume the whitelisted gas
+            #ctx.charge_gas(#gas)?;
+            #(#stmts)*
+        }
+
+        // The module entrypoint callable from transactions
+        #[no_mangle]
+        extern "C" fn _call_tx(input_ptr: u64, input_len: u64) {
+            let input = unsafe {
+                core::slice::from_raw_parts(
+                    input_ptr as *const u8,
+                    input_len as _,
+                )
+            }
+            .to_vec();
+
+            // The context is provided by the VM of the calling transaction,
+            // like for the `#[transaction]` macro we're "faking" it.
+            let mut ctx = unsafe { namada_tx_prelude::Ctx::new() };
+
+            match #ident(&mut ctx, input) {
+                Ok(output) => namada_tx_prelude::set_call_output(&output),
+                Err(err) => {
+                    namada_tx_prelude::debug_log!("Call error: {}", err);
+                    // crash the call to abort the calling transaction
+                    panic!();
+                }
+            }
+        }
+    };
+    TokenStream::from(gen)
+}
+
 /// Generate WASM binding for validity predicate main entrypoint function.
 ///
 /// It expects an attribute in the form: `gas = u64`, so that a call to the gas
@@ -2446,6 +2446,7 @@ where
         storage
             .write(&dest_key, dest_balance)
             .expect("Unable to write token balance for PoS system");
+        storage_api::account::record_outflow(storage, src, token, amount)?;
     } else {
         tracing::error!("PoS system transfer error, the source has no balance");
     }
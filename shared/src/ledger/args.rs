@@ -12,11 +12,13 @@ use serde::{Deserialize, Serialize};
 use zeroize::Zeroizing;
 
 use crate::ibc::core::ics24_host::identifier::{ChannelId, PortId};
+use crate::ledger::masp::PaymentDisclosure;
 use crate::types::address::Address;
 use crate::types::keccak::KeccakHash;
 use crate::types::key::{common, SchemeType};
 use crate::types::masp::MaspValue;
 use crate::types::storage::Epoch;
+use crate::types::transaction::scheduled::ScheduleTarget;
 use crate::types::transaction::GasLimit;
 use crate::types::{storage, token};
 
@@ -159,8 +161,8 @@ pub enum InputAmount {
 pub struct TxIbcTransfer<C: NamadaTypes = SdkTypes> {
     /// Common tx arguments
     pub tx: Tx<C>,
-    /// Transfer source address
-    pub source: C::Address,
+    /// Transfer source address or a spending key to send shielded tokens
+    pub source: C::TransferSource,
     /// Transfer target address
     pub receiver: String,
     /// Transferred token addres    s
@@ -177,10 +179,33 @@ pub struct TxIbcTransfer<C: NamadaTypes = SdkTypes> {
     pub timeout_sec_offset: Option<u64>,
     /// Memo
     pub memo: Option<String>,
+    /// The transparent address refunded when the tokens sent from the
+    /// shielded pool are returned
+    pub refund_target: Option<C::Address>,
+    /// Native token address
+    pub native_token: C::NativeAddress,
     /// Path to the TX WASM code file
     pub tx_code_path: PathBuf,
 }
 
+/// Generate the shielded transfer for an IBC packet received by a MASP
+/// payment address
+#[derive(Clone, Debug)]
+pub struct GenIbcShieldedTransfer<C: NamadaTypes = SdkTypes> {
+    /// The query parameters
+    pub query: Query<C>,
+    /// The payment address receiving the tokens
+    pub target: C::TransferTarget,
+    /// The token address on this chain or the denom on the sending chain
+    pub token: String,
+    /// Transferred token amount
+    pub amount: InputAmount,
+    /// Port ID of the receiving channel on this chain
+    pub port_id: PortId,
+    /// Channel ID of the receiving channel on this chain
+    pub channel_id: ChannelId,
+}
+
 /// Transaction to initialize create a new proposal
 #[derive(Clone, Debug)]
 pub struct InitProposal<C: NamadaTypes = SdkTypes> {
@@ -280,6 +305,25 @@ pub struct TxUpdateAccount<C: NamadaTypes = SdkTypes> {
     pub public_keys: Vec<C::PublicKey>,
     /// The account threshold
     pub threshold: Option<u8>,
+    /// Per-epoch outflow limits of tokens to set in the account's policy
+    pub outflow_limits: Vec<(C::Address, token::DenominatedAmount)>,
+    /// Recipients allowed by the account's policy
+    pub allowed_recipients: Vec<C::Address>,
+    /// Debit amounts of tokens above which the account's policy requires
+    /// the large debit threshold
+    pub large_debit_amounts: Vec<(C::Address, token::DenominatedAmount)>,
+    /// The signature threshold for large debits in the account's policy
+    pub large_debit_threshold: Option<u8>,
+    /// Remove the account's policy
+    pub clear_policy: bool,
+    /// Guardians of the account that may jointly replace its keys
+    pub guardians: Vec<C::Address>,
+    /// The number of guardians' approvals required for a recovery
+    pub guardian_threshold: Option<u8>,
+    /// The number of epochs between a recovery request and its execution
+    pub recovery_delay: Option<u64>,
+    /// Remove the account's guardians
+    pub clear_guardians: bool,
 }
 
 /// Bond arguments
@@ -348,6 +392,15 @@ pub struct QueryPgf<C: NamadaTypes = SdkTypes> {
     pub query: Query<C>,
 }
 
+/// Query the vesting balances of an account
+#[derive(Clone, Debug)]
+pub struct QueryVesting<C: NamadaTypes = SdkTypes> {
+    /// Common query args
+    pub query: Query<C>,
+    /// Address of the vesting account
+    pub owner: C::Address,
+}
+
 /// Withdraw arguments
 #[derive(Clone, Debug)]
 pub struct Withdraw<C: NamadaTypes = SdkTypes> {
@@ -406,6 +459,64 @@ pub struct QueryTransfers<C: NamadaTypes = SdkTypes> {
     pub token: Option<C::Address>,
 }
 
+/// Export the shielded history of a viewing key
+#[derive(Clone, Debug)]
+pub struct ShieldedHistory<C: NamadaTypes = SdkTypes> {
+    /// Common query args
+    pub query: Query<C>,
+    /// The viewing key whose notes are reported
+    pub viewing_key: C::ViewingKey,
+    /// The format of the report
+    pub format: HistoryFormat,
+    /// The file to write the report to instead of the standard output
+    pub output: Option<PathBuf>,
+}
+
+/// The format of an exported shielded history
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryFormat {
+    /// One row per note event and token, with a header row
+    Csv,
+    /// An array of note events
+    Json,
+}
+
+impl std::str::FromStr for HistoryFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "Unexpected history format {s}, expected \"csv\" or \"json\""
+            )),
+        }
+    }
+}
+
+/// Disclose the shielded payments made in a transaction
+#[derive(Clone, Debug)]
+pub struct DisclosePayment<C: NamadaTypes = SdkTypes> {
+    /// Common query args
+    pub query: Query<C>,
+    /// The viewing key of the account that made the payments
+    pub viewing_key: C::ViewingKey,
+    /// Height of the block containing the transaction
+    pub height: storage::BlockHeight,
+    /// Index of the transaction within its block
+    pub tx_index: storage::TxIndex,
+}
+
+/// Verify a shielded payment disclosure against the chain
+#[derive(Clone, Debug)]
+pub struct VerifyDisclosure<C: NamadaTypes = SdkTypes> {
+    /// Common query args
+    pub query: Query<C>,
+    /// The disclosure to verify
+    pub disclosure: PaymentDisclosure,
+}
+
 /// Query PoS bond(s)
 #[derive(Clone, Debug)]
 pub struct QueryBonds<C: NamadaTypes = SdkTypes> {
@@ -476,6 +587,93 @@ pub struct ResignSteward<C: NamadaTypes = SdkTypes> {
     pub tx_code_path: PathBuf,
 }
 
+#[derive(Clone, Debug)]
+/// Schedule a transaction for execution at a future block height or epoch
+/// args
+pub struct ScheduleTx<C: NamadaTypes = SdkTypes> {
+    /// Common tx arguments
+    pub tx: Tx<C>,
+    /// The owner of the scheduled tx, paying for its execution
+    pub owner: C::Address,
+    /// The height or epoch at which the tx is executed
+    pub target: ScheduleTarget,
+    /// The serialized tx to be scheduled
+    pub tx_data: C::Data,
+    /// Path to the TX WASM code file
+    pub tx_code_path: PathBuf,
+}
+
+#[derive(Clone, Debug)]
+/// Cancel a scheduled transaction args
+pub struct CancelScheduledTx<C: NamadaTypes = SdkTypes> {
+    /// Common tx arguments
+    pub tx: Tx<C>,
+    /// The owner of the scheduled tx
+    pub owner: C::Address,
+    /// The height or epoch at which the tx is scheduled
+    pub target: ScheduleTarget,
+    /// The hash of the scheduled tx
+    pub tx_hash: String,
+    /// Path to the TX WASM code file
+    pub tx_code_path: PathBuf,
+}
+
+#[derive(Clone, Debug)]
+/// Guardian-based account recovery args
+pub struct TxRecoverAccount<C: NamadaTypes = SdkTypes> {
+    /// Common tx arguments
+    pub tx: Tx<C>,
+    /// The address of the recovered account
+    pub owner: C::Address,
+    /// The guardian requesting or approving the recovery
+    pub guardian: Option<C::Address>,
+    /// The new public keys of the account, to request a recovery
+    pub public_keys: Vec<C::PublicKey>,
+    /// The new signature threshold of the account, to request a recovery
+    pub threshold: Option<u8>,
+    /// Approve the pending recovery
+    pub approve: bool,
+    /// Cancel the pending recovery
+    pub cancel: bool,
+    /// Execute the approved recovery
+    pub execute: bool,
+    /// Path to the TX WASM code file
+    pub tx_code_path: PathBuf,
+}
+
+#[derive(Clone, Debug)]
+/// Add a session key to an account args
+pub struct TxAddSessionKey<C: NamadaTypes = SdkTypes> {
+    /// Common tx arguments
+    pub tx: Tx<C>,
+    /// The address of the account
+    pub owner: C::Address,
+    /// The public key of the session key
+    pub public_key: C::PublicKey,
+    /// The paths to the tx codes that the session key may sign
+    pub allowed_txs: Vec<PathBuf>,
+    /// The maximum amounts of tokens that a tx signed by the session key may
+    /// debit
+    pub max_spend: Vec<(C::Address, token::DenominatedAmount)>,
+    /// The last epoch in which the session key can be used
+    pub expiry: Epoch,
+    /// Path to the TX WASM code file
+    pub tx_code_path: PathBuf,
+}
+
+#[derive(Clone, Debug)]
+/// Revoke a session key of an account args
+pub struct TxRevokeSessionKey<C: NamadaTypes = SdkTypes> {
+    /// Common tx arguments
+    pub tx: Tx<C>,
+    /// The address of the account
+    pub owner: C::Address,
+    /// The public key of the session key
+    pub public_key: C::PublicKey,
+    /// Path to the TX WASM code file
+    pub tx_code_path: PathBuf,
+}
+
 #[derive(Clone, Debug)]
 /// Re-activate a jailed validator args
 pub struct TxUnjailValidator<C: NamadaTypes = SdkTypes> {
@@ -498,6 +696,17 @@ pub struct SignTx<C: NamadaTypes = SdkTypes> {
     pub owner: C::Address,
 }
 
+/// Assemble the offline signatures of a transaction and submit it
+#[derive(Clone, Debug)]
+pub struct AssembleTx<C: NamadaTypes = SdkTypes> {
+    /// Common tx arguments, whose signatures are the offline ones
+    pub tx: Tx<C>,
+    /// Transaction data
+    pub tx_data: C::Data,
+    /// The account address
+    pub owner: C::Address,
+}
+
 /// Query PoS commission rate
 #[derive(Clone, Debug)]
 pub struct QueryCommissionRate<C: NamadaTypes = SdkTypes> {
@@ -552,6 +761,8 @@ pub struct Tx<C: NamadaTypes = SdkTypes> {
     pub dry_run: bool,
     /// Simulate applying both the wrapper and inner transactions
     pub dry_run_wrapper: bool,
+    /// Print a breakdown of the gas used by a simulated transaction
+    pub gas_report: bool,
     /// Dump the transaction bytes to file
     pub dump_tx: bool,
     /// The output directory path to where serialize the data
@@ -595,6 +806,42 @@ pub struct Tx<C: NamadaTypes = SdkTypes> {
     pub verification_key: Option<C::PublicKey>,
     /// Password to decrypt key
     pub password: Option<Zeroizing<String>>,
+    /// The holder of the keys signing the transaction
+    pub signer: SignerKind,
+}
+
+/// The holder of the keys signing a transaction
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SignerKind {
+    /// The keys are held by the local wallet
+    #[default]
+    Wallet,
+    /// The keys are held by an external signer process spawned with the given
+    /// command line, whose arguments may be quoted like in a shell, spoken to
+    /// over its standard input and output
+    Process(String),
+    /// The keys are held by an external signer process listening on the
+    /// given Unix socket
+    Socket(PathBuf),
+}
+
+impl std::str::FromStr for SignerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("wallet") {
+            Ok(Self::Wallet)
+        } else if let Some(command) = s.strip_prefix("stdio:") {
+            Ok(Self::Process(command.to_string()))
+        } else if let Some(path) = s.strip_prefix("unix:") {
+            Ok(Self::Socket(PathBuf::from(path)))
+        } else {
+            Err(format!(
+                "Unexpected signer {s}, expected \"wallet\", \
+                 \"stdio:<command>\" or \"unix:<socket path>\""
+            ))
+        }
+    }
 }
 
 /// MASP add key or address arguments
@@ -606,6 +853,9 @@ pub struct MaspAddrKeyAdd {
     pub alias_force: bool,
     /// Any MASP value
     pub value: MaspValue,
+    /// Height before which a spending or viewing key cannot have received
+    /// notes
+    pub birthday: Option<storage::BlockHeight>,
     /// Don't encrypt the keypair
     pub unsafe_dont_encrypt: bool,
 }
@@ -617,6 +867,8 @@ pub struct MaspSpendKeyGen {
     pub alias: String,
     /// Whether to force overwrite the alias
     pub alias_force: bool,
+    /// Height before which the key cannot have received notes
+    pub birthday: Option<storage::BlockHeight>,
     /// Don't encrypt the keypair
     pub unsafe_dont_encrypt: bool,
 }
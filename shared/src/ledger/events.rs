@@ -13,6 +13,7 @@ use serde_json::Value;
 use crate::ledger::governance::utils::ProposalEvent;
 use crate::tendermint_proto::abci::EventAttribute;
 use crate::types::error::{EncodingError, Error, EventError};
+use crate::types::event::TxEvent;
 use crate::types::ibc::IbcEvent;
 #[cfg(feature = "ferveo-tpke")]
 use crate::types::transaction::TxType;
@@ -53,6 +54,8 @@ pub enum EventType {
     Proposal,
     /// The pgf payment
     PgfPayment,
+    /// A custom event emitted by a transaction, with its namespaced type
+    Custom(String),
 }
 
 impl Display for EventType {
@@ -63,6 +66,7 @@ impl Display for EventType {
             EventType::Ibc(t) => write!(f, "{}", t),
             EventType::Proposal => write!(f, "proposal"),
             EventType::PgfPayment => write!(f, "pgf_payment"),
+            EventType::Custom(t) => write!(f, "{}", t),
         }?;
         Ok(())
     }
@@ -142,6 +146,25 @@ impl Event {
     pub fn get(&self, key: &str) -> Option<&String> {
         self.attributes.get(key)
     }
+
+    /// Attach the list of the custom events emitted by a transaction to this
+    /// event, in JSON under `tx_events`
+    pub fn add_tx_events(&mut self, events: &[TxEvent]) {
+        if events.is_empty() {
+            return;
+        }
+        match serde_json::to_string(events) {
+            Ok(events) => {
+                self["tx_events"] = events;
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Failed to serialize the transaction events: {}",
+                    err
+                );
+            }
+        }
+    }
 }
 
 impl Index<&str> for Event {
@@ -171,6 +194,16 @@ impl From<IbcEvent> for Event {
     }
 }
 
+impl From<TxEvent> for Event {
+    fn from(tx_event: TxEvent) -> Self {
+        Self {
+            event_type: EventType::Custom(tx_event.event_type()),
+            level: EventLevel::Tx,
+            attributes: tx_event.attributes.into_iter().collect(),
+        }
+    }
+}
+
 impl From<ProposalEvent> for Event {
     fn from(proposal_event: ProposalEvent) -> Self {
         Self {
@@ -12,6 +12,7 @@ use crate::ibc::core::ics04_channel::packet::Sequence;
 use crate::ibc::core::ics24_host::identifier::{ChannelId, ClientId, PortId};
 use crate::ibc::Height as IbcHeight;
 use crate::ledger::events::{Event, EventType};
+use crate::types::event::TxEvent;
 use crate::types::hash::Hash;
 use crate::types::storage::BlockHeight;
 
@@ -59,6 +60,15 @@ impl QueryMatcher {
         }
     }
 
+    /// Returns a query matching the custom events of the type of the given
+    /// event, with at least the given attributes
+    pub fn tx_event(event: &TxEvent) -> Self {
+        Self {
+            event_type: EventType::Custom(event.event_type()),
+            attributes: event.attributes.clone().into_iter().collect(),
+        }
+    }
+
     /// Returns a query matching the given IBC UpdateClient parameters
     pub fn ibc_update_client(
         client_id: ClientId,
@@ -165,4 +175,43 @@ mod tests {
             }
         }
     }
+
+    /// Test that the custom events of a transaction can be matched.
+    #[test]
+    fn test_tx_event_matching() {
+        let swap = TxEvent::new("swap")
+            .with_namespace("my_dapp")
+            .with_attribute("pool", "nam-eth")
+            .with_attribute("amount", 100);
+        let other_swap = TxEvent::new("swap")
+            .with_namespace("my_dapp")
+            .with_attribute("pool", "nam-btc")
+            .with_attribute("amount", 5);
+
+        // Events of the same type are kept apart
+        let swap_event = Event::from(swap.clone());
+        let other_swap_event = Event::from(other_swap.clone());
+        assert!(QueryMatcher::tx_event(&swap).matches(&swap_event));
+        assert!(!QueryMatcher::tx_event(&swap).matches(&other_swap_event));
+        assert!(QueryMatcher::tx_event(&other_swap).matches(&other_swap_event));
+        assert!(!QueryMatcher::tx_event(&other_swap).matches(&swap_event));
+        let any_swap = TxEvent::new("swap").with_namespace("my_dapp");
+        assert!(QueryMatcher::tx_event(&any_swap).matches(&swap_event));
+        assert!(QueryMatcher::tx_event(&any_swap).matches(&other_swap_event));
+        let other_dapp = TxEvent::new("swap")
+            .with_namespace("other_dapp")
+            .with_attribute("pool", "nam-eth");
+        assert!(!QueryMatcher::tx_event(&other_dapp).matches(&swap_event));
+
+        let mut event = Event {
+            event_type: EventType::Applied,
+            level: EventLevel::Tx,
+            attributes: HashMap::new(),
+        };
+        event["hash"] = "DEADBEEF".to_string();
+        event.add_tx_events(&[swap.clone(), other_swap.clone()]);
+        let tx_events: Vec<TxEvent> =
+            serde_json::from_str(&event["tx_events"]).unwrap();
+        assert_eq!(tx_events, vec![swap, other_swap]);
+    }
 }
@@ -3,12 +3,24 @@
 pub use namada_core::ledger::ibc::storage;
 pub mod vp;
 
+use std::str::FromStr;
+
 use namada_core::ledger::ibc::storage::{
     channel_counter_key, client_counter_key, connection_counter_key,
 };
 use namada_core::ledger::storage::WlStorage;
 use namada_core::ledger::storage_api::StorageWrite;
+use namada_core::types::address::Address;
+use namada_core::types::time::DateTimeUtc;
+use namada_core::types::token::{self, DenominatedAmount, Denomination};
 
+use crate::ibc::applications::transfer::msgs::transfer::MsgTransfer;
+use crate::ibc::applications::transfer::packet::PacketData;
+use crate::ibc::applications::transfer::PrefixedCoin;
+use crate::ibc::core::ics04_channel::timeout::TimeoutHeight;
+use crate::ibc::core::ics24_host::identifier::{ChannelId, PortId};
+use crate::ibc::core::timestamp::Timestamp as IbcTimestamp;
+use crate::ibc::core::Msg;
 use crate::ledger::storage::{self as ledger_storage, StorageHasher};
 
 /// Initialize storage in the genesis block.
@@ -41,3 +53,99 @@ where
         .write_bytes(&key, value)
         .expect("Unable to write the initial channel counter");
 }
+
+/// Build the data of a `tx_ibc` transaction transferring the given amount of
+/// a token from the source address to a receiver on the counterparty chain of
+/// the given channel. The transfer times out at the given time.
+pub fn make_transfer_data(
+    token: &Address,
+    amount: token::Amount,
+    source: &Address,
+    receiver: &str,
+    port_id: &str,
+    channel_id: &str,
+    timeout: DateTimeUtc,
+) -> Result<Vec<u8>, String> {
+    let port_id = PortId::from_str(port_id).map_err(|e| e.to_string())?;
+    let channel_id =
+        ChannelId::from_str(channel_id).map_err(|e| e.to_string())?;
+    let token = PrefixedCoin {
+        denom: token.to_string().parse().map_err(|e| format!("{e:?}"))?,
+        // Set the IBC amount as an integer
+        amount: DenominatedAmount {
+            amount,
+            denom: Denomination(0),
+        }
+        .into(),
+    };
+    let packet_data = PacketData {
+        token,
+        sender: source.to_string().into(),
+        receiver: receiver.to_string().into(),
+        memo: String::default().into(),
+    };
+    let timeout: crate::tendermint::Time =
+        timeout.try_into().map_err(|e| format!("{e:?}"))?;
+    let msg = MsgTransfer {
+        port_id_on_a: port_id,
+        chan_id_on_a: channel_id,
+        packet_data,
+        timeout_height_on_b: TimeoutHeight::Never,
+        timeout_timestamp_on_b: IbcTimestamp::from(timeout),
+    };
+
+    let mut data = vec![];
+    prost::Message::encode(&msg.to_any(), &mut data)
+        .map_err(|e| e.to_string())?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use super::*;
+    use crate::ibc_proto::google::protobuf::Any;
+    use crate::types::address::nam;
+    use crate::types::address::testing::established_address_1;
+
+    #[test]
+    fn test_make_transfer_data() {
+        let source = established_address_1();
+        let timeout = DateTimeUtc::now();
+        let data = make_transfer_data(
+            &nam(),
+            token::Amount::from(100u64),
+            &source,
+            "cosmos1receiver",
+            "transfer",
+            "channel-0",
+            timeout,
+        )
+        .expect("the transfer data should be valid");
+
+        let any = Any::decode(&data[..]).unwrap();
+        let msg = MsgTransfer::try_from(any).unwrap();
+        assert_eq!(msg.port_id_on_a, PortId::transfer());
+        assert_eq!(msg.chan_id_on_a, ChannelId::new(0));
+        assert_eq!(msg.packet_data.sender.to_string(), source.to_string());
+        assert_eq!(msg.packet_data.receiver.to_string(), "cosmos1receiver");
+        assert_eq!(msg.packet_data.token.denom.to_string(), nam().to_string());
+        assert_eq!(msg.packet_data.token.amount.to_string(), "100");
+        assert_eq!(msg.timeout_height_on_b, TimeoutHeight::Never);
+
+        // invalid identifiers are rejected
+        assert!(
+            make_transfer_data(
+                &nam(),
+                token::Amount::from(100u64),
+                &source,
+                "cosmos1receiver",
+                "transfer",
+                "not a channel",
+                timeout,
+            )
+            .is_err()
+        );
+    }
+}
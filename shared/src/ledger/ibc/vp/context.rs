@@ -8,10 +8,15 @@ use namada_core::ledger::ibc::{IbcCommonContext, IbcStorageContext};
 use namada_core::ledger::storage::write_log::StorageModification;
 use namada_core::ledger::storage::{self as ledger_storage, StorageHasher};
 use namada_core::ledger::storage_api::StorageRead;
-use namada_core::types::address::{Address, InternalAddress};
-use namada_core::types::ibc::IbcEvent;
-use namada_core::types::storage::{BlockHeight, Header, Key};
-use namada_core::types::token::{self, Amount, DenominatedAmount};
+use namada_core::types::address::{self, Address, InternalAddress};
+use namada_core::types::ibc::{IbcEvent, IbcShieldedTransfer};
+use namada_core::types::storage::{
+    BlockHeight, Epoch, Header, Key, KeySeg, TxIndex,
+};
+use namada_core::types::token::{
+    self, Amount, DenominatedAmount, Transfer, HEAD_TX_KEY, PIN_KEY_PREFIX,
+    TX_KEY_PREFIX,
+};
 
 use super::Error;
 use crate::ledger::native_vp::CtxPreStorageRead;
@@ -232,6 +237,53 @@ where
         )
     }
 
+    fn handle_masp_tx(
+        &mut self,
+        shielded: &IbcShieldedTransfer,
+    ) -> Result<(), Self::Error> {
+        let masp_addr = address::masp();
+        let head_tx_key = Key::from(masp_addr.to_db_key())
+            .push(&HEAD_TX_KEY.to_owned())
+            .expect("Cannot obtain a storage key");
+        let current_tx_idx: u64 = self
+            .ctx
+            .read(&head_tx_key)
+            .map_err(Error::NativeVpError)?
+            .unwrap_or(0);
+        let current_tx_key = Key::from(masp_addr.to_db_key())
+            .push(&(TX_KEY_PREFIX.to_owned() + &current_tx_idx.to_string()))
+            .expect("Cannot obtain a storage key");
+        let record: (Epoch, BlockHeight, TxIndex, Transfer, _) = (
+            self.ctx.get_block_epoch().map_err(Error::NativeVpError)?,
+            self.ctx.get_block_height().map_err(Error::NativeVpError)?,
+            self.ctx.get_tx_index().map_err(Error::NativeVpError)?,
+            shielded.transfer.clone(),
+            shielded.masp_tx.clone(),
+        );
+        self.write(
+            &current_tx_key,
+            record.try_to_vec().expect("encoding shouldn't fail"),
+        )?;
+        self.write(
+            &head_tx_key,
+            (current_tx_idx + 1)
+                .try_to_vec()
+                .expect("encoding shouldn't fail"),
+        )?;
+        if let Some(key) = &shielded.transfer.key {
+            let pin_key = Key::from(masp_addr.to_db_key())
+                .push(&(PIN_KEY_PREFIX.to_owned() + key))
+                .expect("Cannot obtain a storage key");
+            self.write(
+                &pin_key,
+                current_tx_idx
+                    .try_to_vec()
+                    .expect("encoding shouldn't fail"),
+            )?;
+        }
+        Ok(())
+    }
+
     /// Get the current height of this chain
     fn get_height(&self) -> Result<BlockHeight, Self::Error> {
         self.ctx.get_block_height().map_err(Error::NativeVpError)
@@ -362,6 +414,13 @@ where
         unimplemented!("Validation doesn't burn")
     }
 
+    fn handle_masp_tx(
+        &mut self,
+        _shielded: &IbcShieldedTransfer,
+    ) -> Result<(), Self::Error> {
+        unimplemented!("Validation doesn't handle a MASP transaction")
+    }
+
     fn get_height(&self) -> Result<BlockHeight, Self::Error> {
         self.ctx.get_block_height().map_err(Error::NativeVpError)
     }
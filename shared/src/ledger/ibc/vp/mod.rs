@@ -15,6 +15,7 @@ use namada_core::ledger::storage::write_log::StorageModification;
 use namada_core::ledger::storage::{self as ledger_storage, StorageHasher};
 use namada_core::proto::Tx;
 use namada_core::types::address::Address;
+use namada_core::types::ibc::IbcShieldedTransfer;
 use namada_core::types::storage::Key;
 use namada_proof_of_stake::read_pos_params;
 use thiserror::Error;
@@ -75,7 +76,7 @@ where
         let tx_data = signed.data().ok_or(Error::NoTxData)?;
 
         // Pseudo execution and compare them
-        self.validate_state(&tx_data, keys_changed)?;
+        self.validate_state(signed, &tx_data, keys_changed)?;
 
         // Validate the state according to the given IBC message
         self.validate_with_msg(&tx_data)?;
@@ -95,6 +96,7 @@ where
 {
     fn validate_state(
         &self,
+        tx: &Tx,
         tx_data: &[u8],
         keys_changed: &BTreeSet<Key>,
     ) -> VpResult<()> {
@@ -104,6 +106,9 @@ where
         let mut actions = IbcActions::new(ctx.clone());
         let module = TransferModule::new(ctx.clone());
         actions.add_transfer_route(module.module_id(), module);
+        if let Some(shielded) = IbcShieldedTransfer::from_tx(tx) {
+            actions.set_shielded_transfer(shielded);
+        }
         actions.execute(tx_data)?;
 
         let changed_ibc_keys: HashSet<&Key> =
@@ -5,12 +5,15 @@ use std::env;
 use std::fmt::Debug;
 #[cfg(feature = "masp-tx-gen")]
 use std::ops::Deref;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 use async_trait::async_trait;
 // use async_std::io::prelude::WriteExt;
 // use async_std::io::{self};
 use borsh::{BorshDeserialize, BorshSerialize};
+use data_encoding::HEXUPPER;
+use futures::stream::{self, StreamExt, TryStreamExt};
 use itertools::Either;
 use masp_primitives::asset_type::AssetType;
 #[cfg(feature = "mainnet")]
@@ -24,7 +27,7 @@ use masp_primitives::memo::MemoBytes;
 use masp_primitives::merkle_tree::{
     CommitmentTree, IncrementalWitness, MerklePath,
 };
-use masp_primitives::sapling::keys::FullViewingKey;
+use masp_primitives::sapling::keys::{FullViewingKey, OutgoingViewingKey};
 use masp_primitives::sapling::note_encryption::*;
 use masp_primitives::sapling::redjubjub::PublicKey;
 use masp_primitives::sapling::{
@@ -60,8 +63,12 @@ use sha2::Digest;
 use thiserror::Error;
 
 use crate::ledger::args::InputAmount;
-use crate::ledger::queries::Client;
-use crate::ledger::rpc::{query_conversion, query_storage_value};
+use crate::ledger::queries::{
+    Client, CompactMaspTx, IndexedMaspTx, MAX_MASP_TXS_PER_QUERY,
+};
+use crate::ledger::rpc::query_conversion;
+#[cfg(feature = "masp-tx-gen")]
+use crate::ledger::signing::{ExternalSigner, SharedExternalSigner};
 use crate::ledger::tx::decode_component;
 use crate::ledger::{args, rpc};
 use crate::proto::Tx;
@@ -71,7 +78,10 @@ use crate::types::address::{masp, Address};
 use crate::types::error::{
     EncodingError, Error, PinnedBalanceError, QueryError,
 };
-use crate::types::masp::{BalanceOwner, ExtendedViewingKey, PaymentAddress};
+use crate::types::masp::{
+    BalanceOwner, ExtendedViewingKey, PaymentAddress, TransferSource,
+    TransferTarget,
+};
 use crate::types::storage::{BlockHeight, Epoch, Key, KeySeg, TxIndex};
 use crate::types::token;
 use crate::types::token::{
@@ -79,6 +89,9 @@ use crate::types::token::{
 };
 use crate::types::transaction::{EllipticCurve, PairingEngine, WrapperTx};
 
+#[cfg(feature = "masp-tx-gen")]
+pub mod external_signer;
+
 /// Env var to point to a dir with MASP parameters. When not specified,
 /// the default OS specific path is used.
 pub const ENV_VAR_MASP_PARAMS_DIR: &str = "NAMADA_MASP_PARAMS_DIR";
@@ -108,6 +121,10 @@ pub const OUTPUT_NAME: &str = "masp-output.params";
 /// Convert circuit name
 pub const CONVERT_NAME: &str = "masp-convert.params";
 
+/// The maximum number of shielded transaction batches being fetched from the
+/// ledger at once
+pub const MASP_FETCH_CONCURRENCY: usize = 8;
+
 /// Shielded transfer
 #[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
 pub struct ShieldedTransfer {
@@ -145,7 +162,18 @@ fn load_pvks() -> (
     PreparedVerifyingKey<Bls12>,
     PreparedVerifyingKey<Bls12>,
 ) {
-    let params_dir = get_params_dir();
+    load_pvks_from(&get_params_dir())
+}
+
+/// Load the verifying keys from the MASP parameters in the given directory,
+/// downloading the parameters if they are missing
+fn load_pvks_from(
+    params_dir: &Path,
+) -> (
+    PreparedVerifyingKey<Bls12>,
+    PreparedVerifyingKey<Bls12>,
+    PreparedVerifyingKey<Bls12>,
+) {
     let [spend_path, convert_path, output_path] =
         [SPEND_NAME, CONVERT_NAME, OUTPUT_NAME].map(|p| params_dir.join(p));
 
@@ -276,6 +304,25 @@ fn partial_deauthorize(
     ))
 }
 
+/// Compute the sighash signed by the spends and the binding signature of the
+/// given partially deauthorized transaction
+fn compute_sighash(
+    unauth_tx_data: &TransactionData<PartialAuthorized>,
+) -> [u8; 32] {
+    let txid_parts = unauth_tx_data.digest(TxIdDigester);
+    // the commitment being signed is shared across all Sapling inputs; once
+    // V4 transactions are deprecated this should just be the txid, but
+    // for now we need to continue to compute it here.
+    *signature_hash(unauth_tx_data, &SignableInput::Shielded, &txid_parts)
+        .as_ref()
+}
+
+/// Compute the sighash signed by the spends and the binding signature of the
+/// given shielded transaction
+pub fn shielded_sighash(transaction: &Transaction) -> Option<[u8; 32]> {
+    partial_deauthorize(transaction.deref()).map(|x| compute_sighash(&x))
+}
+
 /// Verify a shielded transaction.
 pub fn verify_shielded_tx(transaction: &Transaction) -> bool {
     tracing::info!("entered verify_shielded_tx()");
@@ -293,21 +340,17 @@ pub fn verify_shielded_tx(transaction: &Transaction) -> bool {
         None => return false,
     };
 
-    let txid_parts = unauth_tx_data.digest(TxIdDigester);
-    // the commitment being signed is shared across all Sapling inputs; once
-    // V4 transactions are deprecated this should just be the txid, but
-    // for now we need to continue to compute it here.
-    let sighash =
-        signature_hash(&unauth_tx_data, &SignableInput::Shielded, &txid_parts);
+    let sighash = compute_sighash(&unauth_tx_data);
 
     tracing::info!("sighash computed");
 
     let (spend_pvk, convert_pvk, output_pvk) = load_pvks();
 
     let mut ctx = SaplingVerificationContext::new(true);
-    let spends_valid = sapling_bundle.shielded_spends.iter().all(|spend| {
-        check_spend(spend, sighash.as_ref(), &mut ctx, &spend_pvk)
-    });
+    let spends_valid = sapling_bundle
+        .shielded_spends
+        .iter()
+        .all(|spend| check_spend(spend, &sighash, &mut ctx, &spend_pvk));
     let converts_valid = sapling_bundle
         .shielded_converts
         .iter()
@@ -332,7 +375,7 @@ pub fn verify_shielded_tx(transaction: &Transaction) -> bool {
 
     let result = ctx.final_check(
         assets_and_values,
-        sighash.as_ref(),
+        &sighash,
         sapling_bundle.authorization.binding_sig,
     );
     tracing::info!("final check result {result}");
@@ -386,6 +429,55 @@ impl<P1, R1, N1>
     fn map_notifier(&self, _s: N1) {}
 }
 
+/// The owner of the funds moved by a shielded transfer
+#[cfg(feature = "masp-tx-gen")]
+#[derive(Clone, Copy)]
+enum Spender<'a> {
+    /// A transparent source, whose funds are moved by the containing
+    /// Transfer, with the outgoing viewing key of the shielded outputs if
+    /// they have to be recoverable
+    Transparent(&'a TransferSource, Option<OutgoingViewingKey>),
+    /// A spending key available locally
+    Local(ExtendedSpendingKey),
+    /// A spending key held by an external signer, whose spends are proven
+    /// with the given prover
+    External(ExtendedFullViewingKey, &'a external_signer::ExternalProver),
+}
+
+#[cfg(feature = "masp-tx-gen")]
+impl<'a> Spender<'a> {
+    /// Get the full viewing key of the shielded spender, if any
+    fn full_viewing_key(&self) -> Option<ExtendedFullViewingKey> {
+        match self {
+            Self::Transparent(..) => None,
+            Self::Local(sk) => Some(sk.into()),
+            Self::External(fvk, _) => Some(*fvk),
+        }
+    }
+
+    /// Get the outgoing viewing key the shielded outputs are sent with, if
+    /// any
+    fn outgoing_viewing_key(&self) -> Option<OutgoingViewingKey> {
+        match self {
+            Self::Transparent(_, ovk) => *ovk,
+            _ => self.full_viewing_key().map(|x| x.fvk.ovk),
+        }
+    }
+
+    /// Get the spending key that the builder spends the notes with, if any
+    fn builder_key(&self) -> Option<ExtendedSpendingKey> {
+        match self {
+            Self::Transparent(..) => None,
+            Self::Local(sk) => Some(*sk),
+            Self::External(_, prover) => {
+                Some(external_signer::stand_in_spending_key(
+                    &prover.proof_generation_key,
+                ))
+            }
+        }
+    }
+}
+
 /// Abstracts platform specific details away from the logic of shielded pool
 /// operations.
 #[async_trait(? Send)]
@@ -400,6 +492,17 @@ pub trait ShieldedUtils:
 
     /// Sace the given ShieldedContext for future loads
     async fn save(&self, ctx: &ShieldedContext<Self>) -> std::io::Result<()>;
+
+    /// Persist the given transactions, which were scanned into the given
+    /// ShieldedContext since it was last loaded or saved. Implementations
+    /// that cannot persist them incrementally save the whole context.
+    async fn append(
+        &self,
+        ctx: &ShieldedContext<Self>,
+        _updates: &[ScannedTx],
+    ) -> std::io::Result<()> {
+        self.save(ctx).await
+    }
 }
 
 /// Make a ViewingKey that can view notes encrypted by given ExtendedSpendingKey
@@ -440,6 +543,116 @@ pub fn is_amount_required(src: I128Sum, dest: I128Sum, delta: I128Sum) -> bool {
     false
 }
 
+/// A note decrypted from a shielded output: the index of the output in its
+/// transaction, the viewing key that decrypted it, the note itself, the payment
+/// address it was sent to and its memo
+pub type DecryptedOutput = (
+    usize,
+    ViewingKey,
+    Note,
+    masp_primitives::sapling::PaymentAddress,
+    MemoBytes,
+);
+
+/// Try to decrypt every output of the given shielded transaction with each of
+/// the given viewing keys
+pub fn trial_decrypt_outputs(
+    shielded: &Transaction,
+    vks: &[ViewingKey],
+) -> Vec<DecryptedOutput> {
+    let mut decrypted = Vec::new();
+    for (output_idx, so) in shielded
+        .sapling_bundle()
+        .map_or(&vec![], |x| &x.shielded_outputs)
+        .iter()
+        .enumerate()
+    {
+        for vk in vks {
+            let decres = try_sapling_note_decryption::<_, OutputDescription<<<Authorized as Authorization>::SaplingAuth as masp_primitives::transaction::components::sapling::Authorization>::Proof>>(
+                &NETWORK,
+                1.into(),
+                &PreparedIncomingViewingKey::new(&vk.ivk()),
+                so,
+            );
+            if let Some((note, pa, memo)) = decres {
+                decrypted.push((output_idx, *vk, note, pa, memo));
+            }
+        }
+    }
+    decrypted
+}
+
+/// A note decrypted from a compact shielded output: the index of the output in
+/// its transaction, the viewing key that decrypted it, the note itself and the
+/// payment address it was sent to
+pub type DecryptedCompactOutput = (
+    usize,
+    ViewingKey,
+    Note,
+    masp_primitives::sapling::PaymentAddress,
+);
+
+/// Try to decrypt every output of the given compact shielded transaction with
+/// each of the given viewing keys. Compact outputs do not carry memos, so only
+/// the notes and the addresses they were sent to are recovered.
+pub fn trial_decrypt_compact_outputs(
+    shielded: &CompactMaspTx,
+    vks: &[ViewingKey],
+) -> Vec<DecryptedCompactOutput> {
+    let mut decrypted = Vec::new();
+    for (output_idx, co) in shielded.outputs.iter().enumerate() {
+        let Some(co) = co.to_description() else {
+            continue;
+        };
+        for vk in vks {
+            let decres = try_sapling_compact_note_decryption(
+                &NETWORK,
+                1.into(),
+                &PreparedIncomingViewingKey::new(&vk.ivk()),
+                &co,
+            );
+            if let Some((note, pa)) = decres {
+                decrypted.push((output_idx, *vk, note, pa));
+            }
+        }
+    }
+    decrypted
+}
+
+/// Trial-decrypt the outputs of each of the given shielded transactions,
+/// paired with the height at which they were applied, as per
+/// [`trial_decrypt_outputs`]. Keys are not tried on the transactions applied
+/// before their birthday, if any. With the `multicore` feature, the
+/// transactions are decrypted in parallel.
+pub fn trial_decrypt_txs<'a>(
+    shielded: impl Iterator<Item = (BlockHeight, &'a Transaction)>,
+    vks: &[ViewingKey],
+    birthdays: &HashMap<ViewingKey, BlockHeight>,
+) -> Vec<Vec<DecryptedOutput>> {
+    let shielded: Vec<(BlockHeight, &Transaction)> = shielded.collect();
+    let decrypt = |(height, stx): (BlockHeight, &Transaction)| {
+        let vks: Vec<ViewingKey> = vks
+            .iter()
+            .filter(|vk| {
+                birthdays
+                    .get(vk)
+                    .map_or(true, |birthday| *birthday <= height)
+            })
+            .cloned()
+            .collect();
+        trial_decrypt_outputs(stx, &vks)
+    };
+    #[cfg(feature = "multicore")]
+    {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+        shielded.into_par_iter().map(decrypt).collect()
+    }
+    #[cfg(not(feature = "multicore"))]
+    {
+        shielded.into_iter().map(decrypt).collect()
+    }
+}
+
 // #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 // pub struct MaspAmount {
 //     pub asset: Address,
@@ -558,6 +771,190 @@ pub type TransferDelta = HashMap<Address, MaspChange>;
 /// Represents the changes that were made to a list of shielded accounts
 pub type TransactionDelta = HashMap<ViewingKey, MaspAmount>;
 
+/// Whether a note has been received or spent by a viewing key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NoteEvent {
+    /// The note was created for the viewing key
+    Received,
+    /// The note of the viewing key was spent
+    Spent,
+}
+
+/// A received or spent note in the shielded history of a viewing key
+#[derive(Debug, Clone)]
+pub struct ShieldedHistoryEntry {
+    /// Height of the block containing the transaction
+    pub height: BlockHeight,
+    /// Index of the transaction within its block
+    pub index: TxIndex,
+    /// Epoch in which the transaction was made
+    pub epoch: Epoch,
+    /// Whether the note was received or spent
+    pub event: NoteEvent,
+    /// Position of the note in the commitment tree
+    pub note_pos: usize,
+    /// Memo attached to the note
+    pub memo: MemoBytes,
+    /// Value of the note decoded into the epoch and token of its asset type
+    pub amount: MaspAmount,
+    /// Value of the note exchanged into the epoch of the transaction with
+    /// the conversions applied
+    pub exchanged: HashMap<Address, token::Change>,
+}
+
+/// Magic bytes identifying versioned shielded context snapshots and logs.
+/// Snapshots saved before versioning was introduced have no header and
+/// start directly with the serialized context.
+const SHIELDED_CONTEXT_MAGIC: [u8; 8] = *b"NAMMASP\0";
+
+/// The version of the shielded context snapshot and log formats. Version 2
+/// appends the catch-up context to the snapshots, while the logs are the same
+/// as in version 1.
+pub const SHIELDED_CONTEXT_VERSION: u32 = 2;
+
+/// The header prepended to shielded context snapshots and logs
+fn versioned_header() -> Vec<u8> {
+    let mut header = SHIELDED_CONTEXT_MAGIC.to_vec();
+    header.extend(SHIELDED_CONTEXT_VERSION.to_le_bytes());
+    header
+}
+
+/// Split the version off the given versioned snapshot or log. Returns None
+/// if the given bytes do not start with a version header.
+fn split_version(bytes: &[u8]) -> Option<(u32, &[u8])> {
+    let rest = bytes.strip_prefix(&SHIELDED_CONTEXT_MAGIC[..])?;
+    if rest.len() < 4 {
+        return None;
+    }
+    let (version, rest) = rest.split_at(4);
+    let version = u32::from_le_bytes(version.try_into().ok()?);
+    Some((version, rest))
+}
+
+/// A note decrypted by one of our viewing keys while scanning a transaction
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct ScannedNote {
+    /// Position of the note in the commitment tree
+    pub pos: usize,
+    /// The viewing key that decrypted the note
+    pub vk: ViewingKey,
+    /// The decrypted note
+    pub note: Note,
+    /// Memo attached to the note
+    pub memo: MemoBytes,
+    /// Diversifier of the payment address the note was sent to
+    pub diversifier: Diversifier,
+    /// Nullifier revealed when the note is spent
+    pub nullifier: Nullifier,
+}
+
+/// The changes made to a ShieldedContext by scanning a single transaction.
+/// Replaying these on top of the context that the transaction was scanned
+/// into reproduces the scan without fetching or decrypting it again.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct ScannedTx {
+    /// Index of the transaction among all the accepted shielded transactions
+    pub txidx: u64,
+    /// Height of the block containing the transaction
+    pub height: BlockHeight,
+    /// Index of the transaction within its block
+    pub index: TxIndex,
+    /// Epoch in which the transaction was made
+    pub epoch: Epoch,
+    /// The note commitments of all the transaction outputs
+    pub commitments: Vec<Node>,
+    /// The outputs that were decrypted by our viewing keys
+    pub notes: Vec<ScannedNote>,
+    /// Positions of our notes spent by the transaction
+    pub spent: Vec<usize>,
+    /// Changes made to the transparent accounts
+    pub transfer_delta: TransferDelta,
+    /// Changes made to the shielded accounts
+    pub transaction_delta: TransactionDelta,
+}
+
+impl ScannedTx {
+    /// Encode the given scanned transactions for appending to a shielded
+    /// context log. Each record is prefixed by its length so that a record
+    /// torn by an interrupted write can be detected. The log header is
+    /// prepended if the records start a new log.
+    pub fn encode_log(updates: &[Self], new_log: bool) -> Vec<u8> {
+        let mut bytes = if new_log {
+            versioned_header()
+        } else {
+            Vec::new()
+        };
+        for update in updates {
+            let record =
+                update.try_to_vec().expect("cannot serialize scanned tx");
+            bytes.extend((record.len() as u32).to_le_bytes());
+            bytes.extend(record);
+        }
+        bytes
+    }
+}
+
+/// A disclosure of a single shielded payment. It carries the outgoing cipher
+/// key of one output of the payment transaction, which lets anyone decrypt
+/// that output without being able to view any other note of the payer.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct PaymentDisclosure {
+    /// Index of the transaction among all the accepted shielded transactions
+    pub txidx: u64,
+    /// Height of the block containing the transaction
+    pub height: BlockHeight,
+    /// Index of the transaction within its block
+    pub index: TxIndex,
+    /// Index of the disclosed output within the transaction
+    pub output_idx: usize,
+    /// The outgoing cipher key of the disclosed output
+    pub ock: [u8; 32],
+    /// The payment address the note was sent to
+    pub payment_address: PaymentAddress,
+    /// The asset type of the note
+    pub asset_type: AssetType,
+    /// The value of the note in its asset type
+    pub value: u64,
+    /// Memo attached to the note
+    pub memo: MemoBytes,
+}
+
+impl std::fmt::Display for PaymentDisclosure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bytes = self
+            .try_to_vec()
+            .expect("Encoding a payment disclosure shouldn't fail");
+        write!(f, "{}", HEXUPPER.encode(&bytes))
+    }
+}
+
+impl FromStr for PaymentDisclosure {
+    type Err = EncodingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = HEXUPPER
+            .decode(s.as_bytes())
+            .map_err(|err| EncodingError::Decoding(err.to_string()))?;
+        Self::try_from_slice(&bytes)
+            .map_err(|err| EncodingError::Decoding(err.to_string()))
+    }
+}
+
+/// A shielded payment whose disclosure was verified against the chain
+#[derive(Debug, Clone)]
+pub struct DisclosedPayment {
+    /// Epoch in which the payment was made
+    pub epoch: Epoch,
+    /// The token of the note
+    pub token: Address,
+    /// The denomination of the note value
+    pub denom: MaspDenom,
+    /// The epoch of the note asset type
+    pub asset_epoch: Epoch,
+    /// The amount of the token paid
+    pub amount: token::Amount,
+}
+
 /// Represents the current state of the shielded pool from the perspective of
 /// the chosen viewing keys.
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
@@ -592,6 +989,24 @@ pub struct ShieldedContext<U: ShieldedUtils> {
     pub asset_types: HashMap<AssetType, (Address, MaspDenom, Epoch)>,
     /// Maps note positions to their corresponding viewing keys
     pub vk_map: HashMap<usize, ViewingKey>,
+    /// Maps note positions to the transaction that created them
+    pub note_tx_map: HashMap<usize, (BlockHeight, TxIndex)>,
+    /// Maps spent note positions to the transaction that spent them
+    pub spent_tx_map: HashMap<usize, (BlockHeight, TxIndex)>,
+    /// The scanning state of the keys that are still being caught up with
+    /// the transactions scanned for the other keys of this context, if any
+    pub catch_up: Option<Box<ShieldedContext<U>>>,
+    /// Maps viewing keys to the block height before which they cannot have
+    /// received any notes. These are supplied by the wallet.
+    #[borsh_skip]
+    pub birthdays: HashMap<ViewingKey, BlockHeight>,
+    /// The transactions scanned since this context was last loaded or saved
+    #[borsh_skip]
+    pub unsaved: Vec<ScannedTx>,
+    /// Whether this context has changed in ways that the scanned
+    /// transactions do not capture, and hence must be saved whole
+    #[borsh_skip]
+    pub snapshot_stale: bool,
 }
 
 /// Default implementation to ease construction of TxContexts. Derive cannot be
@@ -612,6 +1027,63 @@ impl<U: ShieldedUtils + Default> Default for ShieldedContext<U> {
             delta_map: BTreeMap::default(),
             asset_types: HashMap::default(),
             vk_map: HashMap::default(),
+            note_tx_map: HashMap::default(),
+            spent_tx_map: HashMap::default(),
+            catch_up: None,
+            birthdays: HashMap::default(),
+            unsaved: Vec::default(),
+            // Nothing has been saved yet
+            snapshot_stale: true,
+        }
+    }
+}
+
+/// The layout of shielded contexts saved before snapshots were versioned.
+/// These predate the tracking of the transactions that created and spent
+/// our notes, as well as the catch-up of new keys.
+#[derive(BorshSerialize, BorshDeserialize)]
+struct UnversionedShieldedContext {
+    last_txidx: u64,
+    tree: CommitmentTree<Node>,
+    pos_map: HashMap<ViewingKey, BTreeSet<usize>>,
+    nf_map: HashMap<Nullifier, usize>,
+    note_map: HashMap<usize, Note>,
+    memo_map: HashMap<usize, MemoBytes>,
+    div_map: HashMap<usize, Diversifier>,
+    witness_map: HashMap<usize, IncrementalWitness<Node>>,
+    delta_map: BTreeMap<
+        (BlockHeight, TxIndex),
+        (Epoch, TransferDelta, TransactionDelta),
+    >,
+    spents: HashSet<usize>,
+    asset_types: HashMap<AssetType, (Address, MaspDenom, Epoch)>,
+    vk_map: HashMap<usize, ViewingKey>,
+}
+
+impl<U: ShieldedUtils> From<UnversionedShieldedContext> for ShieldedContext<U> {
+    fn from(ctx: UnversionedShieldedContext) -> Self {
+        Self {
+            utils: U::default(),
+            last_txidx: ctx.last_txidx,
+            tree: ctx.tree,
+            pos_map: ctx.pos_map,
+            nf_map: ctx.nf_map,
+            note_map: ctx.note_map,
+            memo_map: ctx.memo_map,
+            div_map: ctx.div_map,
+            witness_map: ctx.witness_map,
+            delta_map: ctx.delta_map,
+            spents: ctx.spents,
+            asset_types: ctx.asset_types,
+            vk_map: ctx.vk_map,
+            // The transactions of the notes scanned so far are unknown
+            note_tx_map: HashMap::default(),
+            spent_tx_map: HashMap::default(),
+            catch_up: None,
+            birthdays: HashMap::default(),
+            unsaved: Vec::default(),
+            // Save the migrated context in the current version
+            snapshot_stale: true,
         }
     }
 }
@@ -621,13 +1093,139 @@ impl<U: ShieldedUtils> ShieldedContext<U> {
     /// directory. If this fails, then leave the current context unchanged.
     pub async fn load(&mut self) -> std::io::Result<()> {
         let new_ctx = self.utils.clone().load().await?;
-        *self = new_ctx;
+        // The key birthdays are not persisted, so keep the current ones
+        *self = Self {
+            birthdays: std::mem::take(&mut self.birthdays),
+            ..new_ctx
+        };
+        Ok(())
+    }
+
+    /// Set the birthday heights of the given viewing keys. Transactions
+    /// applied before a key's birthday are not trial-decrypted with it.
+    pub fn set_birthdays(
+        &mut self,
+        birthdays: impl IntoIterator<Item = (ViewingKey, BlockHeight)>,
+    ) {
+        self.birthdays.extend(birthdays);
+    }
+
+    /// Save this shielded context into its associated context directory.
+    /// Only the transactions scanned since the last load or save are
+    /// persisted, unless the context has otherwise changed.
+    pub async fn save(&mut self) -> std::io::Result<()> {
+        if self.snapshot_stale {
+            self.utils.save(self).await?;
+        } else if !self.unsaved.is_empty() {
+            self.utils.append(self, &self.unsaved).await?;
+        }
+        self.unsaved.clear();
+        self.snapshot_stale = false;
         Ok(())
     }
 
-    /// Save this shielded context into its associated context directory
-    pub async fn save(&self) -> std::io::Result<()> {
-        self.utils.save(self).await
+    /// Serialize this shielded context into a versioned snapshot
+    pub fn to_snapshot(&self) -> Vec<u8> {
+        let mut bytes = versioned_header();
+        self.serialize(&mut bytes)
+            .expect("cannot serialize shielded context");
+        bytes
+    }
+
+    /// Deserialize a shielded context from the given snapshot, migrating
+    /// snapshots of older versions forward. Migrated contexts are marked for
+    /// being saved whole in the current version.
+    pub fn from_snapshot(utils: U, bytes: &[u8]) -> std::io::Result<Self> {
+        let mut ctx = match split_version(bytes) {
+            None => UnversionedShieldedContext::try_from_slice(bytes)?.into(),
+            Some((1, body)) => Self::from_v1_snapshot(body)?,
+            Some((SHIELDED_CONTEXT_VERSION, body)) => {
+                Self::try_from_slice(body)?
+            }
+            Some((version, _)) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!(
+                        "Unsupported shielded context version {}, expected \
+                         at most {}",
+                        version, SHIELDED_CONTEXT_VERSION
+                    ),
+                ));
+            }
+        };
+        // Associate the originating context directory with the shielded
+        // context under construction
+        ctx.utils = utils;
+        Ok(ctx)
+    }
+
+    /// Deserialize a shielded context from the body of a version 1 snapshot.
+    /// These lack the trailing catch-up context, as no keys could be caught
+    /// up separately then.
+    fn from_v1_snapshot(body: &[u8]) -> std::io::Result<Self> {
+        let no_catch_up = None::<Box<Self>>.try_to_vec()?;
+        Ok(Self {
+            snapshot_stale: true,
+            ..Self::try_from_slice(&[body, &no_catch_up].concat())?
+        })
+    }
+
+    /// Replay the scanned transactions of the given log on top of this
+    /// context. Records of transactions that this context already contains
+    /// are skipped, and replaying stops at the first torn record or gap, in
+    /// which case the context is marked for being saved whole. Returns the
+    /// number of transactions replayed.
+    pub fn replay_log(&mut self, log: &[u8]) -> std::io::Result<usize> {
+        let mut records = match split_version(log) {
+            Some((1..=SHIELDED_CONTEXT_VERSION, records)) => records,
+            Some((version, _)) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Unsupported shielded log version {}", version),
+                ));
+            }
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Shielded log header is missing",
+                ));
+            }
+        };
+        let mut replayed = 0;
+        while !records.is_empty() {
+            let torn = records.len() < 4;
+            let (len, rest) = records.split_at(records.len().min(4));
+            let len = if torn {
+                0
+            } else {
+                u32::from_le_bytes(
+                    len.try_into().expect("the length prefix has 4 bytes"),
+                ) as usize
+            };
+            // Records following a torn record or a gap cannot be replayed,
+            // nor can new records be appended after them
+            if torn || rest.len() < len {
+                self.snapshot_stale = true;
+                break;
+            }
+            let (record, rest) = rest.split_at(len);
+            records = rest;
+            let update = ScannedTx::try_from_slice(record)?;
+            if update.txidx < self.last_txidx {
+                continue;
+            } else if update.txidx > self.last_txidx {
+                self.snapshot_stale = true;
+                break;
+            }
+            self.apply_scanned_tx(update).map_err(|err| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    err.to_string(),
+                )
+            })?;
+            replayed += 1;
+        }
+        Ok(replayed)
     }
 
     /// Merge data from the given shielded context into the current shielded
@@ -646,6 +1244,11 @@ impl<U: ShieldedUtils> ShieldedContext<U> {
         self.spents.extend(new_ctx.spents);
         self.asset_types.extend(new_ctx.asset_types);
         self.vk_map.extend(new_ctx.vk_map);
+        self.note_tx_map.extend(new_ctx.note_tx_map);
+        self.spent_tx_map.extend(new_ctx.spent_tx_map);
+        self.birthdays.extend(new_ctx.birthdays);
+        // The merged data is not captured by the scanned transactions
+        self.snapshot_stale = true;
         // The deltas are the exception because different keys can reveal
         // different parts of the same transaction. Hence each delta needs to be
         // merged separately.
@@ -660,119 +1263,199 @@ impl<U: ShieldedUtils> ShieldedContext<U> {
     }
 
     /// Fetch the current state of the multi-asset shielded pool into a
-    /// ShieldedContext
+    /// ShieldedContext. Keys new to this context are caught up with the
+    /// transactions scanned so far in a separate context, which is only
+    /// advanced when any of its keys are requested and which is merged into
+    /// this one once level with it. Hence adding a key does not hold up
+    /// fetching the notes of the others.
     pub async fn fetch<C: Client + Sync>(
         &mut self,
         client: &C,
         sks: &[ExtendedSpendingKey],
         fvks: &[ViewingKey],
     ) -> Result<(), Error> {
+        let requested: Vec<ViewingKey> = sks
+            .iter()
+            .map(|esk| to_viewing_key(esk).vk)
+            .chain(fvks.iter().copied())
+            .collect();
         // First determine which of the keys requested to be fetched are new.
         // Necessary because old transactions will need to be scanned for new
         // keys.
-        let mut unknown_keys = Vec::new();
-        for esk in sks {
-            let vk = to_viewing_key(esk).vk;
-            if !self.pos_map.contains_key(&vk) {
-                unknown_keys.push(vk);
-            }
-        }
-        for vk in fvks {
-            if !self.pos_map.contains_key(vk) {
-                unknown_keys.push(*vk);
+        let mut unknown_keys: Vec<ViewingKey> = requested
+            .iter()
+            .filter(|vk| {
+                !self.pos_map.contains_key(vk) && !self.is_catching_up(vk)
+            })
+            .copied()
+            .collect();
+        // Keys born after the last scanned transaction cannot have received
+        // any of the notes scanned so far, so they can be tracked from the
+        // current position on without rescanning the older transactions
+        let last_scanned_height =
+            self.delta_map.keys().next_back().map(|(height, _)| *height);
+        unknown_keys.retain(|vk| {
+            let born_after_last_scan =
+                match (last_scanned_height, self.birthdays.get(vk)) {
+                    (None, _) => true,
+                    (Some(last_height), Some(birthday)) => {
+                        *birthday > last_height
+                    }
+                    (Some(_), None) => false,
+                };
+            if born_after_last_scan {
+                self.pos_map.entry(*vk).or_default();
+                self.snapshot_stale = true;
             }
-        }
-
-        // If unknown keys are being used, we need to scan older transactions
-        // for any unspent notes
-        let (txs, mut tx_iter);
+            !born_after_last_scan
+        });
+        // The other new keys need the older transactions to be scanned for
+        // their unspent notes, which is done in the catch-up context
         if !unknown_keys.is_empty() {
-            // Load all transactions accepted until this point
-            txs = Self::fetch_shielded_transfers(client, 0).await?;
-            tx_iter = txs.iter();
-            // Do this by constructing a shielding context only for unknown keys
-            let mut tx_ctx = Self {
-                utils: self.utils.clone(),
-                ..Default::default()
-            };
+            let utils = self.utils.clone();
+            let asset_types = self.asset_types.clone();
+            let catch_up = self.catch_up.get_or_insert_with(|| {
+                Box::new(Self {
+                    utils,
+                    asset_types,
+                    ..Default::default()
+                })
+            });
             for vk in unknown_keys {
-                tx_ctx.pos_map.entry(vk).or_insert_with(BTreeSet::new);
+                catch_up.pos_map.entry(vk).or_default();
             }
-            // Update this unknown shielded context until it is level with self
-            while tx_ctx.last_txidx != self.last_txidx {
-                if let Some(((height, idx), (epoch, tx, stx))) = tx_iter.next()
-                {
-                    tx_ctx
-                        .scan_tx(client, *height, *idx, *epoch, tx, stx)
-                        .await?;
-                } else {
-                    break;
-                }
+            self.snapshot_stale = true;
+        }
+        // The keys being caught up are only scanned for when requested
+        let catch_up_txidx = self
+            .catch_up
+            .as_ref()
+            .filter(|catch_up| {
+                requested.iter().any(|vk| catch_up.pos_map.contains_key(vk))
+            })
+            .map(|catch_up| catch_up.last_txidx);
+        let txs = Self::fetch_shielded_transfers(
+            client,
+            catch_up_txidx.unwrap_or(self.last_txidx),
+        )
+        .await?;
+        // Trial-decrypt the outputs of all the fetched transactions with both
+        // the old and the new keys up front, as this is the costly part of
+        // the scan and does not depend on the state of the context. Keys are
+        // only tried on the transactions applied from their birthday on.
+        let catch_up_keys = self
+            .catch_up
+            .as_ref()
+            .filter(|_| catch_up_txidx.is_some())
+            .map(|catch_up| catch_up.pos_map.keys());
+        let vks: Vec<ViewingKey> = self
+            .pos_map
+            .keys()
+            .chain(catch_up_keys.into_iter().flatten())
+            .cloned()
+            .collect();
+        let decrypted = trial_decrypt_txs(
+            txs.values().map(|(_, height, _, _, stx)| (*height, stx)),
+            &vks,
+            &self.birthdays,
+        );
+        for ((txidx, (epoch, height, idx, tx, stx)), decrypted) in
+            txs.iter().zip(decrypted)
+        {
+            if *txidx >= self.last_txidx {
+                self.scan_decrypted_tx(
+                    client,
+                    *height,
+                    *idx,
+                    *epoch,
+                    tx,
+                    stx,
+                    decrypted.clone(),
+                )
+                .await?;
+            }
+            if let Some(catch_up) =
+                self.catch_up.as_mut().filter(|_| catch_up_txidx.is_some())
+            {
+                catch_up
+                    .scan_decrypted_tx(
+                        client, *height, *idx, *epoch, tx, stx, decrypted,
+                    )
+                    .await?;
+                // The catch-up context is not captured by the scanned
+                // transactions of this one
+                self.snapshot_stale = true;
             }
-            // Merge the context data originating from the unknown keys into the
-            // current context
-            self.merge(tx_ctx);
-        } else {
-            // Load only transactions accepted from last_txid until this point
-            txs =
-                Self::fetch_shielded_transfers(client, self.last_txidx).await?;
-            tx_iter = txs.iter();
         }
-        // Now that we possess the unspent notes corresponding to both old and
-        // new keys up until tx_pos, proceed to scan the new transactions.
-        for ((height, idx), (epoch, tx, stx)) in &mut tx_iter {
-            self.scan_tx(client, *height, *idx, *epoch, tx, stx).await?;
+        // Once level, the keys being caught up are tracked along with the
+        // others
+        if self
+            .catch_up
+            .as_ref()
+            .map_or(false, |catch_up| catch_up.last_txidx == self.last_txidx)
+        {
+            let catch_up = self.catch_up.take().expect("checked above");
+            self.merge(*catch_up);
         }
         Ok(())
     }
 
+    /// Check whether the given viewing key is being caught up with the
+    /// transactions scanned for the other keys of this context, in which case
+    /// its notes are not known yet
+    pub fn is_catching_up(&self, vk: &ViewingKey) -> bool {
+        self.catch_up
+            .as_ref()
+            .map_or(false, |catch_up| catch_up.pos_map.contains_key(vk))
+    }
+
     /// Obtain a chronologically-ordered list of all accepted shielded
     /// transactions from the ledger. The ledger conceptually stores
     /// transactions as a vector. More concretely, the HEAD_TX_KEY location
     /// stores the index of the last accepted transaction and each transaction
-    /// is stored at a key derived from its index.
+    /// is stored at a key derived from its index. The transactions are
+    /// queried in batches of [`MAX_MASP_TXS_PER_QUERY`], with up to
+    /// [`MASP_FETCH_CONCURRENCY`] batches being fetched at once, and are
+    /// returned keyed by their index. A single transaction can apply several
+    /// shielded transactions, so their block heights and indices are not
+    /// unique.
     pub async fn fetch_shielded_transfers<C: Client + Sync>(
         client: &C,
         last_txidx: u64,
-    ) -> Result<
-        BTreeMap<(BlockHeight, TxIndex), (Epoch, Transfer, Transaction)>,
-        Error,
-    > {
-        // The address of the MASP account
-        let masp_addr = masp();
-        // Construct the key where last transaction pointer is stored
-        let head_tx_key = Key::from(masp_addr.to_db_key())
-            .push(&HEAD_TX_KEY.to_owned())
-            .map_err(|k| {
-                Error::Other(format!("Cannot obtain a storage key: {}", k))
-            })?;
+    ) -> Result<BTreeMap<u64, IndexedMaspTx>, Error> {
         // Query for the index of the last accepted transaction
-        let head_txidx = query_storage_value::<C, u64>(client, &head_tx_key)
-            .await
-            .unwrap_or(0);
-        let mut shielded_txs = BTreeMap::new();
+        let head_txidx = query_head_txidx(client).await?;
         // Fetch all the transactions we do not have yet
-        for i in last_txidx..head_txidx {
-            // Construct the key for where the current transaction is stored
-            let current_tx_key = Key::from(masp_addr.to_db_key())
-                .push(&(TX_KEY_PREFIX.to_owned() + &i.to_string()))
-                .map_err(|e| {
-                    Error::Other(format!("Cannot obtain a storage key {}", e))
-                })?;
-            // Obtain the current transaction
-            let (tx_epoch, tx_height, tx_index, current_tx, current_stx) =
-                query_storage_value::<
-                    C,
-                    (Epoch, BlockHeight, TxIndex, Transfer, Transaction),
-                >(client, &current_tx_key)
-                .await?;
-            // Collect the current transaction
-            shielded_txs.insert(
-                (tx_height, tx_index),
-                (tx_epoch, current_tx, current_stx),
-            );
-        }
-        Ok(shielded_txs)
+        let batches: Vec<Vec<IndexedMaspTx>> = stream::iter(
+            (last_txidx..head_txidx)
+                .step_by(MAX_MASP_TXS_PER_QUERY as usize)
+                .map(|from| async move {
+                    let batch = rpc::query_masp_txs(
+                        client,
+                        from,
+                        MAX_MASP_TXS_PER_QUERY,
+                    )
+                    .await?;
+                    // A short batch would leave a gap in the transactions
+                    let expected =
+                        (head_txidx - from).min(MAX_MASP_TXS_PER_QUERY);
+                    if batch.len() as u64 != expected {
+                        return Err(Error::Other(format!(
+                            "Expected {} shielded transactions from index {}, \
+                             but received {}",
+                            expected,
+                            from,
+                            batch.len()
+                        )));
+                    }
+                    Ok::<_, Error>(batch)
+                }),
+        )
+        .buffered(MASP_FETCH_CONCURRENCY)
+        .try_collect()
+        .await?;
+        // Collect the fetched transactions
+        Ok((last_txidx..).zip(batches.into_iter().flatten()).collect())
     }
 
     /// Applies the given transaction to the supplied context. More precisely,
@@ -791,102 +1474,123 @@ impl<U: ShieldedUtils> ShieldedContext<U> {
         epoch: Epoch,
         tx: &Transfer,
         shielded: &Transaction,
+    ) -> Result<(), Error> {
+        let vks: Vec<ViewingKey> = self
+            .pos_map
+            .keys()
+            .filter(|vk| {
+                self.birthdays
+                    .get(vk)
+                    .map_or(true, |birthday| *birthday <= height)
+            })
+            .cloned()
+            .collect();
+        let decrypted = trial_decrypt_outputs(shielded, &vks);
+        self.scan_decrypted_tx(
+            client, height, index, epoch, tx, shielded, decrypted,
+        )
+        .await
+    }
+
+    /// Applies the given transaction to the supplied context, like
+    /// [`Self::scan_tx`], using the given results of trial-decrypting its
+    /// outputs. Only the decrypted notes of the viewing keys of this context
+    /// are taken into account.
+    #[allow(clippy::too_many_arguments)]
+    async fn scan_decrypted_tx<C: Client + Sync>(
+        &mut self,
+        client: &C,
+        height: BlockHeight,
+        index: TxIndex,
+        epoch: Epoch,
+        tx: &Transfer,
+        shielded: &Transaction,
+        decrypted: Vec<DecryptedOutput>,
     ) -> Result<(), Error> {
         // For tracking the account changes caused by this Transaction
         let mut transaction_delta = TransactionDelta::new();
-        // Listen for notes sent to our viewing keys
-        for so in shielded
+        // The outputs will be appended to the commitment tree from its
+        // current end on
+        let first_pos = self.tree.size();
+        let outputs = shielded
             .sapling_bundle()
-            .map_or(&vec![], |x| &x.shielded_outputs)
-        {
-            // Create merkle tree leaf node from note commitment
-            let node = Node::new(so.cmu.to_repr());
-            // Update each merkle tree in the witness map with the latest
-            // addition
-            for (_, witness) in self.witness_map.iter_mut() {
-                witness.append(node).map_err(|()| {
-                    Error::Other("note commitment tree is full".to_string())
-                })?;
-            }
-            let note_pos = self.tree.size();
-            self.tree.append(node).map_err(|()| {
-                Error::Other("note commitment tree is full".to_string())
-            })?;
-            // Finally, make it easier to construct merkle paths to this new
-            // note
-            let witness = IncrementalWitness::<Node>::from_tree(&self.tree);
-            self.witness_map.insert(note_pos, witness);
-            // Let's try to see if any of our viewing keys can decrypt latest
-            // note
-            let mut pos_map = HashMap::new();
-            std::mem::swap(&mut pos_map, &mut self.pos_map);
-            for (vk, notes) in pos_map.iter_mut() {
-                let decres = try_sapling_note_decryption::<_, OutputDescription<<<Authorized as Authorization>::SaplingAuth as masp_primitives::transaction::components::sapling::Authorization>::Proof>>(
-                    &NETWORK,
-                    1.into(),
-                    &PreparedIncomingViewingKey::new(&vk.ivk()),
-                    so,
+            .map_or(&vec![], |x| &x.shielded_outputs);
+        // Create merkle tree leaf nodes from the note commitments
+        let commitments = outputs
+            .iter()
+            .map(|so| Node::new(so.cmu.to_repr()))
+            .collect();
+        // Listen for notes sent to our viewing keys
+        let mut notes = Vec::new();
+        for output_idx in 0..outputs.len() {
+            // Let's see if any of our viewing keys could decrypt this note
+            let decres = decrypted.iter().find(|(idx, vk, _, _, _)| {
+                *idx == output_idx && self.pos_map.contains_key(vk)
+            });
+            // So this current viewing key does decrypt this current note...
+            if let Some((_, vk, note, pa, memo)) = decres {
+                let note_pos = first_pos + output_idx;
+                // Compute the nullifier now to quickly recognize when spent
+                let nf = note.nf(
+                    &vk.nk,
+                    note_pos.try_into().map_err(|_| {
+                        Error::Other("Can not get nullifier".to_string())
+                    })?,
                 );
-                // So this current viewing key does decrypt this current note...
-                if let Some((note, pa, memo)) = decres {
-                    // Add this note to list of notes decrypted by this viewing
-                    // key
-                    notes.insert(note_pos);
-                    // Compute the nullifier now to quickly recognize when spent
-                    let nf = note.nf(
-                        &vk.nk,
-                        note_pos.try_into().map_err(|_| {
-                            Error::Other("Can not get nullifier".to_string())
+                // Note the account changes
+                let value = self
+                    .decode_all_amounts(
+                        client,
+                        I128Sum::from_nonnegative(
+                            note.asset_type,
+                            note.value as i128,
+                        )
+                        .map_err(|()| {
+                            Error::Other(
+                                "found note with invalid value or asset type"
+                                    .to_string(),
+                            )
                         })?,
-                    );
-                    self.note_map.insert(note_pos, note);
-                    self.memo_map.insert(note_pos, memo);
+                    )
+                    .await;
+                *transaction_delta
+                    .entry(*vk)
+                    .or_insert_with(MaspAmount::default) += value;
+                notes.push(ScannedNote {
+                    pos: note_pos,
+                    vk: *vk,
+                    note: *note,
+                    memo: memo.clone(),
                     // The payment address' diversifier is required to spend
                     // note
-                    self.div_map.insert(note_pos, *pa.diversifier());
-                    self.nf_map.insert(nf, note_pos);
-                    // Note the account changes
-                    let balance = transaction_delta
-                        .entry(*vk)
-                        .or_insert_with(MaspAmount::default);
-                    *balance += self
-                        .decode_all_amounts(
-                            client,
-                            I128Sum::from_nonnegative(
-                                note.asset_type,
-                                note.value as i128,
-                            )
-                            .map_err(|()| {
-                                Error::Other(
-                                    "found note with invalid value or asset \
-                                     type"
-                                        .to_string(),
-                                )
-                            })?,
-                        )
-                        .await;
-
-                    self.vk_map.insert(note_pos, *vk);
-                    break;
-                }
+                    diversifier: *pa.diversifier(),
+                    nullifier: nf,
+                });
             }
-            std::mem::swap(&mut pos_map, &mut self.pos_map);
         }
         // Cancel out those of our notes that have been spent
+        let mut spent = Vec::new();
         for ss in shielded
             .sapling_bundle()
             .map_or(&vec![], |x| &x.shielded_spends)
         {
             // If the shielded spend's nullifier is in our map, then target note
             // is rendered unusable
-            if let Some(note_pos) = self.nf_map.get(&ss.nullifier) {
-                self.spents.insert(*note_pos);
+            let spent_note = match self.nf_map.get(&ss.nullifier) {
+                Some(note_pos) => Some((
+                    *note_pos,
+                    self.vk_map[note_pos],
+                    self.note_map[note_pos],
+                )),
+                None => notes
+                    .iter()
+                    .find(|scanned| scanned.nullifier == ss.nullifier)
+                    .map(|scanned| (scanned.pos, scanned.vk, scanned.note)),
+            };
+            if let Some((note_pos, vk, note)) = spent_note {
+                spent.push(note_pos);
                 // Note the account changes
-                let balance = transaction_delta
-                    .entry(self.vk_map[note_pos])
-                    .or_insert_with(MaspAmount::default);
-                let note = self.note_map[note_pos];
-                *balance -= self
+                let value = self
                     .decode_all_amounts(
                         client,
                         I128Sum::from_nonnegative(
@@ -901,6 +1605,9 @@ impl<U: ShieldedUtils> ShieldedContext<U> {
                         })?,
                     )
                     .await;
+                *transaction_delta
+                    .entry(vk)
+                    .or_insert_with(MaspAmount::default) -= value;
             }
         }
         // Record the changes to the transparent accounts
@@ -913,12 +1620,84 @@ impl<U: ShieldedUtils> ShieldedContext<U> {
                 change: -tx.amount.amount.change(),
             },
         );
+        let update = ScannedTx {
+            txidx: self.last_txidx,
+            height,
+            index,
+            epoch,
+            commitments,
+            notes,
+            spent,
+            transfer_delta,
+            transaction_delta,
+        };
+        self.apply_scanned_tx(update.clone())?;
+        self.unsaved.push(update);
+        Ok(())
+    }
+
+    /// Apply the changes made by scanning a transaction to this context. The
+    /// outputs are added to the commitment tree and the witness map is
+    /// maintained to make it easier to construct note merkle paths in other
+    /// code. Newly discovered notes are associated to their viewing keys,
+    /// nullifiers, memos, and diversifiers, and our spent notes are marked.
+    fn apply_scanned_tx(&mut self, update: ScannedTx) -> Result<(), Error> {
+        for node in update.commitments {
+            // Update each merkle tree in the witness map with the latest
+            // addition
+            for (_, witness) in self.witness_map.iter_mut() {
+                witness.append(node).map_err(|()| {
+                    Error::Other("note commitment tree is full".to_string())
+                })?;
+            }
+            let note_pos = self.tree.size();
+            self.tree.append(node).map_err(|()| {
+                Error::Other("note commitment tree is full".to_string())
+            })?;
+            // Finally, make it easier to construct merkle paths to this new
+            // note
+            let witness = IncrementalWitness::<Node>::from_tree(&self.tree);
+            self.witness_map.insert(note_pos, witness);
+        }
+        let tx_pos = (update.height, update.index);
+        for scanned in update.notes {
+            // Add this note to list of notes decrypted by this viewing key
+            self.pos_map
+                .entry(scanned.vk)
+                .or_default()
+                .insert(scanned.pos);
+            self.note_map.insert(scanned.pos, scanned.note);
+            self.memo_map.insert(scanned.pos, scanned.memo);
+            self.div_map.insert(scanned.pos, scanned.diversifier);
+            self.nf_map.insert(scanned.nullifier, scanned.pos);
+            self.vk_map.insert(scanned.pos, scanned.vk);
+            self.note_tx_map.insert(scanned.pos, tx_pos);
+        }
+        for note_pos in update.spent {
+            self.spents.insert(note_pos);
+            self.spent_tx_map.insert(note_pos, tx_pos);
+        }
         self.last_txidx += 1;
 
-        self.delta_map.insert(
-            (height, index),
-            (epoch, transfer_delta, transaction_delta),
-        );
+        // Several shielded transactions can be applied by the same
+        // transaction, in which case their deltas are accumulated
+        let (_, transfer_delta, transaction_delta) =
+            self.delta_map.entry(tx_pos).or_insert_with(|| {
+                (update.epoch, TransferDelta::new(), TransactionDelta::new())
+            });
+        for (addr, change) in update.transfer_delta {
+            match transfer_delta.get_mut(&addr) {
+                Some(acc) if acc.asset == change.asset => {
+                    acc.change += change.change
+                }
+                _ => {
+                    transfer_delta.insert(addr, change);
+                }
+            }
+        }
+        for (vk, amount) in update.transaction_delta {
+            *transaction_delta.entry(vk).or_default() += amount;
+        }
         Ok(())
     }
 
@@ -1474,33 +2253,152 @@ impl<U: ShieldedUtils> ShieldedContext<U> {
     /// UTXOs are sometimes used to make transactions balanced, but it is
     /// understood that transparent account changes are effected only by the
     /// amounts and signatures specified by the containing Transfer object.
+    /// The notes of a viewing key are spent by the external signer selected
+    /// by the transaction arguments, over the given connection if any, see
+    /// [`ShieldedContext::gen_shielded_transfer_external`].
     #[cfg(feature = "masp-tx-gen")]
     pub async fn gen_shielded_transfer<C: Client + Sync>(
         &mut self,
         client: &C,
         args: args::TxTransfer,
+        external_signer: Option<&SharedExternalSigner>,
     ) -> Result<Option<ShieldedTransfer>, TransferErr> {
-        // No shielded components are needed when neither source nor destination
-        // are shielded
+        let InputAmount::Validated(amt) = args.amount else {
+            unreachable!("The function `gen_shielded_transfer` is only called by `submit_tx` which validates amounts.")
+        };
+        match &args.source {
+            // The notes of viewing keys are spent by the external signer
+            // holding their spending keys
+            TransferSource::ExtendedViewingKey(vk)
+                if args.tx.signer != args::SignerKind::Wallet =>
+            {
+                // Shares the connection opened to sign the transaction
+                let signer = ExternalSigner::shared_or_connect(
+                    external_signer,
+                    &args.tx.signer,
+                )?;
+                let mut signer = ExternalSigner::lock(&signer)?;
+                self.gen_shielded_transfer_external(
+                    client,
+                    (*vk).into(),
+                    &mut *signer,
+                    &args.target,
+                    &args.token,
+                    amt,
+                )
+                .await
+            }
+            source => {
+                self.gen_shielded_transfer_parts(
+                    client,
+                    source,
+                    &args.target,
+                    &args.token,
+                    amt,
+                )
+                .await
+            }
+        }
+    }
 
-        use std::str::FromStr;
+    /// Make the shielded components moving the given validated amount of the
+    /// token from the source to the target. See
+    /// [`ShieldedContext::gen_shielded_transfer`].
+    #[cfg(feature = "masp-tx-gen")]
+    pub async fn gen_shielded_transfer_parts<C: Client + Sync>(
+        &mut self,
+        client: &C,
+        source: &TransferSource,
+        target: &TransferTarget,
+        token: &Address,
+        amt: token::DenominatedAmount,
+    ) -> Result<Option<ShieldedTransfer>, TransferErr> {
+        let spender = match source {
+            TransferSource::ExtendedSpendingKey(sk) => {
+                Spender::Local((*sk).into())
+            }
+            TransferSource::ExtendedViewingKey(vk) => {
+                return Err(Error::Other(format!(
+                    "Spending the notes of the viewing key {} requires an \
+                     external signer holding its spending key",
+                    vk
+                ))
+                .into());
+            }
+            TransferSource::Address(_) => Spender::Transparent(source, None),
+        };
+        self.gen_shielded_transfer_with(client, spender, target, token, amt)
+            .await
+    }
 
+    /// Make the shielded components moving the given validated amount of the
+    /// token from a transparent source to the payment address target, along
+    /// with the outgoing cipher keys disclosing each of their outputs. The
+    /// outputs are sent with a fresh outgoing viewing key, so that nothing
+    /// else can be recovered with the cipher keys.
+    #[cfg(feature = "masp-tx-gen")]
+    pub async fn gen_disclosed_shielding_parts<C: Client + Sync>(
+        &mut self,
+        client: &C,
+        source: &TransferSource,
+        target: &TransferTarget,
+        token: &Address,
+        amt: token::DenominatedAmount,
+    ) -> Result<Option<(ShieldedTransfer, Vec<[u8; 32]>)>, TransferErr> {
+        if !matches!(source, TransferSource::Address(_)) {
+            return Err(Error::Other(
+                "Only the outputs shielding the tokens of a transparent \
+                 source can be disclosed"
+                    .to_string(),
+            )
+            .into());
+        }
+        let mut ovk = [0; 32];
+        OsRng.fill_bytes(&mut ovk);
+        let ovk = OutgoingViewingKey(ovk);
+        let spender = Spender::Transparent(source, Some(ovk));
+        let stx = self
+            .gen_shielded_transfer_with(client, spender, target, token, amt)
+            .await?;
+        Ok(stx.map(|stx| {
+            let ocks = output_cipher_keys(&stx.masp_tx, &ovk);
+            (stx, ocks)
+        }))
+    }
+
+    /// Make the shielded components moving the given validated amount of the
+    /// token from the given spender to the target
+    #[cfg(feature = "masp-tx-gen")]
+    async fn gen_shielded_transfer_with<C: Client + Sync>(
+        &mut self,
+        client: &C,
+        spender: Spender<'_>,
+        target: &TransferTarget,
+        token: &Address,
+        amt: token::DenominatedAmount,
+    ) -> Result<Option<ShieldedTransfer>, TransferErr> {
         use rand::rngs::StdRng;
         use rand_core::SeedableRng;
 
-        let spending_key = args.source.spending_key();
-        let payment_address = args.target.payment_address();
+        let payment_address = target.payment_address();
         // No shielded components are needed when neither source nor
         // destination are shielded
-        if spending_key.is_none() && payment_address.is_none() {
+        if matches!(spender, Spender::Transparent(..))
+            && payment_address.is_none()
+        {
             return Ok(None);
         }
-        // We want to fund our transaction solely from supplied spending key
-        let spending_key = spending_key.map(|x| x.into());
-        let spending_keys: Vec<_> = spending_key.into_iter().collect();
-        // Load the current shielded context given the spending key we possess
+        // We want to fund our transaction solely from the notes of the spender
+        let fvk = spender.full_viewing_key();
+        // Load the current shielded context given the keys we possess
         let _ = self.load().await;
-        self.fetch(client, &spending_keys, &[]).await?;
+        match &spender {
+            Spender::Transparent(..) => self.fetch(client, &[], &[]).await?,
+            Spender::Local(sk) => self.fetch(client, &[*sk], &[]).await?,
+            Spender::External(fvk, _) => {
+                self.fetch(client, &[], &[fvk.fvk.vk]).await?
+            }
+        }
         // Save the update state so that future fetches can be short-circuited
         let _ = self.save().await;
         // Determine epoch in which to submit potential shielded transaction
@@ -1534,20 +2432,16 @@ impl<U: ShieldedUtils> ShieldedContext<U> {
 
         // break up a transfer into a number of transfers with suitable
         // denominations
-        let InputAmount::Validated(amt) = args.amount else {
-            unreachable!("The function `gen_shielded_transfer` is only called by `submit_tx` which validates amounts.")
-        };
         // Convert transaction amount into MASP types
-        let (asset_types, amount) =
-            convert_amount(epoch, &args.token, amt.amount)?;
+        let (asset_types, amount) = convert_amount(epoch, token, amt.amount)?;
 
         // If there are shielded inputs
-        if let Some(sk) = spending_key {
+        if let (Some(fvk), Some(sk)) = (fvk, spender.builder_key()) {
             // Locate unspent notes that can help us meet the transaction amount
             let (_, unspent_notes, used_convs) = self
                 .collect_unspent_notes(
                     client,
-                    &to_viewing_key(&sk).vk,
+                    &fvk.fvk.vk,
                     I128Sum::from_sum(amount),
                     epoch,
                 )
@@ -1570,12 +2464,11 @@ impl<U: ShieldedUtils> ShieldedContext<U> {
                         .map_err(builder::Error::SaplingBuild)?;
                 }
             }
-        } else {
+        } else if let Spender::Transparent(source, _) = spender {
             // We add a dummy UTXO to our transaction, but only the source of
             // the parent Transfer object is used to validate fund
             // availability
-            let source_enc = args
-                .source
+            let source_enc = source
                 .address()
                 .ok_or_else(|| {
                     Error::Other(
@@ -1607,7 +2500,7 @@ impl<U: ShieldedUtils> ShieldedContext<U> {
         // Now handle the outputs of this transaction
         // If there is a shielded output
         if let Some(pa) = payment_address {
-            let ovk_opt = spending_key.map(|x| x.expsk.ovk);
+            let ovk_opt = spender.outgoing_viewing_key();
             for (denom, asset_type) in MaspDenom::iter().zip(asset_types.iter())
             {
                 builder
@@ -1623,8 +2516,7 @@ impl<U: ShieldedUtils> ShieldedContext<U> {
         } else {
             // Embed the transparent target address into the shielded
             // transaction so that it can be signed
-            let target_enc = args
-                .target
+            let target_enc = target
                 .address()
                 .ok_or_else(|| {
                     Error::Other(
@@ -1656,7 +2548,7 @@ impl<U: ShieldedUtils> ShieldedContext<U> {
         }
 
         // Now add outputs representing the change from this payment
-        if let Some(sk) = spending_key {
+        if let Some(fvk) = fvk {
             // Represents the amount of inputs we are short by
             let mut additional = I128Sum::zero();
             for (asset_type, amt) in builder
@@ -1673,8 +2565,8 @@ impl<U: ShieldedUtils> ShieldedContext<U> {
                     // Send the change in this asset type back to the sender
                     builder
                         .add_sapling_output(
-                            Some(sk.expsk.ovk),
-                            sk.default_address().1,
+                            Some(fvk.fvk.ovk),
+                            fvk.default_address().1,
                             *asset_type,
                             *amt as u64,
                             memo.clone(),
@@ -1725,7 +2617,12 @@ impl<U: ShieldedUtils> ShieldedContext<U> {
             LoadOrSaveProofs::Neither
         };
 
-        let builder_clone = builder.clone().map_builder(WalletMap);
+        let builder_clone = match spender {
+            Spender::External(fvk, _) => builder
+                .clone()
+                .map_builder(external_signer::ExternalMap(fvk)),
+            _ => builder.clone().map_builder(WalletMap),
+        };
         #[cfg(feature = "testing")]
         let builder_bytes = BorshSerialize::try_to_vec(&builder_clone)
             .map_err(|e| {
@@ -1734,10 +2631,14 @@ impl<U: ShieldedUtils> ShieldedContext<U> {
 
         let build_transfer =
             || -> Result<ShieldedTransfer, builder::Error<std::convert::Infallible>> {
-                let (masp_tx, metadata) = builder.build(
-                    &self.utils.local_tx_prover(),
-                    &FeeRule::non_standard(U64Sum::zero()),
-                )?;
+                let fee_rule = FeeRule::non_standard(U64Sum::zero());
+                let (masp_tx, metadata) = match spender {
+                    Spender::External(_, prover) => {
+                        builder.build(prover, &fee_rule)?
+                    }
+                    _ => builder
+                        .build(&self.utils.local_tx_prover(), &fee_rule)?,
+                };
                 Ok(ShieldedTransfer {
                     builder: builder_clone,
                     masp_tx,
@@ -1935,6 +2836,276 @@ impl<U: ShieldedUtils> ShieldedContext<U> {
         }
         Ok(transfers)
     }
+
+    /// Obtain the notes received and spent by the given viewing key in the
+    /// order of the transactions that created and spent them. The context is
+    /// brought up to date with the given viewing keys before.
+    pub async fn query_shielded_history<C: Client + Sync>(
+        &mut self,
+        client: &C,
+        vk: &ViewingKey,
+        viewing_keys: &HashMap<String, ExtendedViewingKey>,
+    ) -> Result<Vec<ShieldedHistoryEntry>, Error> {
+        // Only the shielded transactions are needed
+        let deltas = self
+            .query_tx_deltas(
+                client,
+                &Either::Right(vec![]),
+                &None,
+                viewing_keys,
+            )
+            .await?;
+        let positions = match self.pos_map.get(vk) {
+            Some(positions) => positions.clone(),
+            None => return Ok(vec![]),
+        };
+        let mut history = vec![];
+        for note_pos in positions {
+            let note = self.note_map[&note_pos];
+            let memo = self
+                .memo_map
+                .get(&note_pos)
+                .cloned()
+                .unwrap_or_else(MemoBytes::empty);
+            let value =
+                I128Sum::from_nonnegative(note.asset_type, note.value as i128)
+                    .map_err(|()| {
+                        Error::Other(
+                            "found note with invalid value or asset type"
+                                .to_string(),
+                        )
+                    })?;
+            let amount = self.decode_all_amounts(client, value).await;
+            let events = [
+                (NoteEvent::Received, self.note_tx_map.get(&note_pos)),
+                (NoteEvent::Spent, self.spent_tx_map.get(&note_pos)),
+            ];
+            for (event, location) in events {
+                // Notes scanned before their transactions were tracked have no
+                // location
+                let (height, index) = match location {
+                    Some(location) => *location,
+                    None => continue,
+                };
+                let epoch = match deltas.get(&(height, index)) {
+                    Some((epoch, _, _)) => *epoch,
+                    None => continue,
+                };
+                let (exchanged, _) = self
+                    .compute_exchanged_amount(
+                        client,
+                        amount.clone(),
+                        epoch,
+                        Conversions::new(),
+                    )
+                    .await?;
+                let exchanged =
+                    self.decode_amount(client, exchanged, epoch).await;
+                history.push(ShieldedHistoryEntry {
+                    height,
+                    index,
+                    epoch,
+                    event,
+                    note_pos,
+                    memo: memo.clone(),
+                    amount: amount.clone(),
+                    exchanged,
+                });
+            }
+        }
+        history.sort_by_key(|entry| {
+            (entry.height, entry.index, entry.event, entry.note_pos)
+        });
+        Ok(history)
+    }
+
+    /// Find the first shielded transaction applied at the given block height
+    /// and index, along with its index among all the accepted shielded
+    /// transactions. These are stored in the order they were applied in, so
+    /// they are binary searched using their compact form.
+    pub async fn query_shielded_transfer<C: Client + Sync>(
+        client: &C,
+        height: BlockHeight,
+        index: TxIndex,
+    ) -> Result<Option<(u64, IndexedMaspTx)>, Error> {
+        let missing = |txidx: u64| {
+            Error::Other(format!("Shielded transaction {} is missing", txidx))
+        };
+        let head_txidx = query_head_txidx(client).await?;
+        let (mut low, mut high) = (0, head_txidx);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let tx = rpc::query_compact_masp_txs(client, mid, 1)
+                .await?
+                .pop()
+                .ok_or_else(|| missing(mid))?;
+            if (tx.height, tx.index) < (height, index) {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        if low == head_txidx {
+            return Ok(None);
+        }
+        let tx = rpc::query_masp_txs(client, low, 1)
+            .await?
+            .pop()
+            .ok_or_else(|| missing(low))?;
+        Ok(((tx.1, tx.2) == (height, index)).then_some((low, tx)))
+    }
+
+    /// Disclose the outputs of the shielded transaction applied at the given
+    /// block height and index that were sent with the given viewing key. Each
+    /// disclosure can be verified by anyone without access to the key.
+    pub async fn disclose_payments<C: Client + Sync>(
+        client: &C,
+        vk: &ExtendedFullViewingKey,
+        height: BlockHeight,
+        index: TxIndex,
+    ) -> Result<Vec<PaymentDisclosure>, Error> {
+        let (txidx, (_, _, _, _, shielded)) =
+            Self::query_shielded_transfer(client, height, index)
+                .await?
+                .ok_or_else(|| {
+                    Error::Other(format!(
+                        "No shielded transaction found at height {} and \
+                         index {}",
+                        height, index.0
+                    ))
+                })?;
+        let ovk = vk.fvk.ovk;
+        let mut disclosures = vec![];
+        for (output_idx, so) in shielded
+            .sapling_bundle()
+            .map_or(&vec![], |x| &x.shielded_outputs)
+            .iter()
+            .enumerate()
+        {
+            // Only the outputs sent with our key can be recovered
+            let recovered =
+                try_sapling_output_recovery(&NETWORK, 1.into(), &ovk, so);
+            if let Some((note, pa, memo)) = recovered {
+                let ock =
+                    prf_ock(&ovk, &so.cv, &so.cmu.to_repr(), &so.ephemeral_key);
+                disclosures.push(PaymentDisclosure {
+                    txidx,
+                    height,
+                    index,
+                    output_idx,
+                    ock: ock.0,
+                    payment_address: pa.into(),
+                    asset_type: note.asset_type,
+                    value: note.value,
+                    memo,
+                });
+            }
+        }
+        Ok(disclosures)
+    }
+
+    /// Verify the given payment disclosure against the shielded transaction
+    /// it refers to. The disclosed output must decrypt with the disclosed
+    /// key to exactly the disclosed note and memo.
+    pub async fn verify_payment_disclosure<C: Client + Sync>(
+        &mut self,
+        client: &C,
+        disclosure: &PaymentDisclosure,
+    ) -> Result<DisclosedPayment, Error> {
+        let (epoch, height, index, _, shielded) =
+            rpc::query_masp_txs(client, disclosure.txidx, 1)
+                .await?
+                .pop()
+                .ok_or_else(|| {
+                    Error::Other(format!(
+                        "Shielded transaction {} does not exist",
+                        disclosure.txidx
+                    ))
+                })?;
+        if (height, index) != (disclosure.height, disclosure.index) {
+            return Err(Error::Other(format!(
+                "Shielded transaction {} was applied at height {} and index \
+                 {}, not at the disclosed height {} and index {}",
+                disclosure.txidx,
+                height,
+                index.0,
+                disclosure.height,
+                disclosure.index.0
+            )));
+        }
+        let so = shielded
+            .sapling_bundle()
+            .and_then(|x| x.shielded_outputs.get(disclosure.output_idx))
+            .ok_or_else(|| {
+                Error::Other(format!(
+                    "Shielded transaction {} has no output {}",
+                    disclosure.txidx, disclosure.output_idx
+                ))
+            })?;
+        let (note, pa, memo) = try_sapling_output_recovery_with_ock(
+            &NETWORK,
+            1.into(),
+            &OutgoingCipherKey(disclosure.ock),
+            so,
+        )
+        .ok_or_else(|| {
+            Error::Other(
+                "The disclosed key does not decrypt the disclosed output"
+                    .to_string(),
+            )
+        })?;
+        if masp_primitives::sapling::PaymentAddress::from(
+            disclosure.payment_address,
+        ) != pa
+            || note.asset_type != disclosure.asset_type
+            || note.value != disclosure.value
+            || memo != disclosure.memo
+        {
+            return Err(Error::Other(
+                "The disclosed output does not match the disclosed payment"
+                    .to_string(),
+            ));
+        }
+        let (token, denom, asset_epoch) = self
+            .decode_asset_type(client, note.asset_type)
+            .await
+            .ok_or_else(|| {
+                Error::Other(format!(
+                    "Unknown asset type {} of the disclosed payment",
+                    note.asset_type
+                ))
+            })?;
+        Ok(DisclosedPayment {
+            epoch,
+            token,
+            denom,
+            asset_epoch,
+            amount: token::Amount::from_masp_denominated(note.value, denom),
+        })
+    }
+}
+
+/// Query the number of accepted shielded transactions, which is the index
+/// that the next accepted transaction will be stored at
+async fn query_head_txidx<C: Client + Sync>(client: &C) -> Result<u64, Error> {
+    // The address of the MASP account
+    let masp_addr = masp();
+    // Construct the key where last transaction pointer is stored
+    let head_tx_key = Key::from(masp_addr.to_db_key())
+        .push(&HEAD_TX_KEY.to_owned())
+        .map_err(|k| {
+            Error::Other(format!("Cannot obtain a storage key: {}", k))
+        })?;
+    // The head is only written once the first transaction is accepted
+    let (bytes, _proof) =
+        rpc::query_storage_value_bytes(client, &head_tx_key, None, false)
+            .await?;
+    match bytes {
+        Some(bytes) => u64::try_from_slice(&bytes).map_err(|err| {
+            Error::from(EncodingError::Decoding(err.to_string()))
+        }),
+        None => Ok(0),
+    }
 }
 
 /// Extract the payload from the given Tx object
@@ -1999,7 +3170,149 @@ fn convert_amount(
     Ok((asset_types, amount))
 }
 
+/// Derive the outgoing cipher keys of all the outputs of the given shielded
+/// transaction that were sent with the given outgoing viewing key, in order
+pub fn output_cipher_keys(
+    shielded: &Transaction,
+    ovk: &OutgoingViewingKey,
+) -> Vec<[u8; 32]> {
+    shielded
+        .sapling_bundle()
+        .map_or(&vec![], |x| &x.shielded_outputs)
+        .iter()
+        .map(|so| prf_ock(ovk, &so.cv, &so.cmu.to_repr(), &so.ephemeral_key).0)
+        .collect()
+}
+
+/// Helpers for testing the scanning of shielded transactions
+#[cfg(test)]
+pub(crate) mod testing {
+    use masp_primitives::sapling::prover::mock::MockTxProver;
+
+    use super::*;
+    use crate::ledger::storage_api::{StorageRead, StorageWrite};
+
+    /// The asset type of the notes of the test shielded transactions
+    pub fn test_asset_type() -> AssetType {
+        AssetType::new(b"test").unwrap()
+    }
+
+    /// Build a shielded transaction moving the given value of the test asset
+    /// type from a transparent input into a note for the given payment
+    /// address. The proofs of the transaction are not valid.
+    pub fn shielding_tx(
+        pa: masp_primitives::sapling::PaymentAddress,
+        value: u64,
+    ) -> Transaction {
+        shielding_tx_with(test_asset_type(), None, pa, value)
+    }
+
+    /// Build a shielding transaction like [`shielding_tx`] for the given
+    /// asset type, whose output can be recovered with the given outgoing
+    /// viewing key
+    pub fn shielding_tx_with(
+        asset_type: AssetType,
+        ovk: Option<OutgoingViewingKey>,
+        pa: masp_primitives::sapling::PaymentAddress,
+        value: u64,
+    ) -> Transaction {
+        let mut builder =
+            Builder::<TestNetwork, _>::new_with_rng(NETWORK, 1.into(), OsRng);
+        builder
+            .add_transparent_input(TxOut {
+                asset_type,
+                value,
+                address: TransparentAddress([0; 20]),
+            })
+            .unwrap();
+        builder
+            .add_sapling_output(ovk, pa, asset_type, value, MemoBytes::empty())
+            .unwrap();
+        builder
+            .build(&MockTxProver, &FeeRule::non_standard(U64Sum::zero()))
+            .unwrap()
+            .0
+    }
+
+    /// Pair the given shielded transaction with a dummy transfer, as applied
+    /// at the given block height and index
+    pub fn indexed_masp_tx(
+        height: u64,
+        index: u32,
+        shielded: Transaction,
+    ) -> IndexedMaspTx {
+        let transfer = Transfer {
+            source: masp(),
+            target: masp(),
+            token: masp(),
+            amount: token::Amount::zero().native_denominated(),
+            key: None,
+            shielded: None,
+        };
+        (
+            Epoch(0),
+            BlockHeight(height),
+            TxIndex(index),
+            transfer,
+            shielded,
+        )
+    }
+
+    /// Append the given shielded transactions to the ones recorded in the
+    /// storage, as done by the transactions applying them
+    pub fn append_masp_txs<S>(storage: &mut S, txs: &[IndexedMaspTx])
+    where
+        S: StorageRead + StorageWrite,
+    {
+        let masp_addr = masp();
+        let head_tx_key = Key::from(masp_addr.to_db_key())
+            .push(&HEAD_TX_KEY.to_owned())
+            .unwrap();
+        let mut head_txidx: u64 =
+            storage.read(&head_tx_key).unwrap().unwrap_or(0);
+        for tx in txs {
+            let tx_key = Key::from(masp_addr.to_db_key())
+                .push(&(TX_KEY_PREFIX.to_owned() + &head_txidx.to_string()))
+                .unwrap();
+            storage.write(&tx_key, tx.clone()).unwrap();
+            head_txidx += 1;
+        }
+        storage.write(&head_tx_key, head_txidx).unwrap();
+    }
+}
+
+#[cfg(test)]
 mod tests {
+    use std::collections::{BTreeSet, HashMap};
+    use std::str::FromStr;
+
+    use async_trait::async_trait;
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use masp_primitives::memo::MemoBytes;
+    use masp_primitives::sapling::keys::OutgoingViewingKey;
+    use masp_primitives::sapling::Node;
+
+    use masp_primitives::zip32::{ExtendedFullViewingKey, ExtendedSpendingKey};
+
+    use super::testing::{
+        append_masp_txs, indexed_masp_tx, shielding_tx, shielding_tx_with,
+        test_asset_type,
+    };
+    use super::{
+        output_cipher_keys, query_head_txidx, trial_decrypt_compact_outputs,
+        trial_decrypt_outputs, trial_decrypt_txs, MaspChange,
+        PaymentDisclosure, ScannedTx, ShieldedContext,
+        UnversionedShieldedContext, SHIELDED_CONTEXT_MAGIC,
+        SHIELDED_CONTEXT_VERSION,
+    };
+    use crate::ledger::queries::testing::TestClient;
+    use crate::ledger::queries::{CompactMaspTx, RPC};
+    use crate::types::address::{masp, nam, Address, InternalAddress};
+    use crate::types::ibc::IbcShieldedTransfer;
+    use crate::types::masp::PaymentAddress;
+    use crate::types::storage::{BlockHeight, Epoch, TxIndex};
+    use crate::types::token::{self, MaspDenom};
+
     /// quick and dirty test. will fail on size check
     #[test]
     #[should_panic(expected = "parameter file size is not correct")]
@@ -2022,9 +3335,8 @@ mod tests {
                 .expect("expected a writable temp file (on sync)");
         }
 
-        std::env::set_var(super::ENV_VAR_MASP_PARAMS_DIR, tempdir.as_os_str());
         // should panic here
-        super::load_pvks();
+        super::load_pvks_from(&tempdir);
     }
 
     /// a more involved test, using dummy parameters with the right
@@ -2095,8 +3407,565 @@ mod tests {
                 .expect("expected a writable temp file (on sync)");
         }
 
-        std::env::set_var(super::ENV_VAR_MASP_PARAMS_DIR, tempdir.as_os_str());
         // should panic here
-        super::load_pvks();
+        super::load_pvks_from(&tempdir);
+    }
+
+    #[derive(Debug, Default, Clone, BorshSerialize, BorshDeserialize)]
+    struct TestUtils;
+
+    #[async_trait(?Send)]
+    impl super::ShieldedUtils for TestUtils {
+        fn local_tx_prover(&self) -> super::LocalTxProver {
+            unimplemented!()
+        }
+
+        async fn load(self) -> std::io::Result<ShieldedContext<Self>> {
+            unimplemented!()
+        }
+
+        async fn save(
+            &self,
+            _ctx: &ShieldedContext<Self>,
+        ) -> std::io::Result<()> {
+            unimplemented!()
+        }
+    }
+
+    /// A scanned transaction with a single output for none of our keys
+    fn scanned_tx(txidx: u64) -> ScannedTx {
+        ScannedTx {
+            txidx,
+            height: BlockHeight(txidx + 1),
+            index: TxIndex(0),
+            epoch: Epoch(0),
+            commitments: vec![Node::new([txidx as u8; 32])],
+            notes: vec![],
+            spent: vec![],
+            transfer_delta: Default::default(),
+            transaction_delta: Default::default(),
+        }
+    }
+
+    /// Test that shielded contexts are restored from versioned and legacy
+    /// snapshots and brought up to date by replaying their logs.
+    #[test]
+    fn test_shielded_context_persistence() {
+        let mut ctx = ShieldedContext::<TestUtils>::default();
+        ctx.apply_scanned_tx(scanned_tx(0)).unwrap();
+        let snapshot = ctx.to_snapshot();
+
+        // Versioned snapshots round trip
+        let loaded =
+            ShieldedContext::from_snapshot(TestUtils, &snapshot).unwrap();
+        assert_eq!(loaded.last_txidx, 1);
+        assert_eq!(loaded.tree.size(), 1);
+        assert!(!loaded.snapshot_stale);
+
+        // Unversioned snapshots, which lack the transactions of our notes,
+        // are migrated forward
+        ctx.note_tx_map.insert(0, (BlockHeight(1), TxIndex(0)));
+        let unversioned = UnversionedShieldedContext {
+            last_txidx: ctx.last_txidx,
+            tree: ctx.tree.clone(),
+            pos_map: ctx.pos_map.clone(),
+            nf_map: ctx.nf_map.clone(),
+            note_map: ctx.note_map.clone(),
+            memo_map: ctx.memo_map.clone(),
+            div_map: ctx.div_map.clone(),
+            witness_map: ctx.witness_map.clone(),
+            delta_map: ctx.delta_map.clone(),
+            spents: ctx.spents.clone(),
+            asset_types: ctx.asset_types.clone(),
+            vk_map: ctx.vk_map.clone(),
+        }
+        .try_to_vec()
+        .unwrap();
+        let migrated =
+            ShieldedContext::from_snapshot(TestUtils, &unversioned).unwrap();
+        assert_eq!(migrated.last_txidx, 1);
+        assert_eq!(migrated.witness_map.len(), 1);
+        assert!(migrated.note_tx_map.is_empty());
+        assert!(migrated.spent_tx_map.is_empty());
+        assert!(migrated.catch_up.is_none());
+        assert!(migrated.snapshot_stale);
+
+        // Version 1 snapshots, which lack the trailing catch-up context, are
+        // migrated forward
+        let body = ctx.try_to_vec().unwrap();
+        let v1 = [
+            &SHIELDED_CONTEXT_MAGIC[..],
+            &1u32.to_le_bytes(),
+            &body[..body.len() - 1],
+        ]
+        .concat();
+        let migrated = ShieldedContext::from_snapshot(TestUtils, &v1).unwrap();
+        assert_eq!(migrated.last_txidx, 1);
+        assert_eq!(migrated.note_tx_map, ctx.note_tx_map);
+        assert!(migrated.catch_up.is_none());
+        assert!(migrated.snapshot_stale);
+
+        // Snapshots of unknown versions are rejected
+        let mut future = snapshot.clone();
+        future[8..12]
+            .copy_from_slice(&(SHIELDED_CONTEXT_VERSION + 1).to_le_bytes());
+        assert!(ShieldedContext::from_snapshot(TestUtils, &future).is_err());
+
+        // Logged transactions already in the snapshot are skipped
+        let mut log =
+            ScannedTx::encode_log(&[scanned_tx(0), scanned_tx(1)], true);
+        let mut loaded =
+            ShieldedContext::from_snapshot(TestUtils, &snapshot).unwrap();
+        assert_eq!(loaded.replay_log(&log).unwrap(), 1);
+        assert_eq!(loaded.last_txidx, 2);
+        assert_eq!(loaded.tree.size(), 2);
+        assert_eq!(loaded.witness_map.len(), 2);
+        assert!(!loaded.snapshot_stale);
+
+        // Replaying stops at a torn record
+        log.extend(ScannedTx::encode_log(&[scanned_tx(2)], false));
+        log.pop();
+        let mut loaded =
+            ShieldedContext::from_snapshot(TestUtils, &snapshot).unwrap();
+        assert_eq!(loaded.replay_log(&log).unwrap(), 1);
+        assert_eq!(loaded.last_txidx, 2);
+        assert!(loaded.snapshot_stale);
+    }
+
+    /// Test that notes are only decrypted by the viewing keys they were sent
+    /// to, from both full and compact transactions, and that keys are not
+    /// tried on the transactions applied before their birthday.
+    #[test]
+    fn test_trial_decryption() {
+        let fvk = ExtendedFullViewingKey::from(&ExtendedSpendingKey::master(
+            &[1; 32],
+        ));
+        let vk = fvk.fvk.vk;
+        let other_vk = ExtendedFullViewingKey::from(
+            &ExtendedSpendingKey::master(&[2; 32]),
+        )
+        .fvk
+        .vk;
+        let pa = fvk.default_address().1;
+        let stx = shielding_tx(pa, 100);
+
+        let decrypted = trial_decrypt_outputs(&stx, &[other_vk, vk]);
+        assert_eq!(decrypted.len(), 1);
+        let (output_idx, decrypted_vk, note, decrypted_pa, _) = &decrypted[0];
+        assert_eq!(*output_idx, 0);
+        assert_eq!(*decrypted_vk, vk);
+        assert_eq!(note.value, 100);
+        assert_eq!(*decrypted_pa, pa);
+        assert!(trial_decrypt_outputs(&stx, &[other_vk]).is_empty());
+
+        // Compact transactions decrypt to the same notes
+        let compact = CompactMaspTx::from(&indexed_masp_tx(1, 0, stx.clone()));
+        assert_eq!(compact.outputs.len(), 1);
+        assert!(compact.nullifiers.is_empty());
+        let compact_decrypted =
+            trial_decrypt_compact_outputs(&compact, &[other_vk, vk]);
+        assert_eq!(compact_decrypted.len(), 1);
+        let (output_idx, decrypted_vk, compact_note, decrypted_pa) =
+            &compact_decrypted[0];
+        assert_eq!(*output_idx, 0);
+        assert_eq!(*decrypted_vk, vk);
+        assert_eq!(compact_note.value, note.value);
+        assert_eq!(compact_note.cmu(), note.cmu());
+        assert_eq!(*decrypted_pa, pa);
+        assert!(trial_decrypt_compact_outputs(&compact, &[other_vk]).is_empty());
+
+        // Malformed compact outputs are skipped
+        let mut malformed = compact;
+        malformed.outputs[0].enc_ciphertext.pop();
+        assert!(trial_decrypt_compact_outputs(&malformed, &[vk]).is_empty());
+
+        // Keys are only tried from their birthday on
+        let birthdays = HashMap::from([(vk, BlockHeight(2))]);
+        let decrypted = trial_decrypt_txs(
+            [(BlockHeight(1), &stx), (BlockHeight(2), &stx)].into_iter(),
+            &[vk],
+            &birthdays,
+        );
+        assert_eq!(decrypted.len(), 2);
+        assert!(decrypted[0].is_empty());
+        assert_eq!(decrypted[1].len(), 1);
+    }
+
+    /// Test that shielded transactions applied by the same transaction are all
+    /// fetched and that the first of them is found by its position.
+    #[tokio::test]
+    async fn test_fetch_shielded_transfers() {
+        let mut client = TestClient::new(RPC);
+        let pa = ExtendedFullViewingKey::from(&ExtendedSpendingKey::master(
+            &[1; 32],
+        ))
+        .default_address()
+        .1;
+        let txs = [
+            indexed_masp_tx(1, 0, shielding_tx(pa, 1)),
+            indexed_masp_tx(1, 0, shielding_tx(pa, 2)),
+            indexed_masp_tx(2, 0, shielding_tx(pa, 3)),
+        ];
+        append_masp_txs(&mut client.wl_storage, &txs);
+        client.wl_storage.commit_tx();
+        client.wl_storage.commit_block().unwrap();
+
+        let fetched =
+            ShieldedContext::<TestUtils>::fetch_shielded_transfers(&client, 0)
+                .await
+                .unwrap();
+        assert_eq!(fetched.keys().copied().collect::<Vec<_>>(), [0, 1, 2]);
+        for (txidx, tx) in fetched {
+            assert_eq!(tx.4.txid(), txs[txidx as usize].4.txid());
+        }
+        let fetched =
+            ShieldedContext::<TestUtils>::fetch_shielded_transfers(&client, 1)
+                .await
+                .unwrap();
+        assert_eq!(fetched.keys().copied().collect::<Vec<_>>(), [1, 2]);
+
+        let find = |height, index| {
+            ShieldedContext::<TestUtils>::query_shielded_transfer(
+                &client,
+                BlockHeight(height),
+                TxIndex(index),
+            )
+        };
+        let (txidx, tx) = find(1, 0).await.unwrap().unwrap();
+        assert_eq!(txidx, 0);
+        assert_eq!(tx.4.txid(), txs[0].4.txid());
+        assert_eq!(find(2, 0).await.unwrap().unwrap().0, 2);
+        assert!(find(1, 1).await.unwrap().is_none());
+        assert!(find(3, 0).await.unwrap().is_none());
+    }
+
+    /// Test that keys new to a context are caught up separately, without
+    /// holding up the scanning for the other keys, and that they are tracked
+    /// along with the other keys once level with them.
+    #[tokio::test]
+    async fn test_key_catch_up() {
+        let sk_a = ExtendedSpendingKey::master(&[1; 32]);
+        let sk_b = ExtendedSpendingKey::master(&[2; 32]);
+        let fvk_a = ExtendedFullViewingKey::from(&sk_a);
+        let fvk_b = ExtendedFullViewingKey::from(&sk_b);
+        let (vk_a, vk_b) = (fvk_a.fvk.vk, fvk_b.fvk.vk);
+        let txs = [
+            indexed_masp_tx(1, 0, shielding_tx(fvk_a.default_address().1, 1)),
+            indexed_masp_tx(2, 0, shielding_tx(fvk_b.default_address().1, 2)),
+            indexed_masp_tx(3, 0, shielding_tx(fvk_a.default_address().1, 3)),
+        ];
+        // A client of a node lagging behind the other one
+        let mut behind = TestClient::new(RPC);
+        append_masp_txs(&mut behind.wl_storage, &txs[..1]);
+        behind.wl_storage.commit_tx();
+        behind.wl_storage.commit_block().unwrap();
+        let mut client = TestClient::new(RPC);
+        append_masp_txs(&mut client.wl_storage, &txs[..2]);
+        client.wl_storage.commit_tx();
+        client.wl_storage.commit_block().unwrap();
+
+        let mut ctx = ShieldedContext::<TestUtils>::default();
+        ctx.asset_types
+            .insert(test_asset_type(), (nam(), MaspDenom::Zero, Epoch(0)));
+        ctx.fetch(&client, &[], &[vk_a]).await.unwrap();
+        assert_eq!(ctx.last_txidx, 2);
+        assert_eq!(ctx.pos_map[&vk_a], BTreeSet::from([0]));
+
+        // A new key is caught up separately, here only partially
+        ctx.fetch(&behind, &[], &[vk_b]).await.unwrap();
+        assert_eq!(ctx.last_txidx, 2);
+        assert!(ctx.is_catching_up(&vk_b));
+        assert!(!ctx.pos_map.contains_key(&vk_b));
+        assert_eq!(ctx.catch_up.as_ref().unwrap().last_txidx, 1);
+        assert!(
+            ctx.compute_shielded_balance(&client, &vk_b)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(ctx.snapshot_stale);
+
+        // The catch-up is persisted
+        let loaded =
+            ShieldedContext::from_snapshot(TestUtils, &ctx.to_snapshot())
+                .unwrap();
+        assert!(loaded.is_catching_up(&vk_b));
+        assert_eq!(loaded.catch_up.as_ref().unwrap().last_txidx, 1);
+
+        // Fetching the other keys does not advance the catch-up
+        append_masp_txs(&mut client.wl_storage, &txs[2..]);
+        client.wl_storage.commit_tx();
+        client.wl_storage.commit_block().unwrap();
+        ctx.fetch(&client, &[], &[vk_a]).await.unwrap();
+        assert_eq!(ctx.last_txidx, 3);
+        assert_eq!(ctx.pos_map[&vk_a], BTreeSet::from([0, 2]));
+        assert_eq!(ctx.catch_up.as_ref().unwrap().last_txidx, 1);
+
+        // Once level, the key is tracked along with the others, including
+        // when requested by its spending key
+        ctx.fetch(&client, &[sk_b], &[]).await.unwrap();
+        assert!(ctx.catch_up.is_none());
+        assert!(!ctx.is_catching_up(&vk_b));
+        assert_eq!(ctx.last_txidx, 3);
+        assert_eq!(ctx.pos_map[&vk_b], BTreeSet::from([1]));
+        assert_eq!(ctx.note_map[&1].value, 2);
+        assert_eq!(ctx.vk_map[&1], vk_b);
+        assert_eq!(ctx.note_tx_map[&1], (BlockHeight(2), TxIndex(0)));
+        assert_eq!(ctx.pos_map[&vk_a], BTreeSet::from([0, 2]));
+    }
+
+    /// Test that the transactions creating and spending our notes are
+    /// tracked and persisted.
+    #[tokio::test]
+    async fn test_note_tx_tracking() {
+        let fvk = ExtendedFullViewingKey::from(&ExtendedSpendingKey::master(
+            &[1; 32],
+        ));
+        let vk = fvk.fvk.vk;
+        let other_pa = ExtendedFullViewingKey::from(
+            &ExtendedSpendingKey::master(&[2; 32]),
+        )
+        .default_address()
+        .1;
+        let mut client = TestClient::new(RPC);
+        append_masp_txs(
+            &mut client.wl_storage,
+            &[
+                indexed_masp_tx(1, 2, shielding_tx(other_pa, 1)),
+                indexed_masp_tx(2, 1, shielding_tx(fvk.default_address().1, 2)),
+            ],
+        );
+        client.wl_storage.commit_tx();
+        client.wl_storage.commit_block().unwrap();
+
+        let mut ctx = ShieldedContext::<TestUtils>::default();
+        ctx.asset_types
+            .insert(test_asset_type(), (nam(), MaspDenom::Zero, Epoch(0)));
+        ctx.fetch(&client, &[], &[vk]).await.unwrap();
+        assert_eq!(ctx.pos_map[&vk], BTreeSet::from([1]));
+        assert_eq!(
+            ctx.note_tx_map,
+            HashMap::from([(1, (BlockHeight(2), TxIndex(1)))])
+        );
+        assert!(ctx.spent_tx_map.is_empty());
+
+        // Spending the note records the spending transaction
+        let mut spend = scanned_tx(2);
+        spend.height = BlockHeight(4);
+        spend.index = TxIndex(3);
+        spend.spent = vec![1];
+        ctx.apply_scanned_tx(spend).unwrap();
+        assert!(ctx.spents.contains(&1));
+        assert_eq!(
+            ctx.spent_tx_map,
+            HashMap::from([(1, (BlockHeight(4), TxIndex(3)))])
+        );
+        assert_eq!(ctx.note_tx_map[&1], (BlockHeight(2), TxIndex(1)));
+
+        // Both are persisted
+        let loaded =
+            ShieldedContext::from_snapshot(TestUtils, &ctx.to_snapshot())
+                .unwrap();
+        assert_eq!(loaded.note_tx_map, ctx.note_tx_map);
+        assert_eq!(loaded.spent_tx_map, ctx.spent_tx_map);
+    }
+
+    /// Test that the payments sent with a viewing key are disclosed, that the
+    /// disclosures survive their encoding and are verified against the
+    /// chain, and that tampered disclosures are rejected.
+    #[tokio::test]
+    async fn test_payment_disclosure() {
+        let payer = ExtendedFullViewingKey::from(&ExtendedSpendingKey::master(
+            &[1; 32],
+        ));
+        let other = ExtendedFullViewingKey::from(&ExtendedSpendingKey::master(
+            &[2; 32],
+        ));
+        let payee = other.default_address().1;
+        let mut client = TestClient::new(RPC);
+        assert_eq!(query_head_txidx(&client).await.unwrap(), 0);
+        append_masp_txs(
+            &mut client.wl_storage,
+            &[
+                indexed_masp_tx(1, 0, shielding_tx(payee, 3)),
+                indexed_masp_tx(
+                    2,
+                    1,
+                    shielding_tx_with(
+                        test_asset_type(),
+                        Some(payer.fvk.ovk),
+                        payee,
+                        5,
+                    ),
+                ),
+            ],
+        );
+        client.wl_storage.commit_tx();
+        client.wl_storage.commit_block().unwrap();
+        assert_eq!(query_head_txidx(&client).await.unwrap(), 2);
+
+        let disclose = |vk, height, index| {
+            ShieldedContext::<TestUtils>::disclose_payments(
+                &client,
+                vk,
+                BlockHeight(height),
+                TxIndex(index),
+            )
+        };
+        // Only the outputs sent with the given key are disclosed
+        assert!(disclose(&payer, 1, 0).await.unwrap().is_empty());
+        assert!(disclose(&other, 2, 1).await.unwrap().is_empty());
+        assert!(disclose(&payer, 3, 0).await.is_err());
+        let mut disclosures = disclose(&payer, 2, 1).await.unwrap();
+        assert_eq!(disclosures.len(), 1);
+        let disclosure = disclosures.pop().unwrap();
+        assert_eq!(disclosure.txidx, 1);
+        assert_eq!(disclosure.output_idx, 0);
+        assert_eq!(disclosure.value, 5);
+        assert_eq!(
+            masp_primitives::sapling::PaymentAddress::from(
+                disclosure.payment_address
+            ),
+            payee
+        );
+
+        // Disclosures round trip through their encoding
+        let decoded =
+            PaymentDisclosure::from_str(&disclosure.to_string()).unwrap();
+        assert_eq!(
+            decoded.try_to_vec().unwrap(),
+            disclosure.try_to_vec().unwrap()
+        );
+        assert!(PaymentDisclosure::from_str("not a disclosure").is_err());
+
+        let mut ctx = ShieldedContext::<TestUtils>::default();
+        ctx.asset_types
+            .insert(test_asset_type(), (nam(), MaspDenom::Zero, Epoch(0)));
+        let payment = ctx
+            .verify_payment_disclosure(&client, &decoded)
+            .await
+            .unwrap();
+        assert_eq!(payment.token, nam());
+        assert_eq!(payment.epoch, Epoch(0));
+        assert_eq!(
+            payment.amount,
+            token::Amount::from_masp_denominated(5, MaspDenom::Zero)
+        );
+
+        // Tampered disclosures are rejected
+        let tampered = [
+            PaymentDisclosure {
+                value: 6,
+                ..disclosure.clone()
+            },
+            PaymentDisclosure {
+                ock: [0; 32],
+                ..disclosure.clone()
+            },
+            PaymentDisclosure {
+                payment_address: payer.default_address().1.into(),
+                ..disclosure.clone()
+            },
+            PaymentDisclosure {
+                memo: MemoBytes::from_bytes(b"forged").unwrap(),
+                ..disclosure.clone()
+            },
+            PaymentDisclosure {
+                output_idx: 1,
+                ..disclosure.clone()
+            },
+            PaymentDisclosure {
+                txidx: 0,
+                ..disclosure.clone()
+            },
+            PaymentDisclosure {
+                height: BlockHeight(3),
+                ..disclosure.clone()
+            },
+            PaymentDisclosure {
+                txidx: 2,
+                ..disclosure
+            },
+        ];
+        for disclosure in tampered {
+            assert!(
+                ctx.verify_payment_disclosure(&client, &disclosure)
+                    .await
+                    .is_err()
+            );
+        }
+    }
+
+    /// Test that the disclosed outputs of a shielded transfer received over
+    /// IBC are checked to pay the packet receiver.
+    #[test]
+    fn test_ibc_shielded_transfer_receiver() {
+        let receiver = ExtendedFullViewingKey::from(
+            &ExtendedSpendingKey::master(&[1; 32]),
+        )
+        .default_address()
+        .1;
+        let other = ExtendedFullViewingKey::from(&ExtendedSpendingKey::master(
+            &[2; 32],
+        ))
+        .default_address()
+        .1;
+        let ovk = OutgoingViewingKey([3; 32]);
+        let shielded_transfer = |pa| {
+            let masp_tx =
+                shielding_tx_with(test_asset_type(), Some(ovk), pa, 5);
+            IbcShieldedTransfer {
+                transfer: token::Transfer {
+                    source: Address::Internal(InternalAddress::Ibc),
+                    target: masp(),
+                    token: nam(),
+                    amount: token::Amount::zero().native_denominated(),
+                    key: None,
+                    shielded: None,
+                },
+                output_ocks: output_cipher_keys(&masp_tx, &ovk),
+                masp_tx,
+                refund_target: None,
+            }
+        };
+        let paid = PaymentAddress::from(receiver);
+        assert!(shielded_transfer(receiver).pays_only(&paid));
+        // The notes can't be shielded for someone else
+        assert!(!shielded_transfer(other).pays_only(&paid));
+        // Every output has to be disclosed with its own cipher key
+        let mut shielded = shielded_transfer(receiver);
+        shielded.output_ocks.clear();
+        assert!(!shielded.pays_only(&paid));
+        shielded.output_ocks.push([0; 32]);
+        assert!(!shielded.pays_only(&paid));
+    }
+
+    /// Test that the deltas of shielded transactions applied by the same
+    /// transaction are accumulated.
+    #[test]
+    fn test_colliding_tx_deltas() {
+        let source = crate::types::address::testing::established_address_1();
+        let token = crate::types::address::nam();
+        let scanned = |txidx| {
+            let mut tx = scanned_tx(txidx);
+            tx.height = BlockHeight(1);
+            tx.transfer_delta.insert(
+                source.clone(),
+                MaspChange {
+                    asset: token.clone(),
+                    change: token::Change::from(-5i64),
+                },
+            );
+            tx
+        };
+        let mut ctx = ShieldedContext::<TestUtils>::default();
+        ctx.apply_scanned_tx(scanned(0)).unwrap();
+        ctx.apply_scanned_tx(scanned(1)).unwrap();
+        assert_eq!(ctx.last_txidx, 2);
+        assert_eq!(ctx.tree.size(), 2);
+        let deltas = ctx.get_tx_deltas();
+        assert_eq!(deltas.len(), 1);
+        let (_, transfer_delta, _) = &deltas[&(BlockHeight(1), TxIndex(0))];
+        assert_eq!(transfer_delta[&source].change, token::Change::from(-10i64));
     }
 }
@@ -0,0 +1,637 @@
+//! Shielded transfers spending the notes of keys held by an external signer,
+//! such as a hardware wallet.
+//!
+//! The signer never hands out the spend authorizing keys. Instead:
+//! 1. The signer exports the proof generation key of the spending key, which
+//!    suffices to build the proofs of the transaction.
+//! 2. The transaction is built with a stand-in spending key, and the proofs
+//!    are made with the signer's proof generation key instead of the stand-in
+//!    one. The stand-in key shares the nullifier deriving key of the signer's,
+//!    so that the spends reveal the nullifiers of the signer's notes.
+//! 3. The signer is sent the [`MaspBuilder`] section describing the
+//!    transaction together with its sighash and the spend randomizers, and
+//!    returns the spend authorization signatures.
+//! 4. The stand-in signatures of the transaction are replaced by the signer's.
+
+use std::cell::RefCell;
+
+use async_trait::async_trait;
+use borsh::{BorshDeserialize, BorshSerialize};
+use masp_primitives::asset_type::AssetType;
+use masp_primitives::constants::SPENDING_KEY_GENERATOR;
+use masp_primitives::convert::AllowedConversion;
+use masp_primitives::ff::PrimeField;
+use masp_primitives::merkle_tree::MerklePath;
+use masp_primitives::sapling::prover::TxProver;
+use masp_primitives::sapling::redjubjub::{PrivateKey, PublicKey, Signature};
+use masp_primitives::sapling::{
+    spend_sig, Diversifier, Node, PaymentAddress, ProofGenerationKey, Rseed,
+};
+use masp_primitives::transaction::builder::MapBuilder;
+use masp_primitives::transaction::components::{I128Sum, GROTH_PROOF_SIZE};
+use masp_primitives::transaction::{Transaction, TransactionData};
+use masp_primitives::zip32::{ExtendedFullViewingKey, ExtendedSpendingKey};
+use masp_primitives::{bls12_381, jubjub};
+use masp_proofs::prover::LocalTxProver;
+use rand_core::OsRng;
+
+use super::{
+    shielded_sighash, ShieldedContext, ShieldedTransfer, ShieldedUtils,
+    TransferErr,
+};
+use crate::ledger::queries::Client;
+use crate::ledger::tx::used_asset_types;
+use crate::proto::{MaspBuilder, Section};
+use crate::types::address::Address;
+use crate::types::error::Error;
+use crate::types::masp::TransferTarget;
+use crate::types::token;
+
+/// A device holding spending keys that can authorize the spends of shielded
+/// transactions without revealing the keys
+#[async_trait(?Send)]
+pub trait MaspSigner {
+    /// Get the proof generation key of the spending key with the given full
+    /// viewing key
+    async fn proof_generation_key(
+        &mut self,
+        fvk: &ExtendedFullViewingKey,
+    ) -> Result<ProofGenerationKey, Error>;
+
+    /// Sign the spend authorizations of the transaction described by the
+    /// given request, in the order of its spends
+    async fn sign_spends(
+        &mut self,
+        request: &MaspSigningRequest,
+    ) -> Result<Vec<Signature>, Error>;
+}
+
+/// The authorization requested for a single spend of a shielded transaction
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct SpendAuthRequest {
+    /// The full viewing key of the spending key that authorizes the spend
+    pub fvk: ExtendedFullViewingKey,
+    /// The randomizer of the spend authorizing key
+    pub alpha: [u8; 32],
+    /// The randomized spend verification key of the spend
+    pub rk: [u8; 32],
+}
+
+/// A request for the spend authorizations of a shielded transaction
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct MaspSigningRequest {
+    /// The section describing the transaction to be displayed by the signer
+    pub builder: MaspBuilder,
+    /// The sighash of the transaction that the spend authorizations sign
+    pub sighash: [u8; 32],
+    /// The spends of the transaction in order
+    pub spends: Vec<SpendAuthRequest>,
+}
+
+/// A signer holding the spending keys in memory, standing in for a hardware
+/// device
+#[derive(Debug, Clone, Default)]
+pub struct SoftwareMaspSigner {
+    /// The spending keys held by this signer
+    pub keys: Vec<ExtendedSpendingKey>,
+}
+
+impl SoftwareMaspSigner {
+    /// Find the held spending key with the given full viewing key
+    fn find_key(
+        &self,
+        fvk: &ExtendedFullViewingKey,
+    ) -> Result<&ExtendedSpendingKey, Error> {
+        self.keys
+            .iter()
+            .find(|sk| ExtendedFullViewingKey::from(*sk) == *fvk)
+            .ok_or_else(|| {
+                Error::Other(
+                    "The signer does not hold the requested spending key"
+                        .to_string(),
+                )
+            })
+    }
+}
+
+#[async_trait(?Send)]
+impl MaspSigner for SoftwareMaspSigner {
+    async fn proof_generation_key(
+        &mut self,
+        fvk: &ExtendedFullViewingKey,
+    ) -> Result<ProofGenerationKey, Error> {
+        Ok(self.find_key(fvk)?.expsk.proof_generation_key())
+    }
+
+    async fn sign_spends(
+        &mut self,
+        request: &MaspSigningRequest,
+    ) -> Result<Vec<Signature>, Error> {
+        let mut signatures = vec![];
+        for spend in &request.spends {
+            let ask = PrivateKey(self.find_key(&spend.fvk)?.expsk.ask);
+            let alpha = Option::from(jubjub::Fr::from_repr(spend.alpha))
+                .ok_or_else(|| {
+                    Error::Other("Invalid spend randomizer".to_string())
+                })?;
+            // Only sign for the spends that the randomizer was used for
+            let mut rk = [0; 32];
+            PublicKey::from_private(&ask, SPENDING_KEY_GENERATOR)
+                .randomize(alpha, SPENDING_KEY_GENERATOR)
+                .write(&mut rk[..])
+                .map_err(|err| Error::Other(err.to_string()))?;
+            if rk != spend.rk {
+                return Err(Error::Other(
+                    "The spend randomizer does not match the spend".to_string(),
+                ));
+            }
+            signatures.push(spend_sig(
+                ask,
+                alpha,
+                &request.sighash,
+                &mut OsRng,
+            ));
+        }
+        Ok(signatures)
+    }
+}
+
+/// The spending key that the notes of an external signer with the given
+/// proof generation key are spent with when building a transaction. Its proof
+/// generation key and spend authorizations are replaced by the signer's, so
+/// only its nullifier deriving key, which the builder derives the nullifiers
+/// of the spent notes with, is the signer's.
+pub(super) fn stand_in_spending_key(
+    proof_generation_key: &ProofGenerationKey,
+) -> ExtendedSpendingKey {
+    let mut sk = ExtendedSpendingKey::master(&[0; 32]);
+    sk.expsk.nsk = proof_generation_key.nsk;
+    sk
+}
+
+/// Maps the stand-in spending key of a builder to the full viewing key of
+/// the external signer it stands in for
+pub(super) struct ExternalMap(pub ExtendedFullViewingKey);
+
+impl<P1>
+    masp_primitives::transaction::components::sapling::builder::MapBuilder<
+        P1,
+        ExtendedSpendingKey,
+        (),
+        ExtendedFullViewingKey,
+    > for ExternalMap
+{
+    fn map_params(&self, _s: P1) {}
+
+    fn map_key(&self, _s: ExtendedSpendingKey) -> ExtendedFullViewingKey {
+        self.0
+    }
+}
+
+impl<P1, R1, N1>
+    MapBuilder<
+        P1,
+        R1,
+        ExtendedSpendingKey,
+        N1,
+        (),
+        (),
+        ExtendedFullViewingKey,
+        (),
+    > for ExternalMap
+{
+    fn map_rng(&self, _s: R1) {}
+
+    fn map_notifier(&self, _s: N1) {}
+}
+
+/// A prover making the spend proofs with the proof generation key of an
+/// external signer rather than the one of the stand-in spending key. The
+/// spend randomizers are recorded in the order of the spends, for them to be
+/// signed by the external signer.
+pub(super) struct ExternalProver {
+    /// The prover making the proofs
+    pub prover: LocalTxProver,
+    /// The proof generation key of the external signer
+    pub proof_generation_key: ProofGenerationKey,
+    /// The randomizers of the spends proven so far
+    pub alphas: RefCell<Vec<jubjub::Fr>>,
+}
+
+impl TxProver for ExternalProver {
+    type SaplingProvingContext =
+        <LocalTxProver as TxProver>::SaplingProvingContext;
+
+    fn new_sapling_proving_context(&self) -> Self::SaplingProvingContext {
+        self.prover.new_sapling_proving_context()
+    }
+
+    fn spend_proof(
+        &self,
+        ctx: &mut Self::SaplingProvingContext,
+        _proof_generation_key: ProofGenerationKey,
+        diversifier: Diversifier,
+        rseed: Rseed,
+        ar: jubjub::Fr,
+        asset_type: AssetType,
+        value: u64,
+        anchor: bls12_381::Scalar,
+        merkle_path: MerklePath<Node>,
+    ) -> Result<([u8; GROTH_PROOF_SIZE], jubjub::ExtendedPoint, PublicKey), ()>
+    {
+        self.alphas.borrow_mut().push(ar);
+        self.prover.spend_proof(
+            ctx,
+            self.proof_generation_key.clone(),
+            diversifier,
+            rseed,
+            ar,
+            asset_type,
+            value,
+            anchor,
+            merkle_path,
+        )
+    }
+
+    fn output_proof(
+        &self,
+        ctx: &mut Self::SaplingProvingContext,
+        esk: jubjub::Fr,
+        payment_address: PaymentAddress,
+        rcm: jubjub::Fr,
+        asset_type: AssetType,
+        value: u64,
+    ) -> ([u8; GROTH_PROOF_SIZE], jubjub::ExtendedPoint) {
+        self.prover.output_proof(
+            ctx,
+            esk,
+            payment_address,
+            rcm,
+            asset_type,
+            value,
+        )
+    }
+
+    fn convert_proof(
+        &self,
+        ctx: &mut Self::SaplingProvingContext,
+        allowed_conversion: AllowedConversion,
+        value: u64,
+        anchor: bls12_381::Scalar,
+        merkle_path: MerklePath<Node>,
+    ) -> Result<([u8; GROTH_PROOF_SIZE], jubjub::ExtendedPoint), ()> {
+        self.prover.convert_proof(
+            ctx,
+            allowed_conversion,
+            value,
+            anchor,
+            merkle_path,
+        )
+    }
+
+    fn binding_sig(
+        &self,
+        ctx: &mut Self::SaplingProvingContext,
+        amount: &I128Sum,
+        sighash: &[u8; 32],
+    ) -> Result<Signature, ()> {
+        self.prover.binding_sig(ctx, amount, sighash)
+    }
+}
+
+/// Replace the spend authorization signatures of the given transaction with
+/// the given signatures over its sighash, checking that they are valid
+fn apply_spend_signatures(
+    masp_tx: Transaction,
+    sighash: &[u8; 32],
+    signatures: Vec<Signature>,
+) -> Result<Transaction, Error> {
+    let data = masp_tx.into_data();
+    let mut bundle = data.sapling_bundle().cloned().ok_or_else(|| {
+        Error::Other("The transaction has no shielded spends".to_string())
+    })?;
+    if bundle.shielded_spends.len() != signatures.len() {
+        return Err(Error::Other(format!(
+            "Expected {} spend authorizations from the signer, but received \
+             {}",
+            bundle.shielded_spends.len(),
+            signatures.len()
+        )));
+    }
+    for (spend, signature) in bundle.shielded_spends.iter_mut().zip(signatures)
+    {
+        if !spend.rk.verify(sighash, &signature, SPENDING_KEY_GENERATOR) {
+            return Err(Error::Other(
+                "The signer made an invalid spend authorization".to_string(),
+            ));
+        }
+        spend.spend_auth_sig = signature;
+    }
+    TransactionData::from_parts(
+        data.version(),
+        data.consensus_branch_id(),
+        data.lock_time(),
+        data.expiry_height(),
+        data.transparent_bundle().cloned(),
+        Some(bundle),
+    )
+    .freeze()
+    .map_err(|err| Error::Other(err.to_string()))
+}
+
+impl<U: ShieldedUtils> ShieldedContext<U> {
+    /// Make the shielded components moving the given validated amount of the
+    /// token from the notes of the given full viewing key to the target. The
+    /// spends are authorized by the given external signer, which holds the
+    /// corresponding spending key. See
+    /// [`ShieldedContext::gen_shielded_transfer`].
+    pub async fn gen_shielded_transfer_external<
+        C: Client + Sync,
+        S: MaspSigner,
+    >(
+        &mut self,
+        client: &C,
+        fvk: ExtendedFullViewingKey,
+        signer: &mut S,
+        target: &TransferTarget,
+        token: &Address,
+        amt: token::DenominatedAmount,
+    ) -> Result<Option<ShieldedTransfer>, TransferErr> {
+        let proof_generation_key = signer.proof_generation_key(&fvk).await?;
+        if proof_generation_key.to_viewing_key() != fvk.fvk.vk {
+            return Err(Error::Other(
+                "The signer's proof generation key does not belong to the \
+                 spending key"
+                    .to_string(),
+            )
+            .into());
+        }
+        let prover = ExternalProver {
+            prover: self.utils.local_tx_prover(),
+            proof_generation_key,
+            alphas: RefCell::default(),
+        };
+        let Some(transfer) = self
+            .gen_shielded_transfer_with(
+                client,
+                super::Spender::External(fvk, &prover),
+                target,
+                token,
+                amt,
+            )
+            .await?
+        else {
+            return Ok(None);
+        };
+        let sighash = shielded_sighash(&transfer.masp_tx).ok_or_else(|| {
+            Error::Other("Unable to compute the sighash".to_string())
+        })?;
+        let bundle = transfer.masp_tx.sapling_bundle();
+        let spends = bundle.map_or(&[][..], |x| &x.shielded_spends[..]);
+        let alphas = prover.alphas.into_inner();
+        if spends.len() != alphas.len() {
+            return Err(Error::Other(
+                "The spends were not proven with the signer's key".to_string(),
+            )
+            .into());
+        }
+        let spends = spends
+            .iter()
+            .zip(alphas)
+            .map(|(spend, alpha)| {
+                let mut rk = [0; 32];
+                spend
+                    .rk
+                    .write(&mut rk[..])
+                    .map_err(|err| Error::Other(err.to_string()))?;
+                Ok(SpendAuthRequest {
+                    fvk,
+                    alpha: alpha.to_repr(),
+                    rk,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        // Show the signer what it is authorizing
+        let asset_types = used_asset_types(self, client, &transfer.builder)
+            .await
+            .unwrap_or_default();
+        let request = MaspSigningRequest {
+            builder: MaspBuilder {
+                target: Section::MaspTx(transfer.masp_tx.clone()).get_hash(),
+                asset_types,
+                metadata: transfer.metadata.clone(),
+                builder: transfer.builder.clone(),
+            },
+            sighash,
+            spends,
+        };
+        let signatures = signer.sign_spends(&request).await?;
+        let masp_tx =
+            apply_spend_signatures(transfer.masp_tx, &sighash, signatures)?;
+        Ok(Some(ShieldedTransfer {
+            masp_tx,
+            ..transfer
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use masp_primitives::consensus::TestNetwork;
+    use masp_primitives::merkle_tree::FrozenCommitmentTree;
+    use masp_primitives::transaction::builder::Builder;
+    use masp_primitives::transaction::components::sapling::builder::SaplingMetadata;
+    use masp_primitives::transaction::components::I32Sum;
+    use rand_core::RngCore;
+
+    use super::*;
+    use crate::ledger::masp::testing::{
+        append_masp_txs, indexed_masp_tx, shielding_tx_with,
+    };
+    use crate::ledger::masp::{
+        get_params_dir, load_pvks, make_asset_type, verify_shielded_tx,
+        WalletMap, CONVERT_NAME, OUTPUT_NAME, SPEND_NAME,
+    };
+    use crate::ledger::queries::testing::TestClient;
+    use crate::ledger::queries::RPC;
+    use crate::types::address::nam;
+    use crate::types::storage::Epoch;
+    use crate::types::token::MaspDenom;
+
+    /// Shielded utilities making real proofs and persisting nothing
+    #[derive(Debug, Default, Clone, BorshSerialize, BorshDeserialize)]
+    struct ProvingUtils;
+
+    #[async_trait(?Send)]
+    impl ShieldedUtils for ProvingUtils {
+        fn local_tx_prover(&self) -> LocalTxProver {
+            // Download the parameters if they are missing
+            load_pvks();
+            let params_dir = get_params_dir();
+            LocalTxProver::new(
+                &params_dir.join(SPEND_NAME),
+                &params_dir.join(OUTPUT_NAME),
+                &params_dir.join(CONVERT_NAME),
+            )
+        }
+
+        async fn load(self) -> std::io::Result<ShieldedContext<Self>> {
+            Err(std::io::ErrorKind::NotFound.into())
+        }
+
+        async fn save(
+            &self,
+            _ctx: &ShieldedContext<Self>,
+        ) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Test that a shielded transfer spending the notes of a key held by the
+    /// software signer reveals the nullifiers of the notes and is valid.
+    #[tokio::test]
+    async fn test_external_shielded_transfer() {
+        let sk = ExtendedSpendingKey::master(&[1; 32]);
+        let fvk = ExtendedFullViewingKey::from(&sk);
+        let target = ExtendedFullViewingKey::from(
+            &ExtendedSpendingKey::master(&[2; 32]),
+        )
+        .default_address()
+        .1;
+        let mut client = TestClient::new(RPC);
+        // The native token has no conversions, only decodings
+        let asset_types = MaspDenom::iter()
+            .map(|denom| make_asset_type(Some(Epoch(0)), &nam(), denom))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let conversion_state = &mut client.wl_storage.storage.conversion_state;
+        let conv = AllowedConversion::from(I32Sum::zero());
+        let leaves = vec![Node::new(conv.cmu().to_repr()); asset_types.len()];
+        conversion_state.tree = FrozenCommitmentTree::new(&leaves);
+        for (pos, (denom, asset_type)) in
+            MaspDenom::iter().zip(&asset_types).enumerate()
+        {
+            conversion_state.assets.insert(
+                *asset_type,
+                ((nam(), denom), Epoch(0), conv.clone(), pos),
+            );
+        }
+        let note_tx = shielding_tx_with(
+            asset_types[0],
+            None,
+            fvk.default_address().1,
+            10,
+        );
+        append_masp_txs(
+            &mut client.wl_storage,
+            &[indexed_masp_tx(1, 0, note_tx)],
+        );
+        client.wl_storage.commit_tx();
+        client.wl_storage.commit_block().unwrap();
+
+        let mut signer = SoftwareMaspSigner { keys: vec![sk] };
+        let mut ctx = ShieldedContext::<ProvingUtils>::default();
+        let amount = token::DenominatedAmount {
+            amount: token::Amount::from_masp_denominated(6, MaspDenom::Zero),
+            denom: 0.into(),
+        };
+        let transfer = ctx
+            .gen_shielded_transfer_external(
+                &client,
+                fvk,
+                &mut signer,
+                &TransferTarget::PaymentAddress(target.into()),
+                &nam(),
+                amount,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        let spends =
+            &transfer.masp_tx.sapling_bundle().unwrap().shielded_spends;
+        assert_eq!(spends.len(), 1);
+        let nk = sk.expsk.proof_generation_key().to_viewing_key().nk;
+        assert_eq!(spends[0].nullifier, ctx.note_map[&0].nf(&nk, 0));
+        assert!(verify_shielded_tx(&transfer.masp_tx));
+
+        // A signer not holding the spending key cannot stand in for it
+        let mut other = SoftwareMaspSigner {
+            keys: vec![ExtendedSpendingKey::master(&[2; 32])],
+        };
+        assert!(ctx
+            .gen_shielded_transfer_external(
+                &client,
+                fvk,
+                &mut other,
+                &TransferTarget::PaymentAddress(target.into()),
+                &nam(),
+                amount,
+            )
+            .await
+            .is_err());
+    }
+
+    /// Test that the software signer only authorizes the spends of the keys
+    /// and randomizers it is requested to sign for.
+    #[tokio::test]
+    async fn test_software_signer() {
+        let sk = ExtendedSpendingKey::master(&[1; 32]);
+        let fvk = ExtendedFullViewingKey::from(&sk);
+        let mut signer = SoftwareMaspSigner { keys: vec![sk] };
+        let pgk = signer.proof_generation_key(&fvk).await.unwrap();
+        assert_eq!(pgk.ak, sk.expsk.proof_generation_key().ak);
+
+        let mut alpha = [0; 64];
+        OsRng.fill_bytes(&mut alpha);
+        let alpha = jubjub::Fr::from_bytes_wide(&alpha);
+        let rk = PublicKey::from_private(
+            &PrivateKey(sk.expsk.ask),
+            SPENDING_KEY_GENERATOR,
+        )
+        .randomize(alpha, SPENDING_KEY_GENERATOR);
+        let mut rk_bytes = [0; 32];
+        rk.write(&mut rk_bytes[..]).unwrap();
+        let builder = Builder::<TestNetwork, _>::new_with_rng(
+            TestNetwork,
+            1.into(),
+            OsRng,
+        )
+        .map_builder(WalletMap);
+        let mut request = MaspSigningRequest {
+            builder: MaspBuilder {
+                target: Default::default(),
+                asset_types: HashSet::new(),
+                metadata: SaplingMetadata::empty(),
+                builder,
+            },
+            sighash: [2; 32],
+            spends: vec![SpendAuthRequest {
+                fvk,
+                alpha: alpha.to_repr(),
+                rk: rk_bytes,
+            }],
+        };
+        let signatures = signer.sign_spends(&request).await.unwrap();
+        assert_eq!(signatures.len(), 1);
+        assert!(rk.verify(
+            &request.sighash,
+            &signatures[0],
+            SPENDING_KEY_GENERATOR
+        ));
+
+        // A randomizer not matching the spend is rejected
+        request.spends[0].alpha = jubjub::Fr::one().to_repr();
+        assert!(signer.sign_spends(&request).await.is_err());
+
+        // Keys not held by the signer are rejected
+        let other = ExtendedSpendingKey::master(&[3; 32]);
+        request.spends[0].fvk = ExtendedFullViewingKey::from(&other);
+        assert!(signer.sign_spends(&request).await.is_err());
+        assert!(signer
+            .proof_generation_key(&request.spends[0].fvk)
+            .await
+            .is_err());
+    }
+}
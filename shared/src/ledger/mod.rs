@@ -5,7 +5,6 @@ pub mod eth_bridge;
 pub mod events;
 pub mod governance;
 pub mod ibc;
-pub mod inflation;
 pub mod masp;
 pub mod native_vp;
 pub mod pgf;
@@ -22,5 +21,6 @@ pub mod vp_host_fns;
 pub mod wallet;
 
 pub use namada_core::ledger::{
-    gas, parameters, replay_protection, storage_api, tx_env, vp_env,
+    gas, inflation, parameters, replay_protection, storage_api, tx_env,
+    vp_env,
 };
@@ -5,6 +5,9 @@ pub mod ethereum_bridge;
 pub mod multitoken;
 pub mod parameters;
 pub mod replay_protection;
+pub mod scheduled_txs;
+pub mod user;
+pub mod vesting;
 
 use std::cell::RefCell;
 use std::collections::BTreeSet;
@@ -4,6 +4,7 @@ use std::collections::{BTreeSet, HashMap};
 
 use thiserror::Error;
 
+use crate::core::ledger::storage_api::governance;
 use crate::ledger::native_vp::{self, Ctx, NativeVp};
 use crate::ledger::storage;
 use crate::ledger::vp_env::VpEnv;
@@ -11,8 +12,8 @@ use crate::proto::Tx;
 use crate::types::address::{Address, InternalAddress};
 use crate::types::storage::{Key, KeySeg};
 use crate::types::token::{
-    is_any_minted_balance_key, is_any_minter_key, is_any_token_balance_key,
-    minter_key, Amount, Change,
+    is_any_masp_params_key, is_any_minted_balance_key, is_any_minter_key,
+    is_any_token_balance_key, minter_key, Amount, Change, MaspParams,
 };
 use crate::vm::WasmCacheAccess;
 
@@ -47,7 +48,7 @@ where
 
     fn validate_tx(
         &self,
-        _tx: &Tx,
+        tx: &Tx,
         keys_changed: &BTreeSet<Key>,
         verifiers: &BTreeSet<Address>,
     ) -> Result<bool> {
@@ -79,6 +80,19 @@ where
                 if !self.is_valid_minter(token, verifiers)? {
                     return Ok(false);
                 }
+            } else if is_any_masp_params_key(key).is_some() {
+                // The shielded reward parameters can only be changed by
+                // governance and they must be valid
+                let is_accepted = tx.data().map_or(false, |data| {
+                    governance::is_proposal_accepted(&self.ctx.pre(), &data)
+                        .unwrap_or(false)
+                });
+                let params: Option<MaspParams> = self.ctx.read_post(key)?;
+                if !is_accepted
+                    || !params.map_or(true, |params| params.is_valid())
+                {
+                    return Ok(false);
+                }
             } else if key.segments.get(0)
                 == Some(
                     &Address::Internal(InternalAddress::Multitoken).to_db_key(),
@@ -138,11 +152,13 @@ where
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeSet;
+    use std::str::FromStr;
 
     use borsh::BorshSerialize;
     use namada_core::ledger::gas::TxGasMeter;
 
     use super::*;
+    use crate::core::ledger::governance::storage::keys::get_proposal_execution_key;
     use crate::core::ledger::storage::testing::TestWlStorage;
     use crate::core::types::address::nam;
     use crate::core::types::address::testing::{
@@ -152,10 +168,12 @@ mod tests {
     use crate::ledger::ibc::storage::ibc_token;
     use crate::proto::{Code, Data, Section, Signature, Tx};
     use crate::types::address::{Address, InternalAddress};
+    use crate::types::dec::Dec;
     use crate::types::key::testing::keypair_1;
     use crate::types::storage::TxIndex;
     use crate::types::token::{
-        balance_key, minted_balance_key, minter_key, Amount,
+        balance_key, masp_params_key, minted_balance_key, minter_key, Amount,
+        MaspParams, MaspRewardsController,
     };
     use crate::types::transaction::TxType;
     use crate::vm::wasm::compilation_cache::common::testing::cache as wasm_cache;
@@ -609,4 +627,100 @@ mod tests {
                 .expect("validation failed")
         );
     }
+
+    #[test]
+    fn test_masp_params_update() {
+        let mut wl_storage = TestWlStorage::default();
+        let key = masp_params_key(&nam());
+        let keys_changed = BTreeSet::from([key.clone()]);
+        let tx_index = TxIndex::default();
+        let (vp_wasm_cache, _vp_cache_dir) = wasm_cache();
+        let verifiers = BTreeSet::new();
+
+        let proposal_id = 0_u64;
+        wl_storage
+            .storage
+            .write(
+                &get_proposal_execution_key(proposal_id),
+                ().try_to_vec().unwrap(),
+            )
+            .expect("write failed");
+        let tx = dummy_tx(&wl_storage);
+        let governance_tx = {
+            let mut tx = dummy_tx(&wl_storage);
+            tx.set_data(Data::new(proposal_id.try_to_vec().unwrap()));
+            tx
+        };
+        let mut validate = |params: &MaspParams, tx: &Tx| {
+            wl_storage
+                .write_log
+                .write(&key, params.try_to_vec().unwrap())
+                .expect("write failed");
+            let gas_meter = VpGasMeter::new_from_tx_meter(
+                &TxGasMeter::new_from_sub_limit(u64::MAX.into()),
+            );
+            let ctx = Ctx::new(
+                &ADDRESS,
+                &wl_storage.storage,
+                &wl_storage.write_log,
+                tx,
+                &tx_index,
+                gas_meter,
+                &keys_changed,
+                &verifiers,
+                vp_wasm_cache.clone(),
+            );
+            let vp = MultitokenVp { ctx };
+            vp.validate_tx(tx, &keys_changed, &verifiers)
+                .expect("validation failed")
+        };
+
+        let params = MaspParams {
+            reward_rate: (1, 100),
+            controller: None,
+        };
+        // The parameters cannot be changed outside of governance
+        assert!(!validate(&params, &tx));
+        // An accepted proposal can change them
+        assert!(validate(&params, &governance_tx));
+
+        // The reward rate's denominator cannot be zero
+        let zero_precision = MaspParams {
+            reward_rate: (1, 0),
+            controller: None,
+        };
+        assert!(!validate(&zero_precision, &governance_tx));
+        // The reward rate's terms must fit in an `i32`
+        let large_reward = MaspParams {
+            reward_rate: (u32::MAX, 100),
+            controller: None,
+        };
+        assert!(!validate(&large_reward, &governance_tx));
+
+        // The controller's gains must be between 0 and 1
+        let controller = MaspRewardsController {
+            locked_ratio_target: Dec::from_str("0.6667").unwrap(),
+            max_reward_rate: Dec::from_str("0.1").unwrap(),
+            kp_gain_nom: Dec::from_str("0.1").unwrap(),
+            kd_gain_nom: Dec::from_str("0.1").unwrap(),
+        };
+        let with_controller = |controller: MaspRewardsController| MaspParams {
+            reward_rate: (0, 1_000_000),
+            controller: Some(controller),
+        };
+        assert!(validate(
+            &with_controller(controller.clone()),
+            &governance_tx
+        ));
+        let large_gain = MaspRewardsController {
+            kp_gain_nom: Dec::from_str("2.0").unwrap(),
+            ..controller.clone()
+        };
+        assert!(!validate(&with_controller(large_gain), &governance_tx));
+        let negative_gain = MaspRewardsController {
+            kd_gain_nom: Dec::from_str("-0.1").unwrap(),
+            ..controller
+        };
+        assert!(!validate(&with_controller(negative_gain), &governance_tx));
+    }
 }
@@ -0,0 +1,602 @@
+//! Native VP for scheduled transactions
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use namada_core::ledger::gas::get_max_block_gas;
+use namada_core::ledger::replay_protection;
+use namada_core::ledger::scheduled_txs::{
+    get_scheduled_gas_key, is_scheduled_gas_key, is_scheduled_tx_key,
+    is_scheduled_txs_key, ADDRESS,
+};
+use namada_core::ledger::storage;
+use namada_core::ledger::storage_api::scheduled_txs::check_fee_and_gas;
+use namada_core::types::address::Address;
+use namada_core::types::storage::Key;
+use namada_core::types::token::{is_any_token_balance_key, Amount, Change};
+use namada_core::types::transaction::scheduled::{ScheduleTarget, ScheduledTx};
+use namada_core::types::transaction::TxType;
+use thiserror::Error;
+
+use crate::ledger::native_vp::{self, Ctx, NativeVp};
+use crate::ledger::vp_env::VpEnv;
+use crate::proto::Tx;
+use crate::vm::WasmCacheAccess;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Native VP error: {0}")]
+    NativeVpError(#[from] native_vp::Error),
+}
+
+/// Scheduled transactions functions result
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Scheduled transactions VP
+pub struct ScheduledTxsVp<'a, DB, H, CA>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+    CA: WasmCacheAccess,
+{
+    /// Context to interact with the host structures.
+    pub ctx: Ctx<'a, DB, H, CA>,
+}
+
+impl<'a, DB, H, CA> NativeVp for ScheduledTxsVp<'a, DB, H, CA>
+where
+    DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: 'static + storage::StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    type Error = Error;
+
+    fn validate_tx(
+        &self,
+        _tx_data: &Tx,
+        keys_changed: &BTreeSet<Key>,
+        verifiers: &BTreeSet<Address>,
+    ) -> Result<bool> {
+        // The expected change of the escrowed balance of each fee token
+        let mut escrows: HashMap<Address, Change> = HashMap::new();
+        // The actual change of the balance of each token held by this address
+        let mut balances: HashMap<Address, Change> = HashMap::new();
+        // The expected change of the total gas limit at each target
+        let mut gas_changes: BTreeMap<ScheduleTarget, i128> = BTreeMap::new();
+        // The targets whose total gas limit was changed
+        let mut gas_changed = BTreeSet::new();
+
+        for key in keys_changed {
+            if let Some([token, owner]) = is_any_token_balance_key(key) {
+                if owner != &ADDRESS {
+                    continue;
+                }
+                let pre: Amount = self.ctx.read_pre(key)?.unwrap_or_default();
+                let post: Amount = self.ctx.read_post(key)?.unwrap_or_default();
+                *balances.entry(token.clone()).or_default() +=
+                    post.change() - pre.change();
+            } else if let Some(target) = is_scheduled_gas_key(key) {
+                gas_changed.insert(target);
+            } else if let Some((target, hash)) = is_scheduled_tx_key(key) {
+                let pre: Option<ScheduledTx> = self.ctx.read_pre(key)?;
+                let post: Option<ScheduledTx> = self.ctx.read_post(key)?;
+                match (pre, post) {
+                    (None, Some(scheduled_tx)) => {
+                        if !self.is_valid_new_scheduled_tx(
+                            &scheduled_tx,
+                            &target,
+                            &hash,
+                            verifiers,
+                        )? {
+                            return Ok(false);
+                        }
+                        let Some(escrow) = scheduled_tx.escrow() else {
+                            return Ok(false);
+                        };
+                        *gas_changes.entry(target).or_default() +=
+                            i128::from(u64::from(scheduled_tx.gas_limit));
+                        *escrows.entry(scheduled_tx.fee.token).or_default() +=
+                            escrow.change();
+                    }
+                    (Some(scheduled_tx), None) => {
+                        // Only the owner can cancel a scheduled tx
+                        if !verifiers.contains(&scheduled_tx.owner) {
+                            tracing::info!(
+                                "Scheduled tx {} can only be cancelled by its \
+                                 owner {}",
+                                hash,
+                                scheduled_tx.owner
+                            );
+                            return Ok(false);
+                        }
+                        let escrow = scheduled_tx.escrow().unwrap_or_default();
+                        *gas_changes.entry(target).or_default() -=
+                            i128::from(u64::from(scheduled_tx.gas_limit));
+                        *escrows.entry(scheduled_tx.fee.token).or_default() -=
+                            escrow.change();
+                    }
+                    // Scheduled txs cannot be modified in place
+                    (Some(_), Some(_)) => return Ok(false),
+                    (None, None) => {}
+                }
+            } else if is_scheduled_txs_key(key) {
+                // Reject when trying to update an unexpected key under
+                // `#ScheduledTxs/...`
+                return Ok(false);
+            }
+        }
+
+        // The total gas limit at each target must only change by the gas
+        // limits of the added and removed scheduled txs and stay within the
+        // maximum block gas
+        let max_block_gas = get_max_block_gas(&self.ctx.pre())?;
+        let targets: BTreeSet<&ScheduleTarget> =
+            gas_changes.keys().chain(gas_changed.iter()).collect();
+        for target in targets {
+            let key = get_scheduled_gas_key(target);
+            let pre: u64 = self.ctx.read_pre(&key)?.unwrap_or_default();
+            let post: u64 = self.ctx.read_post(&key)?.unwrap_or_default();
+            let change = gas_changes.get(target).copied().unwrap_or_default();
+            if i128::from(post) - i128::from(pre) != change {
+                tracing::info!(
+                    "The total gas limit of the txs scheduled at {} changed \
+                     by an unexpected amount",
+                    target
+                );
+                return Ok(false);
+            }
+            if post > max_block_gas {
+                tracing::info!(
+                    "The total gas limit of the txs scheduled at {} exceeds \
+                     the maximum block gas",
+                    target
+                );
+                return Ok(false);
+            }
+        }
+
+        // The escrowed balances must only change by the fees of the added and
+        // removed scheduled txs
+        let tokens: BTreeSet<&Address> =
+            escrows.keys().chain(balances.keys()).collect();
+        Ok(tokens.into_iter().all(|token| {
+            escrows.get(token).copied().unwrap_or_default()
+                == balances.get(token).copied().unwrap_or_default()
+        }))
+    }
+}
+
+impl<'a, DB, H, CA> ScheduledTxsVp<'a, DB, H, CA>
+where
+    DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: 'static + storage::StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    /// Check that a newly scheduled tx is stored under the key matching its
+    /// target and hash, that it targets a future height or epoch, that its
+    /// fee and gas limit are acceptable, that it has not been executed before
+    /// and that its owner authorized it.
+    fn is_valid_new_scheduled_tx(
+        &self,
+        scheduled_tx: &ScheduledTx,
+        target: &ScheduleTarget,
+        hash: &namada_core::types::hash::Hash,
+        verifiers: &BTreeSet<Address>,
+    ) -> Result<bool> {
+        if &scheduled_tx.target != target
+            || &scheduled_tx.payload_hash() != hash
+        {
+            tracing::info!("Scheduled tx {} stored under an invalid key", hash);
+            return Ok(false);
+        }
+        if !matches!(scheduled_tx.tx.header.tx_type, TxType::Raw) {
+            tracing::info!("Scheduled tx {} must be a raw tx", hash);
+            return Ok(false);
+        }
+        if scheduled_tx.tx.schedule_target() != Some(scheduled_tx.target) {
+            tracing::info!(
+                "Scheduled tx {} is not marked for execution at {}",
+                hash,
+                scheduled_tx.target
+            );
+            return Ok(false);
+        }
+        let is_future = match scheduled_tx.target {
+            ScheduleTarget::Height(height) => {
+                height > self.ctx.get_block_height()?
+            }
+            ScheduleTarget::Epoch(epoch) => {
+                epoch > self.ctx.get_block_epoch()?
+            }
+        };
+        if !is_future {
+            tracing::info!(
+                "Scheduled tx {} targets {} which is not in the future",
+                hash,
+                scheduled_tx.target
+            );
+            return Ok(false);
+        }
+        if let Err(err) = check_fee_and_gas(&self.ctx.pre(), scheduled_tx) {
+            tracing::info!("Scheduled tx {} is invalid: {}", hash, err);
+            return Ok(false);
+        }
+        let replay_key = replay_protection::get_replay_protection_key(hash);
+        if self.ctx.has_key_pre(&replay_key)? {
+            tracing::info!("Scheduled tx {} has already been executed", hash);
+            return Ok(false);
+        }
+        Ok(verifiers.contains(&scheduled_tx.owner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use namada_core::ledger::gas::TxGasMeter;
+    use namada_core::ledger::parameters::storage::{
+        get_gas_cost_key, get_max_block_gas_key,
+    };
+    use namada_core::ledger::scheduled_txs::get_scheduled_tx_key;
+    use namada_core::ledger::storage_api::{StorageRead, StorageWrite};
+
+    use super::*;
+    use crate::core::ledger::storage::testing::TestWlStorage;
+    use crate::core::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+    use crate::core::types::address::{btc, nam};
+    use crate::ledger::gas::VpGasMeter;
+    use crate::proto::{Code, Data};
+    use crate::types::storage::{BlockHeight, Epoch, TxIndex};
+    use crate::types::token::balance_key;
+    use crate::types::transaction::{Fee, GasLimit};
+    use crate::vm::wasm::compilation_cache::common::testing::cache as wasm_cache;
+
+    const MAX_BLOCK_GAS: u64 = 10_000;
+
+    /// Init the parameters read by the VP
+    fn init_storage() -> TestWlStorage {
+        let mut wl_storage = TestWlStorage::default();
+        wl_storage
+            .write(
+                &get_gas_cost_key(),
+                BTreeMap::from([(nam(), Amount::native_whole(1))]),
+            )
+            .unwrap();
+        wl_storage
+            .write(&get_max_block_gas_key(), MAX_BLOCK_GAS)
+            .unwrap();
+        wl_storage.commit_tx();
+        wl_storage
+    }
+
+    fn dummy_scheduled_tx(
+        owner: Address,
+        target: ScheduleTarget,
+    ) -> ScheduledTx {
+        let mut tx = Tx::from_type(TxType::Raw);
+        tx.set_code(Code::new(vec![]));
+        tx.set_data(Data::new(vec![]));
+        tx.set_schedule(target);
+        ScheduledTx {
+            owner,
+            target,
+            fee: Fee {
+                amount_per_gas_unit: Amount::native_whole(1),
+                token: nam(),
+            },
+            gas_limit: GasLimit::from(1_000),
+            tx,
+        }
+    }
+
+    /// Write a newly scheduled tx, together with its escrow and gas, as done
+    /// by `tx_schedule`
+    fn write_scheduled_tx(
+        wl_storage: &mut TestWlStorage,
+        keys_changed: &mut BTreeSet<Key>,
+        scheduled_tx: &ScheduledTx,
+    ) {
+        let key = get_scheduled_tx_key(
+            &scheduled_tx.target,
+            &scheduled_tx.payload_hash(),
+        );
+        wl_storage.write(&key, scheduled_tx.clone()).unwrap();
+        keys_changed.insert(key);
+
+        let escrow_key = balance_key(&scheduled_tx.fee.token, &ADDRESS);
+        let escrow: Amount = wl_storage
+            .read(&escrow_key)
+            .unwrap()
+            .unwrap_or_default()
+            .checked_add(scheduled_tx.escrow().unwrap())
+            .unwrap();
+        wl_storage.write(&escrow_key, escrow).unwrap();
+        keys_changed.insert(escrow_key);
+
+        let gas_key = get_scheduled_gas_key(&scheduled_tx.target);
+        let gas = wl_storage
+            .read::<u64>(&gas_key)
+            .unwrap()
+            .unwrap_or_default()
+            + u64::from(scheduled_tx.gas_limit);
+        wl_storage.write(&gas_key, gas).unwrap();
+        keys_changed.insert(gas_key);
+    }
+
+    fn validate(
+        wl_storage: &TestWlStorage,
+        keys_changed: &BTreeSet<Key>,
+        verifiers: &BTreeSet<Address>,
+    ) -> bool {
+        let tx_index = TxIndex::default();
+        let tx = Tx::from_type(TxType::Raw);
+        let gas_meter = VpGasMeter::new_from_tx_meter(
+            &TxGasMeter::new_from_sub_limit(u64::MAX.into()),
+        );
+        let (vp_wasm_cache, _vp_cache_dir) = wasm_cache();
+        let ctx = Ctx::new(
+            &ADDRESS,
+            &wl_storage.storage,
+            &wl_storage.write_log,
+            &tx,
+            &tx_index,
+            gas_meter,
+            keys_changed,
+            verifiers,
+            vp_wasm_cache,
+        );
+
+        let vp = ScheduledTxsVp { ctx };
+        vp.validate_tx(&tx, keys_changed, verifiers)
+            .expect("validation failed")
+    }
+
+    #[test]
+    fn test_schedule_tx_requires_escrow_and_owner() {
+        let mut wl_storage = init_storage();
+        let mut keys_changed = BTreeSet::new();
+
+        let owner = established_address_1();
+        let scheduled_tx = dummy_scheduled_tx(
+            owner.clone(),
+            ScheduleTarget::Height(BlockHeight(10)),
+        );
+        let escrow = scheduled_tx.escrow().unwrap();
+        let key = get_scheduled_tx_key(
+            &scheduled_tx.target,
+            &scheduled_tx.payload_hash(),
+        );
+        wl_storage.write(&key, scheduled_tx.clone()).unwrap();
+        keys_changed.insert(key);
+        let gas_key = get_scheduled_gas_key(&scheduled_tx.target);
+        wl_storage
+            .write(&gas_key, u64::from(scheduled_tx.gas_limit))
+            .unwrap();
+        keys_changed.insert(gas_key);
+
+        // the fee is not escrowed
+        let verifiers = BTreeSet::from([owner]);
+        assert!(!validate(&wl_storage, &keys_changed, &verifiers));
+
+        let escrow_key = balance_key(&nam(), &ADDRESS);
+        wl_storage.write(&escrow_key, escrow).unwrap();
+        keys_changed.insert(escrow_key);
+        assert!(validate(&wl_storage, &keys_changed, &verifiers));
+
+        // the owner didn't authorize the tx
+        assert!(!validate(&wl_storage, &keys_changed, &BTreeSet::new()));
+    }
+
+    #[test]
+    fn test_schedule_tx_at_epoch() {
+        let owner = established_address_1();
+        let verifiers = BTreeSet::from([owner.clone()]);
+
+        // the target epoch must be in the future
+        for (epoch, is_valid) in [(2, false), (3, false), (4, true)] {
+            let mut wl_storage = init_storage();
+            wl_storage.storage.block.epoch = Epoch(3);
+            let mut keys_changed = BTreeSet::new();
+            let scheduled_tx = dummy_scheduled_tx(
+                owner.clone(),
+                ScheduleTarget::Epoch(Epoch(epoch)),
+            );
+            write_scheduled_tx(
+                &mut wl_storage,
+                &mut keys_changed,
+                &scheduled_tx,
+            );
+            assert_eq!(
+                validate(&wl_storage, &keys_changed, &verifiers),
+                is_valid
+            );
+        }
+    }
+
+    #[test]
+    fn test_schedule_tx_in_the_past() {
+        let mut wl_storage = init_storage();
+        let mut keys_changed = BTreeSet::new();
+        let owner = established_address_1();
+        wl_storage.storage.block.height = BlockHeight(10);
+
+        let scheduled_tx = dummy_scheduled_tx(
+            owner.clone(),
+            ScheduleTarget::Height(BlockHeight(10)),
+        );
+        write_scheduled_tx(&mut wl_storage, &mut keys_changed, &scheduled_tx);
+        assert!(!validate(
+            &wl_storage,
+            &keys_changed,
+            &BTreeSet::from([owner])
+        ));
+    }
+
+    #[test]
+    fn test_schedule_tx_marker() {
+        let owner = established_address_1();
+        let verifiers = BTreeSet::from([owner.clone()]);
+        let target = ScheduleTarget::Height(BlockHeight(10));
+
+        // the tx must be marked for execution at its target
+        for marker in [None, Some(ScheduleTarget::Height(BlockHeight(11)))] {
+            let mut wl_storage = init_storage();
+            let mut keys_changed = BTreeSet::new();
+            let mut scheduled_tx = dummy_scheduled_tx(owner.clone(), target);
+            scheduled_tx
+                .tx
+                .sections
+                .retain(|sec| sec.schedule().is_none());
+            if let Some(marker) = marker {
+                scheduled_tx.tx.set_schedule(marker);
+            }
+            write_scheduled_tx(
+                &mut wl_storage,
+                &mut keys_changed,
+                &scheduled_tx,
+            );
+            assert!(!validate(&wl_storage, &keys_changed, &verifiers));
+        }
+    }
+
+    #[test]
+    fn test_schedule_tx_fee() {
+        let owner = established_address_1();
+        let verifiers = BTreeSet::from([owner.clone()]);
+        let target = ScheduleTarget::Height(BlockHeight(10));
+
+        // the fee must be at least the minimum gas cost
+        let mut scheduled_tx = dummy_scheduled_tx(owner.clone(), target);
+        scheduled_tx.fee.amount_per_gas_unit = Amount::native_whole(1)
+            .checked_sub(Amount::from(1))
+            .unwrap();
+        let mut keys_changed = BTreeSet::new();
+        let mut storage = init_storage();
+        write_scheduled_tx(&mut storage, &mut keys_changed, &scheduled_tx);
+        assert!(!validate(&storage, &keys_changed, &verifiers));
+
+        // a free scheduled tx is rejected
+        scheduled_tx.fee.amount_per_gas_unit = Amount::zero();
+        let mut keys_changed = BTreeSet::new();
+        let mut storage = init_storage();
+        write_scheduled_tx(&mut storage, &mut keys_changed, &scheduled_tx);
+        assert!(!validate(&storage, &keys_changed, &verifiers));
+
+        // the fee token must be allowed for fee payment
+        let mut scheduled_tx = dummy_scheduled_tx(owner, target);
+        scheduled_tx.fee.token = btc();
+        let mut keys_changed = BTreeSet::new();
+        let mut storage = init_storage();
+        write_scheduled_tx(&mut storage, &mut keys_changed, &scheduled_tx);
+        assert!(!validate(&storage, &keys_changed, &verifiers));
+    }
+
+    #[test]
+    fn test_schedule_tx_gas_limit() {
+        let owner = established_address_1();
+        let verifiers = BTreeSet::from([owner.clone()]);
+        let target = ScheduleTarget::Height(BlockHeight(10));
+
+        // a single tx can't exceed the maximum block gas
+        let mut scheduled_tx = dummy_scheduled_tx(owner.clone(), target);
+        scheduled_tx.gas_limit = GasLimit::from(MAX_BLOCK_GAS + 1);
+        let mut keys_changed = BTreeSet::new();
+        let mut storage = init_storage();
+        write_scheduled_tx(&mut storage, &mut keys_changed, &scheduled_tx);
+        assert!(!validate(&storage, &keys_changed, &verifiers));
+
+        // the txs scheduled at the same target can fill up a block
+        let mut storage = init_storage();
+        let mut keys_changed = BTreeSet::new();
+        let mut scheduled_tx = dummy_scheduled_tx(owner.clone(), target);
+        scheduled_tx.gas_limit = GasLimit::from(MAX_BLOCK_GAS / 2);
+        write_scheduled_tx(&mut storage, &mut keys_changed, &scheduled_tx);
+        storage.commit_tx();
+        let mut keys_changed = BTreeSet::new();
+        let mut other_tx = scheduled_tx.clone();
+        other_tx.tx.set_data(Data::new(vec![1]));
+        write_scheduled_tx(&mut storage, &mut keys_changed, &other_tx);
+        assert!(validate(&storage, &keys_changed, &verifiers));
+
+        // but no more than that
+        storage.commit_tx();
+        let mut keys_changed = BTreeSet::new();
+        let mut other_tx = scheduled_tx;
+        other_tx.gas_limit = GasLimit::from(1);
+        other_tx.tx.set_data(Data::new(vec![2]));
+        write_scheduled_tx(&mut storage, &mut keys_changed, &other_tx);
+        assert!(!validate(&storage, &keys_changed, &verifiers));
+
+        // the total gas must match the scheduled txs
+        let mut storage = init_storage();
+        let mut keys_changed = BTreeSet::new();
+        let scheduled_tx = dummy_scheduled_tx(owner, target);
+        write_scheduled_tx(&mut storage, &mut keys_changed, &scheduled_tx);
+        storage
+            .write(&get_scheduled_gas_key(&target), 1_u64)
+            .unwrap();
+        assert!(!validate(&storage, &keys_changed, &verifiers));
+    }
+
+    #[test]
+    fn test_cancel_scheduled_tx() {
+        let mut wl_storage = init_storage();
+        let owner = established_address_1();
+        let target = ScheduleTarget::Height(BlockHeight(10));
+        let scheduled_tx = dummy_scheduled_tx(owner.clone(), target);
+        write_scheduled_tx(
+            &mut wl_storage,
+            &mut BTreeSet::new(),
+            &scheduled_tx,
+        );
+        wl_storage.commit_tx();
+
+        let key = get_scheduled_tx_key(&target, &scheduled_tx.payload_hash());
+        let escrow_key = balance_key(&nam(), &ADDRESS);
+        let gas_key = get_scheduled_gas_key(&target);
+        wl_storage.delete(&key).unwrap();
+        wl_storage.write(&escrow_key, Amount::zero()).unwrap();
+        wl_storage.delete(&gas_key).unwrap();
+        let keys_changed = BTreeSet::from([key.clone(), escrow_key, gas_key]);
+
+        // only the owner can cancel the tx
+        assert!(validate(
+            &wl_storage,
+            &keys_changed,
+            &BTreeSet::from([owner.clone()])
+        ));
+        assert!(!validate(
+            &wl_storage,
+            &keys_changed,
+            &BTreeSet::from([established_address_2()])
+        ));
+
+        // the escrow must be refunded in full
+        let keys_changed = BTreeSet::from([key.clone()]);
+        assert!(!validate(
+            &wl_storage,
+            &keys_changed,
+            &BTreeSet::from([owner.clone()])
+        ));
+
+        // a scheduled tx can't be modified in place
+        let mut wl_storage = init_storage();
+        write_scheduled_tx(
+            &mut wl_storage,
+            &mut BTreeSet::new(),
+            &scheduled_tx,
+        );
+        wl_storage.commit_tx();
+        let mut modified = scheduled_tx;
+        modified.owner = established_address_2();
+        wl_storage.write(&key, modified).unwrap();
+        assert!(!validate(
+            &wl_storage,
+            &BTreeSet::from([key]),
+            &BTreeSet::from([owner, established_address_2()])
+        ));
+    }
+}
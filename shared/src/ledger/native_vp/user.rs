@@ -0,0 +1,502 @@
+//! Native VP for user accounts.
+//!
+//! A built-in equivalent of the wasm `vp_user` that established and implicit
+//! accounts can opt into by setting their validity predicate to
+//! [`NATIVE_VP_USER_CODE_HASH`]. It applies the same rules, but avoids the
+//! cost of running the wasm VP:
+//!
+//! - debits of the account's token balances require a valid signature and
+//!   must satisfy the account's spending policy, if any
+//! - bonds and unbonds with the account as the source require a valid
+//!   signature
+//! - governance votes cast by and PGF stewardship of the account require a
+//!   valid signature
+//! - updates of the account's VP require a valid signature and a whitelisted
+//!   VP
+//! - a pending recovery of the account can be requested and approved by its
+//!   guardians, each with their own valid signature, and cancelled with the
+//!   account's valid signature
+//! - the account's keys and threshold can be replaced without a signature
+//!   only by the execution of an approved recovery
+//! - any other changes in the account's storage sub-space require a valid
+//!   signature
+//!
+//! A signature of one of the account's session keys is accepted instead of a
+//! valid signature only for the txs allowed by the session key, until its
+//! expiry and for debits up to its maximum spend. It is not accepted for
+//! bonds, unbonds, governance votes and PGF stewardship, which are not
+//! bounded by the maximum spend.
+
+use std::cell::OnceCell;
+use std::collections::BTreeSet;
+
+use namada_core::ledger::governance::storage::keys as gov_storage;
+use namada_core::ledger::pgf::storage::keys as pgf_storage;
+use namada_core::ledger::storage;
+use namada_core::ledger::storage_api::governance::is_proposal_accepted;
+use namada_core::ledger::{parameters, storage_api};
+use namada_core::types::account::{
+    AccountPublicKeysMap, DebitAuthorization, RecoveryAuthorization, SessionKey,
+};
+use namada_core::types::address::masp;
+use namada_core::types::hash::Hash;
+use namada_core::types::key::{self, common};
+use namada_core::types::storage::KeySeg;
+use namada_core::types::token::{self, Amount};
+pub use namada_core::types::validity_predicate::NATIVE_VP_USER_CODE_HASH;
+use thiserror::Error;
+
+use crate::ledger::native_vp::{self, Ctx, NativeVp};
+use crate::ledger::pos;
+use crate::ledger::vp_env::VpEnv;
+use crate::proto::Tx;
+use crate::types::address::Address;
+use crate::types::storage::Key;
+use crate::vm::WasmCacheAccess;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Native VP error: {0}")]
+    NativeVpError(#[from] native_vp::Error),
+}
+
+/// User VP functions result
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The type of a changed storage key, as seen by the user VP
+enum KeyType<'a> {
+    Token {
+        token: &'a Address,
+        owner: &'a Address,
+    },
+    PoS,
+    Vp(&'a Address),
+    Masp,
+    PgfSteward(&'a Address),
+    GovernanceVote(&'a Address),
+    Policy(&'a Address),
+    Outflow {
+        owner: &'a Address,
+        token: &'a Address,
+    },
+    Recovery(&'a Address),
+    SessionKey(&'a Address),
+    Unknown,
+}
+
+impl<'a> From<&'a Key> for KeyType<'a> {
+    fn from(key: &'a Key) -> KeyType<'a> {
+        if let Some([token, owner]) = token::is_any_token_balance_key(key) {
+            Self::Token { token, owner }
+        } else if pos::is_pos_key(key) {
+            Self::PoS
+        } else if gov_storage::is_vote_key(key) {
+            match gov_storage::get_voter_address(key) {
+                Some(address) => Self::GovernanceVote(address),
+                None => Self::Unknown,
+            }
+        } else if let Some(address) = pgf_storage::is_stewards_key(key) {
+            Self::PgfSteward(address)
+        } else if let Some(address) = key.is_validity_predicate() {
+            Self::Vp(address)
+        } else if token::is_masp_key(key) {
+            Self::Masp
+        } else if let Some(owner) = key::is_policy_key(key) {
+            Self::Policy(owner)
+        } else if let Some([owner, token]) = key::is_outflow_key(key) {
+            Self::Outflow { owner, token }
+        } else if let Some(owner) = key::is_recovery_key(key) {
+            Self::Recovery(owner)
+        } else if let Some(owner) = key::is_session_key_key(key) {
+            Self::SessionKey(owner)
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+/// User VP
+pub struct UserVp<'a, DB, H, CA>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+    CA: WasmCacheAccess,
+{
+    /// Context to interact with the host structures.
+    pub ctx: Ctx<'a, DB, H, CA>,
+}
+
+impl<'a, DB, H, CA> NativeVp for UserVp<'a, DB, H, CA>
+where
+    DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: 'static + storage::StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    type Error = Error;
+
+    fn validate_tx(
+        &self,
+        tx_data: &Tx,
+        keys_changed: &BTreeSet<Key>,
+        _verifiers: &BTreeSet<Address>,
+    ) -> Result<bool> {
+        let addr = self.ctx.address;
+        // The signatures are only verified once the first key that requires
+        // them is found
+        let verified = OnceCell::new();
+        let valid_sig = || -> Result<bool> {
+            if let Some(valid) = verified.get() {
+                return Ok(*valid);
+            }
+            let valid = self.verify_account_signatures(tx_data, addr)?;
+            Ok(*verified.get_or_init(|| valid))
+        };
+        // The session key that signed the tx, if any, is also only looked up
+        // once it's needed
+        let session = OnceCell::new();
+        let session_key =
+            || -> Result<Option<(common::PublicKey, SessionKey)>> {
+                if let Some(session_key) = session.get() {
+                    return Ok(Option::clone(session_key));
+                }
+                let session_key = self.find_session_key(tx_data, addr)?;
+                Ok(session.get_or_init(|| session_key).clone())
+            };
+        // Updating the account's keys, threshold or spending policy may
+        // require more signatures than the account's threshold
+        let policy_update_sig = || -> Result<bool> {
+            match storage_api::account::policy_update_threshold(
+                &self.ctx.pre(),
+                addr,
+            )? {
+                Some(threshold) => {
+                    self.verify_signatures(tx_data, addr, threshold)
+                }
+                None => valid_sig(),
+            }
+        };
+        let recovery_executed = OnceCell::new();
+        let is_recovery_executed = || -> Result<bool> {
+            if let Some(executed) = recovery_executed.get() {
+                return Ok(*executed);
+            }
+            let executed = storage_api::account::is_recovery_executed(
+                &self.ctx.pre(),
+                &self.ctx.post(),
+                addr,
+            )?;
+            Ok(*recovery_executed.get_or_init(|| executed))
+        };
+
+        if !self.is_valid_tx(tx_data)? {
+            return Ok(false);
+        }
+
+        for key in keys_changed {
+            let is_valid = match KeyType::from(key) {
+                KeyType::Token { token, owner } => {
+                    if owner == addr {
+                        let pre: Amount =
+                            self.ctx.read_pre(key)?.unwrap_or_default();
+                        let post: Amount =
+                            self.ctx.read_post(key)?.unwrap_or_default();
+                        // debit has to signed, credit doesn't
+                        post >= pre
+                            || *addr == masp()
+                            || match storage_api::account::check_debit_policy(
+                                &self.ctx.pre(),
+                                &self.ctx.post(),
+                                addr,
+                                token,
+                                pre - post,
+                                keys_changed,
+                            )? {
+                                DebitAuthorization::Forbidden => false,
+                                DebitAuthorization::AccountThreshold => {
+                                    valid_sig()?
+                                        || self.is_session_key_debit(
+                                            session_key()?,
+                                            addr,
+                                            token,
+                                            pre - post,
+                                        )?
+                                }
+                                DebitAuthorization::LargeDebitThreshold(
+                                    threshold,
+                                ) => self.verify_signatures(
+                                    tx_data, addr, threshold,
+                                )?,
+                            }
+                    } else {
+                        // If this is not the owner, allow any change
+                        true
+                    }
+                }
+                KeyType::PoS => {
+                    let bond_id = pos::is_bond_key(key)
+                        .map(|(bond_id, _)| bond_id)
+                        .or_else(|| {
+                            pos::is_unbond_key(key)
+                                .map(|(bond_id, _, _)| bond_id)
+                        });
+                    match bond_id {
+                        // Bonds and unbonds changes for this address must be
+                        // signed, a session key is not enough
+                        Some(bond_id) => {
+                            &bond_id.source != addr || valid_sig()?
+                        }
+                        // Any other PoS changes are allowed without signature
+                        None => true,
+                    }
+                }
+                KeyType::GovernanceVote(address)
+                | KeyType::PgfSteward(address) => {
+                    address != addr || valid_sig()?
+                }
+                KeyType::Vp(owner) => {
+                    match self.ctx.read_bytes_post(key)? {
+                        Some(vp_hash) => {
+                            (owner != addr || valid_sig()?)
+                                && self.is_vp_whitelisted(&vp_hash)?
+                        }
+                        // The VP cannot be deleted
+                        None => false,
+                    }
+                }
+                KeyType::Masp => true,
+                KeyType::Policy(owner) => owner != addr || policy_update_sig()?,
+                KeyType::Outflow { owner, token } => {
+                    // The outflow can only be increased in the current epoch
+                    owner != addr
+                        || ((valid_sig()? || session_key()?.is_some())
+                            && storage_api::account::is_valid_outflow_update(
+                                &self.ctx.pre(),
+                                &self.ctx.post(),
+                                addr,
+                                token,
+                            )?)
+                }
+                KeyType::Recovery(owner) => {
+                    if owner == addr {
+                        match storage_api::account::check_recovery_update(
+                            &self.ctx.pre(),
+                            &self.ctx.post(),
+                            addr,
+                        )? {
+                            RecoveryAuthorization::Forbidden => false,
+                            RecoveryAuthorization::AccountThreshold => {
+                                valid_sig()?
+                            }
+                            RecoveryAuthorization::Guardians(guardians) => {
+                                // Every new approval must be signed by its
+                                // guardian
+                                let mut valid = true;
+                                for guardian in &guardians {
+                                    valid = valid
+                                        && self.verify_account_signatures(
+                                            tx_data, guardian,
+                                        )?;
+                                }
+                                valid
+                            }
+                            RecoveryAuthorization::Executed => true,
+                        }
+                    } else {
+                        true
+                    }
+                }
+                KeyType::SessionKey(owner) => {
+                    // The session key that signed the tx can only record its
+                    // own spend
+                    owner != addr
+                        || valid_sig()?
+                        || match session_key()? {
+                            Some((public_key, session_key)) => {
+                                *key == key::session_key_key(addr, &public_key)
+                                    && storage_api::account::session_key(
+                                        &self.ctx.post(),
+                                        addr,
+                                        &public_key,
+                                    )?
+                                    .map_or(false, |post| {
+                                        session_key.is_valid_spend_update(&post)
+                                    })
+                            }
+                            None => false,
+                        }
+                }
+                KeyType::Unknown => {
+                    // Unknown changes to this address space require a valid
+                    // signature, anywhere else they are permitted. The keys
+                    // can be replaced by an executed recovery, otherwise they
+                    // require as many signatures as a policy update.
+                    let is_keys_key = key::is_pks_key(key) == Some(addr)
+                        || key::is_threshold_key(key) == Some(addr);
+                    if key.segments.get(0) != Some(&addr.to_db_key()) {
+                        true
+                    } else if is_keys_key {
+                        is_recovery_executed()? || policy_update_sig()?
+                    } else {
+                        valid_sig()?
+                    }
+                }
+            };
+            if !is_valid {
+                tracing::debug!(
+                    "User VP of {} rejected the modification of key {}",
+                    addr,
+                    key
+                );
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl<'a, DB, H, CA> UserVp<'a, DB, H, CA>
+where
+    DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: 'static + storage::StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    /// Check that the tx is whitelisted or that it's the execution of an
+    /// accepted governance proposal
+    fn is_valid_tx(&self, tx_data: &Tx) -> Result<bool> {
+        let key = parameters::storage::get_tx_whitelist_storage_key();
+        let whitelist: Vec<String> =
+            self.ctx.read_pre(&key)?.unwrap_or_default();
+        // if whitelist is empty, allow any transaction
+        if whitelist.is_empty() {
+            return Ok(true);
+        }
+        let tx_hash = self.ctx.get_tx_code_hash()?;
+        if let Some(tx_hash) = tx_hash {
+            if whitelist.contains(&tx_hash.to_string().to_lowercase()) {
+                return Ok(true);
+            }
+        }
+        match tx_data.data() {
+            Some(data) => Ok(is_proposal_accepted(&self.ctx.pre(), &data)?),
+            None => Ok(false),
+        }
+    }
+
+    /// Check that the given VP code hash is whitelisted. The native user VP
+    /// is always allowed.
+    fn is_vp_whitelisted(&self, vp_hash: &[u8]) -> Result<bool> {
+        let Ok(vp_hash) = Hash::try_from(vp_hash) else {
+            return Ok(false);
+        };
+        if vp_hash == NATIVE_VP_USER_CODE_HASH {
+            return Ok(true);
+        }
+        let key = parameters::storage::get_vp_whitelist_storage_key();
+        let whitelist: Vec<String> =
+            self.ctx.read_pre(&key)?.unwrap_or_default();
+        // if whitelist is empty, allow any VP
+        Ok(whitelist.is_empty()
+            || whitelist.contains(&vp_hash.to_string().to_lowercase()))
+    }
+
+    /// Verify the signatures of the tx's data and code sections against the
+    /// public keys of the given account with its threshold
+    fn verify_account_signatures(
+        &self,
+        tx_data: &Tx,
+        owner: &Address,
+    ) -> Result<bool> {
+        let threshold =
+            storage_api::account::threshold(&self.ctx.pre(), owner)?
+                .unwrap_or(1);
+        self.verify_signatures(tx_data, owner, threshold)
+    }
+
+    /// Check that a debit of a token from the given account is authorized by
+    /// the session key that signed the tx and has been recorded in its spend
+    fn is_session_key_debit(
+        &self,
+        session_key: Option<(common::PublicKey, SessionKey)>,
+        owner: &Address,
+        token: &Address,
+        debit: Amount,
+    ) -> Result<bool> {
+        let Some((public_key, session_key)) = session_key else {
+            return Ok(false);
+        };
+        let post = storage_api::account::session_key(
+            &self.ctx.post(),
+            owner,
+            &public_key,
+        )?;
+        Ok(post.map_or(false, |post| {
+            session_key.allows_debit(&post, token, debit)
+        }))
+    }
+
+    /// Find a session key of the given account that is in scope of the tx
+    /// and that signed its data and code sections
+    fn find_session_key(
+        &self,
+        tx_data: &Tx,
+        owner: &Address,
+    ) -> Result<Option<(common::PublicKey, SessionKey)>> {
+        let Some(tx_code_hash) = self.ctx.get_tx_code_hash()? else {
+            return Ok(None);
+        };
+        let max_signatures =
+            parameters::max_signatures_per_transaction(&self.ctx.pre())?;
+        let targets = [*tx_data.data_sechash(), *tx_data.code_sechash()];
+        for (public_key, session_key) in
+            storage_api::account::session_keys_in_scope(
+                &self.ctx.pre(),
+                owner,
+                &tx_code_hash,
+            )?
+        {
+            let public_keys_index_map =
+                AccountPublicKeysMap::from_iter([public_key.clone()]);
+            if tx_data
+                .verify_section_signatures(
+                    &targets,
+                    public_keys_index_map,
+                    1,
+                    max_signatures,
+                    &mut self.ctx.gas_meter.borrow_mut(),
+                )
+                .is_ok()
+            {
+                return Ok(Some((public_key, session_key)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Verify the signatures of the tx's data and code sections against the
+    /// public keys of the given account with the given threshold
+    fn verify_signatures(
+        &self,
+        tx_data: &Tx,
+        owner: &Address,
+        threshold: u8,
+    ) -> Result<bool> {
+        let max_signatures =
+            parameters::max_signatures_per_transaction(&self.ctx.pre())?;
+        let public_keys_index_map =
+            storage_api::account::public_keys_index_map(
+                &self.ctx.pre(),
+                owner,
+            )?;
+        let targets = [*tx_data.data_sechash(), *tx_data.code_sechash()];
+        Ok(tx_data
+            .verify_section_signatures(
+                &targets,
+                public_keys_index_map,
+                threshold,
+                max_signatures,
+                &mut self.ctx.gas_meter.borrow_mut(),
+            )
+            .is_ok())
+    }
+}
@@ -0,0 +1,385 @@
+//! Native VP for vesting accounts
+
+use std::collections::{BTreeSet, HashMap};
+
+use namada_core::ledger::storage;
+use namada_core::ledger::vesting::{
+    get_schedule_key, is_vesting_key, VestingSchedule,
+};
+use namada_core::types::address::Address;
+use namada_core::types::storage::Key;
+use namada_core::types::token::{
+    balance_key, is_any_token_balance_key, Amount, Change,
+};
+use thiserror::Error;
+
+use crate::ledger::native_vp::{self, Ctx, NativeVp};
+use crate::ledger::pos;
+use crate::ledger::vp_env::VpEnv;
+use crate::proto::Tx;
+use crate::vm::WasmCacheAccess;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Native VP error: {0}")]
+    NativeVpError(#[from] native_vp::Error),
+}
+
+/// Vesting functions result
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Vesting VP
+pub struct VestingVp<'a, DB, H, CA>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+    CA: WasmCacheAccess,
+{
+    /// Context to interact with the host structures.
+    pub ctx: Ctx<'a, DB, H, CA>,
+}
+
+impl<'a, DB, H, CA> NativeVp for VestingVp<'a, DB, H, CA>
+where
+    DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: 'static + storage::StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    type Error = Error;
+
+    fn validate_tx(
+        &self,
+        _tx_data: &Tx,
+        keys_changed: &BTreeSet<Key>,
+        _verifiers: &BTreeSet<Address>,
+    ) -> Result<bool> {
+        let epoch = self.ctx.get_block_epoch()?;
+        let native_token = self.ctx.get_native_token()?;
+        // The amount of locked native tokens that left each vesting account.
+        // It can only be moved into PoS, by bonding it from the same account.
+        let mut unlocked: HashMap<Address, Amount> = HashMap::new();
+
+        for key in keys_changed {
+            if let Some([token, owner]) = is_any_token_balance_key(key) {
+                let schedule: Option<VestingSchedule> =
+                    self.ctx.read_pre(&get_schedule_key(owner, token))?;
+                let Some(schedule) = schedule else {
+                    continue;
+                };
+                let locked = schedule.locked_amount(epoch);
+                let pre: Amount = self.ctx.read_pre(key)?.unwrap_or_default();
+                let post: Amount = self.ctx.read_post(key)?.unwrap_or_default();
+                if post >= pre || post >= locked {
+                    continue;
+                }
+                if token != &native_token {
+                    tracing::info!(
+                        "Vesting account {} cannot spend its locked balance \
+                         of {} of token {}",
+                        owner,
+                        locked.to_string_native(),
+                        token
+                    );
+                    return Ok(false);
+                }
+                // Only the part of the debit that went below the locked
+                // amount has to be bonded
+                let debit = pre.min(locked) - post;
+                *unlocked.entry(owner.clone()).or_default() += debit;
+            } else if is_vesting_key(key) {
+                // Vesting schedules can only be set at genesis
+                tracing::info!("Vesting schedules cannot be modified");
+                return Ok(false);
+            }
+        }
+        if unlocked.is_empty() {
+            return Ok(true);
+        }
+
+        // The locked tokens of each vesting account must have been bonded by
+        // the account itself
+        for (owner, unlocked) in &unlocked {
+            let bonded = self.bonded_change(owner, keys_changed)?;
+            if bonded < unlocked.change() {
+                tracing::info!(
+                    "Vesting account {} spent {} locked tokens but only \
+                     bonded {}",
+                    owner,
+                    unlocked.to_string_native(),
+                    bonded.to_string_native()
+                );
+                return Ok(false);
+            }
+        }
+
+        // And they must have all been transferred to PoS
+        let total_unlocked = unlocked
+            .values()
+            .try_fold(Amount::zero(), |acc, amount| acc.checked_add(*amount));
+        let key = balance_key(&native_token, &pos::ADDRESS);
+        let pre: Amount = self.ctx.read_pre(&key)?.unwrap_or_default();
+        let post: Amount = self.ctx.read_post(&key)?.unwrap_or_default();
+        let transferred = post.checked_sub(pre).unwrap_or_default();
+        Ok(total_unlocked
+            .map(|total_unlocked| transferred >= total_unlocked)
+            .unwrap_or_default())
+    }
+}
+
+impl<'a, DB, H, CA> VestingVp<'a, DB, H, CA>
+where
+    DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: 'static + storage::StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    /// Get the net change of the bonds whose source is the given owner. The
+    /// bonds to other validators or at other epochs that the tx reduced are
+    /// deducted, so that bonded tokens cannot be immediately unbonded.
+    fn bonded_change(
+        &self,
+        owner: &Address,
+        keys_changed: &BTreeSet<Key>,
+    ) -> Result<Change> {
+        let mut bonded = Change::zero();
+        for key in keys_changed {
+            let Some((bond_id, _)) = pos::is_bond_key(key) else {
+                continue;
+            };
+            if &bond_id.source != owner {
+                continue;
+            }
+            let pre: Amount = self.ctx.read_pre(key)?.unwrap_or_default();
+            let post: Amount = self.ctx.read_post(key)?.unwrap_or_default();
+            bonded += post.change() - pre.change();
+        }
+        Ok(bonded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use borsh::BorshSerialize;
+    use namada_core::ledger::gas::TxGasMeter;
+    use namada_core::ledger::vesting::{VestingKind, ADDRESS};
+
+    use super::*;
+    use crate::core::ledger::storage::testing::TestWlStorage;
+    use crate::core::types::address::nam;
+    use crate::core::types::address::testing::{
+        established_address_1, established_address_2, established_address_3,
+    };
+    use crate::ledger::gas::VpGasMeter;
+    use crate::types::storage::{Epoch, TxIndex};
+    use crate::types::transaction::TxType;
+    use crate::vm::wasm::compilation_cache::common::testing::cache as wasm_cache;
+
+    fn validate(
+        wl_storage: &TestWlStorage,
+        keys_changed: &BTreeSet<Key>,
+    ) -> bool {
+        let tx_index = TxIndex::default();
+        let tx = Tx::from_type(TxType::Raw);
+        let gas_meter = VpGasMeter::new_from_tx_meter(
+            &TxGasMeter::new_from_sub_limit(u64::MAX.into()),
+        );
+        let verifiers = BTreeSet::new();
+        let (vp_wasm_cache, _vp_cache_dir) = wasm_cache();
+        let ctx = Ctx::new(
+            &ADDRESS,
+            &wl_storage.storage,
+            &wl_storage.write_log,
+            &tx,
+            &tx_index,
+            gas_meter,
+            keys_changed,
+            &verifiers,
+            vp_wasm_cache,
+        );
+
+        let vp = VestingVp { ctx };
+        vp.validate_tx(&tx, keys_changed, &verifiers)
+            .expect("validation failed")
+    }
+
+    #[test]
+    fn test_locked_balance_can_only_be_bonded() {
+        let mut wl_storage = TestWlStorage::default();
+        let mut keys_changed = BTreeSet::new();
+
+        let owner = established_address_1();
+        let token = nam();
+        let schedule = VestingSchedule {
+            amount: Amount::native_whole(100),
+            kind: VestingKind::Cliff {
+                unlock_epoch: Epoch(10),
+            },
+        };
+        wl_storage
+            .storage
+            .write(
+                &get_schedule_key(&owner, &token),
+                schedule.try_to_vec().unwrap(),
+            )
+            .expect("write failed");
+        let owner_key = balance_key(&token, &owner);
+        wl_storage
+            .storage
+            .write(&owner_key, Amount::native_whole(150).try_to_vec().unwrap())
+            .expect("write failed");
+
+        // spending the unlocked part of the balance is allowed
+        wl_storage
+            .write_log
+            .write(&owner_key, Amount::native_whole(100).try_to_vec().unwrap())
+            .expect("write failed");
+        keys_changed.insert(owner_key.clone());
+        assert!(validate(&wl_storage, &keys_changed));
+
+        // spending the locked part is not, unless it is bonded
+        wl_storage
+            .write_log
+            .write(&owner_key, Amount::native_whole(40).try_to_vec().unwrap())
+            .expect("write failed");
+        assert!(!validate(&wl_storage, &keys_changed));
+
+        let bond_key = pos::namada_proof_of_stake::bond_handle(
+            &owner,
+            &established_address_2(),
+        )
+        .get_data_handler()
+        .get_data_key(&Epoch(1));
+        keys_changed.insert(bond_key.clone());
+        let pos_key = balance_key(&token, &pos::ADDRESS);
+        keys_changed.insert(pos_key.clone());
+        // only part of the locked amount is bonded
+        wl_storage
+            .write_log
+            .write(&bond_key, Amount::native_whole(50).try_to_vec().unwrap())
+            .expect("write failed");
+        wl_storage
+            .write_log
+            .write(&pos_key, Amount::native_whole(50).try_to_vec().unwrap())
+            .expect("write failed");
+        assert!(!validate(&wl_storage, &keys_changed));
+
+        wl_storage
+            .write_log
+            .write(&bond_key, Amount::native_whole(60).try_to_vec().unwrap())
+            .expect("write failed");
+        wl_storage
+            .write_log
+            .write(&pos_key, Amount::native_whole(60).try_to_vec().unwrap())
+            .expect("write failed");
+        assert!(validate(&wl_storage, &keys_changed));
+
+        // the schedule cannot be modified
+        let schedule_key = get_schedule_key(&owner, &token);
+        wl_storage
+            .write_log
+            .delete(&schedule_key)
+            .expect("delete failed");
+        keys_changed.insert(schedule_key);
+        assert!(!validate(&wl_storage, &keys_changed));
+    }
+
+    #[test]
+    fn test_locked_balance_cannot_cover_third_party_bond() {
+        let mut wl_storage = TestWlStorage::default();
+        let mut keys_changed = BTreeSet::new();
+
+        let owner = established_address_1();
+        let third_party = established_address_2();
+        let validator = established_address_3();
+        let token = nam();
+        let schedule = VestingSchedule {
+            amount: Amount::native_whole(100),
+            kind: VestingKind::Cliff {
+                unlock_epoch: Epoch(10),
+            },
+        };
+        wl_storage
+            .storage
+            .write(
+                &get_schedule_key(&owner, &token),
+                schedule.try_to_vec().unwrap(),
+            )
+            .expect("write failed");
+        let owner_key = balance_key(&token, &owner);
+        wl_storage
+            .storage
+            .write(&owner_key, Amount::native_whole(100).try_to_vec().unwrap())
+            .expect("write failed");
+
+        // the owner transfers out its locked tokens
+        wl_storage
+            .write_log
+            .write(&owner_key, Amount::zero().try_to_vec().unwrap())
+            .expect("write failed");
+        keys_changed.insert(owner_key);
+        let third_party_key = balance_key(&token, &third_party);
+        wl_storage
+            .write_log
+            .write(
+                &third_party_key,
+                Amount::native_whole(100).try_to_vec().unwrap(),
+            )
+            .expect("write failed");
+        keys_changed.insert(third_party_key);
+
+        // while a third party bonds the same amount
+        let bond_key =
+            pos::namada_proof_of_stake::bond_handle(&third_party, &validator)
+                .get_data_handler()
+                .get_data_key(&Epoch(1));
+        wl_storage
+            .write_log
+            .write(&bond_key, Amount::native_whole(100).try_to_vec().unwrap())
+            .expect("write failed");
+        keys_changed.insert(bond_key);
+        let pos_key = balance_key(&token, &pos::ADDRESS);
+        wl_storage
+            .write_log
+            .write(&pos_key, Amount::native_whole(100).try_to_vec().unwrap())
+            .expect("write failed");
+        keys_changed.insert(pos_key.clone());
+        assert!(!validate(&wl_storage, &keys_changed));
+
+        // the owner's own bond offset by an unbond doesn't count either
+        let own_bond_key =
+            pos::namada_proof_of_stake::bond_handle(&owner, &validator)
+                .get_data_handler()
+                .get_data_key(&Epoch(1));
+        let own_old_bond_key =
+            pos::namada_proof_of_stake::bond_handle(&owner, &third_party)
+                .get_data_handler()
+                .get_data_key(&Epoch(0));
+        wl_storage
+            .storage
+            .write(
+                &own_old_bond_key,
+                Amount::native_whole(100).try_to_vec().unwrap(),
+            )
+            .expect("write failed");
+        wl_storage
+            .write_log
+            .write(
+                &own_bond_key,
+                Amount::native_whole(100).try_to_vec().unwrap(),
+            )
+            .expect("write failed");
+        wl_storage
+            .write_log
+            .delete(&own_old_bond_key)
+            .expect("delete failed");
+        keys_changed.insert(own_bond_key);
+        keys_changed.insert(own_old_bond_key);
+        wl_storage
+            .write_log
+            .write(&pos_key, Amount::native_whole(200).try_to_vec().unwrap())
+            .expect("write failed");
+        assert!(!validate(&wl_storage, &keys_changed));
+    }
+}
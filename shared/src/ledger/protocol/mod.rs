@@ -8,15 +8,16 @@ use masp_primitives::transaction::Transaction;
 use namada_core::ledger::gas::TxGasMeter;
 use namada_core::ledger::storage::wl_storage::WriteLogAndStorage;
 use namada_core::ledger::storage_api::{StorageRead, StorageWrite};
+use namada_core::ledger::vesting::get_schedule_key;
 use namada_core::proto::Section;
 use namada_core::types::hash::Hash;
 use namada_core::types::storage::Key;
-use namada_core::types::token::Amount;
+use namada_core::types::token::{is_any_token_balance_key, Amount};
 use namada_core::types::transaction::WrapperTx;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use thiserror::Error;
 
-use crate::ledger::gas::{self, GasMetering, VpGasMeter};
+use crate::ledger::gas::{self, GasMetering, GasProfile, VpGasMeter};
 use crate::ledger::governance::GovernanceVp;
 use crate::ledger::ibc::vp::Ibc;
 use crate::ledger::native_vp::ethereum_bridge::bridge_pool_vp::BridgePoolVp;
@@ -25,6 +26,9 @@ use crate::ledger::native_vp::ethereum_bridge::vp::EthBridge;
 use crate::ledger::native_vp::multitoken::MultitokenVp;
 use crate::ledger::native_vp::parameters::{self, ParametersVp};
 use crate::ledger::native_vp::replay_protection::ReplayProtectionVp;
+use crate::ledger::native_vp::scheduled_txs::ScheduledTxsVp;
+use crate::ledger::native_vp::user::{UserVp, NATIVE_VP_USER_CODE_HASH};
+use crate::ledger::native_vp::vesting::VestingVp;
 use crate::ledger::native_vp::{self, NativeVp};
 use crate::ledger::pgf::PgfVp;
 use crate::ledger::pos::{self, PosVP};
@@ -89,6 +93,12 @@ pub enum Error {
     ),
     #[error("Non usable tokens native VP error: {0}")]
     NutNativeVpError(native_vp::ethereum_bridge::nut::Error),
+    #[error("Scheduled transactions native VP error: {0}")]
+    ScheduledTxsNativeVpError(native_vp::scheduled_txs::Error),
+    #[error("Vesting native VP error: {0}")]
+    VestingNativeVpError(native_vp::vesting::Error),
+    #[error("User native VP error: {0}")]
+    UserNativeVpError(native_vp::user::Error),
     #[error("Access to an internal address {0} is forbidden")]
     AccessForbidden(InternalAddress),
 }
@@ -206,6 +216,10 @@ where
                 vps_result: VpsResult::default(),
                 initialized_accounts: vec![],
                 ibc_events: BTreeSet::default(),
+                tx_events: vec![],
+                batch_results: vec![],
+                batch_step: None,
+                gas_profile: None,
             })
         }
         TxType::Decrypted(DecryptedTx::Undecryptable) => {
@@ -608,6 +622,11 @@ where
 
 /// Apply a transaction going via the wasm environment. Gas will be metered and
 /// validity predicates will be triggered in the normal way.
+///
+/// The steps of a batch transaction are applied in order, each one being
+/// validated against the changes of the previous ones. If any of the steps
+/// fails or gets rejected, the remaining ones are not applied and the whole
+/// batch must be dropped by the caller.
 pub fn apply_wasm_tx<'a, D, H, CA, WLS>(
     tx: Tx,
     tx_index: &TxIndex,
@@ -638,8 +657,106 @@ where
         )
     };
 
+    let Some(steps) = tx.batch_steps() else {
+        return apply_wasm_tx_step(
+            &tx,
+            tx_index,
+            storage,
+            tx_gas_meter,
+            write_log,
+            vp_wasm_cache,
+            tx_wasm_cache,
+            #[cfg(not(feature = "mainnet"))]
+            has_valid_pow,
+        );
+    };
+
+    let mut result = TxResult::default();
+    for (step_index, step) in steps.iter().enumerate() {
+        // The steps share the index of the batch in its block, their
+        // position in the batch is recorded in their results
+        let mut step_result = apply_wasm_tx_step(
+            step,
+            tx_index,
+            storage,
+            tx_gas_meter,
+            write_log,
+            vp_wasm_cache,
+            tx_wasm_cache,
+            #[cfg(not(feature = "mainnet"))]
+            has_valid_pow,
+        )?;
+        step_result.batch_step = Some(step_index as u64);
+        let accepted = step_result.is_accepted();
+
+        let vps_result = &mut result.vps_result;
+        vps_result
+            .accepted_vps
+            .extend(step_result.vps_result.accepted_vps.iter().cloned());
+        vps_result
+            .rejected_vps
+            .extend(step_result.vps_result.rejected_vps.iter().cloned());
+        vps_result
+            .errors
+            .extend(step_result.vps_result.errors.iter().cloned());
+        // The steps are run one after the other, so their VPs gas adds up
+        vps_result
+            .gas_used
+            .accumulate(&step_result.vps_result.gas_used)?;
+        vps_result.gas_profile = match (
+            vps_result.gas_profile.take(),
+            &step_result.vps_result.gas_profile,
+        ) {
+            (Some(mut profile), Some(step_profile)) => {
+                profile.merge(step_profile.clone());
+                Some(profile)
+            }
+            (profile, step_profile) => profile.or_else(|| step_profile.clone()),
+        };
+        result
+            .initialized_accounts
+            .extend(step_result.initialized_accounts.iter().cloned());
+        result
+            .ibc_events
+            .extend(step_result.ibc_events.iter().cloned());
+        result
+            .tx_events
+            .extend(step_result.tx_events.iter().cloned());
+        result.batch_results.push(step_result);
+
+        if !accepted {
+            break;
+        }
+        // Keep the changes of this step aside, so that the next steps are
+        // validated against them
+        write_log.precommit_batch_step();
+    }
+    result.gas_used = tx_gas_meter.get_tx_consumed_gas();
+    result.changed_keys = write_log.get_keys_with_precommit();
+
+    Ok(result)
+}
+
+/// Execute a single wasm transaction, or a single step of a batch
+/// transaction, and check its validity predicates.
+#[allow(clippy::too_many_arguments)]
+fn apply_wasm_tx_step<D, H, CA>(
+    tx: &Tx,
+    tx_index: &TxIndex,
+    storage: &Storage<D, H>,
+    tx_gas_meter: &mut TxGasMeter,
+    write_log: &mut WriteLog,
+    vp_wasm_cache: &mut VpCache<CA>,
+    tx_wasm_cache: &mut TxCache<CA>,
+    #[cfg(not(feature = "mainnet"))] has_valid_pow: bool,
+) -> Result<TxResult>
+where
+    CA: 'static + WasmCacheAccess + Sync,
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
     let verifiers = execute_tx(
-        &tx,
+        tx,
         tx_index,
         storage,
         tx_gas_meter,
@@ -649,7 +766,7 @@ where
     )?;
 
     let vps_result = check_vps(CheckVps {
-        tx: &tx,
+        tx,
         tx_index,
         storage,
         tx_gas_meter,
@@ -664,6 +781,7 @@ where
     let initialized_accounts = write_log.get_initialized_accounts();
     let changed_keys = write_log.get_keys();
     let ibc_events = write_log.take_ibc_events();
+    let tx_events = write_log.take_tx_events();
 
     Ok(TxResult {
         gas_used,
@@ -671,6 +789,10 @@ where
         vps_result,
         initialized_accounts,
         ibc_events,
+        tx_events,
+        batch_results: vec![],
+        batch_step: None,
+        gas_profile: None,
     })
 }
 
@@ -821,10 +943,13 @@ where
     H: 'static + StorageHasher + Sync,
     CA: 'static + WasmCacheAccess + Sync,
 {
-    let (verifiers, keys_changed) =
+    let (mut verifiers, keys_changed) =
         write_log.verifiers_and_changed_keys(verifiers_from_tx);
+    if changes_vesting_balance(storage, &keys_changed, tx_gas_meter)? {
+        verifiers.insert(Address::Internal(InternalAddress::Vesting));
+    }
 
-    let vps_result = execute_vps(
+    let mut vps_result = execute_vps(
         verifiers,
         keys_changed,
         tx,
@@ -838,10 +963,42 @@ where
     tracing::debug!("Total VPs gas cost {:?}", vps_result.gas_used);
 
     tx_gas_meter.add_vps_gas(&vps_result.gas_used)?;
+    if let (Some(profile), Some(vps_profile)) =
+        (tx_gas_meter.gas_profile(), vps_result.gas_profile.take())
+    {
+        profile.merge(vps_profile);
+    }
 
     Ok(vps_result)
 }
 
+/// Check if any of the changed keys is the balance of a token subject to a
+/// vesting schedule of its owner, in which case the vesting VP must validate
+/// the tx. The vesting schedules can only be written at genesis, so the
+/// committed storage is enough to find them.
+fn changes_vesting_balance<D, H>(
+    storage: &Storage<D, H>,
+    keys_changed: &BTreeSet<storage::Key>,
+    tx_gas_meter: &mut TxGasMeter,
+) -> Result<bool>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    for key in keys_changed {
+        if let Some([token, owner]) = is_any_token_balance_key(key) {
+            let (has_schedule, gas) = storage
+                .has_key(&get_schedule_key(owner, token))
+                .map_err(Error::StorageError)?;
+            tx_gas_meter.consume(gas)?;
+            if has_schedule {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
 /// Execute verifiers' validity predicates
 #[allow(clippy::too_many_arguments)]
 fn execute_vps<D, H, CA>(
@@ -877,25 +1034,45 @@ where
                         return Err(Error::MissingAddress(addr.clone()));
                     };
 
-                    // NOTE: because of the whitelisted gas and the gas metering
-                    // for the exposed vm env functions,
-                    //    the first signature verification (if any) is accounted
-                    // twice
-                    wasm::run::vp(
-                        &vp_code_hash,
-                        tx,
-                        tx_index,
-                        addr,
-                        storage,
-                        write_log,
-                        &mut gas_meter,
-                        &keys_changed,
-                        &verifiers,
-                        vp_wasm_cache.clone(),
-                        #[cfg(not(feature = "mainnet"))]
-                        has_valid_pow,
-                    )
-                    .map_err(Error::VpRunnerError)
+                    if vp_code_hash == NATIVE_VP_USER_CODE_HASH {
+                        let ctx = native_vp::Ctx::new(
+                            addr,
+                            storage,
+                            write_log,
+                            tx,
+                            tx_index,
+                            gas_meter,
+                            &keys_changed,
+                            &verifiers,
+                            vp_wasm_cache.clone(),
+                        );
+                        let user_vp = UserVp { ctx };
+                        let result = user_vp
+                            .validate_tx(tx, &keys_changed, &verifiers)
+                            .map_err(Error::UserNativeVpError);
+                        gas_meter = user_vp.ctx.gas_meter.into_inner();
+                        result
+                    } else {
+                        // NOTE: because of the whitelisted gas and the gas
+                        // metering for the exposed vm env functions,
+                        //    the first signature verification (if any) is
+                        // accounted twice
+                        wasm::run::vp(
+                            &vp_code_hash,
+                            tx,
+                            tx_index,
+                            addr,
+                            storage,
+                            write_log,
+                            &mut gas_meter,
+                            &keys_changed,
+                            &verifiers,
+                            vp_wasm_cache.clone(),
+                            #[cfg(not(feature = "mainnet"))]
+                            has_valid_pow,
+                        )
+                        .map_err(Error::VpRunnerError)
+                    }
                 }
                 Address::Internal(internal_addr) => {
                     let ctx = native_vp::Ctx::new(
@@ -1017,6 +1194,23 @@ where
                             gas_meter = pgf_vp.ctx.gas_meter.into_inner();
                             result
                         }
+                        InternalAddress::ScheduledTxs => {
+                            let scheduled_txs_vp = ScheduledTxsVp { ctx };
+                            let result = scheduled_txs_vp
+                                .validate_tx(tx, &keys_changed, &verifiers)
+                                .map_err(Error::ScheduledTxsNativeVpError);
+                            gas_meter =
+                                scheduled_txs_vp.ctx.gas_meter.into_inner();
+                            result
+                        }
+                        InternalAddress::Vesting => {
+                            let vesting_vp = VestingVp { ctx };
+                            let result = vesting_vp
+                                .validate_tx(tx, &keys_changed, &verifiers)
+                                .map_err(Error::VestingNativeVpError);
+                            gas_meter = vesting_vp.ctx.gas_meter.into_inner();
+                            result
+                        }
                         InternalAddress::Nut(_) => {
                             let non_usable_tokens = NonUsableTokens { ctx };
                             let result = non_usable_tokens
@@ -1040,6 +1234,13 @@ where
                 }
             };
 
+            if let Some(mut profile) = gas_meter.take_gas_profile() {
+                profile.record_vp(addr, gas_meter.get_vp_consumed_gas());
+                result
+                    .gas_profile
+                    .get_or_insert_with(GasProfile::default)
+                    .merge(profile);
+            }
             // Returning error from here will short-circuit the VP parallel
             // execution.
             result.gas_used.set(gas_meter).map_err(Error::GasError)?;
@@ -1070,12 +1271,20 @@ fn merge_vp_results(
     let mut gas_used = a.gas_used;
 
     gas_used.merge(&mut b.gas_used, tx_gas_meter)?;
+    let gas_profile = match (a.gas_profile, b.gas_profile) {
+        (Some(mut a), Some(b)) => {
+            a.merge(b);
+            Some(a)
+        }
+        (a, b) => a.or(b),
+    };
 
     Ok(VpsResult {
         accepted_vps,
         rejected_vps,
         gas_used,
         errors,
+        gas_profile,
     })
 }
 
@@ -1093,7 +1302,7 @@ mod tests {
     };
     use namada_core::types::keccak::keccak_hash;
     use namada_core::types::storage::BlockHeight;
-    use namada_core::types::token::Amount;
+    use namada_core::types::token::{is_any_token_balance_key, Amount};
     use namada_core::types::vote_extensions::bridge_pool_roots::BridgePoolRootVext;
     use namada_core::types::vote_extensions::ethereum_events::EthereumEventsVext;
     use namada_core::types::voting_power::FractionalVotingPower;
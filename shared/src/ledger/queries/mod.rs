@@ -4,6 +4,9 @@
 // Re-export to show in rustdoc!
 pub use shell::Shell;
 use shell::SHELL;
+pub use shell::{
+    CompactMaspOutput, CompactMaspTx, IndexedMaspTx, MAX_MASP_TXS_PER_QUERY,
+};
 #[cfg(any(test, feature = "async-client"))]
 pub use types::Client;
 pub use types::{
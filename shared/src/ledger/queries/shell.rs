@@ -2,14 +2,20 @@ pub(super) mod eth_bridge;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use masp_primitives::asset_type::AssetType;
+use masp_primitives::bls12_381;
+use masp_primitives::ff::PrimeField;
 use masp_primitives::merkle_tree::MerklePath;
 use masp_primitives::sapling::Node;
+use masp_primitives::transaction::components::sapling::CompactOutputDescription;
+use masp_primitives::transaction::Transaction;
 use namada_core::ledger::storage::LastBlock;
 use namada_core::types::account::{Account, AccountPublicKeysMap};
-use namada_core::types::address::Address;
+use namada_core::types::address::{masp, Address};
 use namada_core::types::hash::Hash;
-use namada_core::types::storage::{BlockHeight, BlockResults, KeySeg};
-use namada_core::types::token::MaspDenom;
+use namada_core::types::storage::{BlockHeight, BlockResults, KeySeg, TxIndex};
+use namada_core::types::token::{
+    MaspDenom, Transfer, HEAD_TX_KEY, TX_KEY_PREFIX,
+};
 
 use self::eth_bridge::{EthBridge, ETH_BRIDGE};
 use crate::ibc::core::ics04_channel::packet::Sequence;
@@ -34,6 +40,90 @@ type Conversion = (
     MerklePath<Node>,
 );
 
+/// A shielded transaction as stored by the MASP, together with the epoch,
+/// block height and index at which it was applied
+pub type IndexedMaspTx = (Epoch, BlockHeight, TxIndex, Transfer, Transaction);
+
+/// The maximum number of shielded transactions returned by a single
+/// `masp_txs` or `compact_masp_txs` query
+pub const MAX_MASP_TXS_PER_QUERY: u64 = 128;
+
+/// A shielded output stripped down to its note commitment and the parts of
+/// its ciphertext needed to trial-decrypt the note, without the memo
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct CompactMaspOutput {
+    /// The note commitment
+    pub cmu: [u8; 32],
+    /// The ephemeral public key the note was encrypted with
+    pub ephemeral_key: [u8; 32],
+    /// The prefix of the note ciphertext holding the note plaintext
+    pub enc_ciphertext: Vec<u8>,
+}
+
+impl CompactMaspOutput {
+    /// Get the output description to trial-decrypt, or `None` if the output
+    /// is malformed
+    pub fn to_description(&self) -> Option<CompactOutputDescription> {
+        Some(CompactOutputDescription {
+            ephemeral_key: self.ephemeral_key.into(),
+            cmu: Option::from(bls12_381::Scalar::from_repr(self.cmu))?,
+            enc_ciphertext: self.enc_ciphertext.as_slice().try_into().ok()?,
+        })
+    }
+}
+
+/// A shielded transaction stripped down to the nullifiers it reveals and its
+/// compact outputs, together with the epoch, block height and index at which
+/// it was applied
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct CompactMaspTx {
+    /// The epoch at which the transaction was applied
+    pub epoch: Epoch,
+    /// The height at which the transaction was applied
+    pub height: BlockHeight,
+    /// The index of the transaction in its block
+    pub index: TxIndex,
+    /// The nullifiers of the notes spent by the transaction
+    pub nullifiers: Vec<[u8; 32]>,
+    /// The outputs of the transaction
+    pub outputs: Vec<CompactMaspOutput>,
+}
+
+impl From<&IndexedMaspTx> for CompactMaspTx {
+    fn from((epoch, height, index, _, shielded): &IndexedMaspTx) -> Self {
+        let bundle = shielded.sapling_bundle();
+        let nullifiers = bundle.map_or_else(Vec::new, |bundle| {
+            bundle
+                .shielded_spends
+                .iter()
+                .map(|spend| spend.nullifier.0)
+                .collect()
+        });
+        let outputs = bundle.map_or_else(Vec::new, |bundle| {
+            bundle
+                .shielded_outputs
+                .iter()
+                .map(|output| {
+                    let compact =
+                        CompactOutputDescription::from(output.clone());
+                    CompactMaspOutput {
+                        cmu: compact.cmu.to_repr(),
+                        ephemeral_key: compact.ephemeral_key.0,
+                        enc_ciphertext: compact.enc_ciphertext.to_vec(),
+                    }
+                })
+                .collect()
+        });
+        Self {
+            epoch: *epoch,
+            height: *height,
+            index: *index,
+            nullifiers,
+            outputs,
+        }
+    }
+}
+
 router! {SHELL,
     // Shell provides storage read access, block metadata and can dry-run a tx
 
@@ -67,6 +157,14 @@ router! {SHELL,
     // Conversion state access - read conversion
     ( "conv" / [asset_type: AssetType] ) -> Conversion = read_conversion,
 
+    // MASP transactions access - read a range of shielded transactions
+    ( "masp_txs" / [from: u64] / [count: u64] )
+        -> Vec<IndexedMaspTx> = read_masp_txs,
+
+    // MASP transactions access - read a range of compact shielded transactions
+    ( "compact_masp_txs" / [from: u64] / [count: u64] )
+        -> Vec<CompactMaspTx> = read_compact_masp_txs,
+
     // Block results access - read bit-vec
     ( "results" ) -> Vec<BlockResults> = read_results,
 
@@ -178,6 +276,8 @@ where
         }
     };
 
+    // Record where the gas of the inner tx goes
+    tx_gas_meter.enable_profiling();
     let mut data = protocol::apply_wasm_tx(
         tx,
         &TxIndex(0),
@@ -198,6 +298,7 @@ where
         ))?;
     // Account gas for both inner and wrapper (if available)
     data.gas_used = cumulated_gas;
+    data.gas_profile = tx_gas_meter.take_gas_profile();
     // NOTE: the keys changed by the wrapper transaction (if any) are not
     // returned from this function
     let data = data.try_to_vec().into_storage_result()?;
@@ -279,6 +380,56 @@ where
     }
 }
 
+/// Read up to `count` shielded transactions, starting from the one with index
+/// `from`. The count is capped at [`MAX_MASP_TXS_PER_QUERY`].
+fn read_masp_txs<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    from: u64,
+    count: u64,
+) -> storage_api::Result<Vec<IndexedMaspTx>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let masp_addr = masp();
+    let head_tx_key = storage::Key::from(masp_addr.to_db_key())
+        .push(&HEAD_TX_KEY.to_owned())
+        .into_storage_result()?;
+    let head_txidx: u64 = ctx.wl_storage.read(&head_tx_key)?.unwrap_or(0);
+    let end =
+        head_txidx.min(from.saturating_add(count.min(MAX_MASP_TXS_PER_QUERY)));
+    (from..end)
+        .map(|txidx| {
+            let tx_key = storage::Key::from(masp_addr.to_db_key())
+                .push(&(TX_KEY_PREFIX.to_owned() + &txidx.to_string()))
+                .into_storage_result()?;
+            ctx.wl_storage.read(&tx_key)?.ok_or_else(|| {
+                storage_api::Error::new_const(
+                    "Missing a shielded transaction below the head index",
+                )
+            })
+        })
+        .collect()
+}
+
+/// Read up to `count` shielded transactions in their compact form, starting
+/// from the one with index `from`. The count is capped at
+/// [`MAX_MASP_TXS_PER_QUERY`].
+fn read_compact_masp_txs<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    from: u64,
+    count: u64,
+) -> storage_api::Result<Vec<CompactMaspTx>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    Ok(read_masp_txs(ctx, from, count)?
+        .iter()
+        .map(CompactMaspTx::from)
+        .collect())
+}
+
 #[cfg(not(all(feature = "wasm-runtime", feature = "ferveo-tpke")))]
 fn dry_run_tx<D, H>(
     _ctx: RequestCtx<'_, D, H>,
@@ -574,8 +725,14 @@ where
 #[cfg(test)]
 mod test {
     use borsh::{BorshDeserialize, BorshSerialize};
+    use masp_primitives::ff::PrimeField;
+    use masp_primitives::zip32::{ExtendedFullViewingKey, ExtendedSpendingKey};
     use namada_test_utils::TestWasms;
 
+    use super::MAX_MASP_TXS_PER_QUERY;
+    use crate::ledger::masp::testing::{
+        append_masp_txs, indexed_masp_tx, shielding_tx,
+    };
     use crate::ledger::queries::testing::TestClient;
     use crate::ledger::queries::RPC;
     use crate::ledger::storage_api::{self, StorageWrite};
@@ -605,6 +762,12 @@ mod test {
 
         let path = RPC.shell().storage_has_key_path(&key);
         assert_eq!(format!("/shell/has_key/{}", key), path);
+
+        let path = RPC.shell().read_masp_txs_path(&0, &128);
+        assert_eq!("/shell/masp_txs/0/128", path);
+
+        let path = RPC.shell().read_compact_masp_txs_path(&0, &128);
+        assert_eq!("/shell/compact_masp_txs/0/128", path);
     }
 
     #[tokio::test]
@@ -677,9 +840,27 @@ mod test {
             .unwrap();
         assert!(!has_balance_key);
 
+        // Request a range of shielded transactions, there are none yet
+        let masp_txs =
+            RPC.shell().read_masp_txs(&client, &0, &128).await.unwrap();
+        assert!(masp_txs.is_empty());
+
         // Then write some balance ...
         let balance = token::Amount::native_whole(1000);
         StorageWrite::write(&mut client.wl_storage, &balance_key, balance)?;
+        // ... and some shielded transactions, the first two of which are
+        // applied by the same transaction
+        let pa = ExtendedFullViewingKey::from(&ExtendedSpendingKey::master(
+            &[1; 32],
+        ))
+        .default_address()
+        .1;
+        let txs: Vec<_> = (0..MAX_MASP_TXS_PER_QUERY + 2)
+            .map(|txidx| {
+                indexed_masp_tx(txidx.max(1), 0, shielding_tx(pa, txidx + 1))
+            })
+            .collect();
+        append_masp_txs(&mut client.wl_storage, &txs);
         // It has to be committed to be visible in a query
         client.wl_storage.commit_tx();
         client.wl_storage.commit_block().unwrap();
@@ -711,6 +892,69 @@ mod test {
             .unwrap();
         assert!(has_balance_key);
 
+        // Request a range of shielded transactions
+        let masp_txs =
+            RPC.shell().read_masp_txs(&client, &1, &2).await.unwrap();
+        assert_eq!(masp_txs.len(), 2);
+        assert_eq!((masp_txs[0].1, masp_txs[0].2), (txs[1].1, txs[1].2));
+        assert_eq!((masp_txs[1].1, masp_txs[1].2), (txs[2].1, txs[2].2));
+        assert_eq!(masp_txs[0].4.txid(), txs[1].4.txid());
+        assert_eq!(masp_txs[1].4.txid(), txs[2].4.txid());
+        // The transactions sharing a height and index are both returned
+        let masp_txs =
+            RPC.shell().read_masp_txs(&client, &0, &2).await.unwrap();
+        assert_eq!(masp_txs[0].4.txid(), txs[0].4.txid());
+        assert_eq!(masp_txs[1].4.txid(), txs[1].4.txid());
+        // The response is capped ...
+        let masp_txs = RPC
+            .shell()
+            .read_masp_txs(&client, &0, &u64::MAX)
+            .await
+            .unwrap();
+        assert_eq!(masp_txs.len() as u64, MAX_MASP_TXS_PER_QUERY);
+        // ... and stops at the last shielded transaction
+        let masp_txs = RPC
+            .shell()
+            .read_masp_txs(&client, &MAX_MASP_TXS_PER_QUERY, &128)
+            .await
+            .unwrap();
+        assert_eq!(masp_txs.len(), 2);
+        let masp_txs = RPC
+            .shell()
+            .read_masp_txs(&client, &u64::MAX, &128)
+            .await
+            .unwrap();
+        assert!(masp_txs.is_empty());
+
+        // Request the same range of compact shielded transactions
+        let compact_txs = RPC
+            .shell()
+            .read_compact_masp_txs(&client, &1, &2)
+            .await
+            .unwrap();
+        assert_eq!(compact_txs.len(), 2);
+        for (compact, (epoch, height, index, _, shielded)) in
+            compact_txs.iter().zip(&txs[1..3])
+        {
+            assert_eq!(
+                (compact.epoch, compact.height, compact.index),
+                (*epoch, *height, *index)
+            );
+            let outputs = &shielded.sapling_bundle().unwrap().shielded_outputs;
+            assert_eq!(compact.outputs.len(), outputs.len());
+            assert_eq!(compact.outputs[0].cmu, outputs[0].cmu.to_repr());
+            assert_eq!(
+                compact.outputs[0].ephemeral_key,
+                outputs[0].ephemeral_key.0
+            );
+            assert!(
+                outputs[0]
+                    .enc_ciphertext
+                    .starts_with(&compact.outputs[0].enc_ciphertext)
+            );
+            assert!(compact.nullifiers.is_empty());
+        }
+
         Ok(())
     }
 }
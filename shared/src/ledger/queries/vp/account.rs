@@ -0,0 +1,80 @@
+use namada_core::types::account::{
+    AccountGuardians, AccountPolicy, OutflowAllowance, PendingRecovery,
+    SessionKey,
+};
+use namada_core::types::address::Address;
+use namada_core::types::key::common;
+
+use crate::ledger::queries::types::RequestCtx;
+use crate::ledger::storage::{DBIter, StorageHasher, DB};
+use crate::ledger::storage_api;
+
+// User account validity predicate queries
+router! {ACCOUNT,
+    ( "policy" / [ owner: Address ] ) -> Option<AccountPolicy> = policy,
+    ( "allowances" / [ owner: Address ] ) -> Vec<OutflowAllowance> = allowances,
+    ( "guardians" / [ owner: Address ] ) -> Option<AccountGuardians> = guardians,
+    ( "recovery" / [ owner: Address ] ) -> Option<PendingRecovery> = recovery,
+    ( "session_keys" / [ owner: Address ] )
+        -> Vec<(common::PublicKey, SessionKey)> = session_keys,
+}
+
+/// Query the spending policy of an account, if any
+fn policy<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    owner: Address,
+) -> storage_api::Result<Option<AccountPolicy>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    storage_api::account::policy(ctx.wl_storage, &owner)
+}
+
+/// Query the remaining outflow allowances of an account in the current epoch
+fn allowances<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    owner: Address,
+) -> storage_api::Result<Vec<OutflowAllowance>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    storage_api::account::outflow_allowances(ctx.wl_storage, &owner)
+}
+
+/// Query the guardians of an account, if any
+fn guardians<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    owner: Address,
+) -> storage_api::Result<Option<AccountGuardians>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    storage_api::account::guardians(ctx.wl_storage, &owner)
+}
+
+/// Query the pending recovery of an account, if any
+fn recovery<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    owner: Address,
+) -> storage_api::Result<Option<PendingRecovery>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    storage_api::account::pending_recovery(ctx.wl_storage, &owner)
+}
+
+/// Query the session keys of an account
+fn session_keys<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    owner: Address,
+) -> storage_api::Result<Vec<(common::PublicKey, SessionKey)>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    storage_api::account::session_keys(ctx.wl_storage, &owner)
+}
@@ -1,16 +1,22 @@
 //! Queries router and handlers for validity predicates
 
 // Re-export to show in rustdoc!
+pub use account::Account;
+use account::ACCOUNT;
 pub use governance::Gov;
 use governance::GOV;
 pub use pos::Pos;
 use pos::POS;
 pub use token::Token;
 use token::TOKEN;
+mod account;
 mod governance;
 pub use pgf::Pgf;
 use pgf::PGF;
 mod pgf;
+pub use vesting::Vesting;
+use vesting::VESTING;
+mod vesting;
 
 pub mod pos;
 mod token;
@@ -21,6 +27,8 @@ router! {VP,
     ( "token" ) = (sub TOKEN),
     ( "governance" ) = (sub GOV),
     ( "pgf" ) = (sub PGF),
+    ( "vesting" ) = (sub VESTING),
+    ( "account" ) = (sub ACCOUNT),
 }
 
 /// Client-only methods for the router type are composed from router functions.
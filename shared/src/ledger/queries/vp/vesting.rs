@@ -0,0 +1,23 @@
+use namada_core::ledger::vesting::VestingBalance;
+use namada_core::types::address::Address;
+
+use crate::ledger::queries::types::RequestCtx;
+use crate::ledger::storage::{DBIter, StorageHasher, DB};
+use crate::ledger::storage_api;
+
+// Vesting validity predicate queries
+router! {VESTING,
+    ( "balances" / [ owner: Address ] ) -> Vec<VestingBalance> = balances,
+}
+
+/// Query the vested and locked balances of a vesting account
+fn balances<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    owner: Address,
+) -> storage_api::Result<Vec<VestingBalance>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    storage_api::vesting::get_vesting_balances(ctx.wl_storage, &owner)
+}
@@ -29,7 +29,7 @@ use serde::Serialize;
 use crate::ledger::args::InputAmount;
 use crate::ledger::events::Event;
 use crate::ledger::queries::vp::pos::EnrichedBondsAndUnbondsDetails;
-use crate::ledger::queries::RPC;
+use crate::ledger::queries::{CompactMaspTx, IndexedMaspTx, RPC};
 use crate::proto::Tx;
 use crate::tendermint::block::Height;
 use crate::tendermint::merkle::proof::Proof;
@@ -265,6 +265,36 @@ pub async fn query_conversion<C: crate::ledger::queries::Client + Sync>(
     ))
 }
 
+/// Query up to `count` shielded transactions, starting from the one with
+/// index `from`. The ledger caps the response at
+/// [`crate::ledger::queries::MAX_MASP_TXS_PER_QUERY`] transactions.
+pub async fn query_masp_txs<C: crate::ledger::queries::Client + Sync>(
+    client: &C,
+    from: u64,
+    count: u64,
+) -> Result<Vec<IndexedMaspTx>, Error> {
+    convert_response::<C, _>(
+        RPC.shell().read_masp_txs(client, &from, &count).await,
+    )
+}
+
+/// Query up to `count` shielded transactions in their compact form, starting
+/// from the one with index `from`. The ledger caps the response at
+/// [`crate::ledger::queries::MAX_MASP_TXS_PER_QUERY`] transactions.
+pub async fn query_compact_masp_txs<
+    C: crate::ledger::queries::Client + Sync,
+>(
+    client: &C,
+    from: u64,
+    count: u64,
+) -> Result<Vec<CompactMaspTx>, Error> {
+    convert_response::<C, _>(
+        RPC.shell()
+            .read_compact_masp_txs(client, &from, &count)
+            .await,
+    )
+}
+
 /// Query a wasm code hash
 pub async fn query_wasm_code_hash<C: crate::ledger::queries::Client + Sync>(
     client: &C,
@@ -1,15 +1,34 @@
 //! Functions to sign transactions
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{BufRead, BufReader, Write};
 use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 
+#[cfg(feature = "masp-tx-gen")]
+use async_trait::async_trait;
 use borsh::{BorshDeserialize, BorshSerialize};
-use data_encoding::HEXLOWER;
+use data_encoding::{HEXLOWER, HEXUPPER};
 use itertools::Itertools;
 use masp_primitives::asset_type::AssetType;
+#[cfg(feature = "masp-tx-gen")]
+use masp_primitives::ff::PrimeField;
+#[cfg(feature = "masp-tx-gen")]
+use masp_primitives::group::GroupEncoding;
+#[cfg(feature = "masp-tx-gen")]
+use masp_primitives::jubjub;
+#[cfg(feature = "masp-tx-gen")]
+use masp_primitives::sapling::redjubjub::Signature as RedJubjubSignature;
+#[cfg(feature = "masp-tx-gen")]
+use masp_primitives::sapling::ProofGenerationKey;
 use masp_primitives::transaction::components::sapling::fees::{
     InputView, OutputView,
 };
+#[cfg(feature = "masp-tx-gen")]
+use masp_primitives::zip32::ExtendedFullViewingKey;
 use namada_core::proto::SignatureIndex;
 use namada_core::types::account::AccountPublicKeysMap;
 use namada_core::types::address::{
@@ -24,10 +43,13 @@ use serde::{Deserialize, Serialize};
 use sha2::Digest;
 use zeroize::Zeroizing;
 
+#[cfg(feature = "masp-tx-gen")]
+use super::masp::external_signer::{MaspSigner, MaspSigningRequest};
 use super::masp::{ShieldedContext, ShieldedTransfer, ShieldedUtils};
 use super::rpc::validate_amount;
 use crate::ibc::applications::transfer::msgs::transfer::MsgTransfer;
 use crate::ibc_proto::google::protobuf::Any;
+use crate::ledger::args::SignerKind;
 use crate::ledger::masp::make_asset_type;
 use crate::ledger::parameters::storage as parameter_storage;
 use crate::ledger::rpc::{format_denominated_amount, query_wasm_code_hash};
@@ -60,6 +82,14 @@ const ENV_VAR_LEDGER_LOG_PATH: &str = "NAMADA_LEDGER_LOG_PATH";
 /// Env. var specifying where to store transaction debug outputs
 const ENV_VAR_TX_LOG_PATH: &str = "NAMADA_TX_LOG_PATH";
 
+/// The time given to a spawned signer process to exit once its standard input
+/// is closed, after which it is killed
+const SIGNER_EXIT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The error of a connection to an external signer left in an unknown state
+const SIGNER_POISONED: &str =
+    "The connection to the external signer was interrupted mid-request";
+
 /// A struture holding the signing data to craft a transaction
 #[derive(Clone)]
 pub struct SigningTxData {
@@ -71,6 +101,489 @@ pub struct SigningTxData {
     pub account_public_keys_map: Option<AccountPublicKeysMap>,
     /// The public keys of the fee payer
     pub fee_payer: common::PublicKey,
+    /// The connection to the external signer holding the keys, if any, kept
+    /// open until the transaction is signed
+    pub external_signer: Option<SharedExternalSigner>,
+}
+
+/// A holder of the secret keys that sign transactions
+pub trait Signer {
+    /// Get the public key with the given hash held by this signer
+    fn public_key(
+        &mut self,
+        pkh: &PublicKeyHash,
+    ) -> Result<common::PublicKey, Error>;
+
+    /// Sign the inner sections of the given transaction with each of the
+    /// given keys held by this signer. The keys that are not held are
+    /// skipped.
+    fn sign_sections(
+        &mut self,
+        tx: &Tx,
+        public_keys: &[common::PublicKey],
+    ) -> Result<Vec<(common::PublicKey, common::Signature)>, Error>;
+
+    /// Sign all the sections of the given transaction with the key of its
+    /// fee payer
+    fn sign_wrapper(
+        &mut self,
+        tx: &Tx,
+        fee_payer: &common::PublicKey,
+    ) -> Result<common::Signature, Error>;
+}
+
+/// A signer using the keys of the local wallet
+pub struct WalletSigner<'a, U: WalletUtils> {
+    /// The wallet holding the keys
+    pub wallet: &'a mut Wallet<U>,
+    /// The password decrypting the keys. It is interactively prompted for if
+    /// not given.
+    pub password: Option<Zeroizing<String>>,
+}
+
+impl<'a, U: WalletUtils> WalletSigner<'a, U> {
+    /// Make a signer using the keys of the given wallet
+    pub fn new(
+        wallet: &'a mut Wallet<U>,
+        password: Option<Zeroizing<String>>,
+    ) -> Self {
+        Self { wallet, password }
+    }
+
+    /// Load the secret key corresponding to the given public key
+    pub fn secret_key(
+        &mut self,
+        public_key: &common::PublicKey,
+    ) -> Result<common::SecretKey, Error> {
+        if *public_key == masp_tx_key().ref_to() {
+            // We already know the secret key corresponding to the MASP
+            // sentinal key
+            Ok(masp_tx_key())
+        } else {
+            // Otherwise we need to search the wallet for the secret key
+            self.wallet
+                .find_key_by_pk(public_key, self.password.clone())
+                .map_err(|err| {
+                    Error::Other(format!(
+                        "Unable to load the keypair from the wallet for \
+                         public key {}. Failed with: {}",
+                        public_key, err
+                    ))
+                })
+        }
+    }
+}
+
+impl<'a, U: WalletUtils> Signer for WalletSigner<'a, U> {
+    fn public_key(
+        &mut self,
+        pkh: &PublicKeyHash,
+    ) -> Result<common::PublicKey, Error> {
+        self.wallet
+            .find_key_by_pkh(pkh, self.password.clone())
+            .map(|secret_key| secret_key.ref_to())
+            .map_err(|err| {
+                Error::Other(format!(
+                    "Unable to load the keypair from the wallet for public \
+                     key hash {}. Failed with: {}",
+                    pkh, err
+                ))
+            })
+    }
+
+    fn sign_sections(
+        &mut self,
+        tx: &Tx,
+        public_keys: &[common::PublicKey],
+    ) -> Result<Vec<(common::PublicKey, common::Signature)>, Error> {
+        let sighash = tx.raw_sighash();
+        Ok(public_keys
+            .iter()
+            .filter_map(|public_key| self.secret_key(public_key).ok())
+            .map(|secret_key| {
+                let signature = common::SigScheme::sign(&secret_key, sighash);
+                (secret_key.ref_to(), signature)
+            })
+            .collect())
+    }
+
+    fn sign_wrapper(
+        &mut self,
+        tx: &Tx,
+        fee_payer: &common::PublicKey,
+    ) -> Result<common::Signature, Error> {
+        let secret_key = self.secret_key(fee_payer)?;
+        Ok(common::SigScheme::sign(&secret_key, tx.wrapper_sighash()))
+    }
+}
+
+/// A request sent to an external signer, as a single line of JSON. Keys and
+/// key hashes are in their string forms, transactions and signatures are
+/// upper case hex encodings of their Protobuf and Borsh serializations
+/// respectively.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum SignerRequest {
+    /// Get the public key with the given hash
+    PublicKey {
+        /// The hash of the requested public key
+        pkh: String,
+    },
+    /// Sign the sighash of the inner sections of the transaction with each
+    /// of the given keys held by the signer
+    SignSections {
+        /// The transaction to be signed
+        tx: String,
+        /// The hash to sign, see [`Tx::raw_sighash`]
+        sighash: String,
+        /// The keys to sign with
+        public_keys: Vec<String>,
+    },
+    /// Sign the sighash of all the sections of the transaction with the key
+    /// of its fee payer
+    SignWrapper {
+        /// The transaction to be signed
+        tx: String,
+        /// The hash to sign, see [`Tx::wrapper_sighash`]
+        sighash: String,
+        /// The key of the fee payer
+        public_key: String,
+    },
+    /// Get the proof generation key of the spending key with the given full
+    /// viewing key
+    ProofGenerationKey {
+        /// The full viewing key of the spending key
+        fvk: String,
+    },
+    /// Sign the spend authorizations of a shielded transaction
+    SignSpends {
+        /// The upper case hex encoding of the Borsh serialized
+        /// [`MaspSigningRequest`] describing the transaction and its spends
+        request: String,
+    },
+}
+
+/// A response of an external signer, as a single line of JSON
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum SignerResponse {
+    /// The requested public key
+    PublicKey {
+        /// The public key
+        public_key: String,
+    },
+    /// The signatures made by the requested keys that the signer holds
+    Signatures {
+        /// The pairs of public keys and their signatures
+        signatures: Vec<(String, String)>,
+    },
+    /// The signature made by the requested key
+    Signature {
+        /// The signature
+        signature: String,
+    },
+    /// The requested proof generation key, as the upper case hex encodings
+    /// of the bytes of its components
+    ProofGenerationKey {
+        /// The spend validating key
+        ak: String,
+        /// The proof authorizing key
+        nsk: String,
+    },
+    /// The spend authorization signatures in the order of the spends, as the
+    /// upper case hex encodings of their bytes
+    SpendSignatures {
+        /// The signatures
+        signatures: Vec<String>,
+    },
+    /// The request was refused
+    Error {
+        /// The reason for the refusal
+        message: String,
+    },
+}
+
+/// A signer process holding the keys outside of the local wallet, such as a
+/// bridge to a hardware wallet or a remote key management service. It is
+/// spoken to with a [`SignerRequest`] per line, each of which it answers with
+/// a [`SignerResponse`] line.
+pub struct ExternalSigner {
+    /// The responses of the signer
+    reader: Box<dyn BufRead + Send>,
+    /// The requests to the signer
+    writer: Box<dyn Write + Send>,
+    /// The signer process, if it was spawned by this client
+    child: Option<Child>,
+}
+
+/// A connection to an external signer shared by the steps of the signing of a
+/// transaction
+pub type SharedExternalSigner = Arc<Mutex<ExternalSigner>>;
+
+impl ExternalSigner {
+    /// Connect to the given external signer with a connection to be shared
+    /// by the steps of the signing of a transaction. The connection is closed
+    /// once all of its handles are dropped.
+    pub fn connect_shared(
+        kind: &SignerKind,
+    ) -> Result<SharedExternalSigner, Error> {
+        Ok(Arc::new(Mutex::new(Self::connect(kind)?)))
+    }
+
+    /// Get the given shared connection to the external signer, or connect to
+    /// it if there is none
+    pub fn shared_or_connect(
+        signer: Option<&SharedExternalSigner>,
+        kind: &SignerKind,
+    ) -> Result<SharedExternalSigner, Error> {
+        match signer {
+            Some(signer) => Ok(signer.clone()),
+            None => Self::connect_shared(kind),
+        }
+    }
+
+    /// Get exclusive use of the given shared connection
+    pub fn lock(
+        signer: &SharedExternalSigner,
+    ) -> Result<MutexGuard<'_, Self>, Error> {
+        signer
+            .lock()
+            .map_err(|_| Error::Other(SIGNER_POISONED.to_string()))
+    }
+
+    /// Connect to the given external signer, spawning its process if need be
+    pub fn connect(kind: &SignerKind) -> Result<Self, Error> {
+        match kind {
+            SignerKind::Wallet => {
+                other_err("The wallet is not an external signer".to_string())
+            }
+            SignerKind::Process(command) => {
+                // The command line is split like a shell would so that its
+                // arguments may be quoted, but the program is spawned directly
+                // to be the process killed if it doesn't exit
+                let args = shlex::split(command).ok_or_else(|| {
+                    Error::Other(format!(
+                        "Unable to parse the external signer command {}",
+                        command
+                    ))
+                })?;
+                let (program, args) = args.split_first().ok_or_else(|| {
+                    Error::Other(
+                        "The external signer command is empty".to_string(),
+                    )
+                })?;
+                let mut child = Command::new(program)
+                    .args(args)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .spawn()
+                    .map_err(|err| {
+                        Error::Other(format!(
+                            "Unable to spawn the external signer {}. Failed \
+                             with: {}",
+                            command, err
+                        ))
+                    })?;
+                let stdin = child.stdin.take().expect("stdin is piped");
+                let stdout = child.stdout.take().expect("stdout is piped");
+                Ok(Self {
+                    reader: Box::new(BufReader::new(stdout)),
+                    writer: Box::new(stdin),
+                    child: Some(child),
+                })
+            }
+            #[cfg(unix)]
+            SignerKind::Socket(path) => {
+                let connect = || {
+                    let stream = std::os::unix::net::UnixStream::connect(path)?;
+                    Ok::<_, std::io::Error>((stream.try_clone()?, stream))
+                };
+                let (reader, writer) = connect().map_err(|err| {
+                    Error::Other(format!(
+                        "Unable to connect to the external signer at {}. \
+                         Failed with: {}",
+                        path.to_string_lossy(),
+                        err
+                    ))
+                })?;
+                Ok(Self {
+                    reader: Box::new(BufReader::new(reader)),
+                    writer: Box::new(writer),
+                    child: None,
+                })
+            }
+            #[cfg(not(unix))]
+            SignerKind::Socket(_) => other_err(
+                "Unix socket signers are not supported on this platform"
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// Send the given request to the signer and wait for its response
+    fn request(
+        &mut self,
+        request: &SignerRequest,
+    ) -> Result<SignerResponse, Error> {
+        let io_err = |err: std::io::Error| {
+            Error::Other(format!(
+                "Unable to communicate with the external signer: {}",
+                err
+            ))
+        };
+        let mut line = serde_json::to_string(request)
+            .map_err(|err| EncodingError::Serde(err.to_string()))?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes()).map_err(io_err)?;
+        self.writer.flush().map_err(io_err)?;
+        let mut line = String::new();
+        if self.reader.read_line(&mut line).map_err(io_err)? == 0 {
+            return other_err(
+                "The external signer closed the connection".to_string(),
+            );
+        }
+        match serde_json::from_str(&line)
+            .map_err(|err| EncodingError::Serde(err.to_string()))?
+        {
+            SignerResponse::Error { message } => other_err(format!(
+                "The external signer refused the request: {}",
+                message
+            )),
+            response => Ok(response),
+        }
+    }
+
+    /// Decode a signature of the given key over the given hash, checking
+    /// that it is valid
+    fn decode_signature(
+        public_key: &common::PublicKey,
+        sighash: &crate::types::hash::Hash,
+        signature: &str,
+    ) -> Result<common::Signature, Error> {
+        let signature = HEXUPPER
+            .decode(signature.as_bytes())
+            .ok()
+            .and_then(|bytes| common::Signature::try_from_slice(&bytes).ok())
+            .ok_or_else(|| {
+                Error::from(EncodingError::Decoding(format!(
+                    "Invalid signature from the external signer: {}",
+                    signature
+                )))
+            })?;
+        common::SigScheme::verify_signature(public_key, sighash, &signature)
+            .map_err(|err| {
+                Error::Other(format!(
+                    "The external signer made an invalid signature for {}: \
+                     {}",
+                    public_key, err
+                ))
+            })?;
+        Ok(signature)
+    }
+}
+
+impl Drop for ExternalSigner {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            // Closing its standard input tells the signer process to exit
+            self.writer = Box::new(std::io::sink());
+            let deadline = Instant::now() + SIGNER_EXIT_TIMEOUT;
+            while Instant::now() < deadline {
+                match child.try_wait() {
+                    Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+                    Ok(Some(_)) => return,
+                    Err(_) => break,
+                }
+            }
+            // The signer did not exit in time
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Signer for ExternalSigner {
+    fn public_key(
+        &mut self,
+        pkh: &PublicKeyHash,
+    ) -> Result<common::PublicKey, Error> {
+        let request = SignerRequest::PublicKey {
+            pkh: pkh.to_string(),
+        };
+        let SignerResponse::PublicKey { public_key } = self.request(&request)?
+        else {
+            return other_err(
+                "Unexpected response from the external signer".to_string(),
+            );
+        };
+        let public_key = common::PublicKey::from_str(&public_key)
+            .map_err(|err| EncodingError::Decoding(err.to_string()))?;
+        if PublicKeyHash::from(&public_key) != *pkh {
+            return other_err(format!(
+                "The external signer returned the public key {} for the hash \
+                 {}",
+                public_key, pkh
+            ));
+        }
+        Ok(public_key)
+    }
+
+    fn sign_sections(
+        &mut self,
+        tx: &Tx,
+        public_keys: &[common::PublicKey],
+    ) -> Result<Vec<(common::PublicKey, common::Signature)>, Error> {
+        let sighash = tx.raw_sighash();
+        let request = SignerRequest::SignSections {
+            tx: HEXUPPER.encode(&tx.to_bytes()),
+            sighash: sighash.to_string(),
+            public_keys: public_keys.iter().map(|pk| pk.to_string()).collect(),
+        };
+        let SignerResponse::Signatures { signatures } =
+            self.request(&request)?
+        else {
+            return other_err(
+                "Unexpected response from the external signer".to_string(),
+            );
+        };
+        signatures
+            .into_iter()
+            .map(|(public_key, signature)| {
+                let public_key = common::PublicKey::from_str(&public_key)
+                    .map_err(|err| EncodingError::Decoding(err.to_string()))?;
+                if !public_keys.contains(&public_key) {
+                    return other_err(format!(
+                        "The external signer signed with the unrequested key \
+                         {}",
+                        public_key
+                    ));
+                }
+                let signature =
+                    Self::decode_signature(&public_key, &sighash, &signature)?;
+                Ok((public_key, signature))
+            })
+            .collect()
+    }
+
+    fn sign_wrapper(
+        &mut self,
+        tx: &Tx,
+        fee_payer: &common::PublicKey,
+    ) -> Result<common::Signature, Error> {
+        let sighash = tx.wrapper_sighash();
+        let request = SignerRequest::SignWrapper {
+            tx: HEXUPPER.encode(&tx.to_bytes()),
+            sighash: sighash.to_string(),
+            public_key: fee_payer.to_string(),
+        };
+        let SignerResponse::Signature { signature } = self.request(&request)?
+        else {
+            return other_err(
+                "Unexpected response from the external signer".to_string(),
+            );
+        };
+        Self::decode_signature(fee_payer, &sighash, &signature)
+    }
 }
 
 /// Find the public key for the given address and try to load the keypair
@@ -125,27 +638,14 @@ pub fn find_key_by_pk<U: WalletUtils>(
     args: &args::Tx,
     public_key: &common::PublicKey,
 ) -> Result<common::SecretKey, Error> {
-    if *public_key == masp_tx_key().ref_to() {
-        // We already know the secret key corresponding to the MASP sentinal key
-        Ok(masp_tx_key())
-    } else {
-        // Otherwise we need to search the wallet for the secret key
-        wallet
-            .find_key_by_pk(public_key, args.password.clone())
-            .map_err(|err| {
-                Error::Other(format!(
-                    "Unable to load the keypair from the wallet for public \
-                     key {}. Failed with: {}",
-                    public_key, err
-                ))
-            })
-    }
+    WalletSigner::new(wallet, args.password.clone()).secret_key(public_key)
 }
 
 /// Given CLI arguments and some defaults, determine the rightful transaction
 /// signer. Return the given signing key or public key of the given signer if
 /// possible. If no explicit signer given, use the `default`. If no `default`
-/// is given, an `Error` is returned.
+/// is given, an `Error` is returned. The public keys held by an external
+/// signer are requested over the given connection, if any.
 pub async fn tx_signers<
     C: crate::ledger::queries::Client + Sync,
     U: WalletUtils,
@@ -154,6 +654,7 @@ pub async fn tx_signers<
     wallet: &mut Wallet<U>,
     args: &args::Tx,
     default: Option<Address>,
+    external_signer: Option<&SharedExternalSigner>,
 ) -> Result<Vec<common::PublicKey>, Error> {
     let signer = if !&args.signing_keys.is_empty() {
         let public_keys =
@@ -169,7 +670,17 @@ pub async fn tx_signers<
     // Now actually fetch the signing key and apply it
     match signer {
         Some(signer) if signer == masp() => Ok(vec![masp_tx_key().ref_to()]),
-
+        // Only the external signer knows the keys that it holds
+        Some(Address::Implicit(ImplicitAddress(pkh)))
+            if args.signer != SignerKind::Wallet =>
+        {
+            let signer = ExternalSigner::shared_or_connect(
+                external_signer,
+                &args.signer,
+            )?;
+            let public_key = ExternalSigner::lock(&signer)?.public_key(&pkh)?;
+            Ok(vec![public_key])
+        }
         Some(signer) => Ok(vec![
             find_pk::<C, U>(client, wallet, &signer, args.password.clone())
                 .await?,
@@ -193,12 +704,21 @@ pub async fn tx_signers<
 /// hashes needed for monitoring the tx on chain.
 ///
 /// If it is a dry run, it is not put in a wrapper, but returned as is.
+///
+/// The keys are looked up in the signer selected by the arguments.
 pub fn sign_tx<U: WalletUtils>(
     wallet: &mut Wallet<U>,
     args: &args::Tx,
     tx: &mut Tx,
     signing_data: SigningTxData,
 ) -> Result<(), Error> {
+    let external = match (&args.signer, &signing_data.external_signer) {
+        (SignerKind::Wallet, _) => None,
+        (_, Some(signer)) => Some(signer.clone()),
+        (kind, None) => Some(ExternalSigner::connect_shared(kind)?),
+    };
+    let mut external =
+        external.as_ref().map(ExternalSigner::lock).transpose()?;
     if !args.signatures.is_empty() {
         let signatures = args
             .signatures
@@ -206,25 +726,240 @@ pub fn sign_tx<U: WalletUtils>(
             .map(|bytes| SignatureIndex::deserialize(bytes).unwrap())
             .collect();
         tx.add_signatures(signatures);
-    } else if let Some(account_public_keys_map) =
-        signing_data.account_public_keys_map
-    {
-        let signing_tx_keypairs = signing_data
-            .public_keys
+    } else if let Some(signer) = &mut external {
+        sign_sections_with(&mut **signer, tx, &signing_data)?;
+    } else {
+        sign_raw_tx(wallet, args, tx, &signing_data);
+    }
+
+    match &mut external {
+        // Disposable signing keys only ever live in the wallet
+        Some(signer) if !args.disposable_signing_key => {
+            sign_wrapper_with(&mut **signer, tx, &signing_data.fee_payer)
+        }
+        _ => {
+            let fee_payer_keypair =
+                find_key_by_pk(wallet, args, &signing_data.fee_payer)?;
+            tx.sign_wrapper(fee_payer_keypair);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "masp-tx-gen")]
+#[async_trait(?Send)]
+impl MaspSigner for ExternalSigner {
+    async fn proof_generation_key(
+        &mut self,
+        fvk: &ExtendedFullViewingKey,
+    ) -> Result<ProofGenerationKey, Error> {
+        let request = SignerRequest::ProofGenerationKey {
+            fvk: ExtendedViewingKey::from(*fvk).to_string(),
+        };
+        let SignerResponse::ProofGenerationKey { ak, nsk } =
+            self.request(&request)?
+        else {
+            return other_err(
+                "Unexpected response from the external signer".to_string(),
+            );
+        };
+        let decode = |key: &str| -> Option<[u8; 32]> {
+            HEXUPPER.decode(key.as_bytes()).ok()?.try_into().ok()
+        };
+        let ak = decode(&ak).and_then(|ak| {
+            Option::from(jubjub::SubgroupPoint::from_bytes(&ak))
+        });
+        let nsk = decode(&nsk)
+            .and_then(|nsk| Option::from(jubjub::Fr::from_repr(nsk)));
+        match (ak, nsk) {
+            (Some(ak), Some(nsk)) => Ok(ProofGenerationKey { ak, nsk }),
+            _ => Err(Error::from(EncodingError::Decoding(
+                "Invalid proof generation key from the external signer"
+                    .to_string(),
+            ))),
+        }
+    }
+
+    async fn sign_spends(
+        &mut self,
+        request: &MaspSigningRequest,
+    ) -> Result<Vec<RedJubjubSignature>, Error> {
+        let request = SignerRequest::SignSpends {
+            request: HEXUPPER.encode(
+                &request
+                    .try_to_vec()
+                    .map_err(|err| EncodingError::Encode(err.to_string()))?,
+            ),
+        };
+        let SignerResponse::SpendSignatures { signatures } =
+            self.request(&request)?
+        else {
+            return other_err(
+                "Unexpected response from the external signer".to_string(),
+            );
+        };
+        signatures
             .iter()
-            .filter_map(|public_key| {
-                match find_key_by_pk(wallet, args, public_key) {
-                    Ok(secret_key) => Some(secret_key),
-                    Err(_) => None,
-                }
+            .map(|signature| {
+                HEXUPPER
+                    .decode(signature.as_bytes())
+                    .ok()
+                    .and_then(|bytes| RedJubjubSignature::read(&bytes[..]).ok())
+                    .ok_or_else(|| {
+                        Error::from(EncodingError::Decoding(format!(
+                            "Invalid spend authorization from the external \
+                             signer: {}",
+                            signature
+                        )))
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Sign the inner sections of a transaction with the keys of the account
+/// described by the signing data that can be found in the wallet. Any other
+/// keys, such as the account's session keys, sign separately. The wrapper
+/// header is left unsigned.
+pub fn sign_raw_tx<U: WalletUtils>(
+    wallet: &mut Wallet<U>,
+    args: &args::Tx,
+    tx: &mut Tx,
+    signing_data: &SigningTxData,
+) {
+    let mut signer = WalletSigner::new(wallet, args.password.clone());
+    // The wallet skips the keys it cannot load rather than failing
+    let _ = sign_sections_with(&mut signer, tx, signing_data);
+}
+
+/// Sign the inner sections of a transaction with the keys of the account
+/// described by the signing data that are held by the given signer. Any other
+/// keys, such as the account's session keys, sign separately. The wrapper
+/// header is left unsigned.
+pub fn sign_sections_with(
+    signer: &mut dyn Signer,
+    tx: &mut Tx,
+    signing_data: &SigningTxData,
+) -> Result<(), Error> {
+    let Some(account_public_keys_map) = &signing_data.account_public_keys_map
+    else {
+        return Ok(());
+    };
+    // We already know the secret key corresponding to the MASP sentinel key
+    let (masp_keys, public_keys): (Vec<_>, Vec<_>) = signing_data
+        .public_keys
+        .iter()
+        .cloned()
+        .partition(|public_key| *public_key == masp_tx_key().ref_to());
+    let sighash = tx.raw_sighash();
+    let mut signatures = masp_keys
+        .into_iter()
+        .map(|public_key| {
+            (public_key, common::SigScheme::sign(&masp_tx_key(), sighash))
+        })
+        .collect::<Vec<_>>();
+    signatures.extend(signer.sign_sections(tx, &public_keys)?);
+    let (account_signatures, other_signatures): (Vec<_>, Vec<_>) =
+        signatures.into_iter().partition(|(public_key, _)| {
+            account_public_keys_map
+                .get_index_from_public_key(public_key)
+                .is_some()
+        });
+    if !account_signatures.is_empty() || other_signatures.is_empty() {
+        let signatures = account_signatures
+            .into_iter()
+            .filter_map(|(public_key, signature)| {
+                account_public_keys_map
+                    .get_index_from_public_key(&public_key)
+                    .map(|index| SignatureIndex { signature, index })
             })
-            .collect::<Vec<common::SecretKey>>();
-        tx.sign_raw(signing_tx_keypairs, account_public_keys_map);
+            .collect();
+        tx.add_signatures(signatures);
     }
+    // The keys that are not the account's own, such as its session keys,
+    // sign in sections of their own
+    for (_, signature) in other_signatures {
+        tx.add_signatures(BTreeSet::from([
+            SignatureIndex::from_single_signature(signature),
+        ]));
+    }
+    Ok(())
+}
 
-    let fee_payer_keypair =
-        find_key_by_pk(wallet, args, &signing_data.fee_payer)?;
-    tx.sign_wrapper(fee_payer_keypair);
+/// Sign all the sections of a transaction with the key of its fee payer held
+/// by the given signer
+pub fn sign_wrapper_with(
+    signer: &mut dyn Signer,
+    tx: &mut Tx,
+    fee_payer: &common::PublicKey,
+) -> Result<(), Error> {
+    // The signature must cover the sections submitted to the protocol
+    tx.protocol_filter();
+    let signature = signer.sign_wrapper(tx, fee_payer)?;
+    tx.add_wrapper_signature(signature);
+    Ok(())
+}
+
+/// Sign all the sections of a transaction with the key of its fee payer,
+/// looked up in the signer selected by the arguments
+pub fn sign_tx_wrapper<U: WalletUtils>(
+    wallet: &mut Wallet<U>,
+    args: &args::Tx,
+    tx: &mut Tx,
+    fee_payer: &common::PublicKey,
+) -> Result<(), Error> {
+    match &args.signer {
+        SignerKind::Wallet => {
+            let fee_payer_keypair = find_key_by_pk(wallet, args, fee_payer)?;
+            tx.sign_wrapper(fee_payer_keypair);
+            Ok(())
+        }
+        kind => {
+            let mut signer = ExternalSigner::connect(kind)?;
+            sign_wrapper_with(&mut signer, tx, fee_payer)
+        }
+    }
+}
+
+/// Merge the given offline signatures of the inner sections of a transaction
+/// into a section of its own, checking that they are valid and meet the
+/// threshold of the given account on chain
+pub async fn assemble_signatures<C: crate::ledger::queries::Client + Sync>(
+    client: &C,
+    tx: &mut Tx,
+    owner: &Address,
+    signatures: Vec<SignatureIndex>,
+) -> Result<(), Error> {
+    let account = match owner {
+        Address::Established(_) => rpc::get_account_info(client, owner).await?,
+        _ => None,
+    }
+    .ok_or_else(|| TxError::InvalidAccount(owner.encode()))?;
+    let sighash = tx.raw_sighash();
+    let mut valid_signatures = BTreeSet::new();
+    for signature in signatures {
+        signature
+            .verify(&account.public_keys_map, &sighash)
+            .map_err(|err| {
+                Error::Other(format!(
+                    "The signature with index {} is not valid for the \
+                     account {}: {}",
+                    signature.index, owner, err
+                ))
+            })?;
+        // Several signatures with the same index only count once
+        valid_signatures.insert(signature);
+    }
+    if valid_signatures.len() < usize::from(account.threshold) {
+        return other_err(format!(
+            "Only {} of the {} signatures required by the account {} were \
+             given",
+            valid_signatures.len(),
+            account.threshold,
+            owner
+        ));
+    }
+    tx.add_signatures(valid_signatures);
     Ok(())
 }
 
@@ -240,8 +975,20 @@ pub async fn aux_signing_data<
     owner: &Option<Address>,
     default_signer: Option<Address>,
 ) -> Result<SigningTxData, Error> {
+    // The connection is reused by all the requests made for this transaction
+    let external_signer = match &args.signer {
+        SignerKind::Wallet => None,
+        kind => Some(ExternalSigner::connect_shared(kind)?),
+    };
     let public_keys = if owner.is_some() || args.wrapper_fee_payer.is_none() {
-        tx_signers::<C, U>(client, wallet, args, default_signer.clone()).await?
+        tx_signers::<C, U>(
+            client,
+            wallet,
+            args,
+            default_signer.clone(),
+            external_signer.as_ref(),
+        )
+        .await?
     } else {
         vec![]
     };
@@ -288,6 +1035,7 @@ pub async fn aux_signing_data<
         threshold,
         account_public_keys_map,
         fee_payer,
+        external_signer,
     })
 }
 
@@ -546,7 +1294,7 @@ pub async fn wrap_tx<
                 };
 
                 match shielded
-                    .gen_shielded_transfer(client, transfer_args)
+                    .gen_shielded_transfer(client, transfer_args, None)
                     .await
                 {
                     Ok(Some(ShieldedTransfer {
@@ -1483,3 +2231,513 @@ pub async fn to_ledger_vector<
     format_outputs(&mut tv.output_expert);
     Ok(tv)
 }
+
+#[cfg(all(test, unix))]
+mod test_external_signer {
+    use std::os::unix::net::UnixListener;
+
+    use masp_primitives::zip32::ExtendedSpendingKey;
+    use namada_core::proto::{Code, Data};
+    use namada_core::types::key::testing::{keypair_1, keypair_2};
+
+    use super::*;
+    #[cfg(feature = "masp-tx-gen")]
+    use crate::ledger::masp::external_signer::SoftwareMaspSigner;
+
+    /// Serve the requests of a single client with a signer holding the given
+    /// keys
+    fn serve(
+        listener: UnixListener,
+        secret_key: common::SecretKey,
+        spending_key: ExtendedSpendingKey,
+    ) {
+        #[cfg(feature = "masp-tx-gen")]
+        let mut masp_signer = SoftwareMaspSigner {
+            keys: vec![spending_key],
+        };
+        #[cfg(not(feature = "masp-tx-gen"))]
+        let _ = spending_key;
+        let (stream, _) = listener.accept().unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        let sign = |sighash: &str| {
+            let sighash = crate::types::hash::Hash::from_str(sighash).unwrap();
+            let signature = common::SigScheme::sign(&secret_key, sighash);
+            HEXUPPER.encode(&signature.try_to_vec().unwrap())
+        };
+        for line in BufReader::new(stream).lines() {
+            let response = match serde_json::from_str(&line.unwrap()).unwrap() {
+                SignerRequest::PublicKey { .. } => SignerResponse::PublicKey {
+                    public_key: secret_key.ref_to().to_string(),
+                },
+                SignerRequest::SignSections {
+                    sighash,
+                    public_keys,
+                    ..
+                } => SignerResponse::Signatures {
+                    signatures: public_keys
+                        .into_iter()
+                        .filter(|pk| *pk == secret_key.ref_to().to_string())
+                        .map(|pk| (pk, sign(&sighash)))
+                        .collect(),
+                },
+                SignerRequest::SignWrapper { sighash, .. } => {
+                    SignerResponse::Signature {
+                        signature: sign(&sighash),
+                    }
+                }
+                #[cfg(feature = "masp-tx-gen")]
+                SignerRequest::ProofGenerationKey { fvk } => {
+                    let fvk = ExtendedViewingKey::from_str(&fvk).unwrap();
+                    match futures::executor::block_on(
+                        masp_signer.proof_generation_key(&fvk.into()),
+                    ) {
+                        Ok(key) => SignerResponse::ProofGenerationKey {
+                            ak: HEXUPPER.encode(&key.ak.to_bytes()),
+                            nsk: HEXUPPER.encode(&key.nsk.to_repr()),
+                        },
+                        Err(err) => SignerResponse::Error {
+                            message: err.to_string(),
+                        },
+                    }
+                }
+                #[cfg(feature = "masp-tx-gen")]
+                SignerRequest::SignSpends { request } => {
+                    let request = MaspSigningRequest::try_from_slice(
+                        &HEXUPPER.decode(request.as_bytes()).unwrap(),
+                    )
+                    .unwrap();
+                    match futures::executor::block_on(
+                        masp_signer.sign_spends(&request),
+                    ) {
+                        Ok(signatures) => SignerResponse::SpendSignatures {
+                            signatures: signatures
+                                .iter()
+                                .map(|signature| {
+                                    let mut bytes = vec![];
+                                    signature.write(&mut bytes).unwrap();
+                                    HEXUPPER.encode(&bytes)
+                                })
+                                .collect(),
+                        },
+                        Err(err) => SignerResponse::Error {
+                            message: err.to_string(),
+                        },
+                    }
+                }
+                #[cfg(not(feature = "masp-tx-gen"))]
+                _ => SignerResponse::Error {
+                    message: "No shielded keys are held".to_string(),
+                },
+            };
+            let mut response = serde_json::to_string(&response).unwrap();
+            response.push('\n');
+            writer.write_all(response.as_bytes()).unwrap();
+        }
+    }
+
+    /// Test that the signatures of an external signer over a Unix socket
+    /// authorize the sections and the wrapper of a transaction
+    #[test]
+    fn test_unix_socket_signer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signer.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let spending_key = ExtendedSpendingKey::master(&[1; 32]);
+        let server = std::thread::spawn(move || {
+            serve(listener, keypair_1(), spending_key)
+        });
+        let mut signer =
+            ExternalSigner::connect(&SignerKind::Socket(path)).unwrap();
+
+        let public_key = keypair_1().ref_to();
+        let pkh = PublicKeyHash::from(&public_key);
+        assert_eq!(signer.public_key(&pkh).unwrap(), public_key);
+
+        let mut tx = Tx::from_type(TxType::Raw);
+        tx.set_code(Code::new(vec![1, 2, 3]));
+        tx.set_data(Data::new(vec![4, 5, 6]));
+        let account_public_keys_map =
+            AccountPublicKeysMap::from_iter([public_key.clone()]);
+        let signing_data = SigningTxData {
+            // The key not held by the signer is skipped
+            public_keys: vec![public_key.clone(), keypair_2().ref_to()],
+            threshold: 1,
+            account_public_keys_map: Some(account_public_keys_map.clone()),
+            fee_payer: public_key.clone(),
+            external_signer: None,
+        };
+        sign_sections_with(&mut signer, &mut tx, &signing_data).unwrap();
+        sign_wrapper_with(&mut signer, &mut tx, &public_key).unwrap();
+
+        let sections = tx
+            .sections
+            .iter()
+            .filter_map(|section| match section {
+                Section::SectionSignature(signatures) => Some(signatures),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].total_signatures(), 1);
+        for signature in &sections[0].signatures {
+            signature
+                .verify(&account_public_keys_map, &tx.raw_sighash())
+                .unwrap();
+        }
+        let wrapper_signature = tx
+            .sections
+            .iter()
+            .find_map(|section| match section {
+                Section::Signature(signature) => Some(signature),
+                _ => None,
+            })
+            .unwrap();
+        wrapper_signature.verify_signature(&public_key).unwrap();
+
+        // The signer cannot sign for keys it does not hold
+        assert!(signer.sign_wrapper(&tx, &keypair_2().ref_to()).is_err());
+
+        drop(signer);
+        server.join().unwrap();
+    }
+
+    /// Test that an external signer process is spawned from its quoted
+    /// command line and spoken to over its standard input and output
+    #[test]
+    fn test_process_signer() {
+        let public_key = keypair_1().ref_to();
+        let pkh = PublicKeyHash::from(&public_key);
+        let response = serde_json::to_string(&SignerResponse::PublicKey {
+            public_key: public_key.to_string(),
+        })
+        .unwrap();
+        let refusal = serde_json::to_string(&SignerResponse::Error {
+            message: "the key is not held".to_string(),
+        })
+        .unwrap();
+        // The quoted script must not be split on its spaces
+        let script = format!(
+            "read request && echo '{response}' && read request && echo \
+             '{refusal}'"
+        );
+        let command = format!("sh -c '{}'", script.replace('\'', r"'\''"));
+        let mut signer =
+            ExternalSigner::connect(&SignerKind::Process(command)).unwrap();
+
+        assert_eq!(signer.public_key(&pkh).unwrap(), public_key);
+        assert!(signer.public_key(&pkh).is_err());
+        // The signer process has exited
+        assert!(signer.public_key(&pkh).is_err());
+
+        // A command line with an unterminated quote is rejected
+        let command = "sh -c 'read request".to_string();
+        assert!(ExternalSigner::connect(&SignerKind::Process(command)).is_err());
+    }
+
+    /// Test that a signer process not exiting once its input is closed is
+    /// killed
+    #[test]
+    fn test_process_signer_exit_timeout() {
+        let signer =
+            ExternalSigner::connect(&SignerKind::Process("sleep 60".into()))
+                .unwrap();
+        let start = Instant::now();
+        drop(signer);
+        assert!(start.elapsed() < SIGNER_EXIT_TIMEOUT + Duration::from_secs(5));
+    }
+
+    /// Test that the steps of the signing of a transaction share the
+    /// connection they are given, which is closed once it is no longer used
+    #[test]
+    fn test_shared_signer_connection() {
+        let kind = SignerKind::Process("cat".into());
+        let signer = ExternalSigner::connect_shared(&kind).unwrap();
+        let shared =
+            ExternalSigner::shared_or_connect(Some(&signer), &kind).unwrap();
+        assert!(Arc::ptr_eq(&signer, &shared));
+        drop(shared);
+        // Independent signings don't share a connection
+        let other = ExternalSigner::shared_or_connect(None, &kind).unwrap();
+        assert!(!Arc::ptr_eq(&signer, &other));
+        drop(other);
+
+        // The connection is closed with its last handle
+        let closed = Arc::downgrade(&signer);
+        drop(signer);
+        assert!(closed.upgrade().is_none());
+    }
+
+    /// Test that an external signer over a Unix socket exports the proof
+    /// generation keys and authorizes the spends of the shielded keys it
+    /// holds
+    #[cfg(feature = "masp-tx-gen")]
+    #[test]
+    fn test_unix_socket_masp_signer() {
+        use masp_primitives::constants::SPENDING_KEY_GENERATOR;
+        use masp_primitives::sapling::redjubjub::{PrivateKey, PublicKey};
+        use masp_primitives::transaction::components::sapling::builder::SaplingMetadata;
+        use rand_core::OsRng;
+
+        use crate::ledger::masp::external_signer::SpendAuthRequest;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signer.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let spending_key = ExtendedSpendingKey::master(&[1; 32]);
+        let server = std::thread::spawn(move || {
+            serve(listener, keypair_1(), spending_key)
+        });
+        let mut signer =
+            ExternalSigner::connect(&SignerKind::Socket(path)).unwrap();
+
+        let fvk = ExtendedFullViewingKey::from(&spending_key);
+        let key =
+            futures::executor::block_on(signer.proof_generation_key(&fvk))
+                .unwrap();
+        assert_eq!(key.to_viewing_key(), fvk.fvk.vk);
+
+        let alpha = jubjub::Fr::from(7);
+        let rk = PublicKey::from_private(
+            &PrivateKey(spending_key.expsk.ask),
+            SPENDING_KEY_GENERATOR,
+        )
+        .randomize(alpha, SPENDING_KEY_GENERATOR);
+        let mut rk_bytes = [0; 32];
+        rk.write(&mut rk_bytes[..]).unwrap();
+        let builder = masp_primitives::transaction::builder::Builder::<
+            masp_primitives::consensus::TestNetwork,
+            _,
+        >::new_with_rng(
+            masp_primitives::consensus::TestNetwork,
+            1.into(),
+            OsRng,
+        )
+        .map_builder(crate::ledger::masp::WalletMap);
+        let request = MaspSigningRequest {
+            builder: MaspBuilder {
+                target: Default::default(),
+                asset_types: Default::default(),
+                metadata: SaplingMetadata::empty(),
+                builder,
+            },
+            sighash: [2; 32],
+            spends: vec![SpendAuthRequest {
+                fvk,
+                alpha: alpha.to_repr(),
+                rk: rk_bytes,
+            }],
+        };
+        let signatures =
+            futures::executor::block_on(signer.sign_spends(&request)).unwrap();
+        assert_eq!(signatures.len(), 1);
+        assert!(rk.verify(
+            &request.sighash,
+            &signatures[0],
+            SPENDING_KEY_GENERATOR
+        ));
+
+        // The signer refuses the keys it does not hold
+        let other = ExtendedFullViewingKey::from(&ExtendedSpendingKey::master(
+            &[2; 32],
+        ));
+        assert!(futures::executor::block_on(
+            signer.proof_generation_key(&other)
+        )
+        .is_err());
+
+        drop(signer);
+        server.join().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test_assemble_signatures {
+    use namada_core::ledger::storage_api::account::init_account_storage;
+    use namada_core::ledger::storage_api::StorageWrite;
+    use namada_core::proto::{Code, Data, MultiSignature};
+    use namada_core::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+    use namada_core::types::hash::Hash;
+    use namada_core::types::key::testing::{keypair_1, keypair_2};
+    use namada_core::types::storage::Key;
+
+    use super::*;
+    use crate::ledger::queries::testing::TestClient;
+    use crate::ledger::queries::RPC;
+
+    /// A client whose storage holds the account of the given keys and
+    /// threshold
+    fn client_with_account(
+        owner: &Address,
+        public_keys: &[common::PublicKey],
+        threshold: u8,
+    ) -> TestClient<RPC> {
+        let mut client = TestClient::new(RPC);
+        init_account_storage(
+            &mut client.wl_storage,
+            owner,
+            public_keys,
+            threshold,
+        )
+        .unwrap();
+        // Established accounts exist once they have a validity predicate
+        client
+            .wl_storage
+            .write(&Key::validity_predicate(owner), Hash::zero())
+            .unwrap();
+        client.wl_storage.commit_tx();
+        client.wl_storage.commit_block().unwrap();
+        client
+    }
+
+    /// A transaction to be signed offline
+    fn raw_tx() -> Tx {
+        let mut tx = Tx::from_type(TxType::Raw);
+        tx.set_code(Code::new(vec![1, 2, 3]));
+        tx.set_data(Data::new(vec![4, 5, 6]));
+        tx
+    }
+
+    /// The signature of the key with the given index over the sections of the
+    /// transaction
+    fn offline_signature(
+        tx: &Tx,
+        keypair: &common::SecretKey,
+        index: u8,
+    ) -> SignatureIndex {
+        SignatureIndex {
+            signature: common::SigScheme::sign(keypair, tx.raw_sighash()),
+            index,
+        }
+    }
+
+    /// The signature sections of the transaction
+    fn signature_sections(tx: &Tx) -> Vec<&MultiSignature> {
+        tx.sections
+            .iter()
+            .filter_map(|section| match section {
+                Section::SectionSignature(signatures) => Some(signatures),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Test that the offline signatures meeting the threshold of the account
+    /// are merged into a section
+    #[tokio::test]
+    async fn test_threshold_met() {
+        let owner = established_address_1();
+        let public_keys = [keypair_1().ref_to(), keypair_2().ref_to()];
+        let client = client_with_account(&owner, &public_keys, 2);
+        let mut tx = raw_tx();
+        let signatures = vec![
+            offline_signature(&tx, &keypair_1(), 0),
+            offline_signature(&tx, &keypair_2(), 1),
+        ];
+
+        assemble_signatures(&client, &mut tx, &owner, signatures)
+            .await
+            .unwrap();
+        let sections = signature_sections(&tx);
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].total_signatures(), 2);
+        let public_keys_map = AccountPublicKeysMap::from_iter(public_keys);
+        for signature in &sections[0].signatures {
+            signature
+                .verify(&public_keys_map, &tx.raw_sighash())
+                .unwrap();
+        }
+    }
+
+    /// Test that too few offline signatures are rejected, counting the
+    /// signatures with the same index once
+    #[tokio::test]
+    async fn test_threshold_short() {
+        let owner = established_address_1();
+        let public_keys = [keypair_1().ref_to(), keypair_2().ref_to()];
+        let client = client_with_account(&owner, &public_keys, 2);
+        let mut tx = raw_tx();
+
+        let signatures = vec![offline_signature(&tx, &keypair_1(), 0)];
+        assert!(assemble_signatures(&client, &mut tx, &owner, signatures)
+            .await
+            .is_err());
+        let signatures = vec![
+            offline_signature(&tx, &keypair_1(), 0),
+            offline_signature(&tx, &keypair_1(), 0),
+        ];
+        assert!(assemble_signatures(&client, &mut tx, &owner, signatures)
+            .await
+            .is_err());
+        assert!(signature_sections(&tx).is_empty());
+    }
+
+    /// Test that the signatures not made by the key of their index over the
+    /// sections of the transaction are rejected
+    #[tokio::test]
+    async fn test_invalid_signatures() {
+        let owner = established_address_1();
+        let public_keys = [keypair_1().ref_to(), keypair_2().ref_to()];
+        let client = client_with_account(&owner, &public_keys, 1);
+        let mut tx = raw_tx();
+
+        // Signed by the key of another index
+        let signatures = vec![offline_signature(&tx, &keypair_2(), 0)];
+        assert!(assemble_signatures(&client, &mut tx, &owner, signatures)
+            .await
+            .is_err());
+        // Signed over another transaction
+        let mut other = raw_tx();
+        other.set_data(Data::new(vec![7, 8, 9]));
+        let signatures = vec![offline_signature(&other, &keypair_1(), 0)];
+        assert!(assemble_signatures(&client, &mut tx, &owner, signatures)
+            .await
+            .is_err());
+        // An index without a key
+        let signatures = vec![offline_signature(&tx, &keypair_1(), 2)];
+        assert!(assemble_signatures(&client, &mut tx, &owner, signatures)
+            .await
+            .is_err());
+        assert!(signature_sections(&tx).is_empty());
+    }
+
+    /// Test that only the signatures of existing established accounts are
+    /// assembled
+    #[tokio::test]
+    async fn test_non_established_owners() {
+        let public_keys = [keypair_1().ref_to()];
+        let client =
+            client_with_account(&established_address_1(), &public_keys, 1);
+        let mut tx = raw_tx();
+        let signatures = vec![offline_signature(&tx, &keypair_1(), 0)];
+
+        let implicit = Address::from(&keypair_1().ref_to());
+        assert!(assemble_signatures(
+            &client,
+            &mut tx,
+            &implicit,
+            signatures.clone()
+        )
+        .await
+        .is_err());
+        assert!(assemble_signatures(
+            &client,
+            &mut tx,
+            &masp(),
+            signatures.clone()
+        )
+        .await
+        .is_err());
+        // An established address without an account
+        assert!(assemble_signatures(
+            &client,
+            &mut tx,
+            &established_address_2(),
+            signatures
+        )
+        .await
+        .is_err());
+        assert!(signature_sections(&tx).is_empty());
+    }
+}
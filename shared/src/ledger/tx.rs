@@ -1,8 +1,9 @@
 //! SDK functions to construct different types of transactions
 use std::borrow::Cow;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::time::Duration;
 
 use borsh::BorshSerialize;
@@ -23,9 +24,13 @@ use namada_core::ledger::governance::cli::onchain::{
 use namada_core::ledger::governance::storage::proposal::ProposalType;
 use namada_core::ledger::governance::storage::vote::StorageProposalVote;
 use namada_core::ledger::pgf::cli::steward::Commission;
+use namada_core::types::account::{
+    AccountGuardians, AccountPolicy, SessionKey,
+};
 use namada_core::types::address::{masp, Address, InternalAddress};
 use namada_core::types::dec::Dec;
 use namada_core::types::hash::Hash;
+use namada_core::types::ibc::IbcShieldedTransfer;
 use namada_core::types::token::MaspDenom;
 use namada_core::types::transaction::governance::{
     InitProposalData, VoteProposalData,
@@ -35,7 +40,7 @@ use namada_proof_of_stake::parameters::PosParams;
 use namada_proof_of_stake::types::{CommissionPair, ValidatorState};
 
 use super::rpc::query_wasm_code_hash;
-use super::signing::{self, TxSourcePostBalance};
+use super::signing::{self, SharedExternalSigner, TxSourcePostBalance};
 use crate::ibc::applications::transfer::msgs::transfer::MsgTransfer;
 use crate::ibc::applications::transfer::packet::PacketData;
 use crate::ibc::applications::transfer::PrefixedCoin;
@@ -44,25 +49,32 @@ use crate::ibc::core::timestamp::Timestamp as IbcTimestamp;
 use crate::ibc::core::Msg;
 use crate::ibc::Height as IbcHeight;
 use crate::ledger::args::{self, InputAmount};
-use crate::ledger::ibc::storage::ibc_denom_key;
+use crate::ledger::ibc::storage::{ibc_denom_key, ibc_token};
 use crate::ledger::masp::TransferErr::Build;
 use crate::ledger::masp::{ShieldedContext, ShieldedTransfer, ShieldedUtils};
+use crate::ledger::parameters::storage as parameter_storage;
 use crate::ledger::rpc::{
     self, format_denominated_amount, validate_amount, TxBroadcastData,
     TxResponse,
 };
 use crate::ledger::wallet::{Wallet, WalletUtils};
-use crate::proto::{MaspBuilder, Tx};
+use crate::proto::{MaspBuilder, Section, Tx};
 use crate::tendermint_rpc::endpoint::broadcast::tx_sync::Response;
 use crate::tendermint_rpc::error::Error as RpcError;
 use crate::types::control_flow::{time, ProceedOrElse};
 use crate::types::error::{EncodingError, Error, QueryError, Result, TxError};
 use crate::types::key::*;
-use crate::types::masp::TransferTarget;
+use crate::types::masp::{TransferSource, TransferTarget};
 use crate::types::storage::Epoch;
 use crate::types::time::DateTimeUtc;
-use crate::types::transaction::account::{InitAccount, UpdateAccount};
-use crate::types::transaction::{pos, TxType};
+use crate::types::transaction::account::{
+    InitAccount, RecoverAccount, RecoveryAction, UpdateAccount,
+    UpdateSessionKey,
+};
+use crate::types::transaction::scheduled::{
+    CancelScheduledTx, ScheduleTarget, ScheduledTx,
+};
+use crate::types::transaction::{pos, Fee, TxType};
 use crate::types::{storage, token};
 use crate::vm;
 
@@ -124,6 +136,15 @@ impl ProcessTxResponse {
 pub fn dump_tx(args: &args::Tx, tx: Tx) {
     let tx_id = tx.header_hash();
     let serialized_tx = tx.serialize();
+    // Co-signers check this before signing the transaction offline, which is
+    // only left to do while its sections are unsigned
+    let is_signed = tx.sections.iter().any(|section| {
+        matches!(
+            section,
+            Section::SectionSignature(_) | Section::Signature(_)
+        )
+    });
+    let sighash = (!is_signed).then(|| tx.raw_sighash());
     match args.output_folder.to_owned() {
         Some(path) => {
             let tx_filename = format!("{}.tx", tx_id);
@@ -135,10 +156,27 @@ pub fn dump_tx(args: &args::Tx, tx: Tx) {
                 "Transaction serialized to {}.",
                 tx_path.to_string_lossy()
             );
+            if let Some(sighash) = sighash {
+                let sighash_path = path.join(format!("{}.sighash", tx_id));
+                std::fs::write(&sighash_path, sighash.to_string())
+                    .expect("Should be able to write to file.");
+                println!(
+                    "The hash to be signed by the co-signers was written to \
+                     {}.",
+                    sighash_path.to_string_lossy()
+                );
+            }
         }
         None => {
             println!("Below the serialized transaction: \n");
-            println!("{}", serialized_tx)
+            println!("{}", serialized_tx);
+            if let Some(sighash) = sighash {
+                println!(
+                    "The signatures of the transaction's sections are over \
+                     {}.",
+                    sighash
+                );
+            }
         }
     }
 }
@@ -202,7 +240,12 @@ pub async fn process_tx<
     // println!("HTTP request body: {}", request_body);
 
     if args.dry_run || args.dry_run_wrapper {
-        expect_dry_broadcast(TxBroadcastData::DryRun(tx), client).await
+        expect_dry_broadcast(
+            TxBroadcastData::DryRun(tx),
+            client,
+            args.gas_report,
+        )
+        .await
     } else {
         // We use this to determine when the wrapper tx makes it on-chain
         let wrapper_hash = tx.header_hash().to_string();
@@ -668,6 +711,178 @@ pub async fn build_resign_steward<
     .await
 }
 
+/// Craft transaction to schedule another transaction for execution at a
+/// future block height or epoch. The scheduled transaction is signed with the
+/// keys of the owner.
+pub async fn build_schedule_tx<
+    C: crate::ledger::queries::Client + Sync,
+    U: WalletUtils,
+    V: ShieldedUtils,
+>(
+    client: &C,
+    wallet: &mut Wallet<U>,
+    shielded: &mut ShieldedContext<V>,
+    args::ScheduleTx {
+        tx: tx_args,
+        owner,
+        target,
+        tx_data,
+        tx_code_path,
+    }: args::ScheduleTx,
+    signing_data: &signing::SigningTxData,
+) -> Result<(Tx, Option<Epoch>)> {
+    let mut scheduled = Tx::deserialize(&tx_data)
+        .map_err(|e| TxError::Other(format!("Invalid scheduled tx: {}", e)))?;
+    scheduled.update_header(TxType::Raw);
+    // Bind the tx to its target before signing so that it can't be executed
+    // outside of the scheduled txs
+    scheduled.set_schedule(target);
+    signing::sign_raw_tx(wallet, &tx_args, &mut scheduled, signing_data);
+
+    let is_future = match target {
+        ScheduleTarget::Height(height) => {
+            let current_height = rpc::query_block(client)
+                .await?
+                .map(|block| block.height)
+                .unwrap_or_default();
+            height > current_height
+        }
+        ScheduleTarget::Epoch(epoch) => {
+            epoch > rpc::query_epoch(client).await?
+        }
+    };
+    if !is_future && !tx_args.force {
+        eprintln!("The scheduled {} must be in the future.", target);
+        return Err(Error::from(TxError::Other(format!(
+            "Scheduled {} is not in the future",
+            target
+        ))));
+    }
+
+    let max_block_gas: u64 = rpc::query_storage_value(
+        client,
+        &parameter_storage::get_max_block_gas_key(),
+    )
+    .await?;
+    if u64::from(tx_args.gas_limit) > max_block_gas && !tx_args.force {
+        eprintln!(
+            "The gas limit {} exceeds the maximum block gas {}.",
+            u64::from(tx_args.gas_limit),
+            max_block_gas
+        );
+        return Err(Error::from(TxError::Other(
+            "The gas limit of the scheduled tx is too high".to_string(),
+        )));
+    }
+
+    // The fee token must be allowed for fee payment and the fee defaults to
+    // the minimum gas cost
+    let gas_cost_table: BTreeMap<Address, token::Amount> =
+        rpc::query_storage_value(
+            client,
+            &parameter_storage::get_gas_cost_key(),
+        )
+        .await?;
+    let Some(gas_cost) = gas_cost_table.get(&tx_args.fee_token).copied() else {
+        eprintln!(
+            "The token {} is not allowed for fee payment.",
+            tx_args.fee_token
+        );
+        return Err(Error::from(TxError::Other(format!(
+            "Token {} is not allowed for fee payment",
+            tx_args.fee_token
+        ))));
+    };
+    let amount_per_gas_unit = match tx_args.fee_amount {
+        Some(amount) => {
+            let amount = validate_amount(
+                client,
+                amount,
+                &tx_args.fee_token,
+                tx_args.force,
+            )
+            .await?;
+            token::Amount::from_uint(amount.amount, 0).unwrap()
+        }
+        None => gas_cost,
+    };
+    if amount_per_gas_unit < gas_cost && !tx_args.force {
+        eprintln!(
+            "The fee amount {} is lower than the minimum gas cost {}.",
+            amount_per_gas_unit.to_string_native(),
+            gas_cost.to_string_native()
+        );
+        return Err(Error::from(TxError::Other(
+            "The fee of the scheduled tx is too low".to_string(),
+        )));
+    }
+
+    let data = ScheduledTx {
+        owner,
+        target,
+        fee: Fee {
+            amount_per_gas_unit,
+            token: tx_args.fee_token.clone(),
+        },
+        gas_limit: tx_args.gas_limit,
+        tx: scheduled,
+    };
+
+    build(
+        client,
+        wallet,
+        shielded,
+        &tx_args,
+        tx_code_path,
+        data,
+        do_nothing,
+        &signing_data.fee_payer,
+        None,
+    )
+    .await
+}
+
+/// Craft transaction to cancel a scheduled transaction
+pub async fn build_cancel_scheduled_tx<
+    C: crate::ledger::queries::Client + Sync,
+    U: WalletUtils,
+    V: ShieldedUtils,
+>(
+    client: &C,
+    wallet: &mut Wallet<U>,
+    shielded: &mut ShieldedContext<V>,
+    args::CancelScheduledTx {
+        tx: tx_args,
+        owner,
+        target,
+        tx_hash,
+        tx_code_path,
+    }: args::CancelScheduledTx,
+    gas_payer: &common::PublicKey,
+) -> Result<(Tx, Option<Epoch>)> {
+    let hash = Hash::from_str(&tx_hash)
+        .map_err(|e| TxError::Other(format!("Invalid tx hash: {}", e)))?;
+
+    let data = CancelScheduledTx {
+        owner,
+        target,
+        hash,
+    };
+
+    build(
+        client,
+        wallet,
+        shielded,
+        &tx_args,
+        tx_code_path,
+        data,
+        do_nothing,
+        gas_payer,
+        None,
+    )
+    .await
+}
+
 /// Submit transaction to unjail a jailed validator
 pub async fn build_unjail_validator<
     C: crate::ledger::queries::Client + Sync,
@@ -1292,10 +1507,21 @@ pub async fn build_ibc_transfer<
     fee_payer: common::PublicKey,
 ) -> Result<(Tx, Option<Epoch>)> {
     // Check that the source address exists on chain
-    let source =
-        source_exists_or_err(args.source.clone(), args.tx.force, client)
-            .await?;
+    let source = source_exists_or_err(
+        args.source.effective_address(),
+        args.tx.force,
+        client,
+    )
+    .await?;
     // We cannot check the receiver
+    // The MASP address can't be refunded without a MASP transaction
+    if args.source.spending_key().is_some() && args.refund_target.is_none() {
+        return Err(Error::Other(
+            "A refund target is required to send tokens from the shielded \
+             pool"
+                .to_string(),
+        ));
+    }
 
     // validate the amount given
     let validated_amount =
@@ -1353,6 +1579,21 @@ pub async fn build_ibc_transfer<
         memo: args.memo.unwrap_or_default().into(),
     };
 
+    // Spend the shielded notes when the source is a spending key. The tokens
+    // are unshielded to the IBC address and sent from the MASP address. They
+    // are refunded to the refund target if the packet fails.
+    let shielded_transfer = gen_ibc_shielded_part(
+        client,
+        shielded,
+        &args.source,
+        &TransferTarget::Address(Address::Internal(InternalAddress::Ibc)),
+        &args.token,
+        validated_amount,
+        args.refund_target,
+        false,
+    )
+    .await?;
+
     // this height should be that of the destination chain, not this chain
     let timeout_height = match args.timeout_height {
         Some(h) => {
@@ -1397,6 +1638,12 @@ pub async fn build_ibc_transfer<
     let mut tx = Tx::new(chain_id, args.tx.expiration);
     tx.add_code_from_hash(tx_code_hash)
         .add_serialized_data(data);
+    if let Some(shielded_transfer) = shielded_transfer {
+        let shielded_transfer = shielded_transfer
+            .try_to_vec()
+            .map_err(|e| Error::from(EncodingError::Encode(e.to_string())))?;
+        tx.add_extra_section(shielded_transfer);
+    }
 
     let epoch = prepare_tx::<C, U, V>(
         client,
@@ -1414,6 +1661,105 @@ pub async fn build_ibc_transfer<
     Ok((tx, epoch))
 }
 
+/// Build a shielded transfer to be put into the memo of an IBC packet whose
+/// receiver is a MASP payment address on this chain. The packet has to be
+/// received within the current MASP epoch since the asset types of the notes
+/// are stamped with it.
+pub async fn gen_ibc_shielded_transfer<
+    C: crate::ledger::queries::Client + Sync,
+    V: ShieldedUtils,
+>(
+    client: &C,
+    shielded: &mut ShieldedContext<V>,
+    args: args::GenIbcShieldedTransfer,
+) -> Result<Option<IbcShieldedTransfer>> {
+    // The token received on this chain is either the original token coming
+    // back or an IBC token whose trace is prefixed by the receiving channel
+    let token = match Address::decode(&args.token) {
+        Ok(token) => token,
+        Err(_) => ibc_token(format!(
+            "{}/{}/{}",
+            args.port_id, args.channel_id, args.token
+        )),
+    };
+    let validated_amount =
+        validate_amount(client, args.amount, &token, true).await?;
+    // The source is only used to label the transparent input of the MASP
+    // transaction. The outputs are disclosed for the receiving chain to check
+    // that they pay the packet receiver.
+    gen_ibc_shielded_part(
+        client,
+        shielded,
+        &TransferSource::Address(Address::Internal(InternalAddress::Ibc)),
+        &args.target,
+        &token,
+        validated_amount,
+        None,
+        true,
+    )
+    .await
+}
+
+/// Build the shielded part of an IBC transfer moving the tokens between the
+/// MASP address and a transparent address, disclosing its outputs if
+/// requested
+#[allow(clippy::too_many_arguments)]
+async fn gen_ibc_shielded_part<
+    C: crate::ledger::queries::Client + Sync,
+    V: ShieldedUtils,
+>(
+    client: &C,
+    shielded: &mut ShieldedContext<V>,
+    source: &TransferSource,
+    target: &TransferTarget,
+    token: &Address,
+    amount: token::DenominatedAmount,
+    refund_target: Option<Address>,
+    disclose_outputs: bool,
+) -> Result<Option<IbcShieldedTransfer>> {
+    let stx_result = if disclose_outputs {
+        shielded
+            .gen_disclosed_shielding_parts(
+                client, source, target, token, amount,
+            )
+            .await
+    } else {
+        shielded
+            .gen_shielded_transfer_parts(client, source, target, token, amount)
+            .await
+            .map(|stx| stx.map(|stx| (stx, vec![])))
+    };
+    let shielded_parts = match stx_result {
+        Ok(stx) => Ok(stx),
+        Err(Build(builder::Error::InsufficientFunds(_))) => {
+            Err(TxError::NegativeBalanceAfterTransfer(
+                Box::new(source.effective_address()),
+                amount.amount.to_string_native(),
+                Box::new(token.clone()),
+            ))
+        }
+        Err(err) => Err(TxError::MaspError(err.to_string())),
+    }?;
+    // Determine whether to pin this transaction to a storage key
+    let key = match target {
+        TransferTarget::PaymentAddress(pa) if pa.is_pinned() => Some(pa.hash()),
+        _ => None,
+    };
+    Ok(shielded_parts.map(|(stx, ocks)| IbcShieldedTransfer {
+        transfer: token::Transfer {
+            source: source.effective_address(),
+            target: target.effective_address(),
+            token: token.clone(),
+            amount,
+            key,
+            shielded: None,
+        },
+        masp_tx: stx.masp_tx,
+        refund_target,
+        output_ocks: ocks,
+    }))
+}
+
 /// Abstraction for helping build transactions
 #[allow(clippy::too_many_arguments)]
 pub async fn build<C: crate::ledger::queries::Client + Sync, U, V, F, D>(
@@ -1518,7 +1864,7 @@ async fn add_asset_type<
 /// Collect the asset types used in the given Builder and decode them. This
 /// function provides the data necessary for offline wallets to present asset
 /// type information.
-async fn used_asset_types<
+pub(crate) async fn used_asset_types<
     C: crate::ledger::queries::Client + Sync,
     U: ShieldedUtils,
     P,
@@ -1568,7 +1914,8 @@ async fn used_asset_types<
     Ok(asset_types)
 }
 
-/// Submit an ordinary transfer
+/// Submit an ordinary transfer. The notes of a viewing key are spent over the
+/// given connection to the external signer, if any.
 pub async fn build_transfer<
     C: crate::ledger::queries::Client + Sync,
     U: WalletUtils,
@@ -1579,6 +1926,7 @@ pub async fn build_transfer<
     shielded: &mut ShieldedContext<V>,
     mut args: args::TxTransfer,
     fee_payer: common::PublicKey,
+    external_signer: Option<&SharedExternalSigner>,
 ) -> Result<(Tx, Option<Epoch>)> {
     let source = args.source.effective_address();
     let target = args.target.effective_address();
@@ -1635,7 +1983,9 @@ pub async fn build_transfer<
     let is_source_faucet = false;
 
     // Construct the shielded part of the transaction, if any
-    let stx_result = shielded.gen_shielded_transfer(client, args.clone()).await;
+    let stx_result = shielded
+        .gen_shielded_transfer(client, args.clone(), external_signer)
+        .await;
 
     let shielded_parts = match stx_result {
         Ok(stx) => Ok(stx),
@@ -1812,6 +2162,15 @@ pub async fn build_update_account<
         addr,
         public_keys,
         threshold,
+        outflow_limits,
+        allowed_recipients,
+        large_debit_amounts,
+        large_debit_threshold,
+        clear_policy,
+        guardians,
+        guardian_threshold,
+        recovery_delay,
+        clear_guardians,
     }: args::TxUpdateAccount,
     fee_payer: common::PublicKey,
 ) -> Result<(Tx, Option<Epoch>)> {
@@ -1832,6 +2191,65 @@ pub async fn build_update_account<
         None => None,
     };
 
+    let is_policy_set = !outflow_limits.is_empty()
+        || !allowed_recipients.is_empty()
+        || !large_debit_amounts.is_empty()
+        || large_debit_threshold.is_some();
+    let policy = if clear_policy {
+        Some(AccountPolicy::default())
+    } else if is_policy_set {
+        let mut policy = AccountPolicy {
+            allowed_recipients: allowed_recipients.into_iter().collect(),
+            large_debit_threshold,
+            ..Default::default()
+        };
+        for (token, amount) in outflow_limits {
+            let amount = validate_amount(
+                client,
+                InputAmount::Unvalidated(amount),
+                &token,
+                tx_args.force,
+            )
+            .await?;
+            policy.outflow_limits.insert(token, amount.amount);
+        }
+        for (token, amount) in large_debit_amounts {
+            let amount = validate_amount(
+                client,
+                InputAmount::Unvalidated(amount),
+                &token,
+                tx_args.force,
+            )
+            .await?;
+            policy.large_debit_amounts.insert(token, amount.amount);
+        }
+        Some(policy)
+    } else {
+        None
+    };
+
+    let guardians = if clear_guardians {
+        Some(AccountGuardians::default())
+    } else if !guardians.is_empty() {
+        let threshold = guardian_threshold.ok_or_else(|| {
+            TxError::Other(
+                "A guardian threshold is required to set guardians".to_string(),
+            )
+        })?;
+        let recovery_delay = recovery_delay.ok_or_else(|| {
+            TxError::Other(
+                "A recovery delay is required to set guardians".to_string(),
+            )
+        })?;
+        Some(AccountGuardians {
+            guardians: guardians.into_iter().collect(),
+            threshold,
+            recovery_delay,
+        })
+    } else {
+        None
+    };
+
     let chain_id = tx_args.chain_id.clone().unwrap();
     let mut tx = Tx::new(chain_id, tx_args.expiration);
     let extra_section_hash = vp_code_hash
@@ -1842,6 +2260,8 @@ pub async fn build_update_account<
         vp_code_hash: extra_section_hash,
         public_keys,
         threshold,
+        policy,
+        guardians,
     };
 
     let add_code_hash = |tx: &mut Tx, data: &mut UpdateAccount| {
@@ -1864,6 +2284,175 @@ pub async fn build_update_account<
     .await
 }
 
+/// Submit a step of the guardian-based recovery of an account
+pub async fn build_recover_account<
+    C: crate::ledger::queries::Client + Sync,
+    U: WalletUtils,
+    V: ShieldedUtils,
+>(
+    client: &C,
+    wallet: &mut Wallet<U>,
+    shielded: &mut ShieldedContext<V>,
+    args::TxRecoverAccount {
+        tx: tx_args,
+        owner,
+        guardian,
+        public_keys,
+        threshold,
+        approve,
+        cancel,
+        execute,
+        tx_code_path,
+    }: args::TxRecoverAccount,
+    fee_payer: common::PublicKey,
+) -> Result<(Tx, Option<Epoch>)> {
+    let missing_guardian = || {
+        TxError::Other(
+            "A guardian is required to request or approve a recovery"
+                .to_string(),
+        )
+    };
+    let action = if cancel {
+        RecoveryAction::Cancel
+    } else if execute {
+        RecoveryAction::Execute
+    } else if approve {
+        RecoveryAction::Approve {
+            guardian: guardian.ok_or_else(missing_guardian)?,
+        }
+    } else {
+        if public_keys.is_empty() {
+            return Err(Error::from(TxError::Other(
+                "The new public keys are required to request a recovery"
+                    .to_string(),
+            )));
+        }
+        RecoveryAction::Request {
+            guardian: guardian.ok_or_else(missing_guardian)?,
+            threshold: threshold.unwrap_or(1),
+            public_keys,
+        }
+    };
+
+    let data = RecoverAccount { owner, action };
+
+    build(
+        client,
+        wallet,
+        shielded,
+        &tx_args,
+        tx_code_path,
+        data,
+        do_nothing,
+        &fee_payer,
+        None,
+    )
+    .await
+}
+
+/// Submit a session key addition to an account
+pub async fn build_add_session_key<
+    C: crate::ledger::queries::Client + Sync,
+    U: WalletUtils,
+    V: ShieldedUtils,
+>(
+    client: &C,
+    wallet: &mut Wallet<U>,
+    shielded: &mut ShieldedContext<V>,
+    args::TxAddSessionKey {
+        tx: tx_args,
+        owner,
+        public_key,
+        allowed_txs,
+        max_spend,
+        expiry,
+        tx_code_path,
+    }: args::TxAddSessionKey,
+    fee_payer: common::PublicKey,
+) -> Result<(Tx, Option<Epoch>)> {
+    if allowed_txs.is_empty() {
+        return Err(Error::from(TxError::Other(
+            "A session key must be allowed to sign at least one tx".to_string(),
+        )));
+    }
+    let mut session_key = SessionKey {
+        allowed_txs: BTreeSet::new(),
+        max_spend: BTreeMap::new(),
+        expiry,
+        spent: BTreeMap::new(),
+    };
+    for tx_path in allowed_txs {
+        let tx_code_hash = query_wasm_code_hash_buf(client, &tx_path).await?;
+        session_key.allowed_txs.insert(tx_code_hash);
+    }
+    for (token, amount) in max_spend {
+        let amount = validate_amount(
+            client,
+            InputAmount::Unvalidated(amount),
+            &token,
+            tx_args.force,
+        )
+        .await?;
+        session_key.max_spend.insert(token, amount.amount);
+    }
+
+    let data = UpdateSessionKey {
+        owner,
+        public_key,
+        session_key: Some(session_key),
+    };
+
+    build(
+        client,
+        wallet,
+        shielded,
+        &tx_args,
+        tx_code_path,
+        data,
+        do_nothing,
+        &fee_payer,
+        None,
+    )
+    .await
+}
+
+/// Submit a session key revocation from an account
+pub async fn build_revoke_session_key<
+    C: crate::ledger::queries::Client + Sync,
+    U: WalletUtils,
+    V: ShieldedUtils,
+>(
+    client: &C,
+    wallet: &mut Wallet<U>,
+    shielded: &mut ShieldedContext<V>,
+    args::TxRevokeSessionKey {
+        tx: tx_args,
+        owner,
+        public_key,
+        tx_code_path,
+    }: args::TxRevokeSessionKey,
+    fee_payer: common::PublicKey,
+) -> Result<(Tx, Option<Epoch>)> {
+    let data = UpdateSessionKey {
+        owner,
+        public_key,
+        session_key: None,
+    };
+
+    build(
+        client,
+        wallet,
+        shielded,
+        &tx_args,
+        tx_code_path,
+        data,
+        do_nothing,
+        &fee_payer,
+        None,
+    )
+    .await
+}
+
 /// Submit a custom transaction
 pub async fn build_custom<
     C: crate::ledger::queries::Client + Sync,
@@ -1919,10 +2508,17 @@ pub async fn build_custom<
 async fn expect_dry_broadcast<C: crate::ledger::queries::Client + Sync>(
     to_broadcast: TxBroadcastData,
     client: &C,
+    gas_report: bool,
 ) -> Result<ProcessTxResponse> {
     match to_broadcast {
         TxBroadcastData::DryRun(tx) => {
-            rpc::dry_run_tx(client, tx.to_bytes()).await?;
+            let result = rpc::dry_run_tx(client, tx.to_bytes()).await?;
+            if gas_report {
+                match result.gas_profile {
+                    Some(profile) => println!("{}", profile),
+                    None => println!("No gas report was returned."),
+                }
+            }
             Ok(ProcessTxResponse::DryRun)
         }
         TxBroadcastData::Live {
@@ -52,6 +52,19 @@ pub fn add_gas(gas_meter: &mut VpGasMeter, used_gas: u64) -> EnvResult<()> {
     result
 }
 
+/// Add the gas cost of reading the given storage key in a validity predicate
+/// and record it in the gas profile, if profiling is enabled
+pub fn add_storage_read_gas(
+    gas_meter: &mut VpGasMeter,
+    key: &Key,
+    used_gas: u64,
+) -> EnvResult<()> {
+    if let Some(profile) = gas_meter.gas_profile() {
+        profile.record_storage_read(key, used_gas);
+    }
+    add_gas(gas_meter, used_gas)
+}
+
 /// Storage read prior state (before tx execution). It will try to read from the
 /// storage.
 pub fn read_pre<DB, H>(
@@ -65,7 +78,7 @@ where
     H: StorageHasher,
 {
     let (log_val, gas) = write_log.read_pre(key);
-    add_gas(gas_meter, gas)?;
+    add_storage_read_gas(gas_meter, key, gas)?;
     match log_val {
         Some(write_log::StorageModification::Write { ref value }) => {
             Ok(Some(value.clone()))
@@ -87,7 +100,7 @@ where
             // When not found in write log, try to read from the storage
             let (value, gas) =
                 storage.read(key).map_err(RuntimeError::StorageError)?;
-            add_gas(gas_meter, gas)?;
+            add_storage_read_gas(gas_meter, key, gas)?;
             Ok(value)
         }
     }
@@ -107,7 +120,7 @@ where
 {
     // Try to read from the write log first
     let (log_val, gas) = write_log.read(key);
-    add_gas(gas_meter, gas)?;
+    add_storage_read_gas(gas_meter, key, gas)?;
     match log_val {
         Some(write_log::StorageModification::Write { ref value }) => {
             Ok(Some(value.clone()))
@@ -129,7 +142,7 @@ where
             // When not found in write log, try to read from the storage
             let (value, gas) =
                 storage.read(key).map_err(RuntimeError::StorageError)?;
-            add_gas(gas_meter, gas)?;
+            add_storage_read_gas(gas_meter, key, gas)?;
             Ok(value)
         }
     }
@@ -144,7 +157,7 @@ pub fn read_temp(
 ) -> EnvResult<Option<Vec<u8>>> {
     // Try to read from the write log first
     let (log_val, gas) = write_log.read(key);
-    add_gas(gas_meter, gas)?;
+    add_storage_read_gas(gas_meter, key, gas)?;
     match log_val {
         Some(write_log::StorageModification::Temp { ref value }) => {
             Ok(Some(value.clone()))
@@ -168,7 +181,7 @@ where
 {
     // Try to read from the write log first
     let (log_val, gas) = write_log.read_pre(key);
-    add_gas(gas_meter, gas)?;
+    add_storage_read_gas(gas_meter, key, gas)?;
     match log_val {
         Some(&write_log::StorageModification::Write { .. }) => Ok(true),
         Some(&write_log::StorageModification::Delete) => {
@@ -181,7 +194,7 @@ where
             // When not found in write log, try to check the storage
             let (present, gas) =
                 storage.has_key(key).map_err(RuntimeError::StorageError)?;
-            add_gas(gas_meter, gas)?;
+            add_storage_read_gas(gas_meter, key, gas)?;
             Ok(present)
         }
     }
@@ -201,7 +214,7 @@ where
 {
     // Try to read from the write log first
     let (log_val, gas) = write_log.read(key);
-    add_gas(gas_meter, gas)?;
+    add_storage_read_gas(gas_meter, key, gas)?;
     match log_val {
         Some(&write_log::StorageModification::Write { .. }) => Ok(true),
         Some(&write_log::StorageModification::Delete) => {
@@ -214,7 +227,7 @@ where
             // When not found in write log, try to check the storage
             let (present, gas) =
                 storage.has_key(key).map_err(RuntimeError::StorageError)?;
-            add_gas(gas_meter, gas)?;
+            add_storage_read_gas(gas_meter, key, gas)?;
             Ok(present)
         }
     }
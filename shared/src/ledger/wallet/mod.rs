@@ -12,6 +12,7 @@ use std::str::FromStr;
 use alias::Alias;
 use bip39::{Language, Mnemonic, MnemonicType, Seed};
 use borsh::{BorshDeserialize, BorshSerialize};
+use masp_primitives::sapling::ViewingKey;
 use masp_primitives::zip32::ExtendedFullViewingKey;
 pub use pre_genesis::gen_key_to_store;
 use rand_core::RngCore;
@@ -27,6 +28,7 @@ use crate::types::key::*;
 use crate::types::masp::{
     ExtendedSpendingKey, ExtendedViewingKey, PaymentAddress,
 };
+use crate::types::storage::BlockHeight;
 
 /// Errors of key generation / recovery
 #[derive(Error, Debug)]
@@ -399,6 +401,52 @@ impl<U: WalletUtils> Wallet<U> {
             .ok_or(FindKeyError::KeyNotFound)
     }
 
+    /// Find the birthday height of the spending or viewing key with the given
+    /// alias in the wallet and return it
+    pub fn find_birthday(&self, alias: impl AsRef<str>) -> Option<BlockHeight> {
+        self.store.find_birthday(alias.as_ref()).copied()
+    }
+
+    /// Set the birthday height of the spending or viewing key with the given
+    /// alias in the wallet. Notes sent to the key before this height will not
+    /// be found when scanning the shielded pool.
+    pub fn set_birthday(
+        &mut self,
+        alias: impl AsRef<str>,
+        height: BlockHeight,
+    ) -> Result<(), FindKeyError> {
+        if self.store.set_birthday(&alias.into(), height) {
+            Ok(())
+        } else {
+            Err(FindKeyError::KeyNotFound)
+        }
+    }
+
+    /// Get the birthday heights of the viewing keys of all the known spending
+    /// and viewing keys that have one. The viewing keys of spending keys are
+    /// those stored along with them, or else derived from the spending keys
+    /// that are not encrypted.
+    pub fn get_viewing_key_birthdays(
+        &self,
+    ) -> HashMap<ViewingKey, BlockHeight> {
+        self.store
+            .get_birthdays()
+            .iter()
+            .filter_map(|(alias, height)| {
+                let vk = match self.store.get_viewing_keys().get(alias) {
+                    Some(vk) => ExtendedFullViewingKey::from(*vk),
+                    None => match self.store.get_spending_keys().get(alias)? {
+                        StoredKeypair::Raw(sk) => {
+                            ExtendedFullViewingKey::from(&(*sk).into())
+                        }
+                        StoredKeypair::Encrypted(_) => return None,
+                    },
+                };
+                Some((vk.fvk.vk, *height))
+            })
+            .collect()
+    }
+
     /// Find the payment address with the given alias in the wallet and return
     /// it
     pub fn find_payment_addr(
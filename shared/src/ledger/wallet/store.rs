@@ -24,6 +24,7 @@ use crate::types::key::*;
 use crate::types::masp::{
     ExtendedSpendingKey, ExtendedViewingKey, PaymentAddress,
 };
+use crate::types::storage::BlockHeight;
 
 /// Actions that can be taken when there is an alias conflict
 pub enum ConfirmationResponse {
@@ -72,6 +73,10 @@ pub struct Store {
     spend_keys: HashMap<Alias, StoredKeypair<ExtendedSpendingKey>>,
     /// Known payment addresses
     payment_addrs: HashMap<Alias, PaymentAddress>,
+    /// Birthday heights of the known spending and viewing keys, before which
+    /// they cannot have received any notes
+    #[serde(default)]
+    birthdays: HashMap<Alias, BlockHeight>,
     /// Cryptographic keypairs
     keys: HashMap<Alias, StoredKeypair<common::SecretKey>>,
     /// Namada address book
@@ -130,6 +135,15 @@ impl Store {
         self.view_keys.get(&alias.into())
     }
 
+    /// Find the birthday height of the spending or viewing key with the given
+    /// alias and return it
+    pub fn find_birthday(
+        &self,
+        alias: impl AsRef<str>,
+    ) -> Option<&BlockHeight> {
+        self.birthdays.get(&alias.into())
+    }
+
     /// Find the payment address with the given alias and return it
     pub fn find_payment_addr(
         &self,
@@ -219,6 +233,12 @@ impl Store {
         &self.spend_keys
     }
 
+    /// Get the birthday heights of the spending and viewing keys by their
+    /// alias.
+    pub fn get_birthdays(&self) -> &HashMap<Alias, BlockHeight> {
+        &self.birthdays
+    }
+
     #[cfg(feature = "masp-tx-gen")]
     fn generate_spending_key() -> ExtendedSpendingKey {
         use rand::rngs::OsRng;
@@ -443,6 +463,18 @@ impl Store {
         Some(alias)
     }
 
+    /// Set the birthday height of the spending or viewing key with the given
+    /// alias. Returns `false` if there is no such key.
+    pub fn set_birthday(&mut self, alias: &Alias, height: BlockHeight) -> bool {
+        if !self.view_keys.contains_key(alias)
+            && !self.spend_keys.contains_key(alias)
+        {
+            return false;
+        }
+        self.birthdays.insert(alias.clone(), height);
+        true
+    }
+
     /// Check if any map of the wallet contains the given alias
     pub fn contains_alias(&self, alias: &Alias) -> bool {
         self.payment_addrs.contains_key(alias)
@@ -457,6 +489,7 @@ impl Store {
         self.payment_addrs.remove(alias);
         self.view_keys.remove(alias);
         self.spend_keys.remove(alias);
+        self.birthdays.remove(alias);
         self.keys.remove(alias);
         self.addresses.remove_by_left(alias);
         self.pkhs.retain(|_key, val| val != alias);
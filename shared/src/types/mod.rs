@@ -6,7 +6,7 @@ pub mod ibc;
 pub mod key;
 
 pub use namada_core::types::{
-    address, chain, dec, eth_abi, eth_bridge_pool, ethereum_events, hash,
-    internal, keccak, masp, storage, time, token, transaction, uint,
+    address, chain, dec, eth_abi, eth_bridge_pool, ethereum_events, event,
+    hash, internal, keccak, masp, storage, time, token, transaction, uint,
     validity_predicate, vote_extensions, voting_power,
 };
@@ -15,21 +15,27 @@ use super::wasm::TxCache;
 use super::wasm::VpCache;
 use super::WasmCacheAccess;
 use crate::ledger::gas::{self, VpGasMeter, STORAGE_ACCESS_GAS_PER_BYTE};
+use crate::ledger::native_vp::user::NATIVE_VP_USER_CODE_HASH;
 use crate::ledger::storage::write_log::{self, WriteLog};
 use crate::ledger::storage::{self, Storage, StorageHasher};
 use crate::ledger::vp_host_fns;
-use crate::proto::Tx;
+use crate::proto::{Section, Tx};
 use crate::types::address::{self, Address};
+use crate::types::event::{self, TxEvent};
 use crate::types::hash::Hash;
 use crate::types::ibc::IbcEvent;
 use crate::types::internal::HostEnvResult;
+use crate::types::keccak::keccak_hash;
+use crate::types::key::common::{self, SigHashMode};
 use crate::types::storage::{BlockHeight, Key, TxIndex};
 use crate::types::token::{
     is_any_minted_balance_key, is_any_minter_key, is_any_token_balance_key,
 };
 use crate::vm::memory::VmMemory;
 use crate::vm::prefix_iter::{PrefixIteratorId, PrefixIterators};
-use crate::vm::{HostRef, MutHostRef};
+use crate::vm::{
+    validate_untrusted_wasm, HostRef, MutHostRef, WasmValidationError,
+};
 
 /// These runtime errors will abort tx WASM execution immediately
 #[allow(missing_docs)]
@@ -59,10 +65,39 @@ pub enum TxRuntimeError {
     NumConversionError(TryFromIntError),
     #[error("Memory error: {0}")]
     MemoryError(Box<dyn std::error::Error + Sync + Send + 'static>),
+    #[error("Invalid transaction event: {0}")]
+    InvalidTxEvent(event::Error),
+    #[error(
+        "A transaction cannot emit more than {} events",
+        event::MAX_TX_EVENTS
+    )]
+    TooManyTxEvents,
+    #[error("The transaction has no code to derive the event namespace from")]
+    MissingTxEventNamespace,
+    #[error("Invalid called wasm code hash: {0}")]
+    InvalidCallCodeHash(String),
+    #[error("The maximum call depth of {MAX_TX_CALL_DEPTH} has been exceeded")]
+    CallDepthExceeded,
+    #[error("Called wasm code failed: {0}")]
+    CallError(String),
+    #[error("The called wasm code {0} is not whitelisted")]
+    CallCodeNotWhitelisted(Hash),
+    #[error(
+        "The wasm code called on behalf of {target} cannot modify the key \
+         {key}"
+    )]
+    CallStorageModificationNotAllowed { target: Address, key: Key },
+    #[error("The wasm code {0} to deploy is not whitelisted")]
+    DeployCodeNotWhitelisted(Hash),
+    #[error("Invalid deployed wasm code: {0}")]
+    InvalidDeployedCode(WasmValidationError),
 }
 
 type TxResult<T> = std::result::Result<T, TxRuntimeError>;
 
+/// The maximum depth of nested calls of wasm codes from a transaction
+pub const MAX_TX_CALL_DEPTH: u64 = 4;
+
 /// A transaction's host environment
 pub struct TxVmEnv<'a, MEM, DB, H, CA>
 where
@@ -101,6 +136,12 @@ where
     pub verifiers: MutHostRef<'a, &'a BTreeSet<Address>>,
     /// Cache for 2-step reads from host environment.
     pub result_buffer: MutHostRef<'a, &'a Option<Vec<u8>>>,
+    /// The depth of nested calls made with [`tx_call`], 0 for the
+    /// transaction itself
+    pub call_depth: u64,
+    /// The account and the code of a wasm code called with [`tx_call`],
+    /// `None` for the transaction itself
+    pub callee: Option<TxCallee>,
     /// VP WASM compilation cache (this is available in tx context, because
     /// we're pre-compiling VPs from [`tx_init_account`])
     #[cfg(feature = "wasm-runtime")]
@@ -162,6 +203,8 @@ where
             tx_index,
             verifiers,
             result_buffer,
+            call_depth: 0,
+            callee: None,
             #[cfg(feature = "wasm-runtime")]
             vp_wasm_cache,
             #[cfg(feature = "wasm-runtime")]
@@ -205,6 +248,8 @@ where
             tx_index: self.tx_index.clone(),
             verifiers: self.verifiers.clone(),
             result_buffer: self.result_buffer.clone(),
+            call_depth: self.call_depth,
+            callee: self.callee.clone(),
             #[cfg(feature = "wasm-runtime")]
             vp_wasm_cache: self.vp_wasm_cache.clone(),
             #[cfg(feature = "wasm-runtime")]
@@ -215,6 +260,17 @@ where
     }
 }
 
+/// A wasm code called with [`tx_call`] on behalf of a target account
+#[derive(Clone, Debug)]
+pub struct TxCallee {
+    /// The account on behalf of which the code runs. The called code can
+    /// only modify the storage of this account, whose validity predicate
+    /// then checks the modifications.
+    pub target: Address,
+    /// The hash of the called code
+    pub code_hash: Hash,
+}
+
 /// A validity predicate's host environment
 pub struct VpVmEnv<'a, MEM, DB, H, EVAL, CA>
 where
@@ -504,6 +560,107 @@ where
     vp_host_fns::add_gas(gas_meter, used_gas)
 }
 
+/// Charge the gas of reading the given storage key and record it in the gas
+/// profile, if profiling is enabled.
+fn tx_charge_storage_read_gas<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    key: &Key,
+    gas: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    if let Some(profile) = gas_meter.gas_profile() {
+        profile.record_storage_read(key, gas);
+    }
+    tx_charge_gas(env, gas)
+}
+
+/// Charge the gas of writing or deleting the given storage key and record it
+/// in the gas profile, if profiling is enabled.
+fn tx_charge_storage_write_gas<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    key: &Key,
+    gas: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    if let Some(profile) = gas_meter.gas_profile() {
+        profile.record_storage_write(key, gas);
+    }
+    tx_charge_gas(env, gas)
+}
+
+/// Call a host function and record the gas it consumed in the gas profile, if
+/// profiling is enabled.
+pub fn tx_profile_host_fn<MEM, DB, H, CA, T>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    name: &str,
+    host_fn: impl FnOnce() -> T,
+) -> T
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    if gas_meter.gas_profile().is_none() {
+        return host_fn();
+    }
+    let gas_before = gas_meter.get_tx_consumed_gas();
+    let result = host_fn();
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let gas = gas_meter
+        .get_tx_consumed_gas()
+        .checked_sub(gas_before)
+        .unwrap_or_default();
+    if let Some(profile) = gas_meter.gas_profile() {
+        profile.record_host_fn(name, gas);
+    }
+    result
+}
+
+/// Call a host function and record the gas it consumed in the gas profile of
+/// the VP, if profiling is enabled.
+pub fn vp_profile_host_fn<MEM, DB, H, EVAL, CA, T>(
+    env: &VpVmEnv<MEM, DB, H, EVAL, CA>,
+    name: &str,
+    host_fn: impl FnOnce() -> T,
+) -> T
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    if gas_meter.gas_profile().is_none() {
+        return host_fn();
+    }
+    let gas_before = gas_meter.get_vp_consumed_gas();
+    let result = host_fn();
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let gas = gas_meter
+        .get_vp_consumed_gas()
+        .checked_sub(gas_before)
+        .unwrap_or_default();
+    if let Some(profile) = gas_meter.gas_profile() {
+        profile.record_host_fn(name, gas);
+    }
+    result
+}
+
 /// Storage `has_key` function exposed to the wasm VM Tx environment. It will
 /// try to check the write log first and if no entry found then the storage.
 pub fn tx_has_key<MEM, DB, H, CA>(
@@ -530,7 +687,7 @@ where
     // try to read from the write log first
     let write_log = unsafe { env.ctx.write_log.get() };
     let (log_val, gas) = write_log.read(&key);
-    tx_charge_gas(env, gas)?;
+    tx_charge_storage_read_gas(env, &key, gas)?;
     Ok(match log_val {
         Some(&write_log::StorageModification::Write { .. }) => {
             HostEnvResult::Success.to_i64()
@@ -551,7 +708,7 @@ where
             let (present, gas) = storage
                 .has_key(&key)
                 .map_err(TxRuntimeError::StorageError)?;
-            tx_charge_gas(env, gas)?;
+            tx_charge_storage_read_gas(env, &key, gas)?;
             HostEnvResult::from(present).to_i64()
         }
     })
@@ -586,7 +743,7 @@ where
     // try to read from the write log first
     let write_log = unsafe { env.ctx.write_log.get() };
     let (log_val, gas) = write_log.read(&key);
-    tx_charge_gas(env, gas)?;
+    tx_charge_storage_read_gas(env, &key, gas)?;
     Ok(match log_val {
         Some(write_log::StorageModification::Write { ref value }) => {
             let len: i64 = value
@@ -627,7 +784,7 @@ where
             let storage = unsafe { env.ctx.storage.get() };
             let (value, gas) =
                 storage.read(&key).map_err(TxRuntimeError::StorageError)?;
-            tx_charge_gas(env, gas)?;
+            tx_charge_storage_read_gas(env, &key, gas)?;
             match value {
                 Some(value) => {
                     let len: i64 = value
@@ -821,13 +978,14 @@ where
         tx_validate_vp_code_hash(env, &value)?;
     }
 
+    check_callee_storage_modification(env, &key)?;
     check_address_existence(env, &key)?;
 
     let write_log = unsafe { env.ctx.write_log.get() };
     let (gas, _size_diff) = write_log
         .write(&key, value)
         .map_err(TxRuntimeError::StorageModificationError)?;
-    tx_charge_gas(env, gas)
+    tx_charge_storage_write_gas(env, &key, gas)
 }
 
 /// Temporary storage write function exposed to the wasm VM Tx environment. The
@@ -861,13 +1019,45 @@ where
 
     let key = Key::parse(key).map_err(TxRuntimeError::StorageDataError)?;
 
+    check_callee_storage_modification(env, &key)?;
     check_address_existence(env, &key)?;
 
     let write_log = unsafe { env.ctx.write_log.get() };
     let (gas, _size_diff) = write_log
         .write_temp(&key, value)
         .map_err(TxRuntimeError::StorageModificationError)?;
-    tx_charge_gas(env, gas)
+    tx_charge_storage_write_gas(env, &key, gas)
+}
+
+/// When running a wasm code called with [`tx_call`], check that the given
+/// key belongs to the target account of the call, i.e. that it is a key of
+/// the account's storage or a token balance of the account. The target
+/// account's VP is then the one that validates the modification.
+fn check_callee_storage_modification<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    key: &Key,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let Some(TxCallee { target, .. }) = &env.ctx.callee else {
+        return Ok(());
+    };
+    let owner = match is_any_token_balance_key(key) {
+        Some([_token, owner]) => Some(owner),
+        None => key.fst_address(),
+    };
+    if owner == Some(target) {
+        Ok(())
+    } else {
+        Err(TxRuntimeError::CallStorageModificationNotAllowed {
+            target: target.clone(),
+            key: key.clone(),
+        })
+    }
 }
 
 fn check_address_existence<MEM, DB, H, CA>(
@@ -949,11 +1139,13 @@ where
         return Err(TxRuntimeError::CannotDeleteVp);
     }
 
+    check_callee_storage_modification(env, &key)?;
+
     let write_log = unsafe { env.ctx.write_log.get() };
     let (gas, _size_diff) = write_log
         .delete(&key)
         .map_err(TxRuntimeError::StorageModificationError)?;
-    tx_charge_gas(env, gas)
+    tx_charge_storage_write_gas(env, &key, gas)
 }
 
 /// Emitting an IBC event function exposed to the wasm VM Tx environment.
@@ -981,6 +1173,49 @@ where
     tx_charge_gas(env, gas)
 }
 
+/// Emitting a custom event function exposed to the wasm VM Tx environment.
+/// The namespace of the event is set to the hash of the code of the tx, or of
+/// the code called with [`tx_call`], so that a tx cannot emit events under the
+/// namespace of another application.
+/// The event is validated before it is set to the write log.
+pub fn tx_emit_event<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    event_ptr: u64,
+    event_len: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let write_log = unsafe { env.ctx.write_log.get() };
+    if write_log.get_tx_events().len() >= event::MAX_TX_EVENTS {
+        return Err(TxRuntimeError::TooManyTxEvents);
+    }
+    let (event, gas) = env
+        .memory
+        .read_bytes(event_ptr, event_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+    let event: TxEvent = BorshDeserialize::try_from_slice(&event)
+        .map_err(TxRuntimeError::EncodingError)?;
+    let code_hash = match &env.ctx.callee {
+        Some(callee) => callee.code_hash,
+        None => {
+            let tx = unsafe { env.ctx.tx.get() };
+            tx.get_section(tx.code_sechash())
+                .and_then(|x| Section::code_sec(x.as_ref()))
+                .map(|x| x.code.hash())
+                .ok_or(TxRuntimeError::MissingTxEventNamespace)?
+        }
+    };
+    let event = event.with_namespace(code_hash);
+    event.validate().map_err(TxRuntimeError::InvalidTxEvent)?;
+    let gas = write_log.emit_tx_event(event);
+    tx_charge_gas(env, gas)
+}
+
 /// Getting an IBC event function exposed to the wasm VM Tx environment.
 pub fn tx_get_ibc_event<MEM, DB, H, CA>(
     env: &TxVmEnv<MEM, DB, H, CA>,
@@ -1416,6 +1651,7 @@ where
     tracing::debug!("tx_update_validity_predicate for addr {}", addr);
 
     let key = Key::validity_predicate(&addr);
+    check_callee_storage_modification(env, &key)?;
     let (code_hash, gas) = env
         .memory
         .read_bytes(code_hash_ptr, code_hash_len as _)
@@ -1469,6 +1705,205 @@ where
     tx_charge_gas(env, gas)
 }
 
+/// Call the entrypoint of a stored wasm code on behalf of the given target
+/// account with the given input. The called code runs in the same write log
+/// and gas meter as the caller, but it doesn't share the caller's authority:
+/// it can only modify the storage of the target account, which is added to
+/// the verifiers so that its VP validates these modifications. Returns the
+/// length of the output set by the called code, which is then available in
+/// the result buffer, or `HostEnvResult::Fail` if it didn't set any output.
+#[cfg(feature = "wasm-runtime")]
+#[allow(clippy::too_many_arguments)]
+pub fn tx_call<MEM, DB, H, CA>(
+    env: &TxVmEnv<'static, MEM, DB, H, CA>,
+    target_ptr: u64,
+    target_len: u64,
+    code_hash_ptr: u64,
+    code_hash_len: u64,
+    input_ptr: u64,
+    input_len: u64,
+) -> TxResult<i64>
+where
+    MEM: VmMemory,
+    DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    let (target, gas) = env
+        .memory
+        .read_string(target_ptr, target_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+    let target =
+        Address::decode(target).map_err(TxRuntimeError::AddressError)?;
+    let (code_hash, gas) = env
+        .memory
+        .read_bytes(code_hash_ptr, code_hash_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+    let (input, gas) = env
+        .memory
+        .read_bytes(input_ptr, input_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+
+    let code_hash = Hash::try_from(&code_hash[..])
+        .map_err(|e| TxRuntimeError::InvalidCallCodeHash(e.to_string()))?;
+    if env.ctx.call_depth >= MAX_TX_CALL_DEPTH {
+        return Err(TxRuntimeError::CallDepthExceeded);
+    }
+    if !tx_is_code_whitelisted(env, &code_hash)? {
+        return Err(TxRuntimeError::CallCodeNotWhitelisted(code_hash));
+    }
+    tracing::debug!(
+        "tx_call {} on behalf of {}, depth {}",
+        code_hash,
+        target,
+        env.ctx.call_depth
+    );
+
+    let verifiers = unsafe { env.ctx.verifiers.get() };
+    verifiers.insert(target.clone());
+    let result_buffer = unsafe { env.ctx.result_buffer.get() };
+    result_buffer.take();
+    let ctx = TxCtx {
+        call_depth: env.ctx.call_depth + 1,
+        callee: Some(TxCallee { target, code_hash }),
+        ..env.ctx.clone()
+    };
+    super::wasm::run::tx_call(ctx, &code_hash, &input)
+        .map_err(|e| TxRuntimeError::CallError(e.to_string()))?;
+
+    Ok(match result_buffer.as_ref() {
+        Some(output) => output
+            .len()
+            .try_into()
+            .map_err(TxRuntimeError::NumConversionError)?,
+        None => HostEnvResult::Fail.to_i64(),
+    })
+}
+
+/// Check that the given wasm code hash is in the transactions whitelist. An
+/// empty whitelist allows any code.
+#[cfg(feature = "wasm-runtime")]
+fn tx_is_code_whitelisted<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    code_hash: &Hash,
+) -> TxResult<bool>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let whitelist = tx_read_tx_whitelist(env)?;
+    Ok(whitelist.is_empty()
+        || whitelist.contains(&code_hash.to_string().to_lowercase()))
+}
+
+/// Read the transactions whitelist, which is controlled by governance. The
+/// whitelist is read from the storage, so that it cannot be modified by the
+/// running transaction.
+fn tx_read_tx_whitelist<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+) -> TxResult<Vec<String>>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let key =
+        crate::ledger::parameters::storage::get_tx_whitelist_storage_key();
+    let storage = unsafe { env.ctx.storage.get() };
+    let (value, gas) =
+        storage.read(&key).map_err(TxRuntimeError::StorageError)?;
+    tx_charge_storage_read_gas(env, &key, gas)?;
+    match value {
+        Some(value) => BorshDeserialize::try_from_slice(&value)
+            .map_err(TxRuntimeError::EncodingError),
+        None => Ok(vec![]),
+    }
+}
+
+/// Deploy a wasm code that can then be called with [`tx_call`]. The storage of
+/// wasm codes is controlled by governance, so a code can only be deployed if
+/// its hash is in the transactions whitelist. Unlike for the execution of
+/// codes, an empty whitelist doesn't allow any code. The code is validated
+/// and stored under its hash, which is written to the given result pointer.
+pub fn tx_deploy_code<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    code_ptr: u64,
+    code_len: u64,
+    result_ptr: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (code, gas) = env
+        .memory
+        .read_bytes(code_ptr, code_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+    tx_charge_gas(env, HashFunction::Sha256.gas_cost(code_len))?;
+    let code_hash = Hash::sha256(&code);
+    let whitelist = tx_read_tx_whitelist(env)?;
+    if !whitelist.contains(&code_hash.to_string().to_lowercase()) {
+        return Err(TxRuntimeError::DeployCodeNotWhitelisted(code_hash));
+    }
+    validate_untrusted_wasm(&code)
+        .map_err(TxRuntimeError::InvalidDeployedCode)?;
+
+    tracing::debug!("tx_deploy_code {}", code_hash);
+
+    let write_log = unsafe { env.ctx.write_log.get() };
+    let len_key = Key::wasm_code_len(&code_hash);
+    let code_len = code_len
+        .try_to_vec()
+        .map_err(TxRuntimeError::EncodingError)?;
+    let (gas, _size_diff) = write_log
+        .write(&len_key, code_len)
+        .map_err(TxRuntimeError::StorageModificationError)?;
+    tx_charge_storage_write_gas(env, &len_key, gas)?;
+    let code_key = Key::wasm_code(&code_hash);
+    let (gas, _size_diff) = write_log
+        .write(&code_key, code)
+        .map_err(TxRuntimeError::StorageModificationError)?;
+    tx_charge_storage_write_gas(env, &code_key, gas)?;
+
+    let gas = env
+        .memory
+        .write_bytes(result_ptr, code_hash.0)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)
+}
+
+/// Set the output of a wasm code called with [`tx_call`]. The output is
+/// returned to the caller via the result buffer.
+pub fn tx_set_call_output<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    output_ptr: u64,
+    output_len: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (output, gas) = env
+        .memory
+        .read_bytes(output_ptr, output_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+    let result_buffer = unsafe { env.ctx.result_buffer.get() };
+    result_buffer.replace(output);
+    Ok(())
+}
+
 /// Getting the chain ID function exposed to the wasm VM Tx environment.
 pub fn tx_get_chain_id<MEM, DB, H, CA>(
     env: &TxVmEnv<MEM, DB, H, CA>,
@@ -1921,6 +2356,10 @@ where
 {
     let hash = Hash::try_from(code_hash)
         .map_err(|e| TxRuntimeError::InvalidVpCodeHash(e.to_string()))?;
+    // The native user VP has no code in storage
+    if hash == NATIVE_VP_USER_CODE_HASH {
+        return Ok(());
+    }
     let key = Key::wasm_code(&hash);
     let write_log = unsafe { env.ctx.write_log.get() };
     let (result, gas) = write_log.read(&key);
@@ -2055,6 +2494,352 @@ where
     Ok(())
 }
 
+/// Base gas cost of a hash computation
+const HASH_BASE_GAS: u64 = 10;
+/// Gas cost per byte of a sha256 hash computation
+const SHA256_GAS_PER_BYTE: u64 = 2;
+/// Gas cost per byte of a keccak256 hash computation
+const KECCAK256_GAS_PER_BYTE: u64 = 2;
+/// Gas cost per byte of a blake2b hash computation
+const BLAKE2B_GAS_PER_BYTE: u64 = 1;
+/// Gas cost of an ed25519 signature verification, on top of the hashing of
+/// the message
+const ED25519_VERIFY_GAS: u64 = 10;
+/// Gas cost of a secp256k1 signature verification, on top of the hashing of
+/// the message
+const SECP256K1_VERIFY_GAS: u64 = 30;
+
+/// Hash functions exposed to the wasm VM
+#[derive(Clone, Copy, Debug)]
+enum HashFunction {
+    Sha256,
+    Keccak256,
+    Blake2b,
+}
+
+impl HashFunction {
+    /// The gas cost of hashing the given number of bytes
+    fn gas_cost(self, len: u64) -> u64 {
+        let per_byte = match self {
+            HashFunction::Sha256 => SHA256_GAS_PER_BYTE,
+            HashFunction::Keccak256 => KECCAK256_GAS_PER_BYTE,
+            HashFunction::Blake2b => BLAKE2B_GAS_PER_BYTE,
+        };
+        HASH_BASE_GAS.saturating_add(len.saturating_mul(per_byte))
+    }
+
+    /// Compute the 32 bytes digest of the data
+    fn digest(self, data: &[u8]) -> [u8; 32] {
+        match self {
+            HashFunction::Sha256 => Hash::sha256(data).0,
+            HashFunction::Keccak256 => keccak_hash(data).0,
+            HashFunction::Blake2b => {
+                let mut digest = [0u8; 32];
+                digest.copy_from_slice(
+                    blake2b_simd::Params::new()
+                        .hash_length(32)
+                        .hash(data)
+                        .as_bytes(),
+                );
+                digest
+            }
+        }
+    }
+}
+
+/// The gas cost of verifying a signature made with the given public key on a
+/// message of the given length, hashed according to the given mode
+fn verify_signature_gas_cost(
+    pk: &common::PublicKey,
+    msg_len: u64,
+    mode: SigHashMode,
+) -> u64 {
+    let verify_gas = match pk {
+        common::PublicKey::Ed25519(_) => ED25519_VERIFY_GAS,
+        common::PublicKey::Secp256k1(_) => SECP256K1_VERIFY_GAS,
+    };
+    let hash_gas = match mode {
+        // A raw ed25519 message is hashed by the signature scheme itself
+        SigHashMode::Raw | SigHashMode::Sha256 => {
+            HashFunction::Sha256.gas_cost(msg_len)
+        }
+        SigHashMode::Keccak256 => HashFunction::Keccak256.gas_cost(msg_len),
+    };
+    verify_gas.saturating_add(hash_gas)
+}
+
+/// Hash some data from the wasm VM Tx environment and write the digest to
+/// the result pointer
+fn tx_hash<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    hash_fn: HashFunction,
+    data_ptr: u64,
+    data_len: u64,
+    result_ptr: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (data, gas) = env
+        .memory
+        .read_bytes(data_ptr, data_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+    tx_charge_gas(env, hash_fn.gas_cost(data_len))?;
+    let gas = env
+        .memory
+        .write_bytes(result_ptr, hash_fn.digest(&data))
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)
+}
+
+/// Hash some data from the wasm VM VP environment and write the digest to
+/// the result pointer
+fn vp_hash<MEM, DB, H, EVAL, CA>(
+    env: &VpVmEnv<MEM, DB, H, EVAL, CA>,
+    hash_fn: HashFunction,
+    data_ptr: u64,
+    data_len: u64,
+    result_ptr: u64,
+) -> vp_host_fns::EnvResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let (data, gas) = env
+        .memory
+        .read_bytes(data_ptr, data_len as _)
+        .map_err(|e| vp_host_fns::RuntimeError::MemoryError(Box::new(e)))?;
+    vp_host_fns::add_gas(gas_meter, gas)?;
+    vp_host_fns::add_gas(gas_meter, hash_fn.gas_cost(data_len))?;
+    let gas = env
+        .memory
+        .write_bytes(result_ptr, hash_fn.digest(&data))
+        .map_err(|e| vp_host_fns::RuntimeError::MemoryError(Box::new(e)))?;
+    vp_host_fns::add_gas(gas_meter, gas)
+}
+
+/// Sha256 hash function exposed to the wasm VM Tx environment
+pub fn tx_sha256<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    data_ptr: u64,
+    data_len: u64,
+    result_ptr: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    tx_hash(env, HashFunction::Sha256, data_ptr, data_len, result_ptr)
+}
+
+/// Keccak256 hash function exposed to the wasm VM Tx environment
+pub fn tx_keccak256<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    data_ptr: u64,
+    data_len: u64,
+    result_ptr: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    tx_hash(env, HashFunction::Keccak256, data_ptr, data_len, result_ptr)
+}
+
+/// Blake2b (with a 32 bytes digest) hash function exposed to the wasm VM Tx
+/// environment
+pub fn tx_blake2b<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    data_ptr: u64,
+    data_len: u64,
+    result_ptr: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    tx_hash(env, HashFunction::Blake2b, data_ptr, data_len, result_ptr)
+}
+
+/// Sha256 hash function exposed to the wasm VM VP environment
+pub fn vp_sha256<MEM, DB, H, EVAL, CA>(
+    env: &VpVmEnv<MEM, DB, H, EVAL, CA>,
+    data_ptr: u64,
+    data_len: u64,
+    result_ptr: u64,
+) -> vp_host_fns::EnvResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    vp_hash(env, HashFunction::Sha256, data_ptr, data_len, result_ptr)
+}
+
+/// Keccak256 hash function exposed to the wasm VM VP environment
+pub fn vp_keccak256<MEM, DB, H, EVAL, CA>(
+    env: &VpVmEnv<MEM, DB, H, EVAL, CA>,
+    data_ptr: u64,
+    data_len: u64,
+    result_ptr: u64,
+) -> vp_host_fns::EnvResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    vp_hash(env, HashFunction::Keccak256, data_ptr, data_len, result_ptr)
+}
+
+/// Blake2b (with a 32 bytes digest) hash function exposed to the wasm VM VP
+/// environment
+pub fn vp_blake2b<MEM, DB, H, EVAL, CA>(
+    env: &VpVmEnv<MEM, DB, H, EVAL, CA>,
+    data_ptr: u64,
+    data_len: u64,
+    result_ptr: u64,
+) -> vp_host_fns::EnvResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    vp_hash(env, HashFunction::Blake2b, data_ptr, data_len, result_ptr)
+}
+
+/// Verify a signature of an arbitrary message, exposed to the wasm VM Tx
+/// environment. The public key and the signature are borsh encoded
+/// [`common::PublicKey`] and [`common::Signature`], so both ed25519 and
+/// secp256k1 are supported. The message is hashed according to the
+/// [`SigHashMode`] encoded in `hash_mode` before it gets verified. An unknown
+/// mode fails the verification.
+#[allow(clippy::too_many_arguments)]
+pub fn tx_verify_signature<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    pk_ptr: u64,
+    pk_len: u64,
+    sig_ptr: u64,
+    sig_len: u64,
+    msg_ptr: u64,
+    msg_len: u64,
+    hash_mode: u64,
+) -> TxResult<i64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (pk, gas) = env
+        .memory
+        .read_bytes(pk_ptr, pk_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+    let pk = common::PublicKey::try_from_slice(&pk)
+        .map_err(TxRuntimeError::EncodingError)?;
+
+    let (sig, gas) = env
+        .memory
+        .read_bytes(sig_ptr, sig_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+    let sig = common::Signature::try_from_slice(&sig)
+        .map_err(TxRuntimeError::EncodingError)?;
+
+    let (msg, gas) = env
+        .memory
+        .read_bytes(msg_ptr, msg_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+
+    let Ok(mode) = SigHashMode::try_from(hash_mode) else {
+        return Ok(HostEnvResult::Fail.to_i64());
+    };
+    tx_charge_gas(env, verify_signature_gas_cost(&pk, msg_len, mode))?;
+    Ok(HostEnvResult::from(
+        common::verify_signature_with_mode(&pk, &msg, &sig, mode).is_ok(),
+    )
+    .to_i64())
+}
+
+/// Verify a signature of an arbitrary message, exposed to the wasm VM VP
+/// environment. See [`tx_verify_signature`] for the encoding of the
+/// arguments.
+#[allow(clippy::too_many_arguments)]
+pub fn vp_verify_signature<MEM, DB, H, EVAL, CA>(
+    env: &VpVmEnv<MEM, DB, H, EVAL, CA>,
+    pk_ptr: u64,
+    pk_len: u64,
+    sig_ptr: u64,
+    sig_len: u64,
+    msg_ptr: u64,
+    msg_len: u64,
+    hash_mode: u64,
+) -> vp_host_fns::EnvResult<i64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+
+    let (pk, gas) = env
+        .memory
+        .read_bytes(pk_ptr, pk_len as _)
+        .map_err(|e| vp_host_fns::RuntimeError::MemoryError(Box::new(e)))?;
+    vp_host_fns::add_gas(gas_meter, gas)?;
+    let pk = common::PublicKey::try_from_slice(&pk)
+        .map_err(vp_host_fns::RuntimeError::EncodingError)?;
+
+    let (sig, gas) = env
+        .memory
+        .read_bytes(sig_ptr, sig_len as _)
+        .map_err(|e| vp_host_fns::RuntimeError::MemoryError(Box::new(e)))?;
+    vp_host_fns::add_gas(gas_meter, gas)?;
+    let sig = common::Signature::try_from_slice(&sig)
+        .map_err(vp_host_fns::RuntimeError::EncodingError)?;
+
+    let (msg, gas) = env
+        .memory
+        .read_bytes(msg_ptr, msg_len as _)
+        .map_err(|e| vp_host_fns::RuntimeError::MemoryError(Box::new(e)))?;
+    vp_host_fns::add_gas(gas_meter, gas)?;
+
+    let Ok(mode) = SigHashMode::try_from(hash_mode) else {
+        return Ok(HostEnvResult::Fail.to_i64());
+    };
+    vp_host_fns::add_gas(
+        gas_meter,
+        verify_signature_gas_cost(&pk, msg_len, mode),
+    )?;
+    Ok(HostEnvResult::from(
+        common::verify_signature_with_mode(&pk, &msg, &sig, mode).is_ok(),
+    )
+    .to_i64())
+}
+
 /// A helper module for testing
 #[cfg(feature = "testing")]
 pub mod testing {
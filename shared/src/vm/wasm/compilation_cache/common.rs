@@ -2,7 +2,8 @@
 //! The cache is backed by in-memory LRU cache with configurable size
 //! limit and a file system cache of compiled modules (either to dynamic libs
 //! compiled via the `dylib` module, or serialized modules compiled via the
-//! `universal` module).
+//! `universal` module). The compiled modules on disk are invalidated when the
+//! wasmer version or the host's CPU features change.
 
 use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
@@ -91,6 +92,13 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
         let dir = dir.into();
         fs::create_dir_all(&dir)
             .expect("Couldn't create the wasm cache directory");
+        if let Err(err) = invalidate_stale_artifacts(&dir) {
+            tracing::error!(
+                "Failed to invalidate the stale WASM compilation cache in {}: \
+                 {err}",
+                dir.to_string_lossy()
+            );
+        }
         Self {
             dir,
             progress: Default::default(),
@@ -460,7 +468,66 @@ fn hash_of_code(code: impl AsRef<[u8]>) -> Hash {
 }
 
 fn hash_to_store_dir(hash: &Hash) -> PathBuf {
-    PathBuf::from("vp_wasm_cache").join(hash.to_string().to_lowercase())
+    PathBuf::from(ARTIFACTS_DIR).join(hash.to_string().to_lowercase())
+}
+
+/// The sub-directory of the cache directory with the compiled modules
+const ARTIFACTS_DIR: &str = "vp_wasm_cache";
+
+/// The file in the cache directory that records the [`artifacts_version`]
+/// with which the compiled modules on disk were produced
+const ARTIFACTS_VERSION_FILE: &str = "artifacts_version";
+
+/// The version of compiled modules produced by this build. Serialized modules
+/// are only loadable by the same wasmer version and they may use CPU
+/// features that are not available on a different host, so both are part of
+/// the version.
+fn artifacts_version() -> String {
+    let cpu_features = wasmer::CpuFeature::for_host()
+        .iter()
+        .map(|feature| feature.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "wasmer-{}/{}/{}/{}",
+        wasmer::VERSION,
+        wasmer::Triple::host(),
+        file_ext(),
+        cpu_features
+    )
+}
+
+/// Remove the compiled modules on disk if they were produced with a different
+/// [`artifacts_version`] than the current one, so that they get re-compiled
+/// instead of being loaded.
+fn invalidate_stale_artifacts(dir: impl AsRef<Path>) -> std::io::Result<()> {
+    let dir = dir.as_ref();
+    let version_file = dir.join(ARTIFACTS_VERSION_FILE);
+    let current = artifacts_version();
+    let artifacts = dir.join(ARTIFACTS_DIR);
+    match fs::read_to_string(&version_file) {
+        Ok(version) if version == current => return Ok(()),
+        Ok(version) => {
+            tracing::info!(
+                "Invalidating WASM compilation cache in {} built for \
+                 {version}, current is {current}.",
+                dir.to_string_lossy()
+            );
+        }
+        // Artifacts written before versioning was introduced can't be
+        // trusted either
+        Err(_) if artifacts.exists() => {
+            tracing::info!(
+                "Invalidating unversioned WASM compilation cache in {}.",
+                dir.to_string_lossy()
+            );
+        }
+        Err(_) => {}
+    }
+    if artifacts.exists() {
+        fs::remove_dir_all(&artifacts)?;
+    }
+    fs::write(&version_file, current)
 }
 
 fn compile(
@@ -1018,6 +1085,53 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_stale_artifacts_invalidated() {
+        let vp_always_true = load_wasm(TestWasms::VpAlwaysTrue.path());
+        let dir = tempdir().unwrap();
+
+        // Compile a module to a file
+        {
+            let mut cache: Cache<TestCache, WasmCacheRwAccess> =
+                Cache::new(dir.path(), vp_always_true.size + 1);
+            cache.compile_or_fetch(&vp_always_true.code).unwrap();
+            assert!(
+                module_file_exists(&cache.dir, &vp_always_true.hash),
+                "The file must be written"
+            );
+        }
+
+        // Re-opening the cache with the same artifacts version keeps the file
+        {
+            let cache: Cache<TestCache, WasmCacheRwAccess> =
+                Cache::new(dir.path(), vp_always_true.size + 1);
+            assert!(
+                module_file_exists(&cache.dir, &vp_always_true.hash),
+                "The file must be kept"
+            );
+        }
+
+        // Re-opening the cache with a different artifacts version removes it
+        {
+            fs::write(
+                dir.path().join(ARTIFACTS_VERSION_FILE),
+                "wasmer-0.0.0/unknown/bin/",
+            )
+            .unwrap();
+            let cache: Cache<TestCache, WasmCacheRwAccess> =
+                Cache::new(dir.path(), vp_always_true.size + 1);
+            assert!(
+                !module_file_exists(&cache.dir, &vp_always_true.hash),
+                "The stale file must be removed"
+            );
+            assert_eq!(
+                fs::read_to_string(dir.path().join(ARTIFACTS_VERSION_FILE))
+                    .unwrap(),
+                artifacts_version()
+            );
+        }
+    }
+
     /// Get the WASM code bytes, its hash and find the compiled module's size
     fn load_wasm(file: impl AsRef<Path>) -> WasmWithMeta {
         // When `WeightScale` calls `loupe::size_of_val` in the cache, for some
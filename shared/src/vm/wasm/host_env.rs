@@ -3,6 +3,7 @@
 //! Here, we expose the host functions into wasm's
 //! imports, so they can be called from inside the wasm.
 
+use namada_core::ledger::gas::GasMetering;
 use wasmer::{
     Function, HostEnvInitError, ImportObject, Instance, Memory, Store,
     WasmerEnv,
@@ -43,6 +44,48 @@ where
     }
 }
 
+/// Wrap a tx host function to be exposed to the vm guest. When profiling is
+/// enabled, the gas consumed by the host function is recorded in the gas
+/// profile, otherwise the host function is exposed as is.
+macro_rules! tx_host_fn {
+    ($store:expr, $env:expr, $profile:expr, $name:literal, $fn:ident ( $($arg:ident : $type:ty),* )) => {
+        if $profile {
+            Function::new_native_with_env(
+                $store,
+                $env.clone(),
+                |env: &TxVmEnv<'static, WasmMemory, DB, H, CA>, $($arg: $type),*| {
+                    host_env::tx_profile_host_fn(env, $name, || {
+                        host_env::$fn(env, $($arg),*)
+                    })
+                },
+            )
+        } else {
+            Function::new_native_with_env($store, $env.clone(), host_env::$fn)
+        }
+    };
+}
+
+/// Wrap a VP host function to be exposed to the vm guest. When profiling is
+/// enabled, the gas consumed by the host function is recorded in the gas
+/// profile, otherwise the host function is exposed as is.
+macro_rules! vp_host_fn {
+    ($store:expr, $env:expr, $profile:expr, $name:literal, $fn:ident ( $($arg:ident : $type:ty),* )) => {
+        if $profile {
+            Function::new_native_with_env(
+                $store,
+                $env.clone(),
+                |env: &VpVmEnv<'static, WasmMemory, DB, H, EVAL, CA>, $($arg: $type),*| {
+                    host_env::vp_profile_host_fn(env, $name, || {
+                        host_env::$fn(env, $($arg),*)
+                    })
+                },
+            )
+        } else {
+            Function::new_native_with_env($store, $env.clone(), host_env::$fn)
+        }
+    };
+}
+
 /// Prepare imports (memory and host functions) exposed to the vm guest running
 /// transaction code
 #[allow(clippy::too_many_arguments)]
@@ -52,39 +95,49 @@ pub fn tx_imports<DB, H, CA>(
     env: TxVmEnv<'static, WasmMemory, DB, H, CA>,
 ) -> ImportObject
 where
-    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
-    H: StorageHasher,
-    CA: WasmCacheAccess,
+    DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
 {
+    // Only instrument the host functions when a gas profile is requested
+    let profile = unsafe { env.ctx.gas_meter.get() }.gas_profile().is_some();
     wasmer::imports! {
         // default namespace
         "env" => {
             "memory" => initial_memory,
             // Wasm middleware gas injectiong hook
-            "gas" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_charge_gas),
+            "gas" => tx_host_fn!(wasm_store, env, profile, "gas", tx_charge_gas(used_gas: u64)),
             // Whitelisted gas exposed function, we need two different functions just because of colliding names in the vm_host_env macro to generate implementations
-            "namada_tx_charge_gas" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_charge_gas),
-            "namada_tx_read" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_read),
-            "namada_tx_result_buffer" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_result_buffer),
-            "namada_tx_has_key" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_has_key),
-            "namada_tx_write" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_write),
-            "namada_tx_write_temp" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_write_temp),
-            "namada_tx_delete" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_delete),
-            "namada_tx_iter_prefix" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_iter_prefix),
-            "namada_tx_iter_next" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_iter_next),
-            "namada_tx_insert_verifier" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_insert_verifier),
-            "namada_tx_update_validity_predicate" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_update_validity_predicate),
-            "namada_tx_init_account" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_init_account),
-            "namada_tx_emit_ibc_event" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_emit_ibc_event),
-            "namada_tx_get_ibc_event" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_ibc_event),
-            "namada_tx_get_chain_id" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_chain_id),
-            "namada_tx_get_tx_index" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_tx_index),
-            "namada_tx_get_block_height" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_block_height),
-            "namada_tx_get_block_header" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_block_header),
-            "namada_tx_get_block_hash" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_block_hash),
-            "namada_tx_get_block_epoch" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_block_epoch),
-            "namada_tx_get_native_token" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_native_token),
-            "namada_tx_log_string" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_log_string),
+            "namada_tx_charge_gas" => tx_host_fn!(wasm_store, env, profile, "namada_tx_charge_gas", tx_charge_gas(used_gas: u64)),
+            "namada_tx_read" => tx_host_fn!(wasm_store, env, profile, "namada_tx_read", tx_read(key_ptr: u64, key_len: u64)),
+            "namada_tx_result_buffer" => tx_host_fn!(wasm_store, env, profile, "namada_tx_result_buffer", tx_result_buffer(result_ptr: u64)),
+            "namada_tx_has_key" => tx_host_fn!(wasm_store, env, profile, "namada_tx_has_key", tx_has_key(key_ptr: u64, key_len: u64)),
+            "namada_tx_write" => tx_host_fn!(wasm_store, env, profile, "namada_tx_write", tx_write(key_ptr: u64, key_len: u64, val_ptr: u64, val_len: u64)),
+            "namada_tx_write_temp" => tx_host_fn!(wasm_store, env, profile, "namada_tx_write_temp", tx_write_temp(key_ptr: u64, key_len: u64, val_ptr: u64, val_len: u64)),
+            "namada_tx_delete" => tx_host_fn!(wasm_store, env, profile, "namada_tx_delete", tx_delete(key_ptr: u64, key_len: u64)),
+            "namada_tx_iter_prefix" => tx_host_fn!(wasm_store, env, profile, "namada_tx_iter_prefix", tx_iter_prefix(prefix_ptr: u64, prefix_len: u64)),
+            "namada_tx_iter_next" => tx_host_fn!(wasm_store, env, profile, "namada_tx_iter_next", tx_iter_next(iter_id: u64)),
+            "namada_tx_insert_verifier" => tx_host_fn!(wasm_store, env, profile, "namada_tx_insert_verifier", tx_insert_verifier(addr_ptr: u64, addr_len: u64)),
+            "namada_tx_update_validity_predicate" => tx_host_fn!(wasm_store, env, profile, "namada_tx_update_validity_predicate", tx_update_validity_predicate(addr_ptr: u64, addr_len: u64, code_hash_ptr: u64, code_hash_len: u64)),
+            "namada_tx_init_account" => tx_host_fn!(wasm_store, env, profile, "namada_tx_init_account", tx_init_account(code_hash_ptr: u64, code_hash_len: u64, result_ptr: u64)),
+            "namada_tx_emit_ibc_event" => tx_host_fn!(wasm_store, env, profile, "namada_tx_emit_ibc_event", tx_emit_ibc_event(event_ptr: u64, event_len: u64)),
+            "namada_tx_emit_event" => tx_host_fn!(wasm_store, env, profile, "namada_tx_emit_event", tx_emit_event(event_ptr: u64, event_len: u64)),
+            "namada_tx_get_ibc_event" => tx_host_fn!(wasm_store, env, profile, "namada_tx_get_ibc_event", tx_get_ibc_event(event_type_ptr: u64, event_type_len: u64)),
+            "namada_tx_call" => tx_host_fn!(wasm_store, env, profile, "namada_tx_call", tx_call(target_ptr: u64, target_len: u64, code_hash_ptr: u64, code_hash_len: u64, input_ptr: u64, input_len: u64)),
+            "namada_tx_deploy_code" => tx_host_fn!(wasm_store, env, profile, "namada_tx_deploy_code", tx_deploy_code(code_ptr: u64, code_len: u64, result_ptr: u64)),
+            "namada_tx_set_call_output" => tx_host_fn!(wasm_store, env, profile, "namada_tx_set_call_output", tx_set_call_output(output_ptr: u64, output_len: u64)),
+            "namada_tx_get_chain_id" => tx_host_fn!(wasm_store, env, profile, "namada_tx_get_chain_id", tx_get_chain_id(result_ptr: u64)),
+            "namada_tx_get_tx_index" => tx_host_fn!(wasm_store, env, profile, "namada_tx_get_tx_index", tx_get_tx_index()),
+            "namada_tx_get_block_height" => tx_host_fn!(wasm_store, env, profile, "namada_tx_get_block_height", tx_get_block_height()),
+            "namada_tx_get_block_header" => tx_host_fn!(wasm_store, env, profile, "namada_tx_get_block_header", tx_get_block_header(height: u64)),
+            "namada_tx_get_block_hash" => tx_host_fn!(wasm_store, env, profile, "namada_tx_get_block_hash", tx_get_block_hash(result_ptr: u64)),
+            "namada_tx_get_block_epoch" => tx_host_fn!(wasm_store, env, profile, "namada_tx_get_block_epoch", tx_get_block_epoch()),
+            "namada_tx_get_native_token" => tx_host_fn!(wasm_store, env, profile, "namada_tx_get_native_token", tx_get_native_token(result_ptr: u64)),
+            "namada_tx_log_string" => tx_host_fn!(wasm_store, env, profile, "namada_tx_log_string", tx_log_string(str_ptr: u64, str_len: u64)),
+            "namada_tx_sha256" => tx_host_fn!(wasm_store, env, profile, "namada_tx_sha256", tx_sha256(data_ptr: u64, data_len: u64, result_ptr: u64)),
+            "namada_tx_keccak256" => tx_host_fn!(wasm_store, env, profile, "namada_tx_keccak256", tx_keccak256(data_ptr: u64, data_len: u64, result_ptr: u64)),
+            "namada_tx_blake2b" => tx_host_fn!(wasm_store, env, profile, "namada_tx_blake2b", tx_blake2b(data_ptr: u64, data_len: u64, result_ptr: u64)),
+            "namada_tx_verify_signature" => tx_host_fn!(wasm_store, env, profile, "namada_tx_verify_signature", tx_verify_signature(pk_ptr: u64, pk_len: u64, sig_ptr: u64, sig_len: u64, msg_ptr: u64, msg_len: u64)),
         },
     }
 }
@@ -102,36 +155,42 @@ where
     EVAL: VpEvaluator<Db = DB, H = H, Eval = EVAL, CA = CA>,
     CA: WasmCacheAccess,
 {
+    // Only instrument the host functions when a gas profile is requested
+    let profile = unsafe { env.ctx.gas_meter.get() }.gas_profile().is_some();
     wasmer::imports! {
         // default namespace
         "env" => {
             "memory" => initial_memory,
             // Wasm middleware gas injectiong hook
-            "gas" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_charge_gas),
+            "gas" => vp_host_fn!(wasm_store, env, profile, "gas", vp_charge_gas(used_gas: u64)),
             // Whitelisted gas exposed function, we need two different functions just because of colliding names in the vm_host_env macro to generate implementations
-            "namada_vp_charge_gas" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_charge_gas),
-            "namada_vp_read_pre" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_read_pre),
-            "namada_vp_read_post" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_read_post),
-            "namada_vp_read_temp" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_read_temp),
-            "namada_vp_result_buffer" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_result_buffer),
-            "namada_vp_has_key_pre" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_has_key_pre),
-            "namada_vp_has_key_post" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_has_key_post),
-            "namada_vp_iter_prefix_pre" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_iter_prefix_pre),
-            "namada_vp_iter_prefix_post" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_iter_prefix_pre),
-            "namada_vp_iter_next" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_iter_next),
-            "namada_vp_get_chain_id" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_chain_id),
-            "namada_vp_get_tx_index" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_tx_index),
-            "namada_vp_get_block_height" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_block_height),
-            "namada_vp_get_block_header" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_block_header),
-            "namada_vp_get_block_hash" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_block_hash),
-            "namada_vp_get_tx_code_hash" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_tx_code_hash),
-            "namada_vp_get_block_epoch" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_block_epoch),
-            "namada_vp_verify_tx_section_signature" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_verify_tx_section_signature),
-            "namada_vp_verify_masp" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_verify_masp),
-            "namada_vp_eval" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_eval),
-            "namada_vp_get_native_token" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_native_token),
-            "namada_vp_has_valid_pow" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_has_valid_pow),
-            "namada_vp_log_string" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_log_string),
+            "namada_vp_charge_gas" => vp_host_fn!(wasm_store, env, profile, "namada_vp_charge_gas", vp_charge_gas(used_gas: u64)),
+            "namada_vp_read_pre" => vp_host_fn!(wasm_store, env, profile, "namada_vp_read_pre", vp_read_pre(key_ptr: u64, key_len: u64)),
+            "namada_vp_read_post" => vp_host_fn!(wasm_store, env, profile, "namada_vp_read_post", vp_read_post(key_ptr: u64, key_len: u64)),
+            "namada_vp_read_temp" => vp_host_fn!(wasm_store, env, profile, "namada_vp_read_temp", vp_read_temp(key_ptr: u64, key_len: u64)),
+            "namada_vp_result_buffer" => vp_host_fn!(wasm_store, env, profile, "namada_vp_result_buffer", vp_result_buffer(result_ptr: u64)),
+            "namada_vp_has_key_pre" => vp_host_fn!(wasm_store, env, profile, "namada_vp_has_key_pre", vp_has_key_pre(key_ptr: u64, key_len: u64)),
+            "namada_vp_has_key_post" => vp_host_fn!(wasm_store, env, profile, "namada_vp_has_key_post", vp_has_key_post(key_ptr: u64, key_len: u64)),
+            "namada_vp_iter_prefix_pre" => vp_host_fn!(wasm_store, env, profile, "namada_vp_iter_prefix_pre", vp_iter_prefix_pre(prefix_ptr: u64, prefix_len: u64)),
+            "namada_vp_iter_prefix_post" => vp_host_fn!(wasm_store, env, profile, "namada_vp_iter_prefix_post", vp_iter_prefix_pre(prefix_ptr: u64, prefix_len: u64)),
+            "namada_vp_iter_next" => vp_host_fn!(wasm_store, env, profile, "namada_vp_iter_next", vp_iter_next(iter_id: u64)),
+            "namada_vp_get_chain_id" => vp_host_fn!(wasm_store, env, profile, "namada_vp_get_chain_id", vp_get_chain_id(result_ptr: u64)),
+            "namada_vp_get_tx_index" => vp_host_fn!(wasm_store, env, profile, "namada_vp_get_tx_index", vp_get_tx_index()),
+            "namada_vp_get_block_height" => vp_host_fn!(wasm_store, env, profile, "namada_vp_get_block_height", vp_get_block_height()),
+            "namada_vp_get_block_header" => vp_host_fn!(wasm_store, env, profile, "namada_vp_get_block_header", vp_get_block_header(height: u64)),
+            "namada_vp_get_block_hash" => vp_host_fn!(wasm_store, env, profile, "namada_vp_get_block_hash", vp_get_block_hash(result_ptr: u64)),
+            "namada_vp_get_tx_code_hash" => vp_host_fn!(wasm_store, env, profile, "namada_vp_get_tx_code_hash", vp_get_tx_code_hash(result_ptr: u64)),
+            "namada_vp_get_block_epoch" => vp_host_fn!(wasm_store, env, profile, "namada_vp_get_block_epoch", vp_get_block_epoch()),
+            "namada_vp_verify_tx_section_signature" => vp_host_fn!(wasm_store, env, profile, "namada_vp_verify_tx_section_signature", vp_verify_tx_section_signature(hash_list_ptr: u64, hash_list_len: u64, public_keys_map_ptr: u64, public_keys_map_len: u64, threshold: u8, max_signatures_ptr: u64, max_signatures_len: u64)),
+            "namada_vp_verify_masp" => vp_host_fn!(wasm_store, env, profile, "namada_vp_verify_masp", vp_verify_masp(tx_ptr: u64, tx_len: u64)),
+            "namada_vp_eval" => vp_host_fn!(wasm_store, env, profile, "namada_vp_eval", vp_eval(vp_code_hash_ptr: u64, vp_code_hash_len: u64, input_data_ptr: u64, input_data_len: u64)),
+            "namada_vp_get_native_token" => vp_host_fn!(wasm_store, env, profile, "namada_vp_get_native_token", vp_get_native_token(result_ptr: u64)),
+            "namada_vp_has_valid_pow" => vp_host_fn!(wasm_store, env, profile, "namada_vp_has_valid_pow", vp_has_valid_pow()),
+            "namada_vp_log_string" => vp_host_fn!(wasm_store, env, profile, "namada_vp_log_string", vp_log_string(str_ptr: u64, str_len: u64)),
+            "namada_vp_sha256" => vp_host_fn!(wasm_store, env, profile, "namada_vp_sha256", vp_sha256(data_ptr: u64, data_len: u64, result_ptr: u64)),
+            "namada_vp_keccak256" => vp_host_fn!(wasm_store, env, profile, "namada_vp_keccak256", vp_keccak256(data_ptr: u64, data_len: u64, result_ptr: u64)),
+            "namada_vp_blake2b" => vp_host_fn!(wasm_store, env, profile, "namada_vp_blake2b", vp_blake2b(data_ptr: u64, data_len: u64, result_ptr: u64)),
+            "namada_vp_verify_signature" => vp_host_fn!(wasm_store, env, profile, "namada_vp_verify_signature", vp_verify_signature(pk_ptr: u64, pk_len: u64, sig_ptr: u64, sig_len: u64, msg_ptr: u64, msg_len: u64)),
         },
     }
 }
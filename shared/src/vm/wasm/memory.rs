@@ -97,6 +97,22 @@ pub fn write_tx_inputs(
     })
 }
 
+/// Write the input of a call from another transaction into wasm memory
+pub fn write_call_inputs(
+    memory: &wasmer::Memory,
+    input: &[u8],
+) -> Result<TxCallInput> {
+    let tx_data_ptr = 0;
+    let tx_data_len = input.len() as _;
+
+    write_memory_bytes(memory, tx_data_ptr, input)?;
+
+    Ok(TxCallInput {
+        tx_data_ptr,
+        tx_data_len,
+    })
+}
+
 /// Input data for validity predicate wasm call
 #[derive(Clone, Debug)]
 pub struct VpCallInput {
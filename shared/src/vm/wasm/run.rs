@@ -22,7 +22,7 @@ use crate::types::address::Address;
 use crate::types::hash::{Error as TxHashError, Hash};
 use crate::types::internal::HostEnvResult;
 use crate::types::storage::{Key, TxIndex};
-use crate::vm::host_env::{TxVmEnv, VpCtx, VpEvaluator, VpVmEnv};
+use crate::vm::host_env::{TxCtx, TxVmEnv, VpCtx, VpEvaluator, VpVmEnv};
 use crate::vm::prefix_iter::PrefixIterators;
 use crate::vm::types::VpInput;
 use crate::vm::wasm::host_env::{tx_imports, vp_imports};
@@ -32,6 +32,7 @@ use crate::vm::{
 };
 
 const TX_ENTRYPOINT: &str = "_apply_tx";
+const TX_CALL_ENTRYPOINT: &str = "_call_tx";
 const VP_ENTRYPOINT: &str = "_validate_tx";
 const WASM_STACK_LIMIT: u32 = u16::MAX as u32;
 
@@ -190,6 +191,68 @@ where
     Ok(verifiers)
 }
 
+/// Call the entrypoint of a stored wasm code from within a running
+/// transaction. The called code runs with the caller's context, so its storage
+/// modifications go into the same write log and its gas is charged to the
+/// caller's gas meter.
+pub fn tx_call<DB, H, CA>(
+    ctx: TxCtx<'static, DB, H, CA>,
+    code_hash: &Hash,
+    input: &[u8],
+) -> Result<()>
+where
+    DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    let tx_wasm_cache = unsafe { ctx.tx_wasm_cache.get() };
+    let write_log = unsafe { ctx.write_log.get() };
+    let storage = unsafe { ctx.storage.get() };
+    let gas_meter = unsafe { ctx.gas_meter.get() };
+
+    let (module, store) = fetch_or_compile(
+        tx_wasm_cache,
+        WasmPayload::Hash(code_hash),
+        write_log,
+        storage,
+        gas_meter,
+    )?;
+
+    let env = TxVmEnv {
+        memory: WasmMemory::default(),
+        ctx,
+    };
+    let initial_memory =
+        memory::prepare_tx_memory(&store).map_err(Error::MemoryError)?;
+    let imports = tx_imports(&store, initial_memory, env);
+
+    // Instantiate the wasm module
+    let instance = wasmer::Instance::new(&module, &imports)
+        .map_err(|e| Error::InstantiationError(Box::new(e)))?;
+
+    let memory = instance
+        .exports
+        .get_memory("memory")
+        .map_err(Error::MissingModuleMemory)?;
+    let memory::TxCallInput {
+        tx_data_ptr: input_ptr,
+        tx_data_len: input_len,
+    } = memory::write_call_inputs(memory, input).map_err(Error::MemoryError)?;
+    let call_tx = instance
+        .exports
+        .get_function(TX_CALL_ENTRYPOINT)
+        .map_err(Error::MissingModuleEntrypoint)?
+        .native::<(u64, u64), ()>()
+        .map_err(|error| Error::UnexpectedModuleEntrypointInterface {
+            entrypoint: TX_CALL_ENTRYPOINT,
+            error,
+        })?;
+    call_tx.call(input_ptr, input_len).map_err(|err| {
+        tracing::debug!("Called tx WASM {} failed with {}", code_hash, err);
+        Error::RuntimeError(err)
+    })
+}
+
 /// Execute a validity predicate code. Returns whether the validity
 /// predicate accepted storage modifications performed by the transaction
 /// that triggered the execution.
@@ -578,9 +641,14 @@ mod tests {
     use super::*;
     use crate::ledger::storage::testing::TestStorage;
     use crate::proto::{Code, Data};
+    use crate::types::address::testing::{
+        established_address_1, established_address_2,
+    };
     use crate::types::hash::Hash;
+    use crate::types::storage::KeySeg;
     use crate::types::transaction::TxType;
     use crate::types::validity_predicate::EvalVp;
+    use crate::vm::host_env::MAX_TX_CALL_DEPTH;
     use crate::vm::wasm;
 
     const TX_GAS_LIMIT: u64 = 100_000_000;
@@ -1200,6 +1268,367 @@ mod tests {
         assert!(!passed);
     }
 
+    /// Test that a transaction can call a stored wasm code, which can in turn
+    /// call other codes up to the maximum call depth.
+    #[test]
+    fn test_tx_call() {
+        let mut storage = TestStorage::default();
+        store_wasm_code(&mut storage, call_tx_callee_wasm());
+
+        // The callee calls itself until the counter reaches 0, then returns
+        // the payload
+        let (result, write_log) =
+            call_tx_wasm(&storage, None, MAX_TX_CALL_DEPTH - 1);
+        let verifiers = result.unwrap();
+        assert_eq!(read_call_output(&write_log), Some(b"payload".to_vec()));
+        // The target account of the call validates it
+        assert!(verifiers.contains(&established_address_1()));
+
+        // One more nested call exceeds the maximum call depth
+        let (result, _) = call_tx_wasm(&storage, None, MAX_TX_CALL_DEPTH);
+        assert!(result.is_err());
+    }
+
+    /// Test that a called wasm code must be whitelisted if there's a
+    /// transactions whitelist.
+    #[test]
+    fn test_tx_call_whitelist() {
+        let callee_code = call_tx_callee_wasm();
+        let callee_hash = Hash::sha256(&callee_code);
+        let mut storage = TestStorage::default();
+        store_wasm_code(&mut storage, callee_code);
+
+        let other_hash = Hash::sha256(b"another tx");
+        write_tx_whitelist(&mut storage, &[other_hash]);
+        let (result, write_log) = call_tx_wasm(&storage, None, 0);
+        assert!(result.is_err());
+        assert_eq!(read_call_output(&write_log), None);
+
+        write_tx_whitelist(&mut storage, &[other_hash, callee_hash]);
+        let (result, write_log) = call_tx_wasm(&storage, None, 0);
+        result.unwrap();
+        assert_eq!(read_call_output(&write_log), Some(b"payload".to_vec()));
+    }
+
+    /// Test that a called wasm code can only modify the storage of the target
+    /// account of the call.
+    #[test]
+    fn test_tx_call_storage_modification() {
+        let callee_code = write_tx_callee_wasm();
+        let callee_hash = Hash::sha256(&callee_code);
+        let mut storage = TestStorage::default();
+        store_wasm_code(&mut storage, callee_code);
+        let target = established_address_1();
+        let other = established_address_2();
+        for addr in [&target, &other] {
+            storage
+                .write(&Key::validity_predicate(addr), callee_hash.0)
+                .unwrap();
+        }
+
+        // The callee writes a key of the target account
+        let key = Key::from(target.to_db_key())
+            .push(&"counter".to_string())
+            .unwrap();
+        let mut input = callee_hash.0.to_vec();
+        input.extend_from_slice(key.to_string().as_bytes());
+        let (result, write_log) =
+            run_call_tx_wasm(&storage, None, &target, input);
+        let verifiers = result.unwrap();
+        assert!(verifiers.contains(&target));
+        assert!(matches!(
+            write_log.read(&key).0,
+            Some(StorageModification::Write { .. })
+        ));
+
+        // The callee cannot write a key of another account, even though the
+        // calling tx could
+        let key = Key::from(other.to_db_key())
+            .push(&"counter".to_string())
+            .unwrap();
+        let mut input = callee_hash.0.to_vec();
+        input.extend_from_slice(key.to_string().as_bytes());
+        let (result, write_log) =
+            run_call_tx_wasm(&storage, None, &target, input);
+        assert!(result.is_err());
+        assert_eq!(write_log.read(&key).0, None);
+    }
+
+    /// Test that a transaction can deploy a whitelisted wasm code and then
+    /// call it.
+    #[test]
+    fn test_tx_deploy_and_call() {
+        let callee_code = call_tx_callee_wasm();
+        let callee_hash = Hash::sha256(&callee_code);
+        let not_wasm = b"not wasm";
+        let mut storage = TestStorage::default();
+
+        // A code that isn't whitelisted cannot be deployed, even without a
+        // transactions whitelist
+        let (result, write_log) =
+            call_tx_wasm(&storage, Some(&callee_code[..]), 1);
+        assert!(result.is_err());
+        assert_eq!(write_log.read(&Key::wasm_code(&callee_hash)).0, None);
+
+        write_tx_whitelist(
+            &mut storage,
+            &[callee_hash, Hash::sha256(not_wasm)],
+        );
+        let (result, write_log) =
+            call_tx_wasm(&storage, Some(&callee_code[..]), 1);
+        result.unwrap();
+        assert_eq!(read_call_output(&write_log), Some(b"payload".to_vec()));
+        match write_log.read(&Key::wasm_code(&callee_hash)).0 {
+            Some(StorageModification::Write { value }) => {
+                assert_eq!(value, &callee_code)
+            }
+            _ => panic!("The deployed code must be stored"),
+        }
+        match write_log.read(&Key::wasm_code_len(&callee_hash)).0 {
+            Some(StorageModification::Write { value }) => assert_eq!(
+                u64::try_from_slice(value).unwrap(),
+                callee_code.len() as u64
+            ),
+            _ => panic!("The deployed code length must be stored"),
+        }
+
+        // A code that isn't valid wasm cannot be deployed
+        let (result, write_log) =
+            call_tx_wasm(&storage, Some(&not_wasm[..]), 0);
+        assert!(result.is_err());
+        assert_eq!(read_call_output(&write_log), None);
+    }
+
+    /// Store the given wasm code under its hash
+    fn store_wasm_code(storage: &mut TestStorage, code: Vec<u8>) {
+        let code_hash = Hash::sha256(&code);
+        let code_len = (code.len() as u64).try_to_vec().unwrap();
+        storage
+            .write(&Key::wasm_code_len(&code_hash), code_len)
+            .unwrap();
+        storage.write(&Key::wasm_code(&code_hash), code).unwrap();
+    }
+
+    /// Write the transactions whitelist with the given code hashes
+    fn write_tx_whitelist(storage: &mut TestStorage, hashes: &[Hash]) {
+        let whitelist_key =
+            crate::ledger::parameters::storage::get_tx_whitelist_storage_key();
+        let whitelist: Vec<String> = hashes
+            .iter()
+            .map(|hash| hash.to_string().to_lowercase())
+            .collect();
+        storage
+            .write(&whitelist_key, whitelist.try_to_vec().unwrap())
+            .unwrap();
+    }
+
+    /// A wasm code to be called with `namada_tx_call`. Its input is its own
+    /// code hash, followed by a counter byte, the target account of the call
+    /// and a payload. While the counter is not 0, it decrements it and calls
+    /// itself, otherwise it sets the payload as its output.
+    fn call_tx_callee_wasm() -> Vec<u8> {
+        let target_len = established_address_1().encode().len();
+        wasmer::wat2wasm(
+            format!(
+                r#"
+            (module
+                (import "env" "namada_tx_call" (func $call (param i64 i64 i64 i64 i64 i64) (result i64)))
+                (import "env" "namada_tx_result_buffer" (func $result_buffer (param i64)))
+                (import "env" "namada_tx_set_call_output" (func $set_output (param i64 i64)))
+
+                (func $_call_tx (param $ptr i64) (param $len i64)
+                (local $counter i32)
+                (local $out_len i64)
+                (local.set $counter
+                    (i32.load8_u offset=32 (i32.wrap_i64 (local.get $ptr))))
+                (if (i32.eqz (local.get $counter))
+                (then
+                    (call $set_output
+                    (i64.add (local.get $ptr) (i64.const {payload_offset}))
+                    (i64.sub (local.get $len) (i64.const {payload_offset}))))
+                (else
+                    (i32.store8 offset=32 (i32.wrap_i64 (local.get $ptr))
+                    (i32.sub (local.get $counter) (i32.const 1)))
+                    (local.set $out_len
+                    (call $call
+                        (i64.add (local.get $ptr) (i64.const 33))
+                        (i64.const {target_len})
+                        (local.get $ptr) (i64.const 32)
+                        (local.get $ptr) (local.get $len)))
+                    (call $result_buffer (i64.const 65536))
+                    (call $set_output (i64.const 65536) (local.get $out_len)))))
+
+                (memory (;0;) 16)
+                (export "memory" (memory 0))
+                (export "_call_tx" (func $_call_tx)))
+            "#,
+                target_len = target_len,
+                payload_offset = 33 + target_len,
+            )
+            .as_bytes(),
+        )
+        .expect("unexpected error converting wat2wasm")
+        .into_owned()
+    }
+
+    /// A wasm code to be called with `namada_tx_call`. Its input is its own
+    /// code hash, followed by a storage key to which it writes the key
+    /// itself.
+    fn write_tx_callee_wasm() -> Vec<u8> {
+        wasmer::wat2wasm(
+            br#"
+            (module
+                (import "env" "namada_tx_write" (func $write (param i64 i64 i64 i64)))
+
+                (func $_call_tx (param $ptr i64) (param $len i64)
+                (call $write
+                    (i64.add (local.get $ptr) (i64.const 32))
+                    (i64.sub (local.get $len) (i64.const 32))
+                    (i64.add (local.get $ptr) (i64.const 32))
+                    (i64.sub (local.get $len) (i64.const 32))))
+
+                (memory (;0;) 16)
+                (export "memory" (memory 0))
+                (export "_call_tx" (func $_call_tx)))
+            "#,
+        )
+        .expect("unexpected error converting wat2wasm")
+        .into_owned()
+    }
+
+    /// The storage key to which the tx from [`run_call_tx_wasm`] writes the
+    /// output of the called code
+    const CALL_OUTPUT_KEY: &str = "call_output";
+
+    /// Run a tx that calls the code from [`call_tx_callee_wasm`] on behalf of
+    /// an established account with the given counter. If a code is given,
+    /// the tx deploys it before the call.
+    fn call_tx_wasm(
+        storage: &TestStorage,
+        deploy: Option<&[u8]>,
+        counter: u64,
+    ) -> (Result<BTreeSet<Address>>, WriteLog) {
+        let target = established_address_1();
+        let mut input = Hash::sha256(call_tx_callee_wasm()).0.to_vec();
+        input.push(counter as u8);
+        input.extend_from_slice(target.encode().as_bytes());
+        input.extend_from_slice(b"payload");
+        run_call_tx_wasm(storage, deploy, &target, input)
+    }
+
+    /// Run a tx that calls the code whose hash is at the start of the given
+    /// input on behalf of the given target account, and writes its output to
+    /// [`CALL_OUTPUT_KEY`]. If a code is given, the tx deploys it before the
+    /// call.
+    fn run_call_tx_wasm(
+        storage: &TestStorage,
+        deploy: Option<&[u8]>,
+        target: &Address,
+        input: Vec<u8>,
+    ) -> (Result<BTreeSet<Address>>, WriteLog) {
+        let target = target.encode();
+        let (deploy_call, deploy_code) = match deploy {
+            Some(code) => (
+                format!(
+                    "(call $deploy (i64.const 262144) (i64.const {}) \
+                     (i64.const 327680))",
+                    code.len()
+                ),
+                code,
+            ),
+            None => (String::new(), &[][..]),
+        };
+        let tx_code = wasmer::wat2wasm(
+            format!(
+                r#"
+            (module
+                (import "env" "namada_tx_deploy_code" (func $deploy (param i64 i64 i64)))
+                (import "env" "namada_tx_call" (func $call (param i64 i64 i64 i64 i64 i64) (result i64)))
+                (import "env" "namada_tx_result_buffer" (func $result_buffer (param i64)))
+                (import "env" "namada_tx_write" (func $write (param i64 i64 i64 i64)))
+
+                (func $_apply_tx (param i64 i64)
+                (local $out_len i64)
+                {deploy_call}
+                (local.set $out_len
+                    (call $call
+                    (i64.const 393216) (i64.const {target_len})
+                    (i64.const 65536) (i64.const 32)
+                    (i64.const 65536) (i64.const {input_len})))
+                (call $result_buffer (i64.const 131072))
+                (call $write
+                    (i64.const 196608) (i64.const {key_len})
+                    (i64.const 131072) (local.get $out_len)))
+
+                (memory (;0;) 16)
+                (data (i32.const 65536) "{input}")
+                (data (i32.const 196608) "{key}")
+                (data (i32.const 262144) "{deploy_code}")
+                (data (i32.const 393216) "{target}")
+                (export "memory" (memory 0))
+                (export "_apply_tx" (func $_apply_tx)))
+            "#,
+                input_len = input.len(),
+                input = wat_bytes(&input),
+                key_len = CALL_OUTPUT_KEY.len(),
+                key = CALL_OUTPUT_KEY,
+                deploy_code = wat_bytes(deploy_code),
+                target_len = target.len(),
+                target = target,
+            )
+            .as_bytes(),
+        )
+        .expect("unexpected error converting wat2wasm")
+        .into_owned();
+
+        let tx_index = TxIndex::default();
+        let mut write_log = WriteLog::default();
+        let mut gas_meter = TxGasMeter::new_from_sub_limit(TX_GAS_LIMIT.into());
+        let (mut vp_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let (mut tx_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+
+        // store the tx code
+        let code_hash = Hash::sha256(&tx_code);
+        let code_len = (tx_code.len() as u64).try_to_vec().unwrap();
+        write_log
+            .write(&Key::wasm_code(&code_hash), tx_code)
+            .unwrap();
+        write_log
+            .write(&Key::wasm_code_len(&code_hash), code_len)
+            .unwrap();
+
+        let mut outer_tx = Tx::from_type(TxType::Raw);
+        outer_tx.set_code(Code::from_hash(code_hash));
+        outer_tx.set_data(Data::new(vec![]));
+
+        let result = tx(
+            storage,
+            &mut write_log,
+            &mut gas_meter,
+            &tx_index,
+            &outer_tx,
+            &mut vp_cache,
+            &mut tx_cache,
+        );
+        (result, write_log)
+    }
+
+    /// Read the output written by the tx from [`run_call_tx_wasm`]
+    fn read_call_output(write_log: &WriteLog) -> Option<Vec<u8>> {
+        let key = Key::parse(CALL_OUTPUT_KEY).unwrap();
+        match write_log.read(&key).0 {
+            Some(StorageModification::Write { value }) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    /// Escape the given bytes for a wat string literal
+    fn wat_bytes(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("\\{:02x}", byte)).collect()
+    }
+
     fn loop_in_tx_wasm(loops: u32) -> Result<BTreeSet<Address>> {
         // A transaction with a recursive loop.
         // The boilerplate code is generated from tx_template.wasm using
@@ -30,7 +30,8 @@ use namada_apps::config::genesis::genesis_config::{
 use namada_apps::config::utils::convert_tm_addr_to_socket_addr;
 use namada_apps::facade::tendermint_config::net::Address as TendermintAddress;
 use namada_core::ledger::governance::cli::onchain::{
-    PgfFunding, PgfFundingTarget, StewardsUpdate,
+    PgfContinousFunding, PgfFunding, PgfFundingTarget, PgfTarget,
+    StewardsUpdate,
 };
 use namada_test_utils::TestWasms;
 use namada_vp_prelude::{testnet_pow, BTreeSet};
@@ -2458,14 +2459,19 @@ fn pgf_governance_proposal() -> Result<()> {
     let christel = find_address(&test, CHRISTEL)?;
 
     let pgf_funding = PgfFunding {
-        continous: vec![PgfFundingTarget {
-            amount: token::Amount::from_u64(10),
-            address: bertha.clone(),
+        continous: vec![PgfContinousFunding {
+            target: PgfTarget::Internal(PgfFundingTarget {
+                amount: token::Amount::from_u64(10),
+                address: bertha.clone(),
+            }),
+            start_epoch: None,
+            end_epoch: None,
+            cap: None,
         }],
-        retro: vec![PgfFundingTarget {
+        retro: vec![PgfTarget::Internal(PgfFundingTarget {
             amount: token::Amount::from_u64(5),
             address: christel,
-        }],
+        })],
     };
 
     let valid_proposal_json_path =
@@ -14,6 +14,8 @@
 pub mod ibc;
 pub mod tx;
 pub mod vp;
+#[cfg(all(test, feature = "wasm-runtime"))]
+mod vp_user;
 
 #[cfg(test)]
 mod tests {
@@ -22,18 +24,26 @@ mod tests {
     use std::panic;
 
     use itertools::Itertools;
+    use masp_primitives::consensus::{
+        BlockHeight as MaspBlockHeight, BranchId,
+    };
+    use masp_primitives::transaction::{TransactionData, TxVersion};
+    use namada::ibc::applications::transfer::packet::PacketData;
     use namada::ibc::core::Msg;
     use namada::ledger::ibc::storage as ibc_storage;
     use namada::ledger::ibc::vp::{
         get_dummy_header as tm_dummy_header, Error as IbcError,
     };
     use namada::ledger::tx_env::TxEnv;
-    use namada::proto::Tx;
+    use namada::proto::{Code, Tx};
+    use namada::types::event::TxEvent;
     use namada::types::hash::Hash;
+    use namada::types::ibc::IbcShieldedTransfer;
+    use namada::types::keccak::keccak_hash;
     use namada::types::key::*;
     use namada::types::storage::{self, BlockHash, BlockHeight, Key, KeySeg};
     use namada::types::time::DateTimeUtc;
-    use namada::types::token::{self, Amount};
+    use namada::types::token::{self, Amount, DenominatedAmount};
     use namada::types::{address, key};
     use namada_core::ledger::gas::{TxGasMeter, VpGasMeter};
     use namada_core::ledger::ibc::context::transfer_mod::testing::DummyTransferModule;
@@ -41,10 +51,12 @@ mod tests {
     use namada_test_utils::TestWasms;
     use namada_tx_prelude::address::InternalAddress;
     use namada_tx_prelude::chain::ChainId;
+    use namada_tx_prelude::crypto as tx_crypto;
     use namada_tx_prelude::{
         Address, BorshSerialize, StorageRead, StorageWrite,
     };
     use namada_vp_prelude::account::AccountPublicKeysMap;
+    use namada_vp_prelude::crypto as vp_crypto;
     use namada_vp_prelude::VpEnv;
     use prost::Message;
     use test_log::test;
@@ -53,6 +65,18 @@ mod tests {
     use crate::tx::{tx_host_env, TestTxEnv};
     use crate::vp::{vp_host_env, TestVpEnv};
 
+    const PAYMENT_ADDRESS: &str = "patest1a8sfz9c6axdhn925e5qrgzz86msq6yj4uhmxayynucea7gssepk89dgqkx00srfkn4m6kt9jpau";
+
+    /// The blake2b digest of empty data, with a 32 bytes output
+    const BLAKE2B_EMPTY_DIGEST: &str =
+        "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8";
+
+    fn secp256k1_keypair() -> common::SecretKey {
+        common::SecretKey::Secp256k1(key::testing::gen_keypair::<
+            secp256k1::SigScheme,
+        >())
+    }
+
     #[test]
     fn test_tx_read_write() {
         // The environment must be initialized first
@@ -214,7 +238,7 @@ mod tests {
         // The environment must be initialized first
         tx_host_env::init();
 
-        let code = vec![];
+        let code: Vec<u8> = vec![];
         tx::ctx().init_account(code).unwrap();
     }
 
@@ -272,6 +296,127 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tx_emit_event() {
+        // The environment must be initialized first
+        let mut env = TestTxEnv::default();
+        let code: Vec<u8> = vec![];
+        let code_hash = Hash::sha256(&code);
+        env.tx.set_code(Code::new(code));
+        tx_host_env::set(env);
+
+        // The namespace is set to the hash of the code of the tx, whatever
+        // the tx sets it to
+        let event = TxEvent::new("swap")
+            .with_namespace("other_dapp")
+            .with_attribute("pool", "nam-eth")
+            .with_attribute("amount", 100);
+        tx::ctx().emit_event(&event).unwrap();
+        tx_host_env::with(|env| {
+            assert_eq!(
+                env.wl_storage.write_log.get_tx_events(),
+                &[event.clone().with_namespace(code_hash)]
+            )
+        });
+
+        // A reserved attribute key is not allowed
+        let invalid_event = TxEvent::new("swap").with_attribute("height", 1);
+        assert!(
+            panic::catch_unwind(|| {
+                tx::ctx().emit_event(&invalid_event).unwrap()
+            })
+            .err()
+            .map(|a| a.downcast_ref::<String>().cloned().unwrap())
+            .unwrap()
+            .contains("InvalidTxEvent")
+        );
+    }
+
+    #[test]
+    fn test_tx_call_unknown_code() {
+        // The environment must be initialized first
+        tx_host_env::init();
+
+        // Calling a code that is not in storage must fail the transaction
+        let target = address::testing::established_address_1();
+        let code_hash = Hash::sha256(b"not a stored code");
+        assert!(
+            panic::catch_unwind(|| {
+                tx::ctx().call(&target, &code_hash, b"input").unwrap()
+            })
+            .err()
+            .map(|a| a.downcast_ref::<String>().cloned().unwrap())
+            .unwrap()
+            .contains("CallError")
+        );
+    }
+
+    #[test]
+    fn test_tx_crypto() {
+        // The environment must be initialized first
+        tx_host_env::init();
+
+        let data = b"some arbitrary data";
+        assert_eq!(tx_crypto::sha256(data), Hash::sha256(data));
+        assert_eq!(tx_crypto::keccak256(data), keccak_hash(data));
+        assert_eq!(
+            tx_crypto::blake2b(&[]),
+            Hash::try_from(BLAKE2B_EMPTY_DIGEST).unwrap()
+        );
+
+        for keypair in [key::testing::keypair_1(), secp256k1_keypair()] {
+            let pk = keypair.ref_to();
+            let sig = common::SigScheme::sign(&keypair, data.to_vec());
+            assert!(tx_crypto::verify_signature(&pk, data, &sig));
+            assert!(!tx_crypto::verify_signature(&pk, b"other data", &sig));
+            let other_pk = key::testing::keypair_2().ref_to();
+            assert!(!tx_crypto::verify_signature(&other_pk, data, &sig));
+        }
+
+        // Ethereum signatures are made over the keccak256 hash of the message
+        let keypair = key::testing::keypair_3();
+        let pk = keypair.ref_to();
+        let sig = common::SigScheme::sign(&keypair, keccak_hash(data));
+        assert!(tx_crypto::verify_signature_with_mode(
+            &pk,
+            data,
+            &sig,
+            tx_crypto::SigHashMode::Keccak256
+        ));
+        assert!(!tx_crypto::verify_signature(&pk, data, &sig));
+        // a raw secp256k1 message is the signed digest
+        assert!(tx_crypto::verify_signature_with_mode(
+            &pk,
+            &keccak_hash(data).0,
+            &sig,
+            tx_crypto::SigHashMode::Raw
+        ));
+
+        // raw ed25519 signatures are made over the whole message
+        let keypair = key::testing::keypair_1();
+        let pk = keypair.ref_to();
+        let common::SecretKey::Ed25519(raw_keypair) = &keypair else {
+            unreachable!()
+        };
+        let sig = common::Signature::Ed25519(ed25519::Signature(
+            raw_keypair.0.sign(data),
+        ));
+        assert!(tx_crypto::verify_signature_with_mode(
+            &pk,
+            data,
+            &sig,
+            tx_crypto::SigHashMode::Raw
+        ));
+        assert!(!tx_crypto::verify_signature(&pk, data, &sig));
+        // a different message is rejected in any mode
+        assert!(!tx_crypto::verify_signature_with_mode(
+            &pk,
+            b"other data",
+            &sig,
+            tx_crypto::SigHashMode::Raw
+        ));
+    }
+
     /// An example how to write a VP host environment integration test
     #[test]
     fn test_vp_host_env() {
@@ -511,6 +656,72 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_vp_crypto() {
+        // The environment must be initialized first
+        vp_host_env::init();
+
+        let data = b"some arbitrary data";
+        assert_eq!(vp_crypto::sha256(data), Hash::sha256(data));
+        assert_eq!(vp_crypto::keccak256(data), keccak_hash(data));
+        assert_eq!(
+            vp_crypto::blake2b(&[]),
+            Hash::try_from(BLAKE2B_EMPTY_DIGEST).unwrap()
+        );
+
+        for keypair in [key::testing::keypair_1(), secp256k1_keypair()] {
+            let pk = keypair.ref_to();
+            let sig = common::SigScheme::sign(&keypair, data.to_vec());
+            assert!(vp_crypto::verify_signature(&pk, data, &sig));
+            assert!(!vp_crypto::verify_signature(&pk, b"other data", &sig));
+            let other_pk = key::testing::keypair_2().ref_to();
+            assert!(!vp_crypto::verify_signature(&other_pk, data, &sig));
+        }
+
+        // Ethereum signatures are made over the keccak256 hash of the message
+        let keypair = key::testing::keypair_3();
+        let pk = keypair.ref_to();
+        let sig = common::SigScheme::sign(&keypair, keccak_hash(data));
+        assert!(vp_crypto::verify_signature_with_mode(
+            &pk,
+            data,
+            &sig,
+            vp_crypto::SigHashMode::Keccak256
+        ));
+        assert!(!vp_crypto::verify_signature(&pk, data, &sig));
+        // a raw secp256k1 message is the signed digest
+        assert!(vp_crypto::verify_signature_with_mode(
+            &pk,
+            &keccak_hash(data).0,
+            &sig,
+            vp_crypto::SigHashMode::Raw
+        ));
+
+        // raw ed25519 signatures are made over the whole message
+        let keypair = key::testing::keypair_1();
+        let pk = keypair.ref_to();
+        let common::SecretKey::Ed25519(raw_keypair) = &keypair else {
+            unreachable!()
+        };
+        let sig = common::Signature::Ed25519(ed25519::Signature(
+            raw_keypair.0.sign(data),
+        ));
+        assert!(vp_crypto::verify_signature_with_mode(
+            &pk,
+            data,
+            &sig,
+            vp_crypto::SigHashMode::Raw
+        ));
+        assert!(!vp_crypto::verify_signature(&pk, data, &sig));
+        // a different message is rejected in any mode
+        assert!(!vp_crypto::verify_signature_with_mode(
+            &pk,
+            b"other data",
+            &sig,
+            vp_crypto::SigHashMode::Raw
+        ));
+    }
+
     #[test]
     fn test_vp_get_metadata() {
         // The environment must be initialized first
@@ -1850,4 +2061,428 @@ mod tests {
         let result = ibc::validate_multitoken_vp_from_tx(&env, &tx, &escrow);
         assert!(result.expect("token validation failed unexpectedly"));
     }
+
+    /// Make a shielded transfer with an empty MASP transaction, which is
+    /// only checked by the MASP VP
+    fn ibc_shielded_transfer(
+        source: Address,
+        target: Address,
+        token: &Address,
+        refund_target: Option<Address>,
+    ) -> IbcShieldedTransfer {
+        let masp_tx = TransactionData::from_parts(
+            TxVersion::MASPv5,
+            BranchId::MASP,
+            0,
+            MaspBlockHeight::from_u32(0),
+            None,
+            None,
+        )
+        .freeze()
+        .expect("building the MASP transaction failed");
+        IbcShieldedTransfer {
+            transfer: token::Transfer {
+                source,
+                target,
+                token: token.clone(),
+                amount: DenominatedAmount {
+                    amount: Amount::from_uint(100, ibc::ANY_DENOMINATION)
+                        .unwrap(),
+                    denom: token::Denomination(ibc::ANY_DENOMINATION),
+                },
+                key: None,
+                shielded: None,
+            },
+            masp_tx,
+            refund_target,
+            output_ocks: vec![],
+        }
+    }
+
+    /// Read the number of the MASP transactions recorded in the storage
+    fn masp_head_tx_idx() -> Option<u64> {
+        let key = Key::from(address::masp().to_db_key())
+            .push(&token::HEAD_TX_KEY.to_owned())
+            .expect("Cannot obtain a storage key");
+        tx_host_env::with(|env| env.wl_storage.read(&key).expect("read error"))
+    }
+
+    #[test]
+    fn test_ibc_send_token_from_masp() {
+        // The environment must be initialized first
+        tx_host_env::init();
+
+        // Set the initial state before starting transactions
+        let (token, _) = ibc::init_storage();
+        let (client_id, _client_state, mut writes) = ibc::prepare_client();
+        let (conn_id, conn_writes) = ibc::prepare_opened_connection(&client_id);
+        writes.extend(conn_writes);
+        let (port_id, channel_id, channel_writes) =
+            ibc::prepare_opened_channel(&conn_id, false);
+        writes.extend(channel_writes);
+        // the shielded tokens are held by the MASP address
+        let masp = address::masp();
+        let masp_balance_key = token::balance_key(&token, &masp);
+        let init_bal = Amount::from_uint(100, ibc::ANY_DENOMINATION).unwrap();
+        writes.insert(masp_balance_key.clone(), init_bal.try_to_vec().unwrap());
+        writes.into_iter().for_each(|(key, val)| {
+            tx_host_env::with(|env| {
+                env.wl_storage
+                    .storage
+                    .write(&key, &val)
+                    .expect("write error");
+            });
+        });
+
+        let keypair = key::testing::keypair_1();
+        let keypairs = vec![keypair.clone()];
+        let pks_map = AccountPublicKeysMap::from_iter([
+            key::testing::keypair_1().ref_to(),
+        ]);
+
+        // Start a transaction to send a packet from the shielded pool
+        let msg = ibc::msg_transfer(
+            port_id.clone(),
+            channel_id.clone(),
+            token.to_string(),
+            &masp,
+        );
+        let mut tx_data = vec![];
+        msg.clone()
+            .to_any()
+            .encode(&mut tx_data)
+            .expect("encoding failed");
+
+        // A refund target is required to send tokens from the shielded pool
+        let shielded = ibc_shielded_transfer(
+            masp.clone(),
+            Address::Internal(InternalAddress::Ibc),
+            &token,
+            None,
+        );
+        let mut actions = tx_host_env::ibc::ibc_actions(tx::ctx());
+        actions.set_shielded_transfer(shielded);
+        let result = actions.execute(&tx_data);
+        assert!(matches!(result, Err(IbcActionError::MaspTx(_))));
+
+        let refund_target = address::testing::established_address_1();
+        let shielded = ibc_shielded_transfer(
+            masp.clone(),
+            Address::Internal(InternalAddress::Ibc),
+            &token,
+            Some(refund_target.clone()),
+        );
+        let mut tx = Tx::new(ChainId::default(), None);
+        tx.add_code(vec![]).add_serialized_data(tx_data.clone());
+        tx.add_extra_section(shielded.try_to_vec().unwrap());
+        tx.sign_raw(keypairs.clone(), pks_map.clone())
+            .sign_wrapper(keypair.clone());
+        // send the token and a packet with the data
+        let mut actions = tx_host_env::ibc::ibc_actions(tx::ctx());
+        actions.set_shielded_transfer(shielded);
+        actions.execute(&tx_data).expect("sending a token failed");
+
+        // Check
+        let mut env = tx_host_env::take();
+        let result = ibc::validate_ibc_vp_from_tx(&env, &tx);
+        assert!(result.expect("validation failed unexpectedly"));
+        let escrow = token::balance_key(
+            &token,
+            &address::Address::Internal(address::InternalAddress::Ibc),
+        );
+        let token_vp_result =
+            ibc::validate_multitoken_vp_from_tx(&env, &tx, &escrow);
+        assert!(token_vp_result.expect("token validation failed unexpectedly"));
+        // Check the shielded tokens were sent and the refund target was kept
+        let refund_target_key = ibc_storage::refund_target_key(
+            &port_id,
+            &channel_id,
+            ibc::Sequence::from(1),
+        );
+        let balance: Option<Amount> =
+            env.wl_storage.read(&masp_balance_key).expect("read error");
+        assert_eq!(balance, Some(Amount::zero()));
+        let stored: Option<Address> =
+            env.wl_storage.read(&refund_target_key).expect("read error");
+        assert_eq!(stored, Some(refund_target));
+        tx_host_env::set(env);
+        assert_eq!(masp_head_tx_idx(), Some(1));
+
+        // Commit
+        let mut env = tx_host_env::take();
+        env.commit_tx_and_block();
+        // for the next block
+        env.wl_storage
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(2))
+            .unwrap();
+        env.wl_storage
+            .storage
+            .set_header(tm_dummy_header())
+            .unwrap();
+        tx_host_env::set(env);
+
+        // Start the next transaction for receiving an ack
+        let counterparty = ibc::dummy_channel_counterparty();
+        let packet = ibc::packet_from_message(
+            &msg,
+            ibc::Sequence::from(1),
+            &counterparty,
+        );
+        let msg = ibc::msg_packet_ack(packet);
+        let mut tx_data = vec![];
+        msg.to_any().encode(&mut tx_data).expect("encoding failed");
+
+        let mut tx = Tx::new(ChainId::default(), None);
+        tx.add_code(vec![])
+            .add_serialized_data(tx_data.clone())
+            .sign_raw(keypairs, pks_map)
+            .sign_wrapper(keypair);
+        // ack the packet with the message
+        tx_host_env::ibc::ibc_actions(tx::ctx())
+            .execute(&tx_data)
+            .expect("ack failed");
+
+        // Check
+        let env = tx_host_env::take();
+        let result = ibc::validate_ibc_vp_from_tx(&env, &tx);
+        assert!(result.expect("validation failed unexpectedly"));
+        // The refund target is removed with the acknowledged packet
+        let stored: Option<Address> =
+            env.wl_storage.read(&refund_target_key).expect("read error");
+        assert_eq!(stored, None);
+    }
+
+    #[test]
+    fn test_ibc_shielded_packet_timeout() {
+        // The environment must be initialized first
+        tx_host_env::init();
+
+        let keypair = key::testing::keypair_1();
+        let keypairs = vec![keypair.clone()];
+        let pks_map = AccountPublicKeysMap::from_iter([
+            key::testing::keypair_1().ref_to(),
+        ]);
+
+        // Set the initial state before starting transactions
+        let (token, _) = ibc::init_storage();
+        let (client_id, _client_state, mut writes) = ibc::prepare_client();
+        let (conn_id, conn_writes) = ibc::prepare_opened_connection(&client_id);
+        writes.extend(conn_writes);
+        let (port_id, channel_id, channel_writes) =
+            ibc::prepare_opened_channel(&conn_id, true);
+        writes.extend(channel_writes);
+        // the shielded tokens are held by the MASP address
+        let masp = address::masp();
+        let masp_balance_key = token::balance_key(&token, &masp);
+        let init_bal = Amount::from_uint(100, ibc::ANY_DENOMINATION).unwrap();
+        writes.insert(masp_balance_key.clone(), init_bal.try_to_vec().unwrap());
+        writes.into_iter().for_each(|(key, val)| {
+            tx_host_env::with(|env| {
+                env.wl_storage
+                    .storage
+                    .write(&key, &val)
+                    .expect("write error");
+            })
+        });
+
+        // Start a transaction to send a packet from the shielded pool
+        let mut msg = ibc::msg_transfer(
+            port_id.clone(),
+            channel_id.clone(),
+            token.to_string(),
+            &masp,
+        );
+        ibc::set_timeout_timestamp(&mut msg);
+        let mut tx_data = vec![];
+        msg.clone()
+            .to_any()
+            .encode(&mut tx_data)
+            .expect("encoding failed");
+        let refund_target = address::testing::established_address_1();
+        let shielded = ibc_shielded_transfer(
+            masp.clone(),
+            Address::Internal(InternalAddress::Ibc),
+            &token,
+            Some(refund_target.clone()),
+        );
+        // send a packet with the message
+        let mut actions = tx_host_env::ibc::ibc_actions(tx::ctx());
+        actions.set_shielded_transfer(shielded);
+        actions.execute(&tx_data).expect("sending a token failed");
+
+        // Commit
+        let mut env = tx_host_env::take();
+        env.commit_tx_and_block();
+        // for the next block
+        env.wl_storage
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(2))
+            .unwrap();
+        env.wl_storage
+            .storage
+            .set_header(tm_dummy_header())
+            .unwrap();
+        tx_host_env::set(env);
+
+        // Start a transaction to notify the timeout
+        let counterparty = ibc::dummy_channel_counterparty();
+        let packet = ibc::packet_from_message(
+            &msg,
+            ibc::Sequence::from(1),
+            &counterparty,
+        );
+        let msg = ibc::msg_timeout(packet, ibc::Sequence::from(1));
+        let mut tx_data = vec![];
+        msg.to_any().encode(&mut tx_data).expect("encoding failed");
+        let mut tx = Tx::new(ChainId::default(), None);
+        tx.add_code(vec![])
+            .add_serialized_data(tx_data.clone())
+            .sign_raw(keypairs, pks_map)
+            .sign_wrapper(keypair);
+
+        // timeout the packet
+        tx_host_env::ibc::ibc_actions(tx::ctx())
+            .execute(&tx_data)
+            .expect("timeout failed");
+
+        // Check
+        let env = tx_host_env::take();
+        let result = ibc::validate_ibc_vp_from_tx(&env, &tx);
+        assert!(result.expect("validation failed unexpectedly"));
+        let escrow = token::balance_key(
+            &token,
+            &address::Address::Internal(address::InternalAddress::Ibc),
+        );
+        let result = ibc::validate_multitoken_vp_from_tx(&env, &tx, &escrow);
+        assert!(result.expect("token validation failed unexpectedly"));
+        // The tokens are refunded to the refund target instead of the MASP
+        // address which would need a MASP transaction
+        let balance: Option<Amount> =
+            env.wl_storage.read(&masp_balance_key).expect("read error");
+        assert_eq!(balance, Some(Amount::zero()));
+        let refund_balance_key = token::balance_key(&token, &refund_target);
+        let balance: Option<Amount> = env
+            .wl_storage
+            .read(&refund_balance_key)
+            .expect("read error");
+        assert_eq!(balance, Some(init_bal));
+        let refund_target_key = ibc_storage::refund_target_key(
+            &port_id,
+            &channel_id,
+            ibc::Sequence::from(1),
+        );
+        let stored: Option<Address> =
+            env.wl_storage.read(&refund_target_key).expect("read error");
+        assert_eq!(stored, None);
+    }
+
+    #[test]
+    fn test_ibc_receive_token_to_payment_address() {
+        // The environment must be initialized first
+        tx_host_env::init();
+
+        let keypair = key::testing::keypair_1();
+        let keypairs = vec![keypair.clone()];
+        let pks_map = AccountPublicKeysMap::from_iter([
+            key::testing::keypair_1().ref_to(),
+        ]);
+
+        // Set the initial state before starting transactions
+        let (token, receiver) = ibc::init_storage();
+        let (client_id, _client_state, mut writes) = ibc::prepare_client();
+        let (conn_id, conn_writes) = ibc::prepare_opened_connection(&client_id);
+        writes.extend(conn_writes);
+        let (port_id, channel_id, channel_writes) =
+            ibc::prepare_opened_channel(&conn_id, false);
+        writes.extend(channel_writes);
+
+        writes.into_iter().for_each(|(key, val)| {
+            tx_host_env::with(|env| {
+                env.wl_storage
+                    .storage
+                    .write(&key, &val)
+                    .expect("write error");
+            });
+        });
+
+        // packet to a payment address with the MASP transaction in the memo
+        let masp = address::masp();
+        let denom = format!("{}/{}/{}", port_id, channel_id, token);
+        let ibc_token = ibc::ibc_token(&denom);
+        let shielded = ibc_shielded_transfer(
+            Address::Internal(InternalAddress::Ibc),
+            masp.clone(),
+            &ibc_token,
+            None,
+        );
+        let shielded_packet = |sequence: u64, memo: String| {
+            let mut packet = ibc::received_packet(
+                port_id.clone(),
+                channel_id.clone(),
+                ibc::Sequence::from(sequence),
+                token.to_string(),
+                &receiver,
+            );
+            let mut data: PacketData =
+                serde_json::from_slice(&packet.data).unwrap();
+            data.receiver = PAYMENT_ADDRESS.to_string().into();
+            data.memo = memo.into();
+            packet.data = serde_json::to_vec(&data).unwrap();
+            packet
+        };
+        let packet = shielded_packet(1, shielded.to_string());
+
+        // Start a transaction to receive a packet
+        let msg = ibc::msg_packet_recv(packet);
+        let mut tx_data = vec![];
+        msg.to_any().encode(&mut tx_data).expect("encoding failed");
+
+        let mut tx = Tx::new(ChainId::default(), None);
+        tx.add_code(vec![])
+            .add_serialized_data(tx_data.clone())
+            .sign_raw(keypairs, pks_map)
+            .sign_wrapper(keypair);
+        // receive a packet with the message
+        tx_host_env::ibc::ibc_actions(tx::ctx())
+            .execute(&tx_data)
+            .expect("receiving the token failed");
+
+        // Check
+        let env = tx_host_env::take();
+        let result = ibc::validate_ibc_vp_from_tx(&env, &tx);
+        assert!(result.expect("validation failed unexpectedly"));
+        let minted_key = token::minted_balance_key(&ibc_token);
+        let result =
+            ibc::validate_multitoken_vp_from_tx(&env, &tx, &minted_key);
+        assert!(result.expect("token validation failed unexpectedly"));
+        // The tokens are received by the MASP address with the MASP
+        // transaction
+        assert!(env.verifiers.contains(&masp));
+        tx_host_env::set(env);
+        let key = ibc::balance_key_with_ibc_prefix(denom, &masp);
+        let balance: Option<Amount> = tx_host_env::with(|env| {
+            env.wl_storage.read(&key).expect("read error")
+        });
+        assert_eq!(balance, Some(Amount::from_u64(100)));
+        assert_eq!(masp_head_tx_idx(), Some(1));
+
+        // A payment address can't receive tokens without a MASP transaction
+        let msg = ibc::msg_packet_recv(shielded_packet(2, String::new()));
+        let mut tx_data = vec![];
+        msg.to_any().encode(&mut tx_data).expect("encoding failed");
+        let result = tx_host_env::ibc::ibc_actions(tx::ctx()).execute(&tx_data);
+        assert!(matches!(result, Err(IbcActionError::MaspTx(_))));
+
+        // The relayer can't shield the tokens with its own MASP transaction,
+        // which could pay anyone
+        let msg = ibc::msg_packet_recv(shielded_packet(3, String::new()));
+        let mut tx_data = vec![];
+        msg.to_any().encode(&mut tx_data).expect("encoding failed");
+        let mut actions = tx_host_env::ibc::ibc_actions(tx::ctx());
+        actions.set_shielded_transfer(shielded);
+        let result = actions.execute(&tx_data);
+        assert!(matches!(result, Err(IbcActionError::MaspTx(_))));
+        assert_eq!(masp_head_tx_idx(), Some(1));
+    }
 }
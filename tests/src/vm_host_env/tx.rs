@@ -447,7 +447,22 @@ mod native_tx_host_env {
         result_ptr: u64
     ));
     native_host_fn!(tx_emit_ibc_event(event_ptr: u64, event_len: u64));
+    native_host_fn!(tx_emit_event(event_ptr: u64, event_len: u64));
     native_host_fn!(tx_get_ibc_event(event_type_ptr: u64, event_type_len: u64) -> i64);
+    native_host_fn!(tx_call(
+        target_ptr: u64,
+        target_len: u64,
+        code_hash_ptr: u64,
+        code_hash_len: u64,
+        input_ptr: u64,
+        input_len: u64,
+    ) -> i64);
+    native_host_fn!(tx_deploy_code(
+        code_ptr: u64,
+        code_len: u64,
+        result_ptr: u64
+    ));
+    native_host_fn!(tx_set_call_output(output_ptr: u64, output_len: u64));
     native_host_fn!(tx_get_chain_id(result_ptr: u64));
     native_host_fn!(tx_get_block_height() -> u64);
     native_host_fn!(tx_get_tx_index() -> u32);
@@ -457,4 +472,20 @@ mod native_tx_host_env {
     native_host_fn!(tx_get_native_token(result_ptr: u64));
     native_host_fn!(tx_log_string(str_ptr: u64, str_len: u64));
     native_host_fn!(tx_charge_gas(used_gas: u64));
+    native_host_fn!(tx_sha256(data_ptr: u64, data_len: u64, result_ptr: u64));
+    native_host_fn!(tx_keccak256(
+        data_ptr: u64,
+        data_len: u64,
+        result_ptr: u64
+    ));
+    native_host_fn!(tx_blake2b(data_ptr: u64, data_len: u64, result_ptr: u64));
+    native_host_fn!(tx_verify_signature(
+        pk_ptr: u64,
+        pk_len: u64,
+        sig_ptr: u64,
+        sig_len: u64,
+        msg_ptr: u64,
+        msg_len: u64,
+        hash_mode: u64,
+    ) -> i64);
 }
@@ -385,4 +385,20 @@ mod native_vp_host_env {
         -> i64
     );
     native_host_fn!(vp_charge_gas(used_gas: u64));
+    native_host_fn!(vp_sha256(data_ptr: u64, data_len: u64, result_ptr: u64));
+    native_host_fn!(vp_keccak256(
+        data_ptr: u64,
+        data_len: u64,
+        result_ptr: u64
+    ));
+    native_host_fn!(vp_blake2b(data_ptr: u64, data_len: u64, result_ptr: u64));
+    native_host_fn!(vp_verify_signature(
+        pk_ptr: u64,
+        pk_len: u64,
+        sig_ptr: u64,
+        sig_len: u64,
+        msg_ptr: u64,
+        msg_len: u64,
+        hash_mode: u64,
+    ) -> i64);
 }
@@ -0,0 +1,923 @@
+//! Differential tests of the native user VP against the wasm `vp_user`. Every
+//! scenario applies a tx and validates it with both VPs, which must agree.
+//!
+//! The wasm `vp_user` has to be built first, e.g. with
+//! `make build-wasm-scripts`.
+
+use std::path::PathBuf;
+
+use namada::core::ledger::governance::storage::keys as gov_storage;
+use namada::core::ledger::pgf::storage::keys as pgf_storage;
+use namada::core::types::account::{
+    AccountGuardians, AccountPolicy, AccountPublicKeysMap, SessionKey,
+};
+use namada::core::types::transaction::account::RecoveryAction;
+use namada::ledger::gas::VpGasMeter;
+use namada::ledger::native_vp::user::{UserVp, NATIVE_VP_USER_CODE_HASH};
+use namada::ledger::pos::{GenesisValidator, PosParams};
+use namada::ledger::storage_api;
+use namada::ledger::storage_api::collections::lazy_map;
+use namada::proto::{Code, Data, MultiSignature, Section};
+use namada::types::address::{self, Address};
+use namada::types::dec::Dec;
+use namada::types::hash::Hash;
+use namada::types::key::{self, common, RefTo};
+use namada::types::storage::{Epoch, Key, KeySeg};
+use namada::types::token;
+use namada::vm::wasm;
+use namada_apps::wasm_loader;
+use namada_test_utils::TestWasms;
+use namada_tx_prelude::{StorageWrite, TxEnv};
+use test_log::test;
+
+use crate::native_vp::pos::init_pos;
+use crate::native_vp::TestNativeVpEnv;
+use crate::tx::{self, tx_host_env, TestTxEnv};
+
+const VP_USER_WASM: &str = "vp_user.wasm";
+
+/// Gets the absolute path to wasm directory
+fn wasm_dir() -> PathBuf {
+    let mut current_path = std::env::current_dir()
+        .expect("Current directory should exist")
+        .canonicalize()
+        .expect("Current directory should exist");
+    while current_path.file_name().unwrap() != "tests" {
+        current_path.pop();
+    }
+    current_path.pop();
+    current_path.join("wasm")
+}
+
+/// Apply the tx changes from `apply_tx`, optionally sign the tx with the
+/// `signer` key and validate the changes for the `vp_owner` with both the wasm
+/// and the native user VP. Asserts that both VPs agree and returns whether
+/// the tx has been accepted.
+fn validate_with_both(
+    tx_env: TestTxEnv,
+    vp_owner: &Address,
+    signer: Option<common::SecretKey>,
+    apply_tx: impl FnOnce(),
+) -> bool {
+    tx_host_env::set(tx_env);
+    apply_tx();
+    let mut tx_env = tx_host_env::take();
+
+    if let Some(keypair) = signer {
+        let pks_map = AccountPublicKeysMap::from_iter(vec![keypair.ref_to()]);
+        let tx = &mut tx_env.tx;
+        tx.set_data(Data::new(vec![]));
+        tx.set_code(Code::new(vec![]));
+        tx.add_section(Section::SectionSignature(MultiSignature::new(
+            vec![*tx.data_sechash(), *tx.code_sechash()],
+            &[keypair],
+            &pks_map,
+        )));
+    }
+
+    let vp_code = wasm_loader::read_wasm_or_exit(wasm_dir(), VP_USER_WASM);
+    let vp_hash = Hash::sha256(&vp_code);
+    tx_env.store_wasm_code(vp_code);
+
+    let keys_changed = tx_env.all_touched_storage_keys();
+    let verifiers = tx_env.get_verifiers();
+    let mut gas_meter = VpGasMeter::new_from_tx_meter(&tx_env.gas_meter);
+    let wasm_result = wasm::run::vp(
+        &vp_hash,
+        &tx_env.tx,
+        &tx_env.tx_index,
+        vp_owner,
+        &tx_env.wl_storage.storage,
+        &tx_env.wl_storage.write_log,
+        &mut gas_meter,
+        &keys_changed,
+        &verifiers,
+        tx_env.vp_wasm_cache.clone(),
+        #[cfg(not(feature = "mainnet"))]
+        false,
+    );
+
+    let native_env = TestNativeVpEnv::from_tx_env(tx_env, vp_owner.clone());
+    let native_result = native_env.validate_tx(|ctx| UserVp { ctx });
+
+    // The wasm VP may fail with an error where the native VP rejects
+    let wasm_accepted = matches!(wasm_result, Ok(true));
+    let native_accepted = matches!(native_result, Ok(true));
+    assert_eq!(
+        wasm_accepted, native_accepted,
+        "The user VPs disagree, wasm: {wasm_result:?}, native: \
+         {native_result:?}"
+    );
+    native_accepted
+}
+
+/// Initialize a tx env with a `vp_owner` account, that has the public key of
+/// [`key::testing::keypair_1`], and with some NAM credited to it
+fn init_tx_env(vp_owner: &Address) -> TestTxEnv {
+    let mut tx_env = TestTxEnv::default();
+    tx_env.init_parameters(None, None, None, None);
+    init_account(&mut tx_env, vp_owner);
+    tx_env
+}
+
+fn init_account(tx_env: &mut TestTxEnv, vp_owner: &Address) {
+    let token = address::nam();
+    tx_env.spawn_accounts([vp_owner, &token]);
+    tx_env.init_account_storage(
+        vp_owner,
+        vec![key::testing::keypair_1().ref_to()],
+        1,
+    );
+    storage_api::token::write_denom(
+        &mut tx_env.wl_storage,
+        &token,
+        token::NATIVE_MAX_DECIMAL_PLACES.into(),
+    )
+    .unwrap();
+    tx_env.credit_tokens(vp_owner, &token, amount().amount);
+}
+
+fn amount() -> token::DenominatedAmount {
+    token::DenominatedAmount {
+        amount: token::Amount::from_uint(10_098_123, 0).unwrap(),
+        denom: token::NATIVE_MAX_DECIMAL_PLACES.into(),
+    }
+}
+
+fn transfer(source: &Address, target: &Address) {
+    tx_host_env::token::transfer(
+        tx::ctx(),
+        source,
+        target,
+        &address::nam(),
+        amount(),
+        &None,
+        &None,
+        &None,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_no_op_tx() {
+    let vp_owner = address::testing::established_address_1();
+    let tx_env = init_tx_env(&vp_owner);
+
+    assert!(validate_with_both(tx_env, &vp_owner, None, || {}));
+}
+
+#[test]
+fn test_credit_transfer() {
+    let vp_owner = address::testing::established_address_1();
+    let source = address::testing::established_address_2();
+    let mut tx_env = init_tx_env(&vp_owner);
+    init_account(&mut tx_env, &source);
+
+    assert!(validate_with_both(tx_env, &vp_owner, None, || {
+        transfer(&source, &vp_owner)
+    }));
+}
+
+#[test]
+fn test_debit_transfer() {
+    let vp_owner = address::testing::established_address_1();
+    let target = address::testing::established_address_2();
+    let init = || {
+        let mut tx_env = init_tx_env(&vp_owner);
+        tx_env.spawn_accounts([&target]);
+        tx_env
+    };
+    let apply_tx = || transfer(&vp_owner, &target);
+
+    assert!(!validate_with_both(init(), &vp_owner, None, apply_tx));
+    assert!(!validate_with_both(
+        init(),
+        &vp_owner,
+        Some(key::testing::keypair_2()),
+        apply_tx
+    ));
+    assert!(validate_with_both(
+        init(),
+        &vp_owner,
+        Some(key::testing::keypair_1()),
+        apply_tx
+    ));
+}
+
+#[test]
+fn test_spending_policy() {
+    let vp_owner = address::testing::established_address_1();
+    let target = address::testing::established_address_2();
+    let other = address::testing::established_address_3();
+    let token = address::nam();
+    let init = |policy: AccountPolicy| {
+        let mut tx_env = init_tx_env(&vp_owner);
+        tx_env.spawn_accounts([&target]);
+        storage_api::account::set_policy(
+            &mut tx_env.wl_storage,
+            &vp_owner,
+            &policy,
+        )
+        .unwrap();
+        tx_env
+    };
+    let apply_tx = || transfer(&vp_owner, &target);
+    let signer = || Some(key::testing::keypair_1());
+    let small_amount = token::Amount::from_uint(1, 0).unwrap();
+
+    // A debit within the outflow limit is accepted
+    let within_limit = AccountPolicy {
+        outflow_limits: [(token.clone(), amount().amount)].into(),
+        ..Default::default()
+    };
+    assert!(validate_with_both(
+        init(within_limit.clone()),
+        &vp_owner,
+        signer(),
+        apply_tx
+    ));
+
+    // A debit above the outflow limit is rejected
+    let above_limit = AccountPolicy {
+        outflow_limits: [(token.clone(), small_amount)].into(),
+        ..Default::default()
+    };
+    assert!(!validate_with_both(
+        init(above_limit),
+        &vp_owner,
+        signer(),
+        apply_tx
+    ));
+
+    // A limited debit that's not recorded is rejected
+    assert!(!validate_with_both(
+        init(within_limit),
+        &vp_owner,
+        signer(),
+        || {
+            let balance_key = token::balance_key(&token, &vp_owner);
+            tx::ctx()
+                .write(&balance_key, token::Amount::default())
+                .unwrap();
+        }
+    ));
+
+    // A debit to a recipient that's not allowed is rejected
+    let allowed_recipients = AccountPolicy {
+        allowed_recipients: [other].into(),
+        ..Default::default()
+    };
+    assert!(!validate_with_both(
+        init(allowed_recipients),
+        &vp_owner,
+        signer(),
+        apply_tx
+    ));
+
+    // A large debit requires the large debit threshold
+    let large_debit = AccountPolicy {
+        large_debit_amounts: [(token.clone(), small_amount)].into(),
+        large_debit_threshold: Some(2),
+        ..Default::default()
+    };
+    assert!(!validate_with_both(
+        init(large_debit.clone()),
+        &vp_owner,
+        signer(),
+        apply_tx
+    ));
+
+    // Adding a key or changing the threshold requires the large debit
+    // threshold, so that it cannot be used to bypass the policy
+    let add_key = || {
+        storage_api::account::set_public_key_at(
+            tx::ctx(),
+            &vp_owner,
+            &key::testing::keypair_2().ref_to(),
+            1,
+        )
+        .unwrap();
+    };
+    let update_threshold = || {
+        tx::ctx()
+            .write(&key::threshold_key(&vp_owner), 1_u8)
+            .unwrap();
+    };
+    assert!(!validate_with_both(
+        init(large_debit.clone()),
+        &vp_owner,
+        signer(),
+        add_key
+    ));
+    assert!(!validate_with_both(
+        init(large_debit),
+        &vp_owner,
+        signer(),
+        update_threshold
+    ));
+
+    // Without a large debit threshold, the account's threshold is enough
+    assert!(validate_with_both(
+        init(AccountPolicy::default()),
+        &vp_owner,
+        signer(),
+        add_key
+    ));
+    assert!(validate_with_both(
+        init(AccountPolicy::default()),
+        &vp_owner,
+        signer(),
+        update_threshold
+    ));
+}
+
+#[test]
+fn test_recovery() {
+    let vp_owner = address::testing::established_address_1();
+    let guardian_1 = address::testing::established_address_2();
+    let guardian_2 = address::testing::established_address_3();
+    let new_key = key::testing::keypair_4();
+    // Initialize the account with guardians and optionally with a recovery
+    // approved by the given guardians
+    let init = |recovery_delay: u64, approvals: &[&Address]| {
+        let mut tx_env = init_tx_env(&vp_owner);
+        tx_env.spawn_accounts([&guardian_1, &guardian_2]);
+        tx_env.init_account_storage(
+            &guardian_1,
+            vec![key::testing::keypair_2().ref_to()],
+            1,
+        );
+        tx_env.init_account_storage(
+            &guardian_2,
+            vec![key::testing::keypair_3().ref_to()],
+            1,
+        );
+        let guardians = AccountGuardians {
+            guardians: [guardian_1.clone(), guardian_2.clone()].into(),
+            threshold: 2,
+            recovery_delay,
+        };
+        storage_api::account::set_guardians(
+            &mut tx_env.wl_storage,
+            &vp_owner,
+            &guardians,
+        )
+        .unwrap();
+        if let Some((first, rest)) = approvals.split_first() {
+            let request = RecoveryAction::Request {
+                guardian: (*first).clone(),
+                public_keys: vec![new_key.ref_to()],
+                threshold: 1,
+            };
+            storage_api::account::recover_account(
+                &mut tx_env.wl_storage,
+                &vp_owner,
+                request,
+            )
+            .unwrap();
+            for guardian in rest {
+                let approve = RecoveryAction::Approve {
+                    guardian: (*guardian).clone(),
+                };
+                storage_api::account::recover_account(
+                    &mut tx_env.wl_storage,
+                    &vp_owner,
+                    approve,
+                )
+                .unwrap();
+            }
+        }
+        tx_env
+    };
+    let owner = &vp_owner;
+    let recover = |action: RecoveryAction| {
+        move || {
+            storage_api::account::recover_account(tx::ctx(), owner, action)
+                .unwrap()
+        }
+    };
+    let request = || {
+        recover(RecoveryAction::Request {
+            guardian: guardian_1.clone(),
+            public_keys: vec![new_key.ref_to()],
+            threshold: 1,
+        })
+    };
+    let approve = || {
+        recover(RecoveryAction::Approve {
+            guardian: guardian_2.clone(),
+        })
+    };
+
+    // A recovery request must be signed by the requesting guardian
+    assert!(!validate_with_both(
+        init(1, &[]),
+        &vp_owner,
+        None,
+        request()
+    ));
+    assert!(!validate_with_both(
+        init(1, &[]),
+        &vp_owner,
+        Some(key::testing::keypair_1()),
+        request()
+    ));
+    assert!(validate_with_both(
+        init(1, &[]),
+        &vp_owner,
+        Some(key::testing::keypair_2()),
+        request()
+    ));
+
+    // An approval must be signed by the approving guardian
+    assert!(!validate_with_both(
+        init(1, &[&guardian_1]),
+        &vp_owner,
+        Some(key::testing::keypair_2()),
+        approve()
+    ));
+    assert!(validate_with_both(
+        init(1, &[&guardian_1]),
+        &vp_owner,
+        Some(key::testing::keypair_3()),
+        approve()
+    ));
+
+    // A recovery can be cancelled only by the account
+    let cancel = || recover(RecoveryAction::Cancel);
+    assert!(!validate_with_both(
+        init(1, &[&guardian_1]),
+        &vp_owner,
+        Some(key::testing::keypair_2()),
+        cancel()
+    ));
+    assert!(validate_with_both(
+        init(1, &[&guardian_1]),
+        &vp_owner,
+        Some(key::testing::keypair_1()),
+        cancel()
+    ));
+
+    // An approved recovery replaces the keys without the account's signature
+    assert!(validate_with_both(
+        init(0, &[&guardian_1, &guardian_2]),
+        &vp_owner,
+        None,
+        recover(RecoveryAction::Execute)
+    ));
+
+    // The keys cannot be replaced before the delay has passed
+    assert!(!validate_with_both(
+        init(1, &[&guardian_1, &guardian_2]),
+        &vp_owner,
+        None,
+        || {
+            storage_api::account::clear_public_keys(tx::ctx(), &vp_owner)
+                .unwrap();
+            storage_api::account::set_public_key_at(
+                tx::ctx(),
+                &vp_owner,
+                &new_key.ref_to(),
+                0,
+            )
+            .unwrap();
+            tx::ctx().delete(&key::recovery_key(&vp_owner)).unwrap();
+        }
+    ));
+}
+
+#[test]
+fn test_session_key() {
+    let vp_owner = address::testing::established_address_1();
+    let target = address::testing::established_address_2();
+    let session_keypair = key::testing::keypair_2();
+    // The signed txs have an empty code section
+    let tx_code_hash = Code::new(vec![]).code.hash();
+    // Initialize the account with a session key, optionally in a later epoch
+    let init = |allowed_tx: Hash, max_spend: token::Amount, epoch: u64| {
+        let mut tx_env = init_tx_env(&vp_owner);
+        tx_env.spawn_accounts([&target]);
+        let session_key = SessionKey {
+            allowed_txs: [allowed_tx].into(),
+            max_spend: [(address::nam(), max_spend)].into(),
+            expiry: Epoch(0),
+            spent: Default::default(),
+        };
+        storage_api::account::add_session_key(
+            &mut tx_env.wl_storage,
+            &vp_owner,
+            &session_keypair.ref_to(),
+            &session_key,
+        )
+        .unwrap();
+        tx_env.wl_storage.storage.block.epoch = Epoch(epoch);
+        tx_env
+    };
+    // Debit the account and record it in the session key's spend
+    let apply_tx = || {
+        transfer(&vp_owner, &target);
+        storage_api::account::record_session_key_spend(
+            tx::ctx(),
+            &vp_owner,
+            &session_keypair.ref_to(),
+            &address::nam(),
+            amount().amount,
+        )
+        .unwrap();
+    };
+    // Initialize the account with a session key that has already been used
+    // for a debit in a committed tx
+    let init_spent = |max_spend: token::Amount| {
+        let mut tx_env = init(tx_code_hash, max_spend, 0);
+        let balance = amount().amount.checked_add(amount().amount).unwrap();
+        tx_env.credit_tokens(&vp_owner, &address::nam(), balance);
+        tx_host_env::set(tx_env);
+        apply_tx();
+        let mut tx_env = tx_host_env::take();
+        tx_env.wl_storage.commit_tx();
+        tx_env
+    };
+
+    // A debit within the maximum spend can be signed by the session key
+    assert!(validate_with_both(
+        init(tx_code_hash, amount().amount, 0),
+        &vp_owner,
+        Some(session_keypair.clone()),
+        apply_tx
+    ));
+    // ... but only if it's recorded in the session key's spend
+    assert!(!validate_with_both(
+        init(tx_code_hash, amount().amount, 0),
+        &vp_owner,
+        Some(session_keypair.clone()),
+        || transfer(&vp_owner, &target)
+    ));
+    // ... and the maximum spend is cumulative, so repeating the debit is
+    // rejected once the previous debits used it up
+    assert!(!validate_with_both(
+        init_spent(amount().amount),
+        &vp_owner,
+        Some(session_keypair.clone()),
+        apply_tx
+    ));
+    assert!(validate_with_both(
+        init_spent(amount().amount.checked_add(amount().amount).unwrap()),
+        &vp_owner,
+        Some(session_keypair.clone()),
+        apply_tx
+    ));
+    // ... but not a debit over it
+    assert!(!validate_with_both(
+        init(tx_code_hash, token::Amount::from_uint(1, 0).unwrap(), 0),
+        &vp_owner,
+        Some(session_keypair.clone()),
+        apply_tx
+    ));
+    // ... nor a tx that's not allowed
+    assert!(!validate_with_both(
+        init(Hash::zero(), amount().amount, 0),
+        &vp_owner,
+        Some(session_keypair.clone()),
+        apply_tx
+    ));
+    // ... nor a tx after the session key's expiry
+    assert!(!validate_with_both(
+        init(tx_code_hash, amount().amount, 1),
+        &vp_owner,
+        Some(session_keypair.clone()),
+        apply_tx
+    ));
+
+    // The session key cannot change the account's storage
+    assert!(!validate_with_both(
+        init(tx_code_hash, amount().amount, 0),
+        &vp_owner,
+        Some(session_keypair.clone()),
+        || {
+            tx::ctx()
+                .write(&key::threshold_key(&vp_owner), 2_u8)
+                .unwrap();
+        }
+    ));
+    // ... nor vote in governance, even with an allowed tx
+    assert!(!validate_with_both(
+        init(tx_code_hash, amount().amount, 0),
+        &vp_owner,
+        Some(session_keypair.clone()),
+        || {
+            let vote_key = gov_storage::get_vote_proposal_key(
+                0,
+                vp_owner.clone(),
+                target.clone(),
+            );
+            tx::ctx().write_bytes(&vote_key, [0]).unwrap();
+        }
+    ));
+    // ... nor the session key's scope
+    assert!(!validate_with_both(
+        init(tx_code_hash, token::Amount::from_uint(1, 0).unwrap(), 0),
+        &vp_owner,
+        Some(session_keypair.clone()),
+        || {
+            let session_key = SessionKey {
+                allowed_txs: [tx_code_hash].into(),
+                max_spend: [(address::nam(), amount().amount)].into(),
+                expiry: Epoch(0),
+                spent: Default::default(),
+            };
+            storage_api::account::add_session_key(
+                tx::ctx(),
+                &vp_owner,
+                &key::testing::keypair_2().ref_to(),
+                &session_key,
+            )
+            .unwrap();
+        }
+    ));
+    assert!(!validate_with_both(
+        init(tx_code_hash, amount().amount, 0),
+        &vp_owner,
+        Some(session_keypair.clone()),
+        || {
+            storage_api::account::revoke_session_key(
+                tx::ctx(),
+                &vp_owner,
+                &key::testing::keypair_2().ref_to(),
+            )
+            .unwrap();
+        }
+    ));
+}
+
+#[test]
+fn test_pos_bond_and_unbond() {
+    let validator = address::testing::established_address_3();
+    let vp_owner = address::testing::established_address_2();
+    let init = || {
+        let genesis_validators = [GenesisValidator {
+            address: validator.clone(),
+            tokens: token::Amount::from_uint(10_098_123, 0).unwrap(),
+            consensus_key: key::testing::keypair_2().ref_to(),
+            eth_cold_key: key::testing::keypair_3().ref_to(),
+            eth_hot_key: key::testing::keypair_4().ref_to(),
+            commission_rate: Dec::new(5, 2).unwrap(),
+            max_commission_rate_change: Dec::new(1, 2).unwrap(),
+        }];
+        init_pos(&genesis_validators[..], &PosParams::default(), Epoch(0));
+        let mut tx_env = tx_host_env::take();
+        tx_env.init_parameters(None, None, None, None);
+        init_account(&mut tx_env, &vp_owner);
+        tx_env
+    };
+    let bond_amount = token::Amount::from_uint(5_098_123, 0).unwrap();
+    let apply_tx = || {
+        let unbond_amount = token::Amount::from_uint(3_098_123, 0).unwrap();
+        tx::ctx()
+            .bond_tokens(Some(&vp_owner), &validator, bond_amount)
+            .unwrap();
+        tx::ctx()
+            .unbond_tokens(Some(&vp_owner), &validator, unbond_amount)
+            .unwrap();
+    };
+
+    assert!(!validate_with_both(init(), &vp_owner, None, apply_tx));
+    assert!(validate_with_both(
+        init(),
+        &vp_owner,
+        Some(key::testing::keypair_1()),
+        apply_tx
+    ));
+
+    // A session key cannot bond, even with an allowed tx and a debit within
+    // its maximum spend
+    let session_keypair = key::testing::keypair_3();
+    let mut tx_env = init();
+    let session_key = SessionKey {
+        allowed_txs: [Code::new(vec![]).code.hash()].into(),
+        max_spend: [(address::nam(), bond_amount)].into(),
+        expiry: Epoch(0),
+        spent: Default::default(),
+    };
+    storage_api::account::add_session_key(
+        &mut tx_env.wl_storage,
+        &vp_owner,
+        &session_keypair.ref_to(),
+        &session_key,
+    )
+    .unwrap();
+    assert!(!validate_with_both(
+        tx_env,
+        &vp_owner,
+        Some(session_keypair.clone()),
+        || {
+            apply_tx();
+            storage_api::account::record_session_key_spend(
+                tx::ctx(),
+                &vp_owner,
+                &session_keypair.ref_to(),
+                &address::nam(),
+                bond_amount,
+            )
+            .unwrap();
+        }
+    ));
+}
+
+#[test]
+fn test_governance_vote() {
+    let vp_owner = address::testing::established_address_1();
+    let validator = address::testing::established_address_2();
+    let init = || init_tx_env(&vp_owner);
+    let apply_tx = || {
+        let vote_key = gov_storage::get_vote_proposal_key(
+            0,
+            vp_owner.clone(),
+            validator.clone(),
+        );
+        tx::ctx().write_bytes(&vote_key, [0]).unwrap();
+    };
+
+    assert!(!validate_with_both(init(), &vp_owner, None, apply_tx));
+    assert!(validate_with_both(
+        init(),
+        &vp_owner,
+        Some(key::testing::keypair_1()),
+        apply_tx
+    ));
+}
+
+#[test]
+fn test_pgf_steward() {
+    let vp_owner = address::testing::established_address_1();
+    let init = || init_tx_env(&vp_owner);
+    let apply_tx = || {
+        let steward_key = pgf_storage::stewards_key_prefix()
+            .push(&lazy_map::DATA_SUBKEY.to_owned())
+            .and_then(|key| key.push(&vp_owner))
+            .unwrap();
+        tx::ctx().write_bytes(&steward_key, [0]).unwrap();
+    };
+
+    assert!(!validate_with_both(init(), &vp_owner, None, apply_tx));
+    assert!(validate_with_both(
+        init(),
+        &vp_owner,
+        Some(key::testing::keypair_1()),
+        apply_tx
+    ));
+}
+
+#[test]
+fn test_vp_update() {
+    let vp_owner = address::testing::established_address_1();
+    let vp_code = TestWasms::VpAlwaysTrue.read_bytes();
+    let vp_hash = Hash::sha256(&vp_code);
+    let init = || {
+        let mut tx_env = init_tx_env(&vp_owner);
+        tx_env.store_wasm_code(vp_code.clone());
+        tx_env
+    };
+    let apply_tx = || {
+        tx::ctx()
+            .update_validity_predicate(&vp_owner, vp_hash)
+            .unwrap();
+    };
+
+    assert!(!validate_with_both(init(), &vp_owner, None, apply_tx));
+    assert!(validate_with_both(
+        init(),
+        &vp_owner,
+        Some(key::testing::keypair_1()),
+        apply_tx
+    ));
+
+    // A VP that's not whitelisted is rejected even with a valid signature
+    let mut tx_env = init();
+    tx_env.init_parameters(
+        None,
+        Some(vec!["some_hash".to_string()]),
+        None,
+        None,
+    );
+    assert!(!validate_with_both(
+        tx_env,
+        &vp_owner,
+        Some(key::testing::keypair_1()),
+        apply_tx
+    ));
+
+    // The native user VP can be opted into even when it's not whitelisted
+    let mut tx_env = init();
+    tx_env.init_parameters(
+        None,
+        Some(vec!["some_hash".to_string()]),
+        None,
+        None,
+    );
+    assert!(validate_with_both(
+        tx_env,
+        &vp_owner,
+        Some(key::testing::keypair_1()),
+        || {
+            tx::ctx()
+                .update_validity_predicate(&vp_owner, NATIVE_VP_USER_CODE_HASH)
+                .unwrap();
+        }
+    ));
+}
+
+#[test]
+fn test_masp() {
+    let vp_owner = address::testing::established_address_1();
+    let masp = address::masp();
+    let init = || {
+        let mut tx_env = init_tx_env(&vp_owner);
+        init_account(&mut tx_env, &masp);
+        tx_env
+    };
+    let masp_key = || {
+        Key::from(masp.to_db_key())
+            .push(&token::HEAD_TX_KEY.to_owned())
+            .unwrap()
+    };
+
+    // Changes of the MASP keys don't need the account's signature
+    assert!(validate_with_both(init(), &vp_owner, None, || {
+        tx::ctx().write(&masp_key(), 1_u64).unwrap();
+    }));
+
+    // Shielding the account's tokens needs its signature
+    let shield = || {
+        transfer(&vp_owner, &masp);
+        tx::ctx().write(&masp_key(), 1_u64).unwrap();
+    };
+    assert!(!validate_with_both(init(), &vp_owner, None, shield));
+    assert!(validate_with_both(
+        init(),
+        &vp_owner,
+        Some(key::testing::keypair_1()),
+        shield
+    ));
+
+    // Debits of the MASP's own balance are left to the MASP VP
+    assert!(validate_with_both(init(), &masp, None, || {
+        transfer(&masp, &vp_owner);
+        tx::ctx().write(&masp_key(), 1_u64).unwrap();
+    }));
+}
+
+#[test]
+fn test_own_storage_write() {
+    let vp_owner = address::testing::established_address_1();
+    let init = || init_tx_env(&vp_owner);
+    let apply_tx = || {
+        let key = Key::from(vp_owner.to_db_key())
+            .push(&"some_key".to_owned())
+            .unwrap();
+        tx::ctx().write(&key, "some_value".to_owned()).unwrap();
+    };
+
+    assert!(!validate_with_both(init(), &vp_owner, None, apply_tx));
+    assert!(validate_with_both(
+        init(),
+        &vp_owner,
+        Some(key::testing::keypair_1()),
+        apply_tx
+    ));
+}
+
+#[test]
+fn test_foreign_storage_write() {
+    let vp_owner = address::testing::established_address_1();
+    let other = address::testing::established_address_2();
+    let mut tx_env = init_tx_env(&vp_owner);
+    init_account(&mut tx_env, &other);
+
+    // Changes outside of the owner's sub-space don't need its signature
+    assert!(validate_with_both(tx_env, &vp_owner, None, || {
+        let key = Key::from(other.to_db_key())
+            .push(&"some_key".to_owned())
+            .unwrap();
+        tx::ctx().write(&key, "some_value".to_owned()).unwrap();
+        transfer(&other, &vp_owner);
+    }));
+}
+
+#[test]
+fn test_tx_not_whitelisted() {
+    let vp_owner = address::testing::established_address_1();
+    let mut tx_env = init_tx_env(&vp_owner);
+    tx_env.init_parameters(
+        None,
+        None,
+        Some(vec!["some_hash".to_string()]),
+        None,
+    );
+
+    assert!(!validate_with_both(
+        tx_env,
+        &vp_owner,
+        Some(key::testing::keypair_1()),
+        || {}
+    ));
+}
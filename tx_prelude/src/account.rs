@@ -1,3 +1,6 @@
+use namada_core::types::account::AccountPublicKeysMap;
+use namada_core::types::key::common;
+use namada_core::types::token;
 use namada_core::types::transaction::account::InitAccount;
 
 use super::*;
@@ -16,3 +19,49 @@ pub fn init_account(
 
     Ok(owner.to_owned())
 }
+
+/// Record a debit of a token from an account against the spend of the
+/// account's session keys that signed the tx. The user VP rejects debits
+/// authorized by a session key that haven't been recorded.
+pub fn record_session_key_spend(
+    ctx: &mut Ctx,
+    tx: &Tx,
+    owner: &Address,
+    token: &Address,
+    amount: token::Amount,
+) -> EnvResult<()> {
+    let epoch = ctx.get_block_epoch()?;
+    for (public_key, session_key) in
+        storage_api::account::session_keys(ctx, owner)?
+    {
+        if epoch > session_key.expiry
+            || !session_key.max_spend.contains_key(token)
+            || !is_signed_by(tx, &public_key)
+        {
+            continue;
+        }
+        storage_api::account::record_session_key_spend(
+            ctx,
+            owner,
+            &public_key,
+            token,
+            amount,
+        )?;
+    }
+    Ok(())
+}
+
+/// Check if any of the tx's section signatures is made with the given key
+fn is_signed_by(tx: &Tx, public_key: &common::PublicKey) -> bool {
+    let public_keys_index_map =
+        AccountPublicKeysMap::from_iter([public_key.clone()]);
+    tx.sections.iter().any(|section| match section {
+        Section::SectionSignature(signatures) => {
+            let raw_hash = signatures.get_raw_hash();
+            signatures.signatures.iter().any(|signature| {
+                signature.verify(&public_keys_index_map, &raw_hash).is_ok()
+            })
+        }
+        _ => false,
+    })
+}
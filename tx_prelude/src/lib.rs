@@ -11,6 +11,7 @@ pub mod ibc;
 pub mod key;
 pub mod pgf;
 pub mod proof_of_stake;
+pub mod scheduled_txs;
 pub mod token;
 
 use core::slice;
@@ -36,7 +37,8 @@ pub use namada_core::types::storage::{
     self, BlockHash, BlockHeight, Epoch, Header, BLOCK_HASH_LENGTH,
 };
 pub use namada_core::types::{eth_bridge_pool, *};
-pub use namada_macros::transaction;
+pub use namada_macros::{transaction, tx_call};
+pub use namada_vm_env::crypto::tx as crypto;
 use namada_vm_env::tx::*;
 use namada_vm_env::{read_from_buffer, read_key_val_bytes_from_buffer};
 
@@ -48,6 +50,14 @@ pub fn log_string<T: AsRef<str>>(msg: T) {
     }
 }
 
+/// Set the output returned to the caller of a wasm code called with
+/// [`TxEnv::call`]. This is used by the `#[tx_call]` macro.
+pub fn set_call_output(output: &[u8]) {
+    unsafe {
+        namada_tx_set_call_output(output.as_ptr() as _, output.len() as _);
+    }
+}
+
 /// Format and log a string in a debug build.
 ///
 /// In WASM target debug build, the message will be printed at the
@@ -326,6 +336,49 @@ impl TxEnv for Ctx {
         Ok(())
     }
 
+    fn emit_event(&mut self, event: &event::TxEvent) -> Result<(), Error> {
+        let event = BorshSerialize::try_to_vec(event).unwrap();
+        unsafe { namada_tx_emit_event(event.as_ptr() as _, event.len() as _) };
+        Ok(())
+    }
+
+    fn call(
+        &mut self,
+        target: &Address,
+        code_hash: &hash::Hash,
+        input: impl AsRef<[u8]>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let target = target.encode();
+        let input = input.as_ref();
+        let call_result = unsafe {
+            namada_tx_call(
+                target.as_ptr() as _,
+                target.len() as _,
+                code_hash.0.as_ptr() as _,
+                code_hash.0.len() as _,
+                input.as_ptr() as _,
+                input.len() as _,
+            )
+        };
+        Ok(read_from_buffer(call_result, namada_tx_result_buffer))
+    }
+
+    fn deploy_code(
+        &mut self,
+        code: impl AsRef<[u8]>,
+    ) -> Result<hash::Hash, Error> {
+        let code = code.as_ref();
+        let mut result = [0u8; hash::HASH_LENGTH];
+        unsafe {
+            namada_tx_deploy_code(
+                code.as_ptr() as _,
+                code.len() as _,
+                result.as_mut_ptr() as _,
+            )
+        };
+        Ok(hash::Hash(result))
+    }
+
     fn charge_gas(&mut self, used_gas: u64) -> Result<(), Error> {
         unsafe { namada_tx_charge_gas(used_gas) };
         Ok(())
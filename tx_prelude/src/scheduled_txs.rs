@@ -0,0 +1,23 @@
+use namada_core::types::transaction::scheduled::{
+    CancelScheduledTx, ScheduledTx,
+};
+
+use super::*;
+
+pub fn schedule_tx(ctx: &mut Ctx, data: ScheduledTx) -> EnvResult<()> {
+    // The owner must authorize the scheduling, not only the fee escrow
+    ctx.insert_verifier(&data.owner)?;
+    storage_api::scheduled_txs::schedule_tx(ctx, data)?;
+
+    Ok(())
+}
+
+pub fn cancel_scheduled_tx(
+    ctx: &mut Ctx,
+    data: &CancelScheduledTx,
+) -> EnvResult<()> {
+    ctx.insert_verifier(&data.owner)?;
+    storage_api::scheduled_txs::cancel_scheduled_tx(ctx, data)?;
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+//! Cryptographic hash functions and signature verification, computed by the
+//! host. These are much cheaper in gas than running the same code in wasm.
+//!
+//! The same functions are provided for the transaction and the validity
+//! predicate environments, on top of their respective host imports.
+
+use core::slice;
+
+use borsh::BorshSerialize;
+use namada_core::types::hash::HASH_LENGTH;
+use namada_core::types::internal::HostEnvResult;
+use namada_core::types::key::common;
+pub use namada_core::types::key::common::SigHashMode;
+
+/// Define the cryptographic functions of an environment on top of its host
+/// imports
+macro_rules! host_crypto {
+    (
+        $(#[$attr:meta])*
+        $env:ident {
+            sha256: $sha256:path,
+            keccak256: $keccak256:path,
+            blake2b: $blake2b:path,
+            verify_signature: $verify_signature:path $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        pub mod $env {
+            use namada_core::types::hash::Hash;
+            use namada_core::types::keccak::KeccakHash;
+            use namada_core::types::key::common;
+
+            pub use super::SigHashMode;
+
+            /// Hash the given bytes with sha256
+            pub fn sha256(bytes: &[u8]) -> Hash {
+                Hash(super::hash_with($sha256, bytes))
+            }
+
+            /// Hash the given bytes with keccak256
+            pub fn keccak256(bytes: &[u8]) -> KeccakHash {
+                KeccakHash(super::hash_with($keccak256, bytes))
+            }
+
+            /// Hash the given bytes with blake2b, with a 32 bytes digest
+            pub fn blake2b(bytes: &[u8]) -> Hash {
+                Hash(super::hash_with($blake2b, bytes))
+            }
+
+            /// Check that the signature of the given message was made with
+            /// the secret key of the given public key. The message is hashed
+            /// with sha256 before it gets verified, like the signatures made
+            /// with `common::SigScheme::sign`.
+            pub fn verify_signature(
+                pk: &common::PublicKey,
+                msg: &[u8],
+                sig: &common::Signature,
+            ) -> bool {
+                verify_signature_with_mode(pk, msg, sig, SigHashMode::Sha256)
+            }
+
+            /// Check that the signature of the given message was made with
+            /// the secret key of the given public key. The message is hashed
+            /// according to the given mode before it gets verified, e.g.
+            /// with keccak256 for Ethereum signatures or not at all for raw
+            /// ed25519 signatures.
+            pub fn verify_signature_with_mode(
+                pk: &common::PublicKey,
+                msg: &[u8],
+                sig: &common::Signature,
+                mode: SigHashMode,
+            ) -> bool {
+                super::verify_signature_with(
+                    $verify_signature,
+                    pk,
+                    msg,
+                    sig,
+                    mode,
+                )
+            }
+        }
+    };
+}
+
+host_crypto! {
+    /// Cryptographic functions of the transaction environment
+    tx {
+        sha256: crate::tx::namada_tx_sha256,
+        keccak256: crate::tx::namada_tx_keccak256,
+        blake2b: crate::tx::namada_tx_blake2b,
+        verify_signature: crate::tx::namada_tx_verify_signature,
+    }
+}
+
+host_crypto! {
+    /// Cryptographic functions of the validity predicate environment
+    vp {
+        sha256: crate::vp::namada_vp_sha256,
+        keccak256: crate::vp::namada_vp_keccak256,
+        blake2b: crate::vp::namada_vp_blake2b,
+        verify_signature: crate::vp::namada_vp_verify_signature,
+    }
+}
+
+/// Call a host hash function that writes a 32 bytes digest into a buffer
+fn hash_with(
+    hash_fn: unsafe extern "C" fn(u64, u64, u64),
+    bytes: &[u8],
+) -> [u8; HASH_LENGTH] {
+    let result = Vec::with_capacity(HASH_LENGTH);
+    unsafe {
+        hash_fn(bytes.as_ptr() as _, bytes.len() as _, result.as_ptr() as _);
+    }
+    let slice = unsafe { slice::from_raw_parts(result.as_ptr(), HASH_LENGTH) };
+    slice.try_into().expect("Cannot convert the digest")
+}
+
+/// Call a host signature verification function
+fn verify_signature_with(
+    verify_fn: unsafe extern "C" fn(u64, u64, u64, u64, u64, u64, u64) -> i64,
+    pk: &common::PublicKey,
+    msg: &[u8],
+    sig: &common::Signature,
+    mode: SigHashMode,
+) -> bool {
+    let pk = pk.try_to_vec().unwrap();
+    let sig = sig.try_to_vec().unwrap();
+    let valid = unsafe {
+        verify_fn(
+            pk.as_ptr() as _,
+            pk.len() as _,
+            sig.as_ptr() as _,
+            sig.len() as _,
+            msg.as_ptr() as _,
+            msg.len() as _,
+            u64::from(mode),
+        )
+    };
+    HostEnvResult::is_success(valid)
+}
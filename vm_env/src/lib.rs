@@ -5,6 +5,8 @@
 #![deny(rustdoc::broken_intra_doc_links)]
 #![deny(rustdoc::private_intra_doc_links)]
 
+pub mod crypto;
+
 use std::mem::ManuallyDrop;
 
 use borsh::BorshDeserialize;
@@ -78,12 +80,36 @@ pub mod tx {
         // Emit an IBC event
         pub fn namada_tx_emit_ibc_event(event_ptr: u64, event_len: u64);
 
+        // Emit a custom event
+        pub fn namada_tx_emit_event(event_ptr: u64, event_len: u64);
+
         // Get an IBC event
         pub fn namada_tx_get_ibc_event(
             event_type_ptr: u64,
             event_type_len: u64,
         ) -> i64;
 
+        // Call a stored wasm code on behalf of the given account with the
+        // given input, returns the length of the output, if any
+        pub fn namada_tx_call(
+            target_ptr: u64,
+            target_len: u64,
+            code_hash_ptr: u64,
+            code_hash_len: u64,
+            input_ptr: u64,
+            input_len: u64,
+        ) -> i64;
+
+        // Deploy a wasm code that can be called, returns its hash
+        pub fn namada_tx_deploy_code(
+            code_ptr: u64,
+            code_len: u64,
+            result_ptr: u64,
+        );
+
+        // Set the output of a called wasm code
+        pub fn namada_tx_set_call_output(output_ptr: u64, output_len: u64);
+
         // Get the chain ID
         pub fn namada_tx_get_chain_id(result_ptr: u64);
 
@@ -110,6 +136,35 @@ pub mod tx {
 
         /// Charge the provided amount of gas for the current tx
         pub fn namada_tx_charge_gas(used_gas: u64);
+
+        // Hash the data with sha256 and write the 32 bytes digest to the
+        // result pointer
+        pub fn namada_tx_sha256(data_ptr: u64, data_len: u64, result_ptr: u64);
+
+        // Hash the data with keccak256 and write the 32 bytes digest to the
+        // result pointer
+        pub fn namada_tx_keccak256(
+            data_ptr: u64,
+            data_len: u64,
+            result_ptr: u64,
+        );
+
+        // Hash the data with blake2b and write the 32 bytes digest to the
+        // result pointer
+        pub fn namada_tx_blake2b(data_ptr: u64, data_len: u64, result_ptr: u64);
+
+        // Verify a signature of an arbitrary message with a public key. The
+        // public key and the signature must be borsh encoded. The message is
+        // hashed according to the given `SigHashMode` code.
+        pub fn namada_tx_verify_signature(
+            pk_ptr: u64,
+            pk_len: u64,
+            sig_ptr: u64,
+            sig_len: u64,
+            msg_ptr: u64,
+            msg_len: u64,
+            hash_mode: u64,
+        ) -> i64;
     }
 }
 
@@ -220,6 +275,35 @@ pub mod vp {
 
         /// Charge the provided amount of gas for the current vp
         pub fn namada_vp_charge_gas(used_gas: u64);
+
+        // Hash the data with sha256 and write the 32 bytes digest to the
+        // result pointer
+        pub fn namada_vp_sha256(data_ptr: u64, data_len: u64, result_ptr: u64);
+
+        // Hash the data with keccak256 and write the 32 bytes digest to the
+        // result pointer
+        pub fn namada_vp_keccak256(
+            data_ptr: u64,
+            data_len: u64,
+            result_ptr: u64,
+        );
+
+        // Hash the data with blake2b and write the 32 bytes digest to the
+        // result pointer
+        pub fn namada_vp_blake2b(data_ptr: u64, data_len: u64, result_ptr: u64);
+
+        // Verify a signature of an arbitrary message with a public key. The
+        // public key and the signature must be borsh encoded. The message is
+        // hashed according to the given `SigHashMode` code.
+        pub fn namada_vp_verify_signature(
+            pk_ptr: u64,
+            pk_len: u64,
+            sig_ptr: u64,
+            sig_len: u64,
+            msg_ptr: u64,
+            msg_len: u64,
+            hash_mode: u64,
+        ) -> i64;
     }
 }
 
@@ -33,6 +33,7 @@ use namada_core::types::storage::{
 pub use namada_core::types::*;
 pub use namada_macros::validity_predicate;
 pub use namada_proof_of_stake::storage as proof_of_stake;
+pub use namada_vm_env::crypto::vp as crypto;
 use namada_vm_env::vp::*;
 use namada_vm_env::{read_from_buffer, read_key_val_bytes_from_buffer};
 pub use sha2::{Digest, Sha256, Sha384, Sha512};
@@ -55,6 +56,10 @@ pub fn is_tx_whitelisted(ctx: &Ctx) -> VpResult {
 
 pub fn is_vp_whitelisted(ctx: &Ctx, vp_hash: &[u8]) -> VpResult {
     let vp_hash = Hash::try_from(vp_hash).unwrap();
+    // the native user VP is always allowed
+    if vp_hash == validity_predicate::NATIVE_VP_USER_CODE_HASH {
+        return Ok(true);
+    }
     let key = parameters::storage::get_vp_whitelist_storage_key();
     let whitelist: Vec<String> = ctx.read_pre(&key)?.unwrap_or_default();
     // if whitelist is empty, allow any transaction
@@ -80,13 +85,117 @@ pub fn is_proposal_accepted(ctx: &Ctx, proposal_id: u64) -> VpResult {
 
 /// Verify section signatures
 pub fn verify_signatures(ctx: &Ctx, tx: &Tx, owner: &Address) -> VpResult {
-    let max_signatures_per_transaction =
-        parameters::max_signatures_per_transaction(&ctx.pre())?;
+    let threshold =
+        storage_api::account::threshold(&ctx.pre(), owner)?.unwrap_or(1);
+    verify_signatures_with_threshold(ctx, tx, owner, threshold)
+}
 
+/// Verify section signatures against the given threshold instead of the
+/// account's threshold
+pub fn verify_signatures_with_threshold(
+    ctx: &Ctx,
+    tx: &Tx,
+    owner: &Address,
+    threshold: u8,
+) -> VpResult {
     let public_keys_index_map =
         storage_api::account::public_keys_index_map(&ctx.pre(), owner)?;
-    let threshold =
-        storage_api::account::threshold(&ctx.pre(), owner)?.unwrap_or(1);
+    verify_signatures_with_keys(ctx, tx, public_keys_index_map, threshold)
+}
+
+/// Verify the section signatures required to update the account's keys,
+/// threshold or spending policy, which may be more than the account's
+/// threshold. The given closure checks the account's threshold.
+pub fn verify_policy_update_signatures(
+    ctx: &Ctx,
+    tx: &Tx,
+    owner: &Address,
+    valid_sig: impl FnOnce() -> bool,
+) -> VpResult {
+    match storage_api::account::policy_update_threshold(&ctx.pre(), owner)? {
+        Some(threshold) => {
+            verify_signatures_with_threshold(ctx, tx, owner, threshold)
+        }
+        None => Ok(valid_sig()),
+    }
+}
+
+/// Check that a debit of a token from the account is authorized by the
+/// session key that signed the tx and has been recorded in its spend
+pub fn is_session_key_debit(
+    ctx: &Ctx,
+    session_key: Option<&(key::common::PublicKey, account::SessionKey)>,
+    owner: &Address,
+    token: &Address,
+    debit: token::Amount,
+) -> VpResult {
+    let Some((public_key, session_key)) = session_key else {
+        return Ok(false);
+    };
+    let Some(post) =
+        storage_api::account::session_key(&ctx.post(), owner, public_key)?
+    else {
+        return Ok(false);
+    };
+    Ok(session_key.allows_debit(&post, token, debit))
+}
+
+/// Check that an update of the account's session key is only a record of the
+/// spend of the session key that signed the tx
+pub fn is_valid_session_key_update(
+    ctx: &Ctx,
+    session_key: Option<&(key::common::PublicKey, account::SessionKey)>,
+    owner: &Address,
+    storage_key: &storage::Key,
+) -> VpResult {
+    let Some((public_key, session_key)) = session_key else {
+        return Ok(false);
+    };
+    if *storage_key != key::session_key_key(owner, public_key) {
+        return Ok(false);
+    }
+    let Some(post) =
+        storage_api::account::session_key(&ctx.post(), owner, public_key)?
+    else {
+        return Ok(false);
+    };
+    Ok(session_key.is_valid_spend_update(&post))
+}
+
+/// Find a session key of the account that may sign this tx in the current
+/// epoch and that has signed it
+pub fn find_session_key(
+    ctx: &Ctx,
+    tx: &Tx,
+    owner: &Address,
+) -> EnvResult<Option<(key::common::PublicKey, account::SessionKey)>> {
+    let Some(tx_code_hash) = ctx.get_tx_code_hash()? else {
+        return Ok(None);
+    };
+    let session_keys = storage_api::account::session_keys_in_scope(
+        &ctx.pre(),
+        owner,
+        &tx_code_hash,
+    )?;
+    for (public_key, session_key) in session_keys {
+        let public_keys_index_map =
+            account::AccountPublicKeysMap::from_iter([public_key.clone()]);
+        if verify_signatures_with_keys(ctx, tx, public_keys_index_map, 1)? {
+            return Ok(Some((public_key, session_key)));
+        }
+    }
+    Ok(None)
+}
+
+/// Verify section signatures against the given public keys and threshold
+fn verify_signatures_with_keys(
+    ctx: &Ctx,
+    tx: &Tx,
+    public_keys_index_map: account::AccountPublicKeysMap,
+    threshold: u8,
+) -> VpResult {
+    let max_signatures_per_transaction =
+        parameters::max_signatures_per_transaction(&ctx.pre())?;
 
     let targets = [*tx.data_sechash(), *tx.code_sechash()];
 
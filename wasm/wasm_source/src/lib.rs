@@ -2,6 +2,8 @@
 pub mod tx_bond;
 #[cfg(feature = "tx_bridge_pool")]
 pub mod tx_bridge_pool;
+#[cfg(feature = "tx_cancel_scheduled")]
+pub mod tx_cancel_scheduled;
 #[cfg(feature = "tx_change_validator_commission")]
 pub mod tx_change_validator_commission;
 #[cfg(feature = "tx_ibc")]
@@ -12,10 +14,14 @@ pub mod tx_init_account;
 pub mod tx_init_proposal;
 #[cfg(feature = "tx_init_validator")]
 pub mod tx_init_validator;
+#[cfg(feature = "tx_recover_account")]
+pub mod tx_recover_account;
 #[cfg(feature = "tx_resign_steward")]
 pub mod tx_resign_steward;
 #[cfg(feature = "tx_reveal_pk")]
 pub mod tx_reveal_pk;
+#[cfg(feature = "tx_schedule")]
+pub mod tx_schedule;
 #[cfg(feature = "tx_transfer")]
 pub mod tx_transfer;
 #[cfg(feature = "tx_unbond")]
@@ -24,6 +30,8 @@ pub mod tx_unbond;
 pub mod tx_unjail_validator;
 #[cfg(feature = "tx_update_account")]
 pub mod tx_update_account;
+#[cfg(feature = "tx_update_session_key")]
+pub mod tx_update_session_key;
 #[cfg(feature = "tx_update_steward_commission")]
 pub mod tx_update_steward_commission;
 #[cfg(feature = "tx_vote_proposal")]
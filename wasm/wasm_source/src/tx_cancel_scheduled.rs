@@ -0,0 +1,15 @@
+//! A tx to cancel a scheduled tx
+
+use namada_tx_prelude::transaction::scheduled::CancelScheduledTx;
+use namada_tx_prelude::*;
+
+#[transaction(gas = 40000)]
+fn apply_tx(ctx: &mut Ctx, tx_data: Tx) -> TxResult {
+    let signed = tx_data;
+    let data = signed.data().ok_or_err_msg("Missing data")?;
+    let cancel = CancelScheduledTx::try_from_slice(&data[..])
+        .wrap_err("failed to decode CancelScheduledTx")?;
+    debug_log!("apply_tx called to cancel a scheduled tx");
+
+    scheduled_txs::cancel_scheduled_tx(ctx, &cancel)
+}
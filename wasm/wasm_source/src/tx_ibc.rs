@@ -2,6 +2,9 @@
 //! This tx executes an IBC operation according to the given IBC message as the
 //! tx_data. This tx uses an IBC message wrapped inside
 //! `key::ed25519::SignedTxData` as its input as declared in `ibc` crate.
+//! A shielded transfer can be attached in an extra data section to send
+//! tokens from the shielded pool. Tokens are received for a payment address
+//! with the shielded transfer in the packet memo.
 
 use namada_tx_prelude::*;
 
@@ -10,5 +13,9 @@ fn apply_tx(ctx: &mut Ctx, tx_data: Tx) -> TxResult {
     let signed = tx_data;
     let data = signed.data().ok_or_err_msg("Missing data")?;
 
-    ibc::ibc_actions(ctx).execute(&data).into_storage_result()
+    let mut actions = ibc::ibc_actions(ctx);
+    if let Some(shielded) = ibc::IbcShieldedTransfer::from_tx(&signed) {
+        actions.set_shielded_transfer(shielded);
+    }
+    actions.execute(&data).into_storage_result()
 }
@@ -0,0 +1,17 @@
+//! A tx to request, approve, cancel or execute the guardian-based recovery of
+//! an account.
+
+use namada_tx_prelude::transaction::account::RecoverAccount;
+use namada_tx_prelude::*;
+
+#[transaction(gas = 140000)]
+fn apply_tx(ctx: &mut Ctx, tx_data: Tx) -> TxResult {
+    let signed = tx_data;
+    let data = signed.data().ok_or_err_msg("Missing data")?;
+    let RecoverAccount { owner, action } =
+        RecoverAccount::try_from_slice(&data[..])
+            .wrap_err("failed to decode RecoverAccount")?;
+    debug_log!("apply_tx called to recover account {}", owner);
+
+    storage_api::account::recover_account(ctx, &owner, action)
+}
@@ -0,0 +1,15 @@
+//! A tx to schedule another tx for execution at a future block height
+
+use namada_tx_prelude::transaction::scheduled::ScheduledTx;
+use namada_tx_prelude::*;
+
+#[transaction(gas = 40000)]
+fn apply_tx(ctx: &mut Ctx, tx_data: Tx) -> TxResult {
+    let signed = tx_data;
+    let data = signed.data().ok_or_err_msg("Missing data")?;
+    let scheduled_tx = ScheduledTx::try_from_slice(&data[..])
+        .wrap_err("failed to decode ScheduledTx")?;
+    debug_log!("apply_tx called to schedule a tx");
+
+    scheduled_txs::schedule_tx(ctx, scheduled_tx)
+}
@@ -37,5 +37,15 @@ fn apply_tx(ctx: &mut Ctx, tx_data: Tx) -> TxResult {
         &key,
         &shielded_hash,
         &shielded,
-    )
+    )?;
+    if source != target {
+        account::record_session_key_spend(
+            ctx,
+            &signed,
+            &source,
+            &token,
+            amount.amount,
+        )?;
+    }
+    Ok(())
 }
@@ -33,6 +33,14 @@ fn apply_tx(ctx: &mut Ctx, tx: Tx) -> TxResult {
         ctx.write(&threshold_key, threshold)?;
     }
 
+    if let Some(policy) = tx_data.policy {
+        storage_api::account::set_policy(ctx, owner, &policy)?;
+    }
+
+    if let Some(guardians) = tx_data.guardians {
+        storage_api::account::set_guardians(ctx, owner, &guardians)?;
+    }
+
     if !tx_data.public_keys.is_empty() {
         storage_api::account::clear_public_keys(ctx, owner)?;
         for (index, public_key) in tx_data.public_keys.iter().enumerate() {
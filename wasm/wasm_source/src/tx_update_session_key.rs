@@ -0,0 +1,29 @@
+//! A tx to add or revoke a session key of an account.
+
+use namada_tx_prelude::transaction::account::UpdateSessionKey;
+use namada_tx_prelude::*;
+
+#[transaction(gas = 140000)]
+fn apply_tx(ctx: &mut Ctx, tx_data: Tx) -> TxResult {
+    let signed = tx_data;
+    let data = signed.data().ok_or_err_msg("Missing data")?;
+    let UpdateSessionKey {
+        owner,
+        public_key,
+        session_key,
+    } = UpdateSessionKey::try_from_slice(&data[..])
+        .wrap_err("failed to decode UpdateSessionKey")?;
+    debug_log!("apply_tx called to update a session key of {}", owner);
+
+    match session_key {
+        Some(session_key) => storage_api::account::add_session_key(
+            ctx,
+            &owner,
+            &public_key,
+            &session_key,
+        ),
+        None => {
+            storage_api::account::revoke_session_key(ctx, &owner, &public_key)
+        }
+    }
+}
@@ -2,9 +2,10 @@ use std::cmp::Ordering;
 
 use masp_primitives::asset_type::AssetType;
 use masp_primitives::transaction::components::I128Sum;
+use masp_primitives::transaction::Transaction;
 /// Multi-asset shielded pool VP.
 use namada_vp_prelude::address::masp;
-use namada_vp_prelude::storage::Epoch;
+use namada_vp_prelude::storage::{BlockHeight, Epoch, KeySeg, TxIndex};
 use namada_vp_prelude::*;
 use ripemd::{Digest, Ripemd160};
 
@@ -73,6 +74,79 @@ fn convert_amount(
     (asset_type, amount)
 }
 
+/// Get the shielded transfer which an IBC transaction recorded in the MASP
+/// storage, after checking that the balance of the MASP address changed
+/// exactly by the transferred amount. The tokens received for a payment
+/// address must be shielded by the MASP transaction of the packet memo, and
+/// only for that payment address.
+fn ibc_shielded_transfer(
+    ctx: &Ctx,
+    tx_data: &[u8],
+) -> EnvResult<Option<(token::Transfer, Transaction)>> {
+    let masp_addr = masp();
+    let head_tx_key = storage::Key::from(masp_addr.to_db_key())
+        .push(&token::HEAD_TX_KEY.to_owned())
+        .expect("Cannot obtain a storage key");
+    let current_tx_idx: u64 = ctx.read_pre(&head_tx_key)?.unwrap_or(0);
+    let current_tx_key = storage::Key::from(masp_addr.to_db_key())
+        .push(&(token::TX_KEY_PREFIX.to_owned() + &current_tx_idx.to_string()))
+        .expect("Cannot obtain a storage key");
+    let record: Option<(
+        Epoch,
+        BlockHeight,
+        TxIndex,
+        token::Transfer,
+        Transaction,
+    )> = ctx.read_post(&current_tx_key)?;
+    let (_, _, _, transfer, shielded) = match record {
+        Some(record) => record,
+        None => return Ok(None),
+    };
+
+    let balance_key = token::balance_key(&transfer.token, &masp_addr);
+    let pre: token::Amount = ctx.read_pre(&balance_key)?.unwrap_or_default();
+    let post: token::Amount = ctx.read_post(&balance_key)?.unwrap_or_default();
+    let change = if transfer.target == masp_addr {
+        post.checked_sub(pre)
+    } else if transfer.source == masp_addr {
+        pre.checked_sub(post)
+    } else {
+        None
+    };
+    if change != Some(transfer.amount.amount) {
+        debug_log!(
+            "The MASP balance change {:?} disagrees with the IBC shielded \
+             transfer amount {}",
+            change,
+            transfer.amount
+        );
+        return Ok(None);
+    }
+
+    if transfer.target == masp_addr {
+        let received =
+            ibc::get_received_shielded_transfer(tx_data).unwrap_or_default();
+        let Some((receiver, memo_transfer)) = received else {
+            debug_log!("No MASP transaction in the received packet memo");
+            return Ok(None);
+        };
+        if memo_transfer.masp_tx.txid() != shielded.txid() {
+            debug_log!(
+                "The MASP transaction differs from the one in the packet memo"
+            );
+            return Ok(None);
+        }
+        if !memo_transfer.pays_only(&receiver) {
+            debug_log!(
+                "The MASP transaction doesn't only pay the packet receiver {}",
+                receiver
+            );
+            return Ok(None);
+        }
+    }
+    Ok(Some((transfer, shielded)))
+}
+
 #[validity_predicate(gas = 8030000)]
 fn validate_tx(
     ctx: &Ctx,
@@ -91,19 +165,28 @@ fn validate_tx(
     );
 
     let signed = tx_data;
-    let transfer =
-        token::Transfer::try_from_slice(&signed.data().unwrap()[..]).unwrap();
-
-    let shielded = transfer
-        .shielded
-        .as_ref()
-        .map(|hash| {
-            signed
-                .get_section(hash)
-                .and_then(|x| x.as_ref().masp_tx())
-                .ok_or_err_msg("unable to find shielded section")
-        })
-        .transpose()?;
+    let data = signed.data().unwrap();
+    let (transfer, shielded) = match token::Transfer::try_from_slice(&data[..])
+    {
+        Ok(transfer) => {
+            let shielded = transfer
+                .shielded
+                .as_ref()
+                .map(|hash| {
+                    signed
+                        .get_section(hash)
+                        .and_then(|x| x.as_ref().masp_tx())
+                        .ok_or_err_msg("unable to find shielded section")
+                })
+                .transpose()?;
+            (transfer, shielded)
+        }
+        // The tx data is an IBC message for a shielded IBC transfer
+        Err(_) => match ibc_shielded_transfer(ctx, &data)? {
+            Some((transfer, shielded)) => (transfer, Some(shielded)),
+            None => return reject(),
+        },
+    };
     if let Some(shielded_tx) = shielded {
         let mut transparent_tx_pool = I128Sum::zero();
         // The Sapling value balance adds to the transparent tx pool
@@ -272,3 +355,145 @@ fn validate_tx(
         reject()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use masp_primitives::consensus::{
+        BlockHeight as MaspBlockHeight, BranchId,
+    };
+    use masp_primitives::transaction::{TransactionData, TxVersion};
+    use namada::proto::Data;
+    use namada::types::transaction::TxType;
+    // Use this as `#[test]` annotation to enable logging
+    use namada_tests::log::test;
+    use namada_tests::tx::{self, tx_host_env, TestTxEnv};
+    use namada_tests::vp::*;
+
+    use super::*;
+
+    /// Make a transfer into the MASP address with an empty MASP transaction
+    fn shielding_transfer(
+        source: &Address,
+        token: &Address,
+        amount: token::Amount,
+    ) -> (token::Transfer, Transaction) {
+        let transfer = token::Transfer {
+            source: source.clone(),
+            target: masp(),
+            token: token.clone(),
+            amount: token::DenominatedAmount {
+                amount,
+                denom: token::NATIVE_MAX_DECIMAL_PLACES.into(),
+            },
+            key: None,
+            shielded: None,
+        };
+        let masp_tx = TransactionData::from_parts(
+            TxVersion::MASPv5,
+            BranchId::MASP,
+            0,
+            MaspBlockHeight::from_u32(0),
+            None,
+            None,
+        )
+        .freeze()
+        .expect("building the MASP transaction failed");
+        (transfer, masp_tx)
+    }
+
+    /// Run a transaction crediting the MASP address with the given amount
+    /// from the IBC address, recording the given shielded transfer if any,
+    /// and validate it as an IBC transaction
+    fn validate_ibc_credit(
+        credit: token::Amount,
+        shielded: Option<(token::Transfer, Transaction)>,
+    ) -> (bool, Option<token::Transfer>) {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let source = Address::Internal(address::InternalAddress::Ibc);
+        let token = address::nam();
+        tx_env.spawn_accounts([&token]);
+        tx_env.credit_tokens(&source, &token, credit);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(masp(), tx_env, |address| {
+            if let Some((transfer, masp_tx)) = &shielded {
+                tx_host_env::token::handle_masp_tx(
+                    tx::ctx(),
+                    transfer,
+                    masp_tx,
+                )
+                .unwrap();
+            }
+            // The tokens are received or refunded by the IBC transaction
+            tx_host_env::token::transfer(
+                tx::ctx(),
+                &source,
+                address,
+                &token,
+                token::DenominatedAmount {
+                    amount: credit,
+                    denom: token::NATIVE_MAX_DECIMAL_PLACES.into(),
+                },
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+        });
+
+        let vp_env = vp_host_env::take();
+        // The tx data is an IBC message rather than a transfer
+        let mut tx_data = Tx::from_type(TxType::Raw);
+        tx_data.set_data(Data::new(b"IBC message".to_vec()));
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        let found = ibc_shielded_transfer(&CTX)
+            .unwrap()
+            .map(|(transfer, _)| transfer);
+        let valid = validate_tx(&CTX, tx_data, masp(), keys_changed, verifiers)
+            .unwrap();
+        (valid, found)
+    }
+
+    /// Test that the shielded transfer recorded by an IBC transaction is
+    /// found when the MASP balance changes by its amount.
+    #[test]
+    fn test_ibc_shielded_transfer_found() {
+        let amount = token::Amount::from_uint(10_098_123, 0).unwrap();
+        let source = Address::Internal(address::InternalAddress::Ibc);
+        let shielded = shielding_transfer(&source, &address::nam(), amount);
+        let expected = shielded.0.clone();
+
+        let (_, found) = validate_ibc_credit(amount, Some(shielded));
+        assert_eq!(found, Some(expected));
+    }
+
+    /// Test that an IBC transaction crediting the MASP address more than its
+    /// shielded transfer is rejected.
+    #[test]
+    fn test_ibc_shielded_transfer_amount_mismatch_rejected() {
+        let amount = token::Amount::from_uint(10_098_123, 0).unwrap();
+        let source = Address::Internal(address::InternalAddress::Ibc);
+        let shielded = shielding_transfer(&source, &address::nam(), amount);
+
+        let credit = token::Amount::from_uint(20_196_246, 0).unwrap();
+        let (valid, found) = validate_ibc_credit(credit, Some(shielded));
+        assert_eq!(found, None);
+        assert!(!valid);
+    }
+
+    /// Test that an IBC transaction crediting the MASP address without a
+    /// MASP transaction, e.g. a refund to the MASP address, is rejected.
+    #[test]
+    fn test_ibc_credit_without_masp_tx_rejected() {
+        let amount = token::Amount::from_uint(10_098_123, 0).unwrap();
+
+        let (valid, found) = validate_ibc_credit(amount, None);
+        assert_eq!(found, None);
+        assert!(!valid);
+    }
+}
@@ -6,27 +6,52 @@
 //! It allows to bond, unbond and withdraw tokens to and from PoS system with a
 //! valid signature.
 //!
+//! Debits are also checked against the account's optional spending policy,
+//! which may limit the outflow of tokens per epoch, restrict the recipients
+//! and require more signatures for large debits.
+//!
+//! The account's guardians may jointly replace its keys after a delay, during
+//! which the account can cancel the recovery. Every guardian's approval must
+//! be signed by the guardian.
+//!
+//! Signatures of the account's session keys are accepted instead of the
+//! account's signatures only for the txs allowed by the session key, until
+//! its expiry and for debits up to its maximum spend. They are not accepted
+//! for PoS, governance and PGF actions, which are not bounded by the maximum
+//! spend.
+//!
 //! Any other storage key changes are allowed only with a valid signature.
 
+use namada_vp_prelude::account::{DebitAuthorization, RecoveryAuthorization};
 use namada_vp_prelude::address::masp;
 use namada_vp_prelude::storage::KeySeg;
 use namada_vp_prelude::*;
 use once_cell::unsync::Lazy;
 
 enum KeyType<'a> {
-    Token { owner: &'a Address },
+    Token {
+        token: &'a Address,
+        owner: &'a Address,
+    },
     PoS,
     Vp(&'a Address),
     Masp,
     PgfStward(&'a Address),
     GovernanceVote(&'a Address),
+    Policy(&'a Address),
+    Outflow {
+        owner: &'a Address,
+        token: &'a Address,
+    },
+    Recovery(&'a Address),
+    SessionKey(&'a Address),
     Unknown,
 }
 
 impl<'a> From<&'a storage::Key> for KeyType<'a> {
     fn from(key: &'a storage::Key) -> KeyType<'a> {
-        if let Some([_, owner]) = token::is_any_token_balance_key(key) {
-            Self::Token { owner }
+        if let Some([token, owner]) = token::is_any_token_balance_key(key) {
+            Self::Token { token, owner }
         } else if proof_of_stake::is_pos_key(key) {
             Self::PoS
         } else if gov_storage::keys::is_vote_key(key) {
@@ -42,6 +67,14 @@ impl<'a> From<&'a storage::Key> for KeyType<'a> {
             Self::Vp(address)
         } else if token::is_masp_key(key) {
             Self::Masp
+        } else if let Some(owner) = key::is_policy_key(key) {
+            Self::Policy(owner)
+        } else if let Some([owner, token]) = key::is_outflow_key(key) {
+            Self::Outflow { owner, token }
+        } else if let Some(owner) = key::is_recovery_key(key) {
+            Self::Recovery(owner)
+        } else if let Some(owner) = key::is_session_key_key(key) {
+            Self::SessionKey(owner)
         } else {
             Self::Unknown
         }
@@ -66,6 +99,18 @@ fn validate_tx(
     let valid_sig = Lazy::new(|| {
         matches!(verify_signatures(ctx, &tx_data, &addr), Ok(true))
     });
+    let session_key =
+        Lazy::new(|| find_session_key(ctx, &tx_data, &addr).ok().flatten());
+    let recovery_executed = Lazy::new(|| {
+        matches!(
+            storage_api::account::is_recovery_executed(
+                &ctx.pre(),
+                &ctx.post(),
+                &addr
+            ),
+            Ok(true)
+        )
+    });
 
     if !is_valid_tx(ctx, &tx_data)? {
         return reject();
@@ -74,16 +119,42 @@ fn validate_tx(
     for key in keys_changed.iter() {
         let key_type: KeyType = key.into();
         let is_valid = match key_type {
-            KeyType::Token { owner, .. } => {
+            KeyType::Token { token, owner } => {
                 if owner == &addr {
                     let pre: token::Amount =
                         ctx.read_pre(key)?.unwrap_or_default();
                     let post: token::Amount =
                         ctx.read_post(key)?.unwrap_or_default();
                     let change = post.change() - pre.change();
+                    let debit = pre.checked_sub(post).unwrap_or_default();
                     // debit has to signed, credit doesn't
-                    let valid =
-                        change.non_negative() || addr == masp() || *valid_sig;
+                    let valid = change.non_negative()
+                        || addr == masp()
+                        || match storage_api::account::check_debit_policy(
+                            &ctx.pre(),
+                            &ctx.post(),
+                            &addr,
+                            token,
+                            debit,
+                            &keys_changed,
+                        )? {
+                            DebitAuthorization::Forbidden => false,
+                            DebitAuthorization::AccountThreshold => {
+                                *valid_sig
+                                    || is_session_key_debit(
+                                        ctx,
+                                        session_key.as_ref(),
+                                        &addr,
+                                        token,
+                                        debit,
+                                    )?
+                            }
+                            DebitAuthorization::LargeDebitThreshold(
+                                threshold,
+                            ) => verify_signatures_with_threshold(
+                                ctx, &tx_data, &addr, threshold,
+                            )?,
+                        };
                     debug_log!(
                         "token key: {}, change: {:?}, valid_sig: {}, valid \
                          modification: {}",
@@ -116,7 +187,7 @@ fn validate_tx(
                 let valid = match bond_id {
                     Some(bond_id) => {
                         // Bonds and unbonds changes for this address
-                        // must be signed
+                        // must be signed, a session key is not enough
                         bond_id.source != addr || *valid_sig
                     }
                     None => {
@@ -161,11 +232,85 @@ fn validate_tx(
                 }
             }
             KeyType::Masp => true,
+            KeyType::Policy(owner) => {
+                owner != &addr
+                    || verify_policy_update_signatures(
+                        ctx,
+                        &tx_data,
+                        &addr,
+                        || *valid_sig,
+                    )?
+            }
+            KeyType::Outflow { owner, token } => {
+                if owner == &addr {
+                    // The outflow can only be increased in the current epoch
+                    (*valid_sig || session_key.is_some())
+                        && storage_api::account::is_valid_outflow_update(
+                            &ctx.pre(),
+                            &ctx.post(),
+                            &addr,
+                            token,
+                        )?
+                } else {
+                    true
+                }
+            }
+            KeyType::Recovery(owner) => {
+                if owner == &addr {
+                    match storage_api::account::check_recovery_update(
+                        &ctx.pre(),
+                        &ctx.post(),
+                        &addr,
+                    )? {
+                        RecoveryAuthorization::Forbidden => false,
+                        RecoveryAuthorization::AccountThreshold => *valid_sig,
+                        RecoveryAuthorization::Guardians(guardians) => {
+                            // Every new approval must be signed by its
+                            // guardian
+                            guardians.iter().all(|guardian| {
+                                matches!(
+                                    verify_signatures(ctx, &tx_data, guardian),
+                                    Ok(true)
+                                )
+                            })
+                        }
+                        RecoveryAuthorization::Executed => true,
+                    }
+                } else {
+                    true
+                }
+            }
+            KeyType::SessionKey(owner) => {
+                // The session key that signed the tx can only record its own
+                // spend
+                owner != &addr
+                    || *valid_sig
+                    || is_valid_session_key_update(
+                        ctx,
+                        session_key.as_ref(),
+                        &addr,
+                        key,
+                    )?
+            }
             KeyType::Unknown => {
                 if key.segments.get(0) == Some(&addr.to_db_key()) {
                     // Unknown changes to this address space require a valid
-                    // signature
-                    *valid_sig
+                    // signature. The keys can be replaced by an executed
+                    // recovery, otherwise they require as many signatures as
+                    // a policy update.
+                    let is_keys_key = key::is_pks_key(key) == Some(&addr)
+                        || key::is_threshold_key(key) == Some(&addr);
+                    if is_keys_key {
+                        *recovery_executed
+                            || verify_policy_update_signatures(
+                                ctx,
+                                &tx_data,
+                                &addr,
+                                || *valid_sig,
+                            )?
+                    } else {
+                        *valid_sig
+                    }
                 } else {
                     // Unknown changes anywhere else are permitted
                     true